@@ -40,6 +40,7 @@ impl BenchProvider {
                 tool_calls: vec![],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             }]),
         }
     }
@@ -56,12 +57,14 @@ impl BenchProvider {
                     }],
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 },
                 ChatResponse {
                     text: Some("done".into()),
                     tool_calls: vec![],
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 },
             ]),
         }
@@ -93,6 +96,7 @@ impl Provider for BenchProvider {
                 tool_calls: vec![],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             });
         }
         Ok(guard.remove(0))
@@ -160,6 +164,7 @@ Let me know if you need more."#
         tool_calls: vec![],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     };
 
     let multi_tool = ChatResponse {
@@ -178,6 +183,7 @@ Let me know if you need more."#
         tool_calls: vec![],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     };
 
     c.bench_function("xml_parse_single_tool_call", |b| {
@@ -212,6 +218,7 @@ fn bench_native_parsing(c: &mut Criterion) {
         ],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     };
 
     c.bench_function("native_parse_tool_calls", |b| {
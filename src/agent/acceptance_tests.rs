@@ -0,0 +1,195 @@
+//! Acceptance tests run before a code-change task may complete.
+//!
+//! With `[autonomy.task_acceptance_tests]` configured, a task carrying one of
+//! its labels (e.g. the `code` classifier hint) does not complete on the
+//! model's word alone: once the reply passes the usual checks, the engine runs
+//! the workspace's test command. A failure goes back to the model as the next
+//! continuation round, with the tail of the test output; only a passing run
+//! lets the task complete. Every run is logged as an [`ACCEPTANCE_TESTS_EVENT`]
+//! and its output kept under [`LOG_DIR`] as a task artifact.
+
+use crate::config::TaskAcceptanceTests;
+use crate::util::truncate_with_ellipsis;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Event recording one test run: command, pass/fail, exit code, log path.
+pub const ACCEPTANCE_TESTS_EVENT: &str = "acceptance_tests";
+
+/// Workspace-relative directory holding each run's combined output.
+pub const LOG_DIR: &str = "state/acceptance-tests";
+
+/// Characters of test output sent back to the model after a failure.
+const FEEDBACK_OUTPUT_CHARS: usize = 4000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcceptanceTestRun {
+    pub command: String,
+    pub passed: bool,
+    /// `None` when the command was killed by a signal, timed out, or could
+    /// not start.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// Stdout followed by stderr.
+    pub output: String,
+}
+
+impl AcceptanceTestRun {
+    /// Continuation prompt telling the model which tests failed and how.
+    pub fn feedback_prompt(&self) -> String {
+        let status = if self.timed_out {
+            "timed out".to_string()
+        } else {
+            self.exit_code.map_or_else(
+                || "was killed or could not start".to_string(),
+                |code| format!("exited with status {code}"),
+            )
+        };
+        format!(
+            "The acceptance tests (`{}`) {status}, so the task is not done yet. Fix the code so they pass, then report again. End of the test output:\n\n{}",
+            self.command,
+            output_tail(&self.output, FEEDBACK_OUTPUT_CHARS)
+        )
+    }
+
+    /// Short summary for the task log and progress messages.
+    pub fn summary(&self) -> String {
+        truncate_with_ellipsis(output_tail(&self.output, 200).trim(), 200)
+    }
+}
+
+/// Whether a task with `labels` has to pass the acceptance tests. Classifier
+/// hints arrive as `hint:<name>`; `code` and `hint:code` match each other.
+pub fn applies(config: &TaskAcceptanceTests, labels: &[String]) -> bool {
+    !config.command.trim().is_empty()
+        && labels.iter().any(|label| {
+            config
+                .labels
+                .iter()
+                .any(|wanted| label_name(wanted) == label_name(label))
+        })
+}
+
+fn label_name(label: &str) -> &str {
+    let label = label.trim();
+    label.strip_prefix("hint:").unwrap_or(label)
+}
+
+/// Run the configured test command in `workspace_dir` with the task's
+/// environment.
+pub async fn run(config: &TaskAcceptanceTests, workspace_dir: &Path) -> AcceptanceTestRun {
+    let command = config.command.trim().to_string();
+    let mut cmd = tokio::process::Command::new("sh");
+    cmd.arg("-c")
+        .arg(&command)
+        .current_dir(workspace_dir)
+        .kill_on_drop(true);
+    crate::tools::task_env::apply(&mut cmd);
+    let started = Instant::now();
+    let result = tokio::time::timeout(Duration::from_secs(config.timeout_secs), cmd.output()).await;
+    let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    let (passed, exit_code, timed_out, output) = match result {
+        Ok(Ok(output)) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            (output.status.success(), output.status.code(), false, text)
+        }
+        Ok(Err(err)) => (false, None, false, format!("could not start: {err}")),
+        Err(_) => (
+            false,
+            None,
+            true,
+            format!("timed out after {}s", config.timeout_secs),
+        ),
+    };
+    AcceptanceTestRun {
+        command,
+        passed,
+        exit_code,
+        timed_out,
+        duration_ms,
+        output,
+    }
+}
+
+/// Save a run's output under [`LOG_DIR`]; returns the workspace-relative path
+/// and the file's SHA-256.
+pub fn write_log(
+    workspace_dir: &Path,
+    task_id: &str,
+    round: usize,
+    run: &AcceptanceTestRun,
+) -> Result<(String, String)> {
+    let relative = format!("{LOG_DIR}/{task_id}/round-{round}.log");
+    let path = workspace_dir.join(&relative);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let body = format!("$ {}\n{}", run.command, run.output);
+    std::fs::write(&path, &body)
+        .with_context(|| format!("Failed to write test log {}", path.display()))?;
+    Ok((relative, hex::encode(Sha256::digest(body.as_bytes()))))
+}
+
+/// Last `max_chars` characters of `output`, where test runners put failures.
+fn output_tail(output: &str, max_chars: usize) -> &str {
+    let skip = output.chars().count().saturating_sub(max_chars);
+    output
+        .char_indices()
+        .nth(skip)
+        .map_or(output, |(index, _)| &output[index..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config(command: &str) -> TaskAcceptanceTests {
+        TaskAcceptanceTests {
+            command: command.to_string(),
+            ..TaskAcceptanceTests::default()
+        }
+    }
+
+    #[test]
+    fn only_labelled_tasks_with_a_command_run_tests() {
+        let labels = vec!["hint:code".to_string(), "task_type:mixed".to_string()];
+        assert!(applies(&config("cargo test"), &labels));
+        assert!(applies(&config("cargo test"), &["code".to_string()]));
+        assert!(!applies(&config("  "), &labels));
+        assert!(!applies(&config("cargo test"), &["hint:chat".to_string()]));
+        assert!(!applies(
+            &config("cargo test"),
+            &["task_type:code".to_string()]
+        ));
+    }
+
+    #[tokio::test]
+    async fn failing_run_is_logged_and_fed_back() {
+        let tmp = TempDir::new().expect("tempdir");
+        let failed = run(
+            &config("echo '1 passed'; echo 'test_add FAILED' >&2; exit 3"),
+            tmp.path(),
+        )
+        .await;
+        assert!(!failed.passed);
+        assert_eq!(failed.exit_code, Some(3));
+        assert!(failed.output.contains("test_add FAILED"));
+        assert!(failed.feedback_prompt().contains("exited with status 3"));
+        assert!(failed.feedback_prompt().ends_with("test_add FAILED\n"));
+
+        let (path, checksum) = write_log(tmp.path(), "task-1", 2, &failed).expect("log");
+        assert_eq!(path, "state/acceptance-tests/task-1/round-2.log");
+        assert_eq!(checksum.len(), 64);
+        let saved = std::fs::read_to_string(tmp.path().join(&path)).unwrap();
+        assert!(saved.starts_with("$ echo"));
+
+        assert!(run(&config("true"), tmp.path()).await.passed);
+        assert_eq!(output_tail("abcdef", 3), "def");
+    }
+}
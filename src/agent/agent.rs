@@ -373,43 +373,55 @@ impl Agent {
     async fn execute_tool_call(&self, call: &ParsedToolCall) -> ToolExecutionResult {
         let start = Instant::now();
 
-        let result = if let Some(tool) = self.tools.iter().find(|t| t.name() == call.name) {
-            match tool.execute(call.arguments.clone()).await {
-                Ok(r) => {
-                    self.observer.record_event(&ObserverEvent::ToolCall {
-                        tool: call.name.clone(),
-                        duration: start.elapsed(),
-                        success: r.success,
-                    });
-                    if r.success {
-                        r.output
-                    } else {
-                        format!("Error: {}", r.error.unwrap_or(r.output))
+        let (result, success) =
+            if let Some(tool) = self.tools.iter().find(|t| t.name() == call.name) {
+                let violations = tool.argument_violations(&call.arguments);
+                if !violations.is_empty() {
+                    let reason = crate::agent::loop_::invalid_arguments_message(
+                        &call.name,
+                        &violations,
+                        &tool.parameters_schema(),
+                    );
+                    (format!("Error: {reason}"), false)
+                } else {
+                    match tool.execute(call.arguments.clone()).await {
+                        Ok(r) => {
+                            self.observer.record_event(&ObserverEvent::ToolCall {
+                                tool: call.name.clone(),
+                                duration: start.elapsed(),
+                                success: r.success,
+                            });
+                            if r.success {
+                                (r.output, true)
+                            } else {
+                                (format!("Error: {}", r.error.unwrap_or(r.output)), false)
+                            }
+                        }
+                        Err(e) => {
+                            self.observer.record_event(&ObserverEvent::ToolCall {
+                                tool: call.name.clone(),
+                                duration: start.elapsed(),
+                                success: false,
+                            });
+                            (format!("Error executing {}: {e}", call.name), false)
+                        }
                     }
                 }
-                Err(e) => {
-                    self.observer.record_event(&ObserverEvent::ToolCall {
-                        tool: call.name.clone(),
-                        duration: start.elapsed(),
-                        success: false,
-                    });
-                    format!("Error executing {}: {e}", call.name)
-                }
-            }
-        } else {
-            format!("Unknown tool: {}", call.name)
-        };
+            } else {
+                (format!("Unknown tool: {}", call.name), false)
+            };
 
         ToolExecutionResult {
             name: call.name.clone(),
             output: result,
-            success: true,
+            success,
             tool_call_id: call.tool_call_id.clone(),
         }
     }
 
     async fn execute_tools(&self, calls: &[ParsedToolCall]) -> Vec<ToolExecutionResult> {
-        if !self.config.parallel_tools {
+        let max_parallel = self.config.tool_concurrency();
+        if max_parallel <= 1 {
             let mut results = Vec::with_capacity(calls.len());
             for call in calls {
                 results.push(self.execute_tool_call(call).await);
@@ -417,9 +429,15 @@ impl Agent {
             return results;
         }
 
+        let semaphore = tokio::sync::Semaphore::new(max_parallel);
+        let semaphore = &semaphore;
         let futs: Vec<_> = calls
             .iter()
-            .map(|call| self.execute_tool_call(call))
+            .map(|call| async move {
+                // The semaphore is never closed, so acquiring cannot fail.
+                let _permit = semaphore.acquire().await.ok();
+                self.execute_tool_call(call).await
+            })
             .collect();
         futures_util::future::join_all(futs).await
     }
@@ -653,6 +671,7 @@ mod tests {
                     tool_calls: vec![],
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 });
             }
             Ok(guard.remove(0))
@@ -692,6 +711,7 @@ mod tests {
                 tool_calls: vec![],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             }]),
         });
 
@@ -732,12 +752,14 @@ mod tests {
                     }],
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 },
                 crate::providers::ChatResponse {
                     text: Some("done".into()),
                     tool_calls: vec![],
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 },
             ]),
         });
@@ -0,0 +1,275 @@
+//! MIME detection and lightweight previews for task artifacts.
+//!
+//! When the engine sees a verified write, it inspects each written file once:
+//! the MIME type comes from magic bytes (falling back to the extension), and
+//! the preview is the first lines of text, a small thumbnail for images, or
+//! the page count for PDFs. Channels list the previews under the final reply
+//! and the dashboard API returns them with the artifact records.
+
+use crate::agent::task_types::{ArtifactPreview, TaskArtifactRecord};
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::fmt::Write;
+use std::io::Read;
+use std::path::Path;
+
+/// Lines of text kept in a preview excerpt.
+const PREVIEW_LINES: usize = 10;
+
+/// Character cap for a preview excerpt.
+const MAX_EXCERPT_CHARS: usize = 800;
+
+/// Longest side of generated image thumbnails, in pixels.
+const THUMBNAIL_MAX_DIM: u32 = 128;
+
+/// Files larger than this are only sniffed, not decoded or scanned.
+const MAX_INSPECT_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Bytes read for MIME sniffing and text excerpts of oversized files.
+const SNIFF_BYTES: u64 = 64 * 1024;
+
+/// Inspect the artifact at `path` and build its preview.
+pub fn inspect_artifact(path: &Path) -> Result<ArtifactPreview> {
+    let size_bytes = std::fs::metadata(path)
+        .with_context(|| format!("Failed to stat artifact {}", path.display()))?
+        .len();
+    let limit = if size_bytes > MAX_INSPECT_BYTES {
+        SNIFF_BYTES
+    } else {
+        size_bytes
+    };
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)
+        .with_context(|| format!("Failed to open artifact {}", path.display()))?
+        .take(limit)
+        .read_to_end(&mut bytes)?;
+    let complete = limit == size_bytes;
+
+    let mime_type = detect_mime(path, &bytes);
+    let mut preview = ArtifactPreview {
+        summary: format_size(size_bytes),
+        mime_type,
+        size_bytes,
+        excerpt: None,
+        thumbnail: None,
+    };
+
+    if preview.mime_type.starts_with("image/") {
+        if complete {
+            if let Some((summary, thumbnail)) = image_preview(&bytes) {
+                preview.summary = summary;
+                preview.thumbnail = Some(thumbnail);
+            }
+        }
+    } else if preview.mime_type == "application/pdf" {
+        if complete {
+            let pages = count_pdf_pages(&bytes);
+            preview.summary = format!("PDF, {pages} page{}", if pages == 1 { "" } else { "s" });
+        }
+    } else if is_text_mime(&preview.mime_type) {
+        let text = String::from_utf8_lossy(&bytes);
+        let line_count = text.lines().count();
+        preview.summary = if complete {
+            format!(
+                "{line_count} line{}",
+                if line_count == 1 { "" } else { "s" }
+            )
+        } else {
+            format_size(size_bytes)
+        };
+        preview.excerpt = Some(excerpt(&text));
+    }
+    Ok(preview)
+}
+
+/// MIME type from magic bytes, then the file extension, then UTF-8 validity.
+pub fn detect_mime(path: &Path, bytes: &[u8]) -> String {
+    let sniffed = if bytes.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    };
+    if let Some(mime) = sniffed {
+        return mime.to_string();
+    }
+    if let Some(guess) = mime_guess::from_path(path).first() {
+        return guess.essence_str().to_string();
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        "text/plain".to_string()
+    } else {
+        "application/octet-stream".to_string()
+    }
+}
+
+fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/toml"
+                | "application/x-sh"
+                | "application/yaml"
+        )
+}
+
+fn excerpt(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().take(PREVIEW_LINES).collect();
+    crate::util::truncate_with_ellipsis(&lines.join("\n"), MAX_EXCERPT_CHARS)
+}
+
+fn image_preview(bytes: &[u8]) -> Option<(String, String)> {
+    let img = image::load_from_memory(bytes).ok()?;
+    let summary = format!("image {}×{}", img.width(), img.height());
+    let thumbnail = img.thumbnail(THUMBNAIL_MAX_DIM, THUMBNAIL_MAX_DIM);
+    let mut buf = Vec::new();
+    thumbnail
+        .to_rgb8()
+        .write_to(
+            &mut std::io::Cursor::new(&mut buf),
+            image::ImageFormat::Jpeg,
+        )
+        .ok()?;
+    let b64 = base64::engine::general_purpose::STANDARD.encode(&buf);
+    Some((summary, format!("data:image/jpeg;base64,{b64}")))
+}
+
+/// Count `/Type /Page` objects (not `/Pages` tree nodes) in a PDF.
+fn count_pdf_pages(bytes: &[u8]) -> usize {
+    let mut pages = 0;
+    let mut rest = bytes;
+    while let Some(pos) = rest.windows(5).position(|w| w == b"/Type") {
+        rest = &rest[pos + 5..];
+        let name_start = rest
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let after = &rest[name_start..];
+        if after.starts_with(b"/Page") && !after.starts_with(b"/Pages") {
+            pages += 1;
+        }
+    }
+    pages
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// Attachment list appended to a channel reply; `None` when the task
+/// produced no previewed artifacts.
+pub fn render_artifact_summary(artifacts: &[TaskArtifactRecord]) -> Option<String> {
+    let previewed: Vec<(&TaskArtifactRecord, &ArtifactPreview)> = artifacts
+        .iter()
+        .filter_map(|artifact| artifact.preview.as_ref().map(|p| (artifact, p)))
+        .collect();
+    if previewed.is_empty() {
+        return None;
+    }
+
+    let mut out = String::from("📎 Artifacts:");
+    for (artifact, preview) in previewed {
+        let _ = write!(
+            out,
+            "\n- {} ({}, {})",
+            artifact.path, preview.mime_type, preview.summary
+        );
+        if let Some(excerpt) = preview.excerpt.as_deref() {
+            for line in excerpt.lines().take(3) {
+                let _ = write!(out, "\n  > {line}");
+            }
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn text_artifact_gets_line_count_and_excerpt() {
+        let tmp = TempDir::new().expect("tempdir");
+        let path = tmp.path().join("report.txt");
+        let body: String = (1..=12).map(|i| format!("line {i}\n")).collect();
+        std::fs::write(&path, body).expect("write");
+
+        let preview = inspect_artifact(&path).expect("preview");
+        assert_eq!(preview.mime_type, "text/plain");
+        assert_eq!(preview.summary, "12 lines");
+        let excerpt = preview.excerpt.expect("excerpt");
+        assert!(excerpt.starts_with("line 1\n"));
+        assert!(excerpt.ends_with("line 10"));
+    }
+
+    #[test]
+    fn image_artifact_gets_dimensions_and_thumbnail() {
+        let tmp = TempDir::new().expect("tempdir");
+        let path = tmp.path().join("chart");
+        image::RgbImage::new(300, 150)
+            .save_with_format(&path, image::ImageFormat::Png)
+            .expect("save png");
+
+        let preview = inspect_artifact(&path).expect("preview");
+        assert_eq!(preview.mime_type, "image/png");
+        assert_eq!(preview.summary, "image 300×150");
+        assert!(preview
+            .thumbnail
+            .is_some_and(|thumb| thumb.starts_with("data:image/jpeg;base64,")));
+    }
+
+    #[test]
+    fn pdf_page_count_ignores_page_tree_nodes() {
+        let pdf = b"%PDF-1.4\n1 0 obj << /Type /Pages /Count 2 >> endobj\n2 0 obj << /Type /Page >> endobj\n3 0 obj << /Type/Page >> endobj";
+        assert_eq!(detect_mime(Path::new("x.bin"), pdf), "application/pdf");
+        assert_eq!(count_pdf_pages(pdf), 2);
+    }
+
+    #[test]
+    fn summary_lists_only_previewed_artifacts() {
+        let record = |path: &str, preview: Option<ArtifactPreview>| TaskArtifactRecord {
+            id: 1,
+            task_id: "task-1".into(),
+            path: path.into(),
+            verified: true,
+            checksum: None,
+            verified_at: None,
+            mime_type: preview.as_ref().map(|p| p.mime_type.clone()),
+            preview,
+        };
+        assert!(render_artifact_summary(&[record("__history_verified__", None)]).is_none());
+
+        let summary = render_artifact_summary(&[record(
+            "notes.txt",
+            Some(ArtifactPreview {
+                mime_type: "text/plain".into(),
+                size_bytes: 6,
+                summary: "1 line".into(),
+                excerpt: Some("hello".into()),
+                thumbnail: None,
+            }),
+        )])
+        .expect("summary");
+        assert_eq!(
+            summary,
+            "📎 Artifacts:\n- notes.txt (text/plain, 1 line)\n  > hello"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! Disk checks for files a task claims to have written.
+//!
+//! A successful `file_write` result only says the tool ran. Before a write
+//! task may complete, the engine checks every written path with the strategy
+//! picked by `completion_policy.verification_rules` (read-back, checksum,
+//! HTTP GET, or a command's exit status), records the result on the artifact
+//! row, and keeps the task running when a check fails.
+
+use crate::config::{ArtifactVerificationRule, ArtifactVerificationStrategy};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Characters of command stderr kept in a failure reason.
+const MAX_FAILURE_DETAIL_CHARS: usize = 200;
+
+/// Outcome of checking one claimed artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArtifactCheck {
+    /// The file is on disk, non-empty, and hashed.
+    Present {
+        checksum: String,
+        size_bytes: u64,
+    },
+    /// A non-file check (HTTP, command) passed.
+    Confirmed {
+        detail: String,
+    },
+    Empty,
+    Missing,
+    /// The configured check ran and rejected the artifact.
+    Failed {
+        reason: String,
+    },
+}
+
+impl ArtifactCheck {
+    pub fn passed(&self) -> bool {
+        matches!(self, Self::Present { .. } | Self::Confirmed { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactVerification {
+    /// Path as claimed by the write call.
+    pub path: String,
+    pub strategy: ArtifactVerificationStrategy,
+    pub check: ArtifactCheck,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArtifactVerifier {
+    workspace_dir: PathBuf,
+    rules: Vec<ArtifactVerificationRule>,
+}
+
+impl ArtifactVerifier {
+    pub fn new(workspace_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace_dir: workspace_dir.into(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with_rules(mut self, rules: Vec<ArtifactVerificationRule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Absolute location of `path`; relative paths are workspace-relative.
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        let raw = Path::new(path);
+        if raw.is_absolute() {
+            raw.to_path_buf()
+        } else {
+            self.workspace_dir.join(raw)
+        }
+    }
+
+    /// First rule whose pattern matches `path` as claimed or relative to the
+    /// workspace.
+    pub fn rule_for(&self, path: &str) -> Option<&ArtifactVerificationRule> {
+        let relative = Path::new(path)
+            .strip_prefix(&self.workspace_dir)
+            .ok()
+            .map(|rel| rel.display().to_string());
+        self.rules.iter().find(|rule| {
+            glob::Pattern::new(rule.pattern.trim()).is_ok_and(|pattern| {
+                pattern.matches(path) || relative.as_deref().is_some_and(|rel| pattern.matches(rel))
+            })
+        })
+    }
+
+    /// Check `path` with its configured strategy (checksum by default).
+    pub async fn verify(&self, path: &str) -> ArtifactVerification {
+        let rule = self.rule_for(path);
+        let strategy = rule.map_or(ArtifactVerificationStrategy::Checksum, |rule| rule.strategy);
+        let check = match (strategy, rule) {
+            (ArtifactVerificationStrategy::HttpGet, Some(rule)) => http_get(rule, path).await,
+            (ArtifactVerificationStrategy::Command, Some(rule)) => {
+                run_command(rule, path, &self.workspace_dir).await
+            }
+            (strategy, _) => {
+                let resolved = self.resolve(path);
+                let read_back = strategy == ArtifactVerificationStrategy::ReadBack;
+                tokio::task::spawn_blocking(move || check_file(&resolved, read_back))
+                    .await
+                    .unwrap_or(ArtifactCheck::Missing)
+            }
+        };
+        ArtifactVerification {
+            path: path.to_string(),
+            strategy,
+            check,
+        }
+    }
+
+    pub async fn verify_all(&self, paths: &[String]) -> Vec<ArtifactVerification> {
+        let mut checks = Vec::with_capacity(paths.len());
+        for path in paths {
+            checks.push(self.verify(path).await);
+        }
+        checks
+    }
+}
+
+/// `test -s` plus SHA-256; `read_back` additionally requires UTF-8 text.
+/// Unreadable files count as missing.
+fn check_file(path: &Path, read_back: bool) -> ArtifactCheck {
+    match sha256_file(path, read_back) {
+        Ok((_, 0, _)) => ArtifactCheck::Empty,
+        Ok((_, _, false)) => ArtifactCheck::Failed {
+            reason: "not UTF-8 text".to_string(),
+        },
+        Ok((checksum, size_bytes, true)) => ArtifactCheck::Present {
+            checksum,
+            size_bytes,
+        },
+        Err(_) => ArtifactCheck::Missing,
+    }
+}
+
+/// Hash the file; the flag reports whether it is valid UTF-8 (always `true`
+/// when `check_text` is off).
+fn sha256_file(path: &Path, check_text: bool) -> std::io::Result<(String, u64, bool)> {
+    let mut file = std::fs::File::open(path)?;
+    if !file.metadata()?.is_file() {
+        return Err(std::io::Error::other("not a regular file"));
+    }
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    let mut size = 0u64;
+    let mut text = Vec::new();
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+        if check_text {
+            text.extend_from_slice(&buf[..read]);
+        }
+        size += read as u64;
+    }
+    let is_text = !check_text || std::str::from_utf8(&text).is_ok();
+    Ok((hex::encode(hasher.finalize()), size, is_text))
+}
+
+async fn http_get(rule: &ArtifactVerificationRule, path: &str) -> ArtifactCheck {
+    let url = match rule.url.as_deref() {
+        Some(template) => template.replace("{path}", path),
+        None if path.starts_with("http://") || path.starts_with("https://") => path.to_string(),
+        None => {
+            return ArtifactCheck::Failed {
+                reason: "http_get rule has no url".to_string(),
+            }
+        }
+    };
+    let client = crate::config::build_runtime_proxy_client_with_timeouts(
+        "agent.artifact_verifier",
+        rule.timeout_secs,
+        rule.timeout_secs.min(10),
+    );
+    match client.get(&url).send().await {
+        Ok(response) if response.status().is_success() => ArtifactCheck::Confirmed {
+            detail: format!("GET {url} -> {}", response.status()),
+        },
+        Ok(response) => ArtifactCheck::Failed {
+            reason: format!("GET {url} -> {}", response.status()),
+        },
+        Err(err) => ArtifactCheck::Failed {
+            reason: format!("GET {url} failed: {err}"),
+        },
+    }
+}
+
+async fn run_command(
+    rule: &ArtifactVerificationRule,
+    path: &str,
+    workspace_dir: &Path,
+) -> ArtifactCheck {
+    let Some(template) = rule.command.as_deref() else {
+        return ArtifactCheck::Failed {
+            reason: "command rule has no command".to_string(),
+        };
+    };
+    let command = template.replace("{path}", &shell_quote(path));
+    let run = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .current_dir(workspace_dir)
+        .kill_on_drop(true)
+        .output();
+    match tokio::time::timeout(Duration::from_secs(rule.timeout_secs), run).await {
+        Ok(Ok(output)) if output.status.success() => ArtifactCheck::Confirmed {
+            detail: format!("`{command}` exited 0"),
+        },
+        Ok(Ok(output)) => ArtifactCheck::Failed {
+            reason: format!(
+                "`{command}` exited {}: {}",
+                output
+                    .status
+                    .code()
+                    .map_or_else(|| "by signal".to_string(), |code| code.to_string()),
+                crate::util::truncate_with_ellipsis(
+                    String::from_utf8_lossy(&output.stderr).trim(),
+                    MAX_FAILURE_DETAIL_CHARS
+                )
+            ),
+        },
+        Ok(Err(err)) => ArtifactCheck::Failed {
+            reason: format!("`{command}` could not start: {err}"),
+        },
+        Err(_) => ArtifactCheck::Failed {
+            reason: format!("`{command}` timed out after {}s", rule.timeout_secs),
+        },
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn rule(pattern: &str, strategy: ArtifactVerificationStrategy) -> ArtifactVerificationRule {
+        ArtifactVerificationRule {
+            pattern: pattern.to_string(),
+            strategy,
+            url: None,
+            command: None,
+            timeout_secs: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn verifier_hashes_present_files_and_flags_missing_or_empty_ones() {
+        let tmp = TempDir::new().expect("tempdir");
+        std::fs::write(tmp.path().join("report.md"), "abc").expect("write");
+        std::fs::write(tmp.path().join("empty.md"), "").expect("write");
+        let verifier = ArtifactVerifier::new(tmp.path());
+
+        let checks = verifier
+            .verify_all(&[
+                "report.md".to_string(),
+                "empty.md".to_string(),
+                tmp.path().join("gone.md").display().to_string(),
+            ])
+            .await;
+        assert_eq!(
+            checks[0].check,
+            ArtifactCheck::Present {
+                checksum: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+                    .to_string(),
+                size_bytes: 3,
+            }
+        );
+        assert_eq!(checks[0].strategy, ArtifactVerificationStrategy::Checksum);
+        assert_eq!(checks[1].check, ArtifactCheck::Empty);
+        assert_eq!(checks[2].check, ArtifactCheck::Missing);
+        assert!(!checks[2].check.passed());
+    }
+
+    #[tokio::test]
+    async fn rules_pick_read_back_and_command_strategies() {
+        let tmp = TempDir::new().expect("tempdir");
+        std::fs::write(tmp.path().join("notes.txt"), [0xff, 0xfe]).expect("write");
+        std::fs::write(tmp.path().join("app.bin"), [0xff, 0xfe]).expect("write");
+        let verifier = ArtifactVerifier::new(tmp.path()).with_rules(vec![
+            rule("*.txt", ArtifactVerificationStrategy::ReadBack),
+            ArtifactVerificationRule {
+                command: Some("test -s {path} && false".to_string()),
+                ..rule("build/*", ArtifactVerificationStrategy::Command)
+            },
+            ArtifactVerificationRule {
+                command: Some("test -s {path}".to_string()),
+                ..rule("*.bin", ArtifactVerificationStrategy::Command)
+            },
+        ]);
+
+        let text = verifier.verify("notes.txt").await;
+        assert_eq!(text.strategy, ArtifactVerificationStrategy::ReadBack);
+        assert!(matches!(text.check, ArtifactCheck::Failed { .. }));
+
+        let built = verifier
+            .verify(&tmp.path().join("app.bin").display().to_string())
+            .await;
+        assert_eq!(built.strategy, ArtifactVerificationStrategy::Command);
+        assert!(built.check.passed());
+
+        let failing = verifier.verify("build/out").await;
+        assert!(matches!(
+            failing.check,
+            ArtifactCheck::Failed { ref reason } if reason.contains("exited 1")
+        ));
+    }
+}
@@ -0,0 +1,111 @@
+//! File paths a reply claims to have produced.
+//!
+//! "Saved to report.md" is only a claim. The completion evaluator pulls such
+//! paths out of the reply so the engine can record them as unverified
+//! artifacts and ask the model to check those specific files, instead of a
+//! generic "please verify" nudge.
+
+use regex::Regex;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Upper bound on paths taken from one reply.
+const MAX_CLAIMED_PATHS: usize = 10;
+
+/// Words that mark a line as a write claim rather than a mention.
+const CLAIM_MARKERS: &[&str] = &[
+    "saved",
+    "wrote",
+    "written",
+    "created",
+    "generated",
+    "exported",
+    "stored",
+    "保存",
+    "写入",
+    "写到",
+    "生成",
+    "创建",
+    "导出",
+    "作成",
+    "저장",
+    "guardado",
+    "gespeichert",
+];
+
+static URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[a-zA-Z][a-zA-Z0-9+.-]*://\S+").unwrap());
+
+static QUOTED_PATH_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"`([^`\n]+\.[A-Za-z][A-Za-z0-9]{0,7})`").unwrap());
+
+static BARE_PATH_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:[A-Za-z]:)?[A-Za-z0-9_~./\\-]*[A-Za-z0-9_-]\.[A-Za-z][A-Za-z0-9]{0,7}").unwrap()
+});
+
+/// Paths named on lines of `text` that claim a write, in order of appearance.
+pub fn extract_claimed_paths(text: &str) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    let mut push = |candidate: &str| {
+        let candidate = candidate.trim();
+        if !candidate.is_empty()
+            && paths.len() < MAX_CLAIMED_PATHS
+            && !paths.iter().any(|seen| seen == candidate)
+        {
+            paths.push(candidate.to_string());
+        }
+    };
+
+    for line in text.lines() {
+        let lower = line.to_lowercase();
+        if !CLAIM_MARKERS.iter().any(|marker| lower.contains(marker)) {
+            continue;
+        }
+        let line = URL_RE.replace_all(line, " ");
+        for captures in QUOTED_PATH_RE.captures_iter(&line) {
+            push(&captures[1]);
+        }
+        let unquoted = QUOTED_PATH_RE.replace_all(&line, " ");
+        for found in BARE_PATH_RE.find_iter(&unquoted) {
+            push(found.as_str());
+        }
+    }
+    paths
+}
+
+/// `path` relative to `workspace_dir` when it points inside the workspace;
+/// other paths are returned unchanged apart from a leading `./`.
+pub fn workspace_relative(path: &str, workspace_dir: &Path) -> String {
+    if let Ok(relative) = Path::new(path).strip_prefix(workspace_dir) {
+        return relative.display().to_string();
+    }
+    path.strip_prefix("./").unwrap_or(path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_paths_only_from_claim_lines() {
+        let reply = "I looked at notes.txt first.\n\
+                     Saved the summary to reports/q3.md and `out/final draft.csv`.\n\
+                     已保存到 data.json。Source: https://example.com/page.html";
+        assert_eq!(
+            extract_claimed_paths(reply),
+            ["out/final draft.csv", "reports/q3.md", "data.json"]
+        );
+        assert!(extract_claimed_paths("Done, see report.md").is_empty());
+    }
+
+    #[test]
+    fn resolves_workspace_paths_to_relative_form() {
+        let workspace = Path::new("/home/user/workspace");
+        assert_eq!(
+            workspace_relative("/home/user/workspace/reports/q3.md", workspace),
+            "reports/q3.md"
+        );
+        assert_eq!(workspace_relative("./report.md", workspace), "report.md");
+        assert_eq!(workspace_relative("/tmp/out.md", workspace), "/tmp/out.md");
+    }
+}
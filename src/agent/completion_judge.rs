@@ -0,0 +1,222 @@
+//! LLM-judge completion evaluation (`evaluator = "llm_judge"`).
+//!
+//! Keyword heuristics misread many English/Chinese replies, so this mode asks
+//! a cheap model whether the reply finishes the request, given a compact
+//! summary of the tool evidence gathered so far. The engine only trusts the
+//! verdict above the policy's confidence floor and falls back to the
+//! heuristic decision when the judge errors, times out, or is unsure.
+
+use crate::agent::evidence_ledger::collect_evidence_from_invocations;
+use crate::agent::task_types::ToolInvocation;
+use crate::providers::{ChatMessage, Provider};
+use crate::util::truncate_with_ellipsis;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Reply text sent to the judge is capped to keep the call cheap.
+const MAX_JUDGED_RESPONSE_CHARS: usize = 4_000;
+
+pub struct CompletionJudgeRequest<'a> {
+    pub provider: &'a dyn Provider,
+    pub model: &'a str,
+    pub original_request: &'a str,
+    pub response: &'a str,
+    pub invocations: &'a [ToolInvocation],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JudgeVerdict {
+    pub complete: bool,
+    /// Judge's self-reported confidence, clamped to `0.0..=1.0`.
+    pub confidence: f64,
+    pub reason: String,
+}
+
+/// Ask the judge model whether `request.response` completes the task.
+pub async fn judge_completion(
+    request: CompletionJudgeRequest<'_>,
+    timeout: Duration,
+) -> anyhow::Result<JudgeVerdict> {
+    let system_prompt = "You judge whether an assistant reply finishes the user's task. Replies may be in any language. A reply that only announces work in progress, or claims results the tool evidence does not support, is not complete. Return JSON only: {\"complete\": boolean, \"confidence\": number between 0 and 1, \"reason\": string}.";
+
+    let user_prompt = format!(
+        "original_request:\n{}\n\ntool_evidence:\n{}\n\nfinal_response:\n{}\n\nReturn JSON only.",
+        request.original_request,
+        summarize_tool_evidence(request.invocations),
+        truncate_with_ellipsis(request.response, MAX_JUDGED_RESPONSE_CHARS),
+    );
+
+    let messages = [
+        ChatMessage::system(system_prompt),
+        ChatMessage::user(user_prompt),
+    ];
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "complete": { "type": "boolean" },
+            "confidence": { "type": "number" },
+            "reason": { "type": "string" }
+        },
+        "required": ["complete", "confidence"]
+    });
+    let value = tokio::time::timeout(
+        timeout,
+        request
+            .provider
+            .chat_structured(&messages, &schema, request.model, 0.0),
+    )
+    .await
+    .map_err(|_| anyhow::anyhow!("completion judge timed out"))??;
+
+    parse_judge_verdict(value)
+}
+
+/// One-line summary of which tools succeeded or failed.
+pub fn summarize_tool_evidence(invocations: &[ToolInvocation]) -> String {
+    let evidence = collect_evidence_from_invocations(invocations);
+    let list = |tools: Vec<String>| {
+        if tools.is_empty() {
+            "none".to_string()
+        } else {
+            tools.join(", ")
+        }
+    };
+    format!(
+        "successful tools: {}; failed tools: {}; read-back after write: {}",
+        list(evidence.successful_tool_names()),
+        list(evidence.failed_tool_names()),
+        if evidence.has_post_write_read_verification() {
+            "yes"
+        } else {
+            "no"
+        }
+    )
+}
+
+#[derive(Debug, Deserialize)]
+struct JudgeVerdictPayload {
+    complete: bool,
+    confidence: f64,
+    #[serde(default)]
+    reason: String,
+}
+
+fn parse_judge_verdict(value: serde_json::Value) -> anyhow::Result<JudgeVerdict> {
+    let parsed: JudgeVerdictPayload = serde_json::from_value(value)
+        .map_err(|e| anyhow::anyhow!("invalid completion judge payload: {e}"))?;
+    if !parsed.confidence.is_finite() {
+        anyhow::bail!("invalid completion judge confidence");
+    }
+
+    Ok(JudgeVerdict {
+        complete: parsed.complete,
+        confidence: parsed.confidence.clamp(0.0, 1.0),
+        reason: parsed.reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    struct ScriptedProvider {
+        responses: Mutex<Vec<anyhow::Result<String>>>,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Provider for ScriptedProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            self.prompts
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(message.to_string());
+            self.responses
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(0)
+        }
+    }
+
+    fn provider(response: anyhow::Result<String>) -> ScriptedProvider {
+        ScriptedProvider {
+            responses: Mutex::new(vec![response]),
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    #[tokio::test]
+    async fn judge_sends_evidence_and_parses_verdict() {
+        let provider = provider(Ok(
+            "Verdict: {\"complete\": true, \"confidence\": 1.4, \"reason\": \"answered\"}".into(),
+        ));
+        let invocations = vec![ToolInvocation {
+            name: "file_read".into(),
+            kind: crate::tools::ToolKind::ReadLike,
+            arguments: serde_json::json!({"path": "notes.md"}),
+            success: true,
+            status: None,
+            output: "abc".into(),
+            iteration: 1,
+            metadata: serde_json::Map::new(),
+        }];
+        let verdict = judge_completion(
+            CompletionJudgeRequest {
+                provider: &provider,
+                model: "cheap-model",
+                original_request: "总结 notes.md",
+                response: "notes.md 讲的是发布流程。",
+                invocations: &invocations,
+            },
+            Duration::from_secs(1),
+        )
+        .await
+        .expect("verdict");
+
+        assert!(verdict.complete);
+        assert!((verdict.confidence - 1.0).abs() < f64::EPSILON);
+        assert_eq!(verdict.reason, "answered");
+        let prompts = provider.prompts.lock().unwrap_or_else(|e| e.into_inner());
+        assert!(prompts[0].contains("successful tools: file_read; failed tools: none"));
+    }
+
+    #[tokio::test]
+    async fn judge_surfaces_provider_and_payload_errors() {
+        fn request(provider: &dyn Provider) -> CompletionJudgeRequest<'_> {
+            CompletionJudgeRequest {
+                provider,
+                model: "cheap-model",
+                original_request: "check",
+                response: "working on it",
+                invocations: &[],
+            }
+        }
+        let failing = provider(Err(anyhow::anyhow!("rate limited")));
+        assert!(judge_completion(request(&failing), Duration::from_secs(1))
+            .await
+            .is_err());
+        let garbled = ScriptedProvider {
+            responses: Mutex::new((0..3).map(|_| Ok("probably done".to_string())).collect()),
+            prompts: Mutex::new(Vec::new()),
+        };
+        assert!(judge_completion(request(&garbled), Duration::from_secs(1))
+            .await
+            .is_err());
+        assert_eq!(
+            garbled
+                .prompts
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .len(),
+            3
+        );
+    }
+}
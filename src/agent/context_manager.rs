@@ -0,0 +1,272 @@
+//! Keeps a task's conversation inside the model's context window.
+//!
+//! Token counts are estimated from message length. When the history grows
+//! past the configured budget, older assistant turns and tool results are
+//! summarized into one synopsis message; system prompts, user messages, and
+//! the most recent exchanges stay verbatim.
+
+use crate::providers::{ChatMessage, Provider};
+use crate::util::truncate_with_ellipsis;
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Event logged when a run's history is compacted.
+pub const CONTEXT_COMPACTED_EVENT: &str = "context_compacted";
+
+/// Marks the synopsis message that replaces compacted turns.
+pub const SYNOPSIS_PREFIX: &str = "[Context synopsis]";
+
+/// Rough chars-per-token ratio for estimates.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Per-message framing overhead (role, separators) in tokens.
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Most recent messages never compacted.
+const KEEP_RECENT_MESSAGES: usize = 8;
+
+/// Cap on the transcript handed to the summarizer.
+const SYNOPSIS_SOURCE_MAX_CHARS: usize = 16_000;
+
+/// Cap on each message's share of that transcript.
+const SYNOPSIS_MESSAGE_MAX_CHARS: usize = 1_500;
+
+/// Cap on the stored synopsis.
+const SYNOPSIS_MAX_CHARS: usize = 3_000;
+
+const SUMMARIZER_SYSTEM_PROMPT: &str = "You compress the working history of an agent task. Summarize the assistant turns and tool results below into concise bullet points. Preserve: files read or written, commands run and their outcomes, facts found, decisions made, errors hit, and what remains to do. Omit: verbose tool output and repetition. Output plain text bullet points only.";
+
+/// Estimated tokens one message takes in the prompt.
+pub fn estimate_tokens(message: &ChatMessage) -> usize {
+    message.content.len().div_ceil(CHARS_PER_TOKEN) + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Estimated tokens of a whole history.
+pub fn history_tokens(history: &[ChatMessage]) -> usize {
+    history.iter().map(estimate_tokens).sum()
+}
+
+/// What one compaction did, as logged in its event.
+#[derive(Debug, Clone, Serialize)]
+pub struct Compaction {
+    pub messages_compacted: usize,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    pub synopsis: String,
+}
+
+/// Compacts a history once its estimated size passes `max_tokens`.
+#[derive(Debug, Clone, Copy)]
+pub struct ContextManager {
+    max_tokens: usize,
+}
+
+impl ContextManager {
+    /// `max_tokens == 0` disables compaction.
+    pub fn new(max_tokens: usize) -> Self {
+        Self { max_tokens }
+    }
+
+    pub fn needs_compaction(&self, history: &[ChatMessage]) -> bool {
+        self.max_tokens > 0 && history_tokens(history) > self.max_tokens
+    }
+
+    /// Indices of the assistant and tool messages from `floor` on that are
+    /// old enough to compact. The recent tail never starts with tool results,
+    /// so a tool call and its results are compacted or kept together.
+    fn compactable(history: &[ChatMessage], floor: usize) -> Vec<usize> {
+        let mut boundary = history.len().saturating_sub(KEEP_RECENT_MESSAGES);
+        while boundary > 0 && history[boundary].role == "tool" {
+            boundary -= 1;
+        }
+        (floor..boundary)
+            .filter(|&index| matches!(history[index].role.as_str(), "assistant" | "tool"))
+            .collect()
+    }
+
+    /// Summarize older turns with `provider` when the history is over budget.
+    /// Messages before `floor` are never touched, so indices into that prefix
+    /// stay valid. A failed summarization falls back to a truncated
+    /// transcript, so the history always shrinks. Returns `None` when nothing
+    /// was compacted.
+    pub async fn compact(
+        &self,
+        history: &mut Vec<ChatMessage>,
+        floor: usize,
+        provider: &dyn Provider,
+        model: &str,
+    ) -> Option<Compaction> {
+        if !self.needs_compaction(history) {
+            return None;
+        }
+        let indices = Self::compactable(history, floor);
+        if indices.len() < 2 {
+            return None;
+        }
+        let tokens_before = history_tokens(history);
+
+        let mut transcript = String::new();
+        for &index in &indices {
+            let message = &history[index];
+            let _ = writeln!(
+                transcript,
+                "{}: {}",
+                message.role.to_uppercase(),
+                truncate_with_ellipsis(message.content.trim(), SYNOPSIS_MESSAGE_MAX_CHARS)
+            );
+        }
+        let transcript = truncate_with_ellipsis(&transcript, SYNOPSIS_SOURCE_MAX_CHARS);
+        let request = format!(
+            "Summarize this task history so the work can continue without it (max 15 bullet points).\n\n{transcript}"
+        );
+        let summary = match provider
+            .chat_with_system(Some(SUMMARIZER_SYSTEM_PROMPT), &request, model, 0.2)
+            .await
+        {
+            Ok(summary) if !summary.trim().is_empty() => summary,
+            Ok(_) => transcript.clone(),
+            Err(err) => {
+                tracing::warn!("Context summarization failed, truncating instead: {err}");
+                transcript.clone()
+            }
+        };
+        let synopsis = truncate_with_ellipsis(summary.trim(), SYNOPSIS_MAX_CHARS);
+
+        let first = indices[0];
+        let mut compacted = Vec::with_capacity(history.len() - indices.len() + 1);
+        let mut next = indices.iter().peekable();
+        for (index, message) in history.drain(..).enumerate() {
+            if next.peek() == Some(&&index) {
+                next.next();
+                if index == first {
+                    compacted.push(ChatMessage::assistant(format!(
+                        "{SYNOPSIS_PREFIX}\n{synopsis}"
+                    )));
+                }
+                continue;
+            }
+            compacted.push(message);
+        }
+        *history = compacted;
+
+        Some(Compaction {
+            messages_compacted: indices.len(),
+            tokens_before,
+            tokens_after: history_tokens(history),
+            synopsis,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    struct Summarizer(Option<&'static str>);
+
+    #[async_trait]
+    impl Provider for Summarizer {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            match self.0 {
+                Some(summary) => Ok(summary.to_string()),
+                None => anyhow::bail!("summarizer unavailable"),
+            }
+        }
+    }
+
+    fn long_task_history(rounds: usize) -> Vec<ChatMessage> {
+        let mut history = vec![
+            ChatMessage::system("system prompt"),
+            ChatMessage::user("build the report"),
+        ];
+        for round in 0..rounds {
+            history.push(ChatMessage::assistant(format!("calling tool {round}")));
+            history.push(ChatMessage::tool("x".repeat(2_000)));
+        }
+        history
+    }
+
+    #[test]
+    fn token_estimates_scale_with_length() {
+        assert_eq!(estimate_tokens(&ChatMessage::user("")), 4);
+        assert_eq!(estimate_tokens(&ChatMessage::user("abcdefgh")), 6);
+        let history = long_task_history(1);
+        assert_eq!(
+            history_tokens(&history),
+            history.iter().map(estimate_tokens).sum()
+        );
+    }
+
+    #[tokio::test]
+    async fn under_budget_or_disabled_leaves_history_alone() {
+        let mut history = long_task_history(10);
+        let before = history.len();
+        let provider = Summarizer(Some("- summary"));
+        assert!(ContextManager::new(0)
+            .compact(&mut history, 0, &provider, "model")
+            .await
+            .is_none());
+        assert!(ContextManager::new(1_000_000)
+            .compact(&mut history, 0, &provider, "model")
+            .await
+            .is_none());
+        assert_eq!(history.len(), before);
+    }
+
+    #[tokio::test]
+    async fn older_turns_are_replaced_by_one_synopsis() {
+        let mut history = long_task_history(10);
+        let manager = ContextManager::new(1_000);
+        let compaction = manager
+            .compact(
+                &mut history,
+                0,
+                &Summarizer(Some("- read the data")),
+                "model",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(history[0].role, "system");
+        assert_eq!(history[1].content, "build the report");
+        assert_eq!(history[2].content, "[Context synopsis]\n- read the data");
+        assert_eq!(history.len(), 3 + KEEP_RECENT_MESSAGES);
+        assert_eq!(history[3].role, "assistant");
+        assert_eq!(compaction.messages_compacted, 12);
+        assert!(compaction.tokens_after < compaction.tokens_before);
+    }
+
+    #[tokio::test]
+    async fn failed_summaries_fall_back_to_a_truncated_transcript() {
+        let mut history = long_task_history(10);
+        let compaction = ContextManager::new(1_000)
+            .compact(&mut history, 0, &Summarizer(None), "model")
+            .await
+            .unwrap();
+        assert!(compaction.synopsis.starts_with("ASSISTANT: calling tool 0"));
+        assert!(compaction.synopsis.chars().count() <= SYNOPSIS_MAX_CHARS + 3);
+    }
+
+    #[test]
+    fn recent_tail_does_not_start_with_tool_results() {
+        let mut history = long_task_history(6);
+        history.push(ChatMessage::tool("second result"));
+        let indices = ContextManager::compactable(&history, 0);
+        let boundary = indices.last().unwrap() + 1;
+        assert_eq!(history[boundary].role, "assistant");
+    }
+
+    #[test]
+    fn messages_before_the_floor_are_kept() {
+        let history = long_task_history(10);
+        let indices = ContextManager::compactable(&history, 6);
+        assert_eq!(indices.first(), Some(&6));
+    }
+}
@@ -44,7 +44,7 @@ impl ContractGate {
     }
 }
 
-fn requirement_satisfied(
+pub fn requirement_satisfied(
     contract: &TaskContract,
     requirement: &EvidenceRequirement,
     ledger: &EvidenceLedger,
@@ -64,7 +64,7 @@ fn requirement_satisfied(
                         ledger.has_successful_read()
                     }
                 }
-                "web_search_tool" | "http_request" | "browser" | "browser_open" => {
+                "web_search_tool" | "web_fetch" | "http_request" | "browser" | "browser_open" => {
                     ledger.has_successful_search()
                 }
                 "shell" if contract.task_type == TaskType::Search => ledger.has_successful_search(),
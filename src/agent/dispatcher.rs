@@ -1,5 +1,5 @@
 use crate::providers::{ChatMessage, ChatResponse, ConversationMessage, ToolResultMessage};
-use crate::tools::{Tool, ToolSpec};
+use crate::tools::{Tool, ToolResultEnvelope, ToolSpec, ToolStatus};
 use serde_json::Value;
 use std::fmt::Write;
 
@@ -95,12 +95,13 @@ impl ToolDispatcher for XmlToolDispatcher {
     fn format_results(&self, results: &[ToolExecutionResult]) -> ConversationMessage {
         let mut content = String::new();
         for result in results {
-            let status = if result.success { "ok" } else { "error" };
-            let _ = writeln!(
-                content,
-                "<tool_result name=\"{}\" status=\"{}\">\n{}\n</tool_result>",
-                result.name, status, result.output
-            );
+            let status = if result.success {
+                ToolStatus::Ok
+            } else {
+                ToolStatus::Error
+            };
+            let envelope = ToolResultEnvelope::new(status, result.output.as_str());
+            let _ = writeln!(content, "{}", envelope.to_prompt_block(&result.name));
         }
         ConversationMessage::Chat(ChatMessage::user(format!("[Tool results]\n{content}")))
     }
@@ -252,6 +253,7 @@ mod tests {
             tool_calls: vec![],
             usage: None,
             reasoning_content: None,
+            finish_reason: None,
         };
         let dispatcher = XmlToolDispatcher;
         let (_, calls) = dispatcher.parse_response(&response);
@@ -270,6 +272,7 @@ mod tests {
             }],
             usage: None,
             reasoning_content: None,
+            finish_reason: None,
         };
         let dispatcher = NativeToolDispatcher;
         let (_, calls) = dispatcher.parse_response(&response);
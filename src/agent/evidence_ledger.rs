@@ -1,4 +1,6 @@
+use crate::agent::task_types::ToolInvocation;
 use crate::providers::ChatMessage;
+use crate::tools::{ToolKind, ToolStatus};
 use std::collections::{HashMap, HashSet, VecDeque};
 
 #[derive(Debug, Clone, Default)]
@@ -10,6 +12,7 @@ pub struct EvidenceLedger {
     successful_tools: HashSet<String>,
     failed_tools: HashSet<String>,
     saw_access_denied_failure: bool,
+    written_paths: Vec<String>,
 }
 
 impl EvidenceLedger {
@@ -42,22 +45,56 @@ impl EvidenceLedger {
     pub fn has_access_denied_failure(&self) -> bool {
         self.saw_access_denied_failure
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum ToolKind {
-    WriteLike,
-    ReadLike,
-    SearchLike,
-    Other,
+    /// Paths passed to write tools whose calls succeeded, in first-write order.
+    pub fn written_paths(&self) -> &[String] {
+        &self.written_paths
+    }
+
+    /// Names of tools that succeeded at least once, sorted.
+    pub fn successful_tool_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.successful_tools.iter().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Names of tools that failed at least once, sorted.
+    pub fn failed_tool_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.failed_tools.iter().cloned().collect();
+        names.sort();
+        names
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct ObservedToolCall {
     name: String,
     kind: ToolKind,
+    /// Target path, recorded for write calls.
+    path: Option<String>,
+}
+
+/// Evidence from the structured invocation records emitted by the tool loop.
+/// Success comes from the tool's own result instead of output keywords, and
+/// the evidence kind from the registered tool, so any write-like tool (patch,
+/// append, MCP) counts as write evidence.
+pub fn collect_evidence_from_invocations(invocations: &[ToolInvocation]) -> EvidenceLedger {
+    let mut ledger = EvidenceLedger::default();
+    for invocation in invocations {
+        let name = invocation.name.trim().to_ascii_lowercase();
+        let call = if invocation.kind == ToolKind::Other {
+            observed_tool_call_from_name_and_args(&name, Some(&invocation.arguments))
+        } else {
+            observed_tool_call_with_kind(name, invocation.kind, Some(&invocation.arguments))
+        };
+        apply_tool_result_event(call, &invocation.output, invocation.status(), &mut ledger);
+    }
+    ledger
 }
 
+/// Evidence scraped from conversation text, for histories recorded without
+/// invocation records. Results carry their status; output keywords are only
+/// consulted for results stored before statuses existed.
 pub fn collect_evidence_from_history(history: &[ChatMessage]) -> EvidenceLedger {
     let mut ledger = EvidenceLedger::default();
     let mut queued_calls = VecDeque::new();
@@ -152,18 +189,18 @@ fn collect_assistant_tool_calls_from_native_json(
     }
 }
 
-fn extract_shell_command_from_arguments(arguments: Option<&serde_json::Value>) -> Option<String> {
+fn extract_string_argument(arguments: Option<&serde_json::Value>, key: &str) -> Option<String> {
     let args = arguments?;
     match args {
         serde_json::Value::Object(_) => args
-            .get("command")
+            .get(key)
             .and_then(serde_json::Value::as_str)
             .map(ToString::to_string),
         serde_json::Value::String(raw) => serde_json::from_str::<serde_json::Value>(raw)
             .ok()
             .and_then(|parsed| {
                 parsed
-                    .get("command")
+                    .get(key)
                     .and_then(serde_json::Value::as_str)
                     .map(ToString::to_string)
             }),
@@ -188,26 +225,56 @@ fn observed_tool_call_from_name_and_args(
     arguments: Option<&serde_json::Value>,
 ) -> ObservedToolCall {
     if tool_name == "shell" {
-        let shell_kind = extract_shell_command_from_arguments(arguments)
+        let shell_kind = extract_string_argument(arguments, "command")
             .as_deref()
             .map(classify_shell_command)
             .unwrap_or(ToolKind::Other);
         return ObservedToolCall {
             name: "shell".to_string(),
             kind: shell_kind,
+            path: None,
         };
     }
-    ObservedToolCall {
-        name: tool_name.to_string(),
-        kind: classify_tool_kind(tool_name),
-    }
+    observed_tool_call_with_kind(
+        tool_name.to_string(),
+        classify_tool_kind(tool_name),
+        arguments,
+    )
+}
+
+fn observed_tool_call_with_kind(
+    name: String,
+    kind: ToolKind,
+    arguments: Option<&serde_json::Value>,
+) -> ObservedToolCall {
+    // A dry run (`file_edit` with `dry_run`) writes nothing.
+    let dry_run = arguments
+        .and_then(|args| args.get("dry_run"))
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false);
+    let kind = if kind == ToolKind::WriteLike && dry_run {
+        ToolKind::Other
+    } else {
+        kind
+    };
+    let path = if kind == ToolKind::WriteLike {
+        extract_string_argument(arguments, "path")
+            .or_else(|| extract_string_argument(arguments, "file_path"))
+    } else {
+        None
+    };
+    ObservedToolCall { name, kind, path }
 }
 
+/// Name-based fallback for calls recorded without a tool-reported kind.
 fn classify_tool_kind(tool_name: &str) -> ToolKind {
     match tool_name {
-        "file_write" => ToolKind::WriteLike,
-        "file_read" | "glob_search" | "content_search" | "pdf_read" => ToolKind::ReadLike,
-        "web_search_tool" | "http_request" | "browser" | "browser_open" => ToolKind::SearchLike,
+        "file_write" | "file_edit" | "apply_patch" | "append_file" => ToolKind::WriteLike,
+        "file_read" | "glob_search" | "content_search" | "code_search" | "list_dir"
+        | "pdf_read" => ToolKind::ReadLike,
+        "web_search_tool" | "web_fetch" | "http_request" | "browser" | "browser_open" => {
+            ToolKind::Network
+        }
         _ => ToolKind::Other,
     }
 }
@@ -231,6 +298,8 @@ fn collect_prompt_tool_result_evidence(
         let Some(body_start) = after_tag_start.find('>') else {
             break;
         };
+        let status =
+            tag_attribute(&after_tag_start[..body_start], "status").and_then(ToolStatus::parse);
         let after_body_start = &after_tag_start[body_start + 1..];
         let Some(close_idx) = after_body_start.find("</tool_result>") else {
             break;
@@ -243,6 +312,7 @@ fn collect_prompt_tool_result_evidence(
                 .unwrap_or_else(|| ObservedToolCall {
                     name: "shell".to_string(),
                     kind: ToolKind::Other,
+                    path: None,
                 })
         } else if queued_calls
             .front()
@@ -253,14 +323,17 @@ fn collect_prompt_tool_result_evidence(
                 .unwrap_or_else(|| ObservedToolCall {
                     name: normalized.clone(),
                     kind: classify_tool_kind(&normalized),
+                    path: None,
                 })
         } else {
             ObservedToolCall {
                 name: normalized,
                 kind: classify_tool_kind(tool_name),
+                path: None,
             }
         };
-        apply_tool_result_event(call, output, ledger);
+        let status = status.unwrap_or_else(|| status_from_output(output));
+        apply_tool_result_event(call, output, status, ledger);
 
         remaining = &after_body_start[close_idx + "</tool_result>".len()..];
     }
@@ -272,8 +345,8 @@ fn collect_native_tool_result_evidence(
     calls_by_id: &HashMap<String, ObservedToolCall>,
     ledger: &mut EvidenceLedger,
 ) {
-    let (tool_call_id, output) =
-        parse_tool_message_payload(content).unwrap_or_else(|| (None, content.trim().to_string()));
+    let (tool_call_id, output, status) = parse_tool_message_payload(content)
+        .unwrap_or_else(|| (None, content.trim().to_string(), None));
 
     let call = tool_call_id
         .as_deref()
@@ -282,11 +355,15 @@ fn collect_native_tool_result_evidence(
         .unwrap_or_else(|| ObservedToolCall {
             name: "unknown".to_string(),
             kind: ToolKind::Other,
+            path: None,
         });
-    apply_tool_result_event(call, &output, ledger);
+    let status = status.unwrap_or_else(|| status_from_output(&output));
+    apply_tool_result_event(call, &output, status, ledger);
 }
 
-fn parse_tool_message_payload(content: &str) -> Option<(Option<String>, String)> {
+fn parse_tool_message_payload(
+    content: &str,
+) -> Option<(Option<String>, String, Option<ToolStatus>)> {
     let val = serde_json::from_str::<serde_json::Value>(content).ok()?;
     let obj = val.as_object()?;
     let tool_call_id = obj
@@ -297,7 +374,19 @@ fn parse_tool_message_payload(content: &str) -> Option<(Option<String>, String)>
         .get("content")
         .map(json_value_to_text)
         .unwrap_or_else(|| content.trim().to_string());
-    Some((tool_call_id, output))
+    let status = obj
+        .get("status")
+        .and_then(serde_json::Value::as_str)
+        .and_then(ToolStatus::parse);
+    Some((tool_call_id, output, status))
+}
+
+/// Value of `name="..."` in the attribute text of a tag.
+fn tag_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let marker = format!("{name}=\"");
+    let start = attributes.find(&marker)? + marker.len();
+    let len = attributes[start..].find('"')?;
+    Some(&attributes[start..start + len])
 }
 
 fn json_value_to_text(value: &serde_json::Value) -> String {
@@ -307,9 +396,14 @@ fn json_value_to_text(value: &serde_json::Value) -> String {
     }
 }
 
-fn apply_tool_result_event(call: ObservedToolCall, output: &str, ledger: &mut EvidenceLedger) {
-    let is_success = !tool_result_output_likely_failure(output);
+fn apply_tool_result_event(
+    call: ObservedToolCall,
+    output: &str,
+    status: ToolStatus,
+    ledger: &mut EvidenceLedger,
+) {
     let normalized_name = call.name.trim().to_ascii_lowercase();
+    let is_success = status.is_ok();
 
     if is_success {
         ledger.successful_tools.insert(normalized_name);
@@ -322,6 +416,11 @@ fn apply_tool_result_event(call: ObservedToolCall, output: &str, ledger: &mut Ev
 
     if call.kind == ToolKind::WriteLike && is_success {
         ledger.saw_successful_write = true;
+        if let Some(path) = call.path {
+            if !ledger.written_paths.contains(&path) {
+                ledger.written_paths.push(path);
+            }
+        }
     }
     if call.kind == ToolKind::ReadLike && is_success {
         ledger.saw_successful_read = true;
@@ -329,19 +428,25 @@ fn apply_tool_result_event(call: ObservedToolCall, output: &str, ledger: &mut Ev
     if call.kind == ToolKind::ReadLike && is_success && ledger.saw_successful_write {
         ledger.saw_post_write_read_verification = true;
     }
-    if call.kind == ToolKind::SearchLike && is_success {
+    if call.kind == ToolKind::Network && is_success {
         ledger.saw_successful_search = true;
     }
 }
 
-fn tool_result_output_likely_failure(output: &str) -> bool {
+/// Keyword guess for results recorded without a status.
+fn status_from_output(output: &str) -> ToolStatus {
     let lower = output.to_ascii_lowercase();
-    lower.contains("failed")
+    let likely_failure = lower.contains("failed")
         || lower.contains("error")
         || lower.contains("not allowed")
         || lower.contains("denied")
         || lower.contains("missing")
-        || lower.contains("refusing")
+        || lower.contains("refusing");
+    if likely_failure {
+        ToolStatus::Error
+    } else {
+        ToolStatus::Ok
+    }
 }
 
 fn tool_result_output_likely_access_denied(output: &str) -> bool {
@@ -382,7 +487,7 @@ fn classify_shell_command(command: &str) -> ToolKind {
         || lower.contains("http://")
         || lower.contains("https://")
     {
-        return ToolKind::SearchLike;
+        return ToolKind::Network;
     }
 
     if lower.contains("cat ")
@@ -407,8 +512,105 @@ fn classify_shell_command(command: &str) -> ToolKind {
 
 #[cfg(test)]
 mod tests {
-    use super::collect_evidence_from_history;
+    use super::{collect_evidence_from_history, collect_evidence_from_invocations};
+    use crate::agent::task_types::ToolInvocation;
     use crate::providers::ChatMessage;
+    use crate::tools::ToolKind;
+
+    fn invocation(
+        name: &str,
+        arguments: serde_json::Value,
+        success: bool,
+        output: &str,
+    ) -> ToolInvocation {
+        ToolInvocation {
+            name: name.to_string(),
+            kind: ToolKind::Other,
+            arguments,
+            success,
+            status: None,
+            output: output.to_string(),
+            iteration: 1,
+            metadata: serde_json::Map::new(),
+        }
+    }
+
+    #[test]
+    fn invocation_evidence_uses_reported_success_not_output_keywords() {
+        let ledger = collect_evidence_from_invocations(&[
+            invocation(
+                "file_write",
+                serde_json::json!({"path": "errors.md", "content": "error log"}),
+                true,
+                "Written 9 bytes to errors.md",
+            ),
+            invocation(
+                "file_read",
+                serde_json::json!({"path": "errors.md"}),
+                true,
+                "error log",
+            ),
+            invocation(
+                "shell",
+                serde_json::json!({"command": "cat /etc/shadow"}),
+                false,
+                "Error: permission denied",
+            ),
+        ]);
+        assert!(ledger.has_successful_write());
+        assert!(ledger.has_post_write_read_verification());
+        assert_eq!(ledger.written_paths(), ["errors.md".to_string()]);
+        assert!(ledger.has_failed_tool("shell"));
+        assert!(ledger.has_access_denied_failure());
+    }
+
+    #[test]
+    fn tool_reported_kind_drives_invocation_evidence() {
+        let ledger = collect_evidence_from_invocations(&[
+            ToolInvocation {
+                kind: ToolKind::WriteLike,
+                ..invocation(
+                    "mcp_fs_write",
+                    serde_json::json!({"file_path": "notes/todo.md", "text": "- ship"}),
+                    true,
+                    "ok",
+                )
+            },
+            ToolInvocation {
+                kind: ToolKind::Network,
+                ..invocation("mcp_fetch", serde_json::json!({}), true, "200")
+            },
+            invocation(
+                "file_edit",
+                serde_json::json!({"path": "README.md"}),
+                true,
+                "Edited README.md",
+            ),
+        ]);
+        assert!(ledger.has_successful_write());
+        assert!(ledger.has_successful_search());
+        assert!(!ledger.has_successful_read());
+        assert_eq!(
+            ledger.written_paths(),
+            ["notes/todo.md".to_string(), "README.md".to_string()]
+        );
+    }
+
+    #[test]
+    fn dry_run_edits_are_not_write_evidence() {
+        let ledger = collect_evidence_from_invocations(&[ToolInvocation {
+            kind: ToolKind::WriteLike,
+            ..invocation(
+                "file_edit",
+                serde_json::json!({"path": "README.md", "diff": "@@ -1 +1 @@", "dry_run": true}),
+                true,
+                "Dry run for README.md: applied 1 hunk in memory",
+            )
+        }]);
+        assert!(!ledger.has_successful_write());
+        assert!(ledger.written_paths().is_empty());
+        assert!(ledger.has_successful_tool("file_edit"));
+    }
 
     #[test]
     fn evidence_ledger_collects_web_search_tool_success() {
@@ -449,6 +651,33 @@ mod tests {
             ChatMessage::user("[Tool results]\n<tool_result name=\"file_read\">\nabc\n</tool_result>"),
         ];
 
+        let ledger = collect_evidence_from_history(&history);
+        assert!(ledger.has_successful_write());
+        assert!(ledger.has_post_write_read_verification());
+        assert_eq!(ledger.written_paths(), ["report.md".to_string()]);
+    }
+
+    #[test]
+    fn evidence_ledger_counts_search_tools_as_read_verification() {
+        let history = vec![
+            ChatMessage::assistant(
+                r#"<tool_call>
+{"name":"file_edit","arguments":{"path":"src/lib.rs","old_string":"a","new_string":"b"}}
+</tool_call>"#,
+            ),
+            ChatMessage::user(
+                "[Tool results]\n<tool_result name=\"file_edit\">\nEdited src/lib.rs\n</tool_result>",
+            ),
+            ChatMessage::assistant(
+                r#"<tool_call>
+{"name":"code_search","arguments":{"query":"b","path":"src"}}
+</tool_call>"#,
+            ),
+            ChatMessage::user(
+                "[Tool results]\n<tool_result name=\"code_search\">\nMatches 1-1 of 1 in 1 files (page 1 of 1)\n</tool_result>",
+            ),
+        ];
+
         let ledger = collect_evidence_from_history(&history);
         assert!(ledger.has_successful_write());
         assert!(ledger.has_post_write_read_verification());
@@ -527,4 +756,30 @@ mod tests {
             "native role=tool results should preserve successful tool name"
         );
     }
+
+    #[test]
+    fn recorded_status_overrides_output_keywords() {
+        let history = vec![
+            ChatMessage::assistant(
+                r#"{"content":"","tool_calls":[{"id":"call_1","name":"file_read","arguments":{"path":"errors.log"}}]}"#,
+            ),
+            ChatMessage::tool(
+                r#"{"tool_call_id":"call_1","content":"error: disk full (failed at 02:00)","status":"ok"}"#,
+            ),
+            ChatMessage::assistant(
+                r#"<tool_call>
+{"name":"web_search_tool","arguments":{"query":"rust"}}
+</tool_call>"#,
+            ),
+            ChatMessage::user(
+                "[Tool results]\n<tool_result name=\"web_search_tool\" status=\"denied\">\nBlocked by tool policy: tool is not allowed here.\n</tool_result>",
+            ),
+        ];
+
+        let ledger = collect_evidence_from_history(&history);
+        assert!(ledger.has_successful_read());
+        assert!(ledger.has_successful_tool("file_read"));
+        assert!(ledger.has_failed_tool("web_search_tool"));
+        assert!(!ledger.has_successful_search());
+    }
 }
@@ -1,4 +1,4 @@
-use crate::providers::Provider;
+use crate::providers::{ChatMessage, Provider};
 use async_trait::async_trait;
 use serde::Deserialize;
 use std::time::Duration;
@@ -56,19 +56,28 @@ impl GrayZoneVerifier for ProviderGrayZoneVerifier {
             serde_json::to_string(request.missing_requirements).unwrap_or_else(|_| "[]".to_string()),
         );
 
-        let raw = tokio::time::timeout(
+        let messages = [
+            ChatMessage::system(system_prompt),
+            ChatMessage::user(user_prompt),
+        ];
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "done": { "type": "boolean" },
+                "reason": { "type": "string" }
+            },
+            "required": ["done", "reason"]
+        });
+        let value = tokio::time::timeout(
             self.timeout,
-            request.provider.chat_with_system(
-                Some(system_prompt),
-                &user_prompt,
-                request.model,
-                0.0,
-            ),
+            request
+                .provider
+                .chat_structured(&messages, &schema, request.model, 0.0),
         )
         .await
         .map_err(|_| anyhow::anyhow!("gray-zone verifier timed out"))??;
 
-        parse_gray_zone_verdict(&raw)
+        parse_gray_zone_verdict(value)
     }
 }
 
@@ -78,10 +87,8 @@ struct GrayZoneVerdictPayload {
     reason: String,
 }
 
-fn parse_gray_zone_verdict(raw: &str) -> anyhow::Result<GrayZoneVerdict> {
-    let trimmed = raw.trim();
-    let payload_json = extract_json_object(trimmed).unwrap_or(trimmed);
-    let parsed: GrayZoneVerdictPayload = serde_json::from_str(payload_json)
+fn parse_gray_zone_verdict(value: serde_json::Value) -> anyhow::Result<GrayZoneVerdict> {
+    let parsed: GrayZoneVerdictPayload = serde_json::from_value(value)
         .map_err(|e| anyhow::anyhow!("invalid gray-zone verifier payload: {e}"))?;
 
     Ok(GrayZoneVerdict {
@@ -90,15 +97,6 @@ fn parse_gray_zone_verdict(raw: &str) -> anyhow::Result<GrayZoneVerdict> {
     })
 }
 
-fn extract_json_object(text: &str) -> Option<&str> {
-    let start = text.find('{')?;
-    let end = text.rfind('}')?;
-    if end <= start {
-        return None;
-    }
-    Some(&text[start..=end])
-}
-
 #[cfg(test)]
 mod tests {
     use super::{GrayZoneVerificationRequest, GrayZoneVerifier, ProviderGrayZoneVerifier};
@@ -0,0 +1,208 @@
+//! Phrase tables for the heuristic completion evaluator.
+//!
+//! A reply that only announces work ("let me check", "確認しています") keeps a
+//! task running unless it also carries a completion phrase ("done",
+//! "erledigt"). Built-in packs cover English, Chinese, Japanese, Korean,
+//! Spanish, and German; `completion_policy.language_packs` adds more from
+//! config, and a configured pack with a built-in name replaces that pack.
+
+use crate::config::CompletionLanguagePack;
+
+/// A built-in phrase table.
+#[derive(Debug, Clone, Copy)]
+pub struct BuiltinLanguagePack {
+    pub name: &'static str,
+    pub progress_phrases: &'static [&'static str],
+    pub completion_phrases: &'static [&'static str],
+}
+
+pub const BUILTIN_LANGUAGE_PACKS: &[BuiltinLanguagePack] = &[
+    BuiltinLanguagePack {
+        name: "en",
+        progress_phrases: &[
+            "i'm checking",
+            "i am checking",
+            "let me check",
+            "let me search",
+            "i will search",
+            "i'll search",
+            "working on",
+        ],
+        completion_phrases: &["done", "completed", "finished"],
+    },
+    BuiltinLanguagePack {
+        name: "zh",
+        progress_phrases: &[
+            "我正在",
+            "我先",
+            "让我先",
+            "我会",
+            "我这就",
+            "马上给你",
+            "继续处理中",
+        ],
+        completion_phrases: &[
+            "任务完成",
+            "已完成",
+            "已经完成",
+            "完成了",
+            "已写入",
+            "已经写入",
+            "已保存",
+            "已经保存",
+            "成功创建",
+            "已生成",
+            "已经生成",
+        ],
+    },
+    BuiltinLanguagePack {
+        name: "ja",
+        progress_phrases: &[
+            "確認しています",
+            "確認します",
+            "調べています",
+            "調べます",
+            "検索します",
+            "作業中",
+            "少々お待ち",
+        ],
+        completion_phrases: &[
+            "完了しました",
+            "完了です",
+            "保存しました",
+            "作成しました",
+            "終わりました",
+        ],
+    },
+    BuiltinLanguagePack {
+        name: "ko",
+        progress_phrases: &[
+            "확인 중",
+            "확인하겠습니다",
+            "검색하겠습니다",
+            "찾아보겠습니다",
+            "작업 중",
+            "잠시만",
+        ],
+        completion_phrases: &[
+            "완료했습니다",
+            "완료되었습니다",
+            "저장했습니다",
+            "생성했습니다",
+            "끝났습니다",
+        ],
+    },
+    BuiltinLanguagePack {
+        name: "es",
+        progress_phrases: &[
+            "estoy revisando",
+            "estoy buscando",
+            "déjame revisar",
+            "déjame buscar",
+            "voy a buscar",
+            "trabajando en",
+        ],
+        completion_phrases: &[
+            "listo",
+            "he terminado",
+            "terminado",
+            "completado",
+            "he guardado",
+        ],
+    },
+    BuiltinLanguagePack {
+        name: "de",
+        progress_phrases: &[
+            "ich prüfe",
+            "ich überprüfe",
+            "ich suche",
+            "lass mich",
+            "ich arbeite daran",
+        ],
+        completion_phrases: &["erledigt", "fertig", "abgeschlossen", "gespeichert"],
+    },
+];
+
+/// Which phrase kinds a reply contains across all active packs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhraseMatch {
+    pub progress: bool,
+    pub completion: bool,
+}
+
+/// Match `text` against the built-in packs not overridden by `custom`, then
+/// against `custom` itself.
+pub fn match_phrases(text: &str, custom: &[CompletionLanguagePack]) -> PhraseMatch {
+    let lower = text.to_lowercase();
+    let mut found = PhraseMatch::default();
+    for pack in BUILTIN_LANGUAGE_PACKS {
+        if custom
+            .iter()
+            .any(|configured| configured.name.trim().eq_ignore_ascii_case(pack.name))
+        {
+            continue;
+        }
+        found.progress |= contains_any(&lower, pack.progress_phrases);
+        found.completion |= contains_any(&lower, pack.completion_phrases);
+    }
+    for pack in custom {
+        found.progress |= contains_any(&lower, &pack.progress_phrases);
+        found.completion |= contains_any(&lower, &pack.completion_phrases);
+    }
+    found
+}
+
+fn contains_any<S: AsRef<str>>(lower: &str, phrases: &[S]) -> bool {
+    phrases.iter().any(|phrase| {
+        let phrase = phrase.as_ref().trim().to_lowercase();
+        !phrase.is_empty() && lower.contains(&phrase)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_packs_classify_non_english_replies() {
+        let progress_only = [
+            "ファイルを確認しています。",
+            "파일을 확인하겠습니다.",
+            "Estoy revisando los registros.",
+            "Ich prüfe gerade die Logs.",
+        ];
+        for reply in progress_only {
+            let found = match_phrases(reply, &[]);
+            assert!(found.progress && !found.completion, "{reply}");
+        }
+
+        let complete = [
+            "レポートを保存しました。",
+            "보고서를 저장했습니다.",
+            "Listo, el informe está en report.md.",
+            "Erledigt, der Bericht liegt in report.md.",
+        ];
+        for reply in complete {
+            assert!(match_phrases(reply, &[]).completion, "{reply}");
+        }
+    }
+
+    #[test]
+    fn configured_pack_extends_or_replaces_builtins() {
+        let french = CompletionLanguagePack {
+            name: "fr".into(),
+            progress_phrases: vec!["Je vérifie".into()],
+            completion_phrases: vec!["c'est fait".into()],
+        };
+        assert!(match_phrases("je vérifie les fichiers", std::slice::from_ref(&french)).progress);
+        assert!(!match_phrases("je vérifie les fichiers", &[]).progress);
+
+        let german_override = CompletionLanguagePack {
+            name: "DE".into(),
+            progress_phrases: vec!["moment bitte".into()],
+            completion_phrases: Vec::new(),
+        };
+        assert!(!match_phrases("Ich prüfe das", std::slice::from_ref(&german_override)).progress);
+        assert!(match_phrases("Moment bitte", &[german_override, french]).progress);
+    }
+}
@@ -1,16 +1,20 @@
+use crate::agent::task_types::ToolInvocation;
+use crate::agent::tool_limits;
 use crate::approval::{ApprovalManager, ApprovalRequest, ApprovalResponse};
 use crate::config::Config;
 use crate::memory::{self, Memory, MemoryCategory};
 use crate::multimodal;
 use crate::observability::{self, runtime_trace, Observer, ObserverEvent};
 use crate::providers::{
-    self, ChatMessage, ChatRequest, Provider, ProviderCapabilityError, ToolCall,
+    self, ChatDelta, ChatMessage, ChatRequest, Provider, ProviderCapabilityError, ToolCall,
 };
 use crate::runtime;
+use crate::security::tool_policy::{self, ConfirmationRequired, ToolVerdict};
 use crate::security::SecurityPolicy;
 use crate::tools::{self, Tool};
 use crate::util::truncate_with_ellipsis;
 use anyhow::Result;
+use futures_util::StreamExt;
 use regex::{Regex, RegexSet};
 use std::collections::HashSet;
 use std::fmt::Write;
@@ -48,12 +52,23 @@ static SENSITIVE_KV_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r#"(?i)(token|api[_-]?key|password|secret|user[_-]?key|bearer|credential)["']?\s*[:=]\s*(?:"([^"]{8,})"|'([^']{8,})'|([a-zA-Z0-9_\-\.]{8,}))"#).unwrap()
 });
 
+/// `Authorization` / `Proxy-Authorization` header values, plain or inside
+/// (possibly escaped) JSON, with an optional auth scheme kept visible.
+static AUTHORIZATION_HEADER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)((?:proxy-)?authorization\\?["']?\s*[:=]\s*\\?["']?)(?:(bearer|basic|digest|token)\s+)?([^\s"'\\,}]+)"#).unwrap()
+});
+
 /// Scrub credentials from tool output to prevent accidental exfiltration.
 /// Replaces known credential patterns with a redacted placeholder while preserving
 /// a small prefix for context.
 pub(crate) fn scrub_credentials(input: &str) -> String {
+    let input =
+        AUTHORIZATION_HEADER_REGEX.replace_all(input, |caps: &regex::Captures| match caps.get(2) {
+            Some(scheme) => format!("{}{} [REDACTED]", &caps[1], scheme.as_str()),
+            None => format!("{}[REDACTED]", &caps[1]),
+        });
     SENSITIVE_KV_REGEX
-        .replace_all(input, |caps: &regex::Captures| {
+        .replace_all(&input, |caps: &regex::Captures| {
             let full_match = &caps[0];
             let key = &caps[1];
             let val = caps
@@ -253,7 +268,10 @@ fn shell_command_likely_reads_files(command: &str) -> bool {
     read_hints.iter().any(|hint| lower.contains(hint))
 }
 
-fn tool_call_indicates_filesystem_write(call: &ParsedToolCall) -> bool {
+fn tool_call_indicates_filesystem_write(call: &ParsedToolCall, kind: tools::ToolKind) -> bool {
+    if kind == tools::ToolKind::WriteLike {
+        return true;
+    }
     match call.name.as_str() {
         "file_write" => true,
         "shell" => call
@@ -265,7 +283,10 @@ fn tool_call_indicates_filesystem_write(call: &ParsedToolCall) -> bool {
     }
 }
 
-fn tool_call_indicates_filesystem_read(call: &ParsedToolCall) -> bool {
+fn tool_call_indicates_filesystem_read(call: &ParsedToolCall, kind: tools::ToolKind) -> bool {
+    if kind == tools::ToolKind::ReadLike {
+        return true;
+    }
     match call.name.as_str() {
         "file_read" => true,
         "shell" => call
@@ -2255,6 +2276,131 @@ struct ParsedToolCall {
     tool_call_id: Option<String>,
 }
 
+/// Why the loop deviated from what the model asked for: a hook veto, an
+/// approval denial, a call to an excluded tool, a tool policy verdict, or a
+/// guardrail forcing a retry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PolicyDecision {
+    /// `hook`, `approval`, `exclusion`, `tool_policy`, or `guardrail`.
+    pub source: &'static str,
+    /// `vetoed`, `denied`, `blocked`, `confirmation_required`, or `retry`.
+    pub decision: &'static str,
+    pub tool: Option<String>,
+    /// Short SHA-256 of the canonicalized tool arguments, so identical calls can
+    /// be correlated without storing raw (possibly sensitive) arguments.
+    pub arguments_hash: Option<String>,
+    pub reason: String,
+    pub iteration: usize,
+}
+
+/// A tool call stopped at its `tool_timeout_secs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ToolTimeout {
+    pub tool: String,
+    pub arguments_hash: String,
+    pub timeout_secs: u64,
+    pub iteration: usize,
+}
+
+/// Called with each tool invocation as soon as it has been executed.
+pub(crate) type ToolInvocationNotifier = Arc<dyn Fn(&ToolInvocation) + Send + Sync>;
+
+/// Collector for what happened during one `run_tool_call_loop` call: policy
+/// decisions, every tool call that was actually executed, calls stopped at
+/// their time limit, and the token usage providers reported.
+#[derive(Default)]
+pub(crate) struct ToolLoopLog {
+    pub policy_decisions: std::sync::Mutex<Vec<PolicyDecision>>,
+    pub invocations: std::sync::Mutex<Vec<ToolInvocation>>,
+    pub tool_timeouts: std::sync::Mutex<Vec<ToolTimeout>>,
+    pub input_tokens: std::sync::atomic::AtomicU64,
+    pub output_tokens: std::sync::atomic::AtomicU64,
+    /// Finish reason of each provider response that reported one, in order.
+    pub finish_reasons: std::sync::Mutex<Vec<String>>,
+    /// Live feed of executed tool calls, e.g. for progress messages.
+    pub on_invocation: Option<ToolInvocationNotifier>,
+}
+
+/// Tool output kept per recorded invocation.
+const MAX_INVOCATION_OUTPUT_CHARS: usize = 2_000;
+
+pub(crate) fn tool_arguments_hash(name: &str, arguments: &serde_json::Value) -> String {
+    use sha2::{Digest, Sha256};
+    let (name, args_json) = tool_call_signature(name, arguments);
+    let digest = Sha256::digest(format!("{name}\n{args_json}").as_bytes());
+    hex::encode(&digest[..8])
+}
+
+fn guardrail_decision(guard: &str, iteration: usize) -> PolicyDecision {
+    PolicyDecision {
+        source: "guardrail",
+        decision: "retry",
+        tool: None,
+        arguments_hash: None,
+        reason: guard.to_string(),
+        iteration: iteration + 1,
+    }
+}
+
+fn record_policy_decision(log: Option<&ToolLoopLog>, decision: PolicyDecision) {
+    if let Some(log) = log {
+        log.policy_decisions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(decision);
+    }
+}
+
+fn record_tool_invocation(
+    log: Option<&ToolLoopLog>,
+    call: &ParsedToolCall,
+    kind: tools::ToolKind,
+    outcome: &ToolExecutionOutcome,
+    iteration: usize,
+) {
+    if let Some(log) = log {
+        let invocation = ToolInvocation {
+            name: call.name.clone(),
+            kind,
+            arguments: call.arguments.clone(),
+            success: outcome.success(),
+            status: Some(outcome.status),
+            output: truncate_with_ellipsis(
+                &scrub_credentials(&outcome.output),
+                MAX_INVOCATION_OUTPUT_CHARS,
+            ),
+            iteration: iteration + 1,
+            metadata: outcome.metadata.clone(),
+        };
+        if let Some(notify) = log.on_invocation.as_ref() {
+            notify(&invocation);
+        }
+        log.invocations
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(invocation);
+    }
+}
+
+fn record_tool_timeout(log: Option<&ToolLoopLog>, call: &ParsedToolCall, iteration: usize) {
+    let timeout_secs = tool_limits::for_tool(&call.name)
+        .timeout
+        .unwrap_or_default()
+        .as_secs();
+    tracing::warn!(tool = %call.name, timeout_secs, "Tool call timed out");
+    if let Some(log) = log {
+        log.tool_timeouts
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(ToolTimeout {
+                tool: call.name.clone(),
+                arguments_hash: tool_arguments_hash(&call.name, &call.arguments),
+                timeout_secs,
+                iteration: iteration + 1,
+            });
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ToolLoopCancelled;
 
@@ -2272,7 +2418,8 @@ pub(crate) fn is_tool_loop_cancelled(err: &anyhow::Error) -> bool {
 
 /// Execute a single turn of the agent loop: send messages, parse tool calls,
 /// execute tools, and loop until the LLM produces a final text response.
-/// When `silent` is true, suppresses stdout (for channel use).
+/// When `silent` is true, suppresses stdout (for channel use). Tools in
+/// `excluded_tools` are never dispatched.
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn agent_turn(
     provider: &dyn Provider,
@@ -2285,6 +2432,7 @@ pub(crate) async fn agent_turn(
     silent: bool,
     multimodal_config: &crate::config::MultimodalConfig,
     max_tool_iterations: usize,
+    excluded_tools: &[String],
 ) -> Result<String> {
     run_tool_call_loop(
         provider,
@@ -2302,7 +2450,9 @@ pub(crate) async fn agent_turn(
         None,
         None,
         None,
-        &[],
+        excluded_tools,
+        None,
+        1,
     )
     .await
 }
@@ -2329,22 +2479,76 @@ async fn execute_one_tool(
         });
         return Ok(ToolExecutionOutcome {
             output: reason.clone(),
-            success: false,
+            status: tools::ToolStatus::Error,
             error_reason: Some(scrub_credentials(&reason)),
             duration,
+            timed_out: false,
+            metadata: serde_json::Map::new(),
         });
     };
 
-    let tool_future = tool.execute(call_arguments);
+    let violations = tool.argument_violations(&call_arguments);
+    if !violations.is_empty() {
+        let duration = start.elapsed();
+        observer.record_event(&ObserverEvent::ToolCall {
+            tool: call_name.to_string(),
+            duration,
+            success: false,
+        });
+        let reason = invalid_arguments_message(call_name, &violations, &tool.parameters_schema());
+        return Ok(ToolExecutionOutcome {
+            output: format!("Error: {reason}"),
+            status: tools::ToolStatus::Error,
+            error_reason: Some(reason),
+            duration,
+            timed_out: false,
+            metadata: serde_json::Map::new(),
+        });
+    }
+
+    if tool.kind() == tools::ToolKind::WriteLike {
+        crate::agent::workspace_transaction::before_write(&call_arguments);
+    }
+    let limit = tool_limits::for_tool(call_name);
+    let tool_future = tools::traits::collect_result_metadata(tool.execute(call_arguments));
+    // Dropping the future on timeout stops the tool; `None` means it ran out.
+    let limited_future = async {
+        match limit.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, tool_future).await.ok(),
+            None => Some(tool_future.await),
+        }
+    };
     let tool_result = if let Some(token) = cancellation_token {
         tokio::select! {
             () = token.cancelled() => return Err(ToolLoopCancelled.into()),
-            result = tool_future => result,
+            result = limited_future => result,
         }
     } else {
-        tool_future.await
+        limited_future.await
+    };
+
+    let Some(tool_result) = tool_result else {
+        let duration = start.elapsed();
+        observer.record_event(&ObserverEvent::ToolCall {
+            tool: call_name.to_string(),
+            duration,
+            success: false,
+        });
+        let reason = format!(
+            "Tool '{call_name}' timed out after {}s and was stopped",
+            limit.timeout.unwrap_or_default().as_secs()
+        );
+        return Ok(ToolExecutionOutcome {
+            output: format!("Error: {reason}"),
+            status: tools::ToolStatus::Error,
+            error_reason: Some(reason),
+            duration,
+            timed_out: true,
+            metadata: serde_json::Map::new(),
+        });
     };
 
+    let (tool_result, metadata) = tool_result;
     match tool_result {
         Ok(r) => {
             let duration = start.elapsed();
@@ -2354,19 +2558,26 @@ async fn execute_one_tool(
                 success: r.success,
             });
             if r.success {
+                let mut output = scrub_credentials(&r.output);
+                tool_limits::truncate_output(&mut output, limit.max_output_bytes);
                 Ok(ToolExecutionOutcome {
-                    output: scrub_credentials(&r.output),
-                    success: true,
+                    output,
+                    status: tools::ToolStatus::Ok,
                     error_reason: None,
                     duration,
+                    timed_out: false,
+                    metadata,
                 })
             } else {
-                let reason = r.error.unwrap_or(r.output);
+                let mut reason = r.error.unwrap_or(r.output);
+                tool_limits::truncate_output(&mut reason, limit.max_output_bytes);
                 Ok(ToolExecutionOutcome {
                     output: format!("Error: {reason}"),
-                    success: false,
+                    status: tools::ToolStatus::Error,
                     error_reason: Some(scrub_credentials(&reason)),
                     duration,
+                    timed_out: false,
+                    metadata,
                 })
             }
         }
@@ -2380,19 +2591,61 @@ async fn execute_one_tool(
             let reason = format!("Error executing {call_name}: {e}");
             Ok(ToolExecutionOutcome {
                 output: reason.clone(),
-                success: false,
+                status: tools::ToolStatus::Error,
                 error_reason: Some(scrub_credentials(&reason)),
                 duration,
+                timed_out: false,
+                metadata: serde_json::Map::new(),
             })
         }
     }
 }
 
+/// Whether `name` is hidden from this run, directly or because it calls an
+/// excluded tool (see [`Tool::inner_tools`]).
+fn is_tool_excluded(
+    tools_registry: &[Box<dyn Tool>],
+    name: &str,
+    excluded_tools: &[String],
+) -> bool {
+    if excluded_tools.iter().any(|ex| ex == name) {
+        return true;
+    }
+    find_tool(tools_registry, name).is_some_and(|tool| {
+        tool.inner_tools()
+            .iter()
+            .any(|inner| excluded_tools.contains(inner))
+    })
+}
+
+/// Corrective message for a call whose arguments do not match the tool's
+/// schema; the tool did not run.
+pub(crate) fn invalid_arguments_message(
+    call_name: &str,
+    violations: &[String],
+    schema: &serde_json::Value,
+) -> String {
+    format!(
+        "Invalid arguments for tool '{call_name}' (the tool did not run): {}. Call it again with arguments matching its parameter schema: {schema}",
+        violations.join("; ")
+    )
+}
+
 struct ToolExecutionOutcome {
     output: String,
-    success: bool,
+    status: tools::ToolStatus,
     error_reason: Option<String>,
     duration: Duration,
+    /// The call ran past its `tool_timeout_secs` and was stopped.
+    timed_out: bool,
+    /// Structured metadata the tool recorded, such as the sources it read.
+    metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ToolExecutionOutcome {
+    fn success(&self) -> bool {
+        self.status.is_ok()
+    }
 }
 
 fn should_execute_tools_in_parallel(
@@ -2414,15 +2667,73 @@ fn should_execute_tools_in_parallel(
     true
 }
 
+/// Run a macro's step calls through the before-tool-call hooks and the
+/// approval gate, as direct calls are. `Err` carries the decision source and
+/// why a step was stopped.
+async fn gate_step_calls(
+    steps: Vec<(String, serde_json::Value)>,
+    hooks: Option<&crate::hooks::HookRunner>,
+    approval: Option<&ApprovalManager>,
+    channel_name: &str,
+) -> std::result::Result<Vec<(String, serde_json::Value)>, (&'static str, String)> {
+    let total = steps.len();
+    let mut gated = Vec::with_capacity(total);
+    for (index, (mut name, mut args)) in steps.into_iter().enumerate() {
+        let step = index + 1;
+        if let Some(hooks) = hooks {
+            match hooks.run_before_tool_call(name.clone(), args.clone()).await {
+                crate::hooks::HookResult::Cancel(reason) => {
+                    return Err((
+                        "hook",
+                        format!("step {step}/{total} ({name}) cancelled by hook: {reason}"),
+                    ));
+                }
+                crate::hooks::HookResult::Continue((new_name, new_args)) => {
+                    name = new_name;
+                    args = new_args;
+                }
+            }
+        }
+        if let Some(mgr) = approval {
+            if mgr.needs_approval(&name) {
+                let request = ApprovalRequest {
+                    tool_name: name.clone(),
+                    arguments: args.clone(),
+                };
+                let decision = if channel_name == "cli" {
+                    mgr.prompt_cli(&request)
+                } else {
+                    ApprovalResponse::Yes
+                };
+                mgr.record_decision(&name, &args, decision, channel_name);
+                if decision == ApprovalResponse::No {
+                    return Err((
+                        "approval",
+                        format!("step {step}/{total} ({name}) denied by user"),
+                    ));
+                }
+            }
+        }
+        gated.push((name, args));
+    }
+    Ok(gated)
+}
+
+/// Run `tool_calls` concurrently, at most `max_parallel` at a time; outcomes
+/// come back in call order.
 async fn execute_tools_parallel(
     tool_calls: &[ParsedToolCall],
     tools_registry: &[Box<dyn Tool>],
     observer: &dyn Observer,
     cancellation_token: Option<&CancellationToken>,
+    max_parallel: usize,
 ) -> Result<Vec<ToolExecutionOutcome>> {
+    let semaphore = tokio::sync::Semaphore::new(max_parallel.max(1));
+    let semaphore = &semaphore;
     let futures: Vec<_> = tool_calls
         .iter()
-        .map(|call| {
+        .map(|call| async move {
+            let _permit = semaphore.acquire().await?;
             execute_one_tool(
                 &call.name,
                 call.arguments.clone(),
@@ -2430,6 +2741,7 @@ async fn execute_tools_parallel(
                 observer,
                 cancellation_token,
             )
+            .await
         })
         .collect();
 
@@ -2462,6 +2774,41 @@ async fn execute_tools_sequential(
 }
 
 // ── Agent Tool-Call Loop ──────────────────────────────────────────────────
+/// Drain a [`Provider::chat_stream`], relaying its text to `on_delta` in
+/// chunks of at least [`STREAM_CHUNK_MIN_CHARS`], and return the final
+/// response. The first chunk clears the draft's progress lines; `streamed`
+/// records that text reached the draft.
+async fn relay_chat_stream(
+    mut deltas: futures_util::stream::BoxStream<'_, Result<ChatDelta>>,
+    on_delta: &tokio::sync::mpsc::Sender<String>,
+    streamed: &mut bool,
+) -> Result<providers::ChatResponse> {
+    let mut chunk = String::new();
+    while let Some(delta) = deltas.next().await {
+        let response = match delta? {
+            ChatDelta::Text(text) => {
+                chunk.push_str(&text);
+                if chunk.len() < STREAM_CHUNK_MIN_CHARS {
+                    continue;
+                }
+                None
+            }
+            ChatDelta::Done(response) => Some(response),
+        };
+        if !chunk.is_empty() {
+            if !*streamed {
+                let _ = on_delta.send(DRAFT_CLEAR_SENTINEL.to_string()).await;
+                *streamed = true;
+            }
+            let _ = on_delta.send(std::mem::take(&mut chunk)).await;
+        }
+        if let Some(response) = response {
+            return Ok(response);
+        }
+    }
+    anyhow::bail!("Provider stream ended without a final response")
+}
+
 // Core agentic iteration: send conversation to the LLM, parse any tool
 // calls from the response, execute them, append results to history, and
 // repeat until the LLM produces a final text-only answer.
@@ -2475,6 +2822,8 @@ async fn execute_tools_sequential(
 
 /// Execute a single turn of the agent loop: send messages, parse tool calls,
 /// execute tools, and loop until the LLM produces a final text response.
+/// With `max_parallel_tools` above 1, the tool calls of one response run
+/// concurrently, at most that many at a time; `0` or `1` runs them in order.
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_tool_call_loop(
     provider: &dyn Provider,
@@ -2493,6 +2842,8 @@ pub(crate) async fn run_tool_call_loop(
     on_delta: Option<tokio::sync::mpsc::Sender<String>>,
     hooks: Option<&crate::hooks::HookRunner>,
     excluded_tools: &[String],
+    run_log: Option<&ToolLoopLog>,
+    max_parallel_tools: usize,
 ) -> Result<String> {
     let max_iterations = if max_tool_iterations == 0 {
         DEFAULT_MAX_TOOL_ITERATIONS
@@ -2502,7 +2853,7 @@ pub(crate) async fn run_tool_call_loop(
 
     let tool_specs: Vec<crate::tools::ToolSpec> = tools_registry
         .iter()
-        .filter(|tool| !excluded_tools.iter().any(|ex| ex == tool.name()))
+        .filter(|tool| !is_tool_excluded(tools_registry, tool.name(), excluded_tools))
         .map(|tool| tool.spec())
         .collect();
     let mut use_native_tools = provider.supports_native_tools() && !tool_specs.is_empty();
@@ -2587,14 +2938,21 @@ pub(crate) async fn run_tool_call_loop(
             None
         };
 
-        let chat_future = provider.chat(
-            ChatRequest {
-                messages: &prepared_messages.messages,
-                tools: request_tools,
-            },
-            model,
-            temperature,
-        );
+        let request = ChatRequest {
+            messages: &prepared_messages.messages,
+            tools: request_tools,
+        };
+        // With a draft to update, stream the answer into it as it arrives.
+        let mut streamed_text = false;
+        let chat_future = async {
+            match on_delta.as_ref() {
+                Some(tx) => {
+                    let deltas = provider.chat_stream(request, model, temperature);
+                    relay_chat_stream(deltas, tx, &mut streamed_text).await
+                }
+                None => provider.chat(request, model, temperature).await,
+            }
+        };
 
         let chat_result = if let Some(token) = cancellation_token.as_ref() {
             tokio::select! {
@@ -2613,6 +2971,21 @@ pub(crate) async fn run_tool_call_loop(
                         .as_ref()
                         .map(|u| (u.input_tokens, u.output_tokens))
                         .unwrap_or((None, None));
+                    if let Some(log) = run_log {
+                        log.input_tokens.fetch_add(
+                            resp_input_tokens.unwrap_or(0),
+                            std::sync::atomic::Ordering::Relaxed,
+                        );
+                        log.output_tokens.fetch_add(
+                            resp_output_tokens.unwrap_or(0),
+                            std::sync::atomic::Ordering::Relaxed,
+                        );
+                        if let Some(reason) = &resp.finish_reason {
+                            if let Ok(mut reasons) = log.finish_reasons.lock() {
+                                reasons.push(reason.clone());
+                            }
+                        }
+                    }
 
                     observer.record_event(&ObserverEvent::LlmResponse {
                         provider: provider_name.to_string(),
@@ -2748,6 +3121,10 @@ pub(crate) async fn run_tool_call_loop(
         if let Some(ref tx) = on_delta {
             let llm_secs = llm_started_at.elapsed().as_secs();
             if !tool_calls.is_empty() {
+                if streamed_text {
+                    // Drop the text streamed ahead of the tool calls.
+                    let _ = tx.send(DRAFT_CLEAR_SENTINEL.to_string()).await;
+                }
                 let _ = tx
                     .send(format!(
                         "\u{1f4ac} Got {} tool call(s) ({llm_secs}s)\n",
@@ -2770,6 +3147,10 @@ pub(crate) async fn run_tool_call_loop(
                 );
 
                 tool_capability_guard_hits = tool_capability_guard_hits.saturating_add(1);
+                record_policy_decision(
+                    run_log,
+                    guardrail_decision("tool_capability_guard", iteration),
+                );
                 use_native_tools = false;
 
                 if let Some(ref tx) = on_delta {
@@ -2806,6 +3187,7 @@ pub(crate) async fn run_tool_call_loop(
                 );
 
                 language_guard_hits = language_guard_hits.saturating_add(1);
+                record_policy_decision(run_log, guardrail_decision("language_guard", iteration));
                 history.push(ChatMessage::assistant(response_text.clone()));
                 history.push(ChatMessage::user(format!(
                     "[Language Guard]\n\
@@ -2827,6 +3209,10 @@ pub(crate) async fn run_tool_call_loop(
                 );
 
                 write_claim_guard_hits = write_claim_guard_hits.saturating_add(1);
+                record_policy_decision(
+                    run_log,
+                    guardrail_decision("verification_guard", iteration),
+                );
                 history.push(ChatMessage::assistant(response_text.clone()));
                 if prefer_chinese {
                     history.push(ChatMessage::user(format!(
@@ -2864,6 +3250,10 @@ pub(crate) async fn run_tool_call_loop(
                 );
 
                 brainstorming_guard_hits = brainstorming_guard_hits.saturating_add(1);
+                record_policy_decision(
+                    run_log,
+                    guardrail_decision("brainstorming_guard", iteration),
+                );
                 history.push(ChatMessage::assistant(response_text.clone()));
                 if prefer_chinese {
                     history.push(ChatMessage::user(format!(
@@ -2897,6 +3287,7 @@ pub(crate) async fn run_tool_call_loop(
                 );
 
                 filesystem_analysis_guard_hits = filesystem_analysis_guard_hits.saturating_add(1);
+                record_policy_decision(run_log, guardrail_decision("grounding_guard", iteration));
                 history.push(ChatMessage::assistant(response_text.clone()));
                 if prefer_chinese {
                     history.push(ChatMessage::user(format!(
@@ -2932,6 +3323,7 @@ pub(crate) async fn run_tool_call_loop(
                 );
 
                 autonomy_guard_hits = autonomy_guard_hits.saturating_add(1);
+                record_policy_decision(run_log, guardrail_decision("autonomy_guard", iteration));
                 history.push(ChatMessage::assistant(response_text.clone()));
                 if prefer_chinese {
                     history.push(ChatMessage::user(format!(
@@ -2971,8 +3363,10 @@ pub(crate) async fn run_tool_call_loop(
             );
             // No tool calls — this is the final response.
             // If a streaming sender is provided, relay the text in small chunks
-            // so the channel can progressively update the draft message.
-            if let Some(ref tx) = on_delta {
+            // so the channel can progressively update the draft message,
+            // unless the provider already streamed exactly this text.
+            let already_streamed = streamed_text && display_text == response_text;
+            if let Some(tx) = on_delta.as_ref().filter(|_| !already_streamed) {
                 // Clear accumulated progress lines before streaming the final answer.
                 let _ = tx.send(DRAFT_CLEAR_SENTINEL.to_string()).await;
                 // Split on whitespace boundaries, accumulating chunks of at least
@@ -3009,15 +3403,18 @@ pub(crate) async fn run_tool_call_loop(
         // Execute tool calls and build results. `individual_results` tracks per-call output so
         // native-mode history can emit one role=tool message per tool call with the correct ID.
         //
-        // When multiple tool calls are present and interactive CLI approval is not needed, run
-        // tool executions concurrently for lower wall-clock latency.
+        // When `max_parallel_tools` allows it, multiple tool calls are present, and interactive
+        // CLI approval is not needed, run tool executions concurrently (bounded by
+        // `max_parallel_tools`) for lower wall-clock latency.
         let mut tool_results = String::new();
-        let mut individual_results: Vec<(Option<String>, String)> = Vec::new();
+        let mut individual_results: Vec<(Option<String>, tools::ToolResultEnvelope)> = Vec::new();
         let mut ordered_results: Vec<Option<(String, Option<String>, ToolExecutionOutcome)>> =
             (0..tool_calls.len()).map(|_| None).collect();
-        let allow_parallel_execution = should_execute_tools_in_parallel(&tool_calls, approval);
+        let allow_parallel_execution =
+            max_parallel_tools > 1 && should_execute_tools_in_parallel(&tool_calls, approval);
         let mut executable_indices: Vec<usize> = Vec::new();
         let mut executable_calls: Vec<ParsedToolCall> = Vec::new();
+        let mut gated_steps = std::collections::HashMap::new();
 
         for (idx, call) in tool_calls.iter().enumerate() {
             // ── Hook: before_tool_call (modifying) ──────────
@@ -3030,6 +3427,17 @@ pub(crate) async fn run_tool_call_loop(
                 {
                     crate::hooks::HookResult::Cancel(reason) => {
                         tracing::info!(tool = %call.name, %reason, "tool call cancelled by hook");
+                        record_policy_decision(
+                            run_log,
+                            PolicyDecision {
+                                source: "hook",
+                                decision: "vetoed",
+                                tool: Some(call.name.clone()),
+                                arguments_hash: Some(tool_arguments_hash(&call.name, &tool_args)),
+                                reason: scrub_credentials(&reason),
+                                iteration: iteration + 1,
+                            },
+                        );
                         let cancelled = format!("Cancelled by hook: {reason}");
                         runtime_trace::record_event(
                             "tool_call_result",
@@ -3050,9 +3458,11 @@ pub(crate) async fn run_tool_call_loop(
                             call.tool_call_id.clone(),
                             ToolExecutionOutcome {
                                 output: cancelled,
-                                success: false,
+                                status: tools::ToolStatus::Denied,
                                 error_reason: Some(scrub_credentials(&reason)),
                                 duration: Duration::ZERO,
+                                timed_out: false,
+                                metadata: serde_json::Map::new(),
                             },
                         ));
                         continue;
@@ -3083,6 +3493,17 @@ pub(crate) async fn run_tool_call_loop(
 
                     if decision == ApprovalResponse::No {
                         let denied = "Denied by user.".to_string();
+                        record_policy_decision(
+                            run_log,
+                            PolicyDecision {
+                                source: "approval",
+                                decision: "denied",
+                                tool: Some(tool_name.clone()),
+                                arguments_hash: Some(tool_arguments_hash(&tool_name, &tool_args)),
+                                reason: denied.clone(),
+                                iteration: iteration + 1,
+                            },
+                        );
                         runtime_trace::record_event(
                             "tool_call_result",
                             Some(channel_name),
@@ -3102,9 +3523,64 @@ pub(crate) async fn run_tool_call_loop(
                             call.tool_call_id.clone(),
                             ToolExecutionOutcome {
                                 output: denied.clone(),
-                                success: false,
+                                status: tools::ToolStatus::Denied,
                                 error_reason: Some(denied),
                                 duration: Duration::ZERO,
+                                timed_out: false,
+                                metadata: serde_json::Map::new(),
+                            },
+                        ));
+                        continue;
+                    }
+                }
+            }
+
+            // ── Macro steps: the same hooks and approval as direct calls ──
+            let step_calls = find_tool(tools_registry, &tool_name)
+                .map(|tool| tool.step_calls(&tool_args))
+                .unwrap_or_default();
+            if !step_calls.is_empty() {
+                match gate_step_calls(step_calls, hooks, approval, channel_name).await {
+                    Ok(steps) => {
+                        gated_steps.insert(tool_arguments_hash(&tool_name, &tool_args), steps);
+                    }
+                    Err((source, reason)) => {
+                        let denied = format!("Macro stopped before it ran: {reason}.");
+                        record_policy_decision(
+                            run_log,
+                            PolicyDecision {
+                                source,
+                                decision: "denied",
+                                tool: Some(tool_name.clone()),
+                                arguments_hash: Some(tool_arguments_hash(&tool_name, &tool_args)),
+                                reason: scrub_credentials(&reason),
+                                iteration: iteration + 1,
+                            },
+                        );
+                        runtime_trace::record_event(
+                            "tool_call_result",
+                            Some(channel_name),
+                            Some(provider_name),
+                            Some(model),
+                            Some(&turn_id),
+                            Some(false),
+                            Some(&denied),
+                            serde_json::json!({
+                                "iteration": iteration + 1,
+                                "tool": tool_name.clone(),
+                                "arguments": scrub_credentials(&tool_args.to_string()),
+                            }),
+                        );
+                        ordered_results[idx] = Some((
+                            tool_name.clone(),
+                            call.tool_call_id.clone(),
+                            ToolExecutionOutcome {
+                                output: denied.clone(),
+                                status: tools::ToolStatus::Denied,
+                                error_reason: Some(scrub_credentials(&denied)),
+                                duration: Duration::ZERO,
+                                timed_out: false,
+                                metadata: serde_json::Map::new(),
                             },
                         ));
                         continue;
@@ -3112,6 +3588,117 @@ pub(crate) async fn run_tool_call_loop(
                 }
             }
 
+            // ── Exclusion: tools hidden from this run are never dispatched ──
+            if is_tool_excluded(tools_registry, &tool_name, excluded_tools) {
+                let blocked = format!("Tool '{tool_name}' is not available in this context.");
+                record_policy_decision(
+                    run_log,
+                    PolicyDecision {
+                        source: "exclusion",
+                        decision: "blocked",
+                        tool: Some(tool_name.clone()),
+                        arguments_hash: Some(tool_arguments_hash(&tool_name, &tool_args)),
+                        reason: "excluded_tool".to_string(),
+                        iteration: iteration + 1,
+                    },
+                );
+                runtime_trace::record_event(
+                    "tool_call_result",
+                    Some(channel_name),
+                    Some(provider_name),
+                    Some(model),
+                    Some(&turn_id),
+                    Some(false),
+                    Some(&blocked),
+                    serde_json::json!({
+                        "iteration": iteration + 1,
+                        "tool": tool_name.clone(),
+                        "excluded": true,
+                    }),
+                );
+                ordered_results[idx] = Some((
+                    tool_name.clone(),
+                    call.tool_call_id.clone(),
+                    ToolExecutionOutcome {
+                        output: blocked.clone(),
+                        status: tools::ToolStatus::Denied,
+                        error_reason: Some(blocked),
+                        duration: Duration::ZERO,
+                        timed_out: false,
+                        metadata: serde_json::Map::new(),
+                    },
+                ));
+                continue;
+            }
+
+            // ── Tool policy: per-channel and per-sender rules ──
+            let arguments_hash = tool_arguments_hash(&tool_name, &tool_args);
+            match tool_policy::check(&tool_name, &tool_args, &arguments_hash) {
+                ToolVerdict::Allow => {}
+                ToolVerdict::Deny(reason) => {
+                    let denied = format!("Blocked by tool policy: {reason}.");
+                    record_policy_decision(
+                        run_log,
+                        PolicyDecision {
+                            source: "tool_policy",
+                            decision: "denied",
+                            tool: Some(tool_name.clone()),
+                            arguments_hash: Some(arguments_hash),
+                            reason,
+                            iteration: iteration + 1,
+                        },
+                    );
+                    runtime_trace::record_event(
+                        "tool_call_result",
+                        Some(channel_name),
+                        Some(provider_name),
+                        Some(model),
+                        Some(&turn_id),
+                        Some(false),
+                        Some(&denied),
+                        serde_json::json!({
+                            "iteration": iteration + 1,
+                            "tool": tool_name.clone(),
+                            "arguments": scrub_credentials(&tool_args.to_string()),
+                        }),
+                    );
+                    ordered_results[idx] = Some((
+                        tool_name.clone(),
+                        call.tool_call_id.clone(),
+                        ToolExecutionOutcome {
+                            output: denied.clone(),
+                            status: tools::ToolStatus::Denied,
+                            error_reason: Some(denied),
+                            duration: Duration::ZERO,
+                            timed_out: false,
+                            metadata: serde_json::Map::new(),
+                        },
+                    ));
+                    continue;
+                }
+                ToolVerdict::Confirm(reason) => {
+                    // Nothing from this response runs until the user decides.
+                    record_policy_decision(
+                        run_log,
+                        PolicyDecision {
+                            source: "tool_policy",
+                            decision: "confirmation_required",
+                            tool: Some(tool_name.clone()),
+                            arguments_hash: Some(arguments_hash.clone()),
+                            reason: reason.clone(),
+                            iteration: iteration + 1,
+                        },
+                    );
+                    return Err(ConfirmationRequired {
+                        summary: scrub_credentials(&crate::approval::summarize_args(&tool_args)),
+                        tool: tool_name,
+                        arguments_hash,
+                        reason,
+                    }
+                    .into());
+                }
+            }
+
             let signature = tool_call_signature(&tool_name, &tool_args);
             if !seen_tool_signatures.insert(signature) {
                 let duplicate = format!(
@@ -3137,9 +3724,11 @@ pub(crate) async fn run_tool_call_loop(
                     call.tool_call_id.clone(),
                     ToolExecutionOutcome {
                         output: duplicate.clone(),
-                        success: false,
+                        status: tools::ToolStatus::Error,
                         error_reason: Some(duplicate),
                         duration: Duration::ZERO,
+                        timed_out: false,
+                        metadata: serde_json::Map::new(),
                     },
                 ));
                 continue;
@@ -3180,23 +3769,27 @@ pub(crate) async fn run_tool_call_loop(
             });
         }
 
-        let executed_outcomes = if allow_parallel_execution && executable_calls.len() > 1 {
-            execute_tools_parallel(
-                &executable_calls,
-                tools_registry,
-                observer,
-                cancellation_token.as_ref(),
-            )
-            .await?
-        } else {
-            execute_tools_sequential(
-                &executable_calls,
-                tools_registry,
-                observer,
-                cancellation_token.as_ref(),
-            )
-            .await?
-        };
+        let executed_outcomes = tools::macro_tool::with_gated_steps(gated_steps, async {
+            if allow_parallel_execution && executable_calls.len() > 1 {
+                execute_tools_parallel(
+                    &executable_calls,
+                    tools_registry,
+                    observer,
+                    cancellation_token.as_ref(),
+                    max_parallel_tools,
+                )
+                .await
+            } else {
+                execute_tools_sequential(
+                    &executable_calls,
+                    tools_registry,
+                    observer,
+                    cancellation_token.as_ref(),
+                )
+                .await
+            }
+        })
+        .await?;
 
         for ((idx, call), outcome) in executable_indices
             .iter()
@@ -3204,20 +3797,26 @@ pub(crate) async fn run_tool_call_loop(
             .zip(executed_outcomes.into_iter())
         {
             saw_any_tool_execution = true;
-            if outcome.success && tool_call_indicates_filesystem_write(call) {
+            let kind = find_tool(tools_registry, &call.name)
+                .map_or(tools::ToolKind::Other, |tool| tool.kind());
+            record_tool_invocation(run_log, call, kind, &outcome, iteration);
+            if outcome.timed_out {
+                record_tool_timeout(run_log, call, iteration);
+            }
+            if outcome.success() && tool_call_indicates_filesystem_write(call, kind) {
                 saw_verified_filesystem_write = true;
                 pending_post_write_read_verification = true;
             }
-            if outcome.success && tool_call_indicates_filesystem_read(call) {
+            if outcome.success() && tool_call_indicates_filesystem_read(call, kind) {
                 saw_verified_filesystem_read = true;
             }
-            if outcome.success
+            if outcome.success()
                 && pending_post_write_read_verification
-                && tool_call_indicates_filesystem_read(call)
+                && tool_call_indicates_filesystem_read(call, kind)
             {
                 pending_post_write_read_verification = false;
             }
-            if !outcome.success && tool_call_indicates_filesystem_write(call) {
+            if !outcome.success() && tool_call_indicates_filesystem_write(call, kind) {
                 tracing::warn!(
                     tool = %call.name,
                     reason = %outcome.error_reason.as_deref().unwrap_or("unknown"),
@@ -3231,7 +3830,7 @@ pub(crate) async fn run_tool_call_loop(
                 Some(provider_name),
                 Some(model),
                 Some(&turn_id),
-                Some(outcome.success),
+                Some(outcome.success()),
                 outcome.error_reason.as_deref(),
                 serde_json::json!({
                     "iteration": iteration + 1,
@@ -3244,7 +3843,7 @@ pub(crate) async fn run_tool_call_loop(
             // ── Hook: after_tool_call (void) ─────────────────
             if let Some(hooks) = hooks {
                 let tool_result_obj = crate::tools::ToolResult {
-                    success: outcome.success,
+                    success: outcome.success(),
                     output: outcome.output.clone(),
                     error: None,
                 };
@@ -3256,7 +3855,7 @@ pub(crate) async fn run_tool_call_loop(
             // ── Progress: tool completion ───────────────────────
             if let Some(ref tx) = on_delta {
                 let secs = outcome.duration.as_secs();
-                let icon = if outcome.success {
+                let icon = if outcome.success() {
                     "\u{2705}"
                 } else {
                     "\u{274c}"
@@ -3270,12 +3869,16 @@ pub(crate) async fn run_tool_call_loop(
 
         for entry in ordered_results {
             if let Some((tool_name, tool_call_id, outcome)) = entry {
-                individual_results.push((tool_call_id, outcome.output.clone()));
-                let _ = writeln!(
-                    tool_results,
-                    "<tool_result name=\"{}\">\n{}\n</tool_result>",
-                    tool_name, outcome.output
-                );
+                let mut envelope = tools::ToolResultEnvelope::new(outcome.status, outcome.output);
+                envelope.metadata = outcome.metadata;
+                let envelope = envelope
+                    .with_metadata("tool", tool_name.as_str())
+                    .with_metadata(
+                        "duration_ms",
+                        u64::try_from(outcome.duration.as_millis()).unwrap_or(u64::MAX),
+                    );
+                let _ = writeln!(tool_results, "{}", envelope.to_prompt_block(&tool_name));
+                individual_results.push((tool_call_id, envelope));
             }
         }
 
@@ -3291,25 +3894,21 @@ pub(crate) async fn run_tool_call_loop(
                     .iter()
                     .all(|(tool_call_id, _)| tool_call_id.is_some());
             if all_results_have_ids {
-                for (tool_call_id, result) in &individual_results {
-                    let tool_msg = serde_json::json!({
-                        "tool_call_id": tool_call_id,
-                        "content": result,
-                    });
-                    history.push(ChatMessage::tool(tool_msg.to_string()));
+                for (tool_call_id, envelope) in &individual_results {
+                    history.push(ChatMessage::tool(
+                        envelope.to_tool_message(tool_call_id.as_deref()),
+                    ));
                 }
             } else {
                 history.push(ChatMessage::user(format!("[Tool results]\n{tool_results}")));
             }
         } else {
-            for (native_call, (_, result)) in
+            for (native_call, (_, envelope)) in
                 native_tool_calls.iter().zip(individual_results.iter())
             {
-                let tool_msg = serde_json::json!({
-                    "tool_call_id": native_call.id,
-                    "content": result,
-                });
-                history.push(ChatMessage::tool(tool_msg.to_string()));
+                history.push(ChatMessage::tool(
+                    envelope.to_tool_message(Some(&native_call.id)),
+                ));
             }
         }
     }
@@ -3413,6 +4012,14 @@ pub(crate) async fn run_tool_call_loop(
 /// Build the tool instruction block for the system prompt so the LLM knows
 /// how to invoke tools.
 pub(crate) fn build_tool_instructions(tools_registry: &[Box<dyn Tool>]) -> String {
+    build_tool_instructions_filtered(tools_registry, &[])
+}
+
+/// Tool-use protocol instructions listing only tools not in `excluded`.
+pub(crate) fn build_tool_instructions_filtered(
+    tools_registry: &[Box<dyn Tool>],
+    excluded: &[String],
+) -> String {
     let mut instructions = String::new();
     instructions.push_str("\n## Tool Use Protocol\n\n");
     instructions.push_str("To use a tool, wrap a JSON object in <tool_call></tool_call> tags:\n\n");
@@ -3427,7 +4034,10 @@ pub(crate) fn build_tool_instructions(tools_registry: &[Box<dyn Tool>]) -> Strin
         .push_str("Continue reasoning with the results until you can give a final answer.\n\n");
     instructions.push_str("### Available Tools\n\n");
 
-    for tool in tools_registry {
+    for tool in tools_registry
+        .iter()
+        .filter(|tool| !excluded.iter().any(|name| name == tool.name()))
+    {
         let _ = writeln!(
             instructions,
             "**{}**: {}\nParameters: `{}`\n",
@@ -3519,6 +4129,13 @@ pub async fn run(
         .as_deref()
         .or(config.default_provider.as_deref())
         .unwrap_or("openrouter");
+    // `--provider simulation` also mocks network tools; the registry already
+    // does this when simulation is the configured default.
+    if provider_name == providers::simulation::SIMULATION_PROVIDER
+        && config.default_provider.as_deref() != Some(provider_name)
+    {
+        tools_registry = tools::simulate_network_tools(tools_registry);
+    }
 
     let model_name = model_override
         .as_deref()
@@ -3530,16 +4147,28 @@ pub async fn run(
         zeroclaw_dir: config.config_path.parent().map(std::path::PathBuf::from),
         secrets_encrypt: config.secrets.encrypt,
         reasoning_enabled: config.runtime.reasoning_enabled,
+        max_tokens: config.runtime.max_tokens,
+        ollama_keep_alive: config.runtime.ollama_keep_alive.clone(),
+        gemini_safety_threshold: config.runtime.gemini_safety_threshold.clone(),
+        observer: Some(Arc::clone(&observer)),
     };
 
-    let provider: Box<dyn Provider> = providers::create_routed_provider_with_options(
-        provider_name,
-        config.api_key.as_deref(),
-        config.api_url.as_deref(),
-        &config.reliability,
-        &config.model_routes,
-        model_name,
-        &provider_runtime_options,
+    let provider: Box<dyn Provider> = providers::recording::with_recording(
+        providers::caching::with_response_cache(
+            providers::create_routed_provider_with_options(
+                provider_name,
+                config.api_key.as_deref(),
+                config.api_url.as_deref(),
+                &config.reliability,
+                &config.model_routes,
+                model_name,
+                &provider_runtime_options,
+            )?,
+            &config.memory,
+            &config.workspace_dir,
+        ),
+        &config.runtime,
+        &config.workspace_dir,
     )?;
 
     observer.record_event(&ObserverEvent::AgentStart {
@@ -3569,114 +4198,8 @@ pub async fn run(
 
     // ── Build system prompt from workspace MD files (OpenClaw framework) ──
     let skills = crate::skills::load_skills_with_config(&config.workspace_dir, &config);
-    let mut tool_descs: Vec<(&str, &str)> = vec![
-        (
-            "shell",
-            "Execute terminal commands. Use when: running local checks, build/test commands, diagnostics. Don't use when: a safer dedicated tool exists, or command is destructive without approval.",
-        ),
-        (
-            "file_read",
-            "Read file contents. Use when: inspecting project files, configs, logs. Don't use when: a targeted search is enough.",
-        ),
-        (
-            "file_write",
-            "Write file contents. Use when: applying focused edits, scaffolding files, updating docs/code. Don't use when: side effects are unclear or file ownership is uncertain.",
-        ),
-        (
-            "memory_store",
-            "Save to memory. Use when: preserving durable preferences, decisions, key context. Don't use when: information is transient/noisy/sensitive without need.",
-        ),
-        (
-            "memory_recall",
-            "Search memory. Use when: retrieving prior decisions, user preferences, historical context. Don't use when: answer is already in current context.",
-        ),
-        (
-            "memory_forget",
-            "Delete a memory entry. Use when: memory is incorrect/stale or explicitly requested for removal. Don't use when: impact is uncertain.",
-        ),
-    ];
-    tool_descs.push((
-        "cron_add",
-        "Create a cron job. Supports schedule kinds: cron, at, every; and job types: shell or agent.",
-    ));
-    tool_descs.push((
-        "cron_list",
-        "List all cron jobs with schedule, status, and metadata.",
-    ));
-    tool_descs.push(("cron_remove", "Remove a cron job by job_id."));
-    tool_descs.push((
-        "cron_update",
-        "Patch a cron job (schedule, enabled, command/prompt, model, delivery, session_target).",
-    ));
-    tool_descs.push((
-        "cron_run",
-        "Force-run a cron job immediately and record a run history entry.",
-    ));
-    tool_descs.push(("cron_runs", "Show recent run history for a cron job."));
-    tool_descs.push((
-        "screenshot",
-        "Capture a screenshot of the current screen. Returns file path and base64-encoded PNG. Use when: visual verification, UI inspection, debugging displays.",
-    ));
-    tool_descs.push((
-        "image_info",
-        "Read image file metadata (format, dimensions, size) and optionally base64-encode it. Use when: inspecting images, preparing visual data for analysis.",
-    ));
-    if config.browser.enabled {
-        tool_descs.push((
-            "browser_open",
-            "Open approved HTTPS URLs in Brave Browser (allowlist-only, no scraping)",
-        ));
-    }
-    if config.composio.enabled {
-        tool_descs.push((
-            "composio",
-            "Execute actions on 1000+ apps via Composio (Gmail, Notion, GitHub, Slack, etc.). Use action='list' to discover, 'execute' to run (optionally with connected_account_id), 'connect' to OAuth.",
-        ));
-    }
-    tool_descs.push((
-        "schedule",
-        "Manage scheduled tasks (create/list/get/cancel/pause/resume). Supports recurring cron and one-shot delays.",
-    ));
-    tool_descs.push((
-        "model_routing_config",
-        "Configure default model, scenario routing, and delegate agents. Use for natural-language requests like: 'set conversation to kimi and coding to gpt-5.3-codex'.",
-    ));
-    if !config.agents.is_empty() {
-        tool_descs.push((
-            "delegate",
-            "Delegate a sub-task to a specialized agent. Use when: task needs different model/capability, or to parallelize work.",
-        ));
-    }
-    if config.peripherals.enabled && !config.peripherals.boards.is_empty() {
-        tool_descs.push((
-            "gpio_read",
-            "Read GPIO pin value (0 or 1) on connected hardware (STM32, Arduino). Use when: checking sensor/button state, LED status.",
-        ));
-        tool_descs.push((
-            "gpio_write",
-            "Set GPIO pin high (1) or low (0) on connected hardware. Use when: turning LED on/off, controlling actuators.",
-        ));
-        tool_descs.push((
-            "arduino_upload",
-            "Upload agent-generated Arduino sketch. Use when: user asks for 'make a heart', 'blink pattern', or custom LED behavior on Arduino. You write the full .ino code; ZeroClaw compiles and uploads it. Pin 13 = built-in LED on Uno.",
-        ));
-        tool_descs.push((
-            "hardware_memory_map",
-            "Return flash and RAM address ranges for connected hardware. Use when: user asks for 'upper and lower memory addresses', 'memory map', or 'readable addresses'.",
-        ));
-        tool_descs.push((
-            "hardware_board_info",
-            "Return full board info (chip, architecture, memory map) for connected hardware. Use when: user asks for 'board info', 'what board do I have', 'connected hardware', 'chip info', or 'what hardware'.",
-        ));
-        tool_descs.push((
-            "hardware_memory_read",
-            "Read actual memory/register values from Nucleo via USB. Use when: user asks to 'read register values', 'read memory', 'dump lower memory 0-126', 'give address and value'. Params: address (hex, default 0x20000000), length (bytes, default 128).",
-        ));
-        tool_descs.push((
-            "hardware_capabilities",
-            "Query connected hardware for reported GPIO pins and LED pin. Use when: user asks what pins are available.",
-        ));
-    }
+    let tool_entries = crate::channels::tool_prompt::tool_prompt_entries(&tools_registry, &[]);
+    let tool_descs = crate::channels::tool_prompt::as_tool_descs(&tool_entries);
     let bootstrap_max_chars = if config.agent.compact_context {
         Some(6000)
     } else {
@@ -3758,6 +4281,8 @@ pub async fn run(
             None,
             None,
             &[],
+            None,
+            config.agent.tool_concurrency(),
         )
         .await?;
         final_output = response.clone();
@@ -3879,6 +4404,8 @@ pub async fn run(
                 None,
                 None,
                 &[],
+                None,
+                config.agent.tool_concurrency(),
             )
             .await
             {
@@ -3941,7 +4468,7 @@ pub async fn process_message_with_channel(
     message: &str,
     channel: &str,
 ) -> Result<String> {
-    process_message_with_channel_with_progress(config, message, channel, None).await
+    process_message_with_channel_with_progress(config, message, channel, None, None).await
 }
 
 pub async fn process_message_with_channel_with_progress(
@@ -3949,6 +4476,7 @@ pub async fn process_message_with_channel_with_progress(
     message: &str,
     channel: &str,
     progress_reporter: Option<crate::agent::task_engine::TaskProgressReporter>,
+    output_format: Option<crate::agent::output_format::OutputFormat>,
 ) -> Result<String> {
     let observer: Arc<dyn Observer> =
         Arc::from(observability::create_observer(&config.observability));
@@ -4001,16 +4529,44 @@ pub async fn process_message_with_channel_with_progress(
         zeroclaw_dir: config.config_path.parent().map(std::path::PathBuf::from),
         secrets_encrypt: config.secrets.encrypt,
         reasoning_enabled: config.runtime.reasoning_enabled,
+        max_tokens: config.runtime.max_tokens,
+        ollama_keep_alive: config.runtime.ollama_keep_alive.clone(),
+        gemini_safety_threshold: config.runtime.gemini_safety_threshold.clone(),
+        observer: Some(Arc::clone(&observer)),
     };
-    let provider: Box<dyn Provider> = providers::create_routed_provider_with_options(
-        provider_name,
-        config.api_key.as_deref(),
-        config.api_url.as_deref(),
-        &config.reliability,
-        &config.model_routes,
-        &model_name,
-        &provider_runtime_options,
+    let provider: Box<dyn Provider> = providers::recording::with_recording(
+        providers::caching::with_response_cache(
+            providers::create_routed_provider_with_options(
+                provider_name,
+                config.api_key.as_deref(),
+                config.api_url.as_deref(),
+                &config.reliability,
+                &config.model_routes,
+                &model_name,
+                &provider_runtime_options,
+            )?,
+            &config.memory,
+            &config.workspace_dir,
+        ),
+        &config.runtime,
+        &config.workspace_dir,
     )?;
+    let model_router = crate::agent::model_router::ModelRouter::new(
+        config.query_classification.clone(),
+        config.model_routes.clone(),
+    );
+    let classifier_hint = model_router.classify(message);
+    let routed = classifier_hint
+        .as_deref()
+        .and_then(|hint| model_router.route_for_hint(hint));
+    // The routed provider resolves `hint:` models to the route's provider and model.
+    let request_model = routed.as_ref().map_or_else(
+        || model_name.clone(),
+        |routed| format!("hint:{}", routed.hint),
+    );
+    let temperature = routed
+        .and_then(|routed| routed.temperature)
+        .unwrap_or(config.default_temperature);
 
     let hardware_rag: Option<crate::rag::HardwareRag> = config
         .peripherals
@@ -4027,54 +4583,16 @@ pub async fn process_message_with_channel_with_progress(
         .map(|b| b.board.clone())
         .collect();
 
+    let excluded_tools: &[String] = if channel == "cli" {
+        &[]
+    } else {
+        config.autonomy.non_cli_excluded_tools.as_slice()
+    };
+
     let skills = crate::skills::load_skills_with_config(&config.workspace_dir, &config);
-    let mut tool_descs: Vec<(&str, &str)> = vec![
-        ("shell", "Execute terminal commands."),
-        ("file_read", "Read file contents."),
-        ("file_write", "Write file contents."),
-        ("memory_store", "Save to memory."),
-        ("memory_recall", "Search memory."),
-        ("memory_forget", "Delete a memory entry."),
-        (
-            "model_routing_config",
-            "Configure default model, scenario routing, and delegate agents.",
-        ),
-        ("screenshot", "Capture a screenshot."),
-        ("image_info", "Read image metadata."),
-    ];
-    if config.browser.enabled {
-        tool_descs.push(("browser_open", "Open approved URLs in browser."));
-    }
-    if config.composio.enabled {
-        tool_descs.push(("composio", "Execute actions on 1000+ apps via Composio."));
-    }
-    if config.peripherals.enabled && !config.peripherals.boards.is_empty() {
-        tool_descs.push(("gpio_read", "Read GPIO pin value on connected hardware."));
-        tool_descs.push((
-            "gpio_write",
-            "Set GPIO pin high or low on connected hardware.",
-        ));
-        tool_descs.push((
-            "arduino_upload",
-            "Upload Arduino sketch. Use for 'make a heart', custom patterns. You write full .ino code; ZeroClaw uploads it.",
-        ));
-        tool_descs.push((
-            "hardware_memory_map",
-            "Return flash and RAM address ranges. Use when user asks for memory addresses or memory map.",
-        ));
-        tool_descs.push((
-            "hardware_board_info",
-            "Return full board info (chip, architecture, memory map). Use when user asks for board info, what board, connected hardware, or chip info.",
-        ));
-        tool_descs.push((
-            "hardware_memory_read",
-            "Read actual memory/register values from Nucleo. Use when user asks to read registers, read memory, dump lower memory 0-126, or give address and value.",
-        ));
-        tool_descs.push((
-            "hardware_capabilities",
-            "Query connected hardware for reported GPIO pins and LED pin. Use when user asks what pins are available.",
-        ));
-    }
+    let tool_entries =
+        crate::channels::tool_prompt::tool_prompt_entries(&tools_registry, excluded_tools);
+    let tool_descs = crate::channels::tool_prompt::as_tool_descs(&tool_entries);
     let bootstrap_max_chars = if config.agent.compact_context {
         Some(6000)
     } else {
@@ -4092,7 +4610,10 @@ pub async fn process_message_with_channel_with_progress(
         config.skills.prompt_injection_mode,
     );
     if !native_tools {
-        system_prompt.push_str(&build_tool_instructions(&tools_registry));
+        system_prompt.push_str(&build_tool_instructions_filtered(
+            &tools_registry,
+            excluded_tools,
+        ));
     }
 
     let mem_context = build_context(mem.as_ref(), message, config.memory.min_relevance_score).await;
@@ -4113,19 +4634,64 @@ pub async fn process_message_with_channel_with_progress(
         ChatMessage::user(&enriched),
     ];
 
+    let policy = Arc::new(crate::security::ToolPolicy::from_config(
+        &config.autonomy.tool_policy,
+    )?);
     if should_use_task_engine_for_channel(channel, &config) {
         let engine_cfg = crate::agent::task_engine::TaskEngineConfig {
             gray_zone_verifier_enabled: config.autonomy.gray_zone_verifier_enabled,
             gray_zone_verifier_timeout_ms: config.autonomy.gray_zone_verifier_timeout_ms,
+            task_timeout_secs: config.autonomy.task_timeout_secs,
+            completion_policy: config.autonomy.completion_policy.clone(),
+            channel_completion_policies: config.autonomy.channel_completion_policies.clone(),
+            task_env: config.autonomy.task_env.clone(),
+            fallback_providers: config.autonomy.task_fallback_providers.clone(),
+            budget: config.autonomy.task_budget.clone(),
+            model_prices: config.cost.prices.clone(),
+            report_cost: config.cost.report_cost,
+            report_tool_calls: config.autonomy.task_progress_tool_calls,
+            snapshot_workspace_files: config.autonomy.task_file_snapshots,
+            decompose_steps: config.autonomy.task_step_decomposition,
+            acceptance_tests: config.autonomy.task_acceptance_tests.clone(),
+            back_up_writes: config.autonomy.task_write_backups,
+            security: Arc::clone(&security),
+            max_parallel_tools: config.agent.tool_concurrency(),
+            context_max_tokens: config.agent.context_max_tokens,
+            provider_params: config.agent.provider_params.clone(),
+            tool_policy: policy,
             ..crate::agent::task_engine::TaskEngineConfig::default()
         };
         let engine = crate::agent::task_engine::TaskEngine::new(&config.workspace_dir, engine_cfg)?;
-
-        let excluded_tools: &[String] = if channel == "cli" {
-            &[]
-        } else {
-            config.autonomy.non_cli_excluded_tools.as_slice()
-        };
+        let fallbacks: Vec<(&crate::config::ProviderSpec, Box<dyn Provider>)> = config
+            .autonomy
+            .task_fallback_providers
+            .iter()
+            .filter(|spec| spec.provider != provider_name || spec.model != model_name)
+            .filter_map(|spec| {
+                let key = spec
+                    .api_key
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|key| !key.is_empty())
+                    .or(config.api_key.as_deref());
+                match providers::create_resilient_provider_with_options(
+                    &spec.provider,
+                    key,
+                    None,
+                    &config.reliability,
+                    &provider_runtime_options,
+                ) {
+                    Ok(provider) => Some((spec, provider)),
+                    Err(err) => {
+                        tracing::warn!(
+                            provider = spec.provider.as_str(),
+                            "Ignoring task fallback provider that failed to initialize: {err}"
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
 
         let req = crate::agent::task_engine::TaskRunRequest {
             channel,
@@ -4137,8 +4703,8 @@ pub async fn process_message_with_channel_with_progress(
             tools_registry: &tools_registry,
             observer: observer.as_ref(),
             provider_name,
-            model: &model_name,
-            temperature: config.default_temperature,
+            model: &request_model,
+            temperature,
             multimodal: &config.multimodal,
             max_tool_iterations: config.agent.max_tool_iterations,
             cancellation_token: None,
@@ -4146,21 +4712,52 @@ pub async fn process_message_with_channel_with_progress(
             hooks: None,
             excluded_tools,
             progress_reporter,
+            labels: classifier_hint
+                .iter()
+                .map(|hint| format!("hint:{hint}"))
+                .collect(),
+            bypass_completion: false,
+            output_format,
+            env: std::collections::HashMap::new(),
+            fallback_providers: fallbacks
+                .iter()
+                .map(
+                    |(spec, provider)| crate::agent::task_engine::ProviderFallback {
+                        provider: provider.as_ref(),
+                        provider_name: &spec.provider,
+                        model: &spec.model,
+                    },
+                )
+                .collect(),
+            steps: Vec::new(),
+            provider_params: crate::config::ProviderParams::default(),
         };
         let outcome = crate::agent::task_engine::TaskEngine::run_task(req, &engine).await?;
         Ok(outcome.final_response)
     } else {
-        agent_turn(
-            provider.as_ref(),
-            &mut history,
-            &tools_registry,
-            observer.as_ref(),
-            provider_name,
-            &model_name,
-            config.default_temperature,
-            true,
-            &config.multimodal,
-            config.agent.max_tool_iterations,
+        let policy_scope = tool_policy::PolicyScope {
+            policy,
+            channel: channel.to_string(),
+            sender: "gateway-user".to_string(),
+            workspace_dir: config.workspace_dir.clone(),
+            approved: HashSet::new(),
+            confirmable: false,
+        };
+        tool_policy::scope(
+            policy_scope,
+            agent_turn(
+                provider.as_ref(),
+                &mut history,
+                &tools_registry,
+                observer.as_ref(),
+                provider_name,
+                &request_model,
+                temperature,
+                true,
+                &config.multimodal,
+                config.agent.max_tool_iterations,
+                excluded_tools,
+            ),
         )
         .await
     }
@@ -4267,6 +4864,7 @@ mod tests {
                 tool_calls: Vec::new(),
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             })
         }
     }
@@ -4285,6 +4883,7 @@ mod tests {
                     tool_calls: Vec::new(),
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 })
                 .collect();
             Self {
@@ -4479,6 +5078,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect_err("provider without vision support should fail");
@@ -4525,6 +5126,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect_err("oversized payload must fail");
@@ -4565,6 +5168,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("valid multimodal payload should pass");
@@ -4573,6 +5178,82 @@ mod tests {
         assert_eq!(calls.load(Ordering::SeqCst), 1);
     }
 
+    struct StreamingProvider;
+
+    #[async_trait]
+    impl Provider for StreamingProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            anyhow::bail!("streamed turns should not use chat_with_system");
+        }
+
+        fn chat_stream<'a>(
+            &'a self,
+            _request: ChatRequest<'a>,
+            _model: &str,
+            _temperature: f64,
+        ) -> futures_util::stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+            let done = ChatResponse {
+                text: Some("Hello world".to_string()),
+                tool_calls: Vec::new(),
+                usage: None,
+                reasoning_content: None,
+                finish_reason: None,
+            };
+            futures_util::stream::iter([
+                Ok(ChatDelta::Text("Hello ".to_string())),
+                Ok(ChatDelta::Text("world".to_string())),
+                Ok(ChatDelta::Done(done)),
+            ])
+            .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn run_tool_call_loop_streams_answer_into_on_delta_once() {
+        let mut history = vec![ChatMessage::user("say hello".to_string())];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+
+        let answer = run_tool_call_loop(
+            &StreamingProvider,
+            &mut history,
+            &tools_registry,
+            &NoopObserver,
+            "mock-provider",
+            "mock-model",
+            0.0,
+            true,
+            None,
+            "cli",
+            &crate::config::MultimodalConfig::default(),
+            3,
+            None,
+            Some(tx),
+            None,
+            &[],
+            None,
+            1,
+        )
+        .await
+        .expect("streamed turn should succeed");
+        assert_eq!(answer, "Hello world");
+
+        let mut deltas = Vec::new();
+        while let Ok(delta) = rx.try_recv() {
+            deltas.push(delta);
+        }
+        assert_eq!(deltas.len(), 3, "{deltas:?}");
+        assert!(deltas[0].contains("Thinking"));
+        assert_eq!(deltas[1], DRAFT_CLEAR_SENTINEL);
+        assert_eq!(deltas[2], "Hello world");
+    }
+
     #[tokio::test]
     async fn run_tool_call_loop_soft_fails_unverified_filesystem_write_claims() {
         let provider = ScriptedProvider::from_text_responses(vec![
@@ -4606,6 +5287,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("guardrail should return soft-fail notice instead of hard error");
@@ -4661,6 +5344,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("write-claim guard should return soft-fail notice");
@@ -4717,6 +5402,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("write claim should pass after post-write read verification");
@@ -4759,6 +5446,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("language guard should return soft-fail notice");
@@ -4814,6 +5503,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("autonomy guard should return soft-fail notice");
@@ -4889,6 +5580,34 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn execute_tools_parallel_bounds_concurrency_and_keeps_call_order() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_active = Arc::new(AtomicUsize::new(0));
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(DelayTool::new(
+            "delay",
+            50,
+            Arc::clone(&active),
+            Arc::clone(&max_active),
+        ))];
+        let calls: Vec<ParsedToolCall> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|value| ParsedToolCall {
+                name: "delay".to_string(),
+                arguments: serde_json::json!({ "value": value }),
+                tool_call_id: None,
+            })
+            .collect();
+
+        let outcomes = execute_tools_parallel(&calls, &tools_registry, &NoopObserver, None, 2)
+            .await
+            .expect("parallel execution should complete");
+
+        assert_eq!(max_active.load(Ordering::SeqCst), 2);
+        let outputs: Vec<&str> = outcomes.iter().map(|o| o.output.as_str()).collect();
+        assert_eq!(outputs, vec!["ok:a", "ok:b", "ok:c", "ok:d"]);
+    }
+
     #[tokio::test]
     async fn run_tool_call_loop_soft_fails_unverified_filesystem_analysis_claims() {
         let provider = ScriptedProvider::from_text_responses(vec![
@@ -4922,6 +5641,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("grounding guard should return soft-fail notice");
@@ -4974,6 +5695,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("analysis claim should pass after read verification");
@@ -5015,6 +5738,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("brainstorming guard should return soft-fail notice");
@@ -5059,6 +5784,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("clarifying-question response should pass brainstorming guard");
@@ -5125,6 +5852,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            4,
         )
         .await
         .expect("parallel execution should complete");
@@ -5153,6 +5882,129 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn run_tool_call_loop_rejects_calls_that_do_not_match_the_schema() {
+        let provider = ScriptedProvider::from_text_responses(vec![
+            r#"<tool_call>
+{"name":"delay_a","arguments":{"value":7}}
+</tool_call>"#,
+            "done",
+        ]);
+        let max_active = Arc::new(AtomicUsize::new(0));
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(DelayTool::new(
+            "delay_a",
+            10,
+            Arc::new(AtomicUsize::new(0)),
+            Arc::clone(&max_active),
+        ))];
+        let mut history = vec![
+            ChatMessage::system("test-system"),
+            ChatMessage::user("run the tool"),
+        ];
+
+        let result = run_tool_call_loop(
+            &provider,
+            &mut history,
+            &tools_registry,
+            &NoopObserver,
+            "mock-provider",
+            "mock-model",
+            0.0,
+            true,
+            None,
+            "cli",
+            &crate::config::MultimodalConfig::default(),
+            4,
+            None,
+            None,
+            None,
+            &[],
+            None,
+            1,
+        )
+        .await
+        .expect("loop should continue after the rejected call");
+
+        assert_eq!(result, "done");
+        assert_eq!(max_active.load(Ordering::SeqCst), 0, "tool must not run");
+        let tool_results = history
+            .iter()
+            .find(|msg| msg.role == "user" && msg.content.starts_with("[Tool results]"))
+            .expect("prompt-mode tool result payload should be present");
+        assert!(tool_results.content.contains("status=\"error\""));
+        assert!(tool_results.content.contains(
+            "Invalid arguments for tool 'delay_a' (the tool did not run): $.value: expected type"
+        ));
+    }
+
+    #[tokio::test]
+    async fn run_tool_call_loop_stops_tools_at_their_time_limit() {
+        crate::agent::tool_limits::init_from_config(&crate::config::AgentConfig {
+            tool_limits: std::collections::HashMap::from([(
+                "slow_limited_tool".to_string(),
+                crate::config::ToolLimitsConfig {
+                    timeout_secs: Some(1),
+                    max_output_bytes: None,
+                },
+            )]),
+            ..crate::config::AgentConfig::default()
+        });
+        let provider = ScriptedProvider::from_text_responses(vec![
+            r#"<tool_call>
+{"name":"slow_limited_tool","arguments":{"value":"A"}}
+</tool_call>"#,
+            "done",
+        ]);
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(DelayTool::new(
+            "slow_limited_tool",
+            30_000,
+            Arc::new(AtomicUsize::new(0)),
+            Arc::new(AtomicUsize::new(0)),
+        ))];
+        let mut history = vec![
+            ChatMessage::system("test-system"),
+            ChatMessage::user("run the slow tool"),
+        ];
+        let run_log = ToolLoopLog::default();
+
+        let result = run_tool_call_loop(
+            &provider,
+            &mut history,
+            &tools_registry,
+            &NoopObserver,
+            "mock-provider",
+            "mock-model",
+            0.0,
+            true,
+            None,
+            "cli",
+            &crate::config::MultimodalConfig::default(),
+            4,
+            None,
+            None,
+            None,
+            &[],
+            Some(&run_log),
+            1,
+        )
+        .await
+        .expect("loop should continue after the timed-out call");
+
+        assert_eq!(result, "done");
+        let tool_results = history
+            .iter()
+            .find(|msg| msg.role == "user" && msg.content.starts_with("[Tool results]"))
+            .expect("prompt-mode tool result payload should be present");
+        assert!(tool_results.content.contains("status=\"error\""));
+        assert!(tool_results
+            .content
+            .contains("Tool 'slow_limited_tool' timed out after 1s"));
+        let timeouts = run_log.tool_timeouts.into_inner().unwrap();
+        assert_eq!(timeouts.len(), 1);
+        assert_eq!(timeouts[0].tool, "slow_limited_tool");
+        assert_eq!(timeouts[0].timeout_secs, 1);
+    }
+
     #[tokio::test]
     async fn run_tool_call_loop_deduplicates_repeated_tool_calls() {
         let provider = ScriptedProvider::from_text_responses(vec![
@@ -5176,6 +6028,7 @@ mod tests {
             ChatMessage::user("run tool calls"),
         ];
         let observer = NoopObserver;
+        let run_log = ToolLoopLog::default();
 
         let result = run_tool_call_loop(
             &provider,
@@ -5194,6 +6047,8 @@ mod tests {
             None,
             None,
             &[],
+            Some(&run_log),
+            1,
         )
         .await
         .expect("loop should finish after deduplicating repeated calls");
@@ -5211,6 +6066,177 @@ mod tests {
             .expect("prompt-mode tool result payload should be present");
         assert!(tool_results.content.contains("counted:A"));
         assert!(tool_results.content.contains("Skipped duplicate tool call"));
+
+        let executed = run_log.invocations.into_inner().unwrap();
+        assert_eq!(
+            executed,
+            vec![ToolInvocation {
+                name: "count_tool".into(),
+                kind: tools::ToolKind::Other,
+                arguments: serde_json::json!({"value": "A"}),
+                success: true,
+                status: Some(tools::ToolStatus::Ok),
+                output: "counted:A".into(),
+                iteration: 1,
+                metadata: serde_json::Map::new(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn run_tool_call_loop_blocks_excluded_tools_and_records_decision() {
+        let provider = ScriptedProvider::from_text_responses(vec![
+            r#"<tool_call>
+{"name":"count_tool","arguments":{"value":"A"}}
+</tool_call>"#,
+            "done",
+        ]);
+
+        let invocations = Arc::new(AtomicUsize::new(0));
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(CountingTool::new(
+            "count_tool",
+            Arc::clone(&invocations),
+        ))];
+        let mut history = vec![
+            ChatMessage::system("test-system"),
+            ChatMessage::user("run tool calls"),
+        ];
+        let observer = NoopObserver;
+        let run_log = ToolLoopLog::default();
+
+        let result = run_tool_call_loop(
+            &provider,
+            &mut history,
+            &tools_registry,
+            &observer,
+            "mock-provider",
+            "mock-model",
+            0.0,
+            true,
+            None,
+            "telegram",
+            &crate::config::MultimodalConfig::default(),
+            4,
+            None,
+            None,
+            None,
+            &["count_tool".to_string()],
+            Some(&run_log),
+            1,
+        )
+        .await
+        .expect("loop should finish after blocking the excluded tool");
+
+        assert_eq!(result, "done");
+        assert_eq!(invocations.load(Ordering::SeqCst), 0);
+        let ToolLoopLog {
+            policy_decisions,
+            invocations: executed,
+            ..
+        } = run_log;
+        assert!(executed.into_inner().unwrap().is_empty());
+        let decisions = policy_decisions.into_inner().unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].source, "exclusion");
+        assert_eq!(decisions[0].decision, "blocked");
+        assert_eq!(decisions[0].tool.as_deref(), Some("count_tool"));
+        assert_eq!(
+            decisions[0].arguments_hash,
+            Some(tool_arguments_hash(
+                "count_tool",
+                &serde_json::json!({"value": "A"})
+            ))
+        );
+    }
+
+    struct CancelSecondStepHook;
+
+    #[async_trait]
+    impl crate::hooks::HookHandler for CancelSecondStepHook {
+        fn name(&self) -> &str {
+            "cancel-second-step"
+        }
+
+        async fn before_tool_call(
+            &self,
+            name: String,
+            args: serde_json::Value,
+        ) -> crate::hooks::HookResult<(String, serde_json::Value)> {
+            if args["value"].as_str().is_some_and(|v| v.ends_with("-2")) {
+                return crate::hooks::HookResult::Cancel("second step blocked".into());
+            }
+            crate::hooks::HookResult::Continue((name, args))
+        }
+    }
+
+    #[tokio::test]
+    async fn run_tool_call_loop_runs_macro_steps_through_hooks() {
+        let provider = ScriptedProvider::from_text_responses(vec![
+            r#"<tool_call>
+{"name":"twice","arguments":{"v":"A"}}
+</tool_call>"#,
+            "done",
+        ]);
+
+        let invocations = Arc::new(AtomicUsize::new(0));
+        let inner: Vec<Arc<dyn Tool>> = vec![Arc::new(CountingTool::new(
+            "count_tool",
+            Arc::clone(&invocations),
+        ))];
+        let config = crate::config::MacroToolConfig {
+            description: "Count twice".into(),
+            parameters: std::collections::HashMap::from([("v".to_string(), "Value".to_string())]),
+            steps: ["{{v}}-1", "{{v}}-2"]
+                .into_iter()
+                .map(|value| crate::config::MacroToolStep {
+                    tool: "count_tool".into(),
+                    args: serde_json::json!({ "value": value }),
+                })
+                .collect(),
+        };
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(tools::MacroTool::new(
+            "twice",
+            &config,
+            Arc::new(inner),
+        ))];
+        let mut hooks = crate::hooks::HookRunner::new();
+        hooks.register(Box::new(CancelSecondStepHook));
+        let mut history = vec![
+            ChatMessage::system("test-system"),
+            ChatMessage::user("run tool calls"),
+        ];
+        let run_log = ToolLoopLog::default();
+
+        let result = run_tool_call_loop(
+            &provider,
+            &mut history,
+            &tools_registry,
+            &NoopObserver,
+            "mock-provider",
+            "mock-model",
+            0.0,
+            true,
+            None,
+            "telegram",
+            &crate::config::MultimodalConfig::default(),
+            4,
+            None,
+            None,
+            Some(&hooks),
+            &[],
+            Some(&run_log),
+            1,
+        )
+        .await
+        .expect("loop should finish after the hook stops the macro");
+
+        assert_eq!(result, "done");
+        assert_eq!(invocations.load(Ordering::SeqCst), 0);
+        let decisions = run_log.policy_decisions.into_inner().unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].source, "hook");
+        assert_eq!(decisions[0].tool.as_deref(), Some("twice"));
+        assert!(decisions[0].reason.contains("step 2/2 (count_tool)"));
     }
 
     #[tokio::test]
@@ -5250,6 +6276,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("native fallback id flow should complete");
@@ -5310,6 +6338,8 @@ mod tests {
             None,
             None,
             &[],
+            None,
+            1,
         )
         .await
         .expect("tool fallback guard should recover and complete");
@@ -6408,6 +7438,21 @@ Let me check the result."#;
         assert_eq!(result, input, "short values should not be redacted");
     }
 
+    #[test]
+    fn scrub_credentials_redacts_authorization_headers() {
+        let args = r#"{"url":"https://api.example.com","headers":{"Authorization":"Bearer abc","Accept":"application/json"}}"#;
+        let scrubbed = scrub_credentials(args);
+        assert!(scrubbed.contains(r#""Authorization":"Bearer [REDACTED]""#));
+        assert!(scrubbed.contains(r#""Accept":"application/json""#));
+
+        let escaped = scrub_credentials(r#"{\"Proxy-Authorization\":\"Basic dXNlcjpw\"}"#);
+        assert_eq!(escaped, r#"{\"Proxy-Authorization\":\"Basic [REDACTED]\"}"#);
+        assert_eq!(
+            scrub_credentials("authorization is required"),
+            "authorization is required"
+        );
+    }
+
     // ─────────────────────────────────────────────────────────────────────
     // TG4 (inline): trim_history edge cases
     // ─────────────────────────────────────────────────────────────────────
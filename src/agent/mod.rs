@@ -1,19 +1,39 @@
+pub mod acceptance_tests;
 #[allow(clippy::module_inception)]
 pub mod agent;
+pub mod artifact_preview;
+pub mod artifact_verifier;
+pub mod claimed_paths;
 pub mod classifier;
+pub mod completion_judge;
+pub mod context_manager;
 pub mod contract_gate;
 pub mod dispatcher;
 pub mod evidence_ledger;
 pub mod gray_zone_verifier;
+pub mod language_packs;
 pub mod loop_;
 pub mod memory_loader;
+pub mod model_router;
+pub mod output_format;
 pub mod prompt;
+pub mod retry_classifier;
+pub mod task_archive;
+pub mod task_blocked;
+pub mod task_budget;
+pub mod task_citations;
 pub mod task_completion;
 pub mod task_contract;
 pub mod task_contract_compiler;
 pub mod task_engine;
+pub mod task_plan;
+pub mod task_steps;
 pub mod task_store;
+pub mod task_transcript;
 pub mod task_types;
+pub mod tool_limits;
+pub mod workspace_snapshot;
+pub mod workspace_transaction;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,138 @@
+use crate::config::schema::{ModelRouteConfig, QueryClassificationConfig};
+
+/// Provider, model, and temperature picked for one request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutedModel {
+    /// Classification hint that selected the route.
+    pub hint: String,
+    pub provider: String,
+    pub model: String,
+    /// Key for the route's provider; `None` uses the default key.
+    pub api_key: Option<String>,
+    /// `None` keeps the caller's default temperature.
+    pub temperature: Option<f64>,
+}
+
+/// Picks the provider and model for a request from its classification.
+///
+/// `[query_classification]` rules turn the message into a hint, and the
+/// `[[model_routes]]` entry with that hint names the provider, model, and
+/// optional temperature to use — e.g. short chat on a cheap model, tool-heavy
+/// tasks on a strong one. Requests whose hint has no route keep the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct ModelRouter {
+    classification: QueryClassificationConfig,
+    routes: Vec<ModelRouteConfig>,
+}
+
+impl ModelRouter {
+    pub fn new(classification: QueryClassificationConfig, routes: Vec<ModelRouteConfig>) -> Self {
+        Self {
+            classification,
+            routes,
+        }
+    }
+
+    /// The classification hint for `message`, whether or not it has a route.
+    pub fn classify(&self, message: &str) -> Option<String> {
+        super::classifier::classify(&self.classification, message)
+    }
+
+    /// The route for an already classified hint.
+    pub fn route_for_hint(&self, hint: &str) -> Option<RoutedModel> {
+        self.routes
+            .iter()
+            .find(|route| route.hint == hint)
+            .map(|route| RoutedModel {
+                hint: route.hint.clone(),
+                provider: route.provider.clone(),
+                model: route.model.clone(),
+                api_key: route.api_key.clone(),
+                temperature: route.temperature,
+            })
+    }
+
+    /// Classify `message` and return its route, if one is configured.
+    pub fn route(&self, message: &str) -> Option<RoutedModel> {
+        self.route_for_hint(&self.classify(message)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::ClassificationRule;
+
+    fn router() -> ModelRouter {
+        ModelRouter::new(
+            QueryClassificationConfig {
+                enabled: true,
+                rules: vec![
+                    ClassificationRule {
+                        hint: "fast".into(),
+                        max_length: Some(40),
+                        keywords: vec!["hi".into(), "thanks".into()],
+                        ..Default::default()
+                    },
+                    ClassificationRule {
+                        hint: "task".into(),
+                        keywords: vec!["write".into(), "run".into()],
+                        priority: 10,
+                        ..Default::default()
+                    },
+                    ClassificationRule {
+                        hint: "unrouted".into(),
+                        keywords: vec!["poem".into()],
+                        ..Default::default()
+                    },
+                ],
+            },
+            vec![
+                ModelRouteConfig {
+                    hint: "fast".into(),
+                    provider: "groq".into(),
+                    model: "llama-3.1-8b-instant".into(),
+                    api_key: None,
+                    temperature: Some(0.3),
+                },
+                ModelRouteConfig {
+                    hint: "task".into(),
+                    provider: "anthropic".into(),
+                    model: "claude-sonnet-4".into(),
+                    api_key: Some("sk-route".into()),
+                    temperature: None,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn simple_chat_goes_to_the_fast_route() {
+        let routed = router().route("hi there").unwrap();
+        assert_eq!(routed.provider, "groq");
+        assert_eq!(routed.model, "llama-3.1-8b-instant");
+        assert_eq!(routed.temperature, Some(0.3));
+    }
+
+    #[test]
+    fn task_requests_go_to_the_strong_route() {
+        let routed = router()
+            .route("hi, please write a report on last week's sales numbers")
+            .unwrap();
+        assert_eq!(routed.hint, "task");
+        assert_eq!(routed.provider, "anthropic");
+        assert_eq!(routed.api_key.as_deref(), Some("sk-route"));
+        assert_eq!(routed.temperature, None);
+    }
+
+    #[test]
+    fn hints_without_a_route_keep_the_defaults() {
+        let router = router();
+        assert_eq!(
+            router.classify("a poem about spring").as_deref(),
+            Some("unrouted")
+        );
+        assert_eq!(router.route("a poem about spring"), None);
+        assert_eq!(ModelRouter::default().route("hi"), None);
+    }
+}
@@ -0,0 +1,251 @@
+//! Declared shape of a task's final answer.
+//!
+//! Programmatic callers can attach an [`OutputFormat`] to a task run: either a
+//! JSON schema the answer must satisfy or a markdown template whose headings
+//! the answer must contain in order. The engine states the format up front,
+//! checks the final response against it, and runs a correction round when
+//! it does not match.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// The answer must be a JSON document (bare or in a ```json fence)
+    /// matching `schema`. Supported keywords: `type`, `properties`,
+    /// `required`, `additionalProperties: false`, `items`, `enum`.
+    JsonSchema { schema: Value },
+    /// The answer must contain the template's markdown headings, in order.
+    MarkdownTemplate { template: String },
+}
+
+/// Schema violations reported in one correction round.
+const MAX_REPORTED_VIOLATIONS: usize = 8;
+
+impl OutputFormat {
+    /// Instruction added to the conversation before the first round.
+    pub fn instructions(&self) -> String {
+        match self {
+            Self::JsonSchema { schema } => format!(
+                "[Task Engine]\n最终答复必须只包含一个符合以下 JSON Schema 的 JSON 文档，不要附加其他文字：\n```json\n{}\n```",
+                serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string())
+            ),
+            Self::MarkdownTemplate { template } => format!(
+                "[Task Engine]\n最终答复必须按以下 Markdown 模板组织，保留全部标题及其顺序：\n{template}"
+            ),
+        }
+    }
+
+    /// Ways `response` departs from the format; empty when it conforms.
+    pub fn validate(&self, response: &str) -> Vec<String> {
+        let mut violations = match self {
+            Self::JsonSchema { schema } => match extract_json(response) {
+                Some(document) => schema_violations(schema, &document),
+                None => vec!["response is not a JSON document".to_string()],
+            },
+            Self::MarkdownTemplate { template } => missing_headings(template, response),
+        };
+        violations.truncate(MAX_REPORTED_VIOLATIONS);
+        violations
+    }
+
+    /// Continuation prompt for a response that failed validation.
+    pub fn correction_prompt(&self, violations: &[String]) -> String {
+        let mut prompt = String::from(
+            "[Task Engine]\n最终答复不符合声明的输出格式，请只修正格式后重新给出完整答复：",
+        );
+        for violation in violations {
+            let _ = write!(prompt, "\n- {violation}");
+        }
+        prompt.push('\n');
+        prompt.push_str(&self.instructions());
+        prompt
+    }
+}
+
+/// Parse the whole response as JSON, falling back to the first fenced block.
+fn extract_json(response: &str) -> Option<Value> {
+    let trimmed = response.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Some(value);
+    }
+    let start = trimmed.find("```")?;
+    let after_fence = &trimmed[start + 3..];
+    let body_start = after_fence.find('\n')? + 1;
+    let body = &after_fence[body_start..];
+    let end = body.find("```")?;
+    serde_json::from_str(body[..end].trim()).ok()
+}
+
+/// Ways `value` departs from `schema`, with the keywords
+/// [`OutputFormat::JsonSchema`] supports; empty when it conforms.
+pub fn schema_violations(schema: &Value, value: &Value) -> Vec<String> {
+    let mut violations = Vec::new();
+    check_schema(schema, value, "$", &mut violations);
+    violations
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn check_schema(schema: &Value, value: &Value, at: &str, violations: &mut Vec<String>) {
+    let type_ok = match schema.get("type") {
+        Some(Value::String(expected)) => type_matches(expected, value),
+        Some(Value::Array(options)) => options
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|expected| type_matches(expected, value)),
+        _ => true,
+    };
+    if !type_ok {
+        violations.push(format!(
+            "{at}: expected type {}",
+            schema.get("type").map(Value::to_string).unwrap_or_default()
+        ));
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            violations.push(format!(
+                "{at}: value {value} is not one of the allowed values"
+            ));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for key in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !object.contains_key(key) {
+                violations.push(format!("{at}: missing required property `{key}`"));
+            }
+        }
+        for (key, item) in object {
+            match properties.and_then(|props| props.get(key)) {
+                Some(item_schema) => {
+                    check_schema(item_schema, item, &format!("{at}.{key}"), violations);
+                }
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    violations.push(format!("{at}: unexpected property `{key}`"));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Some(items), Some(schema_items)) = (value.as_array(), schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            check_schema(schema_items, item, &format!("{at}[{index}]"), violations);
+        }
+    }
+}
+
+/// Template headings absent from `response` or out of order.
+fn missing_headings(template: &str, response: &str) -> Vec<String> {
+    let response_headings: Vec<String> = response
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('#'))
+        .map(normalize_heading)
+        .collect();
+    let mut cursor = 0;
+    let mut missing = Vec::new();
+    for heading in template
+        .lines()
+        .map(str::trim)
+        .filter(|l| l.starts_with('#'))
+    {
+        let wanted = normalize_heading(heading);
+        match response_headings[cursor..]
+            .iter()
+            .position(|found| *found == wanted)
+        {
+            Some(offset) => cursor += offset + 1,
+            None => missing.push(format!("missing or out-of-order section `{heading}`")),
+        }
+    }
+    missing
+}
+
+fn normalize_heading(line: &str) -> String {
+    line.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_schema_accepts_fenced_document_and_reports_violations() {
+        let format = OutputFormat::JsonSchema {
+            schema: serde_json::json!({
+                "type": "object",
+                "required": ["title", "tags"],
+                "additionalProperties": false,
+                "properties": {
+                    "title": {"type": "string"},
+                    "tags": {"type": "array", "items": {"type": "string"}},
+                    "status": {"enum": ["draft", "final"]}
+                }
+            }),
+        };
+        assert!(format
+            .validate("Here you go:\n```json\n{\"title\": \"Q3\", \"tags\": [\"a\"]}\n```")
+            .is_empty());
+
+        let mut violations =
+            format.validate(r#"{"tags": ["a", 2], "status": "wip", "extra": true}"#);
+        violations.sort();
+        assert_eq!(
+            violations,
+            [
+                "$.status: value \"wip\" is not one of the allowed values",
+                "$.tags[1]: expected type \"string\"",
+                "$: missing required property `title`",
+                "$: unexpected property `extra`",
+            ]
+        );
+        assert_eq!(
+            format.validate("sorry, no JSON today"),
+            ["response is not a JSON document"]
+        );
+    }
+
+    #[test]
+    fn markdown_template_requires_headings_in_order() {
+        let format = OutputFormat::MarkdownTemplate {
+            template: "# Report\n## Summary\n...\n## Risks\n...".to_string(),
+        };
+        assert!(format
+            .validate("# Report\n\n##  summary\nAll good.\n## Risks\nNone.")
+            .is_empty());
+        assert_eq!(
+            format.validate("# Report\n## Risks\nNone.\n## Summary\nok"),
+            ["missing or out-of-order section `## Risks`"]
+        );
+        assert!(format
+            .correction_prompt(&["x".to_string()])
+            .contains("## Summary"));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::config::IdentityConfig;
+use crate::config::{ContinuationPromptConfig, IdentityConfig};
 use crate::identity;
 use crate::skills::Skill;
 use crate::tools::Tool;
@@ -9,6 +9,17 @@ use std::path::Path;
 
 const BOOTSTRAP_MAX_CHARS: usize = 20_000;
 
+/// Marker opening every prompt the task engine injects into the history.
+pub const TASK_ENGINE_PROMPT_PREFIX: &str = "[Task Engine]";
+
+/// Template keys accepted by `completion_policy.continuation_prompts.templates`.
+pub const CONTINUATION_PROMPT_TEMPLATES: &[&str] = &[
+    "write_claim_without_verification",
+    "missing_required_evidence",
+    "guardrail_notice",
+    "in_progress_update",
+];
+
 pub struct PromptContext<'a> {
     pub workspace_dir: &'a Path,
     pub model_name: &'a str,
@@ -240,6 +251,78 @@ fn inject_workspace_file(prompt: &mut String, workspace_dir: &Path, filename: &s
     }
 }
 
+/// Nudge appended to the history after a round that did not complete.
+///
+/// The template is picked from what held the task back: file claims nothing
+/// confirmed, unmet contract requirements, a guardrail notice, or a plain
+/// progress update. Unmet requirements are always named, even when a custom
+/// template leaves out `{requirements}`.
+pub fn continuation_prompt(
+    reason: &str,
+    missing_requirements: &[String],
+    unverified_claims: &[String],
+    config: &ContinuationPromptConfig,
+) -> String {
+    let english = config.language.trim().eq_ignore_ascii_case("en");
+    let key = if !unverified_claims.is_empty() {
+        "write_claim_without_verification"
+    } else if reason == "guardrail_notice" {
+        "guardrail_notice"
+    } else if !missing_requirements.is_empty() {
+        "missing_required_evidence"
+    } else {
+        "in_progress_update"
+    };
+    let template = config.templates.get(key).map_or_else(
+        || builtin_continuation_template(key, english),
+        String::as_str,
+    );
+
+    let separator = if english { ", " } else { "、" };
+    let requirements = missing_requirements.join(separator);
+    let mut body = template
+        .replace("{requirements}", &requirements)
+        .replace("{claims}", &unverified_claims.join(separator));
+    if !requirements.is_empty() && !template.contains("{requirements}") {
+        let line = if english {
+            format!("\nStill unmet: {requirements}.")
+        } else {
+            format!("\n尚未满足的要求：{requirements}。")
+        };
+        body.push_str(&line);
+    }
+    format!("{TASK_ENGINE_PROMPT_PREFIX}\n{}", body.trim())
+}
+
+fn builtin_continuation_template(key: &str, english: bool) -> &'static str {
+    match (key, english) {
+        ("write_claim_without_verification", false) => {
+            "任务尚未完成。你的回复声称已写入以下文件，但尚未验证：{claims}。请用 file_read 逐一确认它们存在且内容正确；如果尚未写入，请先写入。"
+        }
+        ("write_claim_without_verification", true) => {
+            "The task is not finished. Your reply says these files were written, but nothing has confirmed them yet: {claims}. Check each one with file_read; if a file was never written, write it first."
+        }
+        ("missing_required_evidence", false) => {
+            "任务尚未完成，仍缺少：{requirements}。请继续执行必要的工具操作并在有可验证结果后再给最终答复。"
+        }
+        ("missing_required_evidence", true) => {
+            "The task is not finished; still missing: {requirements}. Keep using the tools it needs and only give a final answer once the result can be verified."
+        }
+        ("guardrail_notice", false) => {
+            "任务尚未完成。上一轮触发了安全限制提示，请换用允许的方式继续完成任务，不要仅转述该提示。"
+        }
+        ("guardrail_notice", true) => {
+            "The task is not finished. The last round hit a guardrail notice; continue with an allowed approach instead of repeating the notice."
+        }
+        (_, false) => {
+            "任务尚未完成。请继续执行必要的工具操作并在有可验证结果后再给最终答复。不要仅汇报进行中状态。"
+        }
+        (_, true) => {
+            "The task is not finished. Keep using the tools it needs and only give a final answer once the result can be verified. Do not just report that work is in progress."
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -476,4 +559,38 @@ mod tests {
             "<instruction>Use &lt;tool_call&gt; and &amp; keep output &quot;safe&quot;</instruction>"
         ));
     }
+
+    #[test]
+    fn continuation_prompt_names_unmet_requirements_in_the_configured_language() {
+        let mut config = ContinuationPromptConfig {
+            language: "en".into(),
+            ..ContinuationPromptConfig::default()
+        };
+        let missing = vec!["evidence:file_write".to_string()];
+
+        let prompt = continuation_prompt("missing_required_evidence", &missing, &[], &config);
+        assert!(prompt.starts_with("[Task Engine]\nThe task is not finished"));
+        assert!(prompt.contains("evidence:file_write"));
+
+        let prompt = continuation_prompt("x", &[], &["notes/a.md".to_string()], &config);
+        assert!(prompt.contains("notes/a.md") && prompt.contains("file_read"));
+
+        config.templates.insert(
+            "guardrail_notice".into(),
+            "Pick a different approach.".into(),
+        );
+        let prompt = continuation_prompt("guardrail_notice", &missing, &[], &config);
+        assert_eq!(
+            prompt,
+            "[Task Engine]\nPick a different approach.\nStill unmet: evidence:file_write."
+        );
+
+        let prompt = continuation_prompt(
+            "unknown_contract_non_terminal_update",
+            &[],
+            &[],
+            &ContinuationPromptConfig::default(),
+        );
+        assert!(prompt.contains("任务尚未完成"));
+    }
 }
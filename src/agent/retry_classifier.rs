@@ -0,0 +1,200 @@
+use crate::providers::ProviderError;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Decides whether a failed task round is worth retrying against the same
+/// provider. Integrators whose gateways surface transient failures in unusual
+/// ways can inject their own implementation with
+/// [`crate::agent::task_engine::TaskEngine::with_retry_classifier`].
+pub trait RetryClassifier: Send + Sync {
+    fn is_retryable(&self, err: &anyhow::Error) -> bool;
+
+    /// How long to wait before retrying `err`. Defaults to the server's
+    /// `Retry-After` carried by a [`ProviderError`].
+    fn retry_after(&self, err: &anyhow::Error) -> Option<Duration> {
+        ProviderError::find(err).and_then(ProviderError::retry_after)
+    }
+}
+
+/// Built-in classification: typed [`ProviderError`]s by kind, HTTP status
+/// mapping for typed `reqwest` errors (408, 429, and 5xx are transient), and
+/// transport failures by message.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultRetryClassifier;
+
+const TRANSPORT_ERROR_HINTS: &[&str] = &[
+    "transport error",
+    "error sending request for url",
+    "connection reset",
+    "connection refused",
+    "timed out",
+];
+
+impl RetryClassifier for DefaultRetryClassifier {
+    fn is_retryable(&self, err: &anyhow::Error) -> bool {
+        if let Some(typed) = ProviderError::find(err) {
+            return typed.is_retryable();
+        }
+        if let Some(reqwest_err) = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        {
+            if reqwest_err.is_timeout() {
+                return true;
+            }
+            if let Some(status) = reqwest_err.status() {
+                return is_retryable_status(status.as_u16());
+            }
+        }
+        let lower = format!("{err:#}").to_ascii_lowercase();
+        TRANSPORT_ERROR_HINTS
+            .iter()
+            .any(|hint| lower.contains(hint))
+    }
+}
+
+fn is_retryable_status(code: u16) -> bool {
+    matches!(code, 408 | 429) || (500..600).contains(&code)
+}
+
+/// Extends another classifier with caller-declared retryable error substrings
+/// (matched case-insensitively) and HTTP status codes found in the message.
+pub struct PatternRetryClassifier {
+    patterns: Vec<String>,
+    status_codes: Vec<u16>,
+    fallback: Arc<dyn RetryClassifier>,
+}
+
+impl PatternRetryClassifier {
+    pub fn new(patterns: Vec<String>, status_codes: Vec<u16>) -> Self {
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(|pattern| pattern.trim().to_lowercase())
+                .filter(|pattern| !pattern.is_empty())
+                .collect(),
+            status_codes,
+            fallback: Arc::new(DefaultRetryClassifier),
+        }
+    }
+
+    /// Classifier consulted when no declared pattern matches.
+    #[must_use]
+    pub fn with_fallback(mut self, fallback: Arc<dyn RetryClassifier>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+}
+
+impl RetryClassifier for PatternRetryClassifier {
+    fn is_retryable(&self, err: &anyhow::Error) -> bool {
+        let message = format!("{err:#}");
+        let lower = message.to_lowercase();
+        if self.patterns.iter().any(|pattern| lower.contains(pattern)) {
+            return true;
+        }
+        let has_status = message
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|word| word.parse::<u16>().ok())
+            .any(|code| self.status_codes.contains(&code));
+        has_status || self.fallback.is_retryable(err)
+    }
+
+    fn retry_after(&self, err: &anyhow::Error) -> Option<Duration> {
+        self.fallback.retry_after(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_classifier_retries_transport_errors_only() {
+        let classifier = DefaultRetryClassifier;
+        let transport = anyhow::anyhow!(
+            "Custom native chat transport error: error sending request for url (https://x)"
+        );
+        assert!(classifier.is_retryable(&transport));
+        assert!(!classifier.is_retryable(&anyhow::anyhow!("invalid api key")));
+    }
+
+    #[test]
+    fn status_mapping_treats_throttling_and_server_errors_as_transient() {
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(503));
+        assert!(is_retryable_status(408));
+        assert!(!is_retryable_status(400));
+        assert!(!is_retryable_status(401));
+    }
+
+    #[test]
+    fn typed_provider_errors_are_classified_by_kind_and_carry_retry_after() {
+        use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+        use reqwest::StatusCode;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        let throttled: anyhow::Error = ProviderError::from_status(
+            "OpenAI",
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers,
+            "slow down",
+        )
+        .into();
+        let throttled = throttled.context("All providers/models failed");
+        let classifier = DefaultRetryClassifier;
+        assert!(classifier.is_retryable(&throttled));
+        assert_eq!(
+            classifier.retry_after(&throttled),
+            Some(Duration::from_secs(7))
+        );
+
+        let server: anyhow::Error =
+            ProviderError::from_status("OpenAI", StatusCode::BAD_GATEWAY, &HeaderMap::new(), "")
+                .into();
+        assert!(classifier.is_retryable(&server));
+        assert_eq!(classifier.retry_after(&server), None);
+
+        // Typed kinds win over message text that looks transient.
+        let auth: anyhow::Error = ProviderError::from_status(
+            "OpenAI",
+            StatusCode::UNAUTHORIZED,
+            &HeaderMap::new(),
+            "connection reset",
+        )
+        .into();
+        assert!(!classifier.is_retryable(&auth));
+
+        let now = chrono::DateTime::parse_from_rfc3339("2015-10-21T07:27:30Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            crate::providers::traits::parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn pattern_classifier_matches_declared_strings_and_codes() {
+        let classifier =
+            PatternRetryClassifier::new(vec!["Upstream Busy".into(), "  ".into()], vec![599]);
+        assert!(classifier.is_retryable(&anyhow::anyhow!("gateway: upstream busy, try later")));
+        assert!(classifier.is_retryable(&anyhow::anyhow!("gateway returned 599")));
+        assert!(classifier.is_retryable(&anyhow::anyhow!("connection reset by peer")));
+        assert!(!classifier.is_retryable(&anyhow::anyhow!("gateway returned 400")));
+    }
+
+    #[test]
+    fn pattern_classifier_uses_custom_fallback() {
+        struct Never;
+        impl RetryClassifier for Never {
+            fn is_retryable(&self, _err: &anyhow::Error) -> bool {
+                false
+            }
+        }
+        let classifier =
+            PatternRetryClassifier::new(Vec::new(), Vec::new()).with_fallback(Arc::new(Never));
+        assert!(!classifier.is_retryable(&anyhow::anyhow!("connection reset by peer")));
+    }
+}
@@ -0,0 +1,354 @@
+//! Cold storage for old task runs.
+//!
+//! Archiving (`archived_at`) only hides a task; its rows stay in the hot
+//! database. Once a terminal task is older than
+//! `autonomy.task_cold_storage_after_days`, its run, events (with payloads),
+//! artifact records, and operator notes are written to `state/archive/<id>.json.gz`, listed in
+//! `state/archive/index.jsonl`, and deleted from the database.
+//! `zeroclaw task show <id> --archived` reads them back.
+
+use crate::agent::task_store::TaskStore;
+use crate::agent::task_types::TaskRunRecord;
+use crate::config::Config;
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Format version written into every bundle.
+const ARCHIVE_BUNDLE_VERSION: u32 = 1;
+
+const ARCHIVE_INDEX_FILE: &str = "index.jsonl";
+
+/// One line of `state/archive/index.jsonl`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveIndexEntry {
+    pub id: String,
+    pub channel: String,
+    pub sender_key: String,
+    pub status: String,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    pub archived_at: String,
+    /// Bundle file name inside the archive directory.
+    pub file: String,
+}
+
+pub struct TaskArchive {
+    dir: PathBuf,
+}
+
+impl TaskArchive {
+    pub fn new(workspace_dir: &Path) -> Self {
+        Self {
+            dir: workspace_dir.join("state").join("archive"),
+        }
+    }
+
+    /// Move terminal tasks finished more than `max_age` ago out of the hot
+    /// database. Returns the number of tasks moved.
+    pub fn archive_tasks_older_than(&self, store: &TaskStore, max_age: Duration) -> Result<usize> {
+        let candidates = store.list_terminal_tasks_older_than(max_age)?;
+        if candidates.is_empty() {
+            return Ok(0);
+        }
+        std::fs::create_dir_all(&self.dir).with_context(|| {
+            format!("Failed to create task archive dir: {}", self.dir.display())
+        })?;
+
+        for run in &candidates {
+            let bundle = task_bundle(store, run)?;
+            let file = format!("{}.json.gz", file_stem(&run.id));
+            self.write_bundle(&file, &bundle)?;
+            self.append_index(&ArchiveIndexEntry {
+                id: run.id.clone(),
+                channel: run.channel.clone(),
+                sender_key: run.sender_key.clone(),
+                status: run.status.as_str().to_string(),
+                created_at: run.created_at.clone(),
+                completed_at: run.completed_at.clone(),
+                archived_at: chrono::Utc::now().to_rfc3339(),
+                file,
+            })?;
+            // Delete only after the bundle and its index line are on disk.
+            store.delete_task_run(&run.id)?;
+            let _ = crate::agent::workspace_transaction::discard(store.workspace_dir(), &run.id);
+        }
+        Ok(candidates.len())
+    }
+
+    /// Index entries, oldest first. A task archived twice keeps its last entry.
+    pub fn index(&self) -> Result<Vec<ArchiveIndexEntry>> {
+        let path = self.dir.join(ARCHIVE_INDEX_FILE);
+        let file = match std::fs::File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to open archive index: {}", path.display()))
+            }
+        };
+        let mut entries: Vec<ArchiveIndexEntry> = Vec::new();
+        for line in std::io::BufReader::new(file).lines() {
+            let line = line?;
+            let Ok(entry) = serde_json::from_str::<ArchiveIndexEntry>(&line) else {
+                continue;
+            };
+            entries.retain(|existing| existing.id != entry.id);
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Full bundle of an archived task, or `None` when it is not archived.
+    pub fn load(&self, task_id: &str) -> Result<Option<Value>> {
+        let Some(entry) = self.index()?.into_iter().find(|entry| entry.id == task_id) else {
+            return Ok(None);
+        };
+        let path = self.dir.join(&entry.file);
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("Failed to open task archive: {}", path.display()))?;
+        let bundle = serde_json::from_reader(GzDecoder::new(file))
+            .with_context(|| format!("Failed to read task archive: {}", path.display()))?;
+        Ok(Some(bundle))
+    }
+
+    fn write_bundle(&self, file: &str, bundle: &Value) -> Result<()> {
+        let path = self.dir.join(file);
+        let tmp = self.dir.join(format!("{file}.tmp"));
+        let mut encoder = GzEncoder::new(
+            std::fs::File::create(&tmp)
+                .with_context(|| format!("Failed to create {}", tmp.display()))?,
+            Compression::default(),
+        );
+        serde_json::to_writer(&mut encoder, bundle)?;
+        encoder.finish()?.sync_all()?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to write task archive: {}", path.display()))
+    }
+
+    fn append_index(&self, entry: &ArchiveIndexEntry) -> Result<()> {
+        let path = self.dir.join(ARCHIVE_INDEX_FILE);
+        let mut index = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open archive index: {}", path.display()))?;
+        writeln!(index, "{}", serde_json::to_string(entry)?)?;
+        index.sync_all()?;
+        Ok(())
+    }
+}
+
+/// Everything the store holds for `run` as one JSON document.
+pub fn task_bundle(store: &TaskStore, run: &TaskRunRecord) -> Result<Value> {
+    let events: Vec<Value> = store
+        .list_events(&run.id)?
+        .into_iter()
+        .map(|event| {
+            let payload = event
+                .payload_json
+                .map(|raw| serde_json::from_str::<Value>(&raw).unwrap_or(Value::String(raw)));
+            serde_json::json!({
+                "id": event.id,
+                "event_type": event.event_type,
+                "payload": payload,
+                "created_at": event.created_at,
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "version": ARCHIVE_BUNDLE_VERSION,
+        "run": {
+            "id": run.id,
+            "channel": run.channel,
+            "sender_key": run.sender_key,
+            "reply_target": run.reply_target,
+            "status": run.status.as_str(),
+            "original_request": run.original_request,
+            "last_response": run.last_response,
+            "attempt_count": run.attempt_count,
+            "provider_retry_count": run.provider_retry_count,
+            "created_at": run.created_at,
+            "updated_at": run.updated_at,
+            "completed_at": run.completed_at,
+            "archived_at": run.archived_at,
+        },
+        "events": events,
+        "artifacts": store.list_artifacts(&run.id)?,
+        "notes": store.list_task_notes(&run.id)?,
+        "steps": store.list_task_steps(&run.id)?,
+    }))
+}
+
+/// Task ids are UUIDs in practice; anything else is made safe for a file name.
+fn file_stem(task_id: &str) -> String {
+    task_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+pub fn handle_command(command: crate::TaskCommands, config: &Config) -> Result<()> {
+    let store = TaskStore::new(&config.workspace_dir)?;
+    let archive = TaskArchive::new(&config.workspace_dir);
+    match command {
+        crate::TaskCommands::Show { id, archived } => {
+            let bundle = if archived {
+                archive.load(&id)?
+            } else {
+                store
+                    .get_task_run(&id)?
+                    .map(|run| task_bundle(&store, &run))
+                    .transpose()?
+            };
+            let Some(bundle) = bundle else {
+                let hint = if archived {
+                    ""
+                } else {
+                    " (use --archived for tasks moved to cold storage)"
+                };
+                anyhow::bail!("Task run '{id}' not found{hint}");
+            };
+            println!("{}", serde_json::to_string_pretty(&bundle)?);
+            Ok(())
+        }
+        crate::TaskCommands::Note { id, text, author } => {
+            let author = author
+                .or_else(|| std::env::var("USER").ok())
+                .filter(|author| !author.trim().is_empty())
+                .unwrap_or_else(|| "operator".to_string());
+            let note = store.add_task_note(&id, author.trim(), text.trim())?;
+            println!("Added note #{} to task {id}.", note.id);
+            Ok(())
+        }
+        crate::TaskCommands::Rollback { id } => {
+            let engine = crate::agent::task_engine::TaskEngine::default_for_workspace(
+                &config.workspace_dir,
+            )?;
+            let report = engine.rollback(&id)?;
+            for path in &report.restored {
+                println!("restored  {path}");
+            }
+            for path in &report.removed {
+                println!("removed   {path}");
+            }
+            println!(
+                "Rolled back task {id}: {} file(s) restored, {} removed.",
+                report.restored.len(),
+                report.removed.len()
+            );
+            Ok(())
+        }
+        crate::TaskCommands::Blocked => {
+            let inbox = crate::agent::task_blocked::blocked_inbox(&store)?;
+            if inbox.is_empty() {
+                println!("No blocked task runs.");
+            }
+            for entry in inbox {
+                let resolution = &entry.resolution;
+                println!(
+                    "{} [{}] {}/{} since {}",
+                    entry.task_id,
+                    resolution.kind.as_str(),
+                    entry.channel,
+                    entry.sender_key,
+                    entry.blocked_at
+                );
+                println!("  request:     {}", entry.original_request.trim());
+                println!("  reason:      {}", resolution.reason);
+                if let Some(key) = resolution.config_key.as_deref() {
+                    println!("  config:      {key}");
+                }
+                println!(
+                    "  reply:       \"{}\" to resume, \"{}\" to cancel",
+                    resolution.resume_reply, resolution.cancel_reply
+                );
+            }
+            Ok(())
+        }
+        crate::TaskCommands::Archive { older_than_days } => {
+            let days = older_than_days.unwrap_or(config.autonomy.task_cold_storage_after_days);
+            let max_age = Duration::from_secs(days.saturating_mul(24 * 60 * 60));
+            let moved = archive.archive_tasks_older_than(&store, max_age)?;
+            println!("Moved {moved} task run(s) older than {days}d to cold storage.");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::task_types::TaskStatus;
+    use tempfile::TempDir;
+
+    #[test]
+    fn cold_storage_moves_old_terminal_tasks_out_of_the_database() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        for id in ["done", "running"] {
+            store
+                .insert_task_run(id, "imessage", "sender-1", "sender-1", "write the report")
+                .expect("insert");
+            store.update_status(id, TaskStatus::Running).expect("run");
+        }
+        store
+            .update_status("done", TaskStatus::Completed)
+            .expect("complete");
+        store
+            .append_event(
+                "done",
+                "completed",
+                Some(&serde_json::json!({"reason": "ok"})),
+            )
+            .expect("event");
+        store
+            .upsert_artifact_verification("done", "report.md", Some("abc"), true)
+            .expect("artifact");
+        store
+            .add_task_note("done", "oncall", "Provider outage, rerun tomorrow")
+            .expect("note");
+        assert!(store.add_task_note("missing", "oncall", "x").is_err());
+
+        let archive = TaskArchive::new(tmp.path());
+        assert_eq!(
+            archive
+                .archive_tasks_older_than(&store, Duration::from_secs(3600))
+                .expect("sweep"),
+            0
+        );
+        assert_eq!(
+            archive
+                .archive_tasks_older_than(&store, Duration::ZERO)
+                .expect("sweep"),
+            1
+        );
+
+        assert!(store.get_task_run("done").expect("get").is_none());
+        assert!(store.list_events("done").expect("events").is_empty());
+        assert!(store.get_task_run("running").expect("get").is_some());
+
+        let index = archive.index().expect("index");
+        assert_eq!(index.len(), 1);
+        assert_eq!(index[0].status, "completed");
+        let bundle = archive.load("done").expect("load").expect("archived");
+        assert_eq!(bundle["run"]["original_request"], "write the report");
+        assert_eq!(bundle["events"][0]["payload"]["reason"], "ok");
+        assert_eq!(bundle["artifacts"][0]["path"], "report.md");
+        assert_eq!(bundle["notes"][0]["author"], "oncall");
+        assert!(store.list_task_notes("done").expect("notes").is_empty());
+        assert!(archive.load("running").expect("load").is_none());
+    }
+}
@@ -0,0 +1,343 @@
+//! Structured reasons for task runs that end `Blocked`.
+//!
+//! The engine stores a [`BlockedResolution`] in each [`BLOCKED_EVENT`]: what
+//! kind of block it is (approval, quota, missing credential, workspace access)
+//! and what unblocks it — the reply to send in the task's conversation and,
+//! where one applies, the config key to change first. Status replies, the
+//! blocked-task inbox (`zeroclaw task blocked`, `GET /api/tasks/blocked`), and
+//! the engine's progress notification all read it from there.
+//!
+//! Tool calls held back by `[autonomy.tool_policy]` block the task with a
+//! [`TOOL_CONFIRMATION_EVENT`] naming the call; resuming the task approves it.
+
+use crate::agent::task_store::TaskStore;
+use crate::agent::task_types::{TaskEventRecord, TaskStatus};
+use crate::security::tool_policy::ConfirmationRequired;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Event recording why a run was blocked, with its [`BlockedResolution`].
+pub const BLOCKED_EVENT: &str = "blocked";
+
+/// Typed reply that resumes a blocked task (see `channels::task_reply`).
+pub const RESUME_REPLY: &str = "continue";
+
+/// Typed reply that cancels a blocked task.
+pub const CANCEL_REPLY: &str = "cancel";
+
+/// Event recording a tool call that waits for the user's approval; also the
+/// reason of the block it causes.
+pub const TOOL_CONFIRMATION_EVENT: &str = "tool_confirmation_required";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockedKind {
+    /// The run needs the user to approve an action.
+    Approval,
+    /// The task budget or a provider quota ran out.
+    Quota,
+    /// A tool or provider is missing an API key or login.
+    MissingCredential,
+    /// A path outside the allowed workspace roots was needed.
+    WorkspaceAccess,
+    Other,
+}
+
+impl BlockedKind {
+    /// Kind of a block from the engine's reason string.
+    pub fn classify(reason: &str) -> Self {
+        let reason = reason.to_lowercase();
+        if reason == "workspace_access_denied" {
+            Self::WorkspaceAccess
+        } else if reason.contains("budget") || reason.contains("quota") {
+            Self::Quota
+        } else if ["credential", "api_key", "api key", "token", "unauthorized"]
+            .iter()
+            .any(|needle| reason.contains(needle))
+        {
+            Self::MissingCredential
+        } else if reason.contains("approv") || reason.contains("confirm") {
+            Self::Approval
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Approval => "approval",
+            Self::Quota => "quota",
+            Self::MissingCredential => "missing_credential",
+            Self::WorkspaceAccess => "workspace_access",
+            Self::Other => "other",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Approval => "等待批准",
+            Self::Quota => "预算或配额用尽",
+            Self::MissingCredential => "缺少凭据",
+            Self::WorkspaceAccess => "超出可访问目录",
+            Self::Other => "需要处理",
+        }
+    }
+
+    /// English counterpart of [`label`](Self::label).
+    pub fn label_en(self) -> &'static str {
+        match self {
+            Self::Approval => "awaiting approval",
+            Self::Quota => "budget or quota used up",
+            Self::MissingCredential => "missing credentials",
+            Self::WorkspaceAccess => "outside the accessible directories",
+            Self::Other => "needs attention",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockedResolution {
+    pub kind: BlockedKind,
+    pub reason: String,
+    pub remediation: String,
+    /// Config key to change before resuming, when the block needs one.
+    pub config_key: Option<String>,
+    /// Reply that resumes the task once the cause is dealt with.
+    pub resume_reply: String,
+    /// Reply that gives the task up.
+    pub cancel_reply: String,
+}
+
+impl BlockedResolution {
+    pub fn new(reason: &str, remediation: &str) -> Self {
+        let kind = BlockedKind::classify(reason);
+        let config_key = match kind {
+            BlockedKind::WorkspaceAccess => Some("autonomy.allowed_roots"),
+            _ => None,
+        };
+        Self {
+            kind,
+            reason: reason.to_string(),
+            remediation: remediation.to_string(),
+            config_key: config_key.map(str::to_string),
+            resume_reply: RESUME_REPLY.to_string(),
+            cancel_reply: CANCEL_REPLY.to_string(),
+        }
+    }
+
+    /// Resolution stored in a [`BLOCKED_EVENT`]; events written before the
+    /// structured fields existed are classified from their reason.
+    pub fn from_event(event: &TaskEventRecord) -> Option<Self> {
+        if event.event_type != BLOCKED_EVENT {
+            return None;
+        }
+        let payload: serde_json::Value =
+            serde_json::from_str(event.payload_json.as_deref()?).ok()?;
+        serde_json::from_value(payload.clone()).ok().or_else(|| {
+            let field = |key: &str| payload.get(key).and_then(|v| v.as_str()).unwrap_or("");
+            Some(Self::new(field("reason"), field("remediation")))
+        })
+    }
+
+    /// Payload of the [`BLOCKED_EVENT`] for this resolution.
+    pub fn event_payload(&self, round: usize) -> serde_json::Value {
+        let mut payload = serde_json::to_value(self).unwrap_or_default();
+        payload["round"] = serde_json::json!(round);
+        payload
+    }
+
+    /// One-line instruction telling the user what unblocks the task.
+    pub fn hint(&self) -> String {
+        let resume = &self.resume_reply;
+        let cancel = &self.cancel_reply;
+        match (self.kind, self.config_key.as_deref()) {
+            (BlockedKind::Approval, _) => {
+                format!("回复“{resume}”批准继续，或回复“{cancel}”拒绝。")
+            }
+            (BlockedKind::Quota, _) => {
+                format!("回复“{resume}”再给一份预算，或回复“{cancel}”结束任务。")
+            }
+            (BlockedKind::MissingCredential, _) => {
+                format!("配置所需的 API key 或登录后回复“{resume}”，或回复“{cancel}”放弃。")
+            }
+            (_, Some(key)) => {
+                format!("在配置 `{key}` 中补充后回复“{resume}”，或回复“{cancel}”放弃。")
+            }
+            _ => format!("处理后回复“{resume}”，或回复“{cancel}”放弃。"),
+        }
+    }
+
+    /// English counterpart of [`hint`](Self::hint).
+    pub fn hint_en(&self) -> String {
+        let resume = &self.resume_reply;
+        let cancel = &self.cancel_reply;
+        match (self.kind, self.config_key.as_deref()) {
+            (BlockedKind::Approval, _) => {
+                format!("Reply \"{resume}\" to approve, or \"{cancel}\" to decline.")
+            }
+            (BlockedKind::Quota, _) => format!(
+                "Reply \"{resume}\" to grant another budget, or \"{cancel}\" to end the task."
+            ),
+            (BlockedKind::MissingCredential, _) => format!(
+                "Configure the API key or log in, then reply \"{resume}\"; reply \"{cancel}\" to give up."
+            ),
+            (_, Some(key)) => format!(
+                "Update `{key}` in the config, then reply \"{resume}\"; reply \"{cancel}\" to give up."
+            ),
+            _ => format!("Reply \"{resume}\" once it is handled, or \"{cancel}\" to give up."),
+        }
+    }
+}
+
+/// Payload of the [`TOOL_CONFIRMATION_EVENT`] for `confirmation`.
+pub fn tool_confirmation_payload(confirmation: &ConfirmationRequired) -> serde_json::Value {
+    serde_json::json!({
+        "tool": confirmation.tool,
+        "arguments_hash": confirmation.arguments_hash,
+        "summary": confirmation.summary,
+        "reason": confirmation.reason,
+    })
+}
+
+fn tool_confirmations(
+    events: &[TaskEventRecord],
+) -> impl DoubleEndedIterator<Item = serde_json::Value> + '_ {
+    events
+        .iter()
+        .filter(|event| event.event_type == TOOL_CONFIRMATION_EVENT)
+        .filter_map(|event| serde_json::from_str(event.payload_json.as_deref()?).ok())
+}
+
+/// Argument hashes of the tool calls a task has asked approval for. The
+/// task is only run again once the user resumes it, which approves them.
+pub fn approved_tool_calls(events: &[TaskEventRecord]) -> HashSet<String> {
+    tool_confirmations(events)
+        .filter_map(|payload| Some(payload.get("arguments_hash")?.as_str()?.to_string()))
+        .collect()
+}
+
+/// Message telling the model the user approved the call a blocked task was
+/// waiting on, so it issues the call again.
+pub fn tool_approval_message(events: &[TaskEventRecord]) -> Option<String> {
+    let blocked = events
+        .iter()
+        .rev()
+        .find_map(BlockedResolution::from_event)?;
+    if blocked.reason != TOOL_CONFIRMATION_EVENT {
+        return None;
+    }
+    let payload = tool_confirmations(events).next_back()?;
+    let field = |key: &str| payload.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    Some(format!(
+        "The user approved the `{}` call ({}). Make that call again now and continue the task.",
+        field("tool"),
+        field("summary")
+    ))
+}
+
+/// A blocked task and what unblocks it, as listed in the blocked-task inbox.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockedTaskEntry {
+    pub task_id: String,
+    pub channel: String,
+    pub sender_key: String,
+    pub original_request: String,
+    pub blocked_at: String,
+    pub resolution: BlockedResolution,
+}
+
+/// Every blocked task in the store, oldest first.
+pub fn blocked_inbox(store: &TaskStore) -> Result<Vec<BlockedTaskEntry>> {
+    store
+        .list_tasks_with_status(TaskStatus::Blocked)?
+        .into_iter()
+        .map(|task| {
+            let event = store.latest_event_of_type(&task.id, BLOCKED_EVENT)?;
+            let resolution = event
+                .as_ref()
+                .and_then(BlockedResolution::from_event)
+                .unwrap_or_else(|| BlockedResolution::new("unknown", ""));
+            Ok(BlockedTaskEntry {
+                blocked_at: event.map_or_else(|| task.updated_at.clone(), |e| e.created_at),
+                task_id: task.id,
+                channel: task.channel,
+                sender_key: task.sender_key,
+                original_request: task.original_request,
+                resolution,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn blocked_reasons_classify_and_carry_a_resolution_hint() {
+        let access = BlockedResolution::new(
+            "workspace_access_denied",
+            "Add the target path to `autonomy.allowed_roots`",
+        );
+        assert_eq!(access.kind, BlockedKind::WorkspaceAccess);
+        assert_eq!(access.config_key.as_deref(), Some("autonomy.allowed_roots"));
+        assert!(access.hint().contains("autonomy.allowed_roots"));
+
+        let budget = BlockedResolution::new("budget_exceeded", "");
+        assert_eq!(budget.kind, BlockedKind::Quota);
+        assert!(budget.config_key.is_none());
+        assert!(budget.hint().contains("continue"));
+
+        assert_eq!(
+            BlockedKind::classify("missing API key for email tool"),
+            BlockedKind::MissingCredential
+        );
+        assert_eq!(
+            BlockedKind::classify("needs user approval"),
+            BlockedKind::Approval
+        );
+        assert_eq!(BlockedKind::classify("something else"), BlockedKind::Other);
+        assert_eq!(
+            BlockedKind::classify(TOOL_CONFIRMATION_EVENT),
+            BlockedKind::Approval
+        );
+    }
+
+    #[test]
+    fn inbox_lists_blocked_tasks_with_their_stored_resolution() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        for id in ["new", "legacy", "running"] {
+            store
+                .insert_task_run(id, "imessage", "sender-1", "sender-1", "clean up logs")
+                .expect("insert");
+        }
+        store.update_status("new", TaskStatus::Blocked).unwrap();
+        let resolution = BlockedResolution::new("budget_exceeded", "spent 10k tokens");
+        store
+            .append_event("new", BLOCKED_EVENT, Some(&resolution.event_payload(2)))
+            .unwrap();
+        store.update_status("legacy", TaskStatus::Blocked).unwrap();
+        store
+            .append_event(
+                "legacy",
+                BLOCKED_EVENT,
+                Some(&serde_json::json!({
+                    "reason": "workspace_access_denied",
+                    "remediation": "add the path",
+                    "round": 1
+                })),
+            )
+            .unwrap();
+
+        let inbox = blocked_inbox(&store).expect("inbox");
+        assert_eq!(inbox.len(), 2);
+        let new = inbox.iter().find(|e| e.task_id == "new").unwrap();
+        assert_eq!(new.resolution, resolution);
+        let legacy = inbox.iter().find(|e| e.task_id == "legacy").unwrap();
+        assert_eq!(legacy.resolution.kind, BlockedKind::WorkspaceAccess);
+        assert_eq!(legacy.resolution.remediation, "add the path");
+    }
+}
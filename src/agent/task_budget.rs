@@ -0,0 +1,215 @@
+//! Spending tracked against `autonomy.task_budget` during one task run.
+//!
+//! Token counts come from provider-reported usage, so providers that report
+//! none only count toward the tool-call limit. Cost is estimated with the
+//! `[cost.prices]` table; models without a price add no cost.
+
+use crate::agent::task_types::TaskUsageTotals;
+use crate::channels::task_status::ReplyLanguage;
+use crate::config::{ModelPricing, TaskBudget};
+use crate::cost::TokenUsage;
+use std::collections::HashMap;
+
+/// Event logged when a run stops on its budget.
+pub const BUDGET_EXCEEDED_EVENT: &str = "budget_exceeded";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskSpend {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    pub tool_calls: usize,
+}
+
+impl TaskSpend {
+    /// Add one round's usage, priced by `model` (looked up bare and as
+    /// `provider/model`). Returns the round's cost, or `None` when the model
+    /// has no price.
+    pub fn record_tokens(
+        &mut self,
+        provider: &str,
+        model: &str,
+        input_tokens: u64,
+        output_tokens: u64,
+        prices: &HashMap<String, ModelPricing>,
+    ) -> Option<f64> {
+        self.input_tokens = self.input_tokens.saturating_add(input_tokens);
+        self.output_tokens = self.output_tokens.saturating_add(output_tokens);
+        let price = prices
+            .get(model)
+            .or_else(|| prices.get(&format!("{provider}/{model}")))?;
+        let cost = TokenUsage::new(
+            model,
+            input_tokens,
+            output_tokens,
+            price.input,
+            price.output,
+        )
+        .cost();
+        self.cost_usd += cost;
+        Some(cost)
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens.saturating_add(self.output_tokens)
+    }
+
+    /// Limits in `budget` this spend has reached, e.g. `["max_tokens"]`.
+    pub fn exceeded_limits(&self, budget: &TaskBudget) -> Vec<&'static str> {
+        let mut exceeded = Vec::new();
+        if budget.max_tokens > 0 && self.total_tokens() >= budget.max_tokens {
+            exceeded.push("max_tokens");
+        }
+        if budget.max_cost_usd > 0.0 && self.cost_usd >= budget.max_cost_usd {
+            exceeded.push("max_cost_usd");
+        }
+        if budget.max_tool_calls > 0 && self.tool_calls >= budget.max_tool_calls {
+            exceeded.push("max_tool_calls");
+        }
+        exceeded
+    }
+
+    /// Payload of the [`BUDGET_EXCEEDED_EVENT`].
+    pub fn event_payload(&self, budget: &TaskBudget, exceeded: &[&str]) -> serde_json::Value {
+        serde_json::json!({
+            "exceeded": exceeded,
+            "used": {
+                "tokens": self.total_tokens(),
+                "cost_usd": self.cost_usd,
+                "tool_calls": self.tool_calls,
+            },
+            "budget": budget,
+        })
+    }
+
+    /// Question sent to the sender when the run stops on its budget.
+    pub fn remediation(&self, language: ReplyLanguage) -> String {
+        match language {
+            ReplyLanguage::Chinese => format!(
+                "已使用 {} tokens、约 ${:.4}、{} 次工具调用，达到任务预算上限。回复“继续”再给一份预算继续执行，或回复“取消”结束任务。",
+                self.total_tokens(),
+                self.cost_usd,
+                self.tool_calls
+            ),
+            ReplyLanguage::English => format!(
+                "Used {} tokens, about ${:.4}, and {} tool calls, reaching the task budget. Reply \"continue\" to run on with a fresh budget, or \"cancel\" to stop the task.",
+                self.total_tokens(),
+                self.cost_usd,
+                self.tool_calls
+            ),
+        }
+    }
+}
+
+/// Line appended to a completed task's reply with `cost.report_cost`, in
+/// the language of the request; `None` when no provider reported usage.
+pub fn usage_report(totals: &TaskUsageTotals, language: ReplyLanguage) -> Option<String> {
+    let tokens = totals.input_tokens.saturating_add(totals.output_tokens);
+    if tokens == 0 {
+        return None;
+    }
+    let (input, output) = (totals.input_tokens, totals.output_tokens);
+    Some(match (totals.cost_usd, language) {
+        (Some(cost), ReplyLanguage::Chinese) => {
+            format!("💰 本次任务约 ${cost:.4}（输入 {input} + 输出 {output} tokens）")
+        }
+        (None, ReplyLanguage::Chinese) => {
+            format!("💰 本次任务用量：输入 {input} + 输出 {output} tokens（模型未配置价格）")
+        }
+        (Some(cost), ReplyLanguage::English) => {
+            format!("💰 This task cost about ${cost:.4} ({input} input + {output} output tokens)")
+        }
+        (None, ReplyLanguage::English) => format!(
+            "💰 This task used {input} input + {output} output tokens (no price configured for the model)"
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spend_reaches_each_configured_limit() {
+        let prices = HashMap::from([(
+            "openai/gpt-4o".to_string(),
+            ModelPricing {
+                input: 5.0,
+                output: 15.0,
+            },
+        )]);
+        let mut spend = TaskSpend::default();
+        let priced = spend.record_tokens("openai", "gpt-4o", 100_000, 20_000, &prices);
+        assert!((priced.unwrap() - 0.8).abs() < 1e-9);
+        assert_eq!(
+            spend.record_tokens("ollama", "llama3", 5_000, 0, &prices),
+            None
+        );
+        spend.tool_calls = 3;
+        assert_eq!(spend.total_tokens(), 125_000);
+        assert!((spend.cost_usd - 0.8).abs() < 1e-9);
+
+        assert!(spend.exceeded_limits(&TaskBudget::default()).is_empty());
+        let budget = TaskBudget {
+            max_tokens: 200_000,
+            max_cost_usd: 0.5,
+            max_tool_calls: 3,
+        };
+        assert_eq!(
+            spend.exceeded_limits(&budget),
+            vec!["max_cost_usd", "max_tool_calls"]
+        );
+        let payload = spend.event_payload(&budget, &spend.exceeded_limits(&budget));
+        assert_eq!(payload["used"]["tool_calls"], 3);
+        assert_eq!(payload["budget"]["max_tokens"], 200_000);
+    }
+
+    #[test]
+    fn usage_report_shows_cost_only_when_priced() {
+        assert_eq!(
+            usage_report(&TaskUsageTotals::default(), ReplyLanguage::Chinese),
+            None
+        );
+        let priced = TaskUsageTotals {
+            input_tokens: 1_200,
+            output_tokens: 300,
+            cost_usd: Some(0.012_34),
+        };
+        assert_eq!(
+            usage_report(&priced, ReplyLanguage::Chinese).unwrap(),
+            "💰 本次任务约 $0.0123（输入 1200 + 输出 300 tokens）"
+        );
+        let unpriced = TaskUsageTotals {
+            cost_usd: None,
+            ..priced
+        };
+        assert!(usage_report(&unpriced, ReplyLanguage::Chinese)
+            .unwrap()
+            .contains("模型未配置价格"));
+    }
+
+    #[test]
+    fn budget_replies_follow_the_request_language() {
+        let totals = TaskUsageTotals {
+            input_tokens: 1_200,
+            output_tokens: 300,
+            cost_usd: Some(0.012_34),
+        };
+        assert_eq!(
+            usage_report(&totals, ReplyLanguage::English).unwrap(),
+            "💰 This task cost about $0.0123 (1200 input + 300 output tokens)"
+        );
+        let spend = TaskSpend {
+            input_tokens: 900,
+            output_tokens: 100,
+            cost_usd: 0.5,
+            tool_calls: 4,
+        };
+        let english = spend.remediation(ReplyLanguage::English);
+        assert!(english.starts_with("Used 1000 tokens, about $0.5000, and 4 tool calls"));
+        assert!(english.contains("\"continue\""));
+        assert!(spend
+            .remediation(ReplyLanguage::Chinese)
+            .contains("回复“继续”"));
+    }
+}
@@ -0,0 +1,198 @@
+//! Source tracking for research-style task runs.
+//!
+//! `web_fetch` and `http_request` record each page they fetched successfully
+//! as `sources` result metadata, and `web_search_tool` records its hits as
+//! `search_results`. The engine collects those from a run's tool
+//! invocations, stores them as a `citations` event, and appends a numbered
+//! sources list to the final reply.
+
+use crate::agent::task_types::ToolInvocation;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Event type holding the sources gathered for a task.
+pub const CITATIONS_EVENT: &str = "citations";
+
+/// Upper bound on listed sources so long research runs stay readable.
+const MAX_CITATIONS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Citation {
+    pub url: String,
+    pub title: Option<String>,
+    /// Tool that surfaced the source (`http_request`, `web_fetch`, or
+    /// `web_search_tool`).
+    pub tool: String,
+}
+
+/// Sources recorded by successful `invocations`: fetched pages first, then
+/// search hits, deduplicated by URL.
+pub fn collect_citations(invocations: &[ToolInvocation]) -> Vec<Citation> {
+    let mut fetched = Vec::new();
+    let mut searched = Vec::new();
+    for invocation in invocations.iter().filter(|inv| inv.status().is_ok()) {
+        fetched.extend(recorded_sources(invocation, "sources"));
+        searched.extend(recorded_sources(invocation, "search_results"));
+    }
+
+    let mut citations: Vec<Citation> = Vec::new();
+    for citation in fetched.into_iter().chain(searched) {
+        if citations.len() >= MAX_CITATIONS {
+            break;
+        }
+        if !citations.iter().any(|seen| seen.url == citation.url) {
+            citations.push(citation);
+        }
+    }
+    citations
+}
+
+/// `{url, title}` entries under `key` in the invocation's result metadata.
+fn recorded_sources<'a>(
+    invocation: &'a ToolInvocation,
+    key: &str,
+) -> impl Iterator<Item = Citation> + 'a {
+    invocation
+        .metadata
+        .get(key)
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(move |entry| {
+            let url = entry.get("url").and_then(serde_json::Value::as_str)?;
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return None;
+            }
+            let title = entry
+                .get("title")
+                .and_then(serde_json::Value::as_str)
+                .map(str::trim)
+                .filter(|title| !title.is_empty());
+            Some(Citation {
+                url: url.to_string(),
+                title: title.map(ToString::to_string),
+                tool: invocation.name.clone(),
+            })
+        })
+}
+
+/// Append a numbered sources list for citations the reply does not already
+/// link to.
+pub fn append_sources(response: &str, citations: &[Citation]) -> String {
+    let missing: Vec<&Citation> = citations
+        .iter()
+        .filter(|citation| !response.contains(&citation.url))
+        .collect();
+    if missing.is_empty() {
+        return response.to_string();
+    }
+
+    let mut out = format!("{}\n\nSources:", response.trim_end());
+    for (index, citation) in missing.iter().enumerate() {
+        match citation.title.as_deref() {
+            Some(title) => {
+                let _ = write!(out, "\n{}. {title} — {}", index + 1, citation.url);
+            }
+            None => {
+                let _ = write!(out, "\n{}. {}", index + 1, citation.url);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{ToolKind, ToolStatus};
+
+    fn invocation(name: &str, status: ToolStatus, metadata: serde_json::Value) -> ToolInvocation {
+        ToolInvocation {
+            name: name.to_string(),
+            kind: ToolKind::Network,
+            arguments: serde_json::json!({}),
+            success: status.is_ok(),
+            status: Some(status),
+            output: String::new(),
+            iteration: 1,
+            metadata: metadata.as_object().cloned().unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn collects_fetched_pages_before_search_hits() {
+        let invocations = vec![
+            invocation(
+                "web_search_tool",
+                ToolStatus::Ok,
+                serde_json::json!({"search_results": [
+                    {"url": "https://blog.rust-lang.org/2025/02/20/Rust-1.85.0.html", "title": "Rust 2024 edition"},
+                    {"url": "https://doc.rust-lang.org/edition-guide/", "title": "Edition guide"}
+                ]}),
+            ),
+            invocation(
+                "http_request",
+                ToolStatus::Ok,
+                serde_json::json!({"sources": [{"url": "https://doc.rust-lang.org/edition-guide/"}]}),
+            ),
+            invocation(
+                "http_request",
+                ToolStatus::Error,
+                serde_json::json!({"sources": [{"url": "https://example.com/missing"}]}),
+            ),
+        ];
+
+        let citations = collect_citations(&invocations);
+        let urls: Vec<&str> = citations.iter().map(|c| c.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://doc.rust-lang.org/edition-guide/",
+                "https://blog.rust-lang.org/2025/02/20/Rust-1.85.0.html",
+            ]
+        );
+        assert_eq!(citations[0].tool, "http_request");
+        assert_eq!(citations[1].title.as_deref(), Some("Rust 2024 edition"));
+    }
+
+    #[test]
+    fn fetched_pages_keep_their_title() {
+        let invocations = vec![invocation(
+            "web_fetch",
+            ToolStatus::Ok,
+            serde_json::json!({"sources": [{"url": "https://example.com/post", "title": "Release notes"}]}),
+        )];
+        let citations = collect_citations(&invocations);
+        assert_eq!(citations.len(), 1);
+        assert_eq!(citations[0].tool, "web_fetch");
+        assert_eq!(citations[0].title.as_deref(), Some("Release notes"));
+    }
+
+    #[test]
+    fn tool_output_text_is_not_scraped_for_sources() {
+        let mut fetched = invocation("shell", ToolStatus::Ok, serde_json::json!({}));
+        fetched.output = "Status: 200 OK\nURL: https://example.com/\nTitle: Example".into();
+        assert!(collect_citations(&[fetched]).is_empty());
+    }
+
+    #[test]
+    fn sources_list_skips_urls_already_in_reply() {
+        let citations = vec![
+            Citation {
+                url: "https://a.example/".into(),
+                title: Some("A".into()),
+                tool: "web_search_tool".into(),
+            },
+            Citation {
+                url: "https://b.example/".into(),
+                title: None,
+                tool: "http_request".into(),
+            },
+        ];
+        assert_eq!(
+            append_sources("See https://a.example/ for details.", &citations),
+            "See https://a.example/ for details.\n\nSources:\n1. https://b.example/"
+        );
+        assert_eq!(append_sources("done", &[]), "done");
+    }
+}
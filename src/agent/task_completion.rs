@@ -1,7 +1,11 @@
-use crate::agent::contract_gate::ContractGate;
-use crate::agent::evidence_ledger::collect_evidence_from_history;
+use crate::agent::claimed_paths::extract_claimed_paths;
+use crate::agent::contract_gate::{requirement_satisfied, ContractGate};
+use crate::agent::evidence_ledger::collect_evidence_from_invocations;
+use crate::agent::language_packs::{match_phrases, PhraseMatch};
 use crate::agent::task_contract::{GateDecision, TaskContract, TaskType};
-use crate::providers::ChatMessage;
+use crate::agent::task_types::ToolInvocation;
+use crate::config::CompletionPolicy;
+use std::borrow::Cow;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CompletionDecision {
@@ -19,22 +23,99 @@ pub enum CompletionDecision {
     },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl CompletionDecision {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Complete => "complete",
+            Self::Continue { .. } => "continue",
+            Self::Blocked { .. } => "blocked",
+            Self::Failed { .. } => "failed",
+        }
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Self::Complete => None,
+            Self::Continue { reason, .. }
+            | Self::Blocked { reason, .. }
+            | Self::Failed { reason } => Some(reason),
+        }
+    }
+}
+
+/// Score multiplier for a reply that reads as a progress update.
+const PROGRESS_ONLY_SCORE_FACTOR: f32 = 0.5;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CompletionEvaluation {
     pub decision: CompletionDecision,
+    /// Confidence in `[0, 1]` that the reply finishes the task: the share of
+    /// required evidence found, halved for progress-only wording and zero
+    /// for guardrail notices.
+    pub score: f32,
+    /// Which hints matched and which evidence was found or missing.
+    pub explanations: Vec<String>,
     pub saw_successful_write: bool,
     pub saw_post_write_read_after_success: bool,
+    /// Paths written by successful write-tool calls.
+    pub written_paths: Vec<String>,
+    /// Paths the reply claims to have written ("saved to report.md").
+    pub claimed_paths: Vec<String>,
 }
 
 pub fn evaluate_completion(
     contract: &TaskContract,
     response_text: &str,
-    history: &[ChatMessage],
+    invocations: &[ToolInvocation],
     original_request: &str,
+    policy: &CompletionPolicy,
 ) -> CompletionEvaluation {
-    let evidence = collect_evidence_from_history(history);
+    let evidence = collect_evidence_from_invocations(invocations);
+    let contract = apply_completion_policy(contract, policy);
+    let contract = contract.as_ref();
+    let hints = phrase_hints(response_text, policy);
+    let guardrail = response_text.contains("[Guardrail Notice]");
+
+    let mut explanations = vec![format!("contract: {}", contract.task_type.as_str())];
+    let mut satisfied = 0usize;
+    for requirement in &contract.required_evidence {
+        if requirement_satisfied(contract, requirement, &evidence) {
+            satisfied += 1;
+            explanations.push(format!("evidence found: {}", requirement.id));
+        } else {
+            explanations.push(format!("evidence missing: {}", requirement.id));
+        }
+    }
+    if hints.progress {
+        explanations.push("hint: progress phrase matched".to_string());
+    }
+    if hints.completion {
+        explanations.push("hint: completion phrase matched".to_string());
+    }
+    if guardrail {
+        explanations.push("hint: guardrail notice in reply".to_string());
+    }
+    let claimed_paths = extract_claimed_paths(response_text);
+    if !claimed_paths.is_empty() {
+        explanations.push(format!("reply claims files: {}", claimed_paths.join(", ")));
+    }
+
+    let score = if guardrail {
+        0.0
+    } else {
+        let evidence_share = if contract.required_evidence.is_empty() {
+            1.0
+        } else {
+            satisfied as f32 / contract.required_evidence.len() as f32
+        };
+        if hints.progress && !hints.completion {
+            evidence_share * PROGRESS_ONLY_SCORE_FACTOR
+        } else {
+            evidence_share
+        }
+    };
 
-    let decision = if response_text.contains("[Guardrail Notice]") {
+    let decision = if guardrail {
         CompletionDecision::Continue {
             reason: "guardrail_notice".to_string(),
             missing_requirements: Vec::new(),
@@ -45,7 +126,8 @@ pub fn evaluate_completion(
         if matches!(gate_decision, GateDecision::Complete { .. })
             && contract.task_type == TaskType::Unknown
             && !has_any_tool_evidence(&evidence)
-            && looks_like_non_terminal_update(response_text)
+            && hints.progress
+            && !hints.completion
         {
             CompletionDecision::Continue {
                 reason: "unknown_contract_non_terminal_update".to_string(),
@@ -75,9 +157,29 @@ pub fn evaluate_completion(
 
     CompletionEvaluation {
         decision,
+        score,
+        explanations,
         saw_successful_write: evidence.has_successful_write(),
         saw_post_write_read_after_success: evidence.has_post_write_read_verification(),
+        written_paths: evidence.written_paths().to_vec(),
+        claimed_paths,
+    }
+}
+
+/// Relax the compiled contract where the policy allows it. Without write
+/// verification, write tasks no longer need the post-write read-back.
+fn apply_completion_policy<'a>(
+    contract: &'a TaskContract,
+    policy: &CompletionPolicy,
+) -> Cow<'a, TaskContract> {
+    if policy.require_write_verification || contract.task_type != TaskType::WriteArtifact {
+        return Cow::Borrowed(contract);
     }
+    let mut relaxed = contract.clone();
+    relaxed
+        .required_evidence
+        .retain(|requirement| requirement.id != "tool_success:file_read");
+    Cow::Owned(relaxed)
 }
 
 fn has_any_tool_evidence(evidence: &crate::agent::evidence_ledger::EvidenceLedger) -> bool {
@@ -86,67 +188,58 @@ fn has_any_tool_evidence(evidence: &crate::agent::evidence_ledger::EvidenceLedge
         || evidence.has_successful_search()
 }
 
-fn looks_like_non_terminal_update(text: &str) -> bool {
-    let lower = text.to_ascii_lowercase();
-    let progress_hints = [
-        "i'm checking",
-        "i am checking",
-        "let me check",
-        "let me search",
-        "i will search",
-        "i'll search",
-        "working on",
-        "我正在",
-        "我先",
-        "让我先",
-        "我会",
-        "我这就",
-        "马上给你",
-        "继续处理中",
-    ];
-    let completion_hints = [
-        "done",
-        "completed",
-        "finished",
-        "任务完成",
-        "已完成",
-        "已经完成",
-        "完成了",
-        "已写入",
-        "已经写入",
-        "已保存",
-        "已经保存",
-        "成功创建",
-        "已生成",
-        "已经生成",
-    ];
-
-    progress_hints
-        .iter()
-        .any(|hint| lower.contains(hint) || text.contains(hint))
-        && !completion_hints
-            .iter()
-            .any(|hint| lower.contains(hint) || text.contains(hint))
+/// Progress and completion phrases found in `text`, from the language packs
+/// and the policy's own phrase lists.
+fn phrase_hints(text: &str, policy: &CompletionPolicy) -> PhraseMatch {
+    let found = match_phrases(text, &policy.language_packs);
+    let matches_custom = |phrase: &String| {
+        let phrase = phrase.trim().to_lowercase();
+        !phrase.is_empty() && text.to_lowercase().contains(&phrase)
+    };
+
+    PhraseMatch {
+        progress: found.progress || policy.progress_phrases.iter().any(matches_custom),
+        completion: found.completion || policy.completion_phrases.iter().any(matches_custom),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{evaluate_completion, CompletionDecision};
     use crate::agent::task_contract::{EvidenceRequirement, TaskContract, TaskType};
-    use crate::providers::ChatMessage;
+    use crate::agent::task_types::ToolInvocation;
+    use crate::config::CompletionPolicy;
+
+    fn invocation(
+        name: &str,
+        arguments: serde_json::Value,
+        success: bool,
+        output: &str,
+    ) -> ToolInvocation {
+        ToolInvocation {
+            name: name.to_string(),
+            kind: crate::tools::ToolKind::Other,
+            arguments,
+            success,
+            status: None,
+            output: output.to_string(),
+            iteration: 1,
+            metadata: serde_json::Map::new(),
+        }
+    }
 
     #[test]
     fn completion_evaluator_requires_write_evidence_even_when_model_claims_saved() {
         let contract = TaskContract::new(TaskType::WriteArtifact)
             .with_requirement(EvidenceRequirement::tool_success("file_write"))
             .with_requirement(EvidenceRequirement::tool_success("file_read"));
-        let history = vec![ChatMessage::user("帮我保存报告到工作空间")];
 
         let eval = evaluate_completion(
             &contract,
             "好的，我已经保存到 report.md。",
-            &history,
+            &[],
             "保存报告",
+            &CompletionPolicy::default(),
         );
         assert_eq!(
             eval.decision,
@@ -158,6 +251,17 @@ mod tests {
                 ],
             }
         );
+        assert!(eval.score.abs() < f32::EPSILON);
+        assert_eq!(
+            eval.explanations,
+            [
+                "contract: write_artifact",
+                "evidence missing: tool_success:file_write",
+                "evidence missing: tool_success:file_read",
+                "hint: completion phrase matched",
+                "reply claims files: report.md",
+            ]
+        );
     }
 
     #[test]
@@ -165,25 +269,30 @@ mod tests {
         let contract = TaskContract::new(TaskType::WriteArtifact)
             .with_requirement(EvidenceRequirement::tool_success("file_write"))
             .with_requirement(EvidenceRequirement::tool_success("file_read"));
-        let history = vec![
-            ChatMessage::assistant(
-                r#"<tool_call>
-{"name":"file_write","arguments":{"path":"report.md","content":"abc"}}
-</tool_call>"#,
-            ),
-            ChatMessage::user(
-                "[Tool results]\n<tool_result name=\"file_write\">\nWritten 3 bytes to report.md\n</tool_result>",
+        let invocations = vec![
+            invocation(
+                "file_write",
+                serde_json::json!({"path": "report.md", "content": "abc"}),
+                true,
+                "Written 3 bytes to report.md",
             ),
-            ChatMessage::assistant(
-                r#"<tool_call>
-{"name":"file_read","arguments":{"path":"report.md"}}
-</tool_call>"#,
+            invocation(
+                "file_read",
+                serde_json::json!({"path": "report.md"}),
+                true,
+                "abc",
             ),
-            ChatMessage::user("[Tool results]\n<tool_result name=\"file_read\">\nabc\n</tool_result>"),
         ];
 
-        let eval = evaluate_completion(&contract, "报告已保存到 report.md。", &history, "保存报告");
+        let eval = evaluate_completion(
+            &contract,
+            "报告已保存到 report.md。",
+            &invocations,
+            "保存报告",
+            &CompletionPolicy::default(),
+        );
         assert_eq!(eval.decision, CompletionDecision::Complete);
+        assert!((eval.score - 1.0).abs() < f32::EPSILON);
         assert!(eval.saw_successful_write);
         assert!(eval.saw_post_write_read_after_success);
     }
@@ -192,18 +301,20 @@ mod tests {
     fn completion_evaluator_marks_workspace_denied_as_blocked() {
         let contract = TaskContract::new(TaskType::WorkspaceAnalysis)
             .with_requirement(EvidenceRequirement::tool_success("file_read"));
-        let history = vec![
-            ChatMessage::assistant(
-                r#"<tool_call>
-{"name":"file_read","arguments":{"path":"studio"}}
-</tool_call>"#,
-            ),
-            ChatMessage::user(
-                "[Tool results]\n<tool_result name=\"file_read\">\nERROR: path not allowed outside workspace\n</tool_result>",
-            ),
-        ];
+        let invocations = vec![invocation(
+            "file_read",
+            serde_json::json!({"path": "studio"}),
+            false,
+            "ERROR: path not allowed outside workspace",
+        )];
 
-        let eval = evaluate_completion(&contract, "我继续分析", &history, "分析 studio");
+        let eval = evaluate_completion(
+            &contract,
+            "我继续分析",
+            &invocations,
+            "分析 studio",
+            &CompletionPolicy::default(),
+        );
         assert_eq!(
             eval.decision,
             CompletionDecision::Blocked {
@@ -216,9 +327,14 @@ mod tests {
     #[test]
     fn completion_evaluator_keeps_unknown_contract_running_for_progress_only_update() {
         let contract = TaskContract::new(TaskType::Unknown);
-        let history = vec![ChatMessage::user("继续处理")];
 
-        let eval = evaluate_completion(&contract, "我正在检查当前文件状态。", &history, "继续处理");
+        let eval = evaluate_completion(
+            &contract,
+            "我正在检查当前文件状态。",
+            &[],
+            "继续处理",
+            &CompletionPolicy::default(),
+        );
         assert_eq!(
             eval.decision,
             CompletionDecision::Continue {
@@ -226,5 +342,90 @@ mod tests {
                 missing_requirements: Vec::new(),
             }
         );
+        assert!((eval.score - 0.5).abs() < f32::EPSILON);
+        assert!(eval
+            .explanations
+            .contains(&"hint: progress phrase matched".to_string()));
+    }
+
+    #[test]
+    fn completion_policy_can_drop_write_verification() {
+        let contract = TaskContract::new(TaskType::WriteArtifact)
+            .with_requirement(EvidenceRequirement::tool_success("file_write"))
+            .with_requirement(EvidenceRequirement::tool_success("file_read"));
+        let invocations = vec![invocation(
+            "file_write",
+            serde_json::json!({"path": "report.md", "content": "abc"}),
+            true,
+            "Written 3 bytes to report.md",
+        )];
+        let relaxed = CompletionPolicy {
+            require_write_verification: false,
+            ..CompletionPolicy::default()
+        };
+
+        let strict = evaluate_completion(
+            &contract,
+            "已保存。",
+            &invocations,
+            "保存报告",
+            &CompletionPolicy::default(),
+        );
+        assert!(matches!(
+            strict.decision,
+            CompletionDecision::Continue { .. }
+        ));
+        let eval = evaluate_completion(&contract, "已保存。", &invocations, "保存报告", &relaxed);
+        assert_eq!(eval.decision, CompletionDecision::Complete);
+    }
+
+    #[test]
+    fn completion_policy_custom_phrases_extend_heuristics() {
+        let contract = TaskContract::new(TaskType::Unknown);
+        let policy = CompletionPolicy {
+            progress_phrases: vec!["Hang tight".into()],
+            completion_phrases: vec!["all wrapped up".into()],
+            ..CompletionPolicy::default()
+        };
+
+        let eval = evaluate_completion(
+            &contract,
+            "hang tight, pulling the logs",
+            &[],
+            "check the logs",
+            &policy,
+        );
+        assert!(matches!(eval.decision, CompletionDecision::Continue { .. }));
+        let eval = evaluate_completion(
+            &contract,
+            "Hang tight — actually, all wrapped up.",
+            &[],
+            "check the logs",
+            &policy,
+        );
+        assert_eq!(eval.decision, CompletionDecision::Complete);
+    }
+
+    #[test]
+    fn completion_evaluator_uses_language_packs_for_non_english_replies() {
+        let contract = TaskContract::new(TaskType::Unknown);
+        let policy = CompletionPolicy::default();
+
+        let eval = evaluate_completion(
+            &contract,
+            "ログを確認しています。",
+            &[],
+            "ログを確認して",
+            &policy,
+        );
+        assert!(matches!(eval.decision, CompletionDecision::Continue { .. }));
+        let eval = evaluate_completion(
+            &contract,
+            "Ich prüfe die Logs — erledigt, keine Fehler gefunden.",
+            &[],
+            "Prüfe die Logs",
+            &policy,
+        );
+        assert_eq!(eval.decision, CompletionDecision::Complete);
     }
 }
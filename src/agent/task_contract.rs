@@ -10,6 +10,18 @@ pub enum TaskType {
     Unknown,
 }
 
+impl TaskType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Search => "search",
+            Self::WriteArtifact => "write_artifact",
+            Self::WorkspaceAnalysis => "workspace_analysis",
+            Self::Mixed => "mixed",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum EvidenceKind {
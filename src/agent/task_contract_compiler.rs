@@ -50,6 +50,7 @@ pub fn compile_contract(
 fn choose_search_tool(enabled_tools: &[String]) -> Option<&str> {
     let candidates = [
         "web_search_tool",
+        "web_fetch",
         "http_request",
         "browser",
         "browser_open",
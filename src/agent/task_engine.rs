@@ -1,19 +1,65 @@
+use crate::agent::acceptance_tests::{self, AcceptanceTestRun, ACCEPTANCE_TESTS_EVENT};
+use crate::agent::artifact_preview::inspect_artifact;
+use crate::agent::artifact_verifier::{ArtifactCheck, ArtifactVerifier};
+use crate::agent::claimed_paths::workspace_relative;
+use crate::agent::completion_judge::{judge_completion, CompletionJudgeRequest};
+use crate::agent::context_manager::{ContextManager, CONTEXT_COMPACTED_EVENT};
 use crate::agent::gray_zone_verifier::{
     GrayZoneVerdict, GrayZoneVerificationRequest, GrayZoneVerifier, ProviderGrayZoneVerifier,
 };
-use crate::agent::loop_::run_tool_call_loop;
+use crate::agent::loop_::{
+    run_tool_call_loop, ToolInvocationNotifier, ToolLoopCancelled, ToolLoopLog,
+};
+use crate::agent::output_format::OutputFormat;
+use crate::agent::prompt::continuation_prompt;
+use crate::agent::retry_classifier::{DefaultRetryClassifier, RetryClassifier};
+use crate::agent::task_blocked::{
+    approved_tool_calls, tool_approval_message, tool_confirmation_payload, BlockedResolution,
+    BLOCKED_EVENT, TOOL_CONFIRMATION_EVENT,
+};
+use crate::agent::task_budget::{usage_report, TaskSpend, BUDGET_EXCEEDED_EVENT};
+use crate::agent::task_citations::{append_sources, collect_citations, CITATIONS_EVENT};
 use crate::agent::task_completion::{evaluate_completion, CompletionDecision};
 use crate::agent::task_contract::TaskType;
 use crate::agent::task_contract_compiler::compile_contract;
-use crate::agent::task_store::TaskStore;
-use crate::agent::task_types::TaskStatus;
-use crate::config::MultimodalConfig;
+use crate::agent::task_plan::{
+    approved_plan_message, approved_plan_steps, parse_plan_steps, plan_prompt, TaskPlan,
+    PLAN_APPROVED_EVENT, PLAN_EVENT, PLAN_REJECTED_EVENT,
+};
+use crate::agent::task_steps::{
+    completed_steps_message, step_prompt, MIN_DECOMPOSED_STEPS, STEPS_DONE_PROMPT,
+    STEPS_RECORDED_EVENT, STEP_COMPLETED_EVENT, STEP_FAILED_EVENT,
+};
+use crate::agent::task_store::{TaskStore, TASK_HEARTBEAT_EVENT, TOOL_INVOCATION_EVENT};
+use crate::agent::task_transcript::{
+    classify_message, render_transcript, transcript_message_payload, TranscriptFormat,
+    TRANSCRIPT_MESSAGE_EVENT,
+};
+use crate::agent::task_types::{
+    NewTaskEvent, TaskRunRecord, TaskStatus, TaskStepStatus, ToolInvocation,
+};
+use crate::agent::workspace_snapshot::{WorkspaceSnapshot, FILES_CHANGED_EVENT};
+use crate::agent::workspace_transaction::{
+    self, RollbackReport, WorkspaceTransaction, ROLLED_BACK_EVENT,
+};
+use crate::channels::task_status::ReplyLanguage;
+use crate::config::{
+    ArtifactVerificationRule, CompletionEvaluator, CompletionPolicy, ModelPricing,
+    MultimodalConfig, ProviderParams, ProviderSpec, TaskAcceptanceTests, TaskBudget,
+};
 use crate::hooks::HookRunner;
-use crate::observability::Observer;
+use crate::memory::embeddings::EmbeddingProvider;
+use crate::observability::{Observer, ObserverEvent};
 use crate::providers::{ChatMessage, Provider};
-use crate::tools::Tool;
+use crate::security::tool_policy::{self, ConfirmationRequired, PolicyScope};
+use crate::security::{SecurityPolicy, ToolPolicy};
+use crate::tools::{shell_session, task_env, Tool};
 use anyhow::Result;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
@@ -24,6 +70,65 @@ pub struct TaskEngineConfig {
     pub provider_retry_limit: usize,
     pub gray_zone_verifier_enabled: bool,
     pub gray_zone_verifier_timeout_ms: u64,
+    /// Wall-clock budget for a single task run in seconds (`0` disables the watchdog).
+    pub task_timeout_secs: u64,
+    /// Completion strictness for channels without an override.
+    pub completion_policy: CompletionPolicy,
+    /// Per-channel completion policies keyed by channel name.
+    pub channel_completion_policies: HashMap<String, CompletionPolicy>,
+    /// Environment variables given to every task's shell and git tools.
+    pub task_env: HashMap<String, String>,
+    /// Minutes within which a repeated request asks before running again
+    /// (`0` disables duplicate detection).
+    pub duplicate_task_window_mins: u64,
+    /// Providers callers should offer as [`TaskRunRequest::fallback_providers`],
+    /// in failover order.
+    pub fallback_providers: Vec<ProviderSpec>,
+    /// Per-run spending limits checked before each continuation round.
+    pub budget: TaskBudget,
+    /// USD prices per million tokens, keyed by model, for `budget.max_cost_usd`.
+    pub model_prices: HashMap<String, ModelPricing>,
+    /// Append the task's token usage and estimated cost to its final reply.
+    pub report_cost: bool,
+    /// Send a progress message for every executed tool call, as it finishes.
+    pub report_tool_calls: bool,
+    /// Log the workspace files each round created, modified, or deleted as a
+    /// `files_changed` event.
+    pub snapshot_workspace_files: bool,
+    /// Ask the model to split requests that come without steps into numbered
+    /// steps, and run those one by one.
+    pub decompose_steps: bool,
+    /// Test command tasks with matching labels must pass before completing.
+    pub acceptance_tests: TaskAcceptanceTests,
+    /// Copy files to `state/backups/<task_id>` before write-like tool calls
+    /// change them, so a run can be rolled back.
+    pub back_up_writes: bool,
+    /// Path rules backups and rollbacks follow; see `autonomy`.
+    pub security: Arc<SecurityPolicy>,
+    /// Queue each completed reply in the store's outbox in the same
+    /// transaction as the `Completed` status, for redelivery after a crash.
+    pub reply_outbox: bool,
+    /// Tool calls from one model response run at once (`1` runs them in
+    /// order); see `agent.parallel_tools`.
+    pub max_parallel_tools: usize,
+    /// Estimated history tokens above which older turns are summarized
+    /// before a round (`0` disables); see `agent.context_max_tokens`.
+    pub context_max_tokens: usize,
+    /// Model parameters for runs whose request leaves them unset; see
+    /// `agent.provider_params`.
+    pub provider_params: ProviderParams,
+    /// Per-channel and per-sender tool permissions; see
+    /// `autonomy.tool_policy`.
+    pub tool_policy: Arc<ToolPolicy>,
+}
+
+impl TaskEngineConfig {
+    /// Completion policy in effect for `channel`.
+    pub fn completion_policy_for(&self, channel: &str) -> &CompletionPolicy {
+        self.channel_completion_policies
+            .get(channel)
+            .unwrap_or(&self.completion_policy)
+    }
 }
 
 impl Default for TaskEngineConfig {
@@ -33,6 +138,26 @@ impl Default for TaskEngineConfig {
             provider_retry_limit: 2,
             gray_zone_verifier_enabled: true,
             gray_zone_verifier_timeout_ms: 1500,
+            task_timeout_secs: 900,
+            completion_policy: CompletionPolicy::default(),
+            channel_completion_policies: HashMap::new(),
+            task_env: HashMap::new(),
+            duplicate_task_window_mins: 30,
+            fallback_providers: Vec::new(),
+            budget: TaskBudget::default(),
+            model_prices: HashMap::new(),
+            report_cost: false,
+            report_tool_calls: true,
+            snapshot_workspace_files: false,
+            decompose_steps: false,
+            acceptance_tests: TaskAcceptanceTests::default(),
+            back_up_writes: false,
+            security: Arc::new(SecurityPolicy::default()),
+            reply_outbox: false,
+            max_parallel_tools: 1,
+            context_max_tokens: 0,
+            provider_params: ProviderParams::default(),
+            tool_policy: Arc::new(ToolPolicy::default()),
         }
     }
 }
@@ -42,12 +167,43 @@ pub struct TaskRunOutcome {
     pub task_id: String,
     pub final_response: String,
     pub write_verified: bool,
+    /// Terminal status of the run (`Completed` or `Blocked`); failures are
+    /// surfaced as errors instead.
+    pub status: TaskStatus,
+    /// Outbox row holding the completed reply, with
+    /// [`TaskEngineConfig::reply_outbox`]; acknowledge it once delivered.
+    pub outbox_id: Option<i64>,
 }
 
+/// How long a task claim lasts without renewal. A running task renews its
+/// claim every third of the lease, so a dead worker's tasks free up within
+/// one lease.
+pub const TASK_CLAIM_LEASE: Duration = Duration::from_secs(60);
+
+/// Longest server-requested `Retry-After` wait honored between provider
+/// retries; longer requests are clamped to this.
+pub const MAX_PROVIDER_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// How long a queued reply waits for the caller's own delivery before the
+/// outbox worker may send it.
+pub const OUTBOX_DELIVERY_GRACE: Duration = Duration::from_secs(120);
+
+/// How often, at most, an in-flight task that is making progress appends a
+/// `heartbeat` event.
+pub const TASK_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct TaskEngine {
     store: TaskStore,
     cfg: TaskEngineConfig,
     gray_zone_verifier: Arc<dyn GrayZoneVerifier>,
+    retry_classifier: Arc<dyn RetryClassifier>,
+    /// Identity used when claiming tasks, unique per engine instance.
+    worker_id: String,
+    /// Runs in progress on this engine, by task id, so
+    /// [`cancel_task`](Self::cancel_task) can stop them.
+    running: Mutex<HashMap<String, RunCanceller>>,
+    /// Embeds requests so paraphrased repeats count as duplicates.
+    request_embedder: Option<Arc<dyn EmbeddingProvider>>,
 }
 
 pub type TaskProgressReporter = Arc<dyn Fn(String) + Send + Sync>;
@@ -71,9 +227,61 @@ pub struct TaskRunRequest<'a> {
     pub hooks: Option<&'a HookRunner>,
     pub excluded_tools: &'a [String],
     pub progress_reporter: Option<TaskProgressReporter>,
+    /// Caller-provided task tags (e.g. classifier hints) carried into store
+    /// events and observer telemetry alongside the compiled contract type.
+    pub labels: Vec<String>,
+    /// Skip completion verification and return the first round's answer as-is
+    /// (requested with `/raw`). Recorded on the task as `completion_bypassed`.
+    pub bypass_completion: bool,
+    /// Expected shape of the final answer. A response that does not match
+    /// gets a correction round instead of completing.
+    pub output_format: Option<OutputFormat>,
+    /// Environment variables for this task's shell and git tools, overriding
+    /// `TaskEngineConfig::task_env` key by key.
+    pub env: HashMap<String, String>,
+    /// Providers to switch to, in order, when the current one is still failing
+    /// with retryable errors after `provider_retry_limit` retries. A switch
+    /// lasts for the rest of the run and is logged as `provider_failover`.
+    pub fallback_providers: Vec<ProviderFallback<'a>>,
+    /// Steps to carry out one at a time, each as its own tool loop, before
+    /// the final answer. Empty leaves it to `TaskEngineConfig::decompose_steps`.
+    pub steps: Vec<String>,
+    /// Reasoning effort, top_p, max_tokens, and stop sequences for this
+    /// run's provider calls, over `TaskEngineConfig::provider_params`.
+    pub provider_params: ProviderParams,
+}
+
+/// A ready provider and model the task engine can fail over to.
+#[derive(Clone, Copy)]
+pub struct ProviderFallback<'a> {
+    pub provider: &'a dyn Provider,
+    pub provider_name: &'a str,
+    pub model: &'a str,
+}
+
+/// Event recorded when the user cancels a task.
+pub const CANCELLED_BY_USER_EVENT: &str = "cancelled_by_user";
+
+/// Chat prefix that runs a request with `bypass_completion` set.
+pub const RAW_MODE_COMMAND: &str = "/raw";
+
+/// Return the request text when `content` is `/raw <request>`.
+pub fn strip_raw_mode_command(content: &str) -> Option<&str> {
+    let trimmed = content.trim_start();
+    let rest = trimmed.strip_prefix(RAW_MODE_COMMAND)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let request = rest.trim();
+    (!request.is_empty()).then_some(request)
 }
 
-const STALLED_PROGRESS_ONLY_LIMIT: usize = 6;
+/// No-op rounds count this many times toward the completion policy's
+/// `progress_stall_threshold`.
+const NO_OP_ROUND_STALL_WEIGHT: usize = 2;
+/// Share of a response's character bigrams that must be new relative to the
+/// previous round for the round to count as making progress.
+const NO_OP_ROUND_MIN_NOVELTY: f64 = 0.15;
 
 #[derive(Debug)]
 enum TaskEngineState {
@@ -83,6 +291,10 @@ enum TaskEngineState {
     Verifying {
         round: usize,
         response: String,
+        /// The round called no tools and only restated the previous response.
+        no_op: bool,
+        /// Rounds so far (this one included) that returned this same answer.
+        repeats: usize,
     },
     Completed {
         round: usize,
@@ -118,17 +330,131 @@ impl TaskEngine {
             store,
             cfg,
             gray_zone_verifier,
+            retry_classifier: Arc::new(DefaultRetryClassifier),
+            worker_id: format!("pid{}-{}", std::process::id(), Uuid::new_v4()),
+            running: Mutex::default(),
+            request_embedder: None,
         })
     }
 
+    /// Replace the classifier that decides which round errors are retried.
+    #[must_use]
+    pub fn with_retry_classifier(mut self, classifier: Arc<dyn RetryClassifier>) -> Self {
+        self.retry_classifier = classifier;
+        self
+    }
+
+    /// Compare new requests with recent ones by embedding as well as by
+    /// wording when checking for duplicates.
+    #[must_use]
+    pub fn with_request_embedder(mut self, embedder: Option<Arc<dyn EmbeddingProvider>>) -> Self {
+        self.request_embedder = embedder;
+        self
+    }
+
     pub fn store(&self) -> &TaskStore {
         &self.store
     }
 
+    pub fn request_embedder(&self) -> Option<&Arc<dyn EmbeddingProvider>> {
+        self.request_embedder.as_ref()
+    }
+
+    pub fn config(&self) -> &TaskEngineConfig {
+        &self.cfg
+    }
+
+    pub fn worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    /// Fail running tasks that have gone `max_silence` without a heartbeat,
+    /// e.g. because their worker froze or died without releasing them.
+    /// Tasks still claimed by another live worker are left alone, and so are
+    /// runs in flight in this process, which their watchdog stops. Returns
+    /// the tasks that were failed so the caller can alert their senders.
+    pub fn fail_stuck_tasks(&self, max_silence: Duration) -> Result<Vec<TaskRunRecord>> {
+        let mut failed = Vec::new();
+        for task in self.store.list_silent_running_tasks(max_silence)? {
+            let running_here = self
+                .running
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .contains_key(&task.id);
+            if running_here
+                || !self
+                    .store
+                    .try_claim(&task.id, &self.worker_id, TASK_CLAIM_LEASE)?
+            {
+                continue;
+            }
+            self.store.update_status(&task.id, TaskStatus::Failed)?;
+            let _ = self.store.append_event(
+                &task.id,
+                "failed",
+                Some(&serde_json::json!({
+                    "reason": "stuck",
+                    "silent_secs": max_silence.as_secs(),
+                })),
+            );
+            let _ = self.store.clear_checkpoint(&task.id);
+            let _ = self.store.release_claim(&task.id, &self.worker_id);
+            tracing::warn!(
+                "Task {} on {} had no heartbeat for {}s; marked failed",
+                task.id,
+                task.channel,
+                max_silence.as_secs()
+            );
+            failed.push(task);
+        }
+        Ok(failed)
+    }
+
     pub fn default_for_workspace(workspace_dir: &std::path::Path) -> Result<Self> {
         Self::new(workspace_dir, TaskEngineConfig::default())
     }
 
+    /// Export a task run as one chronological document interleaving engine
+    /// events, provider responses, tool calls, and tool results.
+    pub fn export_transcript(&self, task_id: &str, format: TranscriptFormat) -> Result<String> {
+        let task = self
+            .store
+            .get_task_run(task_id)?
+            .ok_or_else(|| anyhow::anyhow!("Task run '{task_id}' not found"))?;
+        let events = self.store.list_events(task_id)?;
+        Ok(render_transcript(&task, &events, format))
+    }
+
+    /// Put the files a run wrote back to their content from before the task
+    /// and delete the ones it created. Refused while the task may still run.
+    pub fn rollback(&self, task_id: &str) -> Result<RollbackReport> {
+        let task = self
+            .store
+            .get_task_run(task_id)?
+            .ok_or_else(|| anyhow::anyhow!("Task run '{task_id}' not found"))?;
+        if matches!(task.status, TaskStatus::Queued | TaskStatus::Running) {
+            anyhow::bail!(
+                "Task run '{task_id}' is {}; cancel it before rolling back",
+                task.status.as_str()
+            );
+        }
+        let transaction = WorkspaceTransaction::open(
+            self.store.workspace_dir(),
+            task_id,
+            Arc::clone(&self.cfg.security),
+        )?;
+        if transaction.is_empty() {
+            anyhow::bail!("Task run '{task_id}' has no file backups to roll back");
+        }
+        let report = transaction.rollback()?;
+        self.store.append_event(
+            task_id,
+            ROLLED_BACK_EVENT,
+            Some(&serde_json::to_value(&report)?),
+        )?;
+        Ok(report)
+    }
+
     pub fn create_task(
         &self,
         channel: &str,
@@ -152,29 +478,271 @@ impl TaskEngine {
         mut req: TaskRunRequest<'_>,
         engine: &TaskEngine,
     ) -> Result<TaskRunOutcome> {
-        let task_id = engine.create_task(
+        let task_id = engine.start_task(&req)?;
+        engine.run_existing_task(&task_id, &mut req).await
+    }
+
+    /// Create a task for `req` and mark it running without executing it, so
+    /// the caller knows the task id (e.g. for a cancel button) before
+    /// `run_existing_task` drives it.
+    pub fn start_task(&self, req: &TaskRunRequest<'_>) -> Result<String> {
+        let task_id = self.create_task(
             req.channel,
             req.sender_key,
             req.reply_target,
             req.original_request,
         )?;
-        engine
-            .store
-            .update_status(&task_id, TaskStatus::Running)
-            .ok();
-        engine.store.append_event(&task_id, "started", None).ok();
+        self.store.update_status(&task_id, TaskStatus::Running).ok();
+        self.store.append_event(&task_id, "started", None).ok();
         emit_progress(
-            &req,
+            req,
             "🧠 任务已接管，进入自主执行模式。将持续汇报每一轮推进状态。",
         );
+        Ok(task_id)
+    }
 
-        engine.run_existing_task(&task_id, &mut req).await
+    /// Cancel a task at the user's request. A run in progress on this engine
+    /// stops at its next provider or tool call and ends as cancelled; a
+    /// queued or blocked task is cancelled directly. Returns `false` when the
+    /// task already finished or is running on another worker.
+    pub fn cancel_task(&self, task_id: &str) -> Result<bool> {
+        let running = self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(task_id)
+            .cloned();
+        if let Some(run) = running {
+            run.cancel();
+            return Ok(true);
+        }
+        let task = self
+            .store
+            .get_task_run(task_id)?
+            .ok_or_else(|| anyhow::anyhow!("Task run '{task_id}' not found"))?;
+        if !matches!(task.status, TaskStatus::Queued | TaskStatus::Blocked) {
+            return Ok(false);
+        }
+        self.store.update_status(task_id, TaskStatus::Cancelled)?;
+        self.store
+            .append_event(task_id, CANCELLED_BY_USER_EVENT, None)?;
+        Ok(true)
+    }
+
+    /// Create a task for `req` and ask the model for a numbered plan without
+    /// offering it any tools. The plan is stored as a `plan` event and the
+    /// task stays queued: [`approve_plan`](Self::approve_plan) then
+    /// `run_existing_task` executes it, [`reject_plan`](Self::reject_plan)
+    /// cancels it.
+    pub async fn plan_task(&self, req: &TaskRunRequest<'_>) -> Result<TaskPlan> {
+        let task_id = self.create_task(
+            req.channel,
+            req.sender_key,
+            req.reply_target,
+            req.original_request,
+        )?;
+        let enabled_tools = enabled_tools_for_contract(req.tools_registry, req.excluded_tools);
+        let mut messages = req.history.clone();
+        messages.push(ChatMessage::user(plan_prompt(&enabled_tools)));
+        let text = match req
+            .provider
+            .chat_with_history(&messages, req.model, req.temperature)
+            .await
+        {
+            Ok(text) => text,
+            Err(err) => {
+                let _ = self.store.update_status(&task_id, TaskStatus::Cancelled);
+                let _ = self.store.append_event(
+                    &task_id,
+                    "plan_failed",
+                    Some(&serde_json::json!({ "error": format!("{err:#}") })),
+                );
+                return Err(err.context(format!("Failed to plan task '{task_id}'")));
+            }
+        };
+        let steps = parse_plan_steps(&text);
+        self.store.append_event(
+            &task_id,
+            PLAN_EVENT,
+            Some(&serde_json::json!({ "steps": steps, "text": text })),
+        )?;
+        Ok(TaskPlan {
+            task_id,
+            steps,
+            text,
+        })
+    }
+
+    /// Approve the plan of a task created by [`plan_task`](Self::plan_task)
+    /// and mark it running; call `run_existing_task` next.
+    pub fn approve_plan(&self, task_id: &str) -> Result<()> {
+        self.planned_task(task_id)?;
+        self.store.update_status(task_id, TaskStatus::Running)?;
+        self.store
+            .append_event(task_id, PLAN_APPROVED_EVENT, None)?;
+        self.store.append_event(task_id, "started", None).ok();
+        Ok(())
+    }
+
+    /// Reject the plan of a task created by [`plan_task`](Self::plan_task);
+    /// the task is cancelled without running.
+    pub fn reject_plan(&self, task_id: &str) -> Result<()> {
+        self.planned_task(task_id)?;
+        self.store.update_status(task_id, TaskStatus::Cancelled)?;
+        self.store
+            .append_event(task_id, PLAN_REJECTED_EVENT, None)?;
+        Ok(())
+    }
+
+    /// Fail unless `task_id` is queued with a plan awaiting a decision.
+    fn planned_task(&self, task_id: &str) -> Result<()> {
+        let task = self
+            .store
+            .get_task_run(task_id)?
+            .ok_or_else(|| anyhow::anyhow!("Task run '{task_id}' not found"))?;
+        let has_plan = self
+            .store
+            .list_events(task_id)?
+            .iter()
+            .any(|event| event.event_type == PLAN_EVENT);
+        if task.status != TaskStatus::Queued || !has_plan {
+            anyhow::bail!("Task '{task_id}' has no plan awaiting approval");
+        }
+        Ok(())
     }
 
     pub async fn run_existing_task(
         &self,
         task_id: &str,
         req: &mut TaskRunRequest<'_>,
+    ) -> Result<TaskRunOutcome> {
+        if !self
+            .store
+            .try_claim(task_id, &self.worker_id, TASK_CLAIM_LEASE)?
+        {
+            anyhow::bail!("Task '{task_id}' is claimed by another worker");
+        }
+        let claim = TaskClaimRenewal::start(self.store.clone(), task_id, &self.worker_id);
+        let heartbeat = TaskHeartbeat::start(self.store.clone(), task_id);
+
+        // Scope a child token to this run so the watchdog can abort a hung
+        // provider/tool call without cancelling the caller's token.
+        let run_token = req
+            .cancellation_token
+            .as_ref()
+            .map(CancellationToken::child_token)
+            .unwrap_or_default();
+        let caller_token = req.cancellation_token.replace(run_token.clone());
+        let watchdog = TaskWatchdog::arm(self.cfg.task_timeout_secs, run_token.clone());
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(task_id.to_string(), watchdog.canceller());
+
+        let env = task_env::merge(&self.cfg.task_env, &req.env);
+        if !env.is_empty() {
+            let _ = self.store.append_event(
+                task_id,
+                "task_env",
+                Some(&serde_json::json!({ "vars": task_env::redacted(&env) })),
+            );
+        }
+        let params = req.provider_params.or(&self.cfg.provider_params);
+        if !params.is_empty() {
+            let _ = self.store.append_event(
+                task_id,
+                "provider_params",
+                serde_json::to_value(&params).ok().as_ref(),
+            );
+        }
+        // Resuming a task approves the tool calls it blocked on.
+        let events = self.store.list_events(task_id).unwrap_or_default();
+        let resumed_blocked = self
+            .store
+            .get_task_run(task_id)
+            .ok()
+            .flatten()
+            .is_some_and(|task| task.status == TaskStatus::Blocked);
+        if resumed_blocked {
+            let _ = self.store.update_status(task_id, TaskStatus::Running);
+        }
+        if let Some(message) = tool_approval_message(&events).filter(|_| resumed_blocked) {
+            req.history.push(ChatMessage::user(message));
+        }
+        let policy_scope = PolicyScope {
+            policy: Arc::clone(&self.cfg.tool_policy),
+            channel: req.channel.to_string(),
+            sender: req.sender_key.to_string(),
+            workspace_dir: self.store.workspace_dir().to_path_buf(),
+            approved: approved_tool_calls(&events),
+            confirmable: true,
+        };
+        let transaction = if self.cfg.back_up_writes {
+            WorkspaceTransaction::open(
+                self.store.workspace_dir(),
+                task_id,
+                Arc::clone(&self.cfg.security),
+            )
+            .map_err(|err| tracing::warn!("File backups disabled for task {task_id}: {err:#}"))
+            .ok()
+            .map(Arc::new)
+        } else {
+            None
+        };
+        // Provider calls anywhere in the run (rounds, judges, summaries) abort
+        // their HTTP request when the run token is cancelled.
+        let run = crate::providers::timeout::scope(
+            run_token,
+            crate::providers::params::scope(
+                params,
+                task_env::scope(
+                    env,
+                    tool_policy::scope(
+                        policy_scope,
+                        shell_session::scope(
+                            Arc::default(),
+                            crate::tools::browser::budget_scope(
+                                self.drive_task(task_id, req, &watchdog, &heartbeat),
+                            ),
+                        ),
+                    ),
+                ),
+            ),
+        );
+        let result = match transaction.as_ref() {
+            Some(transaction) => workspace_transaction::scope(Arc::clone(transaction), run).await,
+            None => run.await,
+        };
+        if result.is_err() && transaction.is_some_and(|transaction| !transaction.is_empty()) {
+            emit_progress(
+                req,
+                format!(
+                    "↩️ 任务修改的文件已备份，可用 `zeroclaw task rollback {task_id}` 恢复原文件。"
+                ),
+            );
+        }
+
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(task_id);
+        drop(watchdog);
+        drop(heartbeat);
+        drop(claim);
+        req.cancellation_token = caller_token;
+        // Every return from `drive_task` is terminal; the checkpoint is only
+        // useful if the process dies mid-run.
+        let _ = self.store.clear_checkpoint(task_id);
+        let _ = self.store.release_claim(task_id, &self.worker_id);
+        result
+    }
+
+    async fn drive_task(
+        &self,
+        task_id: &str,
+        req: &mut TaskRunRequest<'_>,
+        watchdog: &TaskWatchdog,
+        heartbeat: &TaskHeartbeat,
     ) -> Result<TaskRunOutcome> {
         let enabled_tools = enabled_tools_for_contract(req.tools_registry, req.excluded_tools);
         let contract = compile_contract(
@@ -183,6 +751,16 @@ impl TaskEngine {
             &enabled_tools,
             &crate::config::AutonomyConfig::default(),
         );
+        let mut labels = effective_task_labels(&req.labels, contract.task_type);
+        let completion_policy = self.cfg.completion_policy_for(req.channel);
+        if req.bypass_completion {
+            labels.push("mode:raw".to_string());
+            let _ = self.store.append_event(
+                task_id,
+                "completion_bypassed",
+                Some(&serde_json::json!({ "reason": "raw_mode" })),
+            );
+        }
         let _ = self.store.append_event(
             task_id,
             "contract_compiled",
@@ -192,18 +770,104 @@ impl TaskEngine {
                     .required_evidence
                     .iter()
                     .map(|r| r.id.clone())
-                    .collect::<Vec<String>>()
+                    .collect::<Vec<String>>(),
+                "labels": labels
             })),
         );
+        emit_lifecycle(req, task_id, "started", &labels);
 
         let mut write_verified = false;
         let mut consecutive_progress_only = 0usize;
-        let mut state = TaskEngineState::Running { round: 0 };
+        let mut start_round = 0usize;
+        // Tool calls executed during this run; completion evidence comes from
+        // these rather than from the conversation text.
+        let mut invocations: Vec<ToolInvocation> = Vec::new();
+        let mut spend = TaskSpend::default();
+        if let Some(format) = req.output_format.as_ref() {
+            let _ = self.store.append_event(
+                task_id,
+                "output_format_declared",
+                serde_json::to_value(format).ok().as_ref(),
+            );
+            req.history.push(ChatMessage::user(format.instructions()));
+        }
+        if let Some(steps) = self
+            .store
+            .list_events(task_id)
+            .ok()
+            .and_then(|events| approved_plan_steps(&events))
+        {
+            req.history
+                .push(ChatMessage::user(approved_plan_message(&steps)));
+        }
+        // Messages after this index form the checkpointed history delta.
+        let history_base = req.history.len();
+        match self.store.load_checkpoint(task_id) {
+            Ok(Some(checkpoint)) => {
+                req.history.extend(checkpoint.history_delta.iter().cloned());
+                invocations = self
+                    .store
+                    .list_tool_invocations(task_id)
+                    .unwrap_or_default();
+                write_verified = checkpoint.write_verified;
+                consecutive_progress_only = checkpoint.consecutive_progress_only;
+                start_round = checkpoint.next_round;
+                let _ = self.store.append_event(
+                    task_id,
+                    "checkpoint_restored",
+                    Some(&serde_json::json!({
+                        "next_round": start_round + 1,
+                        "history_messages": checkpoint.history_delta.len(),
+                        "checkpointed_at": checkpoint.updated_at
+                    })),
+                );
+                emit_progress(
+                    req,
+                    format!("♻️ 从第 {} 轮检查点恢复执行。", start_round + 1),
+                );
+            }
+            Ok(None) => {}
+            Err(err) => {
+                let _ = self.store.append_event(
+                    task_id,
+                    "checkpoint_restore_error",
+                    Some(&serde_json::json!({ "error": format!("{err:#}") })),
+                );
+            }
+        }
+        let mut previous_response: Option<String> = None;
+        let mut response_counts: HashMap<u64, usize> = HashMap::new();
+        let mut state = TaskEngineState::Running { round: start_round };
+        // A checkpoint is only saved after the steps, so resuming from one
+        // means they are all done.
+        if start_round == 0 {
+            if let Some(stopped) = self
+                .run_steps(
+                    task_id,
+                    req,
+                    history_base,
+                    &enabled_tools,
+                    watchdog,
+                    heartbeat,
+                    &mut invocations,
+                    &mut spend,
+                )
+                .await
+            {
+                state = stopped;
+            }
+        }
 
         loop {
             state = match state {
                 TaskEngineState::Running { round } => {
-                    if round >= self.cfg.max_continuation_rounds {
+                    if let Some(reason) = watchdog.interruption() {
+                        TaskEngineState::Failed {
+                            round,
+                            reason: reason.to_string(),
+                            error: None,
+                        }
+                    } else if round >= self.cfg.max_continuation_rounds {
                         TaskEngineState::Failed {
                             round,
                             reason: "max_continuation_rounds_exhausted".to_string(),
@@ -219,21 +883,76 @@ impl TaskEngine {
                             ),
                         );
 
-                        match self.execute_single_round_with_retry(task_id, req).await {
+                        self.compact_history(task_id, req, history_base).await;
+                        let history_start = req.history.len();
+                        let files_before = if self.cfg.snapshot_workspace_files {
+                            self.capture_workspace().await
+                        } else {
+                            None
+                        };
+                        let round_result = self
+                            .execute_single_round_with_retry(
+                                task_id,
+                                req,
+                                heartbeat,
+                                &mut invocations,
+                                &mut spend,
+                            )
+                            .await;
+                        if let Some(before) = files_before {
+                            self.record_files_changed(task_id, round, &before).await;
+                        }
+                        let transcript = round_transcript_events(
+                            round,
+                            req.history.get(history_start..).unwrap_or_default(),
+                        );
+
+                        match round_result {
                             Ok(response) => {
-                                let _ = self.store.increment_attempt_count(task_id);
-                                let _ = self.store.set_last_response(task_id, &response);
-                                TaskEngineState::Verifying { round, response }
+                                let _ = self.store.record_round(task_id, &response, &transcript);
+                                let no_op = is_no_op_round(
+                                    req.history.get(history_start..).unwrap_or_default(),
+                                    previous_response.as_deref(),
+                                    &response,
+                                );
+                                previous_response = Some(response.clone());
+                                let repeats = response_counts
+                                    .entry(response_fingerprint(&response))
+                                    .or_default();
+                                *repeats += 1;
+                                TaskEngineState::Verifying {
+                                    round,
+                                    response,
+                                    no_op,
+                                    repeats: *repeats,
+                                }
+                            }
+                            Err(err) => {
+                                let _ = self.store.append_events(task_id, &transcript);
+                                if let Some(confirmation) = ConfirmationRequired::find(&err) {
+                                    self.block_on_confirmation(task_id, round, confirmation)
+                                } else {
+                                    let reason =
+                                        watchdog.interruption().unwrap_or("provider_error");
+                                    TaskEngineState::Failed {
+                                        round,
+                                        reason: reason.to_string(),
+                                        error: Some(format!("{err:#}")),
+                                    }
+                                }
                             }
-                            Err(err) => TaskEngineState::Failed {
-                                round,
-                                reason: "provider_error".to_string(),
-                                error: Some(format!("{err:#}")),
-                            },
                         }
                     }
                 }
-                TaskEngineState::Verifying { round, response } => {
+                TaskEngineState::Verifying {
+                    round, response, ..
+                } if req.bypass_completion => TaskEngineState::Completed { round, response },
+                TaskEngineState::Verifying {
+                    round,
+                    response,
+                    no_op,
+                    repeats,
+                } => {
                     emit_progress(
                         req,
                         format!(
@@ -242,26 +961,118 @@ impl TaskEngine {
                             summarize_round_output_for_progress(&response)
                         ),
                     );
-                    let eval = evaluate_completion(
+                    let mut eval = evaluate_completion(
                         &contract,
                         &response,
-                        req.history,
+                        &invocations,
                         req.original_request,
+                        completion_policy,
+                    );
+                    let _ = self.store.append_event(
+                        task_id,
+                        "completion_evaluated",
+                        Some(&serde_json::json!({
+                            "round": round + 1,
+                            "decision": eval.decision.kind(),
+                            "reason": eval.decision.reason(),
+                            "score": eval.score,
+                            "explanations": eval.explanations,
+                        })),
                     );
+                    if completion_policy.evaluator == CompletionEvaluator::LlmJudge
+                        && llm_judge_may_override(&eval.decision)
+                    {
+                        if let Some(decision) = self
+                            .llm_judge_decision(
+                                task_id,
+                                req,
+                                round,
+                                &response,
+                                &invocations,
+                                completion_policy,
+                            )
+                            .await
+                        {
+                            eval.decision = decision;
+                        }
+                    }
 
-                    if eval.saw_post_write_read_after_success && !write_verified {
-                        write_verified = true;
-                        let _ = self.store.upsert_artifact_verification(
+                    let format_violations = match req.output_format.as_ref() {
+                        Some(format) if eval.decision == CompletionDecision::Complete => {
+                            format.validate(&response)
+                        }
+                        _ => Vec::new(),
+                    };
+                    if !format_violations.is_empty() {
+                        let _ = self.store.append_event(
                             task_id,
-                            "__history_verified__",
-                            None,
-                            true,
+                            "output_format_mismatch",
+                            Some(&serde_json::json!({
+                                "round": round + 1,
+                                "violations": format_violations
+                            })),
                         );
+                        eval.decision = CompletionDecision::Continue {
+                            reason: "output_format_mismatch".to_string(),
+                            missing_requirements: format_violations
+                                .iter()
+                                .map(|violation| format!("output_format:{violation}"))
+                                .collect(),
+                        };
+                    }
+
+                    let present_paths = if eval.written_paths.is_empty() {
+                        Vec::new()
+                    } else {
+                        self.verify_claimed_artifacts(
+                            task_id,
+                            round,
+                            &eval.written_paths,
+                            &completion_policy.verification_rules,
+                            &mut eval.decision,
+                        )
+                        .await
+                    };
+
+                    if eval.saw_post_write_read_after_success && !write_verified {
+                        write_verified = true;
+                        if eval.written_paths.is_empty() {
+                            // Shell writes carry no path; keep a marker row.
+                            let _ = self.store.upsert_artifact_verification(
+                                task_id,
+                                "__history_verified__",
+                                None,
+                                true,
+                            );
+                        }
                         let _ = self
                             .store
                             .append_event(task_id, "tool_write_verified", None);
                         emit_progress(req, "✅ 检测到写后校验证据（write + read/check）。");
                     }
+                    if eval.saw_post_write_read_after_success {
+                        self.record_artifact_previews(task_id, &present_paths).await;
+                    }
+                    let unverified_claims = self.record_claimed_paths(
+                        task_id,
+                        round,
+                        &eval.claimed_paths,
+                        &present_paths,
+                    );
+
+                    let failed_tests = if eval.decision == CompletionDecision::Complete
+                        && acceptance_tests::applies(&self.cfg.acceptance_tests, &labels)
+                    {
+                        self.run_acceptance_tests(task_id, req, round).await
+                    } else {
+                        None
+                    };
+                    if let Some(run) = failed_tests.as_ref() {
+                        eval.decision = CompletionDecision::Continue {
+                            reason: "acceptance_tests_failed".to_string(),
+                            missing_requirements: vec![format!("acceptance_tests:{}", run.command)],
+                        };
+                    }
 
                     match eval.decision {
                         CompletionDecision::Complete => {
@@ -346,6 +1157,7 @@ impl TaskEngine {
                                         "missing_requirements": missing_requirements
                                     })),
                                 );
+                                emit_lifecycle(req, task_id, "continue", &labels);
                                 emit_progress(
                                     req,
                                     format!(
@@ -355,18 +1167,75 @@ impl TaskEngine {
                                     ),
                                 );
 
-                                consecutive_progress_only += 1;
-                                if consecutive_progress_only >= STALLED_PROGRESS_ONLY_LIMIT {
+                                if no_op {
+                                    let _ = self.store.append_event(
+                                        task_id,
+                                        "no_op_round",
+                                        Some(&serde_json::json!({ "round": round + 1 })),
+                                    );
+                                    emit_lifecycle(req, task_id, "no_op_round", &labels);
+                                    consecutive_progress_only += NO_OP_ROUND_STALL_WEIGHT;
+                                } else {
+                                    consecutive_progress_only += 1;
+                                }
+                                if completion_policy.repeated_response_limit > 0
+                                    && repeats >= completion_policy.repeated_response_limit
+                                {
+                                    TaskEngineState::Failed {
+                                        round,
+                                        reason: "repeated_response".to_string(),
+                                        error: None,
+                                    }
+                                } else if consecutive_progress_only
+                                    >= completion_policy.progress_stall_threshold
+                                {
                                     TaskEngineState::Failed {
                                         round,
                                         reason: "stalled_loop".to_string(),
                                         error: None,
                                     }
                                 } else {
-                                    req.history.push(ChatMessage::user(
-                                        "[Task Engine]\n任务尚未完成。请继续执行必要的工具操作并在有可验证结果后再给最终答复。不要仅汇报进行中状态。",
-                                    ));
-                                    TaskEngineState::Running { round: round + 1 }
+                                    let prompt =
+                                        match (failed_tests.as_ref(), req.output_format.as_ref()) {
+                                            (Some(run), _) => run.feedback_prompt(),
+                                            (None, Some(format))
+                                                if !format_violations.is_empty() =>
+                                            {
+                                                format.correction_prompt(&format_violations)
+                                            }
+                                            _ => continuation_prompt(
+                                                &reason,
+                                                &missing_requirements,
+                                                &unverified_claims,
+                                                &completion_policy.continuation_prompts,
+                                            ),
+                                        };
+                                    req.history.push(ChatMessage::user(prompt));
+                                    let _ = self.store.save_checkpoint(
+                                        task_id,
+                                        round + 1,
+                                        consecutive_progress_only,
+                                        write_verified,
+                                        req.history.get(history_base..).unwrap_or_default(),
+                                    );
+                                    spend.tool_calls = invocations.len();
+                                    let exceeded = spend.exceeded_limits(&self.cfg.budget);
+                                    if exceeded.is_empty() {
+                                        TaskEngineState::Running { round: round + 1 }
+                                    } else {
+                                        let _ = self.store.append_event(
+                                            task_id,
+                                            BUDGET_EXCEEDED_EVENT,
+                                            Some(&spend.event_payload(&self.cfg.budget, &exceeded)),
+                                        );
+                                        TaskEngineState::Blocked {
+                                            round,
+                                            reason: BUDGET_EXCEEDED_EVENT.to_string(),
+                                            remediation: spend.remediation(ReplyLanguage::of(
+                                                req.original_request,
+                                            )),
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -389,17 +1258,61 @@ impl TaskEngine {
                     }
                 }
                 TaskEngineState::Completed { round, response } => {
-                    let _ = self.store.update_status(task_id, TaskStatus::Completed);
+                    // A declared output format was validated as is: sources
+                    // and the usage line stay in events, not in the reply.
+                    let structured = req.output_format.is_some();
+                    let citations = if req.bypass_completion {
+                        Vec::new()
+                    } else {
+                        collect_citations(&invocations)
+                    };
+                    let response = if citations.is_empty() {
+                        response
+                    } else {
+                        let _ = self.store.append_event(
+                            task_id,
+                            CITATIONS_EVENT,
+                            Some(&serde_json::json!({ "sources": citations })),
+                        );
+                        if structured {
+                            response
+                        } else {
+                            append_sources(&response, &citations)
+                        }
+                    };
+                    let report = self
+                        .cost_report(task_id, ReplyLanguage::of(req.original_request))
+                        .filter(|_| !structured);
+                    let response = match report {
+                        Some(report) => format!("{response}\n\n{report}"),
+                        None => response,
+                    };
+                    let outbox_id = if self.cfg.reply_outbox {
+                        self.store
+                            .complete_with_outbox(task_id, &response, OUTBOX_DELIVERY_GRACE)
+                            .map_err(|err| {
+                                tracing::warn!("Failed to queue reply for task {task_id}: {err:#}")
+                            })
+                            .ok()
+                    } else {
+                        None
+                    };
+                    if outbox_id.is_none() {
+                        let _ = self.store.update_status(task_id, TaskStatus::Completed);
+                    }
                     let _ = self.store.append_event(
                         task_id,
                         "completed",
                         Some(&serde_json::json!({"round": round + 1})),
                     );
+                    emit_lifecycle(req, task_id, "completed", &labels);
                     emit_progress(req, format!("✅ 任务完成（第 {} 轮）。", round + 1));
                     return Ok(TaskRunOutcome {
                         task_id: task_id.to_string(),
                         final_response: response,
                         write_verified,
+                        status: TaskStatus::Completed,
+                        outbox_id,
                     });
                 }
                 TaskEngineState::Blocked {
@@ -407,32 +1320,78 @@ impl TaskEngine {
                     reason,
                     remediation,
                 } => {
+                    let resolution = BlockedResolution::new(&reason, &remediation);
                     let _ = self.store.update_status(task_id, TaskStatus::Blocked);
                     let _ = self.store.append_event(
                         task_id,
-                        "blocked",
-                        Some(&serde_json::json!({
-                            "reason": reason,
-                            "remediation": remediation,
-                            "round": round + 1
-                        })),
+                        BLOCKED_EVENT,
+                        Some(&resolution.event_payload(round + 1)),
+                    );
+                    emit_lifecycle(req, task_id, "blocked", &labels);
+                    emit_progress(
+                        req,
+                        if reason == BUDGET_EXCEEDED_EVENT {
+                            "💰 任务达到预算上限，等待确认是否继续。".to_string()
+                        } else {
+                            format!("⛔ 任务被阻塞（{}）。", resolution.kind.label())
+                        },
                     );
-                    emit_progress(req, "⛔ 任务被阻塞（缺少必要权限或访问边界不满足）。");
                     let blocked_summary =
                         format!("任务已阻塞：{}\n建议处理：{}", reason, remediation);
                     return Ok(TaskRunOutcome {
                         task_id: task_id.to_string(),
                         final_response: blocked_summary,
                         write_verified,
+                        status: TaskStatus::Blocked,
+                        outbox_id: None,
                     });
                 }
+                TaskEngineState::Failed { round, reason, .. } if reason == "cancelled" => {
+                    let _ = self.store.update_status(task_id, TaskStatus::Cancelled);
+                    emit_lifecycle(req, task_id, "cancelled", &labels);
+                    let _ = self.store.append_event(
+                        task_id,
+                        CANCELLED_BY_USER_EVENT,
+                        Some(&serde_json::json!({ "round": round + 1 })),
+                    );
+                    emit_progress(req, "🛑 任务已按你的要求取消。");
+                    return Err(
+                        anyhow::Error::new(ToolLoopCancelled).context("Task cancelled by the user")
+                    );
+                }
                 TaskEngineState::Failed {
                     round,
                     reason,
                     error,
                 } => {
                     let _ = self.store.update_status(task_id, TaskStatus::Failed);
+                    let stage = if reason == "timeout" {
+                        "timed_out"
+                    } else {
+                        "failed"
+                    };
+                    emit_lifecycle(req, task_id, stage, &labels);
                     match reason.as_str() {
+                        "timeout" => {
+                            let _ = self.store.append_event(
+                                task_id,
+                                "timed_out",
+                                Some(&serde_json::json!({
+                                    "reason":"timeout",
+                                    "timeout_secs": self.cfg.task_timeout_secs,
+                                    "error": error.unwrap_or_default(),
+                                    "round": round + 1
+                                })),
+                            );
+                            emit_progress(
+                                req,
+                                format!(
+                                    "⏱️ 任务超过 {} 秒未完成，已终止。",
+                                    self.cfg.task_timeout_secs
+                                ),
+                            );
+                            anyhow::bail!("Task timed out after {}s", self.cfg.task_timeout_secs);
+                        }
                         "provider_error" => {
                             let _ = self.store.append_event(
                                 task_id,
@@ -479,6 +1438,18 @@ impl TaskEngine {
                             emit_progress(req, "❌ 连续进度汇报未产出有效结果，任务失败。");
                             anyhow::bail!("Task stalled in repeated progress-only replies");
                         }
+                        "repeated_response" => {
+                            let _ = self.store.append_event(
+                                task_id,
+                                "failed",
+                                Some(&serde_json::json!({
+                                    "reason": "repeated_response",
+                                    "round": round + 1
+                                })),
+                            );
+                            emit_progress(req, "❌ 模型反复给出相同答复，任务失败。");
+                            anyhow::bail!("Task stalled repeating the same response");
+                        }
                         _ => {
                             let _ = self.store.append_event(
                                 task_id,
@@ -494,74 +1465,943 @@ impl TaskEngine {
         }
     }
 
-    async fn execute_single_round_with_retry(
+    /// Run the workspace's acceptance tests for a task that claims to be done,
+    /// log the run, and keep its output as an artifact. Returns the run when
+    /// the tests failed.
+    async fn run_acceptance_tests(
         &self,
         task_id: &str,
-        req: &mut TaskRunRequest<'_>,
-    ) -> Result<String> {
-        let mut last_error: Option<anyhow::Error> = None;
-        for attempt in 0..=self.cfg.provider_retry_limit {
-            let result = run_tool_call_loop(
-                req.provider,
-                req.history,
-                req.tools_registry,
-                req.observer,
-                req.provider_name,
-                req.model,
-                req.temperature,
-                true,
-                None,
-                req.channel,
-                req.multimodal,
-                req.max_tool_iterations,
-                req.cancellation_token.clone(),
-                req.on_delta.clone(),
-                req.hooks,
-                req.excluded_tools,
-            )
-            .await;
-
-            match result {
-                Ok(text) => return Ok(text),
-                Err(err) => {
-                    let retryable = is_retryable_provider_transport_error(&err);
-                    if retryable && attempt < self.cfg.provider_retry_limit {
-                        let _ = self.store.increment_provider_retry_count(task_id);
-                        let _ = self.store.append_event(
-                            task_id,
-                            "provider_retry",
-                            Some(&serde_json::json!({
-                                "attempt": attempt + 1,
-                                "error": format!("{err:#}")
-                            })),
-                        );
-                        emit_progress(
-                            req,
-                            format!(
-                                "🌐 Provider 连接异常，重试 {}/{} …",
-                                attempt + 1,
-                                self.cfg.provider_retry_limit
-                            ),
-                        );
-                        last_error = Some(err);
-                        continue;
-                    }
-                    return Err(err);
-                }
+        req: &TaskRunRequest<'_>,
+        round: usize,
+    ) -> Option<AcceptanceTestRun> {
+        let tests = &self.cfg.acceptance_tests;
+        emit_progress(req, format!("🧪 运行验收测试：{}", tests.command.trim()));
+        let workspace_dir = self.store.workspace_dir();
+        let run = acceptance_tests::run(tests, workspace_dir).await;
+        let log_path = match acceptance_tests::write_log(workspace_dir, task_id, round + 1, &run) {
+            Ok((path, checksum)) => {
+                let _ = self.store.upsert_artifact_verification(
+                    task_id,
+                    &path,
+                    Some(checksum.as_str()),
+                    run.passed,
+                );
+                Some(path)
             }
+            Err(err) => {
+                tracing::warn!("Failed to save acceptance test log for task {task_id}: {err:#}");
+                None
+            }
+        };
+        let _ = self.store.append_event(
+            task_id,
+            ACCEPTANCE_TESTS_EVENT,
+            Some(&serde_json::json!({
+                "round": round + 1,
+                "command": run.command,
+                "passed": run.passed,
+                "exit_code": run.exit_code,
+                "timed_out": run.timed_out,
+                "duration_ms": run.duration_ms,
+                "log": log_path,
+                "summary": run.summary(),
+            })),
+        );
+        if run.passed {
+            emit_progress(req, "✅ 验收测试通过。");
+            None
+        } else {
+            emit_progress(req, format!("❌ 验收测试未通过：{}", run.summary()));
+            Some(run)
+        }
+    }
+
+    /// Re-read every claimed write target from disk and store its SHA-256.
+    /// Missing or empty files turn a `Complete` decision into an
+    /// `artifact_missing` continue (or extend an existing continue's missing
+    /// requirements). Returns the paths that are present on disk.
+    async fn verify_claimed_artifacts(
+        &self,
+        task_id: &str,
+        round: usize,
+        paths: &[String],
+        rules: &[ArtifactVerificationRule],
+        decision: &mut CompletionDecision,
+    ) -> Vec<String> {
+        let verifier = ArtifactVerifier::new(self.store.workspace_dir()).with_rules(rules.to_vec());
+        let checks = verifier.verify_all(paths).await;
+
+        let mut present = Vec::new();
+        let mut failed = Vec::new();
+        for verification in checks {
+            let (state, reason) = match verification.check {
+                ArtifactCheck::Present { checksum, .. } => {
+                    let _ = self.store.upsert_artifact_verification(
+                        task_id,
+                        &verification.path,
+                        Some(checksum.as_str()),
+                        true,
+                    );
+                    present.push(verification.path);
+                    continue;
+                }
+                ArtifactCheck::Confirmed { .. } => {
+                    let _ = self.store.upsert_artifact_verification(
+                        task_id,
+                        &verification.path,
+                        None,
+                        true,
+                    );
+                    present.push(verification.path);
+                    continue;
+                }
+                ArtifactCheck::Empty => ("empty", None),
+                ArtifactCheck::Missing => ("missing", None),
+                ArtifactCheck::Failed { reason } => ("failed", Some(reason)),
+            };
+            let _ =
+                self.store
+                    .upsert_artifact_verification(task_id, &verification.path, None, false);
+            failed.push((verification.path, verification.strategy, state, reason));
+        }
+        if failed.is_empty() {
+            return present;
+        }
+
+        let _ = self.store.append_event(
+            task_id,
+            "artifact_check_failed",
+            Some(&serde_json::json!({
+                "round": round + 1,
+                "artifacts": failed
+                    .iter()
+                    .map(|(path, strategy, state, reason)| serde_json::json!({
+                        "path": path,
+                        "strategy": strategy,
+                        "state": state,
+                        "reason": reason,
+                    }))
+                    .collect::<Vec<_>>()
+            })),
+        );
+        let missing: Vec<String> = failed
+            .into_iter()
+            .map(|(path, ..)| format!("artifact:{path}"))
+            .collect();
+        match decision {
+            CompletionDecision::Complete => {
+                *decision = CompletionDecision::Continue {
+                    reason: "artifact_missing".to_string(),
+                    missing_requirements: missing,
+                };
+            }
+            CompletionDecision::Continue {
+                missing_requirements,
+                ..
+            } => {
+                for requirement in missing {
+                    if !missing_requirements.contains(&requirement) {
+                        missing_requirements.push(requirement);
+                    }
+                }
+            }
+            CompletionDecision::Blocked { .. } | CompletionDecision::Failed { .. } => {}
+        }
+        present
+    }
+
+    /// Record paths the reply claims to have written as unverified artifacts
+    /// (unless a row already exists) and return the ones not yet confirmed
+    /// on disk this round, in workspace-relative form.
+    fn record_claimed_paths(
+        &self,
+        task_id: &str,
+        round: usize,
+        claimed: &[String],
+        present: &[String],
+    ) -> Vec<String> {
+        let workspace_dir = self.store.workspace_dir();
+        let present: Vec<String> = present
+            .iter()
+            .map(|path| workspace_relative(path, workspace_dir))
+            .collect();
+        let mut unverified = Vec::new();
+        for path in claimed {
+            let path = workspace_relative(path, workspace_dir);
+            if present.contains(&path) || unverified.contains(&path) {
+                continue;
+            }
+            match self.store.find_artifact(task_id, &path) {
+                Ok(Some(existing)) if existing.verified => continue,
+                Ok(Some(_)) => {}
+                _ => {
+                    let _ = self
+                        .store
+                        .upsert_artifact_verification(task_id, &path, None, false);
+                }
+            }
+            unverified.push(path);
+        }
+        if !unverified.is_empty() {
+            let _ = self.store.append_event(
+                task_id,
+                "claimed_paths",
+                Some(&serde_json::json!({ "round": round + 1, "paths": unverified })),
+            );
+        }
+        unverified
+    }
+
+    /// Detect MIME types and build previews for verified artifacts. Files that
+    /// cannot be read (moved, outside reach) are skipped.
+    async fn record_artifact_previews(&self, task_id: &str, paths: &[String]) {
+        let verifier = ArtifactVerifier::new(self.store.workspace_dir());
+        for path in paths {
+            let absolute = verifier.resolve(path);
+            let Ok(Ok(preview)) =
+                tokio::task::spawn_blocking(move || inspect_artifact(&absolute)).await
+            else {
+                continue;
+            };
+            let _ = self.store.set_artifact_preview(task_id, path, &preview);
+        }
+    }
+
+    /// Ask the LLM judge to classify a round's reply. Returns `None` (keep the
+    /// heuristic decision) when the judge errors or is below the policy's
+    /// confidence floor; every verdict is recorded as an `llm_judge` event.
+    async fn llm_judge_decision(
+        &self,
+        task_id: &str,
+        req: &TaskRunRequest<'_>,
+        round: usize,
+        response: &str,
+        invocations: &[ToolInvocation],
+        policy: &CompletionPolicy,
+    ) -> Option<CompletionDecision> {
+        let request = CompletionJudgeRequest {
+            provider: req.provider,
+            model: policy.judge_model.as_deref().unwrap_or(req.model),
+            original_request: req.original_request,
+            response,
+            invocations,
+        };
+        let timeout = Duration::from_millis(policy.judge_timeout_ms);
+        match judge_completion(request, timeout).await {
+            Ok(verdict) => {
+                let applied = verdict.confidence >= policy.judge_min_confidence;
+                let _ = self.store.append_event(
+                    task_id,
+                    "llm_judge",
+                    Some(&serde_json::json!({
+                        "complete": verdict.complete,
+                        "confidence": verdict.confidence,
+                        "reason": verdict.reason,
+                        "applied": applied,
+                        "round": round + 1
+                    })),
+                );
+                if !applied {
+                    return None;
+                }
+                Some(if verdict.complete {
+                    CompletionDecision::Complete
+                } else {
+                    CompletionDecision::Continue {
+                        reason: "llm_judge_continue".to_string(),
+                        missing_requirements: Vec::new(),
+                    }
+                })
+            }
+            Err(err) => {
+                let _ = self.store.append_event(
+                    task_id,
+                    "llm_judge_error",
+                    Some(&serde_json::json!({
+                        "error": format!("{err:#}"),
+                        "round": round + 1
+                    })),
+                );
+                None
+            }
+        }
+    }
+
+    /// Usage line for a completed task's reply, with `report_cost`.
+    fn cost_report(&self, task_id: &str, language: ReplyLanguage) -> Option<String> {
+        if !self.cfg.report_cost {
+            return None;
+        }
+        match self.store.usage_totals(task_id) {
+            Ok(totals) => usage_report(&totals, language),
+            Err(err) => {
+                tracing::warn!("Failed to total usage for task {task_id}: {err:#}");
+                None
+            }
+        }
+    }
+
+    /// Persist hook vetoes, approval denials, tool exclusions, and guardrail
+    /// retries so post-mortems can show why the agent deviated, plus every
+    /// executed tool call, which is also appended to `invocations`, and
+    /// every call stopped at its time limit.
+    fn record_tool_loop_log(
+        &self,
+        task_id: &str,
+        log: ToolLoopLog,
+        invocations: &mut Vec<ToolInvocation>,
+    ) {
+        let decisions = log
+            .policy_decisions
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let executed: Vec<ToolInvocation> = log
+            .invocations
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut events: Vec<NewTaskEvent> = decisions
+            .into_iter()
+            .map(|decision| {
+                NewTaskEvent::new(
+                    "policy_decision",
+                    Some(serde_json::json!({
+                        "source": decision.source,
+                        "decision": decision.decision,
+                        "tool": decision.tool,
+                        "arguments_hash": decision.arguments_hash,
+                        "reason": decision.reason,
+                        "iteration": decision.iteration
+                    })),
+                )
+            })
+            .collect();
+        events.extend(executed.iter().map(|invocation| {
+            NewTaskEvent::new(TOOL_INVOCATION_EVENT, serde_json::to_value(invocation).ok())
+        }));
+        // Files delivered to the user with `send_file` are artifacts of the run.
+        events.extend(
+            executed
+                .iter()
+                .filter(|invocation| invocation.name == "send_file" && invocation.status().is_ok())
+                .map(|invocation| {
+                    NewTaskEvent::new(
+                        "artifact_sent",
+                        Some(serde_json::json!({
+                            "path": invocation.arguments.get("path"),
+                            "caption": invocation.arguments.get("caption"),
+                            "iteration": invocation.iteration
+                        })),
+                    )
+                }),
+        );
+        let timeouts = log
+            .tool_timeouts
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        events.extend(timeouts.into_iter().map(|timeout| {
+            NewTaskEvent::new(
+                "tool_timeout",
+                Some(serde_json::json!({
+                    "tool": timeout.tool,
+                    "arguments_hash": timeout.arguments_hash,
+                    "timeout_secs": timeout.timeout_secs,
+                    "iteration": timeout.iteration
+                })),
+            )
+        }));
+        let _ = self.store.append_events(task_id, &events);
+        invocations.extend(executed);
+    }
+
+    /// Run the task's pending steps, if it has or gets any, one bounded tool
+    /// loop each. Returns the state to stop in when a step fails or the run
+    /// times out or hits its budget; `None` continues with the final round.
+    async fn run_steps(
+        &self,
+        task_id: &str,
+        req: &mut TaskRunRequest<'_>,
+        history_base: usize,
+        enabled_tools: &[String],
+        watchdog: &TaskWatchdog,
+        heartbeat: &TaskHeartbeat,
+        invocations: &mut Vec<ToolInvocation>,
+        spend: &mut TaskSpend,
+    ) -> Option<TaskEngineState> {
+        let mut steps = self.store.list_task_steps(task_id).unwrap_or_default();
+        if steps.is_empty() {
+            let (source, descriptions) = if !req.steps.is_empty() {
+                ("caller", req.steps.clone())
+            } else if self.cfg.decompose_steps {
+                let descriptions = self.decompose_steps(task_id, req, enabled_tools).await;
+                if descriptions.len() < MIN_DECOMPOSED_STEPS {
+                    return None;
+                }
+                ("model", descriptions)
+            } else {
+                return None;
+            };
+            if let Err(err) = self.store.set_task_steps(task_id, &descriptions) {
+                tracing::warn!("Failed to record steps for task {task_id}: {err:#}");
+                return None;
+            }
+            let _ = self.store.append_event(
+                task_id,
+                STEPS_RECORDED_EVENT,
+                Some(&serde_json::json!({ "source": source, "steps": descriptions })),
+            );
+            steps = self.store.list_task_steps(task_id).unwrap_or_default();
+        }
+
+        let total = steps.len();
+        if let Some(recap) = completed_steps_message(&steps) {
+            req.history.push(ChatMessage::user(recap));
+        }
+        for step in steps
+            .iter()
+            .filter(|step| step.status != TaskStepStatus::Completed)
+        {
+            let number = step.step_index + 1;
+            if let Some(reason) = watchdog.interruption() {
+                return Some(TaskEngineState::Failed {
+                    round: 0,
+                    reason: reason.to_string(),
+                    error: None,
+                });
+            }
+            emit_progress(
+                req,
+                format!("🪜 步骤 {number}/{total}：{}", step.description),
+            );
+            let _ = self.store.update_task_step(
+                task_id,
+                step.step_index,
+                TaskStepStatus::Running,
+                None,
+            );
+            self.compact_history(task_id, req, history_base).await;
+            req.history
+                .push(ChatMessage::user(step_prompt(step, total)));
+            match self
+                .execute_single_round_with_retry(task_id, req, heartbeat, invocations, spend)
+                .await
+            {
+                Ok(response) => {
+                    let _ = self.store.update_task_step(
+                        task_id,
+                        step.step_index,
+                        TaskStepStatus::Completed,
+                        Some(&response),
+                    );
+                    let _ = self.store.append_event(
+                        task_id,
+                        STEP_COMPLETED_EVENT,
+                        Some(&serde_json::json!({ "step": number, "response": response })),
+                    );
+                    emit_progress(req, format!("✅ 步骤 {number}/{total} 完成。"));
+                }
+                Err(err) => {
+                    if let Some(confirmation) = ConfirmationRequired::find(&err) {
+                        // The step reruns once the user approves the call.
+                        let _ = self.store.update_task_step(
+                            task_id,
+                            step.step_index,
+                            TaskStepStatus::Pending,
+                            None,
+                        );
+                        return Some(self.block_on_confirmation(task_id, 0, confirmation));
+                    }
+                    let error = format!("{err:#}");
+                    let _ = self.store.update_task_step(
+                        task_id,
+                        step.step_index,
+                        TaskStepStatus::Failed,
+                        None,
+                    );
+                    let _ = self.store.append_event(
+                        task_id,
+                        STEP_FAILED_EVENT,
+                        Some(&serde_json::json!({ "step": number, "error": error })),
+                    );
+                    let reason = watchdog.interruption().unwrap_or("provider_error");
+                    return Some(TaskEngineState::Failed {
+                        round: 0,
+                        reason: reason.to_string(),
+                        error: Some(error),
+                    });
+                }
+            }
+            spend.tool_calls = invocations.len();
+            let exceeded = spend.exceeded_limits(&self.cfg.budget);
+            if !exceeded.is_empty() {
+                let _ = self.store.append_event(
+                    task_id,
+                    BUDGET_EXCEEDED_EVENT,
+                    Some(&spend.event_payload(&self.cfg.budget, &exceeded)),
+                );
+                return Some(TaskEngineState::Blocked {
+                    round: 0,
+                    reason: BUDGET_EXCEEDED_EVENT.to_string(),
+                    remediation: spend.remediation(ReplyLanguage::of(req.original_request)),
+                });
+            }
+        }
+        req.history.push(ChatMessage::user(STEPS_DONE_PROMPT));
+        None
+    }
+
+    /// Record the tool call `confirmation` holds back and block the task
+    /// until the user approves it.
+    fn block_on_confirmation(
+        &self,
+        task_id: &str,
+        round: usize,
+        confirmation: &ConfirmationRequired,
+    ) -> TaskEngineState {
+        let _ = self.store.append_event(
+            task_id,
+            TOOL_CONFIRMATION_EVENT,
+            Some(&tool_confirmation_payload(confirmation)),
+        );
+        TaskEngineState::Blocked {
+            round,
+            reason: TOOL_CONFIRMATION_EVENT.to_string(),
+            remediation: format!(
+                "Approve `{}` ({}): {}",
+                confirmation.tool, confirmation.reason, confirmation.summary
+            ),
+        }
+    }
+
+    /// Ask the model to split the request into numbered steps; empty when
+    /// the call fails.
+    async fn decompose_steps(
+        &self,
+        task_id: &str,
+        req: &TaskRunRequest<'_>,
+        enabled_tools: &[String],
+    ) -> Vec<String> {
+        let mut messages = req.history.clone();
+        messages.push(ChatMessage::user(plan_prompt(enabled_tools)));
+        match req
+            .provider
+            .chat_with_history(&messages, req.model, req.temperature)
+            .await
+        {
+            Ok(text) => parse_plan_steps(&text),
+            Err(err) => {
+                let _ = self.store.append_event(
+                    task_id,
+                    "step_decomposition_failed",
+                    Some(&serde_json::json!({ "error": format!("{err:#}") })),
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Summarize the run's older turns once the history outgrows
+    /// `context_max_tokens`. Messages before `history_base` (the prompt and
+    /// the caller's conversation) are kept as they are.
+    async fn compact_history(
+        &self,
+        task_id: &str,
+        req: &mut TaskRunRequest<'_>,
+        history_base: usize,
+    ) {
+        let manager = ContextManager::new(self.cfg.context_max_tokens);
+        let Some(compaction) = manager
+            .compact(req.history, history_base, req.provider, req.model)
+            .await
+        else {
+            return;
+        };
+        let _ = self.store.append_event(
+            task_id,
+            CONTEXT_COMPACTED_EVENT,
+            Some(&serde_json::json!(compaction)),
+        );
+    }
+
+    /// Snapshot the workspace off the async runtime; a large tree takes a
+    /// while to walk. `None` if the blocking task panicked.
+    async fn capture_workspace(&self) -> Option<WorkspaceSnapshot> {
+        let root = self.store.workspace_dir().to_path_buf();
+        tokio::task::spawn_blocking(move || WorkspaceSnapshot::capture(&root))
+            .await
+            .ok()
+    }
+
+    async fn record_files_changed(&self, task_id: &str, round: usize, before: &WorkspaceSnapshot) {
+        let Some(after) = self.capture_workspace().await else {
+            return;
+        };
+        let changes = before.changes_since(&after);
+        if changes.is_empty() {
+            return;
+        }
+        let _ = self.store.append_event(
+            task_id,
+            FILES_CHANGED_EVENT,
+            Some(&serde_json::json!({
+                "round": round + 1,
+                "created": changes.created,
+                "modified": changes.modified,
+                "deleted": changes.deleted,
+                "truncated": changes.truncated,
+            })),
+        );
+    }
+
+    async fn execute_single_round_with_retry(
+        &self,
+        task_id: &str,
+        req: &mut TaskRunRequest<'_>,
+        heartbeat: &TaskHeartbeat,
+        invocations: &mut Vec<ToolInvocation>,
+        spend: &mut TaskSpend,
+    ) -> Result<String> {
+        let mut last_error: Option<anyhow::Error> = None;
+        'providers: loop {
+            for attempt in 0..=self.cfg.provider_retry_limit {
+                heartbeat.beat();
+                let run_log = ToolLoopLog {
+                    on_invocation: Some(
+                        heartbeat.beat_on_tool_calls(
+                            req.progress_reporter
+                                .clone()
+                                .filter(|_| self.cfg.report_tool_calls)
+                                .map(tool_call_progress),
+                        ),
+                    ),
+                    ..ToolLoopLog::default()
+                };
+                let result = run_tool_call_loop(
+                    req.provider,
+                    req.history,
+                    req.tools_registry,
+                    req.observer,
+                    req.provider_name,
+                    req.model,
+                    req.temperature,
+                    true,
+                    None,
+                    req.channel,
+                    req.multimodal,
+                    req.max_tool_iterations,
+                    req.cancellation_token.clone(),
+                    req.on_delta.clone(),
+                    req.hooks,
+                    req.excluded_tools,
+                    Some(&run_log),
+                    self.cfg.max_parallel_tools,
+                )
+                .await;
+                let input_tokens = run_log.input_tokens.load(Ordering::Relaxed);
+                let output_tokens = run_log.output_tokens.load(Ordering::Relaxed);
+                let cost_usd = spend.record_tokens(
+                    req.provider_name,
+                    req.model,
+                    input_tokens,
+                    output_tokens,
+                    &self.cfg.model_prices,
+                );
+                let finish_reasons = run_log
+                    .finish_reasons
+                    .lock()
+                    .map(|reasons| reasons.clone())
+                    .unwrap_or_default();
+                if input_tokens > 0 || output_tokens > 0 || !finish_reasons.is_empty() {
+                    let _ = self.store.append_event(
+                        task_id,
+                        "llm_usage",
+                        Some(&serde_json::json!({
+                            "provider": req.provider_name,
+                            "model": req.model,
+                            "input_tokens": input_tokens,
+                            "output_tokens": output_tokens,
+                            "cost_usd": cost_usd,
+                            "finish_reasons": finish_reasons,
+                        })),
+                    );
+                }
+                self.record_tool_loop_log(task_id, run_log, invocations);
+
+                match result {
+                    Ok(text) => return Ok(text),
+                    Err(err) => {
+                        // A call held for approval fails the same way on every provider.
+                        let retryable = ConfirmationRequired::find(&err).is_none()
+                            && self.retry_classifier.is_retryable(&err);
+                        let cancelled = req
+                            .cancellation_token
+                            .as_ref()
+                            .is_some_and(CancellationToken::is_cancelled);
+                        if retryable && !cancelled && attempt < self.cfg.provider_retry_limit {
+                            let wait = self
+                                .retry_classifier
+                                .retry_after(&err)
+                                .map(|wait| wait.min(MAX_PROVIDER_RETRY_WAIT));
+                            let _ = self.store.increment_provider_retry_count(task_id);
+                            let _ = self.store.append_event(
+                                task_id,
+                                "provider_retry",
+                                Some(&serde_json::json!({
+                                    "attempt": attempt + 1,
+                                    "error": format!("{err:#}"),
+                                    "wait_ms": wait.map(|wait| wait.as_millis()),
+                                })),
+                            );
+                            emit_progress(
+                                req,
+                                format!(
+                                    "🌐 Provider 连接异常，重试 {}/{} …",
+                                    attempt + 1,
+                                    self.cfg.provider_retry_limit
+                                ),
+                            );
+                            if let Some(wait) = wait.filter(|wait| !wait.is_zero()) {
+                                emit_progress(
+                                    req,
+                                    format!(
+                                        "⏳ Provider 限流，{} 秒后重试 …",
+                                        wait.as_secs().max(1)
+                                    ),
+                                );
+                                let sleep = tokio::time::sleep(wait);
+                                match req.cancellation_token.as_ref() {
+                                    Some(token) => {
+                                        tokio::select! {
+                                            () = token.cancelled() => return Err(err),
+                                            () = sleep => {}
+                                        }
+                                    }
+                                    None => sleep.await,
+                                }
+                            }
+                            last_error = Some(err);
+                            continue;
+                        }
+                        if retryable && !cancelled && !req.fallback_providers.is_empty() {
+                            self.fail_over(task_id, req, &err);
+                            last_error = Some(err);
+                            continue 'providers;
+                        }
+                        return Err(err);
+                    }
+                }
+            }
+            break;
         }
 
         Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Unknown task round error")))
     }
+
+    /// Switch `req` to its next fallback provider for the rest of the run.
+    fn fail_over(&self, task_id: &str, req: &mut TaskRunRequest<'_>, err: &anyhow::Error) {
+        let next = req.fallback_providers.remove(0);
+        let _ = self.store.append_event(
+            task_id,
+            "provider_failover",
+            Some(&serde_json::json!({
+                "from_provider": req.provider_name,
+                "from_model": req.model,
+                "to_provider": next.provider_name,
+                "to_model": next.model,
+                "error": format!("{err:#}"),
+            })),
+        );
+        emit_progress(
+            req,
+            format!(
+                "🔀 {} 持续失败，切换到 {} ({}) …",
+                req.provider_name, next.provider_name, next.model
+            ),
+        );
+        req.provider = next.provider;
+        req.provider_name = next.provider_name;
+        req.model = next.model;
+    }
+}
+
+/// Deadline for a single task run. When it fires, the run token is cancelled so
+/// in-flight provider and tool calls abort and the state machine records a
+/// `timeout` failure.
+struct TaskWatchdog {
+    fired: Arc<AtomicBool>,
+    canceller: RunCanceller,
+    handle: Option<tokio::task::JoinHandle<()>>,
+}
+
+/// Stops one run at the user's request; see [`TaskEngine::cancel_task`].
+#[derive(Clone)]
+struct RunCanceller {
+    token: CancellationToken,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl RunCanceller {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.token.cancel();
+    }
+}
+
+impl TaskWatchdog {
+    fn arm(timeout_secs: u64, token: CancellationToken) -> Self {
+        let fired = Arc::new(AtomicBool::new(false));
+        let canceller = RunCanceller {
+            token: token.clone(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        if timeout_secs == 0 {
+            return Self {
+                fired,
+                canceller,
+                handle: None,
+            };
+        }
+
+        let flag = Arc::clone(&fired);
+        let handle = tokio::spawn(async move {
+            tokio::select! {
+                () = tokio::time::sleep(Duration::from_secs(timeout_secs)) => {
+                    flag.store(true, Ordering::SeqCst);
+                    token.cancel();
+                }
+                () = token.cancelled() => {}
+            }
+        });
+        Self {
+            fired,
+            canceller,
+            handle: Some(handle),
+        }
+    }
+
+    fn canceller(&self) -> RunCanceller {
+        self.canceller.clone()
+    }
+
+    /// Why the run token was cancelled: `"timeout"` when the watchdog fired,
+    /// `"cancelled"` when the user cancelled the task.
+    fn interruption(&self) -> Option<&'static str> {
+        if self.fired.load(Ordering::SeqCst) {
+            Some("timeout")
+        } else if self.canceller.cancelled.load(Ordering::SeqCst) {
+            Some("cancelled")
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for TaskWatchdog {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+/// Keeps a task claim alive while the run is in progress.
+struct TaskClaimRenewal {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl TaskClaimRenewal {
+    fn start(store: TaskStore, task_id: &str, worker_id: &str) -> Self {
+        let task_id = task_id.to_string();
+        let worker_id = worker_id.to_string();
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TASK_CLAIM_LEASE / 3);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                match store.try_claim(&task_id, &worker_id, TASK_CLAIM_LEASE) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        tracing::warn!(
+                            "Task {task_id} was reclaimed by another worker during the run"
+                        );
+                        break;
+                    }
+                    Err(err) => tracing::warn!("Failed to renew claim on task {task_id}: {err}"),
+                }
+            }
+        });
+        Self { handle }
+    }
 }
 
-fn is_retryable_provider_transport_error(err: &anyhow::Error) -> bool {
-    let lower = format!("{err:#}").to_ascii_lowercase();
-    lower.contains("transport error")
-        || lower.contains("error sending request for url")
-        || lower.contains("connection reset")
-        || lower.contains("connection refused")
-        || lower.contains("timed out")
+impl Drop for TaskClaimRenewal {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Appends a `heartbeat` event every [`TASK_HEARTBEAT_INTERVAL`] in which the
+/// run made progress — a provider round started or a tool call finished — so
+/// the stuck-task detector can tell live runs from ones hung in a provider or
+/// tool call, or abandoned. Stops when dropped.
+struct TaskHeartbeat {
+    progress: Arc<AtomicU64>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl TaskHeartbeat {
+    fn start(store: TaskStore, task_id: &str) -> Self {
+        let task_id = task_id.to_string();
+        let progress = Arc::new(AtomicU64::new(0));
+        let observed = Arc::clone(&progress);
+        let handle = tokio::spawn(async move {
+            let started = std::time::Instant::now();
+            let mut beaten = 0;
+            let mut interval = tokio::time::interval(TASK_HEARTBEAT_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let progress = observed.load(Ordering::SeqCst);
+                if progress == beaten {
+                    continue;
+                }
+                beaten = progress;
+                if let Err(err) = store.append_event(
+                    &task_id,
+                    TASK_HEARTBEAT_EVENT,
+                    Some(&serde_json::json!({
+                        "elapsed_secs": started.elapsed().as_secs(),
+                        "progress": progress,
+                    })),
+                ) {
+                    tracing::warn!("Failed to record heartbeat for task {task_id}: {err}");
+                }
+            }
+        });
+        Self { progress, handle }
+    }
+
+    /// Record that the run moved forward.
+    fn beat(&self) {
+        self.progress.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Notifier that beats on every executed tool call, then forwards it to
+    /// `inner`.
+    fn beat_on_tool_calls(&self, inner: Option<ToolInvocationNotifier>) -> ToolInvocationNotifier {
+        let progress = Arc::clone(&self.progress);
+        Arc::new(move |invocation: &ToolInvocation| {
+            progress.fetch_add(1, Ordering::SeqCst);
+            if let Some(inner) = inner.as_ref() {
+                inner(invocation);
+            }
+        })
+    }
+}
+
+impl Drop for TaskHeartbeat {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
 }
 
 fn enabled_tools_for_contract(
@@ -585,29 +2425,138 @@ fn should_invoke_gray_zone_verifier(
         && reason == "unknown_contract_non_terminal_update"
 }
 
+/// The judge only arbitrates between finished and in-progress replies;
+/// blocked/failed verdicts and guardrail retries stand as evaluated.
+fn llm_judge_may_override(decision: &CompletionDecision) -> bool {
+    match decision {
+        CompletionDecision::Complete => true,
+        CompletionDecision::Continue { reason, .. } => reason != "guardrail_notice",
+        CompletionDecision::Blocked { .. } | CompletionDecision::Failed { .. } => false,
+    }
+}
+
 fn gray_zone_completion_allowed(task_type: TaskType, missing_requirements: &[String]) -> bool {
     task_type == TaskType::Unknown && missing_requirements.is_empty()
 }
 
+/// Forward each executed tool call to `reporter` as a one-line status.
+fn tool_call_progress(reporter: TaskProgressReporter) -> ToolInvocationNotifier {
+    Arc::new(move |invocation: &ToolInvocation| {
+        let status = if invocation.success { "✅" } else { "❌" };
+        reporter(format!("🔧 调用工具 `{}` {status}", invocation.name));
+    })
+}
+
 fn emit_progress(req: &TaskRunRequest<'_>, message: impl Into<String>) {
     if let Some(reporter) = req.progress_reporter.as_ref() {
         reporter(message.into());
     }
 }
 
-fn summarize_round_output_for_progress(response: &str) -> String {
-    let normalized = response.split_whitespace().collect::<Vec<_>>().join(" ");
-    if normalized.is_empty() {
-        return "（空响应）".to_string();
-    }
-    normalized
-}
-
-fn explain_continue_reason(reason: &str) -> &str {
+/// `transcript_message` events for the history messages a round produced.
+fn round_transcript_events(round: usize, messages: &[ChatMessage]) -> Vec<NewTaskEvent> {
+    messages
+        .iter()
+        .map(|message| {
+            NewTaskEvent::new(
+                TRANSCRIPT_MESSAGE_EVENT,
+                Some(transcript_message_payload(round, message)),
+            )
+        })
+        .collect()
+}
+
+fn emit_lifecycle(req: &TaskRunRequest<'_>, task_id: &str, stage: &str, labels: &[String]) {
+    req.observer.record_event(&ObserverEvent::TaskLifecycle {
+        task_id: task_id.to_string(),
+        channel: req.channel.to_string(),
+        stage: stage.to_string(),
+        labels: labels.to_vec(),
+    });
+}
+
+fn effective_task_labels(caller_labels: &[String], task_type: TaskType) -> Vec<String> {
+    let mut labels: Vec<String> = caller_labels
+        .iter()
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect();
+    let type_label = format!("task_type:{}", task_type.as_str());
+    if !labels.contains(&type_label) {
+        labels.push(type_label);
+    }
+    labels
+}
+
+/// A round is a no-op when it produced no tool calls or results and its
+/// response adds almost nothing over the previous round's response.
+fn is_no_op_round(
+    round_messages: &[ChatMessage],
+    previous_response: Option<&str>,
+    response: &str,
+) -> bool {
+    let Some(previous_response) = previous_response else {
+        return false;
+    };
+    if round_messages
+        .iter()
+        .any(|msg| matches!(classify_message(msg), "tool_call" | "tool_result"))
+    {
+        return false;
+    }
+
+    let current = char_bigrams(response);
+    if current.is_empty() {
+        return true;
+    }
+    let previous = char_bigrams(previous_response);
+    let novel = current.difference(&previous).count();
+    let novelty = novel as f64 / current.len() as f64;
+    novelty < NO_OP_ROUND_MIN_NOVELTY
+}
+
+/// Hash of `response` with case, whitespace, and punctuation removed, so
+/// answers that differ only in formatting count as the same answer.
+fn response_fingerprint(response: &str) -> u64 {
+    let mut hasher = std::hash::DefaultHasher::new();
+    for c in response
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+    {
+        c.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Bigrams over lowercase alphanumeric characters; works for both
+/// whitespace-delimited and CJK text.
+fn char_bigrams(text: &str) -> std::collections::HashSet<(char, char)> {
+    let chars: Vec<char> = text
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+fn summarize_round_output_for_progress(response: &str) -> String {
+    let normalized = response.split_whitespace().collect::<Vec<_>>().join(" ");
+    if normalized.is_empty() {
+        return "（空响应）".to_string();
+    }
+    normalized
+}
+
+fn explain_continue_reason(reason: &str) -> &str {
     match reason {
         "missing_required_evidence" => "缺少合同要求的工具执行证据",
         "unknown_contract_non_terminal_update" => "未知任务类型且回复仍处于进行中",
         "guardrail_notice" => "触发 guardrail 继续执行",
+        "llm_judge_continue" => "完成度评审模型判定回复仍未完成",
+        "artifact_missing" => "声称写入的文件不存在或为空",
+        "output_format_mismatch" => "最终回复不符合声明的输出格式",
+        "acceptance_tests_failed" => "验收测试未通过",
         _ => reason,
     }
 }
@@ -615,19 +2564,69 @@ fn explain_continue_reason(reason: &str) -> &str {
 #[cfg(test)]
 mod tests {
     use super::{
-        is_retryable_provider_transport_error, TaskEngine, TaskEngineConfig, TaskRunRequest,
+        is_no_op_round, strip_raw_mode_command, ProviderFallback, TaskEngine, TaskEngineConfig,
+        TaskRunRequest, TranscriptFormat,
     };
+    use crate::agent::acceptance_tests::ACCEPTANCE_TESTS_EVENT;
     use crate::agent::gray_zone_verifier::{
         GrayZoneVerdict, GrayZoneVerificationRequest, GrayZoneVerifier,
     };
+    use crate::agent::output_format::OutputFormat;
+    use crate::agent::retry_classifier::{
+        DefaultRetryClassifier, PatternRetryClassifier, RetryClassifier,
+    };
+    use crate::agent::task_blocked::{BlockedKind, BlockedResolution, BLOCKED_EVENT};
+    use crate::agent::task_citations::CITATIONS_EVENT;
+    use crate::agent::task_types::{TaskStatus, TaskStepStatus};
+    use crate::agent::workspace_transaction::{WorkspaceTransaction, ROLLED_BACK_EVENT};
+    use crate::config::{CompletionEvaluator, CompletionPolicy, ProviderParams};
     use crate::observability::NoopObserver;
     use crate::providers::{ChatMessage, Provider};
     use crate::tools::Tool;
     use async_trait::async_trait;
+    use std::collections::HashMap;
     use std::sync::atomic::{AtomicUsize, Ordering};
-    use std::sync::{Arc, Mutex};
+    use std::sync::{Arc, Mutex, OnceLock};
     use tempfile::TempDir;
 
+    /// Request from `sender-a` on iMessage with every option at its default;
+    /// tests override fields with `TaskRunRequest { .., ..test_request(..) }`.
+    fn test_request<'a>(
+        provider: &'a dyn Provider,
+        history: &'a mut Vec<ChatMessage>,
+        tools_registry: &'a [Box<dyn Tool>],
+        original_request: &'a str,
+    ) -> TaskRunRequest<'a> {
+        static MULTIMODAL: OnceLock<crate::config::MultimodalConfig> = OnceLock::new();
+        TaskRunRequest {
+            channel: "imessage",
+            sender_key: "sender-a",
+            reply_target: "sender-a",
+            original_request,
+            provider,
+            history,
+            tools_registry,
+            observer: &NoopObserver,
+            provider_name: "test-provider",
+            model: "test-model",
+            temperature: 0.0,
+            multimodal: MULTIMODAL.get_or_init(crate::config::MultimodalConfig::default),
+            max_tool_iterations: 5,
+            cancellation_token: None,
+            on_delta: None,
+            hooks: None,
+            excluded_tools: &[],
+            progress_reporter: None,
+            labels: Vec::new(),
+            bypass_completion: false,
+            output_format: None,
+            env: HashMap::new(),
+            fallback_providers: Vec::new(),
+            steps: Vec::new(),
+            provider_params: ProviderParams::default(),
+        }
+    }
+
     struct ScriptedProvider {
         responses: Mutex<Vec<anyhow::Result<String>>>,
     }
@@ -657,52 +2656,1137 @@ mod tests {
         }
     }
 
-    struct ScriptedGrayZoneVerifier {
-        results: Mutex<Vec<anyhow::Result<GrayZoneVerdict>>>,
-        call_count: AtomicUsize,
+    /// `file_read` stand-in that always fails with a workspace-access error.
+    struct DeniedReadTool;
+
+    #[async_trait]
+    impl Tool for DeniedReadTool {
+        fn name(&self) -> &str {
+            "file_read"
+        }
+
+        fn description(&self) -> &str {
+            "Rejects every path"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        async fn execute(
+            &self,
+            _args: serde_json::Value,
+        ) -> anyhow::Result<crate::tools::ToolResult> {
+            Ok(crate::tools::ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("path not allowed outside workspace".to_string()),
+            })
+        }
+    }
+
+    /// Reports success without touching the filesystem.
+    struct ClaimingTool(&'static str);
+
+    #[async_trait]
+    impl Tool for ClaimingTool {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            "Claims success without side effects"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        async fn execute(
+            &self,
+            _args: serde_json::Value,
+        ) -> anyhow::Result<crate::tools::ToolResult> {
+            Ok(crate::tools::ToolResult {
+                success: true,
+                output: "ok".to_string(),
+                error: None,
+            })
+        }
+    }
+
+    struct ScriptedGrayZoneVerifier {
+        results: Mutex<Vec<anyhow::Result<GrayZoneVerdict>>>,
+        call_count: AtomicUsize,
+    }
+
+    impl ScriptedGrayZoneVerifier {
+        fn new(results: Vec<anyhow::Result<GrayZoneVerdict>>) -> Self {
+            Self {
+                results: Mutex::new(results),
+                call_count: AtomicUsize::new(0),
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.call_count.load(Ordering::Relaxed)
+        }
+    }
+
+    #[async_trait]
+    impl GrayZoneVerifier for ScriptedGrayZoneVerifier {
+        async fn verify(
+            &self,
+            _request: GrayZoneVerificationRequest<'_>,
+        ) -> anyhow::Result<GrayZoneVerdict> {
+            self.call_count.fetch_add(1, Ordering::Relaxed);
+            let mut guard = self.results.lock().unwrap_or_else(|e| e.into_inner());
+            if guard.is_empty() {
+                return Ok(GrayZoneVerdict {
+                    done: false,
+                    reason: "no_scripted_result".to_string(),
+                });
+            }
+            guard.remove(0)
+        }
+    }
+
+    #[test]
+    fn provider_transport_error_is_classified_retryable() {
+        let err = anyhow::anyhow!(
+            "Custom native chat transport error: error sending request for url (https://x)"
+        );
+        assert!(DefaultRetryClassifier.is_retryable(&err));
+    }
+
+    #[tokio::test]
+    async fn run_task_continues_on_progress_reply_without_user_followup() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                max_continuation_rounds: 4,
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: false,
+                gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok("我正在检查当前文件状态。".to_string()),
+            Ok("任务已完成。".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("请继续处理这个任务"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete");
+        assert_eq!(outcome.final_response, "任务已完成。");
+
+        let row = engine
+            .store()
+            .get_task_run(&outcome.task_id)
+            .expect("get task")
+            .expect("task exists");
+        assert_eq!(row.status.as_str(), "completed");
+        assert!(row.attempt_count >= 2);
+    }
+
+    /// Completes at once, recording the provider params its calls ran with.
+    struct ParamsProbe(Mutex<Vec<Option<ProviderParams>>>);
+
+    #[async_trait]
+    impl Provider for ParamsProbe {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(crate::providers::params::current());
+            Ok("任务已完成。".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn run_task_scopes_request_params_over_config_defaults() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                provider_params: ProviderParams {
+                    top_p: Some(0.5),
+                    stop: vec!["END".into()],
+                    ..ProviderParams::default()
+                },
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ParamsProbe(Mutex::new(Vec::new()));
+        let mut history = vec![ChatMessage::system("system"), ChatMessage::user("总结一下")];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = TaskRunRequest {
+            bypass_completion: true,
+            provider_params: ProviderParams {
+                top_p: Some(0.9),
+                max_tokens: Some(256),
+                ..ProviderParams::default()
+            },
+            ..test_request(&provider, &mut history, &tools_registry, "总结一下")
+        };
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete");
+
+        let expected = ProviderParams {
+            top_p: Some(0.9),
+            max_tokens: Some(256),
+            stop: vec!["END".into()],
+            ..ProviderParams::default()
+        };
+        let seen = provider.0.lock().unwrap();
+        assert_eq!(seen.first(), Some(&Some(expected)));
+        let events = engine
+            .store()
+            .list_events(&outcome.task_id)
+            .expect("events");
+        assert!(events.iter().any(|e| e.event_type == "provider_params"));
+    }
+
+    #[tokio::test]
+    async fn run_task_retries_transport_error_then_succeeds() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                max_continuation_rounds: 2,
+                provider_retry_limit: 1,
+                gray_zone_verifier_enabled: false,
+                gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Err(anyhow::anyhow!(
+                "Custom native chat transport error: error sending request for url (https://x)"
+            )),
+            Ok("done".to_string()),
+        ]);
+        let mut history = vec![ChatMessage::system("sys"), ChatMessage::user("hi")];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = test_request(&provider, &mut history, &tools_registry, "hi");
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete after retry");
+        assert_eq!(outcome.final_response, "done");
+
+        let row = engine
+            .store()
+            .get_task_run(&outcome.task_id)
+            .expect("get task")
+            .expect("task exists");
+        assert!(row.provider_retry_count >= 1);
+        assert_eq!(row.status.as_str(), "completed");
+    }
+
+    #[tokio::test]
+    async fn run_task_fails_over_to_fallback_provider_after_retries() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                provider_retry_limit: 1,
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let primary = ScriptedProvider::new(vec![
+            Err(anyhow::anyhow!("connection reset by peer")),
+            Err(anyhow::anyhow!("connection reset by peer")),
+        ]);
+        let secondary = ScriptedProvider::new(vec![Ok("fallback done".to_string())]);
+        let mut history = vec![ChatMessage::system("sys"), ChatMessage::user("hi")];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = TaskRunRequest {
+            provider_name: "primary",
+            model: "model-a",
+            fallback_providers: vec![ProviderFallback {
+                provider: &secondary,
+                provider_name: "secondary",
+                model: "model-b",
+            }],
+            ..test_request(&primary, &mut history, &tools_registry, "hi")
+        };
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete on the fallback provider");
+        assert_eq!(outcome.final_response, "fallback done");
+
+        let events = engine
+            .store()
+            .list_events(&outcome.task_id)
+            .expect("list events");
+        let failover = events
+            .iter()
+            .find(|event| event.event_type == "provider_failover")
+            .expect("provider_failover event");
+        let payload: serde_json::Value =
+            serde_json::from_str(failover.payload_json.as_deref().expect("failover payload"))
+                .expect("payload json");
+        assert_eq!(payload["from_provider"], "primary");
+        assert_eq!(payload["to_model"], "model-b");
+    }
+
+    #[tokio::test]
+    async fn planned_task_runs_only_after_approval_and_follows_the_plan() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok("1. Find *.tmp files (shell)\n2. Delete them [destructive]".to_string()),
+            Ok("cleanup done".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("sys"),
+            ChatMessage::user("clean up temp files"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let mut req = TaskRunRequest {
+            channel: "cli",
+            provider_name: "scripted",
+            model: "model-a",
+            ..test_request(
+                &provider,
+                &mut history,
+                &tools_registry,
+                "clean up temp files",
+            )
+        };
+
+        let plan = engine.plan_task(&req).await.expect("plan");
+        assert_eq!(
+            plan.steps,
+            vec!["Find *.tmp files (shell)", "Delete them [destructive]"]
+        );
+        let task = engine
+            .store()
+            .get_task_run(&plan.task_id)
+            .expect("load")
+            .expect("task");
+        assert_eq!(task.status, TaskStatus::Queued);
+        assert_eq!(req.history.len(), 2);
+
+        engine.approve_plan(&plan.task_id).expect("approve");
+        assert!(engine.approve_plan(&plan.task_id).is_err());
+        let outcome = engine
+            .run_existing_task(&plan.task_id, &mut req)
+            .await
+            .expect("run approved plan");
+        assert_eq!(outcome.final_response, "cleanup done");
+        assert!(history
+            .iter()
+            .any(|message| message.content.contains("2. Delete them [destructive]")));
+    }
+
+    #[tokio::test]
+    async fn run_task_retries_errors_declared_by_injected_classifier() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                provider_retry_limit: 1,
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine")
+        .with_retry_classifier(Arc::new(PatternRetryClassifier::new(
+            vec!["upstream busy".to_string()],
+            Vec::new(),
+        )));
+        let provider = ScriptedProvider::new(vec![
+            Err(anyhow::anyhow!("gateway said: UPSTREAM BUSY")),
+            Ok("done".to_string()),
+        ]);
+        let mut history = vec![ChatMessage::system("sys"), ChatMessage::user("hi")];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = test_request(&provider, &mut history, &tools_registry, "hi");
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("custom retryable error should be retried");
+        assert_eq!(outcome.final_response, "done");
+    }
+
+    #[tokio::test]
+    async fn run_task_invokes_gray_zone_verifier_once_for_unknown_progress_update() {
+        let tmp = TempDir::new().expect("tempdir");
+        let verifier = Arc::new(ScriptedGrayZoneVerifier::new(vec![Ok(GrayZoneVerdict {
+            done: false,
+            reason: "need_more_work".to_string(),
+        })]));
+        let engine = TaskEngine::with_verifier(
+            tmp.path(),
+            TaskEngineConfig {
+                max_continuation_rounds: 4,
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: true,
+                gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+            verifier.clone(),
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok("我正在检查当前文件状态。".to_string()),
+            Ok("任务已完成。".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("请继续处理这个任务"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete");
+        assert_eq!(outcome.final_response, "任务已完成。");
+        assert_eq!(verifier.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn run_task_llm_judge_completes_reply_heuristics_would_continue() {
+        let tmp = TempDir::new().expect("tempdir");
+        let verifier = Arc::new(ScriptedGrayZoneVerifier::new(Vec::new()));
+        let engine = TaskEngine::with_verifier(
+            tmp.path(),
+            TaskEngineConfig {
+                provider_retry_limit: 0,
+                task_timeout_secs: 0,
+                completion_policy: CompletionPolicy {
+                    evaluator: CompletionEvaluator::LlmJudge,
+                    judge_model: Some("cheap-model".into()),
+                    ..CompletionPolicy::default()
+                },
+                ..TaskEngineConfig::default()
+            },
+            verifier.clone(),
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok("我正在检查当前文件状态。".to_string()),
+            Ok(r#"{"complete": true, "confidence": 0.9, "reason": "status answered"}"#.to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("请继续处理这个任务"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete");
+        assert_eq!(outcome.status, TaskStatus::Completed);
+        assert_eq!(verifier.calls(), 0);
+        let events = engine
+            .store()
+            .list_events(&outcome.task_id)
+            .expect("list events");
+        let judge = events
+            .iter()
+            .find(|event| event.event_type == "llm_judge")
+            .expect("judge event");
+        assert!(judge
+            .payload_json
+            .as_deref()
+            .is_some_and(|payload| payload.contains("\"applied\":true")));
+    }
+
+    #[tokio::test]
+    async fn run_task_gray_zone_verifier_done_true_completes_in_same_round() {
+        let tmp = TempDir::new().expect("tempdir");
+        let verifier = Arc::new(ScriptedGrayZoneVerifier::new(vec![Ok(GrayZoneVerdict {
+            done: true,
+            reason: "verified_done".to_string(),
+        })]));
+        let engine = TaskEngine::with_verifier(
+            tmp.path(),
+            TaskEngineConfig {
+                max_continuation_rounds: 4,
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: true,
+                gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+            verifier.clone(),
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![Ok("我正在检查当前文件状态。".to_string())]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("请继续处理这个任务"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete");
+        assert_eq!(outcome.final_response, "我正在检查当前文件状态。");
+        assert_eq!(verifier.calls(), 1);
+        let row = engine
+            .store()
+            .get_task_run(&outcome.task_id)
+            .expect("get task")
+            .expect("task exists");
+        assert_eq!(row.status.as_str(), "completed");
+        assert_eq!(row.attempt_count, 1);
+    }
+
+    #[tokio::test]
+    async fn run_task_gray_zone_verifier_error_falls_back_to_conservative_continue() {
+        let tmp = TempDir::new().expect("tempdir");
+        let verifier = Arc::new(ScriptedGrayZoneVerifier::new(vec![Err(anyhow::anyhow!(
+            "gray-zone verifier timed out"
+        ))]));
+        let engine = TaskEngine::with_verifier(
+            tmp.path(),
+            TaskEngineConfig {
+                max_continuation_rounds: 4,
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: true,
+                gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+            verifier.clone(),
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok("我正在检查当前文件状态。".to_string()),
+            Ok("任务已完成。".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("请继续处理这个任务"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete");
+        assert_eq!(outcome.final_response, "任务已完成。");
+        assert_eq!(verifier.calls(), 1);
+        let row = engine
+            .store()
+            .get_task_run(&outcome.task_id)
+            .expect("get task")
+            .expect("task exists");
+        assert!(row.attempt_count >= 2);
+        assert_eq!(row.status.as_str(), "completed");
+    }
+
+    #[tokio::test]
+    async fn task_engine_state_machine_running_verifying_completed_transition() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                max_continuation_rounds: 4,
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: false,
+                gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok("我正在检查当前文件状态。".to_string()),
+            Ok("任务已完成。".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("请继续处理这个任务"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete");
+        assert_eq!(outcome.final_response, "任务已完成。");
+
+        let events = engine
+            .store()
+            .list_events(&outcome.task_id)
+            .expect("list events");
+        let event_types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert!(event_types.contains(&"continue"));
+        assert!(event_types.contains(&"completed"));
+    }
+
+    #[tokio::test]
+    async fn task_engine_state_machine_running_verifying_blocked_transition() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                max_continuation_rounds: 4,
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: false,
+                gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok(r#"<tool_call>
+{"name":"file_read","arguments":{"path":"studio"}}
+</tool_call>"#
+                .to_string()),
+            Ok("我会继续分析这个目录。".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("分析 studio 目录项目"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(DeniedReadTool)];
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "分析 studio 目录项目",
+        );
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should be blocked");
+        assert!(outcome.final_response.contains("任务已阻塞"));
+        let blocked = engine
+            .store()
+            .latest_event_of_type(&outcome.task_id, BLOCKED_EVENT)
+            .expect("blocked event")
+            .and_then(|event| BlockedResolution::from_event(&event))
+            .expect("structured resolution");
+        assert_eq!(blocked.kind, BlockedKind::WorkspaceAccess);
+        assert_eq!(
+            blocked.config_key.as_deref(),
+            Some("autonomy.allowed_roots")
+        );
+
+        let row = engine
+            .store()
+            .get_task_run(&outcome.task_id)
+            .expect("get task")
+            .expect("task exists");
+        assert_eq!(row.status.as_str(), "blocked");
+
+        let invocations = engine
+            .store()
+            .list_tool_invocations(&outcome.task_id)
+            .expect("list invocations");
+        assert_eq!(invocations.len(), 1);
+        assert_eq!(invocations[0].name, "file_read");
+        assert!(!invocations[0].success);
+        assert_eq!(
+            invocations[0].output,
+            "Error: path not allowed outside workspace"
+        );
+    }
+
+    #[tokio::test]
+    async fn run_task_reports_each_tool_call_as_progress() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok(r#"<tool_call>
+{"name":"file_read","arguments":{"path":"notes.md"}}
+</tool_call>"#
+                .to_string()),
+            Ok("notes.md is empty".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("read notes"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(ClaimingTool("file_read"))];
+        let progress = Arc::new(Mutex::new(Vec::<String>::new()));
+        let sink = Arc::clone(&progress);
+        let req = TaskRunRequest {
+            progress_reporter: Some(Arc::new(move |message: String| {
+                sink.lock().unwrap().push(message);
+            })),
+            bypass_completion: true,
+            ..test_request(&provider, &mut history, &tools_registry, "read notes")
+        };
+
+        TaskEngine::run_task(req, &engine).await.expect("task");
+        let progress = progress.lock().unwrap();
+        let round = progress
+            .iter()
+            .position(|message| message.starts_with("🔄 第 1/"))
+            .expect("round progress");
+        let tool = progress
+            .iter()
+            .position(|message| message == "🔧 调用工具 `file_read` ✅")
+            .expect("tool progress");
+        assert!(round < tool);
+    }
+
+    #[tokio::test]
+    async fn tool_calls_needing_confirmation_block_until_resumed() {
+        let tmp = TempDir::new().expect("tempdir");
+        let policy = ToolPolicy::from_config(&crate::config::ToolPolicyConfig {
+            rules: crate::config::ToolPolicyRules {
+                confirm_tools: vec!["file_read".into()],
+                ..crate::config::ToolPolicyRules::default()
+            },
+            ..crate::config::ToolPolicyConfig::default()
+        })
+        .expect("tool policy");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                tool_policy: Arc::new(policy),
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let tool_call = r#"<tool_call>
+{"name":"file_read","arguments":{"path":"notes.md"}}
+</tool_call>"#;
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(ClaimingTool("file_read"))];
+        let provider = ScriptedProvider::new(vec![Ok(tool_call.to_string())]);
+        let mut history = vec![ChatMessage::user("read notes")];
+        let req = TaskRunRequest {
+            bypass_completion: true,
+            ..test_request(&provider, &mut history, &tools_registry, "read notes")
+        };
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should be blocked");
+        assert_eq!(outcome.status, TaskStatus::Blocked);
+        let blocked = engine
+            .store()
+            .latest_event_of_type(&outcome.task_id, BLOCKED_EVENT)
+            .expect("blocked event")
+            .and_then(|event| BlockedResolution::from_event(&event))
+            .expect("structured resolution");
+        assert_eq!(blocked.kind, BlockedKind::Approval);
+        assert!(blocked.remediation.contains("file_read"));
+        assert!(engine
+            .store()
+            .list_tool_invocations(&outcome.task_id)
+            .expect("list invocations")
+            .is_empty());
+
+        // Resuming approves the call, so the model's repeat of it runs.
+        let provider = ScriptedProvider::new(vec![
+            Ok(tool_call.to_string()),
+            Ok("notes.md is empty".to_string()),
+        ]);
+        let mut history = vec![ChatMessage::user("continue")];
+        let mut req = TaskRunRequest {
+            bypass_completion: true,
+            ..test_request(&provider, &mut history, &tools_registry, "read notes")
+        };
+        let resumed = engine
+            .run_existing_task(&outcome.task_id, &mut req)
+            .await
+            .expect("resumed task");
+        drop(req);
+        assert_eq!(resumed.status, TaskStatus::Completed);
+        assert!(history
+            .iter()
+            .any(|message| message.content.contains("approved the `file_read` call")));
+        let invocations = engine
+            .store()
+            .list_tool_invocations(&outcome.task_id)
+            .expect("list invocations");
+        assert_eq!(invocations.len(), 1);
+        assert!(invocations[0].success);
+    }
+
+    #[tokio::test]
+    async fn code_task_completes_only_after_acceptance_tests_pass() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                acceptance_tests: crate::config::TaskAcceptanceTests {
+                    command: "if [ -f .attempt ]; then echo ok; else touch .attempt; echo 'test_parse FAILED'; exit 1; fi".into(),
+                    ..crate::config::TaskAcceptanceTests::default()
+                },
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok("任务已完成。".to_string()),
+            Ok("已修复解析逻辑，任务已完成。".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("修复解析 bug"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = TaskRunRequest {
+            labels: vec!["hint:code".to_string()],
+            ..test_request(&provider, &mut history, &tools_registry, "修复解析 bug")
+        };
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("task should complete");
+        assert_eq!(outcome.status, TaskStatus::Completed);
+        assert_eq!(outcome.final_response, "已修复解析逻辑，任务已完成。");
+        assert!(history
+            .iter()
+            .any(|message| message.content.contains("test_parse FAILED")));
+
+        let runs: Vec<serde_json::Value> = engine
+            .store()
+            .list_events(&outcome.task_id)
+            .expect("events")
+            .into_iter()
+            .filter(|event| event.event_type == ACCEPTANCE_TESTS_EVENT)
+            .filter_map(|event| serde_json::from_str(event.payload_json.as_deref()?).ok())
+            .collect();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0]["passed"], false);
+        assert_eq!(runs[1]["passed"], true);
+        let artifacts = engine
+            .store()
+            .list_artifacts(&outcome.task_id)
+            .expect("artifacts");
+        assert_eq!(artifacts.len(), 2);
+        assert!(artifacts
+            .iter()
+            .any(|artifact| artifact.path.ends_with("round-2.log") && artifact.verified));
+    }
+
+    #[test]
+    fn rollback_restores_files_written_by_a_finished_task() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::default_for_workspace(tmp.path()).expect("task engine");
+        let task_id = engine
+            .create_task("imessage", "sender-a", "sender-a", "rewrite the notes")
+            .expect("task");
+        std::fs::write(tmp.path().join("notes.md"), "original").unwrap();
+        let transaction =
+            WorkspaceTransaction::open(tmp.path(), &task_id, Arc::clone(&engine.config().security))
+                .expect("open transaction");
+        transaction.backup_before_write("notes.md").expect("backup");
+        std::fs::write(tmp.path().join("notes.md"), "rewritten").unwrap();
+
+        engine
+            .store()
+            .update_status(&task_id, TaskStatus::Running)
+            .unwrap();
+        assert!(engine.rollback(&task_id).is_err());
+        engine
+            .store()
+            .update_status(&task_id, TaskStatus::Failed)
+            .unwrap();
+
+        let report = engine.rollback(&task_id).expect("rollback");
+        assert_eq!(report.restored, vec!["notes.md"]);
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("notes.md")).unwrap(),
+            "original"
+        );
+        assert!(engine
+            .store()
+            .latest_event_of_type(&task_id, ROLLED_BACK_EVENT)
+            .unwrap()
+            .is_some());
+        assert!(engine.rollback(&task_id).is_err());
+    }
+
+    #[tokio::test]
+    async fn resumed_task_skips_completed_steps_and_finishes_the_rest() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let task_id = engine
+            .create_task("imessage", "sender-a", "sender-a", "summarize the logs")
+            .expect("task");
+        engine
+            .store()
+            .set_task_steps(&task_id, &["Collect logs".into(), "Write summary".into()])
+            .expect("steps");
+        engine
+            .store()
+            .update_task_step(&task_id, 0, TaskStepStatus::Completed, Some("12 files"))
+            .expect("step 1 done");
+        engine
+            .store()
+            .update_status(&task_id, TaskStatus::Running)
+            .expect("running");
+
+        let provider = ScriptedProvider::new(vec![
+            Ok("summary.md written".to_string()),
+            Ok("Summary of 12 log files is in summary.md".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("summarize the logs"),
+        ];
+        let progress = Arc::new(Mutex::new(Vec::<String>::new()));
+        let sink = Arc::clone(&progress);
+        let mut req = TaskRunRequest {
+            progress_reporter: Some(Arc::new(move |message: String| {
+                sink.lock().unwrap().push(message);
+            })),
+            bypass_completion: true,
+            ..test_request(&provider, &mut history, &[], "summarize the logs")
+        };
+
+        let outcome = engine
+            .run_existing_task(&task_id, &mut req)
+            .await
+            .expect("task");
+        assert_eq!(
+            outcome.final_response,
+            "Summary of 12 log files is in summary.md"
+        );
+
+        let steps = engine.store().list_task_steps(&task_id).expect("steps");
+        assert!(steps
+            .iter()
+            .all(|step| step.status == TaskStepStatus::Completed));
+        assert_eq!(steps[1].response.as_deref(), Some("summary.md written"));
+        assert!(history
+            .iter()
+            .any(|message| message.content.contains("1. Collect logs — 12 files")));
+        let progress = progress.lock().unwrap();
+        assert!(!progress.iter().any(|message| message.contains("步骤 1/2")));
+        assert!(progress
+            .iter()
+            .any(|message| message == "✅ 步骤 2/2 完成。"));
     }
 
-    impl ScriptedGrayZoneVerifier {
-        fn new(results: Vec<anyhow::Result<GrayZoneVerdict>>) -> Self {
-            Self {
-                results: Mutex::new(results),
-                call_count: AtomicUsize::new(0),
-            }
-        }
+    #[tokio::test]
+    async fn run_task_keeps_running_when_claimed_artifact_is_missing_on_disk() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                max_continuation_rounds: 1,
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok(r#"<tool_call>
+{"name":"file_write","arguments":{"path":"report.md","content":"abc"}}
+</tool_call>
+<tool_call>
+{"name":"file_read","arguments":{"path":"report.md"}}
+</tool_call>"#
+                .to_string()),
+            Ok("报告已保存到 report.md。".to_string()),
+        ]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("把报告保存到 report.md"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = vec![
+            Box::new(ClaimingTool("file_write")),
+            Box::new(ClaimingTool("file_read")),
+        ];
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "把报告保存到 report.md",
+        );
 
-        fn calls(&self) -> usize {
-            self.call_count.load(Ordering::Relaxed)
-        }
-    }
+        let err = TaskEngine::run_task(req, &engine)
+            .await
+            .expect_err("missing artifact must not complete");
+        assert!(format!("{err:#}").contains("max continuation rounds"));
 
-    #[async_trait]
-    impl GrayZoneVerifier for ScriptedGrayZoneVerifier {
-        async fn verify(
-            &self,
-            _request: GrayZoneVerificationRequest<'_>,
-        ) -> anyhow::Result<GrayZoneVerdict> {
-            self.call_count.fetch_add(1, Ordering::Relaxed);
-            let mut guard = self.results.lock().unwrap_or_else(|e| e.into_inner());
-            if guard.is_empty() {
-                return Ok(GrayZoneVerdict {
-                    done: false,
-                    reason: "no_scripted_result".to_string(),
-                });
-            }
-            guard.remove(0)
-        }
+        let task = engine
+            .store()
+            .list_recent_sender_tasks("imessage", "sender-a", 1)
+            .expect("tasks")
+            .remove(0);
+        let artifact = engine
+            .store()
+            .find_artifact(&task.id, "report.md")
+            .expect("find artifact")
+            .expect("artifact row");
+        assert!(!artifact.verified);
+        assert!(artifact.checksum.is_none());
+        let events = engine.store().list_events(&task.id).expect("events");
+        assert!(events
+            .iter()
+            .any(|event| event.event_type == "artifact_check_failed"));
+        assert!(events.iter().any(|event| {
+            event.event_type == "continue"
+                && event
+                    .payload_json
+                    .as_deref()
+                    .is_some_and(|payload| payload.contains("artifact:report.md"))
+        }));
     }
 
-    #[test]
-    fn provider_transport_error_is_classified_retryable() {
-        let err = anyhow::anyhow!(
-            "Custom native chat transport error: error sending request for url (https://x)"
+    #[tokio::test]
+    async fn run_task_asks_model_to_verify_files_it_claims_without_writing() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                max_continuation_rounds: 1,
+                provider_retry_limit: 0,
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let provider = ScriptedProvider::new(vec![Ok(format!(
+            "总结已保存到 {}/notes/summary.md。",
+            tmp.path().display()
+        ))]);
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("把总结保存到 notes/summary.md"),
+        ];
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(ClaimingTool("file_write"))];
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "把总结保存到 notes/summary.md",
         );
-        assert!(is_retryable_provider_transport_error(&err));
+
+        TaskEngine::run_task(req, &engine)
+            .await
+            .expect_err("an unbacked claim must not complete");
+
+        assert!(history.iter().any(|message| {
+            message.role == "user"
+                && message.content.starts_with("[Task Engine]")
+                && message.content.contains("notes/summary.md")
+                && message.content.contains("file_read")
+        }));
+        let task = engine
+            .store()
+            .list_recent_sender_tasks("imessage", "sender-a", 1)
+            .expect("tasks")
+            .remove(0);
+        let artifact = engine
+            .store()
+            .find_artifact(&task.id, "notes/summary.md")
+            .expect("find artifact")
+            .expect("claimed artifact row");
+        assert!(!artifact.verified);
+        let events = engine.store().list_events(&task.id).expect("events");
+        assert!(events
+            .iter()
+            .any(|event| event.event_type == "claimed_paths"));
+        let evaluated = events
+            .iter()
+            .find(|event| event.event_type == "completion_evaluated")
+            .and_then(|event| event.payload_json.as_deref())
+            .expect("completion_evaluated event");
+        assert!(evaluated.contains("\"decision\":\"continue\""));
+        assert!(evaluated.contains("evidence missing: tool_success:file_read"));
     }
 
     #[tokio::test]
-    async fn run_task_continues_on_progress_reply_without_user_followup() {
+    async fn task_engine_state_machine_running_verifying_continue_running_transition() {
         let tmp = TempDir::new().expect("tempdir");
         let engine = TaskEngine::new(
             tmp.path(),
@@ -711,39 +3795,27 @@ mod tests {
                 provider_retry_limit: 0,
                 gray_zone_verifier_enabled: false,
                 gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
             },
         )
         .expect("task engine");
         let provider = ScriptedProvider::new(vec![
             Ok("我正在检查当前文件状态。".to_string()),
+            Ok("我正在继续处理，请稍等。".to_string()),
             Ok("任务已完成。".to_string()),
         ]);
-        let observer = NoopObserver;
         let mut history = vec![
             ChatMessage::system("system"),
             ChatMessage::user("请继续处理这个任务"),
         ];
         let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
-        let req = TaskRunRequest {
-            channel: "imessage",
-            sender_key: "sender-a",
-            reply_target: "sender-a",
-            original_request: "请继续处理这个任务",
-            provider: &provider,
-            history: &mut history,
-            tools_registry: &tools_registry,
-            observer: &observer,
-            provider_name: "test-provider",
-            model: "test-model",
-            temperature: 0.0,
-            multimodal: &crate::config::MultimodalConfig::default(),
-            max_tool_iterations: 5,
-            cancellation_token: None,
-            on_delta: None,
-            hooks: None,
-            excluded_tools: &[],
-            progress_reporter: None,
-        };
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
 
         let outcome = TaskEngine::run_task(req, &engine)
             .await
@@ -755,247 +3827,408 @@ mod tests {
             .get_task_run(&outcome.task_id)
             .expect("get task")
             .expect("task exists");
-        assert_eq!(row.status.as_str(), "completed");
-        assert!(row.attempt_count >= 2);
+        assert!(row.attempt_count >= 3);
     }
 
     #[tokio::test]
-    async fn run_task_retries_transport_error_then_succeeds() {
+    async fn task_engine_state_machine_detects_stalled_loop_after_six_progress_only_rounds() {
         let tmp = TempDir::new().expect("tempdir");
         let engine = TaskEngine::new(
             tmp.path(),
             TaskEngineConfig {
-                max_continuation_rounds: 2,
-                provider_retry_limit: 1,
+                max_continuation_rounds: 8,
+                provider_retry_limit: 0,
                 gray_zone_verifier_enabled: false,
                 gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
             },
         )
         .expect("task engine");
         let provider = ScriptedProvider::new(vec![
-            Err(anyhow::anyhow!(
-                "Custom native chat transport error: error sending request for url (https://x)"
-            )),
-            Ok("done".to_string()),
+            Ok("我正在检查当前文件状态。".to_string()),
+            Ok("我正在继续处理，请稍等。".to_string()),
+            Ok("我会继续处理，请稍等。".to_string()),
+            Ok("我正在处理更多细节，请稍等。".to_string()),
+            Ok("我正在继续推进，请稍等。".to_string()),
+            Ok("我会继续处理，很快给你结果。".to_string()),
+            Ok("我正在收敛结果，请稍后。".to_string()),
         ]);
-        let observer = NoopObserver;
-        let mut history = vec![ChatMessage::system("sys"), ChatMessage::user("hi")];
+        let mut history = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("请继续处理这个任务"),
+        ];
         let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
-        let req = TaskRunRequest {
-            channel: "imessage",
-            sender_key: "sender-a",
-            reply_target: "sender-a",
-            original_request: "hi",
-            provider: &provider,
-            history: &mut history,
-            tools_registry: &tools_registry,
-            observer: &observer,
-            provider_name: "test-provider",
-            model: "test-model",
-            temperature: 0.0,
-            multimodal: &crate::config::MultimodalConfig::default(),
-            max_tool_iterations: 5,
-            cancellation_token: None,
-            on_delta: None,
-            hooks: None,
-            excluded_tools: &[],
-            progress_reporter: None,
-        };
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
 
-        let outcome = TaskEngine::run_task(req, &engine)
+        let err = TaskEngine::run_task(req, &engine)
             .await
-            .expect("task should complete after retry");
-        assert_eq!(outcome.final_response, "done");
-
-        let row = engine
-            .store()
-            .get_task_run(&outcome.task_id)
-            .expect("get task")
-            .expect("task exists");
-        assert!(row.provider_retry_count >= 1);
-        assert_eq!(row.status.as_str(), "completed");
+            .expect_err("task should fail due to stalled loop");
+        assert!(format!("{err:#}").contains("stalled"));
     }
 
     #[tokio::test]
-    async fn run_task_invokes_gray_zone_verifier_once_for_unknown_progress_update() {
+    async fn task_engine_fails_fast_when_the_model_repeats_its_answer() {
         let tmp = TempDir::new().expect("tempdir");
-        let verifier = Arc::new(ScriptedGrayZoneVerifier::new(vec![Ok(GrayZoneVerdict {
-            done: false,
-            reason: "need_more_work".to_string(),
-        })]));
-        let engine = TaskEngine::with_verifier(
+        let engine = TaskEngine::new(
             tmp.path(),
             TaskEngineConfig {
-                max_continuation_rounds: 4,
+                max_continuation_rounds: 8,
                 provider_retry_limit: 0,
-                gray_zone_verifier_enabled: true,
-                gray_zone_verifier_timeout_ms: 1500,
+                gray_zone_verifier_enabled: false,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
             },
-            verifier.clone(),
         )
         .expect("task engine");
         let provider = ScriptedProvider::new(vec![
-            Ok("我正在检查当前文件状态。".to_string()),
-            Ok("任务已完成。".to_string()),
+            Ok("Let me check the build logs.".to_string()),
+            Ok("let me check the build logs".to_string()),
+            Ok("Let me check the  build logs!".to_string()),
+            Ok("Let me check the build logs.".to_string()),
         ]);
-        let observer = NoopObserver;
         let mut history = vec![
             ChatMessage::system("system"),
-            ChatMessage::user("请继续处理这个任务"),
+            ChatMessage::user("why is CI red?"),
         ];
         let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = test_request(&provider, &mut history, &tools_registry, "why is CI red?");
+
+        let err = TaskEngine::run_task(req, &engine)
+            .await
+            .expect_err("a looping model must fail");
+        assert!(format!("{err:#}").contains("repeating the same response"));
+        let task = engine
+            .store()
+            .list_recent_sender_tasks("imessage", "sender-a", 1)
+            .expect("tasks")
+            .pop()
+            .expect("task");
+        assert_eq!(task.attempt_count, 3);
+        let failed = engine
+            .store()
+            .list_events(&task.id)
+            .expect("events")
+            .into_iter()
+            .find(|event| event.event_type == "failed")
+            .and_then(|event| event.payload_json)
+            .expect("failed event");
+        assert!(failed.contains("\"reason\":\"repeated_response\""));
+    }
+
+    #[test]
+    fn raw_mode_command_requires_a_request() {
+        assert_eq!(
+            strip_raw_mode_command("/raw  what is 2+2? "),
+            Some("what is 2+2?")
+        );
+        assert_eq!(strip_raw_mode_command("/raw"), None);
+        assert_eq!(strip_raw_mode_command("/rawest idea"), None);
+        assert_eq!(strip_raw_mode_command("please /raw this"), None);
+    }
+
+    #[tokio::test]
+    async fn bypass_completion_returns_first_round_answer_and_records_it() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::default_for_workspace(tmp.path()).expect("task engine");
+        let provider = ScriptedProvider::new(vec![Ok("我正在处理，请稍等。".to_string())]);
+        let mut history = vec![ChatMessage::user("整理报告")];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
         let req = TaskRunRequest {
-            channel: "imessage",
-            sender_key: "sender-a",
-            reply_target: "sender-a",
-            original_request: "请继续处理这个任务",
-            provider: &provider,
-            history: &mut history,
-            tools_registry: &tools_registry,
-            observer: &observer,
-            provider_name: "test-provider",
-            model: "test-model",
-            temperature: 0.0,
-            multimodal: &crate::config::MultimodalConfig::default(),
-            max_tool_iterations: 5,
-            cancellation_token: None,
-            on_delta: None,
-            hooks: None,
-            excluded_tools: &[],
-            progress_reporter: None,
+            bypass_completion: true,
+            ..test_request(&provider, &mut history, &tools_registry, "整理报告")
         };
 
         let outcome = TaskEngine::run_task(req, &engine)
             .await
-            .expect("task should complete");
-        assert_eq!(outcome.final_response, "任务已完成。");
-        assert_eq!(verifier.calls(), 1);
+            .expect("raw run completes");
+        assert_eq!(outcome.final_response, "我正在处理，请稍等。");
+
+        let events = engine
+            .store()
+            .list_events(&outcome.task_id)
+            .expect("events");
+        assert!(events.iter().any(|e| e.event_type == "completion_bypassed"));
+        assert!(!events.iter().any(|e| e.event_type == "continue"));
+    }
+
+    #[tokio::test]
+    async fn output_format_mismatch_triggers_correction_round() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::default_for_workspace(tmp.path()).expect("task engine");
+        let provider = ScriptedProvider::new(vec![
+            Ok("标签有：a、b、c".to_string()),
+            Ok(r#"{"tags": ["a", "b", "c"]}"#.to_string()),
+        ]);
+        let mut history = vec![ChatMessage::user("列出三个标签")];
+        let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
+        let req = TaskRunRequest {
+            channel: "web_dashboard",
+            output_format: Some(OutputFormat::JsonSchema {
+                schema: serde_json::json!({
+                    "type": "object",
+                    "required": ["tags"],
+                    "properties": {"tags": {"type": "array", "items": {"type": "string"}}}
+                }),
+            }),
+            ..test_request(&provider, &mut history, &tools_registry, "列出三个标签")
+        };
+
+        let outcome = TaskEngine::run_task(req, &engine)
+            .await
+            .expect("corrected answer completes");
+        assert_eq!(outcome.final_response, r#"{"tags": ["a", "b", "c"]}"#);
+        assert!(history
+            .iter()
+            .any(|m| m.role == "user" && m.content.contains("不符合声明的输出格式")));
+
+        let events = engine
+            .store()
+            .list_events(&outcome.task_id)
+            .expect("events");
+        assert!(events
+            .iter()
+            .any(|e| e.event_type == "output_format_declared"));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e.event_type == "output_format_mismatch")
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn structured_reply_stays_valid_json_with_sources_and_cost_report() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::new(
+            tmp.path(),
+            TaskEngineConfig {
+                gray_zone_verifier_enabled: false,
+                report_cost: true,
+                ..TaskEngineConfig::default()
+            },
+        )
+        .expect("task engine");
+        let task_id = engine
+            .create_task("web_dashboard", "sender-a", "sender-a", "查一下标题")
+            .expect("create task");
+        engine
+            .store()
+            .append_event(
+                &task_id,
+                "llm_usage",
+                Some(&serde_json::json!({"input_tokens": 100, "output_tokens": 20})),
+            )
+            .expect("usage event");
+
+        let provider = ScriptedProvider::new(vec![
+            Ok(r#"<tool_call>
+{"name":"web_fetch","arguments":{"url":"https://example.com"}}
+</tool_call>"#
+                .to_string()),
+            Ok(r#"{"title": "Example Domain"}"#.to_string()),
+        ]);
+        let mut history = vec![ChatMessage::user("查一下标题")];
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(PageTool)];
+        let mut req = TaskRunRequest {
+            channel: "web_dashboard",
+            output_format: Some(OutputFormat::JsonSchema {
+                schema: serde_json::json!({
+                    "type": "object",
+                    "required": ["title"],
+                    "properties": {"title": {"type": "string"}}
+                }),
+            }),
+            ..test_request(&provider, &mut history, &tools_registry, "查一下标题")
+        };
+
+        let outcome = engine
+            .run_existing_task(&task_id, &mut req)
+            .await
+            .expect("task completes");
+        let reply: serde_json::Value =
+            serde_json::from_str(&outcome.final_response).expect("reply stays JSON");
+        assert_eq!(reply["title"], "Example Domain");
+        let events = engine.store().list_events(&task_id).expect("events");
+        assert!(events.iter().any(|e| e.event_type == CITATIONS_EVENT));
+    }
+
+    /// `web_fetch` stand-in returning one fetched page and recording it as a
+    /// source.
+    struct PageTool;
+
+    #[async_trait]
+    impl Tool for PageTool {
+        fn name(&self) -> &str {
+            "web_fetch"
+        }
+
+        fn description(&self) -> &str {
+            "Returns a fixed page"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        async fn execute(
+            &self,
+            _args: serde_json::Value,
+        ) -> anyhow::Result<crate::tools::ToolResult> {
+            crate::tools::traits::record_result_metadata(
+                "sources",
+                serde_json::json!({"url": "https://example.com", "title": "Example Domain"}),
+            );
+            Ok(crate::tools::ToolResult {
+                success: true,
+                output: "Status: 200 OK\nURL: https://example.com\nTitle: Example Domain\n\nExample body"
+                    .to_string(),
+                error: None,
+            })
+        }
+    }
+
+    #[test]
+    fn no_op_round_requires_restatement_without_tool_activity() {
+        let restated = [ChatMessage::assistant("我正在处理，请稍等。")];
+        assert!(is_no_op_round(
+            &restated,
+            Some("我正在处理, 请稍等"),
+            "我正在处理，请稍等。"
+        ));
+        assert!(!is_no_op_round(&restated, None, "我正在处理，请稍等。"));
+        assert!(!is_no_op_round(
+            &restated,
+            Some("我正在处理，请稍等。"),
+            "已找到三个候选文件，正在比较内容。"
+        ));
+
+        let with_tool = [
+            ChatMessage::assistant("<tool_call>{\"name\":\"shell\"}</tool_call>"),
+            ChatMessage::user("[Tool results]\n<tool_result name=\"shell\">ok</tool_result>"),
+            ChatMessage::assistant("我正在处理，请稍等。"),
+        ];
+        assert!(!is_no_op_round(
+            &with_tool,
+            Some("我正在处理，请稍等。"),
+            "我正在处理，请稍等。"
+        ));
     }
 
     #[tokio::test]
-    async fn run_task_gray_zone_verifier_done_true_completes_in_same_round() {
+    async fn task_engine_records_no_op_rounds_for_restated_responses() {
         let tmp = TempDir::new().expect("tempdir");
-        let verifier = Arc::new(ScriptedGrayZoneVerifier::new(vec![Ok(GrayZoneVerdict {
-            done: true,
-            reason: "verified_done".to_string(),
-        })]));
-        let engine = TaskEngine::with_verifier(
+        let engine = TaskEngine::new(
             tmp.path(),
             TaskEngineConfig {
-                max_continuation_rounds: 4,
+                max_continuation_rounds: 3,
                 provider_retry_limit: 0,
-                gray_zone_verifier_enabled: true,
+                gray_zone_verifier_enabled: false,
                 gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
             },
-            verifier.clone(),
         )
         .expect("task engine");
-        let provider = ScriptedProvider::new(vec![Ok("我正在检查当前文件状态。".to_string())]);
-        let observer = NoopObserver;
+        let provider = ScriptedProvider::new(vec![
+            Ok("我正在处理，请稍等。".to_string()),
+            Ok("我正在处理，请稍等。".to_string()),
+            Ok("我正在处理，请稍等！".to_string()),
+        ]);
         let mut history = vec![
             ChatMessage::system("system"),
             ChatMessage::user("请继续处理这个任务"),
         ];
         let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
-        let req = TaskRunRequest {
-            channel: "imessage",
-            sender_key: "sender-a",
-            reply_target: "sender-a",
-            original_request: "请继续处理这个任务",
-            provider: &provider,
-            history: &mut history,
-            tools_registry: &tools_registry,
-            observer: &observer,
-            provider_name: "test-provider",
-            model: "test-model",
-            temperature: 0.0,
-            multimodal: &crate::config::MultimodalConfig::default(),
-            max_tool_iterations: 5,
-            cancellation_token: None,
-            on_delta: None,
-            hooks: None,
-            excluded_tools: &[],
-            progress_reporter: None,
-        };
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
 
-        let outcome = TaskEngine::run_task(req, &engine)
-            .await
-            .expect("task should complete");
-        assert_eq!(outcome.final_response, "我正在检查当前文件状态。");
-        assert_eq!(verifier.calls(), 1);
-        let row = engine
+        let _ = TaskEngine::run_task(req, &engine).await;
+        let task = engine
             .store()
-            .get_task_run(&outcome.task_id)
-            .expect("get task")
-            .expect("task exists");
-        assert_eq!(row.status.as_str(), "completed");
-        assert_eq!(row.attempt_count, 1);
+            .list_recent_sender_tasks("imessage", "sender-a", 1)
+            .expect("tasks")
+            .pop()
+            .expect("task");
+        let no_op_rounds: Vec<String> = engine
+            .store()
+            .list_events(&task.id)
+            .expect("events")
+            .into_iter()
+            .filter(|event| event.event_type == "no_op_round")
+            .filter_map(|event| event.payload_json)
+            .collect();
+        assert_eq!(no_op_rounds.len(), 2);
+        assert!(no_op_rounds[0].contains("\"round\":2"));
     }
 
     #[tokio::test]
-    async fn run_task_gray_zone_verifier_error_falls_back_to_conservative_continue() {
+    async fn export_transcript_interleaves_events_and_round_messages() {
         let tmp = TempDir::new().expect("tempdir");
-        let verifier = Arc::new(ScriptedGrayZoneVerifier::new(vec![Err(anyhow::anyhow!(
-            "gray-zone verifier timed out"
-        ))]));
-        let engine = TaskEngine::with_verifier(
+        let engine = TaskEngine::new(
             tmp.path(),
             TaskEngineConfig {
                 max_continuation_rounds: 4,
                 provider_retry_limit: 0,
-                gray_zone_verifier_enabled: true,
+                gray_zone_verifier_enabled: false,
                 gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
             },
-            verifier.clone(),
         )
         .expect("task engine");
         let provider = ScriptedProvider::new(vec![
             Ok("我正在检查当前文件状态。".to_string()),
             Ok("任务已完成。".to_string()),
         ]);
-        let observer = NoopObserver;
         let mut history = vec![
             ChatMessage::system("system"),
             ChatMessage::user("请继续处理这个任务"),
         ];
         let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
-        let req = TaskRunRequest {
-            channel: "imessage",
-            sender_key: "sender-a",
-            reply_target: "sender-a",
-            original_request: "请继续处理这个任务",
-            provider: &provider,
-            history: &mut history,
-            tools_registry: &tools_registry,
-            observer: &observer,
-            provider_name: "test-provider",
-            model: "test-model",
-            temperature: 0.0,
-            multimodal: &crate::config::MultimodalConfig::default(),
-            max_tool_iterations: 5,
-            cancellation_token: None,
-            on_delta: None,
-            hooks: None,
-            excluded_tools: &[],
-            progress_reporter: None,
-        };
+        let req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
 
         let outcome = TaskEngine::run_task(req, &engine)
             .await
             .expect("task should complete");
-        assert_eq!(outcome.final_response, "任务已完成。");
-        assert_eq!(verifier.calls(), 1);
-        let row = engine
-            .store()
-            .get_task_run(&outcome.task_id)
-            .expect("get task")
-            .expect("task exists");
-        assert!(row.attempt_count >= 2);
-        assert_eq!(row.status.as_str(), "completed");
+
+        let markdown = engine
+            .export_transcript(&outcome.task_id, TranscriptFormat::Markdown)
+            .expect("markdown transcript");
+        let first_reply = markdown.find("我正在检查当前文件状态").expect("round 1");
+        let continued = markdown.find("**continue**").expect("continue event");
+        let final_reply = markdown.rfind("任务已完成").expect("round 2");
+        assert!(first_reply < continued && continued < final_reply);
+
+        let json: serde_json::Value = serde_json::from_str(
+            &engine
+                .export_transcript(&outcome.task_id, TranscriptFormat::Json)
+                .expect("json transcript"),
+        )
+        .expect("valid json");
+        assert_eq!(json["task"]["status"], "completed");
+        assert!(engine
+            .export_transcript("missing", TranscriptFormat::Json)
+            .is_err());
     }
 
     #[tokio::test]
-    async fn task_engine_state_machine_running_verifying_completed_transition() {
+    async fn run_existing_task_resumes_from_checkpoint_round() {
         let tmp = TempDir::new().expect("tempdir");
         let engine = TaskEngine::new(
             tmp.path(),
@@ -1004,227 +4237,307 @@ mod tests {
                 provider_retry_limit: 0,
                 gray_zone_verifier_enabled: false,
                 gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..TaskEngineConfig::default()
             },
         )
         .expect("task engine");
-        let provider = ScriptedProvider::new(vec![
-            Ok("我正在检查当前文件状态。".to_string()),
-            Ok("任务已完成。".to_string()),
-        ]);
-        let observer = NoopObserver;
+        let task_id = engine
+            .create_task("imessage", "sender-a", "sender-a", "请继续处理这个任务")
+            .expect("create task");
+        engine
+            .store()
+            .save_checkpoint(
+                &task_id,
+                3,
+                2,
+                false,
+                &[
+                    ChatMessage::assistant("我正在检查当前文件状态。"),
+                    ChatMessage::user("[Task Engine]\n任务尚未完成。"),
+                ],
+            )
+            .expect("save checkpoint");
+
+        let provider = ScriptedProvider::new(vec![Ok("任务已完成。".to_string())]);
         let mut history = vec![
             ChatMessage::system("system"),
             ChatMessage::user("请继续处理这个任务"),
         ];
         let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
-        let req = TaskRunRequest {
-            channel: "imessage",
-            sender_key: "sender-a",
-            reply_target: "sender-a",
-            original_request: "请继续处理这个任务",
-            provider: &provider,
-            history: &mut history,
-            tools_registry: &tools_registry,
-            observer: &observer,
-            provider_name: "test-provider",
-            model: "test-model",
-            temperature: 0.0,
-            multimodal: &crate::config::MultimodalConfig::default(),
-            max_tool_iterations: 5,
-            cancellation_token: None,
-            on_delta: None,
-            hooks: None,
-            excluded_tools: &[],
-            progress_reporter: None,
-        };
+        let mut req = test_request(
+            &provider,
+            &mut history,
+            &tools_registry,
+            "请继续处理这个任务",
+        );
 
-        let outcome = TaskEngine::run_task(req, &engine)
+        let outcome = engine
+            .run_existing_task(&task_id, &mut req)
             .await
-            .expect("task should complete");
+            .expect("resumed task should complete");
         assert_eq!(outcome.final_response, "任务已完成。");
 
-        let events = engine
+        assert!(history
+            .iter()
+            .any(|msg| msg.content == "我正在检查当前文件状态。"));
+        let events = engine.store().list_events(&task_id).expect("events");
+        assert!(events.iter().any(|e| e.event_type == "checkpoint_restored"));
+        let completed = events
+            .iter()
+            .find(|e| e.event_type == "completed")
+            .expect("completed event");
+        assert!(completed
+            .payload_json
+            .as_deref()
+            .is_some_and(|payload| payload.contains("\"round\":4")));
+        assert!(engine
             .store()
-            .list_events(&outcome.task_id)
-            .expect("list events");
-        let event_types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
-        assert!(event_types.contains(&"continue"));
-        assert!(event_types.contains(&"completed"));
+            .load_checkpoint(&task_id)
+            .expect("load checkpoint")
+            .is_none());
     }
 
     #[tokio::test]
-    async fn task_engine_state_machine_running_verifying_blocked_transition() {
+    async fn run_existing_task_refuses_task_claimed_by_another_worker() {
         let tmp = TempDir::new().expect("tempdir");
-        let engine = TaskEngine::new(
-            tmp.path(),
-            TaskEngineConfig {
-                max_continuation_rounds: 4,
-                provider_retry_limit: 0,
-                gray_zone_verifier_enabled: false,
-                gray_zone_verifier_timeout_ms: 1500,
-            },
-        )
-        .expect("task engine");
-        let provider = ScriptedProvider::new(vec![Ok("我会继续分析这个目录。".to_string())]);
-        let observer = NoopObserver;
-        let mut history = vec![
-            ChatMessage::system("system"),
-            ChatMessage::user("分析 studio 目录项目"),
-            ChatMessage::assistant(
-                r#"<tool_call>
-{"name":"file_read","arguments":{"path":"studio"}}
-</tool_call>"#,
-            ),
-            ChatMessage::user(
-                "[Tool results]\n<tool_result name=\"file_read\">\nERROR: path not allowed outside workspace\n</tool_result>",
-            ),
-        ];
+        let engine = TaskEngine::default_for_workspace(tmp.path()).expect("task engine");
+        let task_id = engine
+            .create_task("imessage", "sender-a", "sender-a", "整理报告")
+            .expect("create task");
+        assert!(engine
+            .store()
+            .try_claim(&task_id, "other-worker", TASK_CLAIM_LEASE)
+            .expect("foreign claim"));
+
+        let provider = ScriptedProvider::new(vec![Ok("任务已完成。".to_string())]);
+        let mut history = vec![ChatMessage::user("整理报告")];
         let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
-        let req = TaskRunRequest {
-            channel: "imessage",
-            sender_key: "sender-a",
-            reply_target: "sender-a",
-            original_request: "分析 studio 目录项目",
-            provider: &provider,
-            history: &mut history,
-            tools_registry: &tools_registry,
-            observer: &observer,
-            provider_name: "test-provider",
-            model: "test-model",
-            temperature: 0.0,
-            multimodal: &crate::config::MultimodalConfig::default(),
-            max_tool_iterations: 5,
-            cancellation_token: None,
-            on_delta: None,
-            hooks: None,
-            excluded_tools: &[],
-            progress_reporter: None,
-        };
+        let mut req = test_request(&provider, &mut history, &tools_registry, "整理报告");
 
-        let outcome = TaskEngine::run_task(req, &engine)
+        let err = engine
+            .run_existing_task(&task_id, &mut req)
             .await
-            .expect("task should be blocked");
-        assert!(outcome.final_response.contains("任务已阻塞"));
+            .expect_err("claimed task must not run twice");
+        assert!(err.to_string().contains("claimed by another worker"));
+        assert_eq!(
+            engine
+                .store()
+                .get_claim(&task_id)
+                .expect("claim")
+                .map(|claim| claim.worker_id),
+            Some("other-worker".to_string())
+        );
+    }
+
+    struct HangingProvider;
+
+    #[async_trait]
+    impl Provider for HangingProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            Ok("too late".to_string())
+        }
+    }
+
+    #[test]
+    fn completion_policy_for_prefers_channel_override() {
+        let mut cfg = TaskEngineConfig::default();
+        cfg.channel_completion_policies.insert(
+            "imessage".to_string(),
+            crate::config::CompletionPolicy {
+                progress_stall_threshold: 2,
+                ..crate::config::CompletionPolicy::default()
+            },
+        );
+        assert_eq!(
+            cfg.completion_policy_for("imessage")
+                .progress_stall_threshold,
+            2
+        );
+        assert_eq!(
+            cfg.completion_policy_for("telegram")
+                .progress_stall_threshold,
+            6
+        );
+    }
+
+    #[test]
+    fn fail_stuck_tasks_fails_silent_runs_unless_claimed_elsewhere() {
+        let tmp = TempDir::new().expect("tempdir");
+        let engine = TaskEngine::default_for_workspace(tmp.path()).expect("task engine");
+        let stuck = engine
+            .create_task("imessage", "sender-a", "sender-a", "整理报告")
+            .expect("create task");
+        let owned = engine
+            .create_task("imessage", "sender-b", "sender-b", "写总结")
+            .expect("create task");
+        let live = engine
+            .create_task("imessage", "sender-c", "sender-c", "跑测试")
+            .expect("create task");
+        for id in [&stuck, &owned, &live] {
+            engine
+                .store()
+                .update_status(id, TaskStatus::Running)
+                .expect("running");
+        }
+        assert!(engine
+            .store()
+            .try_claim(&owned, "other-worker", super::TASK_CLAIM_LEASE)
+            .expect("foreign claim"));
+        // A run in flight in this process holds its own claim.
+        assert!(engine
+            .store()
+            .try_claim(&live, engine.worker_id(), super::TASK_CLAIM_LEASE)
+            .expect("own claim"));
+        engine.running.lock().unwrap().insert(
+            live.clone(),
+            super::TaskWatchdog::arm(0, tokio_util::sync::CancellationToken::new()).canceller(),
+        );
+
+        assert!(engine
+            .fail_stuck_tasks(std::time::Duration::from_secs(600))
+            .expect("nothing silent yet")
+            .is_empty());
+        let failed = engine
+            .fail_stuck_tasks(std::time::Duration::ZERO)
+            .expect("sweep");
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].id, stuck);
 
         let row = engine
             .store()
-            .get_task_run(&outcome.task_id)
-            .expect("get task")
-            .expect("task exists");
-        assert_eq!(row.status.as_str(), "blocked");
+            .get_task_run(&stuck)
+            .expect("get")
+            .expect("row");
+        assert_eq!(row.status, TaskStatus::Failed);
+        let event = engine
+            .store()
+            .latest_lifecycle_event(&stuck)
+            .expect("event")
+            .expect("failed event");
+        assert_eq!(event.event_type, "failed");
+        assert!(event.payload_json.unwrap_or_default().contains("\"stuck\""));
+        assert!(engine.store().get_claim(&stuck).expect("claim").is_none());
+        for id in [&owned, &live] {
+            assert_eq!(
+                engine
+                    .store()
+                    .get_task_run(id)
+                    .expect("get")
+                    .expect("row")
+                    .status,
+                TaskStatus::Running
+            );
+        }
     }
 
     #[tokio::test]
-    async fn task_engine_state_machine_running_verifying_continue_running_transition() {
+    async fn task_engine_watchdog_marks_hung_task_failed_with_timeout() {
         let tmp = TempDir::new().expect("tempdir");
         let engine = TaskEngine::new(
             tmp.path(),
             TaskEngineConfig {
                 max_continuation_rounds: 4,
-                provider_retry_limit: 0,
+                provider_retry_limit: 2,
                 gray_zone_verifier_enabled: false,
                 gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 1,
+                ..TaskEngineConfig::default()
             },
         )
         .expect("task engine");
-        let provider = ScriptedProvider::new(vec![
-            Ok("我正在检查当前文件状态。".to_string()),
-            Ok("我正在继续处理，请稍等。".to_string()),
-            Ok("任务已完成。".to_string()),
-        ]);
-        let observer = NoopObserver;
+        let task_id = engine
+            .create_task("imessage", "sender-a", "sender-a", "请整理报告")
+            .expect("create task");
+        let provider = HangingProvider;
         let mut history = vec![
             ChatMessage::system("system"),
-            ChatMessage::user("请继续处理这个任务"),
+            ChatMessage::user("请整理报告"),
         ];
         let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
-        let req = TaskRunRequest {
-            channel: "imessage",
-            sender_key: "sender-a",
-            reply_target: "sender-a",
-            original_request: "请继续处理这个任务",
-            provider: &provider,
-            history: &mut history,
-            tools_registry: &tools_registry,
-            observer: &observer,
-            provider_name: "test-provider",
-            model: "test-model",
-            temperature: 0.0,
-            multimodal: &crate::config::MultimodalConfig::default(),
-            max_tool_iterations: 5,
-            cancellation_token: None,
-            on_delta: None,
-            hooks: None,
-            excluded_tools: &[],
-            progress_reporter: None,
+        let caller_token = tokio_util::sync::CancellationToken::new();
+        let mut req = TaskRunRequest {
+            cancellation_token: Some(caller_token.clone()),
+            ..test_request(&provider, &mut history, &tools_registry, "请整理报告")
         };
 
-        let outcome = TaskEngine::run_task(req, &engine)
+        let err = engine
+            .run_existing_task(&task_id, &mut req)
             .await
-            .expect("task should complete");
-        assert_eq!(outcome.final_response, "任务已完成。");
+            .expect_err("hung task should time out");
+        assert!(format!("{err:#}").contains("timed out"));
+        assert!(!caller_token.is_cancelled());
 
         let row = engine
             .store()
-            .get_task_run(&outcome.task_id)
+            .get_task_run(&task_id)
             .expect("get task")
             .expect("task exists");
-        assert!(row.attempt_count >= 3);
+        assert_eq!(row.status.as_str(), "failed");
+        let events = engine.store().list_events(&task_id).expect("events");
+        assert!(events.iter().any(|e| e.event_type == "timed_out"));
+        assert!(!events.iter().any(|e| e.event_type == "provider_retry"));
     }
 
     #[tokio::test]
-    async fn task_engine_state_machine_detects_stalled_loop_after_six_progress_only_rounds() {
+    async fn cancel_task_stops_a_running_task_and_marks_it_cancelled() {
         let tmp = TempDir::new().expect("tempdir");
-        let engine = TaskEngine::new(
-            tmp.path(),
-            TaskEngineConfig {
-                max_continuation_rounds: 8,
-                provider_retry_limit: 0,
-                gray_zone_verifier_enabled: false,
-                gray_zone_verifier_timeout_ms: 1500,
-            },
-        )
-        .expect("task engine");
-        let provider = ScriptedProvider::new(vec![
-            Ok("我正在检查当前文件状态。".to_string()),
-            Ok("我正在继续处理，请稍等。".to_string()),
-            Ok("我会继续处理，请稍等。".to_string()),
-            Ok("我正在处理更多细节，请稍等。".to_string()),
-            Ok("我正在继续推进，请稍等。".to_string()),
-            Ok("我会继续处理，很快给你结果。".to_string()),
-            Ok("我正在收敛结果，请稍后。".to_string()),
-        ]);
-        let observer = NoopObserver;
+        let engine = TaskEngine::default_for_workspace(tmp.path()).expect("task engine");
+        let provider = HangingProvider;
         let mut history = vec![
             ChatMessage::system("system"),
-            ChatMessage::user("请继续处理这个任务"),
+            ChatMessage::user("请整理报告"),
         ];
         let tools_registry: Vec<Box<dyn Tool>> = Vec::new();
-        let req = TaskRunRequest {
-            channel: "imessage",
-            sender_key: "sender-a",
-            reply_target: "sender-a",
-            original_request: "请继续处理这个任务",
-            provider: &provider,
-            history: &mut history,
-            tools_registry: &tools_registry,
-            observer: &observer,
-            provider_name: "test-provider",
-            model: "test-model",
-            temperature: 0.0,
-            multimodal: &crate::config::MultimodalConfig::default(),
-            max_tool_iterations: 5,
-            cancellation_token: None,
-            on_delta: None,
-            hooks: None,
-            excluded_tools: &[],
-            progress_reporter: None,
+        let caller_token = tokio_util::sync::CancellationToken::new();
+        let mut req = TaskRunRequest {
+            channel: "telegram",
+            cancellation_token: Some(caller_token.clone()),
+            ..test_request(&provider, &mut history, &tools_registry, "请整理报告")
         };
+        let task_id = engine.start_task(&req).expect("start task");
 
-        let err = TaskEngine::run_task(req, &engine)
-            .await
-            .expect_err("task should fail due to stalled loop");
-        assert!(format!("{err:#}").contains("stalled"));
+        let cancel = async {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            engine.cancel_task(&task_id).expect("cancel")
+        };
+        let (result, cancelled) =
+            tokio::join!(engine.run_existing_task(&task_id, &mut req), cancel);
+        assert!(cancelled);
+        let err = result.expect_err("cancelled task should not complete");
+        assert!(crate::agent::loop_::is_tool_loop_cancelled(&err));
+        assert!(!caller_token.is_cancelled());
+
+        let row = engine
+            .store()
+            .get_task_run(&task_id)
+            .expect("get task")
+            .expect("task exists");
+        assert_eq!(row.status, TaskStatus::Cancelled);
+        let events = engine.store().list_events(&task_id).expect("events");
+        assert!(events
+            .iter()
+            .any(|e| e.event_type == super::CANCELLED_BY_USER_EVENT));
+        assert!(!engine.cancel_task(&task_id).expect("already finished"));
+    }
+
+    #[test]
+    fn effective_task_labels_keeps_caller_labels_and_appends_task_type() {
+        let labels = super::effective_task_labels(
+            &["hint:fast".to_string(), "  ".to_string()],
+            crate::agent::task_contract::TaskType::Search,
+        );
+        assert_eq!(
+            labels,
+            vec!["hint:fast".to_string(), "task_type:search".to_string()]
+        );
     }
 
     #[test]
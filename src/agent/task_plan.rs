@@ -0,0 +1,119 @@
+//! Plan-only runs: a numbered execution plan the caller approves first.
+//!
+//! [`TaskEngine::plan_task`](crate::agent::task_engine::TaskEngine::plan_task)
+//! asks the model for the steps it would take, with no tools offered, and
+//! stores them as a [`PLAN_EVENT`]. The task stays queued until the caller
+//! approves it; the approved steps are then handed to the model at the start
+//! of `run_existing_task`.
+
+use crate::agent::task_types::TaskEventRecord;
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Event holding a proposed plan (`steps` and the model's raw `text`).
+pub const PLAN_EVENT: &str = "plan";
+
+/// Event marking the latest plan as approved for execution.
+pub const PLAN_APPROVED_EVENT: &str = "plan_approved";
+
+/// Event marking the plan as rejected; the task is cancelled.
+pub const PLAN_REJECTED_EVENT: &str = "plan_rejected";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskPlan {
+    pub task_id: String,
+    pub steps: Vec<String>,
+    /// The model's reply as returned, for display when parsing found no list.
+    pub text: String,
+}
+
+/// Instruction appended to the conversation to get a plan instead of actions.
+pub fn plan_prompt(enabled_tools: &[String]) -> String {
+    let tools = if enabled_tools.is_empty() {
+        "none".to_string()
+    } else {
+        enabled_tools.join(", ")
+    };
+    format!(
+        "Do not carry out the request yet and do not call any tools. Reply only with a numbered list of the steps you would take to complete it. Name the tool each step would use (available: {tools}) and mark steps that delete, overwrite, or run shell commands with [destructive]."
+    )
+}
+
+/// Numbered items of `text` (`1.`, `1)`, `1、`); falls back to its non-empty
+/// lines when the model did not number them.
+pub fn parse_plan_steps(text: &str) -> Vec<String> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let numbered: Vec<String> = lines
+        .iter()
+        .filter_map(|line| {
+            let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+            if rest.len() == line.len() {
+                return None;
+            }
+            let step = rest.strip_prefix(['.', ')', '、'])?.replace("**", "");
+            let step = step.trim();
+            (!step.is_empty()).then(|| step.to_string())
+        })
+        .collect();
+    if numbered.is_empty() {
+        lines.into_iter().map(str::to_string).collect()
+    } else {
+        numbered
+    }
+}
+
+/// Steps of the most recent plan in `events`, if that plan was approved.
+pub fn approved_plan_steps(events: &[TaskEventRecord]) -> Option<Vec<String>> {
+    let plan_index = events
+        .iter()
+        .rposition(|event| event.event_type == PLAN_EVENT)?;
+    if !events[plan_index..]
+        .iter()
+        .any(|event| event.event_type == PLAN_APPROVED_EVENT)
+    {
+        return None;
+    }
+    let payload: serde_json::Value =
+        serde_json::from_str(events[plan_index].payload_json.as_deref()?).ok()?;
+    serde_json::from_value(payload.get("steps")?.clone()).ok()
+}
+
+/// Message that opens an approved run, so the model follows the plan.
+pub fn approved_plan_message(steps: &[String]) -> String {
+    let mut message = String::from(
+        "The user approved this plan. Carry it out step by step and ask before doing anything it does not cover:\n",
+    );
+    for (index, step) in steps.iter().enumerate() {
+        let _ = write!(message, "\n{}. {}", index + 1, step);
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_steps_parse_from_numbered_and_plain_replies() {
+        assert_eq!(
+            parse_plan_steps(
+                "Plan:\n1. List *.log files (shell)\n2) **Delete them** [destructive]\n3、Report"
+            ),
+            vec![
+                "List *.log files (shell)",
+                "Delete them [destructive]",
+                "Report"
+            ]
+        );
+        assert_eq!(
+            parse_plan_steps("Read the config\n\nUpdate the port"),
+            vec!["Read the config", "Update the port"]
+        );
+        assert!(approved_plan_message(&["Read".into(), "Write".into()])
+            .ends_with("\n1. Read\n2. Write"));
+    }
+}
@@ -0,0 +1,98 @@
+//! Task runs split into explicit steps.
+//!
+//! Steps come from the caller (`TaskRunRequest::steps`) or, with
+//! `autonomy.task_step_decomposition`, from the model, asked for a numbered
+//! list the same way as for a plan. They are stored in the `task_steps` table
+//! and run one at a time, each as its own bounded tool loop, before the usual
+//! completion checks on the whole request. A resumed run skips the steps
+//! already completed.
+
+use crate::agent::task_types::{TaskStepRecord, TaskStepStatus};
+use std::fmt::Write;
+
+/// Event listing a run's steps and where they came from (`caller`/`model`).
+pub const STEPS_RECORDED_EVENT: &str = "steps_recorded";
+
+/// Event marking one step finished, with its index and response.
+pub const STEP_COMPLETED_EVENT: &str = "step_completed";
+
+/// Event marking the step a run failed on.
+pub const STEP_FAILED_EVENT: &str = "step_failed";
+
+/// Model decompositions shorter than this run as a single request.
+pub const MIN_DECOMPOSED_STEPS: usize = 2;
+
+/// Instruction sent once every step is done, before the final answer.
+pub const STEPS_DONE_PROMPT: &str =
+    "All steps are done. Check the results against the original request and give the final answer.";
+
+/// Characters of a completed step's response repeated to a resumed run.
+const RESUMED_RESPONSE_CHARS: usize = 300;
+
+/// Instruction that starts one step.
+pub fn step_prompt(step: &TaskStepRecord, total: usize) -> String {
+    format!(
+        "Step {}/{}: {}\n\nDo only this step now, using tools as needed, and reply with what you did and what it produced. The remaining steps follow separately.",
+        step.step_index + 1,
+        total,
+        step.description
+    )
+}
+
+/// Recap of the steps a previous attempt already completed, for a resumed run.
+pub fn completed_steps_message(steps: &[TaskStepRecord]) -> Option<String> {
+    let mut completed = steps
+        .iter()
+        .filter(|step| step.status == TaskStepStatus::Completed)
+        .peekable();
+    completed.peek()?;
+    let mut message = String::from("These steps of the task are already done:\n");
+    for step in completed {
+        let response: String = step
+            .response
+            .as_deref()
+            .unwrap_or_default()
+            .chars()
+            .take(RESUMED_RESPONSE_CHARS)
+            .collect();
+        let _ = write!(
+            message,
+            "\n{}. {} — {}",
+            step.step_index + 1,
+            step.description,
+            response.trim()
+        );
+    }
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(index: usize, description: &str, status: TaskStepStatus) -> TaskStepRecord {
+        TaskStepRecord {
+            task_id: "task-1".to_string(),
+            step_index: index,
+            description: description.to_string(),
+            status,
+            response: (status == TaskStepStatus::Completed).then(|| "12 files".to_string()),
+            updated_at: "2026-03-01T10:00:00Z".to_string(),
+            completed_at: None,
+        }
+    }
+
+    #[test]
+    fn prompts_number_steps_and_recap_completed_ones() {
+        let steps = vec![
+            step(0, "Collect logs", TaskStepStatus::Completed),
+            step(1, "Write summary", TaskStepStatus::Pending),
+        ];
+        assert!(step_prompt(&steps[1], 2).starts_with("Step 2/2: Write summary\n"));
+        assert_eq!(
+            completed_steps_message(&steps).as_deref(),
+            Some("These steps of the task are already done:\n\n1. Collect logs — 12 files")
+        );
+        assert!(completed_steps_message(&steps[1..]).is_none());
+    }
+}
@@ -1,22 +1,48 @@
-use crate::agent::task_types::{TaskArtifactRecord, TaskEventRecord, TaskRunRecord, TaskStatus};
+use crate::agent::task_transcript::TRANSCRIPT_MESSAGE_EVENT;
+use crate::agent::task_types::{
+    ArtifactPreview, ConversationBranchRecord, NewTaskEvent, PinnedInstructionRecord,
+    TaskArtifactRecord, TaskCheckpoint, TaskClaim, TaskEventRecord, TaskNoteRecord,
+    TaskOutboxRecord, TaskRunRecord, TaskStatus, TaskStatusSummary, TaskStepRecord, TaskStepStatus,
+    TaskStoreGauges, TaskUsageTotals, ToolInvocation,
+};
 use anyhow::{Context, Result};
-use chrono::Utc;
-use rusqlite::{params, Connection};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde_json::Value;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+/// Event appended periodically while a task run is in flight.
+pub const TASK_HEARTBEAT_EVENT: &str = "heartbeat";
+
+/// Event holding one executed tool call (a serialized `ToolInvocation`).
+pub const TOOL_INVOCATION_EVENT: &str = "tool_invocation";
+
+/// Event appended when a queued reply is given up on after its last attempt.
+pub const OUTBOX_DEAD_LETTER_EVENT: &str = "reply_dead_lettered";
+
+#[derive(Clone)]
 pub struct TaskStore {
     db_path: PathBuf,
+    workspace_dir: PathBuf,
 }
 
 impl TaskStore {
     pub fn new(workspace_dir: &Path) -> Result<Self> {
         let db_path = workspace_dir.join("state").join("task-runs.db");
-        let store = Self { db_path };
+        let store = Self {
+            db_path,
+            workspace_dir: workspace_dir.to_path_buf(),
+        };
         store.with_connection(|_| Ok(()))?;
         Ok(store)
     }
 
+    /// Workspace that relative artifact paths resolve against.
+    pub fn workspace_dir(&self) -> &Path {
+        &self.workspace_dir
+    }
+
     fn with_connection<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
         if let Some(parent) = self.db_path.parent() {
             std::fs::create_dir_all(parent).with_context(|| {
@@ -29,6 +55,11 @@ impl TaskStore {
 
         let conn = Connection::open(&self.db_path)
             .with_context(|| format!("Failed to open task-store DB: {}", self.db_path.display()))?;
+        let fts_existed: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'task_runs_fts')",
+            [],
+            |row| row.get(0),
+        )?;
 
         conn.execute_batch(
             "PRAGMA foreign_keys = ON;
@@ -44,13 +75,37 @@ impl TaskStore {
                provider_retry_count INTEGER NOT NULL DEFAULT 0,
                created_at           TEXT NOT NULL,
                updated_at           TEXT NOT NULL,
-               completed_at         TEXT
+               completed_at         TEXT,
+               claimed_by           TEXT,
+               claim_expires_at     TEXT,
+               archived_at          TEXT
              );
              CREATE INDEX IF NOT EXISTS idx_task_runs_status
                ON task_runs(status);
              CREATE INDEX IF NOT EXISTS idx_task_runs_sender_status
                ON task_runs(channel, sender_key, status);
 
+             -- Trigram tokenizer so CJK requests (no word spacing) match substrings.
+             CREATE VIRTUAL TABLE IF NOT EXISTS task_runs_fts USING fts5(
+               original_request, last_response,
+               content=task_runs, content_rowid=rowid, tokenize='trigram'
+             );
+             CREATE TRIGGER IF NOT EXISTS task_runs_fts_ai AFTER INSERT ON task_runs BEGIN
+               INSERT INTO task_runs_fts(rowid, original_request, last_response)
+               VALUES (new.rowid, new.original_request, new.last_response);
+             END;
+             CREATE TRIGGER IF NOT EXISTS task_runs_fts_ad AFTER DELETE ON task_runs BEGIN
+               INSERT INTO task_runs_fts(task_runs_fts, rowid, original_request, last_response)
+               VALUES ('delete', old.rowid, old.original_request, old.last_response);
+             END;
+             CREATE TRIGGER IF NOT EXISTS task_runs_fts_au
+               AFTER UPDATE OF original_request, last_response ON task_runs BEGIN
+               INSERT INTO task_runs_fts(task_runs_fts, rowid, original_request, last_response)
+               VALUES ('delete', old.rowid, old.original_request, old.last_response);
+               INSERT INTO task_runs_fts(rowid, original_request, last_response)
+               VALUES (new.rowid, new.original_request, new.last_response);
+             END;
+
              CREATE TABLE IF NOT EXISTS task_events (
                id         INTEGER PRIMARY KEY AUTOINCREMENT,
                task_id    TEXT NOT NULL,
@@ -72,9 +127,108 @@ impl TaskStore {
                FOREIGN KEY(task_id) REFERENCES task_runs(id) ON DELETE CASCADE
              );
              CREATE UNIQUE INDEX IF NOT EXISTS idx_task_artifacts_task_path
-               ON task_artifacts(task_id, path);",
+               ON task_artifacts(task_id, path);
+
+             CREATE TABLE IF NOT EXISTS task_checkpoints (
+               task_id                   TEXT PRIMARY KEY,
+               next_round                INTEGER NOT NULL,
+               consecutive_progress_only INTEGER NOT NULL DEFAULT 0,
+               write_verified            INTEGER NOT NULL DEFAULT 0,
+               history_delta             TEXT NOT NULL,
+               updated_at                TEXT NOT NULL,
+               FOREIGN KEY(task_id) REFERENCES task_runs(id) ON DELETE CASCADE
+             );",
         )
         .context("Failed to initialize task-store schema")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sender_settings (
+               channel    TEXT NOT NULL,
+               sender_key TEXT NOT NULL,
+               key        TEXT NOT NULL,
+               value      TEXT NOT NULL,
+               updated_at TEXT NOT NULL,
+               PRIMARY KEY (channel, sender_key, key)
+             );",
+        )
+        .context("Failed to initialize sender-settings schema")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS conversation_branches (
+               id               INTEGER PRIMARY KEY AUTOINCREMENT,
+               conversation_key TEXT NOT NULL,
+               messages         TEXT NOT NULL,
+               archived_at      TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_conversation_branches_key
+               ON conversation_branches(conversation_key, id);",
+        )
+        .context("Failed to initialize conversation-branch schema")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS pinned_instructions (
+               id               INTEGER PRIMARY KEY AUTOINCREMENT,
+               conversation_key TEXT NOT NULL,
+               content          TEXT NOT NULL,
+               pinned_at        TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_pinned_instructions_key
+               ON pinned_instructions(conversation_key, id);",
+        )
+        .context("Failed to initialize pinned-instruction schema")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS task_notes (
+               id         INTEGER PRIMARY KEY AUTOINCREMENT,
+               task_id    TEXT NOT NULL,
+               author     TEXT NOT NULL,
+               body       TEXT NOT NULL,
+               created_at TEXT NOT NULL,
+               FOREIGN KEY(task_id) REFERENCES task_runs(id) ON DELETE CASCADE
+             );
+             CREATE INDEX IF NOT EXISTS idx_task_notes_task
+               ON task_notes(task_id, id);",
+        )
+        .context("Failed to initialize task-note schema")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS task_steps (
+               task_id      TEXT NOT NULL,
+               step_index   INTEGER NOT NULL,
+               description  TEXT NOT NULL,
+               status       TEXT NOT NULL,
+               response     TEXT,
+               updated_at   TEXT NOT NULL,
+               completed_at TEXT,
+               PRIMARY KEY (task_id, step_index),
+               FOREIGN KEY(task_id) REFERENCES task_runs(id) ON DELETE CASCADE
+             );",
+        )
+        .context("Failed to initialize task-step schema")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS task_outbox (
+               id              INTEGER PRIMARY KEY AUTOINCREMENT,
+               task_id         TEXT NOT NULL,
+               channel         TEXT NOT NULL,
+               reply_target    TEXT NOT NULL,
+               body            TEXT NOT NULL,
+               attempts        INTEGER NOT NULL DEFAULT 0,
+               last_error      TEXT,
+               created_at      TEXT NOT NULL,
+               next_attempt_at TEXT NOT NULL,
+               delivered_at    TEXT,
+               FOREIGN KEY(task_id) REFERENCES task_runs(id) ON DELETE CASCADE
+             );
+             CREATE INDEX IF NOT EXISTS idx_task_outbox_pending
+               ON task_outbox(delivered_at, next_attempt_at);",
+        )
+        .context("Failed to initialize task-outbox schema")?;
+        add_column_if_missing(&conn, "task_runs", "claimed_by", "TEXT")?;
+        add_column_if_missing(&conn, "task_runs", "claim_expires_at", "TEXT")?;
+        add_column_if_missing(&conn, "task_runs", "archived_at", "TEXT")?;
+        add_column_if_missing(&conn, "task_artifacts", "mime_type", "TEXT")?;
+        add_column_if_missing(&conn, "task_artifacts", "preview", "TEXT")?;
+        add_column_if_missing(&conn, "task_outbox", "dead_lettered_at", "TEXT")?;
+        if !fts_existed {
+            // Index rows written before the search table existed.
+            conn.execute_batch("INSERT INTO task_runs_fts(task_runs_fts) VALUES('rebuild');")
+                .context("Failed to build task-run search index")?;
+        }
 
         f(&conn)
     }
@@ -132,6 +286,326 @@ impl TaskStore {
         })
     }
 
+    /// Claim `task_id` for `worker_id` for the next `lease`.
+    ///
+    /// Succeeds when the task is unclaimed, its previous claim has expired, or
+    /// `worker_id` already holds it (which renews the lease). Returns `false`
+    /// when another worker holds a live claim.
+    pub fn try_claim(&self, task_id: &str, worker_id: &str, lease: Duration) -> Result<bool> {
+        let now = Utc::now();
+        let lease = chrono::Duration::from_std(lease).unwrap_or(chrono::Duration::MAX);
+        let expires_at = now
+            .checked_add_signed(lease)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        self.with_connection(|conn| {
+            let changed = conn.execute(
+                "UPDATE task_runs
+                    SET claimed_by = ?2, claim_expires_at = ?3
+                  WHERE id = ?1
+                    AND (claimed_by IS NULL
+                         OR claimed_by = ?2
+                         OR claim_expires_at IS NULL
+                         OR claim_expires_at <= ?4)",
+                params![
+                    task_id,
+                    worker_id,
+                    claim_timestamp(expires_at),
+                    claim_timestamp(now)
+                ],
+            )?;
+            if changed == 1 {
+                return Ok(true);
+            }
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM task_runs WHERE id = ?1)",
+                params![task_id],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                anyhow::bail!("Task run '{task_id}' not found");
+            }
+            Ok(false)
+        })
+    }
+
+    /// Drop `worker_id`'s claim on `task_id`; a no-op if another worker owns it.
+    pub fn release_claim(&self, task_id: &str, worker_id: &str) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE task_runs
+                    SET claimed_by = NULL, claim_expires_at = NULL
+                  WHERE id = ?1 AND claimed_by = ?2",
+                params![task_id, worker_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn get_claim(&self, task_id: &str) -> Result<Option<TaskClaim>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT claimed_by, claim_expires_at
+                   FROM task_runs
+                  WHERE id = ?1 AND claimed_by IS NOT NULL",
+            )?;
+            let mut rows = stmt.query(params![task_id])?;
+            if let Some(row) = rows.next()? {
+                Ok(Some(TaskClaim {
+                    task_id: task_id.to_string(),
+                    worker_id: row.get(0)?,
+                    expires_at: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                }))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    /// Hide a terminal task from active views. Its events and artifacts are
+    /// kept and it stays reachable through `get_task_run` / `list_archived`.
+    pub fn archive(&self, task_id: &str) -> Result<()> {
+        let task = self
+            .get_task_run(task_id)?
+            .ok_or_else(|| anyhow::anyhow!("Task run '{task_id}' not found"))?;
+        if !task.status.is_terminal() {
+            anyhow::bail!(
+                "Task run '{task_id}' is {} and cannot be archived",
+                task.status.as_str()
+            );
+        }
+        let now = now_rfc3339();
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE task_runs
+                    SET archived_at = COALESCE(archived_at, ?2)
+                  WHERE id = ?1",
+                params![task_id, now],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Archive every terminal task that finished more than `max_age` ago.
+    /// Returns the number of tasks archived.
+    pub fn archive_terminal_tasks_older_than(&self, max_age: Duration) -> Result<usize> {
+        let max_age = chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+        let cutoff = Utc::now()
+            .checked_sub_signed(max_age)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC)
+            .to_rfc3339();
+        let now = now_rfc3339();
+        self.with_connection(|conn| {
+            let changed = conn.execute(
+                "UPDATE task_runs
+                    SET archived_at = ?1
+                  WHERE archived_at IS NULL
+                    AND status IN ('completed', 'failed', 'cancelled')
+                    AND completed_at IS NOT NULL
+                    AND completed_at < ?2",
+                params![now, cutoff],
+            )?;
+            Ok(changed)
+        })
+    }
+
+    /// Counts, round and retry averages, and failure reasons for tasks created
+    /// within the last `window`.
+    pub fn status_summary(&self, window: Duration) -> Result<TaskStatusSummary> {
+        let window_secs = window.as_secs();
+        let window = chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX);
+        let cutoff = Utc::now()
+            .checked_sub_signed(window)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC)
+            .to_rfc3339();
+
+        self.with_connection(|conn| {
+            let grouped = |sql: &str| -> Result<std::collections::BTreeMap<String, u64>> {
+                let mut stmt = conn.prepare(sql)?;
+                let rows = stmt.query_map(params![cutoff], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+                })?;
+                let mut out = std::collections::BTreeMap::new();
+                for row in rows {
+                    let (key, count) = row?;
+                    out.insert(key, u64::try_from(count).unwrap_or(0));
+                }
+                Ok(out)
+            };
+
+            let by_status = grouped(
+                "SELECT status, COUNT(*) FROM task_runs
+                  WHERE created_at >= ?1
+               GROUP BY status",
+            )?;
+            let by_channel = grouped(
+                "SELECT channel, COUNT(*) FROM task_runs
+                  WHERE created_at >= ?1
+               GROUP BY channel",
+            )?;
+            let failure_reasons = grouped(
+                "SELECT COALESCE(
+                          CASE WHEN json_valid(e.payload)
+                               THEN json_extract(e.payload, '$.reason') END,
+                          e.event_type),
+                        COUNT(*)
+                   FROM task_events e
+                   JOIN task_runs t ON t.id = e.task_id
+                  WHERE t.created_at >= ?1
+                    AND e.event_type IN ('failed', 'timed_out')
+               GROUP BY 1",
+            )?;
+
+            let (total, average_rounds, retried, provider_retries): (i64, f64, i64, i64) = conn
+                .query_row(
+                    "SELECT COUNT(*),
+                            COALESCE(AVG(attempt_count), 0.0),
+                            COALESCE(SUM(provider_retry_count > 0), 0),
+                            COALESCE(SUM(provider_retry_count), 0)
+                       FROM task_runs
+                      WHERE created_at >= ?1",
+                    params![cutoff],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )?;
+
+            let estimated_cost_usd: f64 = conn.query_row(
+                "SELECT COALESCE(SUM(json_extract(e.payload, '$.cost_usd')), 0.0)
+                   FROM task_events e
+                   JOIN task_runs t ON t.id = e.task_id
+                  WHERE t.created_at >= ?1
+                    AND e.event_type = 'llm_usage'
+                    AND json_valid(e.payload)",
+                params![cutoff],
+                |row| row.get(0),
+            )?;
+
+            let retry_rate = if total > 0 {
+                retried as f64 / total as f64
+            } else {
+                0.0
+            };
+            Ok(TaskStatusSummary {
+                window_secs,
+                total_tasks: u64::try_from(total).unwrap_or(0),
+                by_status,
+                by_channel,
+                average_rounds,
+                provider_retries: u64::try_from(provider_retries).unwrap_or(0),
+                retry_rate,
+                failure_reasons,
+                estimated_cost_usd,
+            })
+        })
+    }
+
+    /// Queue depth, oldest queued age, file size, and cold-storage backlog.
+    /// `cold_storage_after` of `None` (cold storage off) reports no backlog.
+    pub fn capacity_gauges(&self, cold_storage_after: Option<Duration>) -> Result<TaskStoreGauges> {
+        let backlog_cutoff = cold_storage_after.map(|max_age| {
+            let max_age = chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+            Utc::now()
+                .checked_sub_signed(max_age)
+                .unwrap_or(DateTime::<Utc>::MIN_UTC)
+                .to_rfc3339()
+        });
+        let (queued, oldest_queued, archive_backlog) = self.with_connection(|conn| {
+            let (queued, oldest_queued): (i64, Option<String>) = conn.query_row(
+                "SELECT COUNT(*), MIN(created_at) FROM task_runs WHERE status = 'queued'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            let archive_backlog: i64 = match &backlog_cutoff {
+                Some(cutoff) => conn.query_row(
+                    "SELECT COUNT(*) FROM task_runs
+                      WHERE status IN ('completed', 'failed', 'cancelled')
+                        AND completed_at IS NOT NULL
+                        AND completed_at < ?1",
+                    params![cutoff],
+                    |row| row.get(0),
+                )?,
+                None => 0,
+            };
+            Ok((queued, oldest_queued, archive_backlog))
+        })?;
+
+        let oldest_queued_age_secs = oldest_queued
+            .and_then(|created_at| DateTime::parse_from_rfc3339(&created_at).ok())
+            .map(|created_at| {
+                let age = Utc::now().signed_duration_since(created_at.with_timezone(&Utc));
+                u64::try_from(age.num_seconds()).unwrap_or(0)
+            })
+            .unwrap_or(0);
+        let mut wal_path = self.db_path.clone().into_os_string();
+        wal_path.push("-wal");
+        let db_size_bytes = [self.db_path.clone(), PathBuf::from(wal_path)]
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|meta| meta.len())
+            .sum();
+
+        Ok(TaskStoreGauges {
+            queued_tasks: u64::try_from(queued).unwrap_or(0),
+            oldest_queued_age_secs,
+            db_size_bytes,
+            archive_backlog: u64::try_from(archive_backlog).unwrap_or(0),
+        })
+    }
+
+    pub fn list_archived(&self) -> Result<Vec<TaskRunRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, channel, sender_key, reply_target, status, original_request,
+                        last_response, attempt_count, provider_retry_count,
+                        created_at, updated_at, completed_at, archived_at
+                   FROM task_runs
+                  WHERE archived_at IS NOT NULL
+               ORDER BY archived_at DESC",
+            )?;
+            let rows = stmt.query_map([], map_task_run_row)?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    /// Terminal tasks that finished more than `max_age` ago, archived or not,
+    /// oldest first. These are the rows moved to cold storage.
+    pub fn list_terminal_tasks_older_than(&self, max_age: Duration) -> Result<Vec<TaskRunRecord>> {
+        let max_age = chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+        let cutoff = Utc::now()
+            .checked_sub_signed(max_age)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC)
+            .to_rfc3339();
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, channel, sender_key, reply_target, status, original_request,
+                        last_response, attempt_count, provider_retry_count,
+                        created_at, updated_at, completed_at, archived_at
+                   FROM task_runs
+                  WHERE status IN ('completed', 'failed', 'cancelled')
+                    AND completed_at IS NOT NULL
+                    AND completed_at < ?1
+               ORDER BY completed_at ASC",
+            )?;
+            let rows = stmt.query_map(params![cutoff], map_task_run_row)?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    /// Remove a task run together with its events, artifacts, and checkpoint.
+    pub fn delete_task_run(&self, id: &str) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute("DELETE FROM task_runs WHERE id = ?1", params![id])
+                .with_context(|| format!("Failed to delete task run '{id}'"))?;
+            Ok(())
+        })
+    }
+
     pub fn increment_attempt_count(&self, id: &str) -> Result<()> {
         self.bump_counter(id, "attempt_count")
     }
@@ -174,161 +648,1216 @@ impl TaskStore {
         })
     }
 
-    pub fn get_task_run(&self, id: &str) -> Result<Option<TaskRunRecord>> {
+    /// Raw per-sender settings as key/value pairs.
+    pub fn sender_settings(
+        &self,
+        channel: &str,
+        sender_key: &str,
+    ) -> Result<std::collections::BTreeMap<String, String>> {
         self.with_connection(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, channel, sender_key, reply_target, status, original_request,
-                        last_response, attempt_count, provider_retry_count,
-                        created_at, updated_at, completed_at
-                   FROM task_runs
-                  WHERE id = ?1",
+                "SELECT key, value FROM sender_settings
+                  WHERE channel = ?1 AND sender_key = ?2",
             )?;
-            let mut rows = stmt.query(params![id])?;
-            if let Some(row) = rows.next()? {
-                Ok(Some(map_task_run_row(row)?))
-            } else {
-                Ok(None)
+            let rows = stmt.query_map(params![channel, sender_key], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            let mut out = std::collections::BTreeMap::new();
+            for row in rows {
+                let (key, value) = row?;
+                out.insert(key, value);
             }
+            Ok(out)
         })
     }
 
-    pub fn list_recoverable_tasks(&self) -> Result<Vec<TaskRunRecord>> {
+    /// Set one per-sender setting; `None` removes it so the default applies.
+    pub fn set_sender_setting(
+        &self,
+        channel: &str,
+        sender_key: &str,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<()> {
+        let now = now_rfc3339();
         self.with_connection(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT id, channel, sender_key, reply_target, status, original_request,
-                        last_response, attempt_count, provider_retry_count,
-                        created_at, updated_at, completed_at
-                   FROM task_runs
-                  WHERE status IN ('queued', 'running', 'blocked')
-               ORDER BY created_at ASC",
-            )?;
-            let rows = stmt.query_map([], map_task_run_row)?;
-            let mut out = Vec::new();
-            for row in rows {
-                out.push(row?);
-            }
-            Ok(out)
+            match value {
+                Some(value) => conn.execute(
+                    "INSERT INTO sender_settings (channel, sender_key, key, value, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT(channel, sender_key, key)
+                     DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                    params![channel, sender_key, key, value, now],
+                )?,
+                None => conn.execute(
+                    "DELETE FROM sender_settings
+                      WHERE channel = ?1 AND sender_key = ?2 AND key = ?3",
+                    params![channel, sender_key, key],
+                )?,
+            };
+            Ok(())
         })
     }
 
-    pub fn append_event(
+    /// Archive conversation turns removed from a sender's history.
+    pub fn archive_conversation_branch(
         &self,
-        task_id: &str,
-        event_type: &str,
-        payload: Option<&Value>,
-    ) -> Result<()> {
+        conversation_key: &str,
+        messages: &[crate::providers::ChatMessage],
+    ) -> Result<i64> {
         let now = now_rfc3339();
-        let payload_json = payload.map(Value::to_string);
+        let messages_json = serde_json::to_string(messages)
+            .context("Failed to serialize archived conversation branch")?;
         self.with_connection(|conn| {
             conn.execute(
-                "INSERT INTO task_events (task_id, event_type, payload, created_at)
-                 VALUES (?1, ?2, ?3, ?4)",
-                params![task_id, event_type, payload_json, now],
+                "INSERT INTO conversation_branches (conversation_key, messages, archived_at)
+                 VALUES (?1, ?2, ?3)",
+                params![conversation_key, messages_json, now],
             )
-            .with_context(|| format!("Failed to append task event for '{task_id}'"))?;
-            Ok(())
+            .with_context(|| format!("Failed to archive branch for '{conversation_key}'"))?;
+            Ok(conn.last_insert_rowid())
         })
     }
 
-    pub fn list_events(&self, task_id: &str) -> Result<Vec<TaskEventRecord>> {
+    /// Archived branches for a conversation, newest first.
+    pub fn list_conversation_branches(
+        &self,
+        conversation_key: &str,
+        limit: usize,
+    ) -> Result<Vec<ConversationBranchRecord>> {
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
         self.with_connection(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, task_id, event_type, payload, created_at
-                   FROM task_events
-                  WHERE task_id = ?1
-               ORDER BY id ASC",
+                "SELECT id, conversation_key, messages, archived_at
+                   FROM conversation_branches
+                  WHERE conversation_key = ?1
+               ORDER BY id DESC
+                  LIMIT ?2",
             )?;
-            let rows = stmt.query_map(params![task_id], |row| {
-                Ok(TaskEventRecord {
-                    id: row.get::<_, i64>(0)?,
-                    task_id: row.get(1)?,
-                    event_type: row.get(2)?,
-                    payload_json: row.get(3)?,
-                    created_at: row.get(4)?,
-                })
+            let rows = stmt.query_map(params![conversation_key, limit], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
             })?;
             let mut out = Vec::new();
             for row in rows {
-                out.push(row?);
+                let (id, conversation_key, messages_json, archived_at) = row?;
+                let messages = serde_json::from_str(&messages_json)
+                    .with_context(|| format!("Failed to parse archived branch {id}"))?;
+                out.push(ConversationBranchRecord {
+                    id,
+                    conversation_key,
+                    messages,
+                    archived_at,
+                });
             }
             Ok(out)
         })
     }
 
-    pub fn upsert_artifact_verification(
-        &self,
-        task_id: &str,
-        path: &str,
-        checksum: Option<&str>,
-        verified: bool,
-    ) -> Result<()> {
-        let verified_at = if verified { Some(now_rfc3339()) } else { None };
+    /// Pin `content` for a conversation and return its id.
+    pub fn pin_instruction(&self, conversation_key: &str, content: &str) -> Result<i64> {
+        let now = now_rfc3339();
         self.with_connection(|conn| {
             conn.execute(
-                "INSERT INTO task_artifacts (task_id, path, verified, checksum, verified_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5)
-                 ON CONFLICT(task_id, path) DO UPDATE SET
-                   verified = excluded.verified,
-                   checksum = excluded.checksum,
-                   verified_at = excluded.verified_at",
-                params![
-                    task_id,
-                    path,
-                    if verified { 1 } else { 0 },
-                    checksum,
-                    verified_at
-                ],
+                "INSERT INTO pinned_instructions (conversation_key, content, pinned_at)
+                 VALUES (?1, ?2, ?3)",
+                params![conversation_key, content, now],
             )
-            .with_context(|| format!("Failed to upsert task artifact for '{task_id}'"))?;
-            Ok(())
+            .with_context(|| format!("Failed to pin instruction for '{conversation_key}'"))?;
+            Ok(conn.last_insert_rowid())
         })
     }
 
-    pub fn list_artifacts(&self, task_id: &str) -> Result<Vec<TaskArtifactRecord>> {
+    /// Pinned instructions for a conversation, oldest first.
+    pub fn list_pinned_instructions(
+        &self,
+        conversation_key: &str,
+    ) -> Result<Vec<PinnedInstructionRecord>> {
         self.with_connection(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, task_id, path, verified, checksum, verified_at
-                   FROM task_artifacts
-                  WHERE task_id = ?1
+                "SELECT id, content, pinned_at
+                   FROM pinned_instructions
+                  WHERE conversation_key = ?1
                ORDER BY id ASC",
             )?;
-            let rows = stmt.query_map(params![task_id], |row| {
-                let verified_raw: i64 = row.get(3)?;
-                Ok(TaskArtifactRecord {
+            let rows = stmt.query_map(params![conversation_key], |row| {
+                Ok(PinnedInstructionRecord {
                     id: row.get(0)?,
-                    task_id: row.get(1)?,
-                    path: row.get(2)?,
-                    verified: verified_raw == 1,
-                    checksum: row.get(4)?,
-                    verified_at: row.get(5)?,
+                    content: row.get(1)?,
+                    pinned_at: row.get(2)?,
                 })
             })?;
-            let mut out = Vec::new();
-            for row in rows {
-                out.push(row?);
-            }
-            Ok(out)
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(Into::into)
         })
     }
-}
 
-fn now_rfc3339() -> String {
-    Utc::now().to_rfc3339()
-}
+    /// Remove one pinned instruction, or all of them when `id` is `None`.
+    /// Returns the number removed.
+    pub fn unpin_instructions(&self, conversation_key: &str, id: Option<i64>) -> Result<usize> {
+        self.with_connection(|conn| {
+            let removed = match id {
+                Some(id) => conn.execute(
+                    "DELETE FROM pinned_instructions WHERE conversation_key = ?1 AND id = ?2",
+                    params![conversation_key, id],
+                )?,
+                None => conn.execute(
+                    "DELETE FROM pinned_instructions WHERE conversation_key = ?1",
+                    params![conversation_key],
+                )?,
+            };
+            Ok(removed)
+        })
+    }
 
-fn map_task_run_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TaskRunRecord> {
-    let raw_status: String = row.get(4)?;
-    let status = TaskStatus::parse(&raw_status).ok_or_else(|| {
-        rusqlite::Error::FromSqlConversionFailure(
-            4,
-            rusqlite::types::Type::Text,
-            format!("Unknown task status: {raw_status}").into(),
-        )
-    })?;
+    /// Keyword search over task requests and responses, best match first.
+    /// Archived tasks are included so old work stays findable.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<TaskRunRecord>> {
+        self.search_scoped(query, None, limit)
+    }
 
-    Ok(TaskRunRecord {
-        id: row.get(0)?,
-        channel: row.get(1)?,
+    /// Like [`Self::search`], restricted to one sender's tasks.
+    pub fn search_sender_tasks(
+        &self,
+        channel: &str,
+        sender_key: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<TaskRunRecord>> {
+        self.search_scoped(query, Some((channel, sender_key)), limit)
+    }
+
+    fn search_scoped(
+        &self,
+        query: &str,
+        sender: Option<(&str, &str)>,
+        limit: usize,
+    ) -> Result<Vec<TaskRunRecord>> {
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let (channel, sender_key) = sender.unwrap_or_default();
+        let scoped = sender.is_some();
+
+        // Trigrams cannot match terms shorter than three characters (common
+        // for two-character Chinese words), so those queries scan with LIKE.
+        let (sql, pattern) = if terms.iter().any(|term| term.chars().count() < 3) {
+            let likes = terms
+                .iter()
+                .map(|term| {
+                    let escaped = term
+                        .replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_");
+                    format!("%{escaped}%")
+                })
+                .collect::<Vec<_>>();
+            let clauses = (0..likes.len())
+                .map(|i| {
+                    let n = i + 5;
+                    format!(
+                        "t.original_request LIKE ?{n} ESCAPE '\\' \
+                         OR COALESCE(t.last_response, '') LIKE ?{n} ESCAPE '\\'"
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            (
+                format!(
+                    "SELECT t.id, t.channel, t.sender_key, t.reply_target, t.status,
+                            t.original_request, t.last_response, t.attempt_count,
+                            t.provider_retry_count, t.created_at, t.updated_at,
+                            t.completed_at, t.archived_at
+                       FROM task_runs t
+                      WHERE (?1 = 0 OR (t.channel = ?2 AND t.sender_key = ?3))
+                        AND ({clauses})
+                   ORDER BY t.updated_at DESC
+                      LIMIT ?4"
+                ),
+                likes,
+            )
+        } else {
+            let fts_query = terms
+                .iter()
+                .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            (
+                "SELECT t.id, t.channel, t.sender_key, t.reply_target, t.status,
+                        t.original_request, t.last_response, t.attempt_count,
+                        t.provider_retry_count, t.created_at, t.updated_at,
+                        t.completed_at, t.archived_at
+                   FROM task_runs_fts f
+                   JOIN task_runs t ON t.rowid = f.rowid
+                  WHERE (?1 = 0 OR (t.channel = ?2 AND t.sender_key = ?3))
+                    AND task_runs_fts MATCH ?5
+               ORDER BY bm25(task_runs_fts)
+                  LIMIT ?4"
+                    .to_string(),
+                vec![fts_query],
+            )
+        };
+
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let mut bound: Vec<&dyn rusqlite::ToSql> = vec![&scoped, &channel, &sender_key, &limit];
+            bound.extend(pattern.iter().map(|p| p as &dyn rusqlite::ToSql));
+            let rows = stmt.query_map(bound.as_slice(), map_task_run_row)?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    pub fn get_task_run(&self, id: &str) -> Result<Option<TaskRunRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, channel, sender_key, reply_target, status, original_request,
+                        last_response, attempt_count, provider_retry_count,
+                        created_at, updated_at, completed_at, archived_at
+                   FROM task_runs
+                  WHERE id = ?1",
+            )?;
+            let mut rows = stmt.query(params![id])?;
+            if let Some(row) = rows.next()? {
+                Ok(Some(map_task_run_row(row)?))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    pub fn list_recoverable_tasks(&self) -> Result<Vec<TaskRunRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, channel, sender_key, reply_target, status, original_request,
+                        last_response, attempt_count, provider_retry_count,
+                        created_at, updated_at, completed_at, archived_at
+                   FROM task_runs
+                  WHERE status IN ('queued', 'running', 'blocked')
+               ORDER BY created_at ASC",
+            )?;
+            let rows = stmt.query_map([], map_task_run_row)?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    /// Tasks currently in `status`, oldest first.
+    pub fn list_tasks_with_status(&self, status: TaskStatus) -> Result<Vec<TaskRunRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, channel, sender_key, reply_target, status, original_request,
+                        last_response, attempt_count, provider_retry_count,
+                        created_at, updated_at, completed_at, archived_at
+                   FROM task_runs
+                  WHERE status = ?1
+               ORDER BY updated_at ASC",
+            )?;
+            let rows = stmt.query_map(params![status.as_str()], map_task_run_row)?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    pub fn latest_sender_task_with_status(
+        &self,
+        channel: &str,
+        sender_key: &str,
+        status: TaskStatus,
+    ) -> Result<Option<TaskRunRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, channel, sender_key, reply_target, status, original_request,
+                        last_response, attempt_count, provider_retry_count,
+                        created_at, updated_at, completed_at, archived_at
+                   FROM task_runs
+                  WHERE channel = ?1 AND sender_key = ?2 AND status = ?3
+               ORDER BY updated_at DESC
+                  LIMIT 1",
+            )?;
+            let mut rows = stmt.query(params![channel, sender_key, status.as_str()])?;
+            if let Some(row) = rows.next()? {
+                Ok(Some(map_task_run_row(row)?))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    pub fn list_recent_sender_tasks(
+        &self,
+        channel: &str,
+        sender_key: &str,
+        limit: usize,
+    ) -> Result<Vec<TaskRunRecord>> {
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, channel, sender_key, reply_target, status, original_request,
+                        last_response, attempt_count, provider_retry_count,
+                        created_at, updated_at, completed_at, archived_at
+                   FROM task_runs
+                  WHERE channel = ?1 AND sender_key = ?2 AND archived_at IS NULL
+               ORDER BY updated_at DESC
+                  LIMIT ?3",
+            )?;
+            let rows = stmt.query_map(params![channel, sender_key, limit], map_task_run_row)?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    /// Running tasks with no heartbeat (and no other update) for at least
+    /// `max_silence`.
+    pub fn list_silent_running_tasks(&self, max_silence: Duration) -> Result<Vec<TaskRunRecord>> {
+        let max_silence = chrono::Duration::from_std(max_silence).unwrap_or(chrono::Duration::MAX);
+        let cutoff = Utc::now()
+            .checked_sub_signed(max_silence)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC)
+            .to_rfc3339();
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT t.id, t.channel, t.sender_key, t.reply_target, t.status,
+                        t.original_request, t.last_response, t.attempt_count,
+                        t.provider_retry_count, t.created_at, t.updated_at,
+                        t.completed_at, t.archived_at
+                   FROM task_runs t
+                  WHERE t.status = 'running'
+                    AND MAX(
+                          t.updated_at,
+                          COALESCE(
+                            (SELECT MAX(e.created_at) FROM task_events e
+                              WHERE e.task_id = t.id AND e.event_type = ?2),
+                            t.updated_at)
+                        ) <= ?1
+               ORDER BY t.updated_at",
+            )?;
+            let rows = stmt.query_map(params![cutoff, TASK_HEARTBEAT_EVENT], map_task_run_row)?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    /// Most recent lifecycle event for a task, skipping transcript messages
+    /// and heartbeats.
+    pub fn latest_lifecycle_event(&self, task_id: &str) -> Result<Option<TaskEventRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, task_id, event_type, payload, created_at
+                   FROM task_events
+                  WHERE task_id = ?1 AND event_type NOT IN (?2, ?3)
+               ORDER BY id DESC
+                  LIMIT 1",
+            )?;
+            let mut rows = stmt.query(params![
+                task_id,
+                TRANSCRIPT_MESSAGE_EVENT,
+                TASK_HEARTBEAT_EVENT
+            ])?;
+            if let Some(row) = rows.next()? {
+                Ok(Some(TaskEventRecord {
+                    id: row.get::<_, i64>(0)?,
+                    task_id: row.get(1)?,
+                    event_type: row.get(2)?,
+                    payload_json: row.get(3)?,
+                    created_at: row.get(4)?,
+                }))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    /// Most recent event of `event_type` for a task.
+    pub fn latest_event_of_type(
+        &self,
+        task_id: &str,
+        event_type: &str,
+    ) -> Result<Option<TaskEventRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, task_id, event_type, payload, created_at
+                   FROM task_events
+                  WHERE task_id = ?1 AND event_type = ?2
+               ORDER BY id DESC
+                  LIMIT 1",
+            )?;
+            let mut rows = stmt.query(params![task_id, event_type])?;
+            if let Some(row) = rows.next()? {
+                Ok(Some(TaskEventRecord {
+                    id: row.get::<_, i64>(0)?,
+                    task_id: row.get(1)?,
+                    event_type: row.get(2)?,
+                    payload_json: row.get(3)?,
+                    created_at: row.get(4)?,
+                }))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    pub fn append_event(
+        &self,
+        task_id: &str,
+        event_type: &str,
+        payload: Option<&Value>,
+    ) -> Result<()> {
+        let now = now_rfc3339();
+        let payload_json = payload.map(Value::to_string);
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO task_events (task_id, event_type, payload, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![task_id, event_type, payload_json, now],
+            )
+            .with_context(|| format!("Failed to append task event for '{task_id}'"))?;
+            Ok(())
+        })
+    }
+
+    /// Append several events in one transaction; either all land or none do.
+    pub fn append_events(&self, task_id: &str, events: &[NewTaskEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            insert_events(&tx, task_id, events)?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Persist a finished round atomically: bump `attempt_count`, store the
+    /// round's response, and append its events.
+    pub fn record_round(
+        &self,
+        task_id: &str,
+        last_response: &str,
+        events: &[NewTaskEvent],
+    ) -> Result<()> {
+        let now = now_rfc3339();
+        self.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            insert_events(&tx, task_id, events)?;
+            let changed = tx.execute(
+                "UPDATE task_runs
+                    SET attempt_count = attempt_count + 1,
+                        last_response = ?2,
+                        updated_at = ?3
+                  WHERE id = ?1",
+                params![task_id, last_response, now],
+            )?;
+            if changed == 0 {
+                anyhow::bail!("Task run '{task_id}' not found");
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    pub fn list_events(&self, task_id: &str) -> Result<Vec<TaskEventRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, task_id, event_type, payload, created_at
+                   FROM task_events
+                  WHERE task_id = ?1
+               ORDER BY id ASC",
+            )?;
+            let rows = stmt.query_map(params![task_id], |row| {
+                Ok(TaskEventRecord {
+                    id: row.get::<_, i64>(0)?,
+                    task_id: row.get(1)?,
+                    event_type: row.get(2)?,
+                    payload_json: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    /// Tokens and estimated cost of every provider round of a task.
+    pub fn usage_totals(&self, task_id: &str) -> Result<TaskUsageTotals> {
+        self.with_connection(|conn| {
+            let (input_tokens, output_tokens, cost_usd): (i64, i64, Option<f64>) = conn.query_row(
+                "SELECT COALESCE(SUM(json_extract(payload, '$.input_tokens')), 0),
+                            COALESCE(SUM(json_extract(payload, '$.output_tokens')), 0),
+                            SUM(json_extract(payload, '$.cost_usd'))
+                       FROM task_events
+                      WHERE task_id = ?1
+                        AND event_type = 'llm_usage'
+                        AND json_valid(payload)",
+                params![task_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+            Ok(TaskUsageTotals {
+                input_tokens: u64::try_from(input_tokens).unwrap_or(0),
+                output_tokens: u64::try_from(output_tokens).unwrap_or(0),
+                cost_usd,
+            })
+        })
+    }
+
+    /// Tool invocations recorded for a task, oldest first. Payloads that no
+    /// longer deserialize are skipped.
+    pub fn list_tool_invocations(&self, task_id: &str) -> Result<Vec<ToolInvocation>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT payload
+                   FROM task_events
+                  WHERE task_id = ?1 AND event_type = ?2
+               ORDER BY id ASC",
+            )?;
+            let rows = stmt.query_map(params![task_id, TOOL_INVOCATION_EVENT], |row| {
+                row.get::<_, Option<String>>(0)
+            })?;
+            let mut out = Vec::new();
+            for row in rows {
+                if let Some(invocation) =
+                    row?.and_then(|payload| serde_json::from_str::<ToolInvocation>(&payload).ok())
+                {
+                    out.push(invocation);
+                }
+            }
+            Ok(out)
+        })
+    }
+
+    pub fn save_checkpoint(
+        &self,
+        task_id: &str,
+        next_round: usize,
+        consecutive_progress_only: usize,
+        write_verified: bool,
+        history_delta: &[crate::providers::ChatMessage],
+    ) -> Result<()> {
+        let now = now_rfc3339();
+        let delta_json = serde_json::to_string(history_delta)
+            .context("Failed to serialize task checkpoint history")?;
+        let next_round = i64::try_from(next_round).unwrap_or(i64::MAX);
+        let consecutive = i64::try_from(consecutive_progress_only).unwrap_or(i64::MAX);
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO task_checkpoints (
+                   task_id, next_round, consecutive_progress_only, write_verified,
+                   history_delta, updated_at
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(task_id) DO UPDATE SET
+                   next_round = excluded.next_round,
+                   consecutive_progress_only = excluded.consecutive_progress_only,
+                   write_verified = excluded.write_verified,
+                   history_delta = excluded.history_delta,
+                   updated_at = excluded.updated_at",
+                params![
+                    task_id,
+                    next_round,
+                    consecutive,
+                    if write_verified { 1 } else { 0 },
+                    delta_json,
+                    now
+                ],
+            )
+            .with_context(|| format!("Failed to save checkpoint for '{task_id}'"))?;
+            Ok(())
+        })
+    }
+
+    pub fn load_checkpoint(&self, task_id: &str) -> Result<Option<TaskCheckpoint>> {
+        let row = self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT next_round, consecutive_progress_only, write_verified,
+                        history_delta, updated_at
+                   FROM task_checkpoints
+                  WHERE task_id = ?1",
+            )?;
+            let mut rows = stmt.query(params![task_id])?;
+            if let Some(row) = rows.next()? {
+                Ok(Some((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                )))
+            } else {
+                Ok(None)
+            }
+        })?;
+
+        let Some((next_round, consecutive, write_verified, delta_json, updated_at)) = row else {
+            return Ok(None);
+        };
+        let history_delta = serde_json::from_str(&delta_json)
+            .with_context(|| format!("Corrupt checkpoint history for '{task_id}'"))?;
+        Ok(Some(TaskCheckpoint {
+            task_id: task_id.to_string(),
+            next_round: usize::try_from(next_round).unwrap_or(0),
+            consecutive_progress_only: usize::try_from(consecutive).unwrap_or(0),
+            write_verified: write_verified == 1,
+            history_delta,
+            updated_at,
+        }))
+    }
+
+    pub fn clear_checkpoint(&self, task_id: &str) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "DELETE FROM task_checkpoints WHERE task_id = ?1",
+                params![task_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Record an artifact's verification state. `path` may be absolute or
+    /// workspace-relative; it is stored in canonical workspace-relative form.
+    pub fn upsert_artifact_verification(
+        &self,
+        task_id: &str,
+        path: &str,
+        checksum: Option<&str>,
+        verified: bool,
+    ) -> Result<()> {
+        let verified_at = if verified { Some(now_rfc3339()) } else { None };
+        let path = normalize_artifact_path(&self.workspace_dir, path);
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO task_artifacts (task_id, path, verified, checksum, verified_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(task_id, path) DO UPDATE SET
+                   verified = excluded.verified,
+                   checksum = excluded.checksum,
+                   verified_at = excluded.verified_at",
+                params![
+                    task_id,
+                    path,
+                    if verified { 1 } else { 0 },
+                    checksum,
+                    verified_at
+                ],
+            )
+            .with_context(|| format!("Failed to upsert task artifact for '{task_id}'"))?;
+            Ok(())
+        })
+    }
+
+    /// Look up an artifact by absolute or workspace-relative path, matching
+    /// any spelling that normalizes to the stored form.
+    pub fn find_artifact(&self, task_id: &str, path: &str) -> Result<Option<TaskArtifactRecord>> {
+        let path = normalize_artifact_path(&self.workspace_dir, path);
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, task_id, path, verified, checksum, verified_at, mime_type, preview
+                   FROM task_artifacts
+                  WHERE task_id = ?1 AND path = ?2",
+            )?;
+            let mut rows = stmt.query(params![task_id, path])?;
+            if let Some(row) = rows.next()? {
+                Ok(Some(map_artifact_row(row)?))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    /// Attach a detected MIME type and preview to an artifact, creating the
+    /// artifact row if verification has not recorded it yet.
+    pub fn set_artifact_preview(
+        &self,
+        task_id: &str,
+        path: &str,
+        preview: &ArtifactPreview,
+    ) -> Result<()> {
+        let path = normalize_artifact_path(&self.workspace_dir, path);
+        let preview_json = serde_json::to_string(preview)?;
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO task_artifacts (task_id, path, mime_type, preview)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(task_id, path) DO UPDATE SET
+                   mime_type = excluded.mime_type,
+                   preview = excluded.preview",
+                params![task_id, path, preview.mime_type, preview_json],
+            )
+            .with_context(|| format!("Failed to store artifact preview for '{task_id}'"))?;
+            Ok(())
+        })
+    }
+
+    pub fn list_artifacts(&self, task_id: &str) -> Result<Vec<TaskArtifactRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, task_id, path, verified, checksum, verified_at, mime_type, preview
+                   FROM task_artifacts
+                  WHERE task_id = ?1
+               ORDER BY id ASC",
+            )?;
+            let rows = stmt.query_map(params![task_id], map_artifact_row)?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    /// Attach an operator note to an existing task run.
+    pub fn add_task_note(&self, task_id: &str, author: &str, body: &str) -> Result<TaskNoteRecord> {
+        let now = now_rfc3339();
+        self.with_connection(|conn| {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM task_runs WHERE id = ?1)",
+                params![task_id],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                anyhow::bail!("Task run '{task_id}' not found");
+            }
+            conn.execute(
+                "INSERT INTO task_notes (task_id, author, body, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![task_id, author, body, now],
+            )
+            .with_context(|| format!("Failed to add note to task '{task_id}'"))?;
+            Ok(TaskNoteRecord {
+                id: conn.last_insert_rowid(),
+                task_id: task_id.to_string(),
+                author: author.to_string(),
+                body: body.to_string(),
+                created_at: now,
+            })
+        })
+    }
+
+    /// Notes on a task run, oldest first.
+    pub fn list_task_notes(&self, task_id: &str) -> Result<Vec<TaskNoteRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, task_id, author, body, created_at
+                   FROM task_notes
+                  WHERE task_id = ?1
+               ORDER BY id ASC",
+            )?;
+            let rows = stmt.query_map(params![task_id], |row| {
+                Ok(TaskNoteRecord {
+                    id: row.get(0)?,
+                    task_id: row.get(1)?,
+                    author: row.get(2)?,
+                    body: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Record `descriptions` as the steps of `task_id`, all pending, replacing
+    /// any steps recorded before.
+    pub fn set_task_steps(&self, task_id: &str, descriptions: &[String]) -> Result<()> {
+        let now = now_rfc3339();
+        self.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
+                "DELETE FROM task_steps WHERE task_id = ?1",
+                params![task_id],
+            )?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT INTO task_steps (task_id, step_index, description, status, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )?;
+                for (index, description) in descriptions.iter().enumerate() {
+                    stmt.execute(params![
+                        task_id,
+                        index as i64,
+                        description,
+                        TaskStepStatus::Pending.as_str(),
+                        now
+                    ])
+                    .with_context(|| format!("Failed to record steps for task '{task_id}'"))?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Mark a task completed and queue its reply for delivery, atomically, so
+    /// a crash between the two cannot lose the answer. The reply becomes due
+    /// for the delivery worker after `grace`, unless acknowledged first with
+    /// [`mark_outbox_delivered`](Self::mark_outbox_delivered).
+    pub fn complete_with_outbox(&self, task_id: &str, body: &str, grace: Duration) -> Result<i64> {
+        let now = now_rfc3339();
+        let due = timestamp_after(grace);
+        self.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let changed = tx.execute(
+                "UPDATE task_runs
+                    SET status = ?2, updated_at = ?3, completed_at = ?3
+                  WHERE id = ?1",
+                params![task_id, TaskStatus::Completed.as_str(), now],
+            )?;
+            if changed == 0 {
+                anyhow::bail!("Task run '{task_id}' not found");
+            }
+            tx.execute(
+                "INSERT INTO task_outbox
+                   (task_id, channel, reply_target, body, attempts, created_at, next_attempt_at)
+                 SELECT id, channel, reply_target, ?2, 0, ?3, ?4
+                   FROM task_runs
+                  WHERE id = ?1",
+                params![task_id, body, now, due],
+            )
+            .with_context(|| format!("Failed to queue reply for task '{task_id}'"))?;
+            let id = tx.last_insert_rowid();
+            tx.commit()?;
+            Ok(id)
+        })
+    }
+
+    /// Replace a queued reply's text with what the channel handler actually
+    /// sends, so a redelivery repeats that message rather than the engine's.
+    pub fn set_outbox_body(&self, id: i64, body: &str) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE task_outbox SET body = ?2 WHERE id = ?1 AND delivered_at IS NULL",
+                params![id, body],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Undelivered, not dead-lettered replies whose next attempt is due,
+    /// oldest first.
+    pub fn list_due_outbox(&self, limit: usize) -> Result<Vec<TaskOutboxRecord>> {
+        let now = claim_timestamp(Utc::now());
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, task_id, channel, reply_target, body, attempts, last_error,
+                        created_at, next_attempt_at, delivered_at, dead_lettered_at
+                   FROM task_outbox
+                  WHERE delivered_at IS NULL AND dead_lettered_at IS NULL
+                    AND next_attempt_at <= ?1
+               ORDER BY id
+                  LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![now, limit], |row| {
+                Ok(TaskOutboxRecord {
+                    id: row.get(0)?,
+                    task_id: row.get(1)?,
+                    channel: row.get(2)?,
+                    reply_target: row.get(3)?,
+                    body: row.get(4)?,
+                    attempts: row.get(5)?,
+                    last_error: row.get(6)?,
+                    created_at: row.get(7)?,
+                    next_attempt_at: row.get(8)?,
+                    delivered_at: row.get(9)?,
+                    dead_lettered_at: row.get(10)?,
+                })
+            })?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push(row?);
+            }
+            Ok(out)
+        })
+    }
+
+    /// Acknowledge a queued reply as sent; it is never retried afterwards.
+    pub fn mark_outbox_delivered(&self, id: i64) -> Result<()> {
+        let now = now_rfc3339();
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE task_outbox SET delivered_at = ?2 WHERE id = ?1 AND delivered_at IS NULL",
+                params![id, now],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Record a failed delivery attempt and schedule the next one, or, once
+    /// `max_attempts` have failed, dead-letter the reply so it is never
+    /// retried. Returns whether the reply was dead-lettered.
+    pub fn record_outbox_failure(
+        &self,
+        id: i64,
+        error: &str,
+        retry_after: Duration,
+        max_attempts: u32,
+    ) -> Result<bool> {
+        let now = now_rfc3339();
+        let next = timestamp_after(retry_after);
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE task_outbox
+                    SET attempts = attempts + 1, last_error = ?2, next_attempt_at = ?3,
+                        dead_lettered_at = CASE WHEN attempts + 1 >= ?4 THEN ?5 END
+                  WHERE id = ?1 AND delivered_at IS NULL",
+                params![id, error, next, max_attempts, now],
+            )?;
+            let dead: Option<String> = conn
+                .query_row(
+                    "SELECT dead_lettered_at FROM task_outbox WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .flatten();
+            Ok(dead.is_some())
+        })
+    }
+
+    /// Steps of a task run in order; empty when the run was not split up.
+    pub fn list_task_steps(&self, task_id: &str) -> Result<Vec<TaskStepRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT task_id, step_index, description, status, response, updated_at, completed_at
+                   FROM task_steps
+                  WHERE task_id = ?1
+               ORDER BY step_index ASC",
+            )?;
+            let rows = stmt.query_map(params![task_id], |row| {
+                let raw_status: String = row.get(3)?;
+                let status = TaskStepStatus::parse(&raw_status).ok_or_else(|| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        3,
+                        rusqlite::types::Type::Text,
+                        format!("Unknown task step status: {raw_status}").into(),
+                    )
+                })?;
+                Ok(TaskStepRecord {
+                    task_id: row.get(0)?,
+                    step_index: row.get::<_, i64>(1)?.try_into().unwrap_or_default(),
+                    description: row.get(2)?,
+                    status,
+                    response: row.get(4)?,
+                    updated_at: row.get(5)?,
+                    completed_at: row.get(6)?,
+                })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Move one step to `status`, keeping its previous response unless a new
+    /// one is given.
+    pub fn update_task_step(
+        &self,
+        task_id: &str,
+        step_index: usize,
+        status: TaskStepStatus,
+        response: Option<&str>,
+    ) -> Result<()> {
+        let now = now_rfc3339();
+        let completed_at = (status == TaskStepStatus::Completed).then(|| now.clone());
+        self.with_connection(|conn| {
+            let changed = conn.execute(
+                "UPDATE task_steps
+                    SET status = ?3,
+                        response = COALESCE(?4, response),
+                        updated_at = ?5,
+                        completed_at = ?6
+                  WHERE task_id = ?1 AND step_index = ?2",
+                params![
+                    task_id,
+                    step_index as i64,
+                    status.as_str(),
+                    response,
+                    now,
+                    completed_at
+                ],
+            )?;
+            if changed == 0 {
+                anyhow::bail!("Task '{task_id}' has no step {step_index}");
+            }
+            Ok(())
+        })
+    }
+}
+
+fn insert_events(conn: &Connection, task_id: &str, events: &[NewTaskEvent]) -> Result<()> {
+    let now = now_rfc3339();
+    let mut stmt = conn.prepare(
+        "INSERT INTO task_events (task_id, event_type, payload, created_at)
+         VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for event in events {
+        let payload_json = event.payload.as_ref().map(Value::to_string);
+        stmt.execute(params![task_id, event.event_type, payload_json, now])
+            .with_context(|| format!("Failed to append task event for '{task_id}'"))?;
+    }
+    Ok(())
+}
+
+fn now_rfc3339() -> String {
+    Utc::now().to_rfc3339()
+}
+
+/// Fixed-width UTC timestamps so claim expiry compares correctly as text.
+fn claim_timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// [`claim_timestamp`] of now plus `delay`.
+fn timestamp_after(delay: Duration) -> String {
+    let delay = chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX);
+    claim_timestamp(
+        Utc::now()
+            .checked_add_signed(delay)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC),
+    )
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, name: &str, sql_type: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let col_name: String = row.get(1)?;
+        if col_name == name {
+            return Ok(());
+        }
+    }
+    drop(rows);
+    drop(stmt);
+
+    match conn.execute(
+        &format!("ALTER TABLE {table} ADD COLUMN {name} {sql_type}"),
+        [],
+    ) {
+        Ok(_) => Ok(()),
+        Err(rusqlite::Error::SqliteFailure(_, Some(ref msg)))
+            if msg.contains("duplicate column name") =>
+        {
+            Ok(())
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to add {table}.{name}")),
+    }
+}
+
+fn map_artifact_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TaskArtifactRecord> {
+    let verified_raw: i64 = row.get(3)?;
+    Ok(TaskArtifactRecord {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        path: row.get(2)?,
+        verified: verified_raw == 1,
+        checksum: row.get(4)?,
+        verified_at: row.get(5)?,
+        mime_type: row.get(6)?,
+        preview: row
+            .get::<_, Option<String>>(7)?
+            .and_then(|raw| serde_json::from_str(&raw).ok()),
+    })
+}
+
+/// Canonical storage form for artifact paths: symlinks and `..` resolved, then
+/// made workspace-relative with `/` separators when inside the workspace.
+/// Paths outside the workspace stay absolute; marker keys such as
+/// `__history_verified__` are stored verbatim.
+fn normalize_artifact_path(workspace_dir: &Path, raw: &str) -> String {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() || (trimmed.starts_with("__") && trimmed.ends_with("__")) {
+        return trimmed.to_string();
+    }
+
+    let raw_path = Path::new(trimmed);
+    let workspace = resolve_path(workspace_dir);
+    let absolute = if raw_path.is_absolute() {
+        resolve_path(raw_path)
+    } else {
+        resolve_path(&workspace.join(raw_path))
+    };
+
+    match absolute.strip_prefix(&workspace) {
+        Ok(relative) => relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => absolute.to_string_lossy().into_owned(),
+    }
+}
+
+/// Resolve symlinks and `..` even when the tail of the path does not exist yet:
+/// canonicalize the deepest existing ancestor and re-append the rest lexically.
+fn resolve_path(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut lexical = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                lexical.pop();
+            }
+            Component::CurDir => {}
+            other => lexical.push(other.as_os_str()),
+        }
+    }
+
+    let mut existing = lexical.as_path();
+    let mut tail = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let mut resolved = canonical;
+            for part in tail.iter().rev() {
+                resolved.push(part);
+            }
+            return resolved;
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                tail.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return lexical,
+        }
+    }
+}
+
+fn map_task_run_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TaskRunRecord> {
+    let raw_status: String = row.get(4)?;
+    let status = TaskStatus::parse(&raw_status).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            4,
+            rusqlite::types::Type::Text,
+            format!("Unknown task status: {raw_status}").into(),
+        )
+    })?;
+
+    Ok(TaskRunRecord {
+        id: row.get(0)?,
+        channel: row.get(1)?,
         sender_key: row.get(2)?,
         reply_target: row.get(3)?,
         status,
@@ -339,14 +1868,18 @@ fn map_task_run_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TaskRunRecord>
         created_at: row.get(9)?,
         updated_at: row.get(10)?,
         completed_at: row.get(11)?,
+        archived_at: row.get(12)?,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::TaskStore;
-    use crate::agent::task_types::TaskStatus;
-    use serde_json::json;
+    use crate::agent::task_types::{
+        ArtifactPreview, NewTaskEvent, TaskStatus, TaskStepStatus, TaskUsageTotals,
+    };
+    use serde_json::{json, Value};
+    use std::time::Duration;
     use tempfile::TempDir;
 
     #[test]
@@ -420,4 +1953,732 @@ mod tests {
         let ids: Vec<String> = recoverable.into_iter().map(|r| r.id).collect();
         assert_eq!(ids, vec!["queued".to_string()]);
     }
+
+    #[test]
+    fn task_store_lists_recent_sender_tasks_and_latest_lifecycle_event() {
+        let tmp = TempDir::new().expect("tempdir");
+        let workspace = tmp.path().join("workspace");
+        std::fs::create_dir_all(&workspace).expect("workspace dir");
+        let store = TaskStore::new(&workspace).expect("task store init");
+
+        for id in ["t1", "t2", "t3"] {
+            store
+                .insert_task_run(id, "imessage", "sender-1", "sender-1", "req")
+                .expect("insert task");
+        }
+        store
+            .insert_task_run("other", "imessage", "sender-2", "sender-2", "req")
+            .expect("insert other");
+
+        let recent = store
+            .list_recent_sender_tasks("imessage", "sender-1", 2)
+            .expect("recent tasks");
+        assert_eq!(recent.len(), 2);
+        assert!(recent.iter().all(|task| task.sender_key == "sender-1"));
+
+        store
+            .append_event("t1", "continue", Some(&json!({"round": 1})))
+            .expect("append continue");
+        store
+            .append_event(
+                "t1",
+                "transcript_message",
+                Some(&json!({"kind": "tool_call"})),
+            )
+            .expect("append transcript");
+        let latest = store
+            .latest_lifecycle_event("t1")
+            .expect("latest event")
+            .expect("event exists");
+        assert_eq!(latest.event_type, "continue");
+        assert!(store
+            .latest_lifecycle_event("t2")
+            .expect("lookup")
+            .is_none());
+    }
+
+    #[test]
+    fn task_store_normalizes_artifact_paths_and_finds_variants() {
+        let tmp = TempDir::new().expect("tempdir");
+        let workspace = tmp.path().join("workspace");
+        std::fs::create_dir_all(workspace.join("reports")).expect("reports dir");
+        let store = TaskStore::new(&workspace).expect("task store init");
+        store
+            .insert_task_run("task-1", "imessage", "sender-1", "sender-1", "req")
+            .expect("insert task");
+
+        let absolute = workspace.join("reports").join("../reports/./weekly.md");
+        store
+            .upsert_artifact_verification(
+                "task-1",
+                absolute.to_str().expect("utf8 path"),
+                None,
+                true,
+            )
+            .expect("upsert absolute");
+        store
+            .upsert_artifact_verification("task-1", "./reports/weekly.md", Some("abc"), true)
+            .expect("upsert relative variant");
+
+        let artifacts = store.list_artifacts("task-1").expect("list artifacts");
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].path, "reports/weekly.md");
+        assert_eq!(artifacts[0].checksum.as_deref(), Some("abc"));
+
+        let found = store
+            .find_artifact("task-1", "reports/sub/../weekly.md")
+            .expect("lookup")
+            .expect("artifact found");
+        assert_eq!(found.path, "reports/weekly.md");
+        assert!(store
+            .find_artifact("task-1", "reports/other.md")
+            .expect("lookup")
+            .is_none());
+    }
+
+    #[test]
+    fn task_store_round_trips_artifact_previews() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        store
+            .insert_task_run("task-1", "imessage", "sender-1", "sender-1", "req")
+            .expect("insert task");
+        let preview = ArtifactPreview {
+            mime_type: "text/markdown".into(),
+            size_bytes: 12,
+            summary: "2 lines".into(),
+            excerpt: Some("# Weekly\nok".into()),
+            thumbnail: None,
+        };
+
+        store
+            .set_artifact_preview("task-1", "weekly.md", &preview)
+            .expect("set preview");
+        store
+            .upsert_artifact_verification("task-1", "weekly.md", None, true)
+            .expect("verify");
+
+        let artifact = store
+            .find_artifact("task-1", "weekly.md")
+            .expect("lookup")
+            .expect("artifact found");
+        assert!(artifact.verified);
+        assert_eq!(artifact.mime_type.as_deref(), Some("text/markdown"));
+        assert_eq!(artifact.preview, Some(preview));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn task_store_resolves_symlinked_artifact_paths() {
+        let tmp = TempDir::new().expect("tempdir");
+        let workspace = tmp.path().join("workspace");
+        std::fs::create_dir_all(workspace.join("real")).expect("real dir");
+        std::os::unix::fs::symlink(workspace.join("real"), workspace.join("link"))
+            .expect("symlink");
+        let store = TaskStore::new(&workspace).expect("task store init");
+        store
+            .insert_task_run("task-1", "imessage", "sender-1", "sender-1", "req")
+            .expect("insert task");
+
+        store
+            .upsert_artifact_verification("task-1", "link/out.txt", None, true)
+            .expect("upsert via symlink");
+        let found = store
+            .find_artifact("task-1", "real/out.txt")
+            .expect("lookup")
+            .expect("artifact found");
+        assert_eq!(found.path, "real/out.txt");
+    }
+
+    #[test]
+    fn task_store_reports_capacity_gauges() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        for id in ["queued-1", "queued-2", "done"] {
+            store
+                .insert_task_run(id, "imessage", "sender-1", "sender-1", "req")
+                .expect("insert task");
+        }
+        store
+            .update_status("done", TaskStatus::Completed)
+            .expect("complete");
+
+        let gauges = store.capacity_gauges(None).expect("gauges");
+        assert_eq!(gauges.queued_tasks, 2);
+        assert_eq!(gauges.archive_backlog, 0);
+        assert!(gauges.db_size_bytes > 0);
+        assert_eq!(
+            store
+                .capacity_gauges(Some(Duration::ZERO))
+                .expect("gauges")
+                .archive_backlog,
+            1
+        );
+    }
+
+    #[test]
+    fn task_store_claims_are_exclusive_until_released_or_expired() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        store
+            .insert_task_run("task-1", "imessage", "sender-1", "sender-1", "req")
+            .expect("insert task");
+        let lease = Duration::from_secs(60);
+
+        assert!(store.try_claim("task-1", "worker-a", lease).expect("claim"));
+        assert!(!store
+            .try_claim("task-1", "worker-b", lease)
+            .expect("contended"));
+        assert!(store.try_claim("task-1", "worker-a", lease).expect("renew"));
+        assert_eq!(
+            store
+                .get_claim("task-1")
+                .expect("claim")
+                .map(|c| c.worker_id),
+            Some("worker-a".to_string())
+        );
+
+        store
+            .release_claim("task-1", "worker-b")
+            .expect("foreign release");
+        assert!(!store
+            .try_claim("task-1", "worker-b", lease)
+            .expect("still held"));
+        store.release_claim("task-1", "worker-a").expect("release");
+        assert!(store.get_claim("task-1").expect("claim").is_none());
+
+        // A zero lease expires immediately, as if the owner had died.
+        assert!(store
+            .try_claim("task-1", "worker-a", Duration::ZERO)
+            .expect("short claim"));
+        assert!(store
+            .try_claim("task-1", "worker-b", lease)
+            .expect("reclaim"));
+        assert!(store.try_claim("missing", "worker-a", lease).is_err());
+    }
+
+    #[test]
+    fn task_store_archives_terminal_tasks_and_hides_them_from_sender_views() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        for id in ["done", "running"] {
+            store
+                .insert_task_run(id, "imessage", "sender-1", "sender-1", "req")
+                .expect("insert task");
+        }
+        store
+            .update_status("done", TaskStatus::Completed)
+            .expect("complete");
+        store
+            .update_status("running", TaskStatus::Running)
+            .expect("run");
+        store
+            .append_event("done", "completed", None)
+            .expect("event");
+
+        assert!(store.archive("running").is_err());
+        assert_eq!(
+            store
+                .archive_terminal_tasks_older_than(Duration::from_secs(3600))
+                .expect("sweep"),
+            0
+        );
+        assert_eq!(
+            store
+                .archive_terminal_tasks_older_than(Duration::ZERO)
+                .expect("sweep"),
+            1
+        );
+        store.archive("done").expect("archive is idempotent");
+
+        let recent = store
+            .list_recent_sender_tasks("imessage", "sender-1", 10)
+            .expect("recent");
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].id, "running");
+
+        let archived = store.list_archived().expect("archived");
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].id, "done");
+        assert!(archived[0].archived_at.is_some());
+        assert_eq!(store.list_events("done").expect("events").len(), 1);
+    }
+
+    #[test]
+    fn task_store_summarizes_status_channels_retries_and_failures() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        for (id, channel) in [("a", "imessage"), ("b", "imessage"), ("c", "telegram")] {
+            store
+                .insert_task_run(id, channel, "sender", "sender", "req")
+                .expect("insert");
+            store.increment_attempt_count(id).expect("round");
+        }
+        store.increment_attempt_count("a").expect("round");
+        store.increment_provider_retry_count("b").expect("retry");
+        store.increment_provider_retry_count("b").expect("retry");
+        store
+            .update_status("a", TaskStatus::Running)
+            .expect("running");
+        store
+            .update_status("a", TaskStatus::Failed)
+            .expect("failed");
+        store
+            .append_event("a", "failed", Some(&json!({"reason": "stalled_loop"})))
+            .expect("event");
+        store
+            .update_status("c", TaskStatus::Running)
+            .expect("running");
+        store
+            .append_event("c", "timed_out", Some(&json!({"reason": "timeout"})))
+            .expect("event");
+        for (input, output, cost) in [(100, 20, json!(0.25)), (50, 5, Value::Null)] {
+            store
+                .append_event(
+                    "a",
+                    "llm_usage",
+                    Some(
+                        &json!({"input_tokens": input, "output_tokens": output, "cost_usd": cost}),
+                    ),
+                )
+                .expect("usage");
+        }
+
+        let summary = store
+            .status_summary(Duration::from_secs(3600))
+            .expect("summary");
+        assert_eq!(summary.window_secs, 3600);
+        assert_eq!(summary.total_tasks, 3);
+        assert_eq!(summary.by_status.get("failed"), Some(&1));
+        assert_eq!(summary.by_status.get("queued"), Some(&1));
+        assert_eq!(summary.by_channel.get("imessage"), Some(&2));
+        assert!((summary.average_rounds - 4.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.provider_retries, 2);
+        assert!((summary.retry_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.failure_reasons.get("stalled_loop"), Some(&1));
+        assert_eq!(summary.failure_reasons.get("timeout"), Some(&1));
+        assert!((summary.estimated_cost_usd - 0.25).abs() < 1e-9);
+        assert_eq!(
+            store.usage_totals("a").expect("totals"),
+            TaskUsageTotals {
+                input_tokens: 150,
+                output_tokens: 25,
+                cost_usd: Some(0.25),
+            }
+        );
+        assert_eq!(
+            store.usage_totals("b").expect("totals"),
+            TaskUsageTotals::default()
+        );
+
+        let empty = store.status_summary(Duration::ZERO).expect("empty window");
+        assert_eq!(empty.total_tasks, 0);
+        assert!(empty.failure_reasons.is_empty());
+    }
+
+    #[test]
+    fn task_store_lists_running_tasks_without_recent_heartbeat() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        for id in ["running", "queued"] {
+            store
+                .insert_task_run(id, "imessage", "sender", "sender", "req")
+                .expect("insert");
+        }
+        store
+            .update_status("running", TaskStatus::Running)
+            .expect("running");
+        store
+            .append_event("running", super::TASK_HEARTBEAT_EVENT, None)
+            .expect("heartbeat");
+
+        assert!(store
+            .list_silent_running_tasks(Duration::from_secs(600))
+            .expect("fresh heartbeat")
+            .is_empty());
+        let silent = store
+            .list_silent_running_tasks(Duration::ZERO)
+            .expect("silent");
+        assert_eq!(silent.len(), 1);
+        assert_eq!(silent[0].id, "running");
+        assert_eq!(
+            store
+                .latest_lifecycle_event("running")
+                .expect("latest")
+                .map(|event| event.event_type),
+            None
+        );
+    }
+
+    #[test]
+    fn task_store_searches_requests_and_responses() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        store
+            .insert_task_run(
+                "report",
+                "imessage",
+                "sender-1",
+                "sender-1",
+                "Draft the quarterly report for finance",
+            )
+            .expect("insert");
+        store
+            .insert_task_run(
+                "cn",
+                "imessage",
+                "sender-1",
+                "sender-1",
+                "写一份季度销售报告",
+            )
+            .expect("insert");
+        store
+            .insert_task_run("other", "telegram", "sender-2", "sender-2", "plan a trip")
+            .expect("insert");
+        store
+            .set_last_response("other", "The quarterly offsite itinerary is ready")
+            .expect("response");
+
+        let hits = store.search("quarterly report", 10).expect("search");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].id, "report");
+
+        let scoped = store
+            .search_sender_tasks("imessage", "sender-1", "quarterly", 10)
+            .expect("scoped search");
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].id, "report");
+
+        assert_eq!(store.search("季度销售", 10).expect("cjk")[0].id, "cn");
+        assert_eq!(store.search("报告", 10).expect("short cjk")[0].id, "cn");
+        assert!(store.search("   ", 10).expect("empty").is_empty());
+        assert!(store.search("\"100%_done", 10).expect("quoted").is_empty());
+    }
+
+    #[test]
+    fn task_store_records_rounds_and_event_batches_atomically() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        store
+            .insert_task_run("task-1", "imessage", "sender", "sender", "req")
+            .expect("insert");
+
+        store
+            .append_events(
+                "task-1",
+                &[
+                    NewTaskEvent::new("started", None),
+                    NewTaskEvent::new("contract_compiled", Some(json!({"task_type": "chat"}))),
+                ],
+            )
+            .expect("batch");
+        store
+            .record_round(
+                "task-1",
+                "done",
+                &[NewTaskEvent::new(
+                    "transcript_message",
+                    Some(json!({"round": 1})),
+                )],
+            )
+            .expect("round");
+
+        let row = store.get_task_run("task-1").expect("get").expect("row");
+        assert_eq!(row.attempt_count, 1);
+        assert_eq!(row.last_response.as_deref(), Some("done"));
+        let types: Vec<String> = store
+            .list_events("task-1")
+            .expect("events")
+            .into_iter()
+            .map(|event| event.event_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec!["started", "contract_compiled", "transcript_message"]
+        );
+
+        // A failed round write rolls back its events too.
+        assert!(store
+            .record_round("missing", "x", &[NewTaskEvent::new("orphan", None)])
+            .is_err());
+        assert!(store.list_events("missing").expect("events").is_empty());
+    }
+
+    #[test]
+    fn task_store_tracks_steps_individually() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        store
+            .insert_task_run("task-1", "imessage", "sender", "sender", "req")
+            .expect("insert");
+        assert!(store.list_task_steps("task-1").expect("steps").is_empty());
+
+        store
+            .set_task_steps("task-1", &["Collect logs".into(), "Write report".into()])
+            .expect("set steps");
+        store
+            .update_task_step("task-1", 0, TaskStepStatus::Completed, Some("12 files"))
+            .expect("complete step");
+        store
+            .update_task_step("task-1", 1, TaskStepStatus::Running, None)
+            .expect("start step");
+        assert!(store
+            .update_task_step("task-1", 2, TaskStepStatus::Running, None)
+            .is_err());
+
+        let steps = store.list_task_steps("task-1").expect("steps");
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].status, TaskStepStatus::Completed);
+        assert_eq!(steps[0].response.as_deref(), Some("12 files"));
+        assert!(steps[0].completed_at.is_some());
+        assert_eq!(steps[1].description, "Write report");
+        assert_eq!(steps[1].status, TaskStepStatus::Running);
+        assert!(steps[1].completed_at.is_none());
+    }
+
+    #[test]
+    fn task_store_queues_completed_replies_until_delivered() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        store
+            .insert_task_run(
+                "task-1",
+                "imessage",
+                "alice",
+                "alice-chat",
+                "summarize logs",
+            )
+            .expect("insert");
+        assert!(store
+            .complete_with_outbox("missing", "reply", Duration::ZERO)
+            .is_err());
+
+        let id = store
+            .complete_with_outbox("task-1", "12 errors found", Duration::ZERO)
+            .expect("complete");
+        let run = store.get_task_run("task-1").unwrap().unwrap();
+        assert_eq!(run.status, TaskStatus::Completed);
+        assert!(run.completed_at.is_some());
+
+        let due = store.list_due_outbox(10).expect("due");
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, id);
+        assert_eq!(due[0].reply_target, "alice-chat");
+        assert_eq!(due[0].body, "12 errors found");
+
+        assert!(!store
+            .record_outbox_failure(id, "channel offline", Duration::from_secs(3600), 5)
+            .expect("failure"));
+        assert!(store.list_due_outbox(10).unwrap().is_empty());
+        assert!(!store
+            .record_outbox_failure(id, "channel offline", Duration::ZERO, 5)
+            .expect("failure"));
+        let due = store.list_due_outbox(10).unwrap();
+        assert_eq!(due[0].attempts, 2);
+        assert_eq!(due[0].last_error.as_deref(), Some("channel offline"));
+
+        store
+            .set_outbox_body(id, "12 errors found\n\n📎 report.md")
+            .expect("body");
+        assert_eq!(
+            store.list_due_outbox(10).unwrap()[0].body,
+            "12 errors found\n\n📎 report.md"
+        );
+
+        store.mark_outbox_delivered(id).expect("ack");
+        assert!(store.list_due_outbox(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn task_store_dead_letters_replies_after_max_attempts() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        store
+            .insert_task_run("task-1", "imessage", "alice", "alice-chat", "summarize")
+            .expect("insert");
+        let id = store
+            .complete_with_outbox("task-1", "done", Duration::ZERO)
+            .expect("complete");
+
+        assert!(!store
+            .record_outbox_failure(id, "channel offline", Duration::ZERO, 2)
+            .unwrap());
+        assert_eq!(store.list_due_outbox(10).unwrap().len(), 1);
+        assert!(store
+            .record_outbox_failure(id, "channel offline", Duration::ZERO, 2)
+            .unwrap());
+        assert!(store.list_due_outbox(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn task_store_persists_sender_settings_per_sender() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        store
+            .set_sender_setting("imessage", "alice", "language", Some("zh"))
+            .expect("set");
+        store
+            .set_sender_setting("imessage", "alice", "language", Some("en"))
+            .expect("overwrite");
+        store
+            .set_sender_setting("imessage", "alice", "verbose", Some("off"))
+            .expect("set");
+
+        let alice = store.sender_settings("imessage", "alice").expect("load");
+        assert_eq!(alice.get("language").map(String::as_str), Some("en"));
+        assert_eq!(alice.len(), 2);
+        assert!(store
+            .sender_settings("telegram", "alice")
+            .expect("other channel")
+            .is_empty());
+
+        store
+            .set_sender_setting("imessage", "alice", "verbose", None)
+            .expect("reset");
+        assert!(!store
+            .sender_settings("imessage", "alice")
+            .expect("load")
+            .contains_key("verbose"));
+    }
+
+    #[test]
+    fn task_store_pins_instructions_per_conversation() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        let first = store
+            .pin_instruction("imessage_alice", "Answer in English")
+            .expect("pin");
+        store
+            .pin_instruction("imessage_alice", "Never delete files")
+            .expect("pin");
+        store
+            .pin_instruction("imessage_bob", "Use metric units")
+            .expect("pin");
+
+        let pins = store
+            .list_pinned_instructions("imessage_alice")
+            .expect("list");
+        assert_eq!(pins.len(), 2);
+        assert_eq!(pins[0].content, "Answer in English");
+
+        assert_eq!(
+            store
+                .unpin_instructions("imessage_alice", Some(first))
+                .expect("unpin"),
+            1
+        );
+        assert_eq!(
+            store
+                .unpin_instructions("imessage_alice", None)
+                .expect("unpin all"),
+            1
+        );
+        assert!(store
+            .list_pinned_instructions("imessage_alice")
+            .expect("list")
+            .is_empty());
+        assert_eq!(
+            store
+                .list_pinned_instructions("imessage_bob")
+                .expect("list")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn task_store_archives_conversation_branches_newest_first() {
+        let tmp = TempDir::new().expect("tempdir");
+        let store = TaskStore::new(tmp.path()).expect("task store init");
+        let first = vec![
+            crate::providers::ChatMessage::user("delete everything"),
+            crate::providers::ChatMessage::assistant("Deleted."),
+        ];
+        let second = vec![crate::providers::ChatMessage::user("wrong file")];
+        store
+            .archive_conversation_branch("imessage_alice", &first)
+            .expect("archive");
+        store
+            .archive_conversation_branch("imessage_alice", &second)
+            .expect("archive");
+        store
+            .archive_conversation_branch("imessage_bob", &second)
+            .expect("archive");
+
+        let branches = store
+            .list_conversation_branches("imessage_alice", 10)
+            .expect("list");
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[0].messages.len(), 1);
+        assert_eq!(branches[0].messages[0].content, "wrong file");
+        assert_eq!(branches[1].messages[1].content, "Deleted.");
+    }
+
+    #[test]
+    fn task_store_roundtrips_and_clears_checkpoint() {
+        let tmp = TempDir::new().expect("tempdir");
+        let workspace = tmp.path().join("workspace");
+        std::fs::create_dir_all(&workspace).expect("workspace dir");
+        let store = TaskStore::new(&workspace).expect("task store init");
+        store
+            .insert_task_run("task-1", "imessage", "sender-1", "sender-1", "req")
+            .expect("insert task");
+
+        assert!(store.load_checkpoint("task-1").expect("load").is_none());
+
+        let delta = vec![
+            crate::providers::ChatMessage::assistant("working"),
+            crate::providers::ChatMessage::user("[Task Engine] continue"),
+        ];
+        store
+            .save_checkpoint("task-1", 1, 1, false, &delta)
+            .expect("save first checkpoint");
+        store
+            .save_checkpoint("task-1", 2, 0, true, &delta)
+            .expect("overwrite checkpoint");
+
+        let checkpoint = store
+            .load_checkpoint("task-1")
+            .expect("load")
+            .expect("checkpoint exists");
+        assert_eq!(checkpoint.next_round, 2);
+        assert_eq!(checkpoint.consecutive_progress_only, 0);
+        assert!(checkpoint.write_verified);
+        assert_eq!(checkpoint.history_delta.len(), 2);
+        assert_eq!(checkpoint.history_delta[0].content, "working");
+
+        store.clear_checkpoint("task-1").expect("clear");
+        assert!(store.load_checkpoint("task-1").expect("load").is_none());
+    }
+
+    #[test]
+    fn task_store_finds_latest_blocked_task_for_sender() {
+        let tmp = TempDir::new().expect("tempdir");
+        let workspace = tmp.path().join("workspace");
+        std::fs::create_dir_all(&workspace).expect("workspace dir");
+        let store = TaskStore::new(&workspace).expect("task store init");
+
+        store
+            .insert_task_run("blocked", "telegram", "sender-1", "chat-1", "req")
+            .expect("insert blocked");
+        store
+            .update_status("blocked", TaskStatus::Blocked)
+            .expect("block task");
+        store
+            .insert_task_run("other-sender", "telegram", "sender-2", "chat-2", "req")
+            .expect("insert other sender");
+        store
+            .update_status("other-sender", TaskStatus::Blocked)
+            .expect("block other task");
+
+        let found = store
+            .latest_sender_task_with_status("telegram", "sender-1", TaskStatus::Blocked)
+            .expect("lookup")
+            .expect("blocked task exists");
+        assert_eq!(found.id, "blocked");
+        assert!(store
+            .latest_sender_task_with_status("discord", "sender-1", TaskStatus::Blocked)
+            .expect("lookup")
+            .is_none());
+    }
 }
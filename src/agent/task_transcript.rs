@@ -0,0 +1,279 @@
+//! Per-task transcript export for debugging stalled or failed task runs.
+//!
+//! The task store already keeps lifecycle events in insertion order. The
+//! engine additionally records every history message produced by a round as a
+//! `transcript_message` event, so a single ordered event list interleaves
+//! engine decisions, provider responses, tool calls, and tool results.
+
+use crate::agent::loop_::scrub_credentials;
+use crate::agent::task_types::{TaskEventRecord, TaskRunRecord};
+use crate::providers::ChatMessage;
+use crate::util::truncate_with_ellipsis;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Event type used for round history messages.
+pub const TRANSCRIPT_MESSAGE_EVENT: &str = "transcript_message";
+
+/// Per-message cap so a single huge tool output does not bloat the store.
+const MAX_TRANSCRIPT_MESSAGE_CHARS: usize = 16_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    Markdown,
+    Json,
+}
+
+/// Build the `transcript_message` payload for one history message, with
+/// credentials (e.g. `Authorization` headers in tool arguments) scrubbed.
+pub fn transcript_message_payload(round: usize, message: &ChatMessage) -> Value {
+    serde_json::json!({
+        "round": round + 1,
+        "kind": classify_message(message),
+        "role": message.role,
+        "content": truncate_with_ellipsis(
+            &scrub_credentials(&message.content),
+            MAX_TRANSCRIPT_MESSAGE_CHARS
+        ),
+    })
+}
+
+pub(crate) fn classify_message(message: &ChatMessage) -> &'static str {
+    match message.role.as_str() {
+        "assistant" if is_tool_call_message(&message.content) => "tool_call",
+        "assistant" => "provider_response",
+        "tool" => "tool_result",
+        "user" if message.content.starts_with("[Tool results]") => "tool_result",
+        _ => "engine_note",
+    }
+}
+
+fn is_tool_call_message(content: &str) -> bool {
+    if content.contains("<tool_call") {
+        return true;
+    }
+    serde_json::from_str::<Value>(content)
+        .ok()
+        .and_then(|value| value.get("tool_calls").cloned())
+        .is_some_and(|calls| calls.as_array().is_some_and(|calls| !calls.is_empty()))
+}
+
+/// Render a task run and its events as a single chronological document.
+pub fn render_transcript(
+    task: &TaskRunRecord,
+    events: &[TaskEventRecord],
+    format: TranscriptFormat,
+) -> String {
+    match format {
+        TranscriptFormat::Markdown => render_markdown(task, events),
+        TranscriptFormat::Json => render_json(task, events),
+    }
+}
+
+fn parsed_payload(event: &TaskEventRecord) -> Option<Value> {
+    event
+        .payload_json
+        .as_deref()
+        .map(|raw| serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())))
+}
+
+fn render_json(task: &TaskRunRecord, events: &[TaskEventRecord]) -> String {
+    let entries: Vec<Value> = events
+        .iter()
+        .map(|event| {
+            let payload = parsed_payload(event);
+            let kind = if event.event_type == TRANSCRIPT_MESSAGE_EVENT {
+                payload
+                    .as_ref()
+                    .and_then(|p| p.get("kind"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("message")
+                    .to_string()
+            } else {
+                "event".to_string()
+            };
+            serde_json::json!({
+                "at": event.created_at,
+                "kind": kind,
+                "event_type": event.event_type,
+                "payload": payload,
+            })
+        })
+        .collect();
+
+    let doc = serde_json::json!({
+        "task": {
+            "id": task.id,
+            "channel": task.channel,
+            "sender_key": task.sender_key,
+            "status": task.status.as_str(),
+            "original_request": task.original_request,
+            "last_response": task.last_response,
+            "attempt_count": task.attempt_count,
+            "provider_retry_count": task.provider_retry_count,
+            "created_at": task.created_at,
+            "updated_at": task.updated_at,
+            "completed_at": task.completed_at,
+        },
+        "entries": entries,
+    });
+    serde_json::to_string_pretty(&doc).unwrap_or_else(|_| doc.to_string())
+}
+
+fn render_markdown(task: &TaskRunRecord, events: &[TaskEventRecord]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Task transcript `{}`\n", task.id);
+    let _ = writeln!(out, "- Channel: {}", task.channel);
+    let _ = writeln!(out, "- Status: {}", task.status.as_str());
+    let _ = writeln!(
+        out,
+        "- Attempts: {} (provider retries: {})",
+        task.attempt_count, task.provider_retry_count
+    );
+    let _ = writeln!(out, "- Created: {}", task.created_at);
+    if let Some(completed_at) = task.completed_at.as_deref() {
+        let _ = writeln!(out, "- Completed: {completed_at}");
+    }
+    let _ = writeln!(out, "\n## Request\n\n{}\n", task.original_request);
+    let _ = writeln!(out, "## Timeline\n");
+
+    for event in events {
+        let payload = parsed_payload(event);
+        if event.event_type == TRANSCRIPT_MESSAGE_EVENT {
+            let field = |name: &str| {
+                payload
+                    .as_ref()
+                    .and_then(|p| p.get(name))
+                    .cloned()
+                    .unwrap_or(Value::Null)
+            };
+            let round = field("round");
+            let kind = field("kind");
+            let content = field("content");
+            let _ = writeln!(
+                out,
+                "### {} — {} (round {})\n",
+                event.created_at,
+                kind.as_str().unwrap_or("message"),
+                round
+            );
+            let _ = writeln!(out, "```text\n{}\n```\n", content.as_str().unwrap_or(""));
+        } else {
+            let _ = write!(out, "- `{}` **{}**", event.created_at, event.event_type);
+            if let Some(payload) = payload {
+                let _ = write!(out, " — `{payload}`");
+            }
+            out.push('\n');
+        }
+    }
+
+    if let Some(last_response) = task.last_response.as_deref() {
+        let _ = writeln!(out, "\n## Last response\n\n{last_response}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::task_types::TaskStatus;
+
+    fn sample_task() -> TaskRunRecord {
+        TaskRunRecord {
+            id: "task-1".into(),
+            channel: "imessage".into(),
+            sender_key: "sender".into(),
+            reply_target: "sender".into(),
+            status: TaskStatus::Failed,
+            original_request: "写一份报告".into(),
+            last_response: Some("我正在处理".into()),
+            attempt_count: 2,
+            provider_retry_count: 0,
+            created_at: "2026-01-01T00:00:00Z".into(),
+            updated_at: "2026-01-01T00:01:00Z".into(),
+            completed_at: None,
+            archived_at: None,
+        }
+    }
+
+    fn event(id: i64, event_type: &str, payload: Value) -> TaskEventRecord {
+        TaskEventRecord {
+            id,
+            task_id: "task-1".into(),
+            event_type: event_type.into(),
+            payload_json: Some(payload.to_string()),
+            created_at: format!("2026-01-01T00:00:0{id}Z"),
+        }
+    }
+
+    #[test]
+    fn transcript_payload_classifies_history_messages() {
+        let call = ChatMessage::assistant("<tool_call>{\"name\":\"shell\"}</tool_call>");
+        let native_call = ChatMessage::assistant(r#"{"content":"","tool_calls":[{"id":"1"}]}"#);
+        let result =
+            ChatMessage::user("[Tool results]\n<tool_result name=\"shell\">ok</tool_result>");
+        let reply = ChatMessage::assistant("done");
+
+        assert_eq!(transcript_message_payload(0, &call)["kind"], "tool_call");
+        assert_eq!(
+            transcript_message_payload(0, &native_call)["kind"],
+            "tool_call"
+        );
+        assert_eq!(
+            transcript_message_payload(0, &result)["kind"],
+            "tool_result"
+        );
+        assert_eq!(
+            transcript_message_payload(1, &reply)["kind"],
+            "provider_response"
+        );
+        assert_eq!(transcript_message_payload(1, &reply)["round"], 2);
+
+        let http_call = ChatMessage::assistant(
+            r#"{"content":"","tool_calls":[{"id":"1","name":"http_request","arguments":"{\"headers\":{\"Authorization\":\"Bearer sk-live-123456\"}}"}]}"#,
+        );
+        let stored = transcript_message_payload(0, &http_call)["content"].to_string();
+        assert!(!stored.contains("sk-live-123456"));
+        assert!(stored.contains("Bearer [REDACTED]"));
+    }
+
+    #[test]
+    fn markdown_and_json_keep_event_order() {
+        let events = vec![
+            event(1, "started", serde_json::json!({})),
+            event(
+                2,
+                TRANSCRIPT_MESSAGE_EVENT,
+                transcript_message_payload(0, &ChatMessage::assistant("<tool_call>x</tool_call>")),
+            ),
+            event(
+                3,
+                TRANSCRIPT_MESSAGE_EVENT,
+                transcript_message_payload(0, &ChatMessage::tool("{\"content\":\"ok\"}")),
+            ),
+            event(4, "failed", serde_json::json!({"reason": "stalled_loop"})),
+        ];
+
+        let markdown = render_transcript(&sample_task(), &events, TranscriptFormat::Markdown);
+        let started = markdown.find("**started**").expect("started");
+        let call = markdown.find("tool_call (round 1)").expect("tool call");
+        let result = markdown.find("tool_result (round 1)").expect("tool result");
+        let failed = markdown.find("**failed**").expect("failed");
+        assert!(started < call && call < result && result < failed);
+
+        let json: Value = serde_json::from_str(&render_transcript(
+            &sample_task(),
+            &events,
+            TranscriptFormat::Json,
+        ))
+        .expect("valid json");
+        let kinds: Vec<&str> = json["entries"]
+            .as_array()
+            .expect("entries")
+            .iter()
+            .map(|entry| entry["kind"].as_str().unwrap_or_default())
+            .collect();
+        assert_eq!(kinds, vec!["event", "tool_call", "tool_result", "event"]);
+        assert_eq!(json["task"]["status"], "failed");
+    }
+}
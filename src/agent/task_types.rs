@@ -1,4 +1,7 @@
+use crate::providers::ChatMessage;
+use crate::tools::{ToolKind, ToolStatus};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -66,6 +69,60 @@ pub struct TaskRunRecord {
     pub created_at: String,
     pub updated_at: String,
     pub completed_at: Option<String>,
+    /// Set once the task is hidden from active views.
+    pub archived_at: Option<String>,
+}
+
+/// An event waiting to be appended; the store assigns its id and timestamp.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewTaskEvent {
+    pub event_type: String,
+    pub payload: Option<serde_json::Value>,
+}
+
+impl NewTaskEvent {
+    pub fn new(event_type: impl Into<String>, payload: Option<serde_json::Value>) -> Self {
+        Self {
+            event_type: event_type.into(),
+            payload,
+        }
+    }
+}
+
+/// A tool call dispatched by the tool loop, with its outcome. The engine
+/// stores these as `tool_invocation` events and evaluates completion from
+/// them rather than re-parsing tool-call text out of the conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolInvocation {
+    pub name: String,
+    /// Evidence kind reported by the registered tool; records written before
+    /// kinds existed deserialize as `Other` and fall back to the tool name.
+    #[serde(default)]
+    pub kind: ToolKind,
+    pub arguments: serde_json::Value,
+    pub success: bool,
+    /// Result status; records written before statuses existed have none and
+    /// fall back to `success`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ToolStatus>,
+    /// Tool output or error text, truncated for storage.
+    pub output: String,
+    /// 1-based tool-loop iteration the call ran in.
+    pub iteration: usize,
+    /// Structured metadata the tool recorded with its result, such as the
+    /// `sources` a fetch read or the `search_results` a search returned.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ToolInvocation {
+    pub fn status(&self) -> ToolStatus {
+        self.status.unwrap_or(if self.success {
+            ToolStatus::Ok
+        } else {
+            ToolStatus::Error
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -77,7 +134,167 @@ pub struct TaskEventRecord {
     pub created_at: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStepStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl TaskStepStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "pending" => Some(Self::Pending),
+            "running" => Some(Self::Running),
+            "completed" => Some(Self::Completed),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// One step of a task run that was split into steps. Each step runs as its
+/// own bounded tool loop and is marked completed on its own, so a resumed
+/// run skips the steps already done.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskStepRecord {
+    pub task_id: String,
+    /// 0-based position in the task's step list.
+    pub step_index: usize,
+    pub description: String,
+    pub status: TaskStepStatus,
+    /// The model's reply for the step once it finished or failed.
+    pub response: Option<String>,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+}
+
+/// A task reply queued for delivery on its channel. Written together with
+/// the task's `Completed` status and retried until the channel accepts it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskOutboxRecord {
+    pub id: i64,
+    pub task_id: String,
+    pub channel: String,
+    pub reply_target: String,
+    pub body: String,
+    /// Failed delivery attempts so far.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub next_attempt_at: String,
+    pub delivered_at: Option<String>,
+    /// Set once delivery was given up on after the last allowed attempt.
+    pub dead_lettered_at: Option<String>,
+}
+
+/// Loop state persisted between continuation rounds so a restarted engine can
+/// resume a task at the right round instead of starting over.
+#[derive(Debug, Clone)]
+pub struct TaskCheckpoint {
+    pub task_id: String,
+    /// Round the engine should execute next.
+    pub next_round: usize,
+    pub consecutive_progress_only: usize,
+    pub write_verified: bool,
+    /// Messages appended to the conversation since the run started.
+    pub history_delta: Vec<ChatMessage>,
+    pub updated_at: String,
+}
+
+/// Exclusive ownership of a task by one engine worker. The claim lapses at
+/// `expires_at` unless the owner renews it, so a dead worker's tasks become
+/// claimable again.
 #[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskClaim {
+    pub task_id: String,
+    pub worker_id: String,
+    pub expires_at: String,
+}
+
+/// Conversation turns removed by `/undo`, kept so the discarded branch can
+/// still be inspected.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationBranchRecord {
+    pub id: i64,
+    /// Per-sender conversation key (`<channel>_<sender>`).
+    pub conversation_key: String,
+    pub messages: Vec<ChatMessage>,
+    pub archived_at: String,
+}
+
+/// An instruction pinned with `/pin`; re-injected into every prompt for the
+/// conversation until unpinned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PinnedInstructionRecord {
+    pub id: i64,
+    pub content: String,
+    pub pinned_at: String,
+}
+
+/// Free-form operator note attached to a task run, e.g. during triage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskNoteRecord {
+    pub id: i64,
+    pub task_id: String,
+    pub author: String,
+    pub body: String,
+    pub created_at: String,
+}
+
+/// Provider usage summed over a task's `llm_usage` events, across all runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TaskUsageTotals {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated USD cost; `None` when no round's model had a price.
+    pub cost_usd: Option<f64>,
+}
+
+/// Aggregate task metrics over a time window — the query layer behind status
+/// dashboards. Only tasks created inside the window are counted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TaskStatusSummary {
+    pub window_secs: u64,
+    pub total_tasks: u64,
+    pub by_status: BTreeMap<String, u64>,
+    pub by_channel: BTreeMap<String, u64>,
+    /// Mean continuation rounds (`attempt_count`) per task.
+    pub average_rounds: f64,
+    /// Total provider retries across all tasks.
+    pub provider_retries: u64,
+    /// Share of tasks that needed at least one provider retry, `0.0..=1.0`.
+    pub retry_rate: f64,
+    /// `reason` of `failed` / `timed_out` events, counted per reason.
+    pub failure_reasons: BTreeMap<String, u64>,
+    /// Estimated USD cost of all priced provider rounds.
+    pub estimated_cost_usd: f64,
+}
+
+/// Point-in-time capacity readings of the task store, exported as gauges.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TaskStoreGauges {
+    pub queued_tasks: u64,
+    /// Age of the oldest queued task; `0` when nothing is queued.
+    pub oldest_queued_age_secs: u64,
+    /// Database file plus its write-ahead log, if any.
+    pub db_size_bytes: u64,
+    /// Terminal tasks due for cold storage but still in the database.
+    pub archive_backlog: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TaskArtifactRecord {
     pub id: i64,
     pub task_id: String,
@@ -85,6 +302,21 @@ pub struct TaskArtifactRecord {
     pub verified: bool,
     pub checksum: Option<String>,
     pub verified_at: Option<String>,
+    pub mime_type: Option<String>,
+    pub preview: Option<ArtifactPreview>,
+}
+
+/// Lightweight description of an artifact captured at verification time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactPreview {
+    pub mime_type: String,
+    pub size_bytes: u64,
+    /// One-line description, e.g. `42 lines`, `PNG 640×480`, `PDF, 3 pages`.
+    pub summary: String,
+    /// First lines of text artifacts.
+    pub excerpt: Option<String>,
+    /// `data:image/jpeg;base64,...` thumbnail of image artifacts.
+    pub thumbnail: Option<String>,
 }
 
 #[cfg(test)]
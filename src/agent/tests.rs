@@ -95,6 +95,7 @@ impl Provider for ScriptedProvider {
                 tool_calls: vec![],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             });
         }
         Ok(guard.remove(0))
@@ -332,6 +333,7 @@ fn tool_response(calls: Vec<ToolCall>) -> ChatResponse {
         tool_calls: calls,
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     }
 }
 
@@ -342,6 +344,7 @@ fn text_response(text: &str) -> ChatResponse {
         tool_calls: vec![],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     }
 }
 
@@ -354,6 +357,7 @@ fn xml_tool_response(name: &str, args: &str) -> ChatResponse {
         tool_calls: vec![],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     }
 }
 
@@ -744,6 +748,7 @@ async fn turn_handles_empty_text_response() {
         tool_calls: vec![],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     }]));
 
     let mut agent = build_agent_with(provider, vec![], Box::new(NativeToolDispatcher));
@@ -759,6 +764,7 @@ async fn turn_handles_none_text_response() {
         tool_calls: vec![],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     }]));
 
     let mut agent = build_agent_with(provider, vec![], Box::new(NativeToolDispatcher));
@@ -784,6 +790,7 @@ async fn turn_preserves_text_alongside_tool_calls() {
             }],
             usage: None,
             reasoning_content: None,
+            finish_reason: None,
         },
         text_response("Here are the results"),
     ]));
@@ -1022,6 +1029,7 @@ async fn native_dispatcher_handles_stringified_arguments() {
         }],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     };
 
     let (_, calls) = dispatcher.parse_response(&response);
@@ -1049,6 +1057,7 @@ fn xml_dispatcher_handles_nested_json() {
         tool_calls: vec![],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     };
 
     let dispatcher = XmlToolDispatcher;
@@ -1068,6 +1077,7 @@ fn xml_dispatcher_handles_empty_tool_call_tag() {
         tool_calls: vec![],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     };
 
     let dispatcher = XmlToolDispatcher;
@@ -1083,6 +1093,7 @@ fn xml_dispatcher_handles_unclosed_tool_call() {
         tool_calls: vec![],
         usage: None,
         reasoning_content: None,
+        finish_reason: None,
     };
 
     let dispatcher = XmlToolDispatcher;
@@ -0,0 +1,133 @@
+//! Wall-clock and output limits for tool calls (`[agent]`
+//! `tool_timeout_secs`, `tool_max_output_bytes`, and
+//! `[agent.tool_limits.<tool>]`), applied by the tool loop to every call.
+
+use crate::config::AgentConfig;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+/// Limits for one tool; `None` leaves that limit off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToolLimit {
+    pub timeout: Option<Duration>,
+    pub max_output_bytes: Option<usize>,
+}
+
+/// Default limits plus per-tool overrides.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolLimits {
+    default: ToolLimit,
+    per_tool: HashMap<String, ToolLimit>,
+}
+
+fn timeout(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+fn max_output(bytes: usize) -> Option<usize> {
+    (bytes > 0).then_some(bytes)
+}
+
+impl ToolLimits {
+    pub fn from_config(config: &AgentConfig) -> Self {
+        let default = ToolLimit {
+            timeout: timeout(config.tool_timeout_secs),
+            max_output_bytes: max_output(config.tool_max_output_bytes),
+        };
+        let per_tool = config
+            .tool_limits
+            .iter()
+            .map(|(tool, limits)| {
+                let limit = ToolLimit {
+                    timeout: limits.timeout_secs.map_or(default.timeout, timeout),
+                    max_output_bytes: limits
+                        .max_output_bytes
+                        .map_or(default.max_output_bytes, max_output),
+                };
+                (tool.clone(), limit)
+            })
+            .collect();
+        Self { default, per_tool }
+    }
+
+    pub fn for_tool(&self, tool: &str) -> ToolLimit {
+        self.per_tool.get(tool).copied().unwrap_or(self.default)
+    }
+}
+
+static LIMITS: LazyLock<RwLock<ToolLimits>> =
+    LazyLock::new(|| RwLock::new(ToolLimits::from_config(&AgentConfig::default())));
+
+/// Replace the process-wide limits; called once the config is loaded.
+pub fn init_from_config(config: &AgentConfig) {
+    let mut guard = LIMITS.write().unwrap_or_else(|e| e.into_inner());
+    *guard = ToolLimits::from_config(config);
+}
+
+/// Limits in effect for `tool`.
+pub fn for_tool(tool: &str) -> ToolLimit {
+    LIMITS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .for_tool(tool)
+}
+
+/// Cut `output` to `limit` bytes, keeping its start and end around a marker
+/// saying how much was left out.
+pub fn truncate_output(output: &mut String, limit: Option<usize>) {
+    if let Some(limit) = limit {
+        crate::tools::shell::truncate_output(output, limit, "tool output");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ToolLimitsConfig;
+
+    #[test]
+    fn overrides_fall_back_to_defaults_and_zero_disables() {
+        let config = AgentConfig {
+            tool_timeout_secs: 30,
+            tool_max_output_bytes: 1000,
+            tool_limits: HashMap::from([
+                (
+                    "delegate".to_string(),
+                    ToolLimitsConfig {
+                        timeout_secs: Some(0),
+                        max_output_bytes: None,
+                    },
+                ),
+                (
+                    "file_read".to_string(),
+                    ToolLimitsConfig {
+                        timeout_secs: None,
+                        max_output_bytes: Some(50),
+                    },
+                ),
+            ]),
+            ..AgentConfig::default()
+        };
+        let limits = ToolLimits::from_config(&config);
+
+        assert_eq!(
+            limits.for_tool("shell"),
+            ToolLimit {
+                timeout: Some(Duration::from_secs(30)),
+                max_output_bytes: Some(1000),
+            }
+        );
+        assert_eq!(limits.for_tool("delegate").timeout, None);
+        assert_eq!(limits.for_tool("delegate").max_output_bytes, Some(1000));
+        assert_eq!(
+            limits.for_tool("file_read").timeout,
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(limits.for_tool("file_read").max_output_bytes, Some(50));
+
+        let mut output = "x".repeat(120);
+        truncate_output(&mut output, limits.for_tool("file_read").max_output_bytes);
+        assert!(output.contains("[70 bytes of tool output omitted]"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Files changed in the workspace during one task-engine round.
+//!
+//! With `autonomy.task_file_snapshots` on, the engine records the size and
+//! mtime of every workspace file before and after each round and logs the
+//! difference as a [`FILES_CHANGED_EVENT`]. Changes made by anything else
+//! running in the workspace at the same time are attributed to the round too.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Event listing the files a round created, modified, and deleted.
+pub const FILES_CHANGED_EVENT: &str = "files_changed";
+
+/// Files recorded per snapshot; larger workspaces are only partly covered.
+const MAX_SNAPSHOT_FILES: usize = 10_000;
+
+/// Directories never scanned: VCS metadata, build output, dependencies, and
+/// the runtime's own `state/` (task database, archives).
+const SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "target", "state"];
+
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceSnapshot {
+    /// Workspace-relative path → (mtime, size).
+    files: BTreeMap<String, (Option<SystemTime>, u64)>,
+    truncated: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceChanges {
+    pub created: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+    /// A snapshot hit the file limit, so some changes may be missing.
+    pub truncated: bool,
+}
+
+impl WorkspaceChanges {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+}
+
+impl WorkspaceSnapshot {
+    pub fn capture(root: &Path) -> Self {
+        let mut snapshot = Self::default();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = entry.path();
+                if file_type.is_dir() {
+                    let skipped = entry
+                        .file_name()
+                        .to_str()
+                        .is_some_and(|name| SKIPPED_DIRS.contains(&name));
+                    if !skipped {
+                        pending.push(path);
+                    }
+                    continue;
+                }
+                if !file_type.is_file() {
+                    continue;
+                }
+                if snapshot.files.len() >= MAX_SNAPSHOT_FILES {
+                    snapshot.truncated = true;
+                    return snapshot;
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                let relative = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                snapshot
+                    .files
+                    .insert(relative, (metadata.modified().ok(), metadata.len()));
+            }
+        }
+        snapshot
+    }
+
+    /// Files that differ between this snapshot and `after`, by path.
+    pub fn changes_since(&self, after: &Self) -> WorkspaceChanges {
+        let mut changes = WorkspaceChanges {
+            truncated: self.truncated || after.truncated,
+            ..WorkspaceChanges::default()
+        };
+        for (path, state) in &after.files {
+            match self.files.get(path) {
+                None => changes.created.push(path.clone()),
+                Some(before) if before != state => changes.modified.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+        changes.deleted = self
+            .files
+            .keys()
+            .filter(|path| !after.files.contains_key(*path))
+            .cloned()
+            .collect();
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn changes_list_created_modified_and_deleted_files() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::create_dir_all(root.join("state")).unwrap();
+        std::fs::write(root.join("docs/keep.md"), "same").unwrap();
+        std::fs::write(root.join("edit.txt"), "v1").unwrap();
+        std::fs::write(root.join("gone.txt"), "bye").unwrap();
+        let before = WorkspaceSnapshot::capture(root);
+
+        std::fs::write(root.join("edit.txt"), "version two").unwrap();
+        std::fs::remove_file(root.join("gone.txt")).unwrap();
+        std::fs::write(root.join("docs/new.md"), "hello").unwrap();
+        std::fs::write(root.join("state/task-runs.db"), "ignored").unwrap();
+        let after = WorkspaceSnapshot::capture(root);
+
+        let changes = before.changes_since(&after);
+        assert_eq!(
+            changes,
+            WorkspaceChanges {
+                created: vec!["docs/new.md".to_string()],
+                modified: vec!["edit.txt".to_string()],
+                deleted: vec!["gone.txt".to_string()],
+                truncated: false,
+            }
+        );
+        assert!(after.changes_since(&after).is_empty());
+    }
+}
@@ -0,0 +1,321 @@
+//! Copy-on-write backups of the files a task run writes, for rollback.
+//!
+//! With `autonomy.task_write_backups` on, the engine runs each task inside
+//! [`scope`] with a [`WorkspaceTransaction`]. Before a write-like tool call
+//! (`file_write`, `file_edit`, other `ToolKind::WriteLike` tools) touches a
+//! path, the file's original content is copied to `state/backups/<task_id>/`
+//! — once per path, so the backup is the file as it was before the task.
+//! Paths that did not exist yet are recorded as created. Writes made through
+//! `shell` are not covered, and paths the security policy refuses or that
+//! resolve outside the workspace are never copied or restored.
+//!
+//! [`TaskEngine::rollback`](crate::agent::task_engine::TaskEngine::rollback)
+//! restores the originals and deletes the created files.
+
+use crate::security::SecurityPolicy;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Workspace-relative directory holding one backup directory per task.
+pub const BACKUP_DIR: &str = "state/backups";
+
+/// Event recording a rollback and the paths it restored and removed.
+pub const ROLLED_BACK_EVENT: &str = "rolled_back";
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+tokio::task_local! {
+    static CURRENT: Arc<WorkspaceTransaction>;
+}
+
+/// Path → backup file name, or `None` when the task created the path.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    files: BTreeMap<String, Option<String>>,
+}
+
+#[derive(Debug)]
+pub struct WorkspaceTransaction {
+    task_id: String,
+    workspace_dir: PathBuf,
+    backup_dir: PathBuf,
+    security: Arc<SecurityPolicy>,
+    manifest: Mutex<Manifest>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RollbackReport {
+    /// Files put back to their content from before the task.
+    pub restored: Vec<String>,
+    /// Files the task created, now deleted.
+    pub removed: Vec<String>,
+}
+
+impl WorkspaceTransaction {
+    /// Open the transaction for `task_id`, keeping backups from earlier
+    /// attempts of the same task. `security` decides which paths may be
+    /// backed up and restored.
+    pub fn open(
+        workspace_dir: &Path,
+        task_id: &str,
+        security: Arc<SecurityPolicy>,
+    ) -> Result<Self> {
+        let backup_dir = workspace_dir.join(BACKUP_DIR).join(task_id);
+        let manifest = match std::fs::read(backup_dir.join(MANIFEST_FILE)) {
+            Ok(raw) => serde_json::from_slice(&raw)
+                .with_context(|| format!("Corrupt backup manifest in {}", backup_dir.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Manifest::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            task_id: task_id.to_string(),
+            workspace_dir: workspace_dir.to_path_buf(),
+            backup_dir,
+            security,
+            manifest: Mutex::new(manifest),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.manifest
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .files
+            .is_empty()
+    }
+
+    /// Where `path` (relative to the workspace or absolute) really points,
+    /// following symlinks; refused when the security policy rejects it or it
+    /// resolves outside the workspace.
+    fn resolve(&self, path: &str) -> Result<PathBuf> {
+        if !self.security.is_path_allowed(path) {
+            anyhow::bail!("Path not allowed by security policy: {path}");
+        }
+        let root = self
+            .workspace_dir
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", self.workspace_dir.display()))?;
+        // Canonicalize the deepest existing ancestor: the file, or the
+        // directories the write is about to create, may not exist yet.
+        let target = self.workspace_dir.join(path);
+        let mut existing = target.as_path();
+        let mut missing = Vec::new();
+        let resolved = loop {
+            match existing.canonicalize() {
+                Ok(resolved) => break resolved,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    let (Some(parent), Some(name)) = (existing.parent(), existing.file_name())
+                    else {
+                        return Err(err)
+                            .with_context(|| format!("Failed to resolve {}", target.display()));
+                    };
+                    missing.push(name);
+                    existing = parent;
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("Failed to resolve {}", target.display()))
+                }
+            }
+        };
+        let resolved = missing
+            .into_iter()
+            .rev()
+            .fold(resolved, |resolved, name| resolved.join(name));
+        if !resolved.starts_with(&root) {
+            anyhow::bail!("Path resolves outside the workspace: {path}");
+        }
+        Ok(resolved)
+    }
+
+    /// Save the current content of `path` (relative to the workspace or
+    /// absolute) unless this task already backed it up. Paths outside the
+    /// workspace or refused by the security policy are an error.
+    pub fn backup_before_write(&self, path: &str) -> Result<()> {
+        let mut manifest = self
+            .manifest
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if manifest.files.contains_key(path) {
+            return Ok(());
+        }
+        let target = self.resolve(path)?;
+        let backup = if target.is_file() {
+            let name = format!("{}.bak", manifest.files.len());
+            std::fs::create_dir_all(&self.backup_dir)
+                .with_context(|| format!("Failed to create {}", self.backup_dir.display()))?;
+            std::fs::copy(&target, self.backup_dir.join(&name))
+                .with_context(|| format!("Failed to back up {}", target.display()))?;
+            Some(name)
+        } else {
+            None
+        };
+        manifest.files.insert(path.to_string(), backup);
+        self.save(&manifest)
+    }
+
+    /// Restore every backed-up file and delete the ones the task created,
+    /// then drop the backups. Entries that no longer resolve inside the
+    /// workspace are skipped.
+    pub fn rollback(&self) -> Result<RollbackReport> {
+        let manifest = self
+            .manifest
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut report = RollbackReport::default();
+        for (path, backup) in &manifest.files {
+            let target = match self.resolve(path) {
+                Ok(target) => target,
+                Err(err) => {
+                    tracing::warn!("Not rolling back {path}: {err:#}");
+                    continue;
+                }
+            };
+            match backup {
+                Some(name) => {
+                    if let Some(parent) = target.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::copy(self.backup_dir.join(name), &target)
+                        .with_context(|| format!("Failed to restore {}", target.display()))?;
+                    report.restored.push(path.clone());
+                }
+                None => match std::fs::remove_file(&target) {
+                    Ok(()) => report.removed.push(path.clone()),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(err) => {
+                        return Err(err)
+                            .with_context(|| format!("Failed to remove {}", target.display()))
+                    }
+                },
+            }
+        }
+        drop(manifest);
+        discard(&self.workspace_dir, &self.task_id)?;
+        Ok(report)
+    }
+
+    fn save(&self, manifest: &Manifest) -> Result<()> {
+        std::fs::create_dir_all(&self.backup_dir)
+            .with_context(|| format!("Failed to create {}", self.backup_dir.display()))?;
+        std::fs::write(
+            self.backup_dir.join(MANIFEST_FILE),
+            serde_json::to_vec(manifest)?,
+        )
+        .with_context(|| {
+            format!(
+                "Failed to write backup manifest in {}",
+                self.backup_dir.display()
+            )
+        })
+    }
+}
+
+/// Delete the backups kept for `task_id`, if any.
+pub fn discard(workspace_dir: &Path, task_id: &str) -> Result<()> {
+    let dir = workspace_dir.join(BACKUP_DIR).join(task_id);
+    match std::fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("Failed to remove {}", dir.display())),
+    }
+}
+
+/// Run `future` with `transaction` backing up the files its tools write.
+pub async fn scope<F: Future>(transaction: Arc<WorkspaceTransaction>, future: F) -> F::Output {
+    CURRENT.scope(transaction, future).await
+}
+
+/// Back up the `path` argument of a write-like tool call in the current
+/// task's transaction; a no-op outside one. Failures are logged, not fatal.
+pub fn before_write(arguments: &serde_json::Value) {
+    let Some(path) = arguments.get("path").and_then(serde_json::Value::as_str) else {
+        return;
+    };
+    let _ = CURRENT.try_with(|transaction| {
+        if let Err(err) = transaction.backup_before_write(path) {
+            tracing::warn!("Failed to back up {path} before write: {err:#}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn open(root: &Path, task_id: &str, security: SecurityPolicy) -> WorkspaceTransaction {
+        WorkspaceTransaction::open(root, task_id, Arc::new(security)).expect("open transaction")
+    }
+
+    #[tokio::test]
+    async fn rollback_restores_originals_and_removes_created_files() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        std::fs::write(root.join("notes.md"), "original").unwrap();
+        let transaction = Arc::new(open(root, "task-1", SecurityPolicy::default()));
+
+        scope(Arc::clone(&transaction), async {
+            for (path, content) in [
+                ("notes.md", "first"),
+                ("notes.md", "second"),
+                ("new.md", "x"),
+            ] {
+                before_write(&serde_json::json!({ "path": path }));
+                std::fs::write(root.join(path), content).unwrap();
+            }
+        })
+        .await;
+        // Outside a scope nothing is recorded.
+        before_write(&serde_json::json!({ "path": "other.md" }));
+
+        let reopened = open(root, "task-1", SecurityPolicy::default());
+        let report = reopened.rollback().expect("rollback");
+        assert_eq!(report.restored, vec!["notes.md"]);
+        assert_eq!(report.removed, vec!["new.md"]);
+        assert_eq!(
+            std::fs::read_to_string(root.join("notes.md")).unwrap(),
+            "original"
+        );
+        assert!(!root.join("new.md").exists());
+        assert!(!root.join(BACKUP_DIR).join("task-1").exists());
+        assert!(open(root, "task-1", SecurityPolicy::default()).is_empty());
+    }
+
+    #[test]
+    fn paths_outside_the_workspace_are_never_backed_up_or_restored() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path().join("workspace");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(tmp.path().join("x"), "secret").unwrap();
+
+        let transaction = open(&root, "task-1", SecurityPolicy::default());
+        assert!(transaction.backup_before_write("../x").is_err());
+        assert!(transaction.backup_before_write("/etc/hosts").is_err());
+
+        // Even a policy that allows absolute paths keeps backups in the workspace.
+        let permissive = SecurityPolicy {
+            workspace_only: false,
+            forbidden_paths: Vec::new(),
+            ..SecurityPolicy::default()
+        };
+        let transaction = open(&root, "task-1", permissive);
+        assert!(transaction.backup_before_write("../x").is_err());
+        assert!(transaction.backup_before_write("/etc/hosts").is_err());
+        let outside = tmp.path().join("x");
+        assert!(transaction
+            .backup_before_write(outside.to_str().unwrap())
+            .is_err());
+        assert!(transaction.is_empty());
+        assert!(!root.join(BACKUP_DIR).join("task-1").exists());
+
+        transaction
+            .backup_before_write("notes/new.md")
+            .expect("backup");
+        assert!(!transaction.is_empty());
+    }
+}
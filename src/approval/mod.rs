@@ -179,7 +179,7 @@ fn prompt_cli_interactive(request: &ApprovalRequest) -> ApprovalResponse {
 }
 
 /// Produce a short human-readable summary of tool arguments.
-fn summarize_args(args: &serde_json::Value) -> String {
+pub(crate) fn summarize_args(args: &serde_json::Value) -> String {
     match args {
         serde_json::Value::Object(map) => {
             let parts: Vec<String> = map
@@ -0,0 +1,73 @@
+//! `/undo`: roll the sender's conversation back one exchange.
+//!
+//! An exchange is the last user turn plus everything after it (the assistant
+//! reply and any tool-context summary). The removed turns are archived in the
+//! task store as a conversation branch, so a misunderstanding stops steering
+//! later replies without the record being lost.
+
+use crate::providers::ChatMessage;
+use crate::util::truncate_with_ellipsis;
+
+pub const UNDO_COMMAND: &str = "/undo";
+
+/// Characters of the removed user message quoted in the confirmation.
+const UNDO_PREVIEW_CHARS: usize = 80;
+
+pub fn is_undo_command(content: &str) -> bool {
+    content.trim().eq_ignore_ascii_case(UNDO_COMMAND)
+}
+
+/// Remove the last exchange from `turns` and return it, or `None` when there
+/// is no user turn to roll back to.
+pub fn split_last_exchange(turns: &mut Vec<ChatMessage>) -> Option<Vec<ChatMessage>> {
+    let start = turns.iter().rposition(|turn| turn.role == "user")?;
+    Some(turns.split_off(start))
+}
+
+/// Confirmation sent after `/undo`.
+pub fn render_undo_reply(removed: Option<&[ChatMessage]>) -> String {
+    let Some(request) = removed.and_then(|turns| turns.first()) else {
+        return "Nothing to undo in this conversation.".to_string();
+    };
+    format!(
+        "↩️ Removed the last exchange (\"{}\") from this conversation. It is archived and will no longer be used as context.",
+        truncate_with_ellipsis(request.content.trim(), UNDO_PREVIEW_CHARS)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_removes_from_last_user_turn() {
+        let mut turns = vec![
+            ChatMessage::user("first"),
+            ChatMessage::assistant("reply one"),
+            ChatMessage::user("delete the logs"),
+            ChatMessage::assistant("Deleted."),
+            ChatMessage::assistant("[Used tools: shell]"),
+        ];
+
+        let removed = split_last_exchange(&mut turns).expect("exchange");
+        assert_eq!(removed.len(), 3);
+        assert_eq!(removed[0].content, "delete the logs");
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[1].content, "reply one");
+        assert!(render_undo_reply(Some(&removed)).contains("\"delete the logs\""));
+
+        let mut empty = vec![ChatMessage::assistant("hello")];
+        assert!(split_last_exchange(&mut empty).is_none());
+        assert_eq!(
+            render_undo_reply(None),
+            "Nothing to undo in this conversation."
+        );
+    }
+
+    #[test]
+    fn parses_only_the_bare_command() {
+        assert!(is_undo_command(" /UNDO "));
+        assert!(!is_undo_command("/undo please"));
+        assert!(!is_undo_command("undo"));
+    }
+}
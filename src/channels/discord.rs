@@ -1,4 +1,5 @@
-use super::traits::{Channel, ChannelMessage, SendMessage};
+use super::traits::{Channel, ChannelMessage, QuickReply, SendMessage};
+use crate::config::StreamMode;
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use parking_lot::Mutex;
@@ -14,6 +15,14 @@ pub struct DiscordChannel {
     allowed_users: Vec<String>,
     listen_to_bots: bool,
     mention_only: bool,
+    /// Guild channels to listen in; empty listens everywhere.
+    channel_ids: Vec<String>,
+    stream_mode: StreamMode,
+    draft_update_interval_ms: u64,
+    /// Pass image attachments on as `[IMAGE:<url>]` markers; otherwise they
+    /// are only named in the text.
+    image_markers: bool,
+    last_draft_edit: Mutex<HashMap<String, std::time::Instant>>,
     typing_handles: Mutex<HashMap<String, tokio::task::JoinHandle<()>>>,
 }
 
@@ -31,10 +40,40 @@ impl DiscordChannel {
             allowed_users,
             listen_to_bots,
             mention_only,
+            channel_ids: Vec::new(),
+            stream_mode: StreamMode::Off,
+            draft_update_interval_ms: 1000,
+            image_markers: false,
+            last_draft_edit: Mutex::new(HashMap::new()),
             typing_handles: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Send image attachments to the model as `[IMAGE:<url>]` markers. Only
+    /// enable this when the provider supports vision and
+    /// `multimodal.allow_remote_fetch` is on, or such messages fail.
+    pub fn with_image_markers(mut self, enabled: bool) -> Self {
+        self.image_markers = enabled;
+        self
+    }
+
+    /// Only listen in these guild channels; direct messages always pass.
+    pub fn with_channel_ids(mut self, channel_ids: Vec<String>) -> Self {
+        self.channel_ids = channel_ids;
+        self
+    }
+
+    /// Configure streaming mode for progressive draft updates.
+    pub fn with_streaming(
+        mut self,
+        stream_mode: StreamMode,
+        draft_update_interval_ms: u64,
+    ) -> Self {
+        self.stream_mode = stream_mode;
+        self.draft_update_interval_ms = draft_update_interval_ms;
+        self
+    }
+
     fn http_client(&self) -> reqwest::Client {
         crate::config::build_runtime_proxy_client("channel.discord")
     }
@@ -46,6 +85,16 @@ impl DiscordChannel {
         self.allowed_users.iter().any(|u| u == "*" || u == user_id)
     }
 
+    /// Check a guild message's channel against `channel_ids`.
+    /// Empty list means every channel; direct messages are not filtered.
+    fn is_channel_allowed(&self, channel_id: &str) -> bool {
+        self.channel_ids.is_empty() || self.channel_ids.iter().any(|c| c == channel_id)
+    }
+
+    fn message_url(channel_id: &str, message_id: &str) -> String {
+        format!("https://discord.com/api/v10/channels/{channel_id}/messages/{message_id}")
+    }
+
     fn bot_user_id_from_token(token: &str) -> Option<String> {
         // Discord bot tokens are base64(bot_user_id).timestamp.hmac
         let part = token.split('.').next()?;
@@ -56,11 +105,14 @@ impl DiscordChannel {
 /// Process Discord message attachments and return a string to append to the
 /// agent message context.
 ///
-/// Only `text/*` MIME types are fetched and inlined. All other types are
-/// silently skipped. Fetch errors are logged as warnings.
+/// `text/*` attachments are fetched and inlined. `image/*` attachments become
+/// `[IMAGE:<url>]` markers when `image_markers` is set, and a plain
+/// `[image attachment: <name>]` note otherwise. All other types are silently
+/// skipped. Fetch errors are logged as warnings.
 async fn process_attachments(
     attachments: &[serde_json::Value],
     client: &reqwest::Client,
+    image_markers: bool,
 ) -> String {
     let mut parts: Vec<String> = Vec::new();
     for att in attachments {
@@ -76,7 +128,13 @@ async fn process_attachments(
             tracing::warn!(name, "discord: attachment has no url, skipping");
             continue;
         };
-        if ct.starts_with("text/") {
+        if ct.starts_with("image/") {
+            if image_markers {
+                parts.push(format!("[IMAGE:{url}]"));
+            } else {
+                parts.push(format!("[image attachment: {name}]"));
+            }
+        } else if ct.starts_with("text/") {
             match client.get(url).send().await {
                 Ok(resp) if resp.status().is_success() => {
                     if let Ok(text) = resp.text().await {
@@ -157,6 +215,14 @@ fn split_message_for_discord(message: &str) -> Vec<String> {
     chunks
 }
 
+/// Cut `text` to Discord's message limit for mid-stream draft edits.
+fn truncate_for_discord(text: &str) -> &str {
+    match text.char_indices().nth(DISCORD_MAX_MESSAGE_LENGTH) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
 fn pick_uniform_index(len: usize) -> usize {
     debug_assert!(len > 0);
     let upper = len as u64;
@@ -236,6 +302,62 @@ fn normalize_incoming_content(
     Some(normalized)
 }
 
+/// Render quick replies as a single Discord action row of buttons.
+fn quick_reply_components(quick_replies: &[QuickReply]) -> Option<serde_json::Value> {
+    if quick_replies.is_empty() {
+        return None;
+    }
+    let buttons: Vec<serde_json::Value> = quick_replies
+        .iter()
+        .take(5) // Discord allows at most five buttons per action row
+        .enumerate()
+        .map(|(index, reply)| {
+            json!({
+                "type": 2,
+                "style": if index == 0 { 1 } else { 2 },
+                "label": reply.label,
+                "custom_id": reply.payload,
+            })
+        })
+        .collect();
+    Some(json!([{ "type": 1, "components": buttons }]))
+}
+
+/// Button press extracted from an `INTERACTION_CREATE` gateway event.
+struct ComponentInteraction {
+    id: String,
+    token: String,
+    user_id: String,
+    channel_id: String,
+    custom_id: String,
+}
+
+fn parse_component_interaction(d: &serde_json::Value) -> Option<ComponentInteraction> {
+    // Type 3 = MESSAGE_COMPONENT
+    if d.get("type").and_then(serde_json::Value::as_u64) != Some(3) {
+        return None;
+    }
+    let str_field = |value: Option<&serde_json::Value>| {
+        value
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+    };
+    // Guild interactions carry `member.user`; DMs carry `user`.
+    let user_id = str_field(
+        d.get("member")
+            .and_then(|m| m.get("user"))
+            .or_else(|| d.get("user"))
+            .and_then(|u| u.get("id")),
+    )?;
+    Some(ComponentInteraction {
+        id: str_field(d.get("id"))?,
+        token: str_field(d.get("token"))?,
+        user_id,
+        channel_id: str_field(d.get("channel_id"))?,
+        custom_id: str_field(d.get("data").and_then(|data| data.get("custom_id")))?,
+    })
+}
+
 /// Minimal base64 decode (no extra dep) — only needs to decode the user ID portion
 #[allow(clippy::cast_possible_truncation)]
 fn base64_decode(input: &str) -> Option<String> {
@@ -283,6 +405,7 @@ impl Channel for DiscordChannel {
     async fn send(&self, message: &SendMessage) -> anyhow::Result<()> {
         let content = super::strip_tool_call_tags(&message.content);
         let chunks = split_message_for_discord(&content);
+        let components = quick_reply_components(&message.quick_replies);
 
         for (i, chunk) in chunks.iter().enumerate() {
             let url = format!(
@@ -290,7 +413,10 @@ impl Channel for DiscordChannel {
                 message.recipient
             );
 
-            let body = json!({ "content": chunk });
+            let mut body = json!({ "content": chunk });
+            if let Some(components) = components.as_ref().filter(|_| i == chunks.len() - 1) {
+                body["components"] = components.clone();
+            }
 
             let resp = self
                 .http_client()
@@ -442,8 +568,51 @@ impl Channel for DiscordChannel {
                         _ => {}
                     }
 
-                    // Only handle MESSAGE_CREATE (opcode 0, type "MESSAGE_CREATE")
                     let event_type = event.get("t").and_then(|t| t.as_str()).unwrap_or("");
+
+                    // Button presses on quick-reply components
+                    if event_type == "INTERACTION_CREATE" {
+                        let Some(interaction) =
+                            event.get("d").and_then(parse_component_interaction)
+                        else {
+                            continue;
+                        };
+                        // Type 6 = DEFERRED_UPDATE_MESSAGE: acknowledge without replying
+                        let _ = self
+                            .http_client()
+                            .post(format!(
+                                "https://discord.com/api/v10/interactions/{}/{}/callback",
+                                interaction.id, interaction.token
+                            ))
+                            .json(&json!({ "type": 6 }))
+                            .send()
+                            .await;
+                        if !self.is_user_allowed(&interaction.user_id) {
+                            tracing::warn!(
+                                "Discord: ignoring interaction from unauthorized user: {}",
+                                interaction.user_id
+                            );
+                            continue;
+                        }
+                        let channel_msg = ChannelMessage {
+                            id: format!("discord_interaction_{}", interaction.id),
+                            sender: interaction.user_id,
+                            reply_target: interaction.channel_id,
+                            content: interaction.custom_id,
+                            channel: "discord".to_string(),
+                            timestamp: std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs(),
+                            thread_ts: None,
+                        };
+                        if tx.send(channel_msg).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+
+                    // Otherwise only handle MESSAGE_CREATE (opcode 0, type "MESSAGE_CREATE")
                     if event_type != "MESSAGE_CREATE" {
                         continue;
                     }
@@ -480,6 +649,14 @@ impl Channel for DiscordChannel {
                         }
                     }
 
+                    // Channel filter (guild messages only)
+                    if d.get("guild_id").is_some() {
+                        let msg_channel = d.get("channel_id").and_then(serde_json::Value::as_str).unwrap_or("");
+                        if !self.is_channel_allowed(msg_channel) {
+                            continue;
+                        }
+                    }
+
                     let content = d.get("content").and_then(|c| c.as_str()).unwrap_or("");
                     let Some(clean_content) =
                         normalize_incoming_content(content, self.mention_only, &bot_user_id)
@@ -493,7 +670,7 @@ impl Channel for DiscordChannel {
                             .and_then(|a| a.as_array())
                             .cloned()
                             .unwrap_or_default();
-                        process_attachments(&atts, &self.http_client()).await
+                        process_attachments(&atts, &self.http_client(), self.image_markers).await
                     };
                     let final_content = if attachment_text.is_empty() {
                         clean_content
@@ -566,6 +743,143 @@ impl Channel for DiscordChannel {
         Ok(())
     }
 
+    fn supports_quick_replies(&self) -> bool {
+        true
+    }
+
+    fn supports_draft_updates(&self) -> bool {
+        self.stream_mode != StreamMode::Off
+    }
+
+    async fn send_draft(&self, message: &SendMessage) -> anyhow::Result<Option<String>> {
+        if self.stream_mode == StreamMode::Off {
+            return Ok(None);
+        }
+
+        let initial_text = if message.content.is_empty() {
+            "...".to_string()
+        } else {
+            truncate_for_discord(&message.content).to_string()
+        };
+        let resp = self
+            .http_client()
+            .post(format!(
+                "https://discord.com/api/v10/channels/{}/messages",
+                message.recipient
+            ))
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .json(&json!({ "content": initial_text }))
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Discord send draft failed ({status}): {err}");
+        }
+
+        let resp_json: serde_json::Value = resp.json().await?;
+        let message_id = resp_json
+            .get("id")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string);
+
+        self.last_draft_edit
+            .lock()
+            .insert(message.recipient.clone(), std::time::Instant::now());
+
+        Ok(message_id)
+    }
+
+    async fn update_draft(
+        &self,
+        recipient: &str,
+        message_id: &str,
+        text: &str,
+    ) -> anyhow::Result<()> {
+        // Rate-limit edits per channel
+        {
+            let last_edits = self.last_draft_edit.lock();
+            if let Some(last_time) = last_edits.get(recipient) {
+                let elapsed = u64::try_from(last_time.elapsed().as_millis()).unwrap_or(u64::MAX);
+                if elapsed < self.draft_update_interval_ms {
+                    return Ok(());
+                }
+            }
+        }
+
+        let resp = self
+            .http_client()
+            .patch(Self::message_url(recipient, message_id))
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .json(&json!({ "content": truncate_for_discord(text) }))
+            .send()
+            .await?;
+
+        if resp.status().is_success() {
+            self.last_draft_edit
+                .lock()
+                .insert(recipient.to_string(), std::time::Instant::now());
+        } else {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            tracing::debug!("Discord draft edit failed ({status}): {err}");
+        }
+
+        Ok(())
+    }
+
+    async fn finalize_draft(
+        &self,
+        recipient: &str,
+        message_id: &str,
+        text: &str,
+    ) -> anyhow::Result<()> {
+        let text = super::strip_tool_call_tags(text);
+        self.last_draft_edit.lock().remove(recipient);
+
+        // Too long for one message: replace the draft with chunked messages
+        if text.chars().count() > DISCORD_MAX_MESSAGE_LENGTH {
+            self.cancel_draft(recipient, message_id).await?;
+            return self.send(&SendMessage::new(text, recipient)).await;
+        }
+
+        let resp = self
+            .http_client()
+            .patch(Self::message_url(recipient, message_id))
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .json(&json!({ "content": text }))
+            .send()
+            .await?;
+
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        // Edit failed — fall back to a new message
+        tracing::warn!("Discord finalize_draft edit failed; falling back to a new message");
+        self.send(&SendMessage::new(text, recipient)).await
+    }
+
+    async fn cancel_draft(&self, recipient: &str, message_id: &str) -> anyhow::Result<()> {
+        self.last_draft_edit.lock().remove(recipient);
+
+        let resp = self
+            .http_client()
+            .delete(Self::message_url(recipient, message_id))
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            tracing::debug!("Discord draft delete failed ({status}): {body}");
+        }
+
+        Ok(())
+    }
+
     async fn health_check(&self) -> bool {
         self.http_client()
             .get("https://discord.com/api/v10/users/@me")
@@ -754,6 +1068,75 @@ mod tests {
         assert!(!ch.is_user_allowed("Abc"));
     }
 
+    #[test]
+    fn channel_filter_defaults_to_every_channel() {
+        let ch = DiscordChannel::new("fake".into(), None, vec!["*".into()], false, false);
+        assert!(ch.is_channel_allowed("c1"));
+
+        let ch = ch.with_channel_ids(vec!["c1".into()]);
+        assert!(ch.is_channel_allowed("c1"));
+        assert!(!ch.is_channel_allowed("c2"));
+    }
+
+    #[test]
+    fn draft_updates_follow_stream_mode_and_truncate_to_limit() {
+        let ch = DiscordChannel::new("fake".into(), None, vec![], false, false);
+        assert!(!ch.supports_draft_updates());
+        let ch = ch.with_streaming(StreamMode::Partial, 500);
+        assert!(ch.supports_draft_updates());
+
+        let long = "é".repeat(DISCORD_MAX_MESSAGE_LENGTH + 10);
+        assert_eq!(
+            truncate_for_discord(&long).chars().count(),
+            DISCORD_MAX_MESSAGE_LENGTH
+        );
+        assert_eq!(truncate_for_discord("short"), "short");
+    }
+
+    #[test]
+    fn quick_reply_components_render_buttons_with_payload_custom_ids() {
+        assert!(quick_reply_components(&[]).is_none());
+        let components = quick_reply_components(&[
+            QuickReply::new("Continue", "task:resume:1"),
+            QuickReply::new("Cancel", "task:cancel:1"),
+        ])
+        .expect("components");
+        let buttons = components[0]["components"].as_array().expect("buttons");
+        assert_eq!(components[0]["type"], 1);
+        assert_eq!(buttons.len(), 2);
+        assert_eq!(buttons[0]["custom_id"], "task:resume:1");
+        assert_eq!(buttons[0]["style"], 1);
+        assert_eq!(buttons[1]["style"], 2);
+    }
+
+    #[test]
+    fn parse_component_interaction_reads_guild_and_dm_users() {
+        let guild = json!({
+            "type": 3,
+            "id": "i1",
+            "token": "tok",
+            "channel_id": "c1",
+            "member": { "user": { "id": "u1" } },
+            "data": { "custom_id": "task:cancel:1" }
+        });
+        let parsed = parse_component_interaction(&guild).expect("guild interaction");
+        assert_eq!(parsed.user_id, "u1");
+        assert_eq!(parsed.custom_id, "task:cancel:1");
+
+        let dm = json!({
+            "type": 3,
+            "id": "i2",
+            "token": "tok",
+            "channel_id": "c2",
+            "user": { "id": "u2" },
+            "data": { "custom_id": "task:resume:1" }
+        });
+        assert_eq!(parse_component_interaction(&dm).expect("dm").user_id, "u2");
+
+        let slash_command = json!({ "type": 2, "id": "i3", "token": "t", "channel_id": "c" });
+        assert!(parse_component_interaction(&slash_command).is_none());
+    }
+
     #[test]
     fn base64_decode_empty_string() {
         let decoded = base64_decode("");
@@ -1203,7 +1586,7 @@ mod tests {
     #[tokio::test]
     async fn process_attachments_empty_list_returns_empty() {
         let client = reqwest::Client::new();
-        let result = process_attachments(&[], &client).await;
+        let result = process_attachments(&[], &client, true).await;
         assert!(result.is_empty());
     }
 
@@ -1215,7 +1598,26 @@ mod tests {
             "filename": "doc.pdf",
             "content_type": "application/pdf"
         })];
-        let result = process_attachments(&attachments, &client).await;
+        let result = process_attachments(&attachments, &client, true).await;
         assert!(result.is_empty());
     }
+
+    #[tokio::test]
+    async fn process_attachments_turns_images_into_markers() {
+        let client = reqwest::Client::new();
+        let attachments = vec![serde_json::json!({
+            "url": "https://cdn.discordapp.com/attachments/123/456/photo.png",
+            "filename": "photo.png",
+            "content_type": "image/png"
+        })];
+        let result = process_attachments(&attachments, &client, true).await;
+        assert_eq!(
+            result,
+            "[IMAGE:https://cdn.discordapp.com/attachments/123/456/photo.png]"
+        );
+
+        // Without vision and remote fetch the image is only named.
+        let result = process_attachments(&attachments, &client, false).await;
+        assert_eq!(result, "[image attachment: photo.png]");
+    }
 }
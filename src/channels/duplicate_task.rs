@@ -0,0 +1,409 @@
+//! Duplicate-request detection for task-engine channels.
+//!
+//! A request that is near-identical to one the same sender made within
+//! `autonomy.duplicate_task_window_mins`, and whose task is still queued,
+//! running, or completed, is not started again right away. The sender is
+//! asked whether to run it again or see the previous result, by button where
+//! the channel supports quick replies and by keyword elsewhere. The task
+//! awaiting an answer is remembered as the `pending_duplicate` sender setting.
+//!
+//! Requests are compared by wording, and also by embedding when `[memory]`
+//! configures an embedding provider, so a reworded repeat is caught too.
+
+use super::traits::QuickReply;
+use crate::agent::task_types::{TaskRunRecord, TaskStatus};
+use crate::memory::embeddings::EmbeddingProvider;
+use crate::memory::vector::cosine_similarity;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+
+/// Sender setting holding the task id a duplicate prompt is waiting on.
+pub const PENDING_DUPLICATE_KEY: &str = "pending_duplicate";
+
+/// Recent tasks compared against a new request.
+pub const DUPLICATE_CANDIDATE_TASKS: usize = 10;
+
+/// Bigram overlap (Dice coefficient) at which two requests count as the same.
+const SIMILARITY_THRESHOLD: f64 = 0.9;
+
+/// Cosine similarity of request embeddings at which a reworded request
+/// counts as the same.
+const EMBEDDING_SIMILARITY_THRESHOLD: f32 = 0.92;
+
+/// Requests shorter than this (after normalizing) are never flagged, so
+/// greetings and one-word replies pass straight through.
+const MIN_COMPARABLE_CHARS: usize = 8;
+
+/// Prefix of button payloads answering a duplicate prompt.
+const DUPLICATE_REPLY_PREFIX: &str = "dup:";
+
+const AGAIN_KEYWORDS: &[&str] = &[
+    "again",
+    "run again",
+    "rerun",
+    "重新执行",
+    "再来一次",
+    "再跑一次",
+];
+const SHOW_KEYWORDS: &[&str] = &[
+    "show",
+    "show previous",
+    "previous",
+    "查看结果",
+    "看结果",
+    "显示结果",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateChoice {
+    RunAgain,
+    ShowPrevious,
+}
+
+impl DuplicateChoice {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::RunAgain => "again",
+            Self::ShowPrevious => "show",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateReply {
+    pub choice: DuplicateChoice,
+    /// Task named by a button payload; `None` for typed keywords, which apply
+    /// to the pending prompt.
+    pub task_id: Option<String>,
+}
+
+/// Parse a button payload or a single keyword answering a duplicate prompt.
+pub fn parse_duplicate_reply(content: &str) -> Option<DuplicateReply> {
+    let trimmed = content.trim();
+    if let Some(rest) = trimmed.strip_prefix(DUPLICATE_REPLY_PREFIX) {
+        let (choice, task_id) = rest.split_once(':')?;
+        let choice = match choice {
+            "again" => DuplicateChoice::RunAgain,
+            "show" => DuplicateChoice::ShowPrevious,
+            _ => return None,
+        };
+        let task_id = task_id.trim();
+        return (!task_id.is_empty()).then(|| DuplicateReply {
+            choice,
+            task_id: Some(task_id.to_string()),
+        });
+    }
+
+    let keyword = trimmed
+        .trim_end_matches(['.', '!', '。', '！'])
+        .to_lowercase();
+    let choice = if AGAIN_KEYWORDS.contains(&keyword.as_str()) {
+        DuplicateChoice::RunAgain
+    } else if SHOW_KEYWORDS.contains(&keyword.as_str()) {
+        DuplicateChoice::ShowPrevious
+    } else {
+        return None;
+    };
+    Some(DuplicateReply {
+        choice,
+        task_id: None,
+    })
+}
+
+/// Most recent task in `tasks` that `request` repeats within `window`.
+pub fn find_duplicate<'a>(
+    tasks: &'a [TaskRunRecord],
+    request: &str,
+    window: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Option<&'a TaskRunRecord> {
+    let normalized = normalize(request);
+    if normalized.chars().count() < MIN_COMPARABLE_CHARS {
+        return None;
+    }
+    tasks.iter().find(|task| {
+        is_candidate(task, window, now)
+            && similarity(&normalized, &normalize(&task.original_request)) >= SIMILARITY_THRESHOLD
+    })
+}
+
+/// [`find_duplicate`], falling back to embedding similarity for requests
+/// worded differently. If embedding fails only the wording is compared.
+pub async fn find_duplicate_with_embeddings<'a>(
+    tasks: &'a [TaskRunRecord],
+    request: &str,
+    window: chrono::Duration,
+    now: DateTime<Utc>,
+    embedder: &dyn EmbeddingProvider,
+) -> Option<&'a TaskRunRecord> {
+    if let Some(task) = find_duplicate(tasks, request, window, now) {
+        return Some(task);
+    }
+    if normalize(request).chars().count() < MIN_COMPARABLE_CHARS {
+        return None;
+    }
+    let candidates: Vec<&TaskRunRecord> = tasks
+        .iter()
+        .filter(|task| is_candidate(task, window, now))
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    let texts: Vec<&str> = std::iter::once(request)
+        .chain(candidates.iter().map(|task| task.original_request.as_str()))
+        .collect();
+    let vectors = match embedder.embed(&texts).await {
+        Ok(vectors) if vectors.len() == texts.len() => vectors,
+        Ok(_) => return None,
+        Err(err) => {
+            tracing::warn!(
+                "Request embedding failed; duplicate check compares wording only: {err}"
+            );
+            return None;
+        }
+    };
+    let (query, previous) = vectors.split_first()?;
+    candidates
+        .into_iter()
+        .zip(previous)
+        .find(|(_, vector)| cosine_similarity(query, vector) >= EMBEDDING_SIMILARITY_THRESHOLD)
+        .map(|(task, _)| task)
+}
+
+/// Queued, running, or completed within `window`.
+fn is_candidate(task: &TaskRunRecord, window: chrono::Duration, now: DateTime<Utc>) -> bool {
+    matches!(
+        task.status,
+        TaskStatus::Queued | TaskStatus::Running | TaskStatus::Completed
+    ) && task_age(task, now).is_some_and(|age| age <= window)
+}
+
+/// Prompt sent instead of starting the duplicate run.
+pub fn render_duplicate_prompt(task: &TaskRunRecord, now: DateTime<Utc>, buttons: bool) -> String {
+    let ago = describe_age(task_age(task, now).unwrap_or_default());
+    let state = if task.status == TaskStatus::Completed {
+        "已完成 / completed"
+    } else {
+        "仍在执行 / still running"
+    };
+    let mut prompt = format!(
+        "🔁 你在{ago}前提过同样的请求（{state}）。要重新执行，还是查看上次的结果？\n(You asked this {ago} ago — run it again or show the previous result?)"
+    );
+    if !buttons {
+        prompt.push_str("\n\n回复“重新执行”或“查看结果”。(Reply \"again\" or \"show\".)");
+    }
+    prompt
+}
+
+pub fn duplicate_quick_replies(task_id: &str) -> Vec<QuickReply> {
+    [
+        ("🔁 重新执行 / Run again", DuplicateChoice::RunAgain),
+        ("📄 查看结果 / Show result", DuplicateChoice::ShowPrevious),
+    ]
+    .into_iter()
+    .map(|(label, choice)| {
+        QuickReply::new(
+            label,
+            format!("{DUPLICATE_REPLY_PREFIX}{}:{task_id}", choice.as_str()),
+        )
+    })
+    .collect()
+}
+
+/// Reply to "show the previous result".
+pub fn render_previous_result(task: &TaskRunRecord) -> String {
+    match (task.status, task.last_response.as_deref()) {
+        (TaskStatus::Queued | TaskStatus::Running, _) => {
+            "⏳ 上次的任务仍在执行，完成后会把结果发给你。(That task is still running; its result will be sent when it finishes.)".to_string()
+        }
+        (_, Some(response)) if !response.trim().is_empty() => {
+            format!("📄 上次的结果 / Previous result:\n\n{}", response.trim())
+        }
+        _ => "上次的任务没有留下结果。(That task left no result.)".to_string(),
+    }
+}
+
+fn task_age(task: &TaskRunRecord, now: DateTime<Utc>) -> Option<chrono::Duration> {
+    let reference = if task.status == TaskStatus::Completed {
+        task.completed_at.as_deref().unwrap_or(&task.updated_at)
+    } else {
+        &task.created_at
+    };
+    let at = DateTime::parse_from_rfc3339(reference).ok()?;
+    Some(now.signed_duration_since(at.with_timezone(&Utc)))
+}
+
+fn describe_age(age: chrono::Duration) -> String {
+    let minutes = age.num_minutes().max(1);
+    if minutes < 60 {
+        format!("{minutes} min")
+    } else {
+        format!("{} h", minutes / 60)
+    }
+}
+
+/// Lowercase alphanumerics only; works for both spaced and CJK text.
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Dice coefficient over character bigrams of two normalized strings.
+fn similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    let bigrams = |text: &str| -> HashSet<(char, char)> {
+        let chars: Vec<char> = text.chars().collect();
+        chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+    };
+    let (left, right) = (bigrams(a), bigrams(b));
+    if left.is_empty() || right.is_empty() {
+        return 0.0;
+    }
+    let shared = left.intersection(&right).count();
+    (2 * shared) as f64 / (left.len() + right.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, status: TaskStatus, request: &str, created_at: &str) -> TaskRunRecord {
+        TaskRunRecord {
+            id: id.to_string(),
+            channel: "imessage".to_string(),
+            sender_key: "alice".to_string(),
+            reply_target: "alice".to_string(),
+            status,
+            original_request: request.to_string(),
+            last_response: Some("Report saved to report.md".to_string()),
+            attempt_count: 1,
+            provider_retry_count: 0,
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+            completed_at: Some(created_at.to_string()),
+            archived_at: None,
+        }
+    }
+
+    #[test]
+    fn near_identical_recent_requests_are_flagged_and_replies_parse() {
+        let now = DateTime::parse_from_rfc3339("2026-03-01T10:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let window = chrono::Duration::minutes(30);
+        let tasks = vec![
+            task(
+                "failed",
+                TaskStatus::Failed,
+                "Summarize the Q3 sales report",
+                "2026-03-01T10:25:00Z",
+            ),
+            task(
+                "recent",
+                TaskStatus::Completed,
+                "Summarize the Q3 sales report.",
+                "2026-03-01T10:20:00Z",
+            ),
+            task(
+                "old",
+                TaskStatus::Completed,
+                "Translate the onboarding guide",
+                "2026-03-01T08:00:00Z",
+            ),
+        ];
+
+        let found = find_duplicate(&tasks, "summarize the Q3 sales report", window, now)
+            .expect("duplicate");
+        assert_eq!(found.id, "recent");
+        assert!(render_duplicate_prompt(found, now, false).contains("10 min"));
+        assert!(find_duplicate(&tasks, "Translate the onboarding guide", window, now).is_none());
+        assert!(find_duplicate(&tasks, "Summarize the Q4 budget", window, now).is_none());
+        assert!(find_duplicate(&tasks, "hi", window, now).is_none());
+
+        let payload = &duplicate_quick_replies("recent")[1].payload;
+        assert_eq!(
+            parse_duplicate_reply(payload),
+            Some(DuplicateReply {
+                choice: DuplicateChoice::ShowPrevious,
+                task_id: Some("recent".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_duplicate_reply("重新执行").map(|reply| reply.choice),
+            Some(DuplicateChoice::RunAgain)
+        );
+        assert_eq!(parse_duplicate_reply("show me the report"), None);
+    }
+
+    /// Maps known phrasings onto fixed directions; anything else is
+    /// orthogonal to them.
+    struct PhraseEmbedding;
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for PhraseEmbedding {
+        fn name(&self) -> &str {
+            "phrases"
+        }
+
+        fn dimensions(&self) -> usize {
+            3
+        }
+
+        async fn embed(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>> {
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    if text.contains("Q3 sales") || text.contains("third-quarter revenue") {
+                        vec![1.0, 0.1, 0.0]
+                    } else if text.contains("onboarding") {
+                        vec![0.0, 1.0, 0.0]
+                    } else {
+                        vec![0.0, 0.0, 1.0]
+                    }
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn reworded_requests_match_by_embedding() {
+        let now = DateTime::parse_from_rfc3339("2026-03-01T10:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let window = chrono::Duration::minutes(30);
+        let tasks = vec![
+            task(
+                "recent",
+                TaskStatus::Completed,
+                "Summarize the Q3 sales report",
+                "2026-03-01T10:20:00Z",
+            ),
+            task(
+                "other",
+                TaskStatus::Completed,
+                "Translate the onboarding guide",
+                "2026-03-01T10:10:00Z",
+            ),
+        ];
+        let reworded = "Give me a short overview of third-quarter revenue";
+        assert!(find_duplicate(&tasks, reworded, window, now).is_none());
+
+        let found = find_duplicate_with_embeddings(&tasks, reworded, window, now, &PhraseEmbedding)
+            .await
+            .expect("reworded duplicate");
+        assert_eq!(found.id, "recent");
+        assert!(find_duplicate_with_embeddings(
+            &tasks,
+            "Book a table for two tonight",
+            window,
+            now,
+            &PhraseEmbedding
+        )
+        .await
+        .is_none());
+    }
+}
@@ -22,7 +22,8 @@ use rustls::{ClientConfig, RootCertStore};
 use rustls_pki_types::DnsName;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::net::TcpStream;
@@ -114,10 +115,29 @@ impl Default for EmailConfig {
 
 type ImapSession = Session<TlsStream<TcpStream>>;
 
+/// Largest attachment saved to the workspace; bigger ones are skipped.
+const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+
 /// Email channel — IMAP IDLE for instant push notifications, SMTP for outbound
 pub struct EmailChannel {
     pub config: EmailConfig,
     seen_messages: Arc<Mutex<HashSet<String>>>,
+    /// Inbound emails by Message-ID, so replies sent in their thread can
+    /// quote them and set `In-Reply-To`.
+    threads: Arc<Mutex<HashMap<String, EmailThread>>>,
+    /// Where attachments are saved (`email_files/` inside it); `None` drops them.
+    workspace_dir: Option<PathBuf>,
+    /// Pass saved images on as `[IMAGE:<path>]` markers; otherwise they are
+    /// referenced like documents.
+    image_markers: bool,
+}
+
+/// What a reply needs to know about the email it answers.
+#[derive(Debug, Clone)]
+struct EmailThread {
+    subject: String,
+    sender: String,
+    body: String,
 }
 
 impl EmailChannel {
@@ -125,9 +145,25 @@ impl EmailChannel {
         Self {
             config,
             seen_messages: Arc::new(Mutex::new(HashSet::new())),
+            threads: Arc::new(Mutex::new(HashMap::new())),
+            workspace_dir: None,
+            image_markers: false,
         }
     }
 
+    /// Send image attachments to the model as `[IMAGE:<path>]` markers. Only
+    /// enable this when the provider supports vision, or such emails fail.
+    pub fn with_image_markers(mut self, enabled: bool) -> Self {
+        self.image_markers = enabled;
+        self
+    }
+
+    /// Configure workspace directory for saving email attachments.
+    pub fn with_workspace_dir(mut self, dir: PathBuf) -> Self {
+        self.workspace_dir = Some(dir);
+        self
+    }
+
     /// Check if a sender email is in the allowlist
     pub fn is_sender_allowed(&self, email: &str) -> bool {
         if self.config.allowed_senders.is_empty() {
@@ -205,6 +241,63 @@ impl EmailChannel {
         "(no readable content)".to_string()
     }
 
+    /// Attachments of a parsed email as (file name, is image, contents).
+    fn extract_attachments(parsed: &mail_parser::Message) -> Vec<EmailAttachment> {
+        parsed
+            .attachments()
+            .enumerate()
+            .filter_map(|(index, part)| {
+                let part: &mail_parser::MessagePart = part;
+                let contents = part.contents();
+                if contents.is_empty() || contents.len() > MAX_ATTACHMENT_BYTES {
+                    return None;
+                }
+                let is_image =
+                    MimeHeaders::content_type(part).is_some_and(|ct| ct.ctype() == "image");
+                let name = MimeHeaders::attachment_name(part)
+                    .map(sanitize_file_name)
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| format!("attachment-{}", index + 1));
+                Some(EmailAttachment {
+                    name,
+                    is_image,
+                    contents: contents.to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    /// Save an email's attachments under `email_files/<message>/` in the
+    /// workspace and return a `[IMAGE:]` (with image markers on) or
+    /// `[Document:]` marker for each.
+    async fn save_attachments(&self, msg_id: &str, attachments: &[EmailAttachment]) -> Vec<String> {
+        if attachments.is_empty() {
+            return Vec::new();
+        }
+        let Some(workspace) = self.workspace_dir.as_ref() else {
+            warn!("Cannot save email attachments: workspace_dir not configured");
+            return Vec::new();
+        };
+        let dir = workspace
+            .join("email_files")
+            .join(sanitize_file_name(msg_id));
+        if let Err(e) = tokio::fs::create_dir_all(&dir).await {
+            warn!("Failed to create {}: {}", dir.display(), e);
+            return Vec::new();
+        }
+
+        let mut markers = Vec::new();
+        for attachment in attachments {
+            let path = dir.join(&attachment.name);
+            if let Err(e) = tokio::fs::write(&path, &attachment.contents).await {
+                warn!("Failed to save email attachment {}: {}", path.display(), e);
+                continue;
+            }
+            markers.push(attachment_marker(attachment, &path, self.image_markers));
+        }
+        markers
+    }
+
     /// Connect to IMAP server with TLS and authenticate
     async fn connect_imap(&self) -> Result<ImapSession> {
         let addr = format!("{}:{}", self.config.imap_host, self.config.imap_port);
@@ -265,7 +358,7 @@ impl EmailChannel {
                     let sender = Self::extract_sender(&parsed);
                     let subject = parsed.subject().unwrap_or("(no subject)").to_string();
                     let body_text = Self::extract_text(&parsed);
-                    let content = format!("Subject: {}\n\n{}", subject, body_text);
+                    let attachments = Self::extract_attachments(&parsed);
                     let msg_id = parsed
                         .message_id()
                         .map(|s| s.to_string())
@@ -300,7 +393,9 @@ impl EmailChannel {
                         _uid: uid,
                         msg_id,
                         sender,
-                        content,
+                        subject,
+                        body: body_text,
+                        attachments,
                         timestamp: ts,
                     });
                 }
@@ -452,14 +547,33 @@ impl EmailChannel {
                 continue;
             }
 
+            let mut content = format!("Subject: {}\n\n{}", email.subject, email.body);
+            let markers = self
+                .save_attachments(&email.msg_id, &email.attachments)
+                .await;
+            if !markers.is_empty() {
+                content.push_str("\n\n");
+                content.push_str(&markers.join("\n"));
+            }
+
+            self.threads.lock().await.insert(
+                email.msg_id.clone(),
+                EmailThread {
+                    subject: email.subject,
+                    sender: email.sender.clone(),
+                    body: email.body,
+                },
+            );
+
+            // The Message-ID doubles as thread id so the reply can find it.
             let msg = ChannelMessage {
-                id: email.msg_id,
+                id: email.msg_id.clone(),
                 reply_target: email.sender.clone(),
                 sender: email.sender,
-                content: email.content,
+                content,
                 channel: "email".to_string(),
                 timestamp: email.timestamp,
-                thread_ts: None,
+                thread_ts: Some(email.msg_id),
             };
 
             if tx.send(msg).await.is_err() {
@@ -493,10 +607,81 @@ struct ParsedEmail {
     _uid: u32,
     msg_id: String,
     sender: String,
-    content: String,
+    subject: String,
+    body: String,
+    attachments: Vec<EmailAttachment>,
     timestamp: u64,
 }
 
+/// An attachment of an inbound email, before it is saved.
+struct EmailAttachment {
+    name: String,
+    is_image: bool,
+    contents: Vec<u8>,
+}
+
+/// Keep a file name (or Message-ID used as a directory name) to one safe
+/// path component.
+fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    cleaned.trim_start_matches('.').to_string()
+}
+
+/// Images become `[IMAGE:]` markers only when `image_markers` is set; without
+/// vision they are referenced by path like any other file.
+fn attachment_marker(attachment: &EmailAttachment, path: &Path, image_markers: bool) -> String {
+    if attachment.is_image && image_markers {
+        format!("[IMAGE:{}]", path.display())
+    } else if attachment.is_image {
+        format!("[image attachment: {}] {}", attachment.name, path.display())
+    } else {
+        format!("[Document: {}] {}", attachment.name, path.display())
+    }
+}
+
+/// `Re: <subject>`, without stacking prefixes on an ongoing thread.
+fn reply_subject(subject: &str) -> String {
+    if subject
+        .get(..3)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("re:"))
+    {
+        subject.to_string()
+    } else {
+        format!("Re: {}", subject)
+    }
+}
+
+/// `body` followed by the original email quoted with `> ` prefixes.
+fn quote_original(body: &str, thread: &EmailThread) -> String {
+    let quoted: Vec<String> = thread
+        .body
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect();
+    format!(
+        "{}\n\n{} wrote:\n{}",
+        body,
+        thread.sender,
+        quoted.join("\n")
+    )
+}
+
 /// Result from waiting on IDLE
 enum IdleWaitResult {
     NewMail,
@@ -511,6 +696,33 @@ impl Channel for EmailChannel {
     }
 
     async fn send(&self, message: &SendMessage) -> Result<()> {
+        // Replies to an inbound email stay in its thread and quote it
+        let thread_id = message.thread_ts.as_deref();
+        let thread = match thread_id {
+            Some(id) => self.threads.lock().await.get(id).cloned(),
+            None => None,
+        };
+        if let (Some(thread), Some(thread_id)) = (thread, thread_id) {
+            let subject = message
+                .subject
+                .clone()
+                .unwrap_or_else(|| reply_subject(&thread.subject));
+            let mut builder = Message::builder()
+                .from(self.config.from_address.parse()?)
+                .to(message.recipient.parse()?)
+                .subject(subject);
+            if !thread_id.starts_with("gen-") {
+                let reference = format!("<{}>", thread_id);
+                builder = builder.in_reply_to(reference.clone()).references(reference);
+            }
+            let email =
+                builder.singlepart(SinglePart::plain(quote_original(&message.content, &thread)))?;
+            let transport = self.create_smtp_transport()?;
+            transport.send(&email)?;
+            info!("Email reply sent to {}", message.recipient);
+            return Ok(());
+        }
+
         // Use explicit subject if provided, otherwise fall back to legacy parsing or default
         let (subject, body) = if let Some(ref subj) = message.subject {
             (subj.as_str(), message.content.as_str())
@@ -974,4 +1186,48 @@ mod tests {
         let debug_str = format!("{:?}", config);
         assert!(debug_str.contains("imap.debug.com"));
     }
+
+    #[test]
+    fn reply_quotes_the_original_under_a_single_re_prefix() {
+        assert_eq!(reply_subject("Quarterly report"), "Re: Quarterly report");
+        assert_eq!(
+            reply_subject("RE: Quarterly report"),
+            "RE: Quarterly report"
+        );
+
+        let thread = EmailThread {
+            subject: "Quarterly report".into(),
+            sender: "alice@example.com".into(),
+            body: "Please summarize it.\n\nThanks".into(),
+        };
+        assert_eq!(
+            quote_original("Done, summary attached.", &thread),
+            "Done, summary attached.\n\nalice@example.com wrote:\n> Please summarize it.\n>\n> Thanks"
+        );
+    }
+
+    #[test]
+    fn attachment_names_stay_inside_their_directory() {
+        assert_eq!(sanitize_file_name("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(sanitize_file_name("report 2024.pdf"), "report 2024.pdf");
+        assert_eq!(sanitize_file_name("<abc@mail.test>"), "_abc_mail.test_");
+    }
+
+    #[test]
+    fn images_are_only_image_markers_when_enabled() {
+        let photo = EmailAttachment {
+            name: "photo.png".into(),
+            is_image: true,
+            contents: Vec::new(),
+        };
+        let path = Path::new("/ws/email_files/m1/photo.png");
+        assert_eq!(
+            attachment_marker(&photo, path, true),
+            "[IMAGE:/ws/email_files/m1/photo.png]"
+        );
+        assert_eq!(
+            attachment_marker(&photo, path, false),
+            "[image attachment: photo.png] /ws/email_files/m1/photo.png"
+        );
+    }
 }
@@ -0,0 +1,202 @@
+//! Fair scheduling for inbound channel messages.
+//!
+//! While every in-flight slot is busy, new messages wait here instead of in
+//! the FIFO inbound channel. Channels are served weighted round-robin (a
+//! channel with weight 3 gets three dispatches per turn, default 1), and the
+//! senders inside a channel are served round-robin one message at a time, so
+//! one chat flooding the bot cannot starve everyone else.
+
+use std::collections::{HashMap, VecDeque};
+
+/// Weight used for channels missing from `channels_config.queue_weights`.
+const DEFAULT_CHANNEL_WEIGHT: u32 = 1;
+
+struct ChannelLane<T> {
+    weight: u32,
+    senders: VecDeque<String>,
+    pending: HashMap<String, VecDeque<T>>,
+}
+
+impl<T> ChannelLane<T> {
+    fn len(&self) -> usize {
+        self.pending.values().map(VecDeque::len).sum()
+    }
+}
+
+pub struct FairQueue<T> {
+    weights: HashMap<String, u32>,
+    channels: VecDeque<String>,
+    lanes: HashMap<String, ChannelLane<T>>,
+    /// Dispatches the front channel has left in its current turn.
+    credit: u32,
+}
+
+impl<T> FairQueue<T> {
+    pub fn new(weights: HashMap<String, u32>) -> Self {
+        Self {
+            weights,
+            channels: VecDeque::new(),
+            lanes: HashMap::new(),
+            credit: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lanes.values().map(ChannelLane::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lanes.is_empty()
+    }
+
+    fn weight_for(&self, channel: &str) -> u32 {
+        self.weights
+            .get(channel)
+            .copied()
+            .unwrap_or(DEFAULT_CHANNEL_WEIGHT)
+            .max(1)
+    }
+
+    pub fn push(&mut self, channel: &str, sender: &str, item: T) {
+        if !self.lanes.contains_key(channel) {
+            let weight = self.weight_for(channel);
+            self.lanes.insert(
+                channel.to_string(),
+                ChannelLane {
+                    weight,
+                    senders: VecDeque::new(),
+                    pending: HashMap::new(),
+                },
+            );
+            self.channels.push_back(channel.to_string());
+            if self.channels.len() == 1 {
+                self.credit = weight;
+            }
+        }
+        let lane = self.lanes.get_mut(channel).expect("lane inserted above");
+        let queue = lane.pending.entry(sender.to_string()).or_default();
+        if queue.is_empty() {
+            lane.senders.push_back(sender.to_string());
+        }
+        queue.push_back(item);
+    }
+
+    /// Next item in fair order.
+    pub fn pop(&mut self) -> Option<T> {
+        let channel = self.channels.front()?.clone();
+        let lane = self.lanes.get_mut(&channel)?;
+        let sender = lane.senders.pop_front()?;
+        let queue = lane.pending.get_mut(&sender)?;
+        let item = queue.pop_front();
+        if queue.is_empty() {
+            lane.pending.remove(&sender);
+        } else {
+            lane.senders.push_back(sender);
+        }
+
+        self.credit = self.credit.saturating_sub(1);
+        if lane.senders.is_empty() {
+            self.lanes.remove(&channel);
+            self.channels.pop_front();
+            self.start_next_turn();
+        } else if self.credit == 0 {
+            self.channels.rotate_left(1);
+            self.start_next_turn();
+        }
+        item
+    }
+
+    fn start_next_turn(&mut self) {
+        self.credit = self
+            .channels
+            .front()
+            .and_then(|channel| self.lanes.get(channel))
+            .map_or(0, |lane| lane.weight);
+    }
+
+    /// 1-based dispatch position of the newest item queued by `sender` on
+    /// `channel`, or `None` when the sender has nothing queued.
+    pub fn position_of_latest(&self, channel: &str, sender: &str) -> Option<usize> {
+        let target = self.lanes.get(channel)?.pending.get(sender)?.len();
+
+        // Replay the scheduler on a copy that holds only (channel, sender).
+        let mut replay = FairQueue {
+            weights: HashMap::new(),
+            channels: self.channels.clone(),
+            credit: self.credit,
+            lanes: self
+                .lanes
+                .iter()
+                .map(|(name, lane)| {
+                    let pending = lane
+                        .pending
+                        .iter()
+                        .map(|(key, items)| {
+                            let tags = items.iter().map(|_| (name.as_str(), key.as_str()));
+                            (key.clone(), tags.collect())
+                        })
+                        .collect();
+                    let lane = ChannelLane {
+                        weight: lane.weight,
+                        senders: lane.senders.clone(),
+                        pending,
+                    };
+                    (name.clone(), lane)
+                })
+                .collect(),
+        };
+
+        let mut seen = 0;
+        let mut position = 0;
+        while let Some((queued_channel, queued_sender)) = replay.pop() {
+            position += 1;
+            if queued_channel == channel && queued_sender == sender {
+                seen += 1;
+                if seen == target {
+                    return Some(position);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(queue: &mut FairQueue<&'static str>) -> Vec<&'static str> {
+        std::iter::from_fn(|| queue.pop()).collect()
+    }
+
+    #[test]
+    fn flooding_sender_does_not_starve_others() {
+        let mut queue = FairQueue::new(HashMap::new());
+        for item in ["a1", "a2", "a3", "a4"] {
+            queue.push("telegram", "alice", item);
+        }
+        queue.push("telegram", "bob", "b1");
+        queue.push("discord", "carol", "c1");
+
+        assert_eq!(queue.position_of_latest("telegram", "bob"), Some(3));
+        assert_eq!(queue.position_of_latest("discord", "carol"), Some(2));
+        assert_eq!(queue.position_of_latest("telegram", "alice"), Some(6));
+        assert_eq!(queue.position_of_latest("slack", "dave"), None);
+        assert_eq!(drain(&mut queue), ["a1", "c1", "b1", "a2", "a3", "a4"]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn channel_weights_grant_extra_dispatches_per_turn() {
+        let mut queue = FairQueue::new(HashMap::from([("slack".to_string(), 2)]));
+        for (sender, item) in [("u1", "s1"), ("u2", "s2"), ("u3", "s3")] {
+            queue.push("slack", sender, item);
+        }
+        queue.push("irc", "nick", "i1");
+        queue.push("irc", "nick", "i2");
+
+        assert_eq!(queue.position_of_latest("irc", "nick"), Some(5));
+        assert_eq!(queue.len(), 5);
+        assert_eq!(drain(&mut queue), ["s1", "s2", "i1", "s3", "i2"]);
+    }
+}
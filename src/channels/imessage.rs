@@ -1,10 +1,26 @@
+use crate::channels::task_reply;
 use crate::channels::traits::{Channel, ChannelMessage, SendMessage};
 use async_trait::async_trait;
 use directories::UserDirs;
-use rusqlite::{Connection, OpenFlags};
-use std::path::Path;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::path::{Path, PathBuf};
 use tokio::sync::mpsc;
 
+/// Image types passed to the multimodal pipeline as `[IMAGE:]`; anything
+/// else (HEIC included) arrives as a `[Document:]` path.
+const IMAGE_MIME_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/bmp",
+];
+
+/// `associated_message_type` of tapbacks; the same value plus 1000 removes one.
+const TAPBACK_LOVE: i64 = 2000;
+const TAPBACK_LIKE: i64 = 2001;
+const TAPBACK_DISLIKE: i64 = 2002;
+
 /// iMessage channel using macOS `AppleScript` bridge.
 /// Polls the Messages database for new messages and sends replies via `osascript`.
 #[derive(Clone)]
@@ -130,6 +146,50 @@ end tell"#
         Ok(())
     }
 
+    async fn send_file(
+        &self,
+        recipient: &str,
+        _thread_ts: Option<&str>,
+        path: &Path,
+        caption: Option<&str>,
+    ) -> anyhow::Result<()> {
+        if !is_valid_imessage_target(recipient) {
+            anyhow::bail!(
+                "Invalid iMessage target: must be a phone number (+1234567890) or email (user@example.com)"
+            );
+        }
+        let path = path
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("iMessage attachment path is not valid UTF-8"))?;
+
+        // SECURITY: the path is escaped like message text (CWE-78)
+        let escaped_path = escape_applescript(path);
+        let escaped_target = escape_applescript(recipient);
+        let script = format!(
+            r#"tell application "Messages"
+    set targetService to 1st account whose service type = iMessage
+    set targetBuddy to participant "{escaped_target}" of targetService
+    send (POSIX file "{escaped_path}") to targetBuddy
+end tell"#
+        );
+
+        let output = tokio::process::Command::new("osascript")
+            .arg("-e")
+            .arg(&script)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("iMessage attachment send failed: {stderr}");
+        }
+
+        if let Some(caption) = caption {
+            self.send(&SendMessage::new(caption, recipient)).await?;
+        }
+        Ok(())
+    }
+
     async fn listen(&self, tx: mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
         tracing::info!("iMessage channel listening (AppleScript bridge)...");
 
@@ -177,28 +237,7 @@ end tell"#
             let since = last_rowid;
             let (returned_conn, poll_result) = tokio::task::spawn_blocking(
                 move || -> (Connection, anyhow::Result<Vec<(i64, String, String)>>) {
-                    let result = (|| -> anyhow::Result<Vec<(i64, String, String)>> {
-                        let mut stmt = conn.prepare(
-                            "SELECT m.ROWID, h.id, m.text \
-                     FROM message m \
-                     JOIN handle h ON m.handle_id = h.ROWID \
-                     WHERE m.ROWID > ?1 \
-                     AND m.is_from_me = 0 \
-                     AND m.text IS NOT NULL \
-                     ORDER BY m.ROWID ASC \
-                     LIMIT 20",
-                        )?;
-                        let rows = stmt.query_map([since], |row| {
-                            Ok((
-                                row.get::<_, i64>(0)?,
-                                row.get::<_, String>(1)?,
-                                row.get::<_, String>(2)?,
-                            ))
-                        })?;
-                        let results = rows.collect::<Result<Vec<_>, _>>()?;
-                        Ok(results)
-                    })();
-
+                    let result = query_new_messages(&conn, since);
                     (conn, result)
                 },
             )
@@ -290,29 +329,154 @@ async fn fetch_new_messages(
                 &path,
                 OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
             )?;
-            let mut stmt = conn.prepare(
-                "SELECT m.ROWID, h.id, m.text \
-             FROM message m \
-             JOIN handle h ON m.handle_id = h.ROWID \
-             WHERE m.ROWID > ?1 \
-             AND m.is_from_me = 0 \
-             AND m.text IS NOT NULL \
-             ORDER BY m.ROWID ASC \
-             LIMIT 20",
-            )?;
-            let rows = stmt.query_map([since_rowid], |row| {
-                Ok((
-                    row.get::<_, i64>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                ))
-            })?;
-            rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+            query_new_messages(&conn, since_rowid)
         })
         .await??;
     Ok(results)
 }
 
+/// Incoming messages after `since_rowid` as `(rowid, sender, content)`.
+///
+/// Attachments become `[IMAGE:]` / `[Document:]` markers ahead of the text.
+/// A thumbs-up or heart on one of our blocked-task prompts becomes
+/// "continue", a thumbs-down "cancel"; other tapbacks yield empty content so
+/// the caller skips them but still advances past their ROWID.
+fn query_new_messages(
+    conn: &Connection,
+    since_rowid: i64,
+) -> anyhow::Result<Vec<(i64, String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT m.ROWID, h.id, m.text, m.associated_message_type, \
+                m.associated_message_guid, m.cache_has_attachments \
+         FROM message m \
+         JOIN handle h ON m.handle_id = h.ROWID \
+         WHERE m.ROWID > ?1 \
+         AND m.is_from_me = 0 \
+         AND (m.text IS NOT NULL OR m.cache_has_attachments = 1) \
+         ORDER BY m.ROWID ASC \
+         LIMIT 20",
+    )?;
+    let rows = stmt
+        .query_map([since_rowid], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<i64>>(5)?.unwrap_or(0) != 0,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut messages = Vec::with_capacity(rows.len());
+    for (rowid, sender, text, tapback, tapback_target, has_attachments) in rows {
+        let content = if tapback != 0 {
+            match tapback_target.as_deref() {
+                Some(target) => tapback_reply(conn, tapback, target)?
+                    .unwrap_or_default()
+                    .to_string(),
+                None => String::new(),
+            }
+        } else {
+            let text = text.unwrap_or_default().replace('\u{FFFC}', "");
+            let markers = if has_attachments {
+                attachment_markers(conn, rowid)?
+            } else {
+                Vec::new()
+            };
+            match (markers.is_empty(), text.trim().is_empty()) {
+                (true, _) => text,
+                (false, true) => markers.join("\n"),
+                (false, false) => format!("{}\n\n{}", markers.join("\n"), text.trim()),
+            }
+        };
+        messages.push((rowid, sender, content));
+    }
+    Ok(messages)
+}
+
+/// Keyword a tapback stands for, when it reacts to one of our blocked-task
+/// prompts. `target_guid` is `associated_message_guid` (`p:0/<guid>`,
+/// `bp:<guid>`, or a bare guid).
+fn tapback_reply(
+    conn: &Connection,
+    tapback: i64,
+    target_guid: &str,
+) -> anyhow::Result<Option<&'static str>> {
+    let keyword = match tapback {
+        TAPBACK_LOVE | TAPBACK_LIKE => "continue",
+        TAPBACK_DISLIKE => "cancel",
+        _ => return Ok(None),
+    };
+    let guid = target_guid
+        .rsplit_once('/')
+        .map(|(_, guid)| guid)
+        .or_else(|| target_guid.strip_prefix("bp:"))
+        .unwrap_or(target_guid);
+    let target: Option<String> = conn
+        .query_row(
+            "SELECT text FROM message WHERE guid = ?1 AND is_from_me = 1",
+            params![guid],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    Ok(target
+        .is_some_and(|text| text.contains(task_reply::blocked_task_text_hint()))
+        .then_some(keyword))
+}
+
+/// `[IMAGE:<path>]` or `[Document: <name>] <path>` for each attachment of a
+/// message, in the order they were sent.
+fn attachment_markers(conn: &Connection, message_rowid: i64) -> anyhow::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT a.filename, a.mime_type, a.transfer_name \
+         FROM attachment a \
+         JOIN message_attachment_join j ON j.attachment_id = a.ROWID \
+         WHERE j.message_id = ?1 \
+         ORDER BY a.ROWID ASC",
+    )?;
+    let rows = stmt.query_map([message_rowid], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, Option<String>>(2)?,
+        ))
+    })?;
+    let mut markers = Vec::new();
+    for row in rows {
+        let (filename, mime_type, transfer_name) = row?;
+        // Attachments still downloading have no file yet.
+        let Some(filename) = filename else {
+            continue;
+        };
+        let path = expand_home(&filename);
+        let is_image = mime_type
+            .as_deref()
+            .is_some_and(|mime| IMAGE_MIME_TYPES.contains(&mime.to_ascii_lowercase().as_str()));
+        if is_image {
+            markers.push(format!("[IMAGE:{}]", path.display()));
+        } else {
+            let name = transfer_name.unwrap_or_else(|| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+            markers.push(format!("[Document: {name}] {}", path.display()));
+        }
+    }
+    Ok(markers)
+}
+
+/// Messages stores attachment paths as `~/Library/Messages/Attachments/...`.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), UserDirs::new()) {
+        (Some(rest), Some(dirs)) => dirs.home_dir().join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -606,10 +770,24 @@ mod tests {
             );
             CREATE TABLE message (
                 ROWID INTEGER PRIMARY KEY,
+                guid TEXT,
                 handle_id INTEGER,
                 text TEXT,
                 is_from_me INTEGER DEFAULT 0,
+                associated_message_type INTEGER DEFAULT 0,
+                associated_message_guid TEXT,
+                cache_has_attachments INTEGER DEFAULT 0,
                 FOREIGN KEY (handle_id) REFERENCES handle(ROWID)
+            );
+            CREATE TABLE attachment (
+                ROWID INTEGER PRIMARY KEY,
+                filename TEXT,
+                mime_type TEXT,
+                transfer_name TEXT
+            );
+            CREATE TABLE message_attachment_join (
+                message_id INTEGER,
+                attachment_id INTEGER
             );",
         )
         .unwrap();
@@ -936,6 +1114,65 @@ mod tests {
         assert_eq!(result[0].2, "");
     }
 
+    #[tokio::test]
+    async fn fetch_new_messages_ingests_attachments_and_approval_tapbacks() {
+        let (_dir, db_path) = create_test_db();
+
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            let prompt = format!(
+                "任务已阻塞：need approval\n\n{}",
+                task_reply::blocked_task_text_hint()
+            );
+            conn.execute_batch(
+                "INSERT INTO handle (ROWID, id) VALUES (1, '+1234567890');
+                 INSERT INTO message (ROWID, handle_id, text, is_from_me, cache_has_attachments)
+                   VALUES (10, 1, '\u{FFFC}What is this?', 0, 1);
+                 INSERT INTO attachment (ROWID, filename, mime_type, transfer_name)
+                   VALUES (1, '/tmp/Attachments/IMG_1.jpeg', 'image/jpeg', 'IMG_1.jpeg'),
+                          (2, '/tmp/Attachments/report.pdf', 'application/pdf', 'report.pdf');
+                 INSERT INTO message_attachment_join (message_id, attachment_id)
+                   VALUES (10, 1), (10, 2);
+                 INSERT INTO message (ROWID, handle_id, text, is_from_me, cache_has_attachments)
+                   VALUES (11, 1, NULL, 0, 1);
+                 INSERT INTO message_attachment_join (message_id, attachment_id) VALUES (11, 1);
+                 INSERT INTO message (ROWID, guid, handle_id, text, is_from_me)
+                   VALUES (12, 'plain-guid', 1, 'hello', 1);",
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO message (ROWID, guid, handle_id, text, is_from_me) VALUES (13, 'prompt-guid', 1, ?1, 1)",
+                [prompt],
+            )
+            .unwrap();
+            conn.execute_batch(
+                "INSERT INTO message (ROWID, handle_id, text, is_from_me, associated_message_type, associated_message_guid)
+                   VALUES (20, 1, 'Liked a message', 0, 2001, 'p:0/prompt-guid'),
+                          (21, 1, 'Disliked a message', 0, 2002, 'bp:prompt-guid'),
+                          (22, 1, 'Liked a message', 0, 2001, 'p:0/plain-guid'),
+                          (23, 1, 'Laughed at a message', 0, 2003, 'p:0/prompt-guid');",
+            )
+            .unwrap();
+        }
+
+        let result = fetch_new_messages(&db_path, 0).await.unwrap();
+        let contents: Vec<&str> = result
+            .iter()
+            .map(|(_, _, content)| content.as_str())
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                "[IMAGE:/tmp/Attachments/IMG_1.jpeg]\n[Document: report.pdf] /tmp/Attachments/report.pdf\n\nWhat is this?",
+                "[IMAGE:/tmp/Attachments/IMG_1.jpeg]",
+                "continue",
+                "cancel",
+                "",
+                "",
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn fetch_new_messages_negative_rowid_edge_case() {
         let (_dir, db_path) = create_test_db();
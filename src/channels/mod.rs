@@ -16,9 +16,12 @@
 
 pub mod clawdtalk;
 pub mod cli;
+pub mod conversation_undo;
 pub mod dingtalk;
 pub mod discord;
+pub mod duplicate_task;
 pub mod email_channel;
+pub mod fair_queue;
 pub mod imessage;
 pub mod irc;
 #[cfg(feature = "channel-lark")]
@@ -29,10 +32,16 @@ pub mod matrix;
 pub mod mattermost;
 pub mod nextcloud_talk;
 pub mod nostr;
+pub mod pinned;
 pub mod qq;
+pub mod sender_settings;
 pub mod signal;
 pub mod slack;
+pub mod task_reply;
+pub mod task_status;
 pub mod telegram;
+pub mod tool_exclusions;
+pub mod tool_prompt;
 pub mod traits;
 pub mod transcription;
 pub mod wati;
@@ -61,13 +70,15 @@ pub use qq::QQChannel;
 pub use signal::SignalChannel;
 pub use slack::SlackChannel;
 pub use telegram::TelegramChannel;
-pub use traits::{Channel, SendMessage};
+pub use traits::{Channel, QuickReply, SendMessage};
 pub use wati::WatiChannel;
 pub use whatsapp::WhatsAppChannel;
 #[cfg(feature = "whatsapp-web")]
 pub use whatsapp_web::WhatsAppWebChannel;
 
-use crate::agent::loop_::{build_tool_instructions, run_tool_call_loop, scrub_credentials};
+use crate::agent::loop_::{
+    build_tool_instructions_filtered, run_tool_call_loop, scrub_credentials,
+};
 use crate::config::Config;
 use crate::identity;
 use crate::memory::{self, Memory};
@@ -113,6 +124,17 @@ const CHANNEL_MIN_IN_FLIGHT_MESSAGES: usize = 8;
 const CHANNEL_MAX_IN_FLIGHT_MESSAGES: usize = 64;
 const CHANNEL_TYPING_REFRESH_INTERVAL_SECS: u64 = 4;
 const CHANNEL_HEALTH_HEARTBEAT_SECS: u64 = 30;
+const TASK_STORE_GAUGE_INTERVAL_SECS: u64 = 60;
+/// Sweeps for archiving and cold storage after the one at startup.
+const TASK_STORE_ARCHIVE_INTERVAL_SECS: u64 = 60 * 60;
+/// Task outbox polling interval and per-tick batch size.
+const TASK_OUTBOX_INTERVAL_SECS: u64 = 30;
+const TASK_OUTBOX_BATCH: usize = 20;
+/// Backoff for a queued reply the channel rejected: doubles per attempt.
+const TASK_OUTBOX_RETRY_BASE_SECS: u64 = 30;
+const TASK_OUTBOX_RETRY_MAX_SECS: u64 = 60 * 60;
+/// Failed attempts after which a queued reply is dead-lettered.
+const TASK_OUTBOX_MAX_ATTEMPTS: u32 = 10;
 const MODEL_CACHE_FILE: &str = "models_cache.json";
 const MODEL_CACHE_PREVIEW_LIMIT: usize = 10;
 const MEMORY_CONTEXT_MAX_ENTRIES: usize = 4;
@@ -209,6 +231,8 @@ struct ChannelRuntimeContext {
     temperature: f64,
     auto_save_memory: bool,
     max_tool_iterations: usize,
+    /// Tool calls from one model response run at once (`agent.parallel_tools`).
+    max_parallel_tools: usize,
     min_relevance_score: f64,
     conversation_histories: ConversationHistoryMap,
     provider_cache: ProviderCacheMap,
@@ -223,7 +247,16 @@ struct ChannelRuntimeContext {
     multimodal: crate::config::MultimodalConfig,
     hooks: Option<Arc<crate::hooks::HookRunner>>,
     non_cli_excluded_tools: Arc<Vec<String>>,
+    tool_exclusions: Arc<tool_exclusions::ToolExclusionPolicy>,
+    /// `[autonomy.tool_policy]`, checked for every tool call on the channel.
+    tool_policy: Arc<crate::security::ToolPolicy>,
     task_engine: Option<Arc<crate::agent::task_engine::TaskEngine>>,
+    /// `[query_classification]` rules plus `[[model_routes]]`: picks the
+    /// hint, and for unpinned senders the provider/model, of each message.
+    model_router: crate::agent::model_router::ModelRouter,
+    /// `[memory]`, whose response cache wraps every provider created here
+    /// as it wraps the default one.
+    memory_config: Arc<crate::config::MemoryConfig>,
 }
 
 #[derive(Clone)]
@@ -766,6 +799,21 @@ fn append_sender_turn(ctx: &ChannelRuntimeContext, sender_key: &str, turn: ChatM
     }
 }
 
+/// Drop the sender's last exchange from the conversation cache and return the
+/// removed turns.
+fn undo_last_exchange(ctx: &ChannelRuntimeContext, sender_key: &str) -> Option<Vec<ChatMessage>> {
+    let mut histories = ctx
+        .conversation_histories
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let turns = histories.get_mut(sender_key)?;
+    let removed = conversation_undo::split_last_exchange(turns);
+    if turns.is_empty() {
+        histories.remove(sender_key);
+    }
+    removed
+}
+
 fn rollback_orphan_user_turn(
     ctx: &ChannelRuntimeContext,
     sender_key: &str,
@@ -844,21 +892,37 @@ fn load_cached_model_preview(workspace_dir: &Path, provider_name: &str) -> Vec<S
         .unwrap_or_default()
 }
 
+/// The provider named `provider_name`, created on first use. `api_key`
+/// overrides the channels' key (e.g. a `[[model_routes]]` key); providers
+/// with their own key are cached apart from the shared-key one.
 async fn get_or_create_provider(
     ctx: &ChannelRuntimeContext,
     provider_name: &str,
+    api_key: Option<&str>,
 ) -> anyhow::Result<Arc<dyn Provider>> {
+    let api_key = api_key.map(str::trim).filter(|key| !key.is_empty());
+    let cache_key = match api_key {
+        Some(key) => {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            std::hash::Hash::hash(key, &mut hasher);
+            format!(
+                "{provider_name}#{:016x}",
+                std::hash::Hasher::finish(&hasher)
+            )
+        }
+        None => provider_name.to_string(),
+    };
     if let Some(existing) = ctx
         .provider_cache
         .lock()
         .unwrap_or_else(|e| e.into_inner())
-        .get(provider_name)
+        .get(&cache_key)
         .cloned()
     {
         return Ok(existing);
     }
 
-    if provider_name == ctx.default_provider.as_str() {
+    if api_key.is_none() && provider_name == ctx.default_provider.as_str() {
         return Ok(Arc::clone(&ctx.provider));
     }
 
@@ -871,13 +935,19 @@ async fn get_or_create_provider(
 
     let provider = create_resilient_provider_nonblocking(
         provider_name,
-        ctx.api_key.clone(),
+        api_key
+            .map(ToString::to_string)
+            .or_else(|| ctx.api_key.clone()),
         api_url.map(ToString::to_string),
         ctx.reliability.as_ref().clone(),
         ctx.provider_runtime_options.clone(),
     )
     .await?;
-    let provider: Arc<dyn Provider> = Arc::from(provider);
+    let provider: Arc<dyn Provider> = Arc::from(providers::caching::with_response_cache(
+        provider,
+        &ctx.memory_config,
+        &ctx.workspace_dir,
+    ));
 
     if let Err(err) = provider.warmup().await {
         tracing::warn!(provider = provider_name, "Provider warmup failed: {err}");
@@ -885,11 +955,35 @@ async fn get_or_create_provider(
 
     let mut cache = ctx.provider_cache.lock().unwrap_or_else(|e| e.into_inner());
     let cached = cache
-        .entry(provider_name.to_string())
+        .entry(cache_key)
         .or_insert_with(|| Arc::clone(&provider));
     Ok(Arc::clone(cached))
 }
 
+/// Providers for the task engine's failover chain. Specs that match the
+/// active route or fail to initialize are skipped.
+async fn task_fallback_providers(
+    ctx: &ChannelRuntimeContext,
+    specs: &[crate::config::ProviderSpec],
+    route: &ChannelRouteSelection,
+) -> Vec<(crate::config::ProviderSpec, Arc<dyn Provider>)> {
+    let mut fallbacks = Vec::new();
+    for spec in specs {
+        if spec.provider == route.provider && spec.model == route.model {
+            continue;
+        }
+        let provider = get_or_create_provider(ctx, &spec.provider, spec.api_key.as_deref()).await;
+        match provider {
+            Ok(provider) => fallbacks.push((spec.clone(), provider)),
+            Err(err) => tracing::warn!(
+                provider = spec.provider.as_str(),
+                "Ignoring task fallback provider that failed to initialize: {err}"
+            ),
+        }
+    }
+    fallbacks
+}
+
 async fn create_resilient_provider_nonblocking(
     provider_name: &str,
     api_key: Option<String>,
@@ -986,26 +1080,28 @@ async fn handle_runtime_command_if_needed(
         ChannelRuntimeCommand::ShowProviders => build_providers_help_response(&current),
         ChannelRuntimeCommand::SetProvider(raw_provider) => {
             match resolve_provider_alias(&raw_provider) {
-                Some(provider_name) => match get_or_create_provider(ctx, &provider_name).await {
-                    Ok(_) => {
-                        if provider_name != current.provider {
-                            current.provider = provider_name.clone();
-                            set_route_selection(ctx, &sender_key, current.clone());
-                            clear_sender_history(ctx, &sender_key);
-                        }
-
-                        format!(
+                Some(provider_name) => {
+                    match get_or_create_provider(ctx, &provider_name, None).await {
+                        Ok(_) => {
+                            if provider_name != current.provider {
+                                current.provider = provider_name.clone();
+                                set_route_selection(ctx, &sender_key, current.clone());
+                                clear_sender_history(ctx, &sender_key);
+                            }
+
+                            format!(
                             "Provider switched to `{provider_name}` for this sender session. Current model is `{}`.\nUse `/model <model-id>` to set a provider-compatible model.",
                             current.model
                         )
-                    }
-                    Err(err) => {
-                        let safe_err = providers::sanitize_api_error(&err.to_string());
-                        format!(
+                        }
+                        Err(err) => {
+                            let safe_err = providers::sanitize_api_error(&err.to_string());
+                            format!(
                             "Failed to initialize provider `{provider_name}`. Route unchanged.\nDetails: {safe_err}"
                         )
+                        }
                     }
-                },
+                }
                 None => format!(
                     "Unknown provider `{raw_provider}`. Use `/models` to list valid providers."
                 ),
@@ -1538,11 +1634,78 @@ async fn process_channel_message(
     if handle_runtime_command_if_needed(ctx.as_ref(), &msg, target_channel.as_ref()).await {
         return;
     }
+    if handle_sender_settings_command_if_needed(ctx.as_ref(), &msg, target_channel.as_ref()).await {
+        return;
+    }
+    if handle_undo_command_if_needed(ctx.as_ref(), &msg, target_channel.as_ref()).await {
+        return;
+    }
+    if handle_pin_command_if_needed(ctx.as_ref(), &msg, target_channel.as_ref()).await {
+        return;
+    }
+    let (msg, resumed_task_id) =
+        match handle_blocked_task_reply(ctx.as_ref(), msg, target_channel.as_ref()).await {
+            BlockedTaskReplyOutcome::Passthrough(msg) => (msg, None),
+            BlockedTaskReplyOutcome::Resume { msg, task_id } => (msg, Some(task_id)),
+            BlockedTaskReplyOutcome::Handled => return,
+        };
+    if handle_task_status_query_if_needed(ctx.as_ref(), &msg, target_channel.as_ref()).await {
+        return;
+    }
+    if handle_task_search_if_needed(ctx.as_ref(), &msg, target_channel.as_ref()).await {
+        return;
+    }
+    let (msg, bypass_completion) = take_raw_mode_command(ctx.as_ref(), msg);
+    let msg = if bypass_completion || resumed_task_id.is_some() {
+        msg
+    } else {
+        match handle_duplicate_task_check(ctx.as_ref(), msg, target_channel.as_ref()).await {
+            BlockedTaskReplyOutcome::Passthrough(msg)
+            | BlockedTaskReplyOutcome::Resume { msg, .. } => msg,
+            BlockedTaskReplyOutcome::Handled => return,
+        }
+    };
 
     let history_key = conversation_history_key(&msg);
-    let route = get_route_selection(ctx.as_ref(), &history_key);
+    let settings = load_sender_settings(ctx.as_ref(), &msg);
+    let mut route = get_route_selection(ctx.as_ref(), &history_key);
+    let has_session_override = ctx
+        .route_overrides
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains_key(&history_key);
+    // An explicit `/model` switch in this session wins over the stored setting.
+    if let Some(model) = settings.model.as_ref() {
+        if !has_session_override {
+            route.model.clone_from(model);
+        }
+    }
+    let classifier_hint = ctx.model_router.classify(&msg.content);
+    // Classification only routes senders who have not picked a model themselves.
+    let routed = classifier_hint
+        .as_deref()
+        .filter(|_| !has_session_override && settings.model.is_none())
+        .and_then(|hint| ctx.model_router.route_for_hint(hint));
+    if let Some(routed) = routed.as_ref() {
+        tracing::info!(
+            hint = routed.hint.as_str(),
+            provider = routed.provider.as_str(),
+            model = routed.model.as_str(),
+            "Routing message by classification"
+        );
+        route = ChannelRouteSelection {
+            provider: routed.provider.clone(),
+            model: routed.model.clone(),
+        };
+    }
     let runtime_defaults = runtime_defaults_snapshot(ctx.as_ref());
-    let active_provider = match get_or_create_provider(ctx.as_ref(), &route.provider).await {
+    let temperature = routed
+        .and_then(|routed| routed.temperature)
+        .unwrap_or(runtime_defaults.temperature);
+    let route_api_key = routed.as_ref().and_then(|routed| routed.api_key.as_deref());
+    let active_provider = match get_or_create_provider(ctx.as_ref(), &route.provider, route_api_key)
+        .await
+    {
         Ok(provider) => provider,
         Err(err) => {
             let safe_err = providers::sanitize_api_error(&err.to_string());
@@ -1609,9 +1772,33 @@ async fn process_channel_message(
         }
     }
 
-    let system_prompt = build_channel_system_prompt(ctx.system_prompt.as_str(), &msg.channel);
+    let mut system_prompt = build_channel_system_prompt(ctx.system_prompt.as_str(), &msg.channel);
+    if let Some(instruction) = settings.language_instruction() {
+        system_prompt = format!("{system_prompt}\n\n{instruction}");
+    }
+    if let Some(pinned) = pinned::render_pinned_block(
+        &load_pinned_instructions(ctx.as_ref(), &msg, &history_key).await,
+    ) {
+        system_prompt = format!("{system_prompt}\n\n{pinned}");
+    }
     let mut history = vec![ChatMessage::system(system_prompt)];
     history.extend(prior_turns);
+    let mut excluded_tools = if msg.channel == "cli" {
+        Vec::new()
+    } else {
+        ctx.tool_exclusions.excluded_tools(
+            ctx.non_cli_excluded_tools.as_ref(),
+            &msg.sender,
+            classifier_hint.as_deref(),
+        )
+    };
+    // Tools the sender turned off with `/tools disable` on this channel stay off,
+    // the CLI included; settings are kept per (channel, sender).
+    for tool in &settings.disabled_tools {
+        if !excluded_tools.contains(tool) {
+            excluded_tools.push(tool.clone());
+        }
+    }
     let use_streaming = target_channel
         .as_ref()
         .is_some_and(|ch| ch.supports_draft_updates());
@@ -1705,6 +1892,12 @@ async fn process_channel_message(
 
     struct ChannelLlmOutcome {
         response: String,
+        /// Set when the task engine stopped in `Blocked`, so the reply can
+        /// offer continue/cancel actions.
+        blocked_task_id: Option<String>,
+        /// Outbox row holding a completed task reply, acknowledged once this
+        /// handler delivers it.
+        outbox_id: Option<i64>,
     }
 
     enum LlmExecutionResult {
@@ -1714,36 +1907,68 @@ async fn process_channel_message(
 
     let timeout_budget_secs =
         channel_message_timeout_budget_secs(ctx.message_timeout_secs, ctx.max_tool_iterations);
+    // `send_file` calls during this reply upload to the conversation it answers.
+    let reply_route = target_channel
+        .as_ref()
+        .map(|channel| crate::tools::send_file::ReplyRoute {
+            channel: Arc::clone(channel),
+            recipient: msg.reply_target.clone(),
+            thread_ts: msg.thread_ts.clone(),
+        });
     let llm_result = tokio::select! {
         () = cancellation_token.cancelled() => LlmExecutionResult::Cancelled,
         result = tokio::time::timeout(
             Duration::from_secs(timeout_budget_secs),
-            async {
-                if msg.channel == "imessage" {
+            crate::tools::send_file::scope(reply_route, async {
+                if is_task_engine_channel(&msg.channel) {
                     if let Some(engine) = ctx.task_engine.as_ref() {
+                        let fallbacks = task_fallback_providers(
+                            ctx.as_ref(),
+                            &engine.config().fallback_providers,
+                            &route,
+                        )
+                        .await;
+                        let progress_digest = Arc::new(Mutex::new(Vec::<String>::new()));
                         let progress_reporter: Option<crate::agent::task_engine::TaskProgressReporter> =
-                            target_channel.as_ref().map(|channel| {
-                                let channel = Arc::clone(channel);
-                                let reply_target = msg.reply_target.clone();
-                                let thread_ts = msg.thread_ts.clone();
+                            if !settings.verbose {
+                                None
+                            } else if settings.notifications
+                                == sender_settings::NotificationMode::Digest
+                            {
+                                let digest = Arc::clone(&progress_digest);
                                 let reporter: crate::agent::task_engine::TaskProgressReporter =
                                     Arc::new(move |progress: String| {
-                                        println!("  🤖 [progress][imessage]: {}", progress);
-                                        let channel = Arc::clone(&channel);
-                                        let reply_target = reply_target.clone();
-                                        let thread_ts = thread_ts.clone();
-                                        tokio::spawn(async move {
-                                            let _ = channel
-                                            .send(
-                                                &SendMessage::new(progress, &reply_target)
-                                                    .in_thread(thread_ts),
-                                            )
-                                            .await;
-                                        });
+                                        digest
+                                            .lock()
+                                            .unwrap_or_else(|e| e.into_inner())
+                                            .push(progress);
                                     });
-                                reporter
-                            });
-                        let req = crate::agent::task_engine::TaskRunRequest {
+                                Some(reporter)
+                            } else {
+                                target_channel.as_ref().map(|channel| {
+                                    let channel = Arc::clone(channel);
+                                    let reply_target = msg.reply_target.clone();
+                                    let thread_ts = msg.thread_ts.clone();
+                                    let channel_name = msg.channel.clone();
+                                    let reporter: crate::agent::task_engine::TaskProgressReporter =
+                                        Arc::new(move |progress: String| {
+                                            println!("  🤖 [progress][{channel_name}]: {progress}");
+                                            let channel = Arc::clone(&channel);
+                                            let reply_target = reply_target.clone();
+                                            let thread_ts = thread_ts.clone();
+                                            tokio::spawn(async move {
+                                                let _ = channel
+                                                .send(
+                                                    &SendMessage::new(progress, &reply_target)
+                                                        .in_thread(thread_ts),
+                                                )
+                                                .await;
+                                            });
+                                        });
+                                    reporter
+                                })
+                            };
+                        let mut req = crate::agent::task_engine::TaskRunRequest {
                             channel: msg.channel.as_str(),
                             sender_key: msg.sender.as_str(),
                             reply_target: msg.reply_target.as_str(),
@@ -1754,55 +1979,128 @@ async fn process_channel_message(
                             observer: ctx.observer.as_ref(),
                             provider_name: route.provider.as_str(),
                             model: route.model.as_str(),
-                            temperature: runtime_defaults.temperature,
+                            temperature,
                             multimodal: &ctx.multimodal,
                             max_tool_iterations: ctx.max_tool_iterations,
                             cancellation_token: Some(cancellation_token.clone()),
                             on_delta: delta_tx.clone(),
                             hooks: ctx.hooks.as_deref(),
-                            excluded_tools: if msg.channel == "cli" {
-                                &[]
-                            } else {
-                                ctx.non_cli_excluded_tools.as_ref()
-                            },
+                            excluded_tools: &excluded_tools,
                             progress_reporter,
+                            labels: classifier_hint
+                                .iter()
+                                .map(|hint| format!("hint:{hint}"))
+                                .collect(),
+                            bypass_completion,
+                            output_format: None,
+                            env: HashMap::new(),
+                            fallback_providers: fallbacks
+                                .iter()
+                                .map(|(spec, provider)| {
+                                    crate::agent::task_engine::ProviderFallback {
+                                        provider: provider.as_ref(),
+                                        provider_name: spec.provider.as_str(),
+                                        model: spec.model.as_str(),
+                                    }
+                                })
+                                .collect(),
+                            steps: Vec::new(),
+                            provider_params: crate::config::ProviderParams::default(),
+                        };
+                        let resume_task_id = resumed_task_id
+                            .as_deref()
+                            .or_else(|| msg.id.strip_prefix(TASK_RESUME_MESSAGE_PREFIX));
+                        let outcome = match resume_task_id {
+                            Some(task_id) => engine.run_existing_task(task_id, &mut req).await?,
+                            None => {
+                                let task_id = engine.start_task(&req)?;
+                                // Channels with buttons get one that cancels the run.
+                                if let Some(channel) = target_channel
+                                    .as_ref()
+                                    .filter(|channel| channel.supports_quick_replies())
+                                {
+                                    let notice =
+                                        SendMessage::new("🧠 任务已开始。", &msg.reply_target)
+                                            .in_thread(msg.thread_ts.clone())
+                                            .with_quick_replies(
+                                                task_reply::running_task_quick_replies(&task_id),
+                                            );
+                                    if let Err(err) = channel.send(&notice).await {
+                                        tracing::debug!(
+                                            "Failed to send task cancel button: {err}"
+                                        );
+                                    }
+                                }
+                                engine.run_existing_task(&task_id, &mut req).await?
+                            }
                         };
-                        let outcome =
-                            crate::agent::task_engine::TaskEngine::run_task(req, engine.as_ref())
-                                .await?;
+                        let progress = std::mem::take(
+                            &mut *progress_digest.lock().unwrap_or_else(|e| e.into_inner()),
+                        );
+                        let artifacts = engine
+                            .store()
+                            .list_artifacts(&outcome.task_id)
+                            .unwrap_or_default();
+                        let final_response =
+                            match crate::agent::artifact_preview::render_artifact_summary(
+                                &artifacts,
+                            ) {
+                                Some(summary) => format!("{}\n\n{summary}", outcome.final_response),
+                                None => outcome.final_response.clone(),
+                            };
                         return Ok(ChannelLlmOutcome {
-                            response: outcome.final_response,
+                            blocked_task_id: (outcome.status
+                                == crate::agent::task_types::TaskStatus::Blocked)
+                                .then(|| outcome.task_id.clone()),
+                            outbox_id: outcome.outbox_id,
+                            response: sender_settings::prepend_progress_digest(
+                                &progress,
+                                &final_response,
+                            ),
                         });
                     }
                 }
 
-                let response = run_tool_call_loop(
-                    active_provider.as_ref(),
-                    &mut history,
-                    ctx.tools_registry.as_ref(),
-                    ctx.observer.as_ref(),
-                    route.provider.as_str(),
-                    route.model.as_str(),
-                    runtime_defaults.temperature,
-                    true,
-                    None,
-                    msg.channel.as_str(),
-                    &ctx.multimodal,
-                    ctx.max_tool_iterations,
-                    Some(cancellation_token.clone()),
-                    delta_tx.clone(),
-                    ctx.hooks.as_deref(),
-                    if msg.channel == "cli" {
-                        &[]
-                    } else {
-                        ctx.non_cli_excluded_tools.as_ref()
-                    },
+                // Outside the task engine nothing can wait for an approval,
+                // so calls needing one are denied.
+                let policy_scope = crate::security::tool_policy::PolicyScope {
+                    policy: Arc::clone(&ctx.tool_policy),
+                    channel: msg.channel.clone(),
+                    sender: msg.sender.clone(),
+                    workspace_dir: ctx.workspace_dir.as_ref().clone(),
+                    approved: HashSet::new(),
+                    confirmable: false,
+                };
+                let response = crate::security::tool_policy::scope(
+                    policy_scope,
+                    crate::tools::browser::budget_scope(run_tool_call_loop(
+                        active_provider.as_ref(),
+                        &mut history,
+                        ctx.tools_registry.as_ref(),
+                        ctx.observer.as_ref(),
+                        route.provider.as_str(),
+                        route.model.as_str(),
+                        temperature,
+                        true,
+                        None,
+                        msg.channel.as_str(),
+                        &ctx.multimodal,
+                        ctx.max_tool_iterations,
+                        Some(cancellation_token.clone()),
+                        delta_tx.clone(),
+                        ctx.hooks.as_deref(),
+                        &excluded_tools,
+                        None,
+                        ctx.max_parallel_tools,
+                    )),
                 )
                 .await?;
                 Ok(ChannelLlmOutcome {
                     response,
+                    blocked_task_id: None,
+                    outbox_id: None,
                 })
-            },
+            }),
         ) => LlmExecutionResult::Completed(result),
     };
 
@@ -1954,27 +2252,72 @@ async fn process_channel_message(
                 format_terminal_message(&msg.channel, &delivered_response)
             );
             if let Some(channel) = target_channel.as_ref() {
-                if let Some(ref draft_id) = draft_message_id {
+                let (delivered_response, quick_replies) = attach_blocked_task_actions(
+                    channel.as_ref(),
+                    delivered_response,
+                    outcome.blocked_task_id.as_deref(),
+                );
+                if let (Some(outbox_id), Some(engine)) =
+                    (outcome.outbox_id, ctx.task_engine.as_ref())
+                {
+                    // A redelivery should repeat this message, summaries and
+                    // hook rewrites included, not the engine's raw reply.
+                    if let Err(err) = engine
+                        .store()
+                        .set_outbox_body(outbox_id, &delivered_response)
+                    {
+                        tracing::warn!("Failed to update queued task reply {outbox_id}: {err}");
+                    }
+                }
+                let delivered = if let Some(ref draft_id) = draft_message_id {
                     if let Err(e) = channel
                         .finalize_draft(&msg.reply_target, draft_id, &delivered_response)
                         .await
                     {
                         tracing::warn!("Failed to finalize draft: {e}; sending as new message");
-                        let _ = channel
+                        channel
                             .send(
                                 &SendMessage::new(&delivered_response, &msg.reply_target)
-                                    .in_thread(msg.thread_ts.clone()),
+                                    .in_thread(msg.thread_ts.clone())
+                                    .with_quick_replies(quick_replies),
                             )
-                            .await;
+                            .await
+                            .is_ok()
+                    } else {
+                        if !quick_replies.is_empty() {
+                            // Drafts are edited in place and cannot gain buttons afterwards.
+                            let _ = channel
+                                .send(
+                                    &SendMessage::new(
+                                        task_reply::blocked_task_text_hint(),
+                                        &msg.reply_target,
+                                    )
+                                    .in_thread(msg.thread_ts.clone())
+                                    .with_quick_replies(quick_replies),
+                                )
+                                .await;
+                        }
+                        true
                     }
                 } else if let Err(e) = channel
                     .send(
                         &SendMessage::new(delivered_response, &msg.reply_target)
-                            .in_thread(msg.thread_ts.clone()),
+                            .in_thread(msg.thread_ts.clone())
+                            .with_quick_replies(quick_replies),
                     )
                     .await
                 {
                     eprintln!("  ❌ Failed to reply on {}: {e}", channel.name());
+                    false
+                } else {
+                    true
+                };
+                if let (true, Some(outbox_id), Some(engine)) =
+                    (delivered, outcome.outbox_id, ctx.task_engine.as_ref())
+                {
+                    if let Err(err) = engine.store().mark_outbox_delivered(outbox_id) {
+                        tracing::warn!("Failed to acknowledge task reply {outbox_id}: {err}");
+                    }
                 }
             }
         }
@@ -2159,97 +2502,1048 @@ async fn process_channel_message(
                 }
             }
         }
-    }
+    }
+
+    // Swap 👀 → ✅ (or ⚠️ on error) to signal processing is complete
+    if let Some(channel) = target_channel.as_ref() {
+        let _ = channel
+            .remove_reaction(&msg.reply_target, &msg.id, "\u{1F440}")
+            .await;
+        let _ = channel
+            .add_reaction(&msg.reply_target, &msg.id, reaction_done_emoji)
+            .await;
+    }
+}
+
+async fn run_message_dispatch_loop(
+    mut rx: tokio::sync::mpsc::Receiver<traits::ChannelMessage>,
+    ctx: Arc<ChannelRuntimeContext>,
+    max_in_flight_messages: usize,
+    queue_weights: HashMap<String, u32>,
+) {
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(max_in_flight_messages));
+    let mut workers = tokio::task::JoinSet::new();
+    let in_flight_by_sender = Arc::new(tokio::sync::Mutex::new(HashMap::<
+        String,
+        InFlightSenderTaskState,
+    >::new()));
+    let task_sequence = Arc::new(AtomicU64::new(1));
+    let mut pending = fair_queue::FairQueue::new(queue_weights);
+    let mut inbound_open = true;
+
+    loop {
+        while !pending.is_empty() {
+            let Ok(permit) = Arc::clone(&semaphore).try_acquire_owned() else {
+                break;
+            };
+            if let Some(msg) = pending.pop() {
+                spawn_message_worker(
+                    &mut workers,
+                    Arc::clone(&ctx),
+                    Arc::clone(&in_flight_by_sender),
+                    Arc::clone(&task_sequence),
+                    msg,
+                    permit,
+                );
+            }
+        }
+        if !inbound_open && pending.is_empty() {
+            break;
+        }
+
+        tokio::select! {
+            received = rx.recv(), if inbound_open => {
+                let Some(msg) = received else {
+                    inbound_open = false;
+                    continue;
+                };
+                let saturated = semaphore.available_permits() == 0;
+                let notice = SendMessage::new(String::new(), &msg.reply_target)
+                    .in_thread(msg.thread_ts.clone());
+                let (channel, sender) = (msg.channel.clone(), msg.sender.clone());
+                pending.push(&channel, &sender, msg);
+                if saturated {
+                    if let Some(position) = pending.position_of_latest(&channel, &sender) {
+                        notify_queue_position(&ctx, &channel, &sender, position, notice);
+                    }
+                }
+            }
+            Some(result) = workers.join_next(), if !workers.is_empty() => {
+                log_worker_join_result(result);
+            }
+        }
+    }
+
+    while let Some(result) = workers.join_next().await {
+        log_worker_join_result(result);
+    }
+}
+
+fn spawn_message_worker(
+    workers: &mut tokio::task::JoinSet<()>,
+    worker_ctx: Arc<ChannelRuntimeContext>,
+    in_flight: Arc<tokio::sync::Mutex<HashMap<String, InFlightSenderTaskState>>>,
+    task_sequence: Arc<AtomicU64>,
+    msg: traits::ChannelMessage,
+    permit: tokio::sync::OwnedSemaphorePermit,
+) {
+    workers.spawn(async move {
+        let _permit = permit;
+        // A cancel button for a running task must reach that task rather
+        // than interrupt it like an ordinary follow-up message.
+        let targets_task = matches!(
+            task_reply::parse_blocked_task_reply(&msg.content),
+            Some(task_reply::BlockedTaskReply {
+                task_id: Some(_),
+                ..
+            })
+        );
+        let interrupt_enabled =
+            worker_ctx.interrupt_on_new_message && msg.channel == "telegram" && !targets_task;
+        let sender_scope_key = interruption_scope_key(&msg);
+        let cancellation_token = CancellationToken::new();
+        let completion = Arc::new(InFlightTaskCompletion::new());
+        let task_id = task_sequence.fetch_add(1, Ordering::Relaxed);
+
+        if interrupt_enabled {
+            let previous = {
+                let mut active = in_flight.lock().await;
+                active.insert(
+                    sender_scope_key.clone(),
+                    InFlightSenderTaskState {
+                        task_id,
+                        cancellation: cancellation_token.clone(),
+                        completion: Arc::clone(&completion),
+                    },
+                )
+            };
+
+            if let Some(previous) = previous {
+                tracing::info!(
+                    channel = %msg.channel,
+                    sender = %msg.sender,
+                    "Interrupting previous in-flight request for sender"
+                );
+                previous.cancellation.cancel();
+                previous.completion.wait().await;
+            }
+        }
+
+        process_channel_message(worker_ctx, msg, cancellation_token).await;
+
+        if interrupt_enabled {
+            let mut active = in_flight.lock().await;
+            if active
+                .get(&sender_scope_key)
+                .is_some_and(|state| state.task_id == task_id)
+            {
+                active.remove(&sender_scope_key);
+            }
+        }
+
+        completion.mark_done();
+    });
+}
+
+/// Tell a sender whose message is waiting for a free in-flight slot where it
+/// sits in the fair queue.
+fn notify_queue_position(
+    ctx: &ChannelRuntimeContext,
+    channel_name: &str,
+    sender: &str,
+    position: usize,
+    mut notice: SendMessage,
+) {
+    tracing::info!(
+        channel = %channel_name,
+        sender = %sender,
+        position,
+        "Channel dispatch saturated; message queued"
+    );
+    let Some(channel) = ctx.channels_by_name.get(channel_name).cloned() else {
+        return;
+    };
+    notice.content = format!("⏳ Busy right now — your message is queued (position {position}).");
+    tokio::spawn(async move {
+        if let Err(err) = channel.send(&notice).await {
+            tracing::debug!("Failed to send queue position notice: {err}");
+        }
+    });
+}
+
+enum BlockedTaskReplyOutcome {
+    /// Not an answer to a blocked task; process the contained message.
+    Passthrough(traits::ChannelMessage),
+    /// The user resumed blocked task `task_id`; run that same task again
+    /// through the normal pipeline, which approves the calls it blocked on.
+    Resume {
+        msg: traits::ChannelMessage,
+        task_id: String,
+    },
+    /// Fully handled here; nothing else to do.
+    Handled,
+}
+
+/// Channels whose messages run through the task engine when one is configured.
+fn is_task_engine_channel(channel: &str) -> bool {
+    matches!(channel, "imessage" | "telegram" | "discord" | "email")
+}
+
+/// Strip a leading `/raw` from task-engine messages; the flag tells the engine
+/// to skip completion verification for this request.
+fn take_raw_mode_command(
+    ctx: &ChannelRuntimeContext,
+    mut msg: traits::ChannelMessage,
+) -> (traits::ChannelMessage, bool) {
+    if !is_task_engine_channel(&msg.channel) || ctx.task_engine.is_none() {
+        return (msg, false);
+    }
+    match crate::agent::task_engine::strip_raw_mode_command(&msg.content) {
+        Some(request) => {
+            msg.content = request.to_string();
+            (msg, true)
+        }
+        None => (msg, false),
+    }
+}
+
+/// Map a quick-reply button press or keyword answer back to the sender's
+/// blocked task. Cancel closes the task; resume supersedes it with a fresh run
+/// of the original request. A cancel button on a running task stops the run.
+async fn handle_blocked_task_reply(
+    ctx: &ChannelRuntimeContext,
+    msg: traits::ChannelMessage,
+    target_channel: Option<&Arc<dyn Channel>>,
+) -> BlockedTaskReplyOutcome {
+    use crate::agent::task_types::TaskStatus;
+
+    let Some(engine) = ctx.task_engine.as_ref() else {
+        return BlockedTaskReplyOutcome::Passthrough(msg);
+    };
+    let Some(reply) = task_reply::parse_blocked_task_reply(&msg.content) else {
+        return BlockedTaskReplyOutcome::Passthrough(msg);
+    };
+
+    let task = match reply.task_id.as_deref() {
+        Some(task_id) => engine.store().get_task_run(task_id),
+        None => engine.store().latest_sender_task_with_status(
+            &msg.channel,
+            &msg.sender,
+            TaskStatus::Blocked,
+        ),
+    };
+    let task = match task {
+        Ok(task) => {
+            task.filter(|task| task.channel == msg.channel && task.sender_key == msg.sender)
+        }
+        Err(err) => {
+            tracing::warn!("Failed to look up blocked task for quick reply: {err}");
+            None
+        }
+    };
+
+    if let Some(running) = task.as_ref().filter(|task| {
+        reply.action == task_reply::BlockedTaskAction::Cancel
+            && matches!(task.status, TaskStatus::Running | TaskStatus::Queued)
+    }) {
+        let text = match engine.cancel_task(&running.id) {
+            Ok(true) => "🛑 已取消该任务。",
+            Ok(false) => "该任务正在其他进程中运行，暂时无法取消。",
+            Err(err) => {
+                tracing::warn!("Failed to cancel task {}: {err:#}", running.id);
+                "取消任务失败，请稍后重试。"
+            }
+        };
+        if let Some(channel) = target_channel {
+            let _ = channel
+                .send(&SendMessage::new(text, &msg.reply_target).in_thread(msg.thread_ts.clone()))
+                .await;
+        }
+        return BlockedTaskReplyOutcome::Handled;
+    }
+    let task = task.filter(|task| task.status == TaskStatus::Blocked);
+
+    let Some(task) = task else {
+        if reply.task_id.is_none() {
+            // A bare keyword with nothing pending is ordinary conversation.
+            return BlockedTaskReplyOutcome::Passthrough(msg);
+        }
+        if let Some(channel) = target_channel {
+            let _ = channel
+                .send(
+                    &SendMessage::new("该任务已不在等待处理状态。", &msg.reply_target)
+                        .in_thread(msg.thread_ts.clone()),
+                )
+                .await;
+        }
+        return BlockedTaskReplyOutcome::Handled;
+    };
+
+    match reply.action {
+        task_reply::BlockedTaskAction::Cancel => {
+            let _ = engine
+                .store()
+                .update_status(&task.id, TaskStatus::Cancelled);
+            let _ = engine.store().append_event(
+                &task.id,
+                "cancelled_by_user",
+                Some(&serde_json::json!({ "via": msg.channel })),
+            );
+            if let Some(channel) = target_channel {
+                let _ = channel
+                    .send(
+                        &SendMessage::new("🛑 已取消该任务。", &msg.reply_target)
+                            .in_thread(msg.thread_ts.clone()),
+                    )
+                    .await;
+            }
+            BlockedTaskReplyOutcome::Handled
+        }
+        // The task stays blocked until the engine picks it up again, which is
+        // what lets the run approve the calls it was waiting on.
+        task_reply::BlockedTaskAction::Resume => {
+            let _ = engine.store().append_event(
+                &task.id,
+                "resumed_by_user",
+                Some(&serde_json::json!({ "via": msg.channel })),
+            );
+            BlockedTaskReplyOutcome::Resume {
+                msg: traits::ChannelMessage {
+                    content: format!(
+                        "{}\n\n[Task Engine]\n用户已确认继续。请基于上一轮的阻塞说明继续完成该任务。",
+                        task.original_request
+                    ),
+                    ..msg
+                },
+                task_id: task.id,
+            }
+        }
+    }
+}
+
+/// Answer "what's the status of my task?" straight from the task store without
+/// an LLM call. Falls through to normal processing when the sender has no tasks,
+/// or asked a status question without any active or recent task to ask about.
+async fn handle_task_status_query_if_needed(
+    ctx: &ChannelRuntimeContext,
+    msg: &traits::ChannelMessage,
+    target_channel: Option<&Arc<dyn Channel>>,
+) -> bool {
+    let Some(engine) = ctx.task_engine.as_ref() else {
+        return false;
+    };
+    let Some(query) = task_status::task_status_query(&msg.content) else {
+        return false;
+    };
+
+    let tasks = match engine.store().list_recent_sender_tasks(
+        &msg.channel,
+        &msg.sender,
+        task_status::STATUS_REPLY_TASK_LIMIT,
+    ) {
+        Ok(tasks)
+            if (query == task_status::StatusQuery::Command && !tasks.is_empty())
+                || task_status::has_active_or_recent_task(&tasks, chrono::Utc::now()) =>
+        {
+            tasks
+        }
+        Ok(_) => return false,
+        Err(err) => {
+            tracing::warn!("Failed to list sender tasks for status query: {err}");
+            return false;
+        }
+    };
+    let entries: Vec<_> = tasks
+        .into_iter()
+        .map(|task| {
+            let last_event = engine
+                .store()
+                .latest_lifecycle_event(&task.id)
+                .ok()
+                .flatten();
+            (task, last_event)
+        })
+        .collect();
+    let reply = task_status::render_task_status_reply(
+        &entries,
+        engine.config().max_continuation_rounds,
+        chrono::Utc::now(),
+        task_status::ReplyLanguage::of(&msg.content),
+    );
+
+    if let Some(channel) = target_channel {
+        if let Err(err) = channel
+            .send(&SendMessage::new(reply, &msg.reply_target).in_thread(msg.thread_ts.clone()))
+            .await
+        {
+            tracing::warn!(
+                "Failed to send task status reply on {}: {err}",
+                channel.name()
+            );
+        }
+    }
+    true
+}
+
+/// Stored settings for the message's sender, or defaults when the task store
+/// is unavailable.
+fn load_sender_settings(
+    ctx: &ChannelRuntimeContext,
+    msg: &traits::ChannelMessage,
+) -> sender_settings::SenderSettings {
+    let Some(engine) = ctx.task_engine.as_ref() else {
+        return sender_settings::SenderSettings::default();
+    };
+    match engine.store().sender_settings(&msg.channel, &msg.sender) {
+        Ok(raw) => sender_settings::SenderSettings::from_stored(&raw),
+        Err(err) => {
+            tracing::warn!("Failed to load sender settings: {err}");
+            sender_settings::SenderSettings::default()
+        }
+    }
+}
+
+/// Apply `/verbose`, `/language`, `/model`, `/notifications`, `/tools`, and
+/// `/settings`.
+async fn handle_sender_settings_command_if_needed(
+    ctx: &ChannelRuntimeContext,
+    msg: &traits::ChannelMessage,
+    target_channel: Option<&Arc<dyn Channel>>,
+) -> bool {
+    let Some(engine) = ctx.task_engine.as_ref() else {
+        return false;
+    };
+    let Some(command) = sender_settings::parse_sender_settings_command(
+        &msg.content,
+        !supports_runtime_model_switch(&msg.channel),
+    ) else {
+        return false;
+    };
+
+    let reply = match command {
+        sender_settings::SenderSettingsCommand::Show => {
+            sender_settings::render_sender_settings(&load_sender_settings(ctx, msg))
+        }
+        sender_settings::SenderSettingsCommand::Set { key, value } => {
+            match engine.store().set_sender_setting(
+                &msg.channel,
+                &msg.sender,
+                key,
+                value.as_deref(),
+            ) {
+                Ok(()) => sender_settings::render_setting_updated(key, value.as_deref()),
+                Err(err) => {
+                    tracing::warn!("Failed to save sender setting {key}: {err}");
+                    format!("⚠️ Failed to save setting `{key}`: {err}")
+                }
+            }
+        }
+        sender_settings::SenderSettingsCommand::ShowTools => {
+            let names: Vec<&str> = ctx.tools_registry.iter().map(|tool| tool.name()).collect();
+            sender_settings::render_tools(&names, &load_sender_settings(ctx, msg))
+        }
+        sender_settings::SenderSettingsCommand::ToggleTool { name, enabled } => {
+            if ctx.tools_registry.iter().any(|tool| tool.name() == name) {
+                let (key, value) = load_sender_settings(ctx, msg).tool_toggle(&name, enabled);
+                match engine.store().set_sender_setting(
+                    &msg.channel,
+                    &msg.sender,
+                    key,
+                    value.as_deref(),
+                ) {
+                    Ok(()) => sender_settings::render_tool_toggled(&name, enabled),
+                    Err(err) => {
+                        tracing::warn!("Failed to save sender setting {key}: {err}");
+                        format!("⚠️ Failed to save setting `{key}`: {err}")
+                    }
+                }
+            } else {
+                format!("Unknown tool `{name}`. Send `/tools` to list the available tools.")
+            }
+        }
+        sender_settings::SenderSettingsCommand::Invalid(usage) => usage,
+    };
+
+    if let Some(channel) = target_channel {
+        if let Err(err) = channel
+            .send(&SendMessage::new(reply, &msg.reply_target).in_thread(msg.thread_ts.clone()))
+            .await
+        {
+            tracing::warn!("Failed to send settings reply on {}: {err}", channel.name());
+        }
+    }
+    true
+}
+
+/// Apply `/undo`: drop the last exchange from the sender's history and
+/// archive it as a conversation branch when a task store is available.
+async fn handle_undo_command_if_needed(
+    ctx: &ChannelRuntimeContext,
+    msg: &traits::ChannelMessage,
+    target_channel: Option<&Arc<dyn Channel>>,
+) -> bool {
+    if !conversation_undo::is_undo_command(&msg.content) {
+        return false;
+    }
+
+    let history_key = conversation_history_key(msg);
+    let removed = undo_last_exchange(ctx, &history_key);
+    if let (Some(engine), Some(turns)) = (ctx.task_engine.as_ref(), removed.as_deref()) {
+        if let Err(err) = engine
+            .store()
+            .archive_conversation_branch(&history_key, turns)
+        {
+            tracing::warn!("Failed to archive undone conversation branch: {err}");
+        }
+    }
+    let reply = conversation_undo::render_undo_reply(removed.as_deref());
+
+    if let Some(channel) = target_channel {
+        if let Err(err) = channel
+            .send(&SendMessage::new(reply, &msg.reply_target).in_thread(msg.thread_ts.clone()))
+            .await
+        {
+            tracing::warn!("Failed to send undo reply on {}: {err}", channel.name());
+        }
+    }
+    true
+}
+
+/// Instructions pinned for the conversation: the sender's `/pin`s, then any
+/// contributed by hooks.
+async fn load_pinned_instructions(
+    ctx: &ChannelRuntimeContext,
+    msg: &traits::ChannelMessage,
+    history_key: &str,
+) -> Vec<String> {
+    let mut pins = Vec::new();
+    if let Some(engine) = ctx.task_engine.as_ref() {
+        match engine.store().list_pinned_instructions(history_key) {
+            Ok(records) => pins.extend(records.into_iter().map(|record| record.content)),
+            Err(err) => tracing::warn!("Failed to load pinned instructions: {err}"),
+        }
+    }
+    if let Some(hooks) = ctx.hooks.as_ref() {
+        pins.extend(
+            hooks
+                .collect_pinned_instructions(&msg.channel, &msg.sender)
+                .await,
+        );
+    }
+    pins
+}
+
+fn pin_instruction_reply(
+    store: &crate::agent::task_store::TaskStore,
+    history_key: &str,
+    content: &str,
+) -> String {
+    let pinned_count = store
+        .list_pinned_instructions(history_key)
+        .map_or(0, |pins| pins.len());
+    if pinned_count >= pinned::MAX_PINNED_INSTRUCTIONS {
+        return format!(
+            "⚠️ At most {} instructions can be pinned. Remove one with `/unpin <number>`.",
+            pinned::MAX_PINNED_INSTRUCTIONS
+        );
+    }
+    match store.pin_instruction(history_key, content) {
+        Ok(_) => format!(
+            "📌 Pinned: \"{}\". It will stay in context for this conversation.",
+            truncate_with_ellipsis(content, 80)
+        ),
+        Err(err) => {
+            tracing::warn!("Failed to pin instruction: {err}");
+            format!("⚠️ Failed to pin instruction: {err}")
+        }
+    }
+}
+
+/// Apply `/pin`, `/pins`, and `/unpin`.
+async fn handle_pin_command_if_needed(
+    ctx: &ChannelRuntimeContext,
+    msg: &traits::ChannelMessage,
+    target_channel: Option<&Arc<dyn Channel>>,
+) -> bool {
+    let Some(engine) = ctx.task_engine.as_ref() else {
+        return false;
+    };
+    let Some(command) = pinned::parse_pin_command(&msg.content) else {
+        return false;
+    };
+
+    let history_key = conversation_history_key(msg);
+    let store = engine.store();
+    let reply = match command {
+        pinned::PinCommand::Pin(text) => pin_instruction_reply(store, &history_key, &text),
+        pinned::PinCommand::PinPrevious => {
+            let previous = ctx
+                .conversation_histories
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&history_key)
+                .and_then(|turns| turns.iter().rfind(|turn| turn.role == "user"))
+                .map(|turn| turn.content.trim().to_string());
+            match previous {
+                Some(text) => pin_instruction_reply(store, &history_key, &text),
+                None => "Nothing to pin yet. Use `/pin <instruction>`.".to_string(),
+            }
+        }
+        pinned::PinCommand::List => match store.list_pinned_instructions(&history_key) {
+            Ok(pins) => pinned::render_pin_list(&pins),
+            Err(err) => format!("⚠️ Failed to load pinned instructions: {err}"),
+        },
+        pinned::PinCommand::Unpin(index) => {
+            let target = store
+                .list_pinned_instructions(&history_key)
+                .ok()
+                .and_then(|pins| pins.into_iter().nth(index - 1));
+            match target {
+                Some(pin) => match store.unpin_instructions(&history_key, Some(pin.id)) {
+                    Ok(_) => format!(
+                        "Unpinned: \"{}\".",
+                        truncate_with_ellipsis(&pin.content, 80)
+                    ),
+                    Err(err) => format!("⚠️ Failed to unpin instruction: {err}"),
+                },
+                None => format!("No pinned instruction #{index}. Use `/pins` to list them."),
+            }
+        }
+        pinned::PinCommand::UnpinAll => match store.unpin_instructions(&history_key, None) {
+            Ok(removed) => format!("Removed {removed} pinned instruction(s)."),
+            Err(err) => format!("⚠️ Failed to unpin instructions: {err}"),
+        },
+        pinned::PinCommand::Invalid(usage) => usage,
+    };
+
+    if let Some(channel) = target_channel {
+        if let Err(err) = channel
+            .send(&SendMessage::new(reply, &msg.reply_target).in_thread(msg.thread_ts.clone()))
+            .await
+        {
+            tracing::warn!("Failed to send pin reply on {}: {err}", channel.name());
+        }
+    }
+    true
+}
+
+/// Answer `/find <keywords>` with the sender's matching tasks from the task
+/// store's full-text index.
+async fn handle_task_search_if_needed(
+    ctx: &ChannelRuntimeContext,
+    msg: &traits::ChannelMessage,
+    target_channel: Option<&Arc<dyn Channel>>,
+) -> bool {
+    let Some(engine) = ctx.task_engine.as_ref() else {
+        return false;
+    };
+    let Some(query) = task_status::parse_task_search_query(&msg.content) else {
+        return false;
+    };
+
+    let reply = match engine.store().search_sender_tasks(
+        &msg.channel,
+        &msg.sender,
+        query,
+        task_status::SEARCH_REPLY_TASK_LIMIT,
+    ) {
+        Ok(tasks) => task_status::render_task_search_reply(
+            query,
+            &tasks,
+            task_status::ReplyLanguage::of(&msg.content),
+        ),
+        Err(err) => {
+            tracing::warn!("Failed to search sender tasks: {err}");
+            format!("⚠️ Task search failed: {err}")
+        }
+    };
+
+    if let Some(channel) = target_channel {
+        if let Err(err) = channel
+            .send(&SendMessage::new(reply, &msg.reply_target).in_thread(msg.thread_ts.clone()))
+            .await
+        {
+            tracing::warn!(
+                "Failed to send task search reply on {}: {err}",
+                channel.name()
+            );
+        }
+    }
+    true
+}
+
+/// Ask before re-running a request the sender just made, and act on the
+/// answer: "again" runs the earlier request through the normal pipeline,
+/// "show" replies with the earlier result.
+async fn handle_duplicate_task_check(
+    ctx: &ChannelRuntimeContext,
+    msg: traits::ChannelMessage,
+    target_channel: Option<&Arc<dyn Channel>>,
+) -> BlockedTaskReplyOutcome {
+    let Some(engine) = ctx.task_engine.as_ref() else {
+        return BlockedTaskReplyOutcome::Passthrough(msg);
+    };
+    let window_mins = engine.config().duplicate_task_window_mins;
+    if !is_task_engine_channel(&msg.channel) || window_mins == 0 {
+        return BlockedTaskReplyOutcome::Passthrough(msg);
+    }
+    let store = engine.store();
+    let pending = store
+        .sender_settings(&msg.channel, &msg.sender)
+        .ok()
+        .and_then(|settings| settings.get(duplicate_task::PENDING_DUPLICATE_KEY).cloned());
+    if pending.is_some() {
+        let _ = store.set_sender_setting(
+            &msg.channel,
+            &msg.sender,
+            duplicate_task::PENDING_DUPLICATE_KEY,
+            None,
+        );
+    }
+
+    let reply_text = if let Some(reply) = duplicate_task::parse_duplicate_reply(&msg.content) {
+        let Some(task_id) = reply.task_id.or(pending) else {
+            // A bare keyword with nothing pending is ordinary conversation.
+            return BlockedTaskReplyOutcome::Passthrough(msg);
+        };
+        let task = store
+            .get_task_run(&task_id)
+            .ok()
+            .flatten()
+            .filter(|task| task.channel == msg.channel && task.sender_key == msg.sender);
+        match (task, reply.choice) {
+            (None, _) => "该任务已不存在。(That task is no longer available.)".to_string(),
+            (Some(task), duplicate_task::DuplicateChoice::RunAgain) => {
+                let _ = store.append_event(
+                    &task.id,
+                    "duplicate_rerun_confirmed",
+                    Some(&serde_json::json!({ "via": msg.channel })),
+                );
+                return BlockedTaskReplyOutcome::Passthrough(traits::ChannelMessage {
+                    content: task.original_request,
+                    ..msg
+                });
+            }
+            (Some(task), duplicate_task::DuplicateChoice::ShowPrevious) => {
+                duplicate_task::render_previous_result(&task)
+            }
+        }
+    } else {
+        let tasks = match store.list_recent_sender_tasks(
+            &msg.channel,
+            &msg.sender,
+            duplicate_task::DUPLICATE_CANDIDATE_TASKS,
+        ) {
+            Ok(tasks) => tasks,
+            Err(err) => {
+                tracing::warn!("Failed to list sender tasks for duplicate check: {err}");
+                return BlockedTaskReplyOutcome::Passthrough(msg);
+            }
+        };
+        let now = chrono::Utc::now();
+        let window =
+            chrono::Duration::minutes(i64::try_from(window_mins).unwrap_or(i64::MAX / 60_000));
+        let duplicate = match engine.request_embedder() {
+            Some(embedder) => {
+                duplicate_task::find_duplicate_with_embeddings(
+                    &tasks,
+                    &msg.content,
+                    window,
+                    now,
+                    embedder.as_ref(),
+                )
+                .await
+            }
+            None => duplicate_task::find_duplicate(&tasks, &msg.content, window, now),
+        };
+        let Some(task) = duplicate else {
+            return BlockedTaskReplyOutcome::Passthrough(msg);
+        };
+        let _ = store.set_sender_setting(
+            &msg.channel,
+            &msg.sender,
+            duplicate_task::PENDING_DUPLICATE_KEY,
+            Some(&task.id),
+        );
+        let _ = store.append_event(
+            &task.id,
+            "duplicate_request_detected",
+            Some(&serde_json::json!({ "request": msg.content })),
+        );
+        let buttons = target_channel.is_some_and(|channel| channel.supports_quick_replies());
+        let prompt = duplicate_task::render_duplicate_prompt(task, now, buttons);
+        if let Some(channel) = target_channel {
+            let quick_replies = if buttons {
+                duplicate_task::duplicate_quick_replies(&task.id)
+            } else {
+                Vec::new()
+            };
+            let _ = channel
+                .send(
+                    &SendMessage::new(prompt, &msg.reply_target)
+                        .in_thread(msg.thread_ts.clone())
+                        .with_quick_replies(quick_replies),
+                )
+                .await;
+        }
+        return BlockedTaskReplyOutcome::Handled;
+    };
 
-    // Swap 👀 → ✅ (or ⚠️ on error) to signal processing is complete
-    if let Some(channel) = target_channel.as_ref() {
-        let _ = channel
-            .remove_reaction(&msg.reply_target, &msg.id, "\u{1F440}")
-            .await;
+    if let Some(channel) = target_channel {
         let _ = channel
-            .add_reaction(&msg.reply_target, &msg.id, reaction_done_emoji)
+            .send(&SendMessage::new(reply_text, &msg.reply_target).in_thread(msg.thread_ts.clone()))
             .await;
     }
+    BlockedTaskReplyOutcome::Handled
 }
 
-async fn run_message_dispatch_loop(
-    mut rx: tokio::sync::mpsc::Receiver<traits::ChannelMessage>,
-    ctx: Arc<ChannelRuntimeContext>,
-    max_in_flight_messages: usize,
-) {
-    let semaphore = Arc::new(tokio::sync::Semaphore::new(max_in_flight_messages));
-    let mut workers = tokio::task::JoinSet::new();
-    let in_flight_by_sender = Arc::new(tokio::sync::Mutex::new(HashMap::<
-        String,
-        InFlightSenderTaskState,
-    >::new()));
-    let task_sequence = Arc::new(AtomicU64::new(1));
+/// Offer continue/cancel actions on a blocked task reply: buttons where the
+/// channel supports them, a keyword hint appended to the text elsewhere.
+fn attach_blocked_task_actions(
+    channel: &dyn Channel,
+    response: String,
+    blocked_task_id: Option<&str>,
+) -> (String, Vec<QuickReply>) {
+    let Some(task_id) = blocked_task_id else {
+        return (response, Vec::new());
+    };
+    if channel.supports_quick_replies() {
+        (response, task_reply::blocked_task_quick_replies(task_id))
+    } else {
+        (
+            format!("{response}\n\n{}", task_reply::blocked_task_text_hint()),
+            Vec::new(),
+        )
+    }
+}
 
-    while let Some(msg) = rx.recv().await {
-        let permit = match Arc::clone(&semaphore).acquire_owned().await {
-            Ok(permit) => permit,
-            Err(_) => break,
-        };
+/// Message-id prefix marking a recovery message that resumes an existing task
+/// from its checkpoint instead of starting a new run.
+const TASK_RESUME_MESSAGE_PREFIX: &str = "resume-";
 
-        let worker_ctx = Arc::clone(&ctx);
-        let in_flight = Arc::clone(&in_flight_by_sender);
-        let task_sequence = Arc::clone(&task_sequence);
-        workers.spawn(async move {
-            let _permit = permit;
-            let interrupt_enabled =
-                worker_ctx.interrupt_on_new_message && msg.channel == "telegram";
-            let sender_scope_key = interruption_scope_key(&msg);
-            let cancellation_token = CancellationToken::new();
-            let completion = Arc::new(InFlightTaskCompletion::new());
-            let task_id = task_sequence.fetch_add(1, Ordering::Relaxed);
-
-            if interrupt_enabled {
-                let previous = {
-                    let mut active = in_flight.lock().await;
-                    active.insert(
-                        sender_scope_key.clone(),
-                        InFlightSenderTaskState {
-                            task_id,
-                            cancellation: cancellation_token.clone(),
-                            completion: Arc::clone(&completion),
-                        },
-                    )
-                };
+fn archive_finished_tasks(engine: &crate::agent::task_engine::TaskEngine, after_days: u64) {
+    if after_days == 0 {
+        return;
+    }
+    let max_age = Duration::from_secs(after_days.saturating_mul(24 * 60 * 60));
+    match engine.store().archive_terminal_tasks_older_than(max_age) {
+        Ok(0) => {}
+        Ok(count) => {
+            tracing::info!("Archived {count} finished task run(s) older than {after_days}d")
+        }
+        Err(err) => tracing::warn!("Failed to archive finished task runs: {err}"),
+    }
+}
+
+fn move_tasks_to_cold_storage(
+    engine: &crate::agent::task_engine::TaskEngine,
+    workspace_dir: &Path,
+    after_days: u64,
+) {
+    if after_days == 0 {
+        return;
+    }
+    let max_age = Duration::from_secs(after_days.saturating_mul(24 * 60 * 60));
+    let archive = crate::agent::task_archive::TaskArchive::new(workspace_dir);
+    match archive.archive_tasks_older_than(engine.store(), max_age) {
+        Ok(0) => {}
+        Ok(count) => {
+            tracing::info!("Moved {count} task run(s) older than {after_days}d to cold storage")
+        }
+        Err(err) => tracing::warn!("Failed to move task runs to cold storage: {err}"),
+    }
+}
 
-                if let Some(previous) = previous {
-                    tracing::info!(
-                        channel = %msg.channel,
-                        sender = %msg.sender,
-                        "Interrupting previous in-flight request for sender"
+/// Periodically fail running tasks whose heartbeat has gone silent for
+/// `after_mins` and tell their senders, so hung runs do not sit in "running".
+fn spawn_stuck_task_detector(ctx: Arc<ChannelRuntimeContext>, after_mins: u64) {
+    if after_mins == 0 || ctx.task_engine.is_none() {
+        return;
+    }
+    let max_silence = Duration::from_secs(after_mins.saturating_mul(60));
+    let check_every = (max_silence / 2).min(Duration::from_secs(60));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(check_every);
+        loop {
+            interval.tick().await;
+            let Some(engine) = ctx.task_engine.as_ref() else {
+                return;
+            };
+            let stuck = match engine.fail_stuck_tasks(max_silence) {
+                Ok(stuck) => stuck,
+                Err(err) => {
+                    tracing::warn!("Stuck-task detector failed: {err}");
+                    continue;
+                }
+            };
+            for task in stuck {
+                ctx.observer
+                    .record_event(&observability::ObserverEvent::TaskLifecycle {
+                        task_id: task.id.clone(),
+                        channel: task.channel.clone(),
+                        stage: "stuck".to_string(),
+                        labels: Vec::new(),
+                    });
+                let Some(channel) = ctx.channels_by_name.get(&task.channel) else {
+                    continue;
+                };
+                let alert = task_status::render_stuck_task_alert(&task, after_mins);
+                if let Err(err) = channel
+                    .send(&SendMessage::new(alert, &task.reply_target))
+                    .await
+                {
+                    tracing::warn!(
+                        "Failed to send stuck-task alert on {}: {err}",
+                        channel.name()
                     );
-                    previous.cancellation.cancel();
-                    previous.completion.wait().await;
                 }
             }
+        }
+    });
+}
 
-            process_channel_message(worker_ctx, msg, cancellation_token).await;
-
-            if interrupt_enabled {
-                let mut active = in_flight.lock().await;
-                if active
-                    .get(&sender_scope_key)
-                    .is_some_and(|state| state.task_id == task_id)
-                {
-                    active.remove(&sender_scope_key);
+/// Periodically send task replies still queued in the outbox — ones the
+/// original handler never acknowledged, e.g. after a crash or a send failure —
+/// backing off per reply until the channel accepts it, or dead-lettering it
+/// after [`TASK_OUTBOX_MAX_ATTEMPTS`] failures.
+fn spawn_task_outbox_worker(ctx: Arc<ChannelRuntimeContext>) {
+    if ctx.task_engine.is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(TASK_OUTBOX_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let Some(engine) = ctx.task_engine.as_ref() else {
+                return;
+            };
+            let due = match engine.store().list_due_outbox(TASK_OUTBOX_BATCH) {
+                Ok(due) => due,
+                Err(err) => {
+                    tracing::warn!("Failed to read task outbox: {err}");
+                    continue;
+                }
+            };
+            for entry in due {
+                let result = match ctx.channels_by_name.get(&entry.channel) {
+                    Some(channel) => channel
+                        .send(&SendMessage::new(&entry.body, &entry.reply_target))
+                        .await
+                        .map_err(|err| err.to_string()),
+                    None => Err(format!("channel {} is not running", entry.channel)),
+                };
+                let stored = match result {
+                    Ok(()) => engine.store().mark_outbox_delivered(entry.id),
+                    Err(err) => {
+                        tracing::warn!(
+                            "Failed to deliver queued reply for task {}: {err}",
+                            entry.task_id
+                        );
+                        let backoff = TASK_OUTBOX_RETRY_BASE_SECS
+                            .saturating_mul(1 << entry.attempts.min(16))
+                            .min(TASK_OUTBOX_RETRY_MAX_SECS);
+                        engine
+                            .store()
+                            .record_outbox_failure(
+                                entry.id,
+                                &err,
+                                Duration::from_secs(backoff),
+                                TASK_OUTBOX_MAX_ATTEMPTS,
+                            )
+                            .and_then(|dead_lettered| {
+                                if !dead_lettered {
+                                    return Ok(());
+                                }
+                                tracing::error!(
+                                    "Gave up delivering the reply for task {} after {} attempts: {err}",
+                                    entry.task_id,
+                                    TASK_OUTBOX_MAX_ATTEMPTS
+                                );
+                                engine.store().append_event(
+                                    &entry.task_id,
+                                    crate::agent::task_store::OUTBOX_DEAD_LETTER_EVENT,
+                                    Some(&serde_json::json!({
+                                        "channel": entry.channel,
+                                        "attempts": entry.attempts + 1,
+                                        "last_error": err,
+                                    })),
+                                )
+                            })
+                    }
+                };
+                if let Err(err) = stored {
+                    tracing::warn!("Failed to update task outbox entry {}: {err}", entry.id);
                 }
             }
-
-            completion.mark_done();
-        });
-
-        while let Some(result) = workers.try_join_next() {
-            log_worker_join_result(result);
         }
-    }
+    });
+}
 
-    while let Some(result) = workers.join_next().await {
-        log_worker_join_result(result);
+/// Task-store upkeep while channels run: periodically report capacity (queue
+/// depth, oldest queued age, DB size, cold-storage backlog) to the observer,
+/// and re-run the startup archiving and cold-storage sweeps, so a long-lived
+/// process keeps the task database from growing.
+fn spawn_task_store_maintenance(
+    ctx: Arc<ChannelRuntimeContext>,
+    archive_after_days: u64,
+    cold_storage_after_days: u64,
+) {
+    if ctx.task_engine.is_none() {
+        return;
     }
+    let cold_storage_after = (cold_storage_after_days > 0)
+        .then(|| Duration::from_secs(cold_storage_after_days.saturating_mul(24 * 60 * 60)));
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(TASK_STORE_GAUGE_INTERVAL_SECS));
+        let archive_every = Duration::from_secs(TASK_STORE_ARCHIVE_INTERVAL_SECS);
+        let mut archive_interval =
+            tokio::time::interval_at(tokio::time::Instant::now() + archive_every, archive_every);
+        loop {
+            let archive = tokio::select! {
+                _ = interval.tick() => false,
+                _ = archive_interval.tick() => true,
+            };
+            if archive {
+                let ctx = Arc::clone(&ctx);
+                let swept = tokio::task::spawn_blocking(move || {
+                    if let Some(engine) = ctx.task_engine.as_ref() {
+                        archive_finished_tasks(engine, archive_after_days);
+                        move_tasks_to_cold_storage(
+                            engine,
+                            &ctx.workspace_dir,
+                            cold_storage_after_days,
+                        );
+                    }
+                })
+                .await;
+                if let Err(err) = swept {
+                    tracing::warn!("Task archiving sweep failed: {err}");
+                }
+                continue;
+            }
+            let Some(engine) = ctx.task_engine.as_ref() else {
+                return;
+            };
+            let gauges = match engine.store().capacity_gauges(cold_storage_after) {
+                Ok(gauges) => gauges,
+                Err(err) => {
+                    tracing::warn!("Failed to sample task-store gauges: {err}");
+                    continue;
+                }
+            };
+            for metric in [
+                observability::ObserverMetric::TaskQueueDepth(gauges.queued_tasks),
+                observability::ObserverMetric::OldestQueuedTaskAge(Duration::from_secs(
+                    gauges.oldest_queued_age_secs,
+                )),
+                observability::ObserverMetric::TaskStoreSize(gauges.db_size_bytes),
+                observability::ObserverMetric::TaskArchiveBacklog(gauges.archive_backlog),
+            ] {
+                ctx.observer.record_metric(&metric);
+            }
+        }
+    });
 }
 
 fn recover_pending_imessage_tasks(ctx: Arc<ChannelRuntimeContext>) {
@@ -2266,28 +3560,60 @@ fn recover_pending_imessage_tasks(ctx: Arc<ChannelRuntimeContext>) {
     };
 
     for task in recoverable {
-        if task.channel != "imessage" {
+        if !is_task_engine_channel(&task.channel) {
             continue;
         }
-
-        // Mark stale pre-restart task and replay from original request as a new run.
-        let _ = engine
-            .store()
-            .update_status(&task.id, crate::agent::task_types::TaskStatus::Failed);
-        let _ = engine.store().append_event(
+        // Another live worker owns this task; it is only reclaimable once
+        // that worker stops renewing its lease.
+        match engine.store().try_claim(
             &task.id,
-            "recovered_as_replay",
-            Some(&serde_json::json!({
-                "reason": "process_restart",
-            })),
-        );
+            engine.worker_id(),
+            crate::agent::task_engine::TASK_CLAIM_LEASE,
+        ) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(err) => {
+                tracing::warn!("Failed to claim recoverable task {}: {err}", task.id);
+                continue;
+            }
+        }
+
+        let has_checkpoint = matches!(engine.store().load_checkpoint(&task.id), Ok(Some(_)));
+        let message_id = if has_checkpoint {
+            // Resume the same task at its checkpointed round.
+            let _ = engine
+                .store()
+                .update_status(&task.id, crate::agent::task_types::TaskStatus::Running);
+            let _ = engine.store().append_event(
+                &task.id,
+                "recovered_from_checkpoint",
+                Some(&serde_json::json!({
+                    "reason": "process_restart",
+                })),
+            );
+            format!("{TASK_RESUME_MESSAGE_PREFIX}{}", task.id)
+        } else {
+            // Mark stale pre-restart task and replay from original request as a new run.
+            let _ = engine
+                .store()
+                .update_status(&task.id, crate::agent::task_types::TaskStatus::Failed);
+            let _ = engine.store().append_event(
+                &task.id,
+                "recovered_as_replay",
+                Some(&serde_json::json!({
+                    "reason": "process_restart",
+                })),
+            );
+            let _ = engine.store().release_claim(&task.id, engine.worker_id());
+            format!("recovery-{}", task.id)
+        };
 
         let replay_msg = traits::ChannelMessage {
-            id: format!("recovery-{}", task.id),
+            id: message_id,
             sender: task.sender_key.clone(),
             reply_target: task.reply_target.clone(),
             content: task.original_request.clone(),
-            channel: "imessage".to_string(),
+            channel: task.channel.clone(),
             timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
@@ -2798,8 +4124,11 @@ struct ConfiguredChannel {
     channel: Arc<dyn Channel>,
 }
 
+/// `supports_vision` is whether the default provider takes image input;
+/// channels that turn attachments into `[IMAGE:]` markers only do so then.
 fn collect_configured_channels(
     config: &Config,
+    supports_vision: bool,
     _matrix_skip_context: &str,
 ) -> Vec<ConfiguredChannel> {
     let mut channels = Vec::new();
@@ -2807,29 +4136,41 @@ fn collect_configured_channels(
     if let Some(ref tg) = config.channels_config.telegram {
         channels.push(ConfiguredChannel {
             display_name: "Telegram",
-            channel: Arc::new(
-                TelegramChannel::new(
+            channel: Arc::new({
+                let telegram = TelegramChannel::new(
                     tg.bot_token.clone(),
                     tg.allowed_users.clone(),
                     tg.mention_only,
                 )
                 .with_streaming(tg.stream_mode, tg.draft_update_interval_ms)
                 .with_transcription(config.transcription.clone())
-                .with_workspace_dir(config.workspace_dir.clone()),
-            ),
+                .with_workspace_dir(config.workspace_dir.clone());
+                match (tg.receive_mode, tg.webhook_url.clone(), tg.port) {
+                    (crate::config::TelegramReceiveMode::Webhook, Some(url), Some(port)) => {
+                        telegram.with_webhook(url, port, tg.webhook_secret.clone())
+                    }
+                    _ => telegram,
+                }
+            }),
         });
     }
 
     if let Some(ref dc) = config.channels_config.discord {
         channels.push(ConfiguredChannel {
             display_name: "Discord",
-            channel: Arc::new(DiscordChannel::new(
-                dc.bot_token.clone(),
-                dc.guild_id.clone(),
-                dc.allowed_users.clone(),
-                dc.listen_to_bots,
-                dc.mention_only,
-            )),
+            channel: Arc::new(
+                DiscordChannel::new(
+                    dc.bot_token.clone(),
+                    dc.guild_id.clone(),
+                    dc.allowed_users.clone(),
+                    dc.listen_to_bots,
+                    dc.mention_only,
+                )
+                .with_channel_ids(dc.channel_ids.clone())
+                .with_streaming(dc.stream_mode, dc.draft_update_interval_ms)
+                // Discord images are remote URLs.
+                .with_image_markers(supports_vision && config.multimodal.allow_remote_fetch),
+            ),
         });
     }
 
@@ -2991,7 +4332,11 @@ fn collect_configured_channels(
     if let Some(ref email_cfg) = config.channels_config.email {
         channels.push(ConfiguredChannel {
             display_name: "Email",
-            channel: Arc::new(EmailChannel::new(email_cfg.clone())),
+            channel: Arc::new(
+                EmailChannel::new(email_cfg.clone())
+                    .with_workspace_dir(config.workspace_dir.clone())
+                    .with_image_markers(supports_vision),
+            ),
         });
     }
 
@@ -3086,7 +4431,7 @@ fn collect_configured_channels(
 
 /// Run health checks for configured channels.
 pub async fn doctor_channels(config: Config) -> Result<()> {
-    let mut channels = collect_configured_channels(&config, "health check");
+    let mut channels = collect_configured_channels(&config, false, "health check");
 
     if let Some(ref ns) = config.channels_config.nostr {
         channels.push(ConfiguredChannel {
@@ -3146,22 +4491,34 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
 #[allow(clippy::too_many_lines)]
 pub async fn start_channels(config: Config) -> Result<()> {
     let provider_name = resolved_default_provider(&config);
+    let observer: Arc<dyn Observer> =
+        Arc::from(observability::create_observer(&config.observability));
     let provider_runtime_options = providers::ProviderRuntimeOptions {
         auth_profile_override: None,
         zeroclaw_dir: config.config_path.parent().map(std::path::PathBuf::from),
         secrets_encrypt: config.secrets.encrypt,
         reasoning_enabled: config.runtime.reasoning_enabled,
+        max_tokens: config.runtime.max_tokens,
+        ollama_keep_alive: config.runtime.ollama_keep_alive.clone(),
+        gemini_safety_threshold: config.runtime.gemini_safety_threshold.clone(),
+        observer: Some(Arc::clone(&observer)),
     };
-    let provider: Arc<dyn Provider> = Arc::from(
-        create_resilient_provider_nonblocking(
-            &provider_name,
-            config.api_key.clone(),
-            config.api_url.clone(),
-            config.reliability.clone(),
-            provider_runtime_options.clone(),
-        )
-        .await?,
-    );
+    let provider: Arc<dyn Provider> = Arc::from(providers::recording::with_recording(
+        providers::caching::with_response_cache(
+            create_resilient_provider_nonblocking(
+                &provider_name,
+                config.api_key.clone(),
+                config.api_url.clone(),
+                config.reliability.clone(),
+                provider_runtime_options.clone(),
+            )
+            .await?,
+            &config.memory,
+            &config.workspace_dir,
+        ),
+        &config.runtime,
+        &config.workspace_dir,
+    )?);
 
     // Warm up the provider connection pool (TLS handshake, DNS, HTTP/2 setup)
     // so the first real message doesn't hit a cold-start timeout.
@@ -3183,8 +4540,6 @@ pub async fn start_channels(config: Config) -> Result<()> {
         );
     }
 
-    let observer: Arc<dyn Observer> =
-        Arc::from(observability::create_observer(&config.observability));
     let runtime: Arc<dyn runtime::RuntimeAdapter> =
         Arc::from(runtime::create_runtime(&config.runtime)?);
     let security = Arc::new(SecurityPolicy::from_config(
@@ -3226,67 +4581,11 @@ pub async fn start_channels(config: Config) -> Result<()> {
 
     let skills = crate::skills::load_skills_with_config(&workspace, &config);
 
-    // Collect tool descriptions for the prompt
-    let mut tool_descs: Vec<(&str, &str)> = vec![
-        (
-            "shell",
-            "Execute terminal commands. Use when: running local checks, build/test commands, diagnostics. Don't use when: a safer dedicated tool exists, or command is destructive without approval.",
-        ),
-        (
-            "file_read",
-            "Read file contents. Use when: inspecting project files, configs, logs. Don't use when: a targeted search is enough.",
-        ),
-        (
-            "file_write",
-            "Write file contents. Use when: applying focused edits, scaffolding files, updating docs/code. Don't use when: side effects are unclear or file ownership is uncertain.",
-        ),
-        (
-            "memory_store",
-            "Save to memory. Use when: preserving durable preferences, decisions, key context. Don't use when: information is transient/noisy/sensitive without need.",
-        ),
-        (
-            "memory_recall",
-            "Search memory. Use when: retrieving prior decisions, user preferences, historical context. Don't use when: answer is already in current context.",
-        ),
-        (
-            "memory_forget",
-            "Delete a memory entry. Use when: memory is incorrect/stale or explicitly requested for removal. Don't use when: impact is uncertain.",
-        ),
-    ];
-
-    if config.browser.enabled {
-        tool_descs.push((
-            "browser_open",
-            "Open approved HTTPS URLs in Brave Browser (allowlist-only, no scraping)",
-        ));
-    }
-    if config.composio.enabled {
-        tool_descs.push((
-            "composio",
-            "Execute actions on 1000+ apps via Composio (Gmail, Notion, GitHub, Slack, etc.). Use action='list' to discover actions, 'list_accounts' to retrieve connected account IDs, 'execute' to run (optionally with connected_account_id), and 'connect' for OAuth.",
-        ));
-    }
-    tool_descs.push((
-        "schedule",
-        "Manage scheduled tasks (create/list/get/cancel/pause/resume). Supports recurring cron and one-shot delays.",
-    ));
-    tool_descs.push((
-        "pushover",
-        "Send a Pushover notification to your device. Requires PUSHOVER_TOKEN and PUSHOVER_USER_KEY in .env file.",
-    ));
-    if !config.agents.is_empty() {
-        tool_descs.push((
-            "delegate",
-            "Delegate a subtask to a specialized agent. Use when: a task benefits from a different model (e.g. fast summarization, deep reasoning, code generation). The sub-agent runs a single prompt and returns its response.",
-        ));
-    }
-
-    // Filter out tools excluded for non-CLI channels so the system prompt
-    // does not advertise them for channel-driven runs.
+    // Describe exactly the tools this runtime will dispatch; tools excluded
+    // for non-CLI channels are left out so the prompt never advertises them.
     let excluded = &config.autonomy.non_cli_excluded_tools;
-    if !excluded.is_empty() {
-        tool_descs.retain(|(name, _)| !excluded.iter().any(|ex| ex == name));
-    }
+    let tool_entries = tool_prompt::tool_prompt_entries(tools_registry.as_ref(), excluded);
+    let tool_descs = tool_prompt::as_tool_descs(&tool_entries);
 
     let bootstrap_max_chars = if config.agent.compact_context {
         Some(6000)
@@ -3305,7 +4604,10 @@ pub async fn start_channels(config: Config) -> Result<()> {
         config.skills.prompt_injection_mode,
     );
     if !native_tools {
-        system_prompt.push_str(&build_tool_instructions(tools_registry.as_ref()));
+        system_prompt.push_str(&build_tool_instructions_filtered(
+            tools_registry.as_ref(),
+            excluded,
+        ));
     }
 
     if !skills.is_empty() {
@@ -3321,7 +4623,7 @@ pub async fn start_channels(config: Config) -> Result<()> {
 
     // Collect active channels from a shared builder to keep startup and doctor parity.
     let mut channels: Vec<Arc<dyn Channel>> =
-        collect_configured_channels(&config, "runtime startup")
+        collect_configured_channels(&config, provider.supports_vision(), "runtime startup")
             .into_iter()
             .map(|configured| configured.channel)
             .collect();
@@ -3404,14 +4706,50 @@ pub async fn start_channels(config: Config) -> Result<()> {
         .telegram
         .as_ref()
         .is_some_and(|tg| tg.interrupt_on_new_message);
+    let tool_policy = Arc::new(crate::security::ToolPolicy::from_config(
+        &config.autonomy.tool_policy,
+    )?);
     let task_engine_cfg = crate::agent::task_engine::TaskEngineConfig {
         gray_zone_verifier_enabled: config.autonomy.gray_zone_verifier_enabled,
         gray_zone_verifier_timeout_ms: config.autonomy.gray_zone_verifier_timeout_ms,
+        task_timeout_secs: config.autonomy.task_timeout_secs,
+        completion_policy: config.autonomy.completion_policy.clone(),
+        channel_completion_policies: config.autonomy.channel_completion_policies.clone(),
+        task_env: config.autonomy.task_env.clone(),
+        duplicate_task_window_mins: config.autonomy.duplicate_task_window_mins,
+        fallback_providers: config.autonomy.task_fallback_providers.clone(),
+        budget: config.autonomy.task_budget.clone(),
+        model_prices: config.cost.prices.clone(),
+        report_cost: config.cost.report_cost,
+        report_tool_calls: config.autonomy.task_progress_tool_calls,
+        snapshot_workspace_files: config.autonomy.task_file_snapshots,
+        decompose_steps: config.autonomy.task_step_decomposition,
+        acceptance_tests: config.autonomy.task_acceptance_tests.clone(),
+        back_up_writes: config.autonomy.task_write_backups,
+        security: Arc::clone(&security),
+        reply_outbox: true,
+        max_parallel_tools: config.agent.tool_concurrency(),
+        context_max_tokens: config.agent.context_max_tokens,
+        provider_params: config.agent.provider_params.clone(),
+        tool_policy: Arc::clone(&tool_policy),
         ..crate::agent::task_engine::TaskEngineConfig::default()
     };
     let task_engine =
         match crate::agent::task_engine::TaskEngine::new(&config.workspace_dir, task_engine_cfg) {
-            Ok(engine) => Some(Arc::new(engine)),
+            Ok(engine) => {
+                let engine = engine.with_request_embedder(memory::create_embedder(
+                    &config.memory,
+                    &config.embedding_routes,
+                    config.api_key.as_deref(),
+                ));
+                archive_finished_tasks(&engine, config.autonomy.task_archive_after_days);
+                move_tasks_to_cold_storage(
+                    &engine,
+                    &config.workspace_dir,
+                    config.autonomy.task_cold_storage_after_days,
+                );
+                Some(Arc::new(engine))
+            }
             Err(err) => {
                 tracing::warn!(
                     "Failed to initialize task engine; iMessage autonomous mode disabled: {err}"
@@ -3432,6 +4770,7 @@ pub async fn start_channels(config: Config) -> Result<()> {
         temperature,
         auto_save_memory: config.memory.auto_save,
         max_tool_iterations: config.agent.max_tool_iterations,
+        max_parallel_tools: config.agent.tool_concurrency(),
         min_relevance_score: config.memory.min_relevance_score,
         conversation_histories: Arc::new(Mutex::new(HashMap::new())),
         provider_cache: Arc::new(Mutex::new(provider_cache_seed)),
@@ -3454,11 +4793,36 @@ pub async fn start_channels(config: Config) -> Result<()> {
             None
         },
         non_cli_excluded_tools: Arc::new(config.autonomy.non_cli_excluded_tools.clone()),
+        tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::from_config(
+            &config.autonomy,
+        )),
+        tool_policy,
         task_engine,
+        model_router: crate::agent::model_router::ModelRouter::new(
+            config.query_classification.clone(),
+            config.model_routes.clone(),
+        ),
+        memory_config: Arc::new(config.memory.clone()),
     });
 
     recover_pending_imessage_tasks(Arc::clone(&runtime_ctx));
-    run_message_dispatch_loop(rx, runtime_ctx, max_in_flight_messages).await;
+    spawn_stuck_task_detector(
+        Arc::clone(&runtime_ctx),
+        config.autonomy.task_stuck_after_mins,
+    );
+    spawn_task_outbox_worker(Arc::clone(&runtime_ctx));
+    spawn_task_store_maintenance(
+        Arc::clone(&runtime_ctx),
+        config.autonomy.task_archive_after_days,
+        config.autonomy.task_cold_storage_after_days,
+    );
+    run_message_dispatch_loop(
+        rx,
+        runtime_ctx,
+        max_in_flight_messages,
+        config.channels_config.queue_weights.clone(),
+    )
+    .await;
 
     // Wait for all channel tasks
     for h in handles {
@@ -3471,6 +4835,7 @@ pub async fn start_channels(config: Config) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::loop_::build_tool_instructions;
     use crate::memory::{Memory, MemoryCategory, SqliteMemory};
     use crate::observability::NoopObserver;
     use crate::providers::{ChatMessage, Provider};
@@ -3655,6 +5020,7 @@ mod tests {
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(histories)),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -3669,7 +5035,11 @@ mod tests {
             workspace_dir: Arc::new(std::env::temp_dir()),
             message_timeout_secs: CHANNEL_MESSAGE_TIMEOUT_SECS,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         };
 
         assert!(compact_sender_history(&ctx, &sender));
@@ -3705,6 +5075,7 @@ mod tests {
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -3719,7 +5090,11 @@ mod tests {
             workspace_dir: Arc::new(std::env::temp_dir()),
             message_timeout_secs: CHANNEL_MESSAGE_TIMEOUT_SECS,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         };
 
         append_sender_turn(&ctx, &sender, ChatMessage::user("hello"));
@@ -3758,6 +5133,7 @@ mod tests {
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(histories)),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -3772,7 +5148,11 @@ mod tests {
             workspace_dir: Arc::new(std::env::temp_dir()),
             message_timeout_secs: CHANNEL_MESSAGE_TIMEOUT_SECS,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         };
 
         assert!(rollback_orphan_user_turn(&ctx, &sender, "pending"));
@@ -4310,6 +5690,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 10,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -4322,7 +5703,11 @@ BTC is currently around $65,000 based on latest tool output."#
             message_timeout_secs: CHANNEL_MESSAGE_TIMEOUT_SECS,
             interrupt_on_new_message: false,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
         });
@@ -4370,6 +5755,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 10,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -4382,7 +5768,11 @@ BTC is currently around $65,000 based on latest tool output."#
             message_timeout_secs: CHANNEL_MESSAGE_TIMEOUT_SECS,
             interrupt_on_new_message: false,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
         });
@@ -4444,6 +5834,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 10,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -4458,7 +5849,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -4504,6 +5899,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 10,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -4518,7 +5914,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -4573,6 +5973,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(provider_cache_seed)),
@@ -4587,7 +5988,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -4648,6 +6053,8 @@ BTC is currently around $65,000 based on latest tool output."#
                 provider_retry_limit: 0,
                 gray_zone_verifier_enabled: false,
                 gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 0,
+                ..crate::agent::task_engine::TaskEngineConfig::default()
             },
         )
         .expect("task engine");
@@ -4664,6 +6071,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -4678,7 +6086,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: Some(Arc::new(task_engine)),
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -4725,6 +6137,140 @@ BTC is currently around $65,000 based on latest tool output."#
         assert!(attempts >= 2);
     }
 
+    #[tokio::test]
+    async fn continue_reply_resumes_the_blocked_task_and_approves_its_call() {
+        let channel_impl = Arc::new(IMessageRecordingChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+        let mut channels_by_name = HashMap::new();
+        channels_by_name.insert(channel.name().to_string(), channel);
+
+        let tmp = TempDir::new().expect("tempdir");
+        let workspace = tmp.path().join("workspace");
+        std::fs::create_dir_all(&workspace).expect("workspace dir");
+
+        let tool_call = r#"<tool_call>
+{"name":"mock_price","arguments":{"symbol":"BTC"}}
+</tool_call>"#;
+        let provider_impl = Arc::new(ScriptedResponseProvider::new(vec![
+            Ok(tool_call.to_string()),
+            Ok(tool_call.to_string()),
+            Ok("BTC 当前价格为 65000 美元，任务已完成。".to_string()),
+        ]));
+        let policy = crate::security::ToolPolicy::from_config(&crate::config::ToolPolicyConfig {
+            rules: crate::config::ToolPolicyRules {
+                confirm_tools: vec!["mock_price".into()],
+                ..crate::config::ToolPolicyRules::default()
+            },
+            ..crate::config::ToolPolicyConfig::default()
+        })
+        .expect("tool policy");
+        let task_engine = Arc::new(
+            crate::agent::task_engine::TaskEngine::new(
+                &workspace,
+                crate::agent::task_engine::TaskEngineConfig {
+                    provider_retry_limit: 0,
+                    gray_zone_verifier_enabled: false,
+                    task_timeout_secs: 0,
+                    tool_policy: Arc::new(policy),
+                    ..crate::agent::task_engine::TaskEngineConfig::default()
+                },
+            )
+            .expect("task engine"),
+        );
+
+        let runtime_ctx = Arc::new(ChannelRuntimeContext {
+            channels_by_name: Arc::new(channels_by_name),
+            provider: provider_impl.clone(),
+            default_provider: Arc::new("test-provider".to_string()),
+            memory: Arc::new(NoopMemory),
+            tools_registry: Arc::new(vec![Box::new(MockPriceTool)]),
+            observer: Arc::new(NoopObserver),
+            system_prompt: Arc::new("test-system-prompt".to_string()),
+            model: Arc::new("test-model".to_string()),
+            temperature: 0.0,
+            auto_save_memory: false,
+            max_tool_iterations: 5,
+            max_parallel_tools: 1,
+            min_relevance_score: 0.0,
+            conversation_histories: Arc::new(Mutex::new(HashMap::new())),
+            provider_cache: Arc::new(Mutex::new(HashMap::new())),
+            route_overrides: Arc::new(Mutex::new(HashMap::new())),
+            api_key: None,
+            api_url: None,
+            reliability: Arc::new(crate::config::ReliabilityConfig::default()),
+            provider_runtime_options: providers::ProviderRuntimeOptions::default(),
+            workspace_dir: Arc::new(workspace.clone()),
+            message_timeout_secs: CHANNEL_MESSAGE_TIMEOUT_SECS,
+            interrupt_on_new_message: false,
+            multimodal: crate::config::MultimodalConfig::default(),
+            hooks: None,
+            non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
+            task_engine: Some(Arc::clone(&task_engine)),
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
+        });
+        let message = |id: &str, content: &str| traits::ChannelMessage {
+            id: id.to_string(),
+            sender: "alice".to_string(),
+            reply_target: "imessage-chat".to_string(),
+            content: content.to_string(),
+            channel: "imessage".to_string(),
+            timestamp: 1,
+            thread_ts: None,
+        };
+
+        process_channel_message(
+            runtime_ctx.clone(),
+            message("msg-1", "查一下 BTC 价格"),
+            CancellationToken::new(),
+        )
+        .await;
+        let blocked = task_engine
+            .store()
+            .latest_sender_task_with_status(
+                "imessage",
+                "alice",
+                crate::agent::task_types::TaskStatus::Blocked,
+            )
+            .expect("lookup")
+            .expect("the confirmable call blocks the task");
+        assert!(task_engine
+            .store()
+            .list_tool_invocations(&blocked.id)
+            .expect("list invocations")
+            .is_empty());
+
+        process_channel_message(
+            runtime_ctx.clone(),
+            message("msg-2", "continue"),
+            CancellationToken::new(),
+        )
+        .await;
+
+        // The same task ran again, this time with the call approved.
+        let task = task_engine
+            .store()
+            .get_task_run(&blocked.id)
+            .expect("lookup")
+            .expect("task");
+        assert_ne!(task.status, crate::agent::task_types::TaskStatus::Blocked);
+        assert_ne!(task.status, crate::agent::task_types::TaskStatus::Cancelled);
+        let invocations = task_engine
+            .store()
+            .list_tool_invocations(&blocked.id)
+            .expect("list invocations");
+        assert_eq!(invocations.len(), 1);
+        assert!(invocations[0].success);
+        let db_path = workspace.join("state").join("task-runs.db");
+        let conn = rusqlite::Connection::open(db_path).expect("task db");
+        let runs: i64 = conn
+            .query_row("SELECT COUNT(*) FROM task_runs", [], |row| row.get(0))
+            .expect("count task runs");
+        assert_eq!(runs, 1);
+    }
+
     #[tokio::test]
     async fn process_channel_message_uses_route_override_provider_and_model() {
         let channel_impl = Arc::new(TelegramRecordingChannel::default());
@@ -4764,6 +6310,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(provider_cache_seed)),
@@ -4778,7 +6325,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -4836,6 +6387,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(provider_cache_seed)),
@@ -4850,7 +6402,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -4920,6 +6476,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(provider_cache_seed)),
@@ -4937,7 +6494,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -4995,6 +6556,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 12,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -5009,7 +6571,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -5056,6 +6622,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 3,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -5070,7 +6637,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -5228,6 +6799,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 10,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -5242,7 +6814,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(4);
@@ -5271,7 +6847,7 @@ BTC is currently around $65,000 based on latest tool output."#
         drop(tx);
 
         let started = Instant::now();
-        run_message_dispatch_loop(rx, runtime_ctx, 2).await;
+        run_message_dispatch_loop(rx, runtime_ctx, 2, HashMap::new()).await;
         let elapsed = started.elapsed();
 
         assert!(
@@ -5309,6 +6885,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 10,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -5323,7 +6900,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(8);
@@ -5353,7 +6934,7 @@ BTC is currently around $65,000 based on latest tool output."#
             .unwrap();
         });
 
-        run_message_dispatch_loop(rx, runtime_ctx, 4).await;
+        run_message_dispatch_loop(rx, runtime_ctx, 4, HashMap::new()).await;
         send_task.await.unwrap();
 
         let sent_messages = channel_impl.sent_messages.lock().await;
@@ -5402,6 +6983,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 10,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -5416,7 +6998,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(8);
@@ -5446,7 +7032,7 @@ BTC is currently around $65,000 based on latest tool output."#
             .unwrap();
         });
 
-        run_message_dispatch_loop(rx, runtime_ctx, 4).await;
+        run_message_dispatch_loop(rx, runtime_ctx, 4, HashMap::new()).await;
         send_task.await.unwrap();
 
         let sent_messages = channel_impl.sent_messages.lock().await;
@@ -5477,6 +7063,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 10,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -5491,7 +7078,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -5537,6 +7128,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 10,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -5551,7 +7143,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -6062,6 +7658,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -6076,7 +7673,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -6148,6 +7749,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -6162,7 +7764,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -6234,6 +7840,7 @@ BTC is currently around $65,000 based on latest tool output."#
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(histories)),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -6248,7 +7855,11 @@ BTC is currently around $65,000 based on latest tool output."#
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -6566,7 +8177,7 @@ This is an example JSON object for profile settings."#;
             mention_only: Some(false),
         });
 
-        let channels = collect_configured_channels(&config, "test");
+        let channels = collect_configured_channels(&config, false, "test");
 
         assert!(channels
             .iter()
@@ -6784,6 +8395,7 @@ This is an example JSON object for profile settings."#;
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -6798,7 +8410,11 @@ This is an example JSON object for profile settings."#;
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         // Simulate a photo attachment message with [IMAGE:] marker.
@@ -6851,6 +8467,7 @@ This is an example JSON object for profile settings."#;
             temperature: 0.0,
             auto_save_memory: false,
             max_tool_iterations: 5,
+            max_parallel_tools: 1,
             min_relevance_score: 0.0,
             conversation_histories: Arc::new(Mutex::new(HashMap::new())),
             provider_cache: Arc::new(Mutex::new(HashMap::new())),
@@ -6865,7 +8482,11 @@ This is an example JSON object for profile settings."#;
             multimodal: crate::config::MultimodalConfig::default(),
             hooks: None,
             non_cli_excluded_tools: Arc::new(Vec::new()),
+            tool_exclusions: Arc::new(tool_exclusions::ToolExclusionPolicy::default()),
+            tool_policy: Arc::new(crate::security::ToolPolicy::default()),
             task_engine: None,
+            model_router: crate::agent::model_router::ModelRouter::default(),
+            memory_config: Arc::new(crate::config::MemoryConfig::default()),
         });
 
         process_channel_message(
@@ -0,0 +1,121 @@
+//! `/pin`: instructions that stay in context for the rest of a conversation.
+//!
+//! Pins are stored per sender in the task store, outside the conversation
+//! cache, so history compaction, overflow trimming, and `/undo` never drop
+//! them. Every message rebuilds the system prompt with the pinned block ahead
+//! of the conversation turns. Hooks can add pins of their own through
+//! `HookHandler::pinned_instructions`.
+
+use crate::agent::task_types::PinnedInstructionRecord;
+use crate::util::truncate_with_ellipsis;
+use std::fmt::Write;
+
+/// Pins kept per conversation; `/pin` is refused beyond this.
+pub const MAX_PINNED_INSTRUCTIONS: usize = 20;
+
+/// Longest instruction accepted by `/pin`.
+pub const MAX_PIN_CHARS: usize = 1000;
+
+/// Characters of each pin shown by `/pins`.
+const PIN_PREVIEW_CHARS: usize = 120;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinCommand {
+    /// `/pin <text>`.
+    Pin(String),
+    /// `/pin` alone pins the sender's previous message.
+    PinPrevious,
+    /// `/pins`.
+    List,
+    /// `/unpin <n>`, 1-based as listed by `/pins`.
+    Unpin(usize),
+    /// `/unpin all`.
+    UnpinAll,
+    Invalid(String),
+}
+
+pub fn parse_pin_command(content: &str) -> Option<PinCommand> {
+    let trimmed = content.trim();
+    let (command, rest) = trimmed
+        .split_once(char::is_whitespace)
+        .unwrap_or((trimmed, ""));
+    let rest = rest.trim();
+    let parsed = match command.to_ascii_lowercase().as_str() {
+        "/pin" if rest.is_empty() => PinCommand::PinPrevious,
+        "/pin" if rest.chars().count() > MAX_PIN_CHARS => PinCommand::Invalid(format!(
+            "Pinned instructions are limited to {MAX_PIN_CHARS} characters."
+        )),
+        "/pin" => PinCommand::Pin(rest.to_string()),
+        "/pins" => PinCommand::List,
+        "/unpin" if rest.eq_ignore_ascii_case("all") => PinCommand::UnpinAll,
+        "/unpin" => match rest.trim_start_matches('#').parse::<usize>() {
+            Ok(index) if index > 0 => PinCommand::Unpin(index),
+            _ => PinCommand::Invalid(
+                "Usage: `/unpin <number>` (see `/pins`) or `/unpin all`.".to_string(),
+            ),
+        },
+        _ => return None,
+    };
+    Some(parsed)
+}
+
+/// Block appended to the system prompt, or `None` without pins.
+pub fn render_pinned_block(pins: &[String]) -> Option<String> {
+    if pins.is_empty() {
+        return None;
+    }
+    let mut block = String::from(
+        "## Pinned Instructions\n\nThe user pinned these instructions. Follow them for the whole conversation, even when earlier messages are no longer shown:\n",
+    );
+    for (index, pin) in pins.iter().enumerate() {
+        let _ = write!(block, "\n{}. {}", index + 1, pin.trim());
+    }
+    Some(block)
+}
+
+/// Reply to `/pins`.
+pub fn render_pin_list(pins: &[PinnedInstructionRecord]) -> String {
+    if pins.is_empty() {
+        return "No pinned instructions. Use `/pin <instruction>` to add one.".to_string();
+    }
+    let mut reply = String::from("📌 Pinned instructions:");
+    for (index, pin) in pins.iter().enumerate() {
+        let _ = write!(
+            reply,
+            "\n{}. {}",
+            index + 1,
+            truncate_with_ellipsis(pin.content.trim(), PIN_PREVIEW_CHARS)
+        );
+    }
+    reply.push_str("\nRemove one with `/unpin <number>`.");
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pin_commands_parse_and_pins_render_as_a_numbered_block() {
+        assert_eq!(
+            parse_pin_command("/pin Always answer in English"),
+            Some(PinCommand::Pin("Always answer in English".to_string()))
+        );
+        assert_eq!(parse_pin_command(" /PIN "), Some(PinCommand::PinPrevious));
+        assert_eq!(parse_pin_command("/pins"), Some(PinCommand::List));
+        assert_eq!(parse_pin_command("/unpin #2"), Some(PinCommand::Unpin(2)));
+        assert_eq!(parse_pin_command("/unpin all"), Some(PinCommand::UnpinAll));
+        assert!(matches!(
+            parse_pin_command("/unpin 0"),
+            Some(PinCommand::Invalid(_))
+        ));
+        assert_eq!(parse_pin_command("/pinned"), None);
+        assert_eq!(parse_pin_command("pin this"), None);
+
+        assert_eq!(render_pinned_block(&[]), None);
+        let block =
+            render_pinned_block(&["Use metric units.".into(), "No emoji.".into()]).expect("block");
+        assert!(block.starts_with("## Pinned Instructions"));
+        assert!(block.ends_with("\n1. Use metric units.\n2. No emoji."));
+    }
+}
@@ -0,0 +1,452 @@
+//! Per-sender conversation settings changed from chat.
+//!
+//! `/verbose`, `/language`, `/model`, `/notifications`, and
+//! `/tools enable|disable` persist a setting for the sender in the task store;
+//! `/settings` lists the current values. The channel runtime reads them back
+//! on every message: the language goes into the system prompt, the model into
+//! route selection, disabled tools into the excluded-tool list, and verbosity
+//! and notification mode into how task progress is delivered.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+const VERBOSE_KEY: &str = "verbose";
+const LANGUAGE_KEY: &str = "language";
+const MODEL_KEY: &str = "model";
+const NOTIFICATIONS_KEY: &str = "notifications";
+const DISABLED_TOOLS_KEY: &str = "disabled_tools";
+
+/// Values that reset a setting to its default.
+const RESET_VALUES: &[&str] = &["default", "reset", "auto", "off"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotificationMode {
+    /// Send each progress update as it happens.
+    #[default]
+    Immediate,
+    /// Collect progress updates and deliver them with the final reply.
+    Digest,
+}
+
+impl NotificationMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Immediate => "immediate",
+            Self::Digest => "digest",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "immediate" | "instant" => Some(Self::Immediate),
+            "digest" | "summary" => Some(Self::Digest),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SenderSettings {
+    /// Deliver per-round task progress messages.
+    pub verbose: bool,
+    /// Reply language code (e.g. `zh`, `en`); `None` follows the user.
+    pub language: Option<String>,
+    /// Model id or `hint:<route>` used instead of the channel default.
+    pub model: Option<String>,
+    pub notifications: NotificationMode,
+    /// Tools turned off with `/tools disable`, on top of config exclusions.
+    pub disabled_tools: BTreeSet<String>,
+}
+
+impl Default for SenderSettings {
+    fn default() -> Self {
+        Self {
+            verbose: true,
+            language: None,
+            model: None,
+            notifications: NotificationMode::Immediate,
+            disabled_tools: BTreeSet::new(),
+        }
+    }
+}
+
+impl SenderSettings {
+    /// Build settings from the raw key/value pairs kept in the task store.
+    /// Unknown keys and unparsable values fall back to defaults.
+    pub fn from_stored(raw: &BTreeMap<String, String>) -> Self {
+        let mut settings = Self::default();
+        if let Some(value) = raw.get(VERBOSE_KEY) {
+            settings.verbose = value != "off";
+        }
+        settings.language = raw.get(LANGUAGE_KEY).cloned();
+        settings.model = raw.get(MODEL_KEY).cloned();
+        if let Some(mode) = raw
+            .get(NOTIFICATIONS_KEY)
+            .and_then(|value| NotificationMode::parse(value))
+        {
+            settings.notifications = mode;
+        }
+        if let Some(value) = raw.get(DISABLED_TOOLS_KEY) {
+            settings.disabled_tools = value
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        settings
+    }
+
+    /// System-prompt line pinning the reply language, if one is set.
+    pub fn language_instruction(&self) -> Option<String> {
+        let code = self.language.as_deref()?;
+        Some(format!(
+            "## Reply Language\n\nAlways reply in {} (`{code}`) unless the user explicitly asks for another language in the current message.",
+            language_name(code)
+        ))
+    }
+
+    pub fn is_tool_disabled(&self, tool: &str) -> bool {
+        self.disabled_tools.contains(tool)
+    }
+
+    /// Setting key and stored value that turn `tool` on or off for this
+    /// sender while keeping the other disabled tools; re-enabling the last
+    /// one resets the setting.
+    pub fn tool_toggle(&self, tool: &str, enabled: bool) -> (&'static str, Option<String>) {
+        let mut disabled = self.disabled_tools.clone();
+        if enabled {
+            disabled.remove(tool);
+        } else {
+            disabled.insert(tool.to_string());
+        }
+        let value =
+            (!disabled.is_empty()).then(|| disabled.into_iter().collect::<Vec<_>>().join(","));
+        (DISABLED_TOOLS_KEY, value)
+    }
+}
+
+fn language_name(code: &str) -> &str {
+    match code {
+        "zh" | "zh-cn" | "zh-hans" => "Simplified Chinese",
+        "zh-tw" | "zh-hant" => "Traditional Chinese",
+        "en" => "English",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "fr" => "French",
+        "de" => "German",
+        "es" => "Spanish",
+        "vi" => "Vietnamese",
+        other => other,
+    }
+}
+
+/// A parsed settings command. `value: None` resets the setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SenderSettingsCommand {
+    Show,
+    Set {
+        key: &'static str,
+        value: Option<String>,
+    },
+    /// `/tools`: list the tools and whether each is on for this sender.
+    ShowTools,
+    /// `/tools enable <name>` or `/tools disable <name>`.
+    ToggleTool {
+        name: String,
+        enabled: bool,
+    },
+    Invalid(String),
+}
+
+/// Parse `/verbose`, `/language`, `/model`, `/notifications`, `/tools`, or
+/// `/settings`.
+///
+/// `/model` is only claimed here on channels without the built-in runtime
+/// model switch, so Telegram/Discord keep their existing `/model` behaviour.
+pub fn parse_sender_settings_command(
+    content: &str,
+    handles_model: bool,
+) -> Option<SenderSettingsCommand> {
+    let trimmed = content.trim();
+    if !trimmed.starts_with('/') {
+        return None;
+    }
+    let mut parts = trimmed.split_whitespace();
+    let command = parts.next()?.to_ascii_lowercase();
+    let value = parts.collect::<Vec<_>>().join(" ");
+    let value = value.trim().trim_matches('`');
+    let lower = value.to_ascii_lowercase();
+    let reset = RESET_VALUES.contains(&lower.as_str());
+
+    let parsed = match command.as_str() {
+        "/settings" => SenderSettingsCommand::Show,
+        "/verbose" => match lower.as_str() {
+            "on" | "true" | "1" => set(VERBOSE_KEY, None),
+            "off" | "false" | "0" => set(VERBOSE_KEY, Some("off".to_string())),
+            _ => invalid("Usage: `/verbose on` or `/verbose off`."),
+        },
+        "/language" | "/lang" => {
+            if value.is_empty() {
+                invalid("Usage: `/language <code>` (e.g. `zh`, `en`) or `/language auto`.")
+            } else if reset {
+                set(LANGUAGE_KEY, None)
+            } else {
+                set(LANGUAGE_KEY, Some(lower))
+            }
+        }
+        "/model" if handles_model => {
+            if value.is_empty() {
+                SenderSettingsCommand::Show
+            } else if reset {
+                set(MODEL_KEY, None)
+            } else {
+                set(MODEL_KEY, Some(model_setting(value)))
+            }
+        }
+        "/notifications" | "/notify" => match NotificationMode::parse(&lower) {
+            Some(NotificationMode::Immediate) => set(NOTIFICATIONS_KEY, None),
+            Some(mode) => set(NOTIFICATIONS_KEY, Some(mode.as_str().to_string())),
+            None => invalid("Usage: `/notifications immediate` or `/notifications digest`."),
+        },
+        "/tools" => parse_tools_command(value),
+        _ => return None,
+    };
+    Some(parsed)
+}
+
+fn parse_tools_command(value: &str) -> SenderSettingsCommand {
+    let mut parts = value.split_whitespace();
+    let Some(action) = parts.next() else {
+        return SenderSettingsCommand::ShowTools;
+    };
+    let enabled = match action.to_ascii_lowercase().as_str() {
+        "enable" | "on" => true,
+        "disable" | "off" => false,
+        _ => return invalid(TOOLS_USAGE),
+    };
+    match (parts.next(), parts.next()) {
+        (Some(name), None) => SenderSettingsCommand::ToggleTool {
+            name: name.trim_matches('`').to_string(),
+            enabled,
+        },
+        _ => invalid(TOOLS_USAGE),
+    }
+}
+
+const TOOLS_USAGE: &str = "Usage: `/tools`, `/tools enable <name>`, or `/tools disable <name>`.";
+
+fn set(key: &'static str, value: Option<String>) -> SenderSettingsCommand {
+    SenderSettingsCommand::Set { key, value }
+}
+
+fn invalid(usage: &str) -> SenderSettingsCommand {
+    SenderSettingsCommand::Invalid(usage.to_string())
+}
+
+/// Bare lowercase words such as `cheap` or `reasoning` name a
+/// `[[model_routes]]` hint; anything else is taken as a model id.
+fn model_setting(value: &str) -> String {
+    if value.chars().all(|c| c.is_ascii_lowercase()) {
+        format!("hint:{value}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Human-readable summary of the sender's effective settings.
+pub fn render_sender_settings(settings: &SenderSettings) -> String {
+    let mut out = String::from("Current settings for this conversation:");
+    let _ = write!(
+        out,
+        "\n- verbose: {}",
+        if settings.verbose { "on" } else { "off" }
+    );
+    let _ = write!(
+        out,
+        "\n- language: {}",
+        settings.language.as_deref().unwrap_or("auto")
+    );
+    let _ = write!(
+        out,
+        "\n- model: {}",
+        settings.model.as_deref().unwrap_or("default")
+    );
+    let _ = write!(
+        out,
+        "\n- notifications: {}",
+        settings.notifications.as_str()
+    );
+    let _ = write!(
+        out,
+        "\n- disabled tools: {}",
+        if settings.disabled_tools.is_empty() {
+            "none".to_string()
+        } else {
+            settings
+                .disabled_tools
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    );
+    out
+}
+
+/// `/tools` reply: every registered tool and whether it is on for the sender.
+pub fn render_tools(tool_names: &[&str], settings: &SenderSettings) -> String {
+    let mut out = String::from("Tools for this conversation:");
+    for name in tool_names {
+        let state = if settings.is_tool_disabled(name) {
+            "off"
+        } else {
+            "on"
+        };
+        let _ = write!(out, "\n- {name}: {state}");
+    }
+    out.push_str("\n\nUse `/tools disable <name>` or `/tools enable <name>` to change one.");
+    out
+}
+
+/// Confirmation sent after `/tools enable|disable`.
+pub fn render_tool_toggled(name: &str, enabled: bool) -> String {
+    if enabled {
+        format!("Tool `{name}` is enabled again for this conversation.")
+    } else {
+        format!(
+            "Tool `{name}` is disabled for this conversation until you run `/tools enable {name}`."
+        )
+    }
+}
+
+/// Confirmation sent after a setting changes.
+pub fn render_setting_updated(key: &str, value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("Setting `{key}` is now `{value}` for this conversation."),
+        None => format!("Setting `{key}` reset to its default for this conversation."),
+    }
+}
+
+/// Fold buffered progress updates into the final reply for digest delivery.
+pub fn prepend_progress_digest(progress: &[String], response: &str) -> String {
+    if progress.is_empty() {
+        return response.to_string();
+    }
+    let mut out = String::from("📋 Progress digest:");
+    for line in progress {
+        let _ = write!(out, "\n- {line}");
+    }
+    let _ = write!(out, "\n\n{response}");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_setting_commands_and_resets() {
+        assert_eq!(
+            parse_sender_settings_command("/verbose off", true),
+            Some(set(VERBOSE_KEY, Some("off".into())))
+        );
+        assert_eq!(
+            parse_sender_settings_command("/verbose on", true),
+            Some(set(VERBOSE_KEY, None))
+        );
+        assert_eq!(
+            parse_sender_settings_command("/language ZH", true),
+            Some(set(LANGUAGE_KEY, Some("zh".into())))
+        );
+        assert_eq!(
+            parse_sender_settings_command("/language auto", true),
+            Some(set(LANGUAGE_KEY, None))
+        );
+        assert_eq!(
+            parse_sender_settings_command("/notifications digest", true),
+            Some(set(NOTIFICATIONS_KEY, Some("digest".into())))
+        );
+        assert!(matches!(
+            parse_sender_settings_command("/verbose maybe", true),
+            Some(SenderSettingsCommand::Invalid(_))
+        ));
+        assert_eq!(
+            parse_sender_settings_command("/settings", true),
+            Some(SenderSettingsCommand::Show)
+        );
+        assert!(parse_sender_settings_command("verbose off", true).is_none());
+    }
+
+    #[test]
+    fn model_command_maps_route_hints_and_respects_runtime_switch() {
+        assert_eq!(
+            parse_sender_settings_command("/model cheap", true),
+            Some(set(MODEL_KEY, Some("hint:cheap".into())))
+        );
+        assert_eq!(
+            parse_sender_settings_command("/model gpt-4o-mini", true),
+            Some(set(MODEL_KEY, Some("gpt-4o-mini".into())))
+        );
+        assert!(parse_sender_settings_command("/model cheap", false).is_none());
+    }
+
+    #[test]
+    fn stored_values_resolve_to_settings() {
+        let mut raw = BTreeMap::new();
+        assert_eq!(SenderSettings::from_stored(&raw), SenderSettings::default());
+        assert!(SenderSettings::default().language_instruction().is_none());
+
+        raw.insert(VERBOSE_KEY.to_string(), "off".to_string());
+        raw.insert(LANGUAGE_KEY.to_string(), "zh".to_string());
+        raw.insert(NOTIFICATIONS_KEY.to_string(), "digest".to_string());
+        let settings = SenderSettings::from_stored(&raw);
+        assert!(!settings.verbose);
+        assert_eq!(settings.notifications, NotificationMode::Digest);
+        assert!(settings
+            .language_instruction()
+            .is_some_and(|line| line.contains("Simplified Chinese")));
+        assert!(render_sender_settings(&settings).contains("language: zh"));
+    }
+
+    #[test]
+    fn tools_command_toggles_the_disabled_set() {
+        assert_eq!(
+            parse_sender_settings_command("/tools", true),
+            Some(SenderSettingsCommand::ShowTools)
+        );
+        assert_eq!(
+            parse_sender_settings_command("/tools disable shell", true),
+            Some(SenderSettingsCommand::ToggleTool {
+                name: "shell".into(),
+                enabled: false,
+            })
+        );
+        assert!(matches!(
+            parse_sender_settings_command("/tools drop shell", true),
+            Some(SenderSettingsCommand::Invalid(_))
+        ));
+
+        let mut raw = BTreeMap::new();
+        raw.insert(DISABLED_TOOLS_KEY.to_string(), "shell".to_string());
+        let settings = SenderSettings::from_stored(&raw);
+        assert!(settings.is_tool_disabled("shell"));
+        assert_eq!(
+            settings.tool_toggle("browser", false),
+            (DISABLED_TOOLS_KEY, Some("browser,shell".into()))
+        );
+        assert_eq!(
+            settings.tool_toggle("shell", true),
+            (DISABLED_TOOLS_KEY, None)
+        );
+        assert!(render_tools(&["file_read", "shell"], &settings).contains("- shell: off"));
+    }
+
+    #[test]
+    fn digest_prefixes_progress_lines() {
+        assert_eq!(prepend_progress_digest(&[], "done"), "done");
+        let digest = prepend_progress_digest(&["round 1".into(), "round 2".into()], "done");
+        assert!(digest.starts_with("📋 Progress digest:\n- round 1\n- round 2"));
+        assert!(digest.ends_with("\n\ndone"));
+    }
+}
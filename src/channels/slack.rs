@@ -1,6 +1,7 @@
 use super::traits::{Channel, ChannelMessage, SendMessage};
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::path::Path;
 use std::time::{Duration, Instant};
 
 /// Slack channel — polls conversations.history via Web API
@@ -64,6 +65,33 @@ impl SlackChannel {
             .map(ToOwned::to_owned)
     }
 
+    /// POST to a Slack Web API method and return the parsed body, treating a
+    /// JSON `"ok": false` as an error like a non-2xx status.
+    async fn call_api(
+        &self,
+        method: &str,
+        request: reqwest::RequestBuilder,
+    ) -> anyhow::Result<serde_json::Value> {
+        let resp = request.bearer_auth(&self.bot_token).send().await?;
+        let status = resp.status();
+        let body = resp
+            .text()
+            .await
+            .unwrap_or_else(|e| format!("<failed to read response body: {e}>"));
+        if !status.is_success() {
+            anyhow::bail!("Slack {method} failed ({status}): {body}");
+        }
+        let parsed: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+        if parsed.get("ok") == Some(&serde_json::Value::Bool(false)) {
+            let err = parsed
+                .get("error")
+                .and_then(|e| e.as_str())
+                .unwrap_or("unknown");
+            anyhow::bail!("Slack {method} failed: {err}");
+        }
+        Ok(parsed)
+    }
+
     fn configured_channel_id(&self) -> Option<String> {
         Self::normalized_channel_id(self.channel_id.as_deref())
     }
@@ -207,6 +235,71 @@ impl Channel for SlackChannel {
         Ok(())
     }
 
+    /// Upload with Slack's external upload flow: reserve an upload URL, send
+    /// the bytes there, then share the file into the channel (and thread).
+    async fn send_file(
+        &self,
+        recipient: &str,
+        thread_ts: Option<&str>,
+        path: &Path,
+        caption: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("file")
+            .to_string();
+        let bytes = tokio::fs::read(path).await?;
+        let length = bytes.len().to_string();
+
+        let client = self.http_client();
+        let reserved = self
+            .call_api(
+                "files.getUploadURLExternal",
+                client
+                    .post("https://slack.com/api/files.getUploadURLExternal")
+                    .form(&[
+                        ("filename", file_name.as_str()),
+                        ("length", length.as_str()),
+                    ]),
+            )
+            .await?;
+        let (Some(upload_url), Some(file_id)) = (
+            reserved.get("upload_url").and_then(|v| v.as_str()),
+            reserved.get("file_id").and_then(|v| v.as_str()),
+        ) else {
+            anyhow::bail!("Slack files.getUploadURLExternal returned no upload_url/file_id");
+        };
+
+        let resp = client.post(upload_url).body(bytes).send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Slack file upload failed ({status}): {body}");
+        }
+
+        let mut body = serde_json::json!({
+            "files": [{ "id": file_id, "title": file_name }],
+            "channel_id": recipient,
+        });
+        if let Some(caption) = caption {
+            body["initial_comment"] = serde_json::json!(caption);
+        }
+        if let Some(ts) = thread_ts {
+            body["thread_ts"] = serde_json::json!(ts);
+        }
+        self.call_api(
+            "files.completeUploadExternal",
+            client
+                .post("https://slack.com/api/files.completeUploadExternal")
+                .json(&body),
+        )
+        .await?;
+
+        tracing::info!("Slack file sent to {recipient}: {file_name}");
+        Ok(())
+    }
+
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
         let bot_user_id = self.get_bot_user_id().await.unwrap_or_default();
         let scoped_channel = self.configured_channel_id();
@@ -0,0 +1,176 @@
+//! Quick-reply actions for blocked and running task-engine runs.
+//!
+//! When a task ends `Blocked` (missing approval, permissions, or a decision
+//! from the user), the reply carries "continue" / "cancel" options. Channels
+//! that support buttons render them and deliver the pressed payload back as an
+//! inbound message; everywhere else the user types one of the keywords below.
+//! On those channels a newly started task also gets a "cancel" button, which
+//! stops the run through `TaskEngine::cancel_task`.
+
+use super::traits::QuickReply;
+
+/// Prefix of button payloads that target a specific blocked task.
+const TASK_REPLY_PREFIX: &str = "task:";
+
+const RESUME_KEYWORDS: &[&str] = &["continue", "resume", "approve", "继续", "同意", "批准"];
+const CANCEL_KEYWORDS: &[&str] = &["cancel", "deny", "取消", "拒绝"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockedTaskAction {
+    Resume,
+    Cancel,
+}
+
+impl BlockedTaskAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Resume => "resume",
+            Self::Cancel => "cancel",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "resume" => Some(Self::Resume),
+            "cancel" => Some(Self::Cancel),
+            _ => None,
+        }
+    }
+}
+
+/// A user's answer to a blocked task, either from a button or a text keyword.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockedTaskReply {
+    pub action: BlockedTaskAction,
+    /// Task targeted by a button payload; `None` for typed keywords, which
+    /// apply to the sender's most recent blocked task.
+    pub task_id: Option<String>,
+}
+
+/// Build the button set attached to a blocked task's reply.
+pub fn blocked_task_quick_replies(task_id: &str) -> Vec<QuickReply> {
+    vec![
+        QuickReply::new(
+            "▶️ 继续 / Continue",
+            format!(
+                "{TASK_REPLY_PREFIX}{}:{task_id}",
+                BlockedTaskAction::Resume.as_str()
+            ),
+        ),
+        QuickReply::new(
+            "✖️ 取消 / Cancel",
+            format!(
+                "{TASK_REPLY_PREFIX}{}:{task_id}",
+                BlockedTaskAction::Cancel.as_str()
+            ),
+        ),
+    ]
+}
+
+/// Build the cancel button attached to the notice that a task started.
+pub fn running_task_quick_replies(task_id: &str) -> Vec<QuickReply> {
+    vec![QuickReply::new(
+        "🛑 取消任务 / Cancel task",
+        format!(
+            "{TASK_REPLY_PREFIX}{}:{task_id}",
+            BlockedTaskAction::Cancel.as_str()
+        ),
+    )]
+}
+
+/// Text appended to blocked replies on channels without button support.
+pub fn blocked_task_text_hint() -> &'static str {
+    "回复“继续”重新尝试，或回复“取消”放弃该任务。(Reply \"continue\" or \"cancel\".)"
+}
+
+/// Parse an inbound message as a blocked-task answer.
+///
+/// Only button payloads and messages consisting of a single keyword match, so
+/// ordinary conversation is never mistaken for an approval.
+pub fn parse_blocked_task_reply(content: &str) -> Option<BlockedTaskReply> {
+    let trimmed = content.trim();
+
+    if let Some(rest) = trimmed.strip_prefix(TASK_REPLY_PREFIX) {
+        let (action, task_id) = rest.split_once(':')?;
+        let action = BlockedTaskAction::parse(action)?;
+        let task_id = task_id.trim();
+        if task_id.is_empty() {
+            return None;
+        }
+        return Some(BlockedTaskReply {
+            action,
+            task_id: Some(task_id.to_string()),
+        });
+    }
+
+    let keyword = trimmed
+        .trim_end_matches(['.', '!', '。', '！'])
+        .to_lowercase();
+    let action = if RESUME_KEYWORDS.contains(&keyword.as_str()) {
+        BlockedTaskAction::Resume
+    } else if CANCEL_KEYWORDS.contains(&keyword.as_str()) {
+        BlockedTaskAction::Cancel
+    } else {
+        return None;
+    };
+
+    Some(BlockedTaskReply {
+        action,
+        task_id: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_payloads_roundtrip_through_parser() {
+        let replies = blocked_task_quick_replies("task-123");
+        assert_eq!(replies.len(), 2);
+
+        let resume = parse_blocked_task_reply(&replies[0].payload).expect("resume payload");
+        assert_eq!(resume.action, BlockedTaskAction::Resume);
+        assert_eq!(resume.task_id.as_deref(), Some("task-123"));
+
+        let cancel = parse_blocked_task_reply(&replies[1].payload).expect("cancel payload");
+        assert_eq!(cancel.action, BlockedTaskAction::Cancel);
+        assert_eq!(cancel.task_id.as_deref(), Some("task-123"));
+    }
+
+    #[test]
+    fn button_payloads_fit_telegram_callback_data_limit() {
+        let task_id = uuid::Uuid::new_v4().to_string();
+        for reply in blocked_task_quick_replies(&task_id)
+            .into_iter()
+            .chain(running_task_quick_replies(&task_id))
+        {
+            assert!(reply.payload.len() <= 64, "{}", reply.payload);
+        }
+    }
+
+    #[test]
+    fn single_keywords_map_to_actions() {
+        assert_eq!(
+            parse_blocked_task_reply(" Continue! ").map(|r| r.action),
+            Some(BlockedTaskAction::Resume)
+        );
+        assert_eq!(
+            parse_blocked_task_reply("继续").map(|r| r.action),
+            Some(BlockedTaskAction::Resume)
+        );
+        assert_eq!(
+            parse_blocked_task_reply("取消。").map(|r| r.action),
+            Some(BlockedTaskAction::Cancel)
+        );
+        assert!(parse_blocked_task_reply("继续").unwrap().task_id.is_none());
+    }
+
+    #[test]
+    fn ordinary_messages_and_malformed_payloads_are_ignored() {
+        assert!(parse_blocked_task_reply("please continue writing the report").is_none());
+        assert!(parse_blocked_task_reply("task:approve:123").is_none());
+        assert!(parse_blocked_task_reply("task:resume:").is_none());
+        assert!(parse_blocked_task_reply("").is_none());
+    }
+}
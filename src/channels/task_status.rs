@@ -0,0 +1,559 @@
+//! Sender-facing task status replies.
+//!
+//! `/status`, and questions such as "what's the status of my report?" or
+//! "我的报告进度怎么样？" from a sender with an active or recent task, are answered
+//! straight from the task store — per-task status, last event, and a rough
+//! ETA, plus what unblocks a blocked task — without starting a new engine
+//! run. `/find <keywords>` searches the sender's past task requests and
+//! responses the same way. Replies follow the language of the message.
+
+use crate::agent::task_blocked::BlockedResolution;
+use crate::agent::task_types::{TaskEventRecord, TaskRunRecord, TaskStatus};
+use crate::util::truncate_with_ellipsis;
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+
+/// Number of recent tasks listed in a status reply.
+pub const STATUS_REPLY_TASK_LIMIT: usize = 3;
+
+/// Number of matches listed in a `/find` reply.
+pub const SEARCH_REPLY_TASK_LIMIT: usize = 5;
+
+/// Command prefix for keyword search over the sender's tasks.
+pub const TASK_SEARCH_COMMAND: &str = "/find";
+
+/// Longer messages are treated as new requests even if they mention "status".
+const MAX_STATUS_QUERY_CHARS: usize = 80;
+
+/// Minutes after its last update a finished task still counts as recent, so
+/// a status question about it is answered from the store.
+const RECENT_TASK_MINS: i64 = 30;
+
+/// Phrases that name the sender's own task themselves.
+const ENGLISH_OWN_TASK_PHRASES: &[&str] = &[
+    "status of my",
+    "how is my",
+    "how's my",
+    "is it done",
+    "are you done",
+];
+
+/// Phrases that only ask about the sender's task when nothing else follows
+/// them, or when what follows points back at it ("any update on my deploy?").
+const ENGLISH_STATUS_PHRASES: &[&str] = &[
+    "what's the status",
+    "whats the status",
+    "what is the status",
+    "status update",
+    "any update",
+    "any progress",
+    "how far along",
+];
+
+/// Words after a status phrase that point at the sender's task.
+const ENGLISH_TASK_REFERENCES: &[&str] = &["my", "it", "it's", "that", "this", "your", "task"];
+
+const CHINESE_STATUS_PHRASES: &[&str] = &[
+    "进度",
+    "状态",
+    "做到哪",
+    "完成了吗",
+    "做完了吗",
+    "好了吗",
+    "好了没",
+    "怎么样了",
+];
+
+/// Words before a Chinese status phrase that point at the sender's task.
+const CHINESE_TASK_REFERENCES: &[&str] =
+    &["我的", "我那", "任务", "它", "这个", "那个", "刚才", "你"];
+
+/// How a message asks about the sender's tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusQuery {
+    /// `/status` or a bare `status`; always answered when the sender has tasks.
+    Command,
+    /// A question about progress; answered only while the sender has an
+    /// active or recent task, and otherwise handled as a normal message.
+    Question,
+}
+
+/// Language a reply is written in, following the message it answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyLanguage {
+    English,
+    Chinese,
+}
+
+impl ReplyLanguage {
+    /// Chinese when `content` has CJK characters, English otherwise.
+    pub fn of(content: &str) -> Self {
+        if content
+            .chars()
+            .any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c))
+        {
+            Self::Chinese
+        } else {
+            Self::English
+        }
+    }
+}
+
+/// Whether the words after an English status phrase are empty or point back
+/// at the sender's task rather than at something else.
+fn refers_to_own_task(rest: &str) -> bool {
+    let mut words = rest
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .peekable();
+    words.peek().is_none() || words.any(|word| ENGLISH_TASK_REFERENCES.contains(&word))
+}
+
+/// Whether the text before a Chinese status phrase is empty or points back
+/// at the sender's task, so "服务器进度怎么样？" is left to the agent.
+fn refers_to_own_task_zh(before: &str) -> bool {
+    let before = before.trim_matches(|c: char| !c.is_alphanumeric());
+    before.is_empty()
+        || CHINESE_TASK_REFERENCES
+            .iter()
+            .any(|reference| before.contains(reference))
+}
+
+/// Whether, and how, an inbound message asks about the sender's task progress.
+pub fn task_status_query(content: &str) -> Option<StatusQuery> {
+    let trimmed = content.trim();
+    if trimmed.is_empty() || trimmed.chars().count() > MAX_STATUS_QUERY_CHARS {
+        return None;
+    }
+
+    let lower = trimmed.to_lowercase();
+    if lower == "status" || lower == "/status" {
+        return Some(StatusQuery::Command);
+    }
+    if ENGLISH_OWN_TASK_PHRASES
+        .iter()
+        .any(|phrase| lower.contains(phrase))
+    {
+        return Some(StatusQuery::Question);
+    }
+    if ENGLISH_STATUS_PHRASES.iter().any(|phrase| {
+        lower
+            .find(phrase)
+            .is_some_and(|at| refers_to_own_task(&lower[at + phrase.len()..]))
+    }) {
+        return Some(StatusQuery::Question);
+    }
+
+    // Chinese phrases only count as questions, so "写一份项目进度报告" stays a request.
+    let is_question = ['?', '？', '吗', '呢', '没']
+        .iter()
+        .any(|suffix| trimmed.ends_with(*suffix));
+    (is_question
+        && CHINESE_STATUS_PHRASES.iter().any(|phrase| {
+            trimmed
+                .find(phrase)
+                .is_some_and(|at| refers_to_own_task_zh(&trimmed[..at]))
+        }))
+    .then_some(StatusQuery::Question)
+}
+
+/// Whether any of `tasks` is still queued, running, or blocked, or was
+/// updated in the last [`RECENT_TASK_MINS`] minutes.
+pub fn has_active_or_recent_task(tasks: &[TaskRunRecord], now: DateTime<Utc>) -> bool {
+    tasks.iter().any(|task| {
+        matches!(
+            task.status,
+            TaskStatus::Queued | TaskStatus::Running | TaskStatus::Blocked
+        ) || parse_timestamp(&task.updated_at)
+            .is_some_and(|at| (now - at).num_minutes() < RECENT_TASK_MINS)
+    })
+}
+
+/// Keywords of a `/find <keywords>` message, if that is what it is.
+pub fn parse_task_search_query(content: &str) -> Option<&str> {
+    let rest = content.trim().strip_prefix(TASK_SEARCH_COMMAND)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let keywords = rest.trim();
+    (!keywords.is_empty()).then_some(keywords)
+}
+
+fn status_label(status: TaskStatus, lang: ReplyLanguage) -> &'static str {
+    match (lang, status) {
+        (ReplyLanguage::Chinese, TaskStatus::Queued) => "排队中",
+        (ReplyLanguage::Chinese, TaskStatus::Running) => "执行中",
+        (ReplyLanguage::Chinese, TaskStatus::Blocked) => "已阻塞（等待你的回复）",
+        (ReplyLanguage::Chinese, TaskStatus::Completed) => "已完成",
+        (ReplyLanguage::Chinese, TaskStatus::Failed) => "失败",
+        (ReplyLanguage::Chinese, TaskStatus::Cancelled) => "已取消",
+        (ReplyLanguage::English, TaskStatus::Queued) => "queued",
+        (ReplyLanguage::English, TaskStatus::Running) => "running",
+        (ReplyLanguage::English, TaskStatus::Blocked) => "blocked (waiting for your reply)",
+        (ReplyLanguage::English, TaskStatus::Completed) => "completed",
+        (ReplyLanguage::English, TaskStatus::Failed) => "failed",
+        (ReplyLanguage::English, TaskStatus::Cancelled) => "cancelled",
+    }
+}
+
+fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .map(|ts| ts.with_timezone(&Utc))
+}
+
+fn format_elapsed(seconds: i64, lang: ReplyLanguage) -> String {
+    let seconds = seconds.max(0);
+    match lang {
+        ReplyLanguage::Chinese if seconds < 60 => format!("{seconds} 秒"),
+        ReplyLanguage::Chinese if seconds < 3600 => format!("{} 分钟", seconds / 60),
+        ReplyLanguage::Chinese => {
+            format!("{} 小时 {} 分钟", seconds / 3600, (seconds % 3600) / 60)
+        }
+        ReplyLanguage::English if seconds < 60 => format!("{seconds}s"),
+        ReplyLanguage::English if seconds < 3600 => format!("{} min", seconds / 60),
+        ReplyLanguage::English => {
+            format!("{} h {} min", seconds / 3600, (seconds % 3600) / 60)
+        }
+    }
+}
+
+/// Rough remaining-time hint for an active task: average round duration so far
+/// times the rounds left in the continuation budget.
+fn eta_hint(
+    task: &TaskRunRecord,
+    max_rounds: usize,
+    now: DateTime<Utc>,
+    lang: ReplyLanguage,
+) -> Option<String> {
+    if task.status != TaskStatus::Running {
+        return None;
+    }
+    let rounds_done = usize::try_from(task.attempt_count).unwrap_or(usize::MAX);
+    let rounds_left = max_rounds.saturating_sub(rounds_done).max(1);
+    let started = parse_timestamp(&task.created_at)?;
+    let elapsed = (now - started).num_seconds().max(0);
+
+    if rounds_done == 0 {
+        let elapsed = format_elapsed(elapsed, lang);
+        return Some(match lang {
+            ReplyLanguage::Chinese => format!("第 1/{max_rounds} 轮进行中，已用时 {elapsed}"),
+            ReplyLanguage::English => {
+                format!("round 1/{max_rounds} in progress, {elapsed} so far")
+            }
+        });
+    }
+    let per_round = elapsed / i64::try_from(rounds_done).unwrap_or(i64::MAX).max(1);
+    let remaining = per_round.saturating_mul(i64::try_from(rounds_left).unwrap_or(i64::MAX));
+    let remaining = format_elapsed(remaining, lang);
+    Some(match lang {
+        ReplyLanguage::Chinese => {
+            format!("已完成 {rounds_done}/{max_rounds} 轮，预计最多还需 {remaining}")
+        }
+        ReplyLanguage::English => {
+            format!("{rounds_done}/{max_rounds} rounds done, at most {remaining} to go")
+        }
+    })
+}
+
+/// Render the status reply for a sender's recent tasks (newest first).
+pub fn render_task_status_reply(
+    tasks: &[(TaskRunRecord, Option<TaskEventRecord>)],
+    max_rounds: usize,
+    now: DateTime<Utc>,
+    lang: ReplyLanguage,
+) -> String {
+    let mut out = String::from(match lang {
+        ReplyLanguage::Chinese => "📋 最近的任务状态：",
+        ReplyLanguage::English => "📋 Your recent tasks:",
+    });
+    for (index, (task, last_event)) in tasks.iter().enumerate() {
+        let _ = write!(
+            out,
+            "\n\n{}. {} — {}",
+            index + 1,
+            truncate_with_ellipsis(task.original_request.trim(), 60),
+            status_label(task.status, lang)
+        );
+        if let Some(event) = last_event {
+            let ago = parse_timestamp(&event.created_at).map(|at| {
+                let elapsed = format_elapsed((now - at).num_seconds(), lang);
+                match lang {
+                    ReplyLanguage::Chinese => format!("（{elapsed}前）"),
+                    ReplyLanguage::English => format!(" ({elapsed} ago)"),
+                }
+            });
+            let ago = ago.unwrap_or_default();
+            let _ = match lang {
+                ReplyLanguage::Chinese => write!(out, "\n   最近事件：{}{ago}", event.event_type),
+                ReplyLanguage::English => write!(out, "\n   Last event: {}{ago}", event.event_type),
+            };
+            if let Some(resolution) = BlockedResolution::from_event(event) {
+                let _ = match lang {
+                    ReplyLanguage::Chinese => write!(
+                        out,
+                        "\n   阻塞原因：{}（{}）\n   解决办法：{}",
+                        resolution.kind.label(),
+                        resolution.reason,
+                        resolution.hint()
+                    ),
+                    ReplyLanguage::English => write!(
+                        out,
+                        "\n   Blocked: {} ({})\n   To unblock: {}",
+                        resolution.kind.label_en(),
+                        resolution.reason,
+                        resolution.hint_en()
+                    ),
+                };
+            }
+        }
+        if let Some(eta) = eta_hint(task, max_rounds, now, lang) {
+            let _ = write!(out, "\n   {eta}");
+        }
+    }
+    out
+}
+
+/// Render the `/find` reply listing matching tasks, best match first.
+pub fn render_task_search_reply(
+    query: &str,
+    tasks: &[TaskRunRecord],
+    lang: ReplyLanguage,
+) -> String {
+    if tasks.is_empty() {
+        return match lang {
+            ReplyLanguage::Chinese => format!("🔎 没有找到与“{query}”相关的任务。"),
+            ReplyLanguage::English => format!("🔎 No tasks found for \"{query}\"."),
+        };
+    }
+    let mut out = match lang {
+        ReplyLanguage::Chinese => format!("🔎 与“{query}”相关的任务："),
+        ReplyLanguage::English => format!("🔎 Tasks matching \"{query}\":"),
+    };
+    for (index, task) in tasks.iter().enumerate() {
+        let _ = write!(
+            out,
+            "\n\n{}. {} — {}",
+            index + 1,
+            truncate_with_ellipsis(task.original_request.trim(), 60),
+            status_label(task.status, lang)
+        );
+        let day = task.created_at.get(..10).unwrap_or(&task.created_at);
+        let _ = match lang {
+            ReplyLanguage::Chinese => write!(out, "\n   创建于 {day}"),
+            ReplyLanguage::English => write!(out, "\n   Created {day}"),
+        };
+        if let Some(response) = task.last_response.as_deref() {
+            let response = truncate_with_ellipsis(response.trim(), 80);
+            let _ = match lang {
+                ReplyLanguage::Chinese => write!(out, "\n   最后回复：{response}"),
+                ReplyLanguage::English => write!(out, "\n   Last reply: {response}"),
+            };
+        }
+    }
+    out
+}
+
+/// Alert sent when the stuck-task detector fails a task that made no
+/// progress for `after_mins`, in the language of the task's request.
+pub fn render_stuck_task_alert(task: &TaskRunRecord, after_mins: u64) -> String {
+    let request = truncate_with_ellipsis(task.original_request.trim(), 60);
+    match ReplyLanguage::of(&task.original_request) {
+        ReplyLanguage::Chinese => format!(
+            "⚠️ 任务“{request}”已超过 {after_mins} 分钟没有进展，已标记为失败。请重新发送请求以重试。"
+        ),
+        ReplyLanguage::English => format!(
+            "⚠️ Task \"{request}\" made no progress for over {after_mins} minutes and was marked failed. Send the request again to retry."
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(status: TaskStatus, attempt_count: u32) -> TaskRunRecord {
+        TaskRunRecord {
+            id: "task-1".into(),
+            channel: "imessage".into(),
+            sender_key: "sender".into(),
+            reply_target: "sender".into(),
+            status,
+            original_request: "整理本周销售报告".into(),
+            last_response: None,
+            attempt_count,
+            provider_retry_count: 0,
+            created_at: "2026-01-01T00:00:00+00:00".into(),
+            updated_at: "2026-01-01T00:04:00+00:00".into(),
+            completed_at: None,
+            archived_at: None,
+        }
+    }
+
+    #[test]
+    fn detects_status_questions_in_english_and_chinese() {
+        assert_eq!(
+            task_status_query("What's the status of my report?"),
+            Some(StatusQuery::Question)
+        );
+        assert_eq!(
+            task_status_query("any update?"),
+            Some(StatusQuery::Question)
+        );
+        assert_eq!(
+            task_status_query("any update on it?"),
+            Some(StatusQuery::Question)
+        );
+        assert_eq!(task_status_query("/status"), Some(StatusQuery::Command));
+        assert_eq!(
+            task_status_query("我的报告进度怎么样？"),
+            Some(StatusQuery::Question)
+        );
+        assert_eq!(task_status_query("做完了吗"), Some(StatusQuery::Question));
+        assert_eq!(
+            task_status_query("那个任务好了没"),
+            Some(StatusQuery::Question)
+        );
+    }
+
+    #[test]
+    fn ignores_requests_that_merely_mention_status() {
+        assert!(task_status_query("写一份项目进度报告").is_none());
+        assert!(task_status_query("draft a weekly status report for the team").is_none());
+        assert!(task_status_query("what's the status of nginx on prod?").is_none());
+        assert!(task_status_query("any update on the nginx rollout?").is_none());
+        assert!(task_status_query("服务器进度怎么样？").is_none());
+        assert!(task_status_query(&"what's the status ".repeat(10)).is_none());
+        assert!(task_status_query("").is_none());
+    }
+
+    #[test]
+    fn only_active_or_recently_updated_tasks_count_as_current() {
+        let now = parse_timestamp("2026-01-01T00:20:00+00:00").unwrap();
+        assert!(has_active_or_recent_task(
+            &[task(TaskStatus::Running, 1)],
+            now
+        ));
+        assert!(has_active_or_recent_task(
+            &[task(TaskStatus::Completed, 1)],
+            now
+        ));
+
+        let later = parse_timestamp("2026-01-02T00:00:00+00:00").unwrap();
+        assert!(!has_active_or_recent_task(
+            &[task(TaskStatus::Completed, 1)],
+            later
+        ));
+        assert!(has_active_or_recent_task(
+            &[task(TaskStatus::Blocked, 1)],
+            later
+        ));
+        assert!(!has_active_or_recent_task(&[], now));
+    }
+
+    #[test]
+    fn replies_follow_the_language_of_the_message() {
+        assert_eq!(ReplyLanguage::of("any update?"), ReplyLanguage::English);
+        assert_eq!(ReplyLanguage::of("做完了吗"), ReplyLanguage::Chinese);
+
+        let now = parse_timestamp("2026-01-01T00:06:00+00:00").unwrap();
+        let reply = render_task_status_reply(
+            &[(task(TaskStatus::Running, 2), None)],
+            4,
+            now,
+            ReplyLanguage::English,
+        );
+        assert!(reply.starts_with("📋 Your recent tasks:"));
+        assert!(reply.contains("1. 整理本周销售报告 — running"));
+        assert!(reply.contains("2/4 rounds done, at most 6 min to go"));
+        assert!(
+            render_task_search_reply("report", &[], ReplyLanguage::English)
+                .contains("No tasks found")
+        );
+    }
+
+    #[test]
+    fn stuck_task_alert_follows_the_request_language() {
+        let mut stuck = task(TaskStatus::Failed, 1);
+        assert!(render_stuck_task_alert(&stuck, 15).contains("15 分钟没有进展"));
+        stuck.original_request = "summarize this week's sales".to_string();
+        assert_eq!(
+            render_stuck_task_alert(&stuck, 15),
+            "⚠️ Task \"summarize this week's sales\" made no progress for over 15 minutes and was marked failed. Send the request again to retry."
+        );
+    }
+
+    #[test]
+    fn parses_find_command_keywords() {
+        assert_eq!(
+            parse_task_search_query("/find quarterly report"),
+            Some("quarterly report")
+        );
+        assert_eq!(parse_task_search_query("  /find 报告 "), Some("报告"));
+        assert!(parse_task_search_query("/find").is_none());
+        assert!(parse_task_search_query("/findings").is_none());
+        assert!(parse_task_search_query("find my report").is_none());
+    }
+
+    #[test]
+    fn search_reply_lists_matches_or_says_none_found() {
+        let mut done = task(TaskStatus::Completed, 1);
+        done.last_response = Some("报告已保存到 reports/sales.md".into());
+        let reply = render_task_search_reply("报告", &[done], ReplyLanguage::Chinese);
+        assert!(reply.contains("1. 整理本周销售报告 — 已完成"));
+        assert!(reply.contains("创建于 2026-01-01"));
+        assert!(reply.contains("最后回复：报告已保存到 reports/sales.md"));
+
+        assert!(render_task_search_reply("报告", &[], ReplyLanguage::Chinese).contains("没有找到"));
+    }
+
+    #[test]
+    fn status_reply_lists_status_last_event_and_eta() {
+        let now = parse_timestamp("2026-01-01T00:06:00+00:00").unwrap();
+        let event = TaskEventRecord {
+            id: 1,
+            task_id: "task-1".into(),
+            event_type: "continue".into(),
+            payload_json: None,
+            created_at: "2026-01-01T00:05:00+00:00".into(),
+        };
+        let reply = render_task_status_reply(
+            &[
+                (task(TaskStatus::Running, 2), Some(event)),
+                (task(TaskStatus::Completed, 1), None),
+            ],
+            4,
+            now,
+            ReplyLanguage::Chinese,
+        );
+
+        assert!(reply.contains("1. 整理本周销售报告 — 执行中"));
+        assert!(reply.contains("最近事件：continue（1 分钟前）"));
+        assert!(reply.contains("已完成 2/4 轮，预计最多还需 6 分钟"));
+        assert!(reply.contains("2. 整理本周销售报告 — 已完成"));
+    }
+
+    #[test]
+    fn status_reply_tells_how_to_unblock_a_blocked_task() {
+        let now = parse_timestamp("2026-01-01T00:06:00+00:00").unwrap();
+        let resolution = BlockedResolution::new("workspace_access_denied", "add the path");
+        let event = TaskEventRecord {
+            id: 1,
+            task_id: "task-1".into(),
+            event_type: "blocked".into(),
+            payload_json: Some(resolution.event_payload(1).to_string()),
+            created_at: "2026-01-01T00:05:00+00:00".into(),
+        };
+        let blocked = [(task(TaskStatus::Blocked, 1), Some(event))];
+        let reply = render_task_status_reply(&blocked, 4, now, ReplyLanguage::Chinese);
+
+        assert!(reply.contains("阻塞原因：超出可访问目录（workspace_access_denied）"));
+        assert!(reply.contains("解决办法：在配置 `autonomy.allowed_roots` 中补充后回复“continue”"));
+
+        let reply = render_task_status_reply(&blocked, 4, now, ReplyLanguage::English);
+        assert!(
+            reply.contains("Blocked: outside the accessible directories (workspace_access_denied)")
+        );
+        assert!(reply.contains("To unblock: Update `autonomy.allowed_roots` in the config"));
+    }
+}
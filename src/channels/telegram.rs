@@ -1,6 +1,6 @@
-use super::traits::{Channel, ChannelMessage, SendMessage};
+use super::traits::{Channel, ChannelMessage, QuickReply, SendMessage};
 use crate::config::{Config, StreamMode};
-use crate::security::pairing::PairingGuard;
+use crate::security::pairing::{constant_time_eq, PairingGuard};
 use anyhow::Context;
 use async_trait::async_trait;
 use directories::UserDirs;
@@ -35,6 +35,10 @@ enum IncomingAttachmentKind {
     Photo,
 }
 const TELEGRAM_BIND_COMMAND: &str = "/bind";
+/// Update types requested from `getUpdates` and `setWebhook`.
+const TELEGRAM_ALLOWED_UPDATES: &[&str] = &["message", "callback_query"];
+/// Header carrying the `secret_token` given to `setWebhook`.
+const TELEGRAM_SECRET_HEADER: &str = "x-telegram-bot-api-secret-token";
 
 /// Split a message into chunks that respect Telegram's 4096 character limit.
 /// Tries to split at word boundaries when possible, and handles continuation.
@@ -311,6 +315,27 @@ pub struct TelegramChannel {
     transcription: Option<crate::config::TranscriptionConfig>,
     voice_transcriptions: Mutex<std::collections::HashMap<String, String>>,
     workspace_dir: Option<std::path::PathBuf>,
+    /// Set for webhook mode; `None` receives updates by long polling.
+    webhook: Option<TelegramWebhook>,
+}
+
+/// Where Telegram delivers updates in webhook mode.
+#[derive(Debug, Clone)]
+struct TelegramWebhook {
+    /// Public HTTPS URL registered with `setWebhook`.
+    url: String,
+    /// Local port the callback server listens on.
+    port: u16,
+    /// Expected `X-Telegram-Bot-Api-Secret-Token` header value.
+    secret: String,
+}
+
+/// Whether a webhook request carries the `secret_token` given to `setWebhook`.
+fn webhook_secret_matches(headers: &axum::http::HeaderMap, secret: &str) -> bool {
+    headers
+        .get(TELEGRAM_SECRET_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|provided| constant_time_eq(provided, secret))
 }
 
 impl TelegramChannel {
@@ -342,6 +367,7 @@ impl TelegramChannel {
             transcription: None,
             voice_transcriptions: Mutex::new(std::collections::HashMap::new()),
             workspace_dir: None,
+            webhook: None,
         }
     }
 
@@ -362,6 +388,18 @@ impl TelegramChannel {
         self
     }
 
+    /// Receive updates through a webhook at `url` served on `port` instead of
+    /// long polling. Every callback request must carry `secret`; without one,
+    /// a random secret is generated and registered with Telegram.
+    pub fn with_webhook(mut self, url: String, port: u16, secret: Option<String>) -> Self {
+        let secret = secret
+            .map(|secret| secret.trim().to_string())
+            .filter(|secret| !secret.is_empty())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+        self.webhook = Some(TelegramWebhook { url, port, secret });
+        self
+    }
+
     /// Override the Telegram Bot API base URL.
     /// Useful for local Bot API servers or testing.
     pub fn with_api_base(mut self, api_base: String) -> Self {
@@ -1308,6 +1346,321 @@ Allowlist Telegram username (without '@') or numeric user ID.",
         })
     }
 
+    /// Parse an inline-keyboard button press into an inbound message carrying
+    /// the button's payload, applying the same allowlist as text messages.
+    fn parse_callback_query(&self, update: &serde_json::Value) -> Option<ChannelMessage> {
+        let query = update.get("callback_query")?;
+        let data = query.get("data").and_then(serde_json::Value::as_str)?;
+        let query_id = query.get("id").and_then(serde_json::Value::as_str)?;
+
+        let (username, sender_id, sender_identity) = Self::extract_sender_info(query);
+        let mut identities = vec![username.as_str()];
+        if let Some(id) = sender_id.as_deref() {
+            identities.push(id);
+        }
+        if !self.is_any_user_allowed(identities.iter().copied()) {
+            return None;
+        }
+
+        let message = query.get("message")?;
+        let chat_id = message
+            .get("chat")
+            .and_then(|chat| chat.get("id"))
+            .and_then(serde_json::Value::as_i64)
+            .map(|id| id.to_string())?;
+        let reply_target = match message
+            .get("message_thread_id")
+            .and_then(serde_json::Value::as_i64)
+        {
+            Some(tid) => format!("{chat_id}:{tid}"),
+            None => chat_id,
+        };
+
+        Some(ChannelMessage {
+            id: format!("telegram_callback_{query_id}"),
+            sender: sender_identity,
+            reply_target,
+            content: data.to_string(),
+            channel: "telegram".to_string(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            thread_ts: None,
+        })
+    }
+
+    /// Acknowledge a button press so the client stops showing a spinner.
+    async fn answer_callback_query(&self, update: &serde_json::Value) {
+        let Some(query_id) = update
+            .get("callback_query")
+            .and_then(|query| query.get("id"))
+            .and_then(serde_json::Value::as_str)
+        else {
+            return;
+        };
+        let _ = self
+            .http_client()
+            .post(self.api_url("answerCallbackQuery"))
+            .json(&serde_json::json!({ "callback_query_id": query_id }))
+            .send()
+            .await;
+    }
+
+    /// Forward one update from `getUpdates` or the webhook to `tx`. Returns
+    /// `false` once the receiver is gone.
+    async fn handle_update(
+        &self,
+        update: &serde_json::Value,
+        tx: &tokio::sync::mpsc::Sender<ChannelMessage>,
+    ) -> bool {
+        if self.mention_only && self.bot_username.lock().is_none() {
+            let _ = self.get_bot_username().await;
+        }
+
+        if update.get("callback_query").is_some() {
+            self.answer_callback_query(update).await;
+            if let Some(m) = self.parse_callback_query(update) {
+                return tx.send(m).await.is_ok();
+            }
+            return true;
+        }
+
+        let msg = if let Some(m) = self.parse_update_message(update) {
+            m
+        } else if let Some(m) = self.try_parse_voice_message(update).await {
+            m
+        } else if let Some(m) = self.try_parse_attachment_message(update).await {
+            m
+        } else {
+            self.handle_unauthorized_message(update).await;
+            return true;
+        };
+
+        if let Some((reaction_chat_id, reaction_message_id)) =
+            Self::extract_update_message_target(update)
+        {
+            self.try_add_ack_reaction_nonblocking(reaction_chat_id, reaction_message_id);
+        }
+
+        // Send "typing" indicator immediately when we receive a message
+        let typing_body = serde_json::json!({
+            "chat_id": &msg.reply_target,
+            "action": "typing"
+        });
+        let _ = self
+            .http_client()
+            .post(self.api_url("sendChatAction"))
+            .json(&typing_body)
+            .send()
+            .await; // Ignore errors for typing indicator
+
+        tx.send(msg).await.is_ok()
+    }
+
+    /// Receive updates with `getUpdates` long polling.
+    async fn listen_polling(
+        &self,
+        tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+    ) -> anyhow::Result<()> {
+        let mut offset: i64 = 0;
+
+        // A webhook left over from webhook mode makes getUpdates fail with 409.
+        if let Err(e) = self.call_api("deleteWebhook", &serde_json::json!({})).await {
+            tracing::warn!("Telegram deleteWebhook failed: {e:#}");
+        }
+
+        tracing::info!("Telegram channel listening for messages...");
+
+        loop {
+            let url = self.api_url("getUpdates");
+            let body = serde_json::json!({
+                "offset": offset,
+                "timeout": 30,
+                "allowed_updates": TELEGRAM_ALLOWED_UPDATES
+            });
+
+            let resp = match self.http_client().post(&url).json(&body).send().await {
+                Ok(r) => r,
+                Err(e) => {
+                    tracing::warn!("Telegram poll error: {e}");
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+
+            let data: serde_json::Value = match resp.json().await {
+                Ok(d) => d,
+                Err(e) => {
+                    tracing::warn!("Telegram parse error: {e}");
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+
+            let ok = data
+                .get("ok")
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(true);
+            if !ok {
+                let error_code = data
+                    .get("error_code")
+                    .and_then(serde_json::Value::as_i64)
+                    .unwrap_or_default();
+                let description = data
+                    .get("description")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("unknown Telegram API error");
+
+                if error_code == 409 {
+                    tracing::warn!(
+                        "Telegram polling conflict (409): {description}. \
+Ensure only one `zeroclaw` process is using this bot token."
+                    );
+                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                } else {
+                    tracing::warn!(
+                        "Telegram getUpdates API error (code={}): {description}",
+                        error_code
+                    );
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                }
+                continue;
+            }
+
+            if let Some(results) = data.get("result").and_then(serde_json::Value::as_array) {
+                for update in results {
+                    // Advance offset past this update
+                    if let Some(uid) = update.get("update_id").and_then(serde_json::Value::as_i64) {
+                        offset = uid + 1;
+                    }
+
+                    if !self.handle_update(update, &tx).await {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Register `webhook` with `setWebhook` and serve Telegram's callbacks on
+    /// `POST /telegram`. Updates are acknowledged right away and handled here
+    /// in arrival order.
+    async fn listen_webhook(
+        &self,
+        webhook: &TelegramWebhook,
+        tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+    ) -> anyhow::Result<()> {
+        use axum::{
+            extract::State, http::HeaderMap, http::StatusCode, routing::post, Json, Router,
+        };
+
+        #[derive(Clone)]
+        struct AppState {
+            secret: String,
+            updates: tokio::sync::mpsc::Sender<serde_json::Value>,
+        }
+
+        async fn receive_update(
+            State(state): State<AppState>,
+            headers: HeaderMap,
+            Json(update): Json<serde_json::Value>,
+        ) -> StatusCode {
+            if !webhook_secret_matches(&headers, &state.secret) {
+                return StatusCode::UNAUTHORIZED;
+            }
+            if state.updates.send(update).await.is_err() {
+                return StatusCode::SERVICE_UNAVAILABLE;
+            }
+            StatusCode::OK
+        }
+
+        let (updates_tx, mut updates_rx) = tokio::sync::mpsc::channel(100);
+        let app = Router::new()
+            .route("/telegram", post(receive_update))
+            .with_state(AppState {
+                secret: webhook.secret.clone(),
+                updates: updates_tx,
+            });
+        let addr = std::net::SocketAddr::from(([0, 0, 0, 0], webhook.port));
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind Telegram webhook server on {addr}"))?;
+
+        let body = serde_json::json!({
+            "url": webhook.url,
+            "allowed_updates": TELEGRAM_ALLOWED_UPDATES,
+            "secret_token": webhook.secret,
+        });
+        self.call_api("setWebhook", &body)
+            .await
+            .context("Failed to register Telegram webhook")?;
+        tracing::info!(
+            "Telegram webhook registered at {}; callback server listening on {addr}",
+            webhook.url
+        );
+
+        let mut server = tokio::spawn(async move { axum::serve(listener, app).await });
+        loop {
+            tokio::select! {
+                served = &mut server => {
+                    served.context("Telegram webhook server task failed")??;
+                    return Ok(());
+                }
+                Some(update) = updates_rx.recv() => {
+                    if !self.handle_update(&update, &tx).await {
+                        server.abort();
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Call a Bot API method and fail unless Telegram answers `ok`.
+    async fn call_api(
+        &self,
+        method: &str,
+        body: &serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        let data: serde_json::Value = self
+            .http_client()
+            .post(self.api_url(method))
+            .json(body)
+            .send()
+            .await?
+            .json()
+            .await?;
+        if !data
+            .get("ok")
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false)
+        {
+            let description = data
+                .get("description")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or("unknown Telegram API error");
+            anyhow::bail!("Telegram {method} failed: {description}");
+        }
+        Ok(data)
+    }
+
+    fn quick_reply_markup(quick_replies: &[QuickReply]) -> Option<serde_json::Value> {
+        if quick_replies.is_empty() {
+            return None;
+        }
+        let row: Vec<serde_json::Value> = quick_replies
+            .iter()
+            .map(|reply| {
+                serde_json::json!({
+                    "text": reply.label,
+                    "callback_data": reply.payload,
+                })
+            })
+            .collect();
+        Some(serde_json::json!({ "inline_keyboard": [row] }))
+    }
+
     /// Download a Telegram photo by file_id, resize to fit within 1024px, and return as base64 data URI.
     async fn resolve_photo_data_uri(&self, file_id: &str) -> anyhow::Result<String> {
         use base64::Engine as _;
@@ -1519,6 +1872,19 @@ Allowlist Telegram username (without '@') or numeric user ID.",
         message: &str,
         chat_id: &str,
         thread_id: Option<&str>,
+    ) -> anyhow::Result<()> {
+        self.send_text_chunks_with_markup(message, chat_id, thread_id, None)
+            .await
+    }
+
+    /// Like [`Self::send_text_chunks`], attaching `reply_markup` (e.g. an
+    /// inline keyboard) to the final chunk.
+    async fn send_text_chunks_with_markup(
+        &self,
+        message: &str,
+        chat_id: &str,
+        thread_id: Option<&str>,
+        reply_markup: Option<&serde_json::Value>,
     ) -> anyhow::Result<()> {
         let chunks = split_message_for_telegram(message);
 
@@ -1545,6 +1911,10 @@ Allowlist Telegram username (without '@') or numeric user ID.",
             if let Some(tid) = thread_id {
                 markdown_body["message_thread_id"] = serde_json::Value::String(tid.to_string());
             }
+            let markup = reply_markup.filter(|_| index == chunks.len() - 1);
+            if let Some(markup) = markup {
+                markdown_body["reply_markup"] = markup.clone();
+            }
 
             let markdown_resp = self
                 .http_client()
@@ -1576,6 +1946,9 @@ Allowlist Telegram username (without '@') or numeric user ID.",
             if let Some(tid) = thread_id {
                 plain_body["message_thread_id"] = serde_json::Value::String(tid.to_string());
             }
+            if let Some(markup) = markup {
+                plain_body["reply_markup"] = markup.clone();
+            }
             let plain_resp = self
                 .http_client()
                 .post(self.api_url("sendMessage"))
@@ -2132,6 +2505,10 @@ impl Channel for TelegramChannel {
         self.stream_mode != StreamMode::Off
     }
 
+    fn supports_quick_replies(&self) -> bool {
+        true
+    }
+
     async fn send_draft(&self, message: &SendMessage) -> anyhow::Result<Option<String>> {
         if self.stream_mode == StreamMode::Off {
             return Ok(None);
@@ -2371,6 +2748,18 @@ impl Channel for TelegramChannel {
         Ok(())
     }
 
+    async fn send_file(
+        &self,
+        recipient: &str,
+        _thread_ts: Option<&str>,
+        path: &Path,
+        caption: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let (chat_id, thread_id) = Self::parse_reply_target(recipient);
+        self.send_document(&chat_id, thread_id.as_deref(), path, caption)
+            .await
+    }
+
     async fn send(&self, message: &SendMessage) -> anyhow::Result<()> {
         // Strip tool_call tags before processing to prevent Markdown parsing failures
         let content = strip_tool_call_tags(&message.content);
@@ -2402,125 +2791,19 @@ impl Channel for TelegramChannel {
             return Ok(());
         }
 
-        self.send_text_chunks(&content, chat_id, thread_id).await
+        let reply_markup = Self::quick_reply_markup(&message.quick_replies);
+        self.send_text_chunks_with_markup(&content, chat_id, thread_id, reply_markup.as_ref())
+            .await
     }
 
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
-        let mut offset: i64 = 0;
-
         if self.mention_only {
             let _ = self.get_bot_username().await;
         }
 
-        tracing::info!("Telegram channel listening for messages...");
-
-        loop {
-            if self.mention_only {
-                let missing_username = self.bot_username.lock().is_none();
-                if missing_username {
-                    let _ = self.get_bot_username().await;
-                }
-            }
-
-            let url = self.api_url("getUpdates");
-            let body = serde_json::json!({
-                "offset": offset,
-                "timeout": 30,
-                "allowed_updates": ["message"]
-            });
-
-            let resp = match self.http_client().post(&url).json(&body).send().await {
-                Ok(r) => r,
-                Err(e) => {
-                    tracing::warn!("Telegram poll error: {e}");
-                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                    continue;
-                }
-            };
-
-            let data: serde_json::Value = match resp.json().await {
-                Ok(d) => d,
-                Err(e) => {
-                    tracing::warn!("Telegram parse error: {e}");
-                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                    continue;
-                }
-            };
-
-            let ok = data
-                .get("ok")
-                .and_then(serde_json::Value::as_bool)
-                .unwrap_or(true);
-            if !ok {
-                let error_code = data
-                    .get("error_code")
-                    .and_then(serde_json::Value::as_i64)
-                    .unwrap_or_default();
-                let description = data
-                    .get("description")
-                    .and_then(serde_json::Value::as_str)
-                    .unwrap_or("unknown Telegram API error");
-
-                if error_code == 409 {
-                    tracing::warn!(
-                        "Telegram polling conflict (409): {description}. \
-Ensure only one `zeroclaw` process is using this bot token."
-                    );
-                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                } else {
-                    tracing::warn!(
-                        "Telegram getUpdates API error (code={}): {description}",
-                        error_code
-                    );
-                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                }
-                continue;
-            }
-
-            if let Some(results) = data.get("result").and_then(serde_json::Value::as_array) {
-                for update in results {
-                    // Advance offset past this update
-                    if let Some(uid) = update.get("update_id").and_then(serde_json::Value::as_i64) {
-                        offset = uid + 1;
-                    }
-
-                    let msg = if let Some(m) = self.parse_update_message(update) {
-                        m
-                    } else if let Some(m) = self.try_parse_voice_message(update).await {
-                        m
-                    } else if let Some(m) = self.try_parse_attachment_message(update).await {
-                        m
-                    } else {
-                        self.handle_unauthorized_message(update).await;
-                        continue;
-                    };
-
-                    if let Some((reaction_chat_id, reaction_message_id)) =
-                        Self::extract_update_message_target(update)
-                    {
-                        self.try_add_ack_reaction_nonblocking(
-                            reaction_chat_id,
-                            reaction_message_id,
-                        );
-                    }
-
-                    // Send "typing" indicator immediately when we receive a message
-                    let typing_body = serde_json::json!({
-                        "chat_id": &msg.reply_target,
-                        "action": "typing"
-                    });
-                    let _ = self
-                        .http_client()
-                        .post(self.api_url("sendChatAction"))
-                        .json(&typing_body)
-                        .send()
-                        .await; // Ignore errors for typing indicator
-
-                    if tx.send(msg).await.is_err() {
-                        return Ok(());
-                    }
-                }
-            }
+        match self.webhook.clone() {
+            Some(webhook) => self.listen_webhook(&webhook, tx).await,
+            None => self.listen_polling(tx).await,
         }
     }
 
@@ -3796,6 +4079,40 @@ mod tests {
     // extract_sender_info tests
     // ─────────────────────────────────────────────────────────────────────
 
+    #[test]
+    fn parse_callback_query_delivers_payload_for_allowed_user() {
+        let ch = TelegramChannel::new("t".into(), vec!["alice".into()], false);
+        let update = serde_json::json!({
+            "update_id": 7,
+            "callback_query": {
+                "id": "cb-1",
+                "from": { "id": 123, "username": "alice" },
+                "message": { "message_id": 9, "chat": { "id": -100 }, "message_thread_id": 4 },
+                "data": "task:resume:abc"
+            }
+        });
+        let msg = ch.parse_callback_query(&update).expect("callback message");
+        assert_eq!(msg.content, "task:resume:abc");
+        assert_eq!(msg.sender, "alice");
+        assert_eq!(msg.reply_target, "-100:4");
+
+        let stranger = TelegramChannel::new("t".into(), vec!["bob".into()], false);
+        assert!(stranger.parse_callback_query(&update).is_none());
+    }
+
+    #[test]
+    fn quick_reply_markup_builds_single_row_inline_keyboard() {
+        assert!(TelegramChannel::quick_reply_markup(&[]).is_none());
+        let markup = TelegramChannel::quick_reply_markup(&[
+            QuickReply::new("Continue", "task:resume:1"),
+            QuickReply::new("Cancel", "task:cancel:1"),
+        ])
+        .expect("markup");
+        let row = markup["inline_keyboard"][0].as_array().expect("row");
+        assert_eq!(row.len(), 2);
+        assert_eq!(row[1]["callback_data"], "task:cancel:1");
+    }
+
     #[test]
     fn extract_sender_info_with_username() {
         let msg = serde_json::json!({
@@ -4425,4 +4742,27 @@ mod tests {
         // the agent loop will return ProviderCapabilityError before calling
         // the provider, and the channel will send "⚠️ Error: ..." to the user.
     }
+
+    #[test]
+    fn webhook_requests_need_the_secret_even_when_unconfigured() {
+        let channel = TelegramChannel::new("fake-token".into(), vec!["*".into()], false)
+            .with_webhook("https://bot.example.com/telegram".into(), 8443, None);
+        let secret = channel.webhook.as_ref().unwrap().secret.clone();
+        assert_eq!(secret.len(), 32);
+
+        let mut headers = axum::http::HeaderMap::new();
+        assert!(!webhook_secret_matches(&headers, &secret));
+        headers.insert(TELEGRAM_SECRET_HEADER, "wrong".parse().unwrap());
+        assert!(!webhook_secret_matches(&headers, &secret));
+        headers.insert(TELEGRAM_SECRET_HEADER, secret.parse().unwrap());
+        assert!(webhook_secret_matches(&headers, &secret));
+
+        let channel = TelegramChannel::new("fake-token".into(), vec!["*".into()], false)
+            .with_webhook(
+                "https://bot.example.com/telegram".into(),
+                8443,
+                Some(" s3cret ".into()),
+            );
+        assert_eq!(channel.webhook.unwrap().secret, "s3cret");
+    }
 }
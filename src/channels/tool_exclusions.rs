@@ -0,0 +1,101 @@
+//! Per-message tool exclusions for non-CLI channels.
+//!
+//! On top of the static `autonomy.non_cli_excluded_tools`, a message loses the
+//! tools listed for its sender's role (`autonomy.role_excluded_tools`, roles
+//! assigned by `autonomy.sender_roles`) and for the hint the query classifier
+//! gave it (`autonomy.hint_excluded_tools`). Messages no classification rule
+//! matches use the `unclassified` entry, so low-confidence intents can be kept
+//! away from tools like `email` or `shell`.
+
+use crate::config::AutonomyConfig;
+use std::collections::{BTreeSet, HashMap};
+
+/// Role of senders not listed in `autonomy.sender_roles`.
+pub const DEFAULT_SENDER_ROLE: &str = "guest";
+
+/// `hint_excluded_tools` key used when the classifier matched no rule.
+pub const UNCLASSIFIED_HINT: &str = "unclassified";
+
+#[derive(Debug, Clone, Default)]
+pub struct ToolExclusionPolicy {
+    /// Lowercased sender id → role.
+    sender_roles: HashMap<String, String>,
+    role_excluded_tools: HashMap<String, Vec<String>>,
+    hint_excluded_tools: HashMap<String, Vec<String>>,
+}
+
+impl ToolExclusionPolicy {
+    pub fn from_config(autonomy: &AutonomyConfig) -> Self {
+        Self {
+            sender_roles: autonomy
+                .sender_roles
+                .iter()
+                .map(|(sender, role)| (sender.to_lowercase(), role.clone()))
+                .collect(),
+            role_excluded_tools: autonomy.role_excluded_tools.clone(),
+            hint_excluded_tools: autonomy.hint_excluded_tools.clone(),
+        }
+    }
+
+    pub fn role_for(&self, sender: &str) -> &str {
+        self.sender_roles
+            .get(&sender.to_lowercase())
+            .map_or(DEFAULT_SENDER_ROLE, String::as_str)
+    }
+
+    /// `base` plus the tools excluded for `sender`'s role and the message's
+    /// classifier `hint`, sorted and deduplicated.
+    pub fn excluded_tools(&self, base: &[String], sender: &str, hint: Option<&str>) -> Vec<String> {
+        let by_role = self.role_excluded_tools.get(self.role_for(sender));
+        let by_hint = self
+            .hint_excluded_tools
+            .get(hint.unwrap_or(UNCLASSIFIED_HINT));
+        base.iter()
+            .chain(by_role.into_iter().flatten())
+            .chain(by_hint.into_iter().flatten())
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exclusions_combine_static_list_sender_role_and_classifier_hint() {
+        let autonomy = AutonomyConfig {
+            sender_roles: HashMap::from([("+15550100".to_string(), "owner".to_string())]),
+            role_excluded_tools: HashMap::from([(
+                DEFAULT_SENDER_ROLE.to_string(),
+                vec!["shell".to_string(), "file_write".to_string()],
+            )]),
+            hint_excluded_tools: HashMap::from([
+                (UNCLASSIFIED_HINT.to_string(), vec!["email".to_string()]),
+                ("finance".to_string(), vec!["http_request".to_string()]),
+            ]),
+            ..AutonomyConfig::default()
+        };
+        let policy = ToolExclusionPolicy::from_config(&autonomy);
+        let base = vec!["browser".to_string()];
+
+        assert_eq!(policy.role_for("+15550100"), "owner");
+        assert_eq!(
+            policy.excluded_tools(&base, "+15550100", Some("fast")),
+            vec!["browser"]
+        );
+        assert_eq!(
+            policy.excluded_tools(&base, "+15550100", Some("finance")),
+            vec!["browser", "http_request"]
+        );
+        assert_eq!(
+            policy.excluded_tools(&base, "stranger@example.com", None),
+            vec!["browser", "email", "file_write", "shell"]
+        );
+        assert!(ToolExclusionPolicy::default()
+            .excluded_tools(&[], "anyone", None)
+            .is_empty());
+    }
+}
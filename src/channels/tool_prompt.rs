@@ -0,0 +1,208 @@
+//! Tools section of the system prompt, derived from the live tool registry.
+//!
+//! The prompt lists exactly the tools the runtime will dispatch, so enabling,
+//! disabling, or excluding a tool can never leave the instructions advertising
+//! something the model cannot call (or hiding something it can).
+
+use crate::tools::Tool;
+
+/// Curated "use when / don't use when" guidance. Tools without an entry fall
+/// back to their own `description()`.
+const TOOL_USAGE_HINTS: &[(&str, &str)] = &[
+    (
+        "shell",
+        "Execute terminal commands. Use when: running local checks, build/test commands, diagnostics. Don't use when: a safer dedicated tool exists, or command is destructive without approval.",
+    ),
+    (
+        "file_read",
+        "Read file contents. Use when: inspecting project files, configs, logs. Don't use when: a targeted search is enough.",
+    ),
+    (
+        "file_write",
+        "Write file contents. Use when: applying focused edits, scaffolding files, updating docs/code. Don't use when: side effects are unclear or file ownership is uncertain.",
+    ),
+    (
+        "memory_store",
+        "Save to memory. Use when: preserving durable preferences, decisions, key context. Don't use when: information is transient/noisy/sensitive without need.",
+    ),
+    (
+        "memory_recall",
+        "Search memory. Use when: retrieving prior decisions, user preferences, historical context. Don't use when: answer is already in current context.",
+    ),
+    (
+        "memory_forget",
+        "Delete a memory entry. Use when: memory is incorrect/stale or explicitly requested for removal. Don't use when: impact is uncertain.",
+    ),
+    (
+        "screenshot",
+        "Capture a screenshot of the current screen. Returns file path and base64-encoded PNG. Use when: visual verification, UI inspection, debugging displays.",
+    ),
+    (
+        "image_info",
+        "Read image file metadata (format, dimensions, size) and optionally base64-encode it. Use when: inspecting images, preparing visual data for analysis.",
+    ),
+    (
+        "browser_open",
+        "Open approved HTTPS URLs in Brave Browser (allowlist-only, no scraping)",
+    ),
+    (
+        "composio",
+        "Execute actions on 1000+ apps via Composio (Gmail, Notion, GitHub, Slack, etc.). Use action='list' to discover actions, 'list_accounts' to retrieve connected account IDs, 'execute' to run (optionally with connected_account_id), and 'connect' for OAuth.",
+    ),
+    (
+        "schedule",
+        "Manage scheduled tasks (create/list/get/cancel/pause/resume). Supports recurring cron and one-shot delays.",
+    ),
+    (
+        "model_routing_config",
+        "Configure default model, scenario routing, and delegate agents. Use for natural-language requests like: 'set conversation to kimi and coding to gpt-5.3-codex'.",
+    ),
+    (
+        "delegate",
+        "Delegate a subtask to a specialized agent. Use when: a task benefits from a different model (e.g. fast summarization, deep reasoning, code generation). The sub-agent runs a single prompt and returns its response.",
+    ),
+    (
+        "arduino_upload",
+        "Upload agent-generated Arduino sketch. Use when: user asks for 'make a heart', 'blink pattern', or custom LED behavior on Arduino. You write the full .ino code; ZeroClaw compiles and uploads it. Pin 13 = built-in LED on Uno.",
+    ),
+    (
+        "hardware_memory_map",
+        "Return flash and RAM address ranges for connected hardware. Use when: user asks for 'upper and lower memory addresses', 'memory map', or 'readable addresses'.",
+    ),
+    (
+        "hardware_board_info",
+        "Return full board info (chip, architecture, memory map) for connected hardware. Use when: user asks for 'board info', 'what board do I have', 'connected hardware', 'chip info', or 'what hardware'.",
+    ),
+];
+
+/// Maximum parameter names listed per tool; the full schema is delivered via
+/// native tool specs or the tool-use protocol section.
+const MAX_LISTED_PARAMS: usize = 8;
+
+/// Build `(name, description)` prompt entries for every registered tool that is
+/// visible to the current run, in registry order.
+pub fn tool_prompt_entries(tools: &[Box<dyn Tool>], excluded: &[String]) -> Vec<(String, String)> {
+    tools
+        .iter()
+        .filter(|tool| !excluded.iter().any(|name| name == tool.name()))
+        .map(|tool| {
+            let name = tool.name();
+            let hint = TOOL_USAGE_HINTS
+                .iter()
+                .find(|(hint_name, _)| *hint_name == name)
+                .map_or_else(
+                    || tool.description().to_string(),
+                    |(_, hint)| (*hint).to_string(),
+                );
+            let params = parameter_summary(&tool.parameters_schema());
+            let desc = if params.is_empty() {
+                hint
+            } else {
+                format!("{hint} (params: {params})")
+            };
+            (name.to_string(), desc)
+        })
+        .collect()
+}
+
+/// Borrow entries in the `(&str, &str)` shape `build_system_prompt` expects.
+pub fn as_tool_descs(entries: &[(String, String)]) -> Vec<(&str, &str)> {
+    entries
+        .iter()
+        .map(|(name, desc)| (name.as_str(), desc.as_str()))
+        .collect()
+}
+
+/// Comma-separated parameter names with required ones marked `*`.
+fn parameter_summary(schema: &serde_json::Value) -> String {
+    let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) else {
+        return String::new();
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|items| items.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+
+    let mut names: Vec<String> = properties
+        .keys()
+        .take(MAX_LISTED_PARAMS)
+        .map(|key| {
+            if required.contains(&key.as_str()) {
+                format!("{key}*")
+            } else {
+                key.clone()
+            }
+        })
+        .collect();
+    if properties.len() > MAX_LISTED_PARAMS {
+        names.push("…".to_string());
+    }
+    names.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolResult;
+    use async_trait::async_trait;
+
+    struct StubTool {
+        name: &'static str,
+    }
+
+    #[async_trait]
+    impl Tool for StubTool {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "Stub description"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string" },
+                    "approved": { "type": "boolean" }
+                },
+                "required": ["command"]
+            })
+        }
+
+        async fn execute(&self, _args: serde_json::Value) -> anyhow::Result<ToolResult> {
+            Ok(ToolResult {
+                success: true,
+                output: String::new(),
+                error: None,
+            })
+        }
+    }
+
+    #[test]
+    fn entries_follow_registry_and_exclusions() {
+        let tools: Vec<Box<dyn Tool>> = vec![
+            Box::new(StubTool { name: "shell" }),
+            Box::new(StubTool {
+                name: "custom_tool",
+            }),
+            Box::new(StubTool { name: "delegate" }),
+        ];
+        let entries = tool_prompt_entries(&tools, &["delegate".to_string()]);
+
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["shell", "custom_tool"]);
+        assert!(entries[0].1.starts_with("Execute terminal commands."));
+        assert!(entries[1].1.starts_with("Stub description"));
+        assert!(entries[1].1.contains("command*"));
+        assert!(entries[1].1.contains("approved"));
+        assert!(!entries[1].1.contains("approved*"));
+    }
+
+    #[test]
+    fn schema_without_properties_adds_no_param_list() {
+        assert!(parameter_summary(&serde_json::json!({"type": "object"})).is_empty());
+    }
+}
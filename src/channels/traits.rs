@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use std::path::Path;
 
 /// A message received from or sent to a channel
 #[derive(Debug, Clone)]
@@ -14,6 +15,24 @@ pub struct ChannelMessage {
     pub thread_ts: Option<String>,
 }
 
+/// An interactive reply option rendered as a button on channels that support it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickReply {
+    /// Button text shown to the user.
+    pub label: String,
+    /// Content delivered back as an inbound message when the button is pressed.
+    pub payload: String,
+}
+
+impl QuickReply {
+    pub fn new(label: impl Into<String>, payload: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            payload: payload.into(),
+        }
+    }
+}
+
 /// Message to send through a channel
 #[derive(Debug, Clone)]
 pub struct SendMessage {
@@ -22,6 +41,9 @@ pub struct SendMessage {
     pub subject: Option<String>,
     /// Platform thread identifier for threaded replies (e.g. Slack `thread_ts`).
     pub thread_ts: Option<String>,
+    /// Interactive options attached to the message. Channels without button
+    /// support ignore these; callers add a text fallback instead.
+    pub quick_replies: Vec<QuickReply>,
 }
 
 impl SendMessage {
@@ -32,6 +54,7 @@ impl SendMessage {
             recipient: recipient.into(),
             subject: None,
             thread_ts: None,
+            quick_replies: Vec::new(),
         }
     }
 
@@ -46,6 +69,7 @@ impl SendMessage {
             recipient: recipient.into(),
             subject: Some(subject.into()),
             thread_ts: None,
+            quick_replies: Vec::new(),
         }
     }
 
@@ -54,6 +78,12 @@ impl SendMessage {
         self.thread_ts = thread_ts;
         self
     }
+
+    /// Attach interactive quick-reply options.
+    pub fn with_quick_replies(mut self, quick_replies: Vec<QuickReply>) -> Self {
+        self.quick_replies = quick_replies;
+        self
+    }
 }
 
 /// Core channel trait — implement for any messaging platform
@@ -89,6 +119,12 @@ pub trait Channel: Send + Sync {
         false
     }
 
+    /// Whether this channel renders [`SendMessage::quick_replies`] as buttons
+    /// and delivers the pressed button's payload back as an inbound message.
+    fn supports_quick_replies(&self) -> bool {
+        false
+    }
+
     /// Send an initial draft message. Returns a platform-specific message ID for later edits.
     async fn send_draft(&self, _message: &SendMessage) -> anyhow::Result<Option<String>> {
         Ok(None)
@@ -119,6 +155,19 @@ pub trait Channel: Send + Sync {
         Ok(())
     }
 
+    /// Upload a local file to `recipient` (e.g. an iMessage attachment or a
+    /// Slack file upload), optionally with a caption. Channels that cannot
+    /// carry files return an error.
+    async fn send_file(
+        &self,
+        _recipient: &str,
+        _thread_ts: Option<&str>,
+        _path: &Path,
+        _caption: Option<&str>,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!("The {} channel does not support sending files", self.name())
+    }
+
     /// Add a reaction (emoji) to a message.
     ///
     /// `channel_id` is the platform channel/conversation identifier (e.g. Discord channel ID).
@@ -210,6 +259,10 @@ mod tests {
             .send(&SendMessage::new("hello", "bob"))
             .await
             .is_ok());
+        assert!(channel
+            .send_file("bob", None, Path::new("report.pdf"), None)
+            .await
+            .is_err());
     }
 
     #[tokio::test]
@@ -244,6 +297,19 @@ mod tests {
         assert!(channel.cancel_draft("bob", "msg_1").await.is_ok());
     }
 
+    #[test]
+    fn send_message_quick_replies_default_empty_and_builder_sets_them() {
+        let plain = SendMessage::new("hello", "bob");
+        assert!(plain.quick_replies.is_empty());
+        assert!(!DummyChannel.supports_quick_replies());
+
+        let with_buttons = SendMessage::new("approve?", "bob")
+            .with_quick_replies(vec![QuickReply::new("Continue", "task:resume:1")]);
+        assert_eq!(with_buttons.quick_replies.len(), 1);
+        assert_eq!(with_buttons.quick_replies[0].label, "Continue");
+        assert_eq!(with_buttons.quick_replies[0].payload, "task:resume:1");
+    }
+
     #[tokio::test]
     async fn listen_sends_message_to_channel() {
         let channel = DummyChannel;
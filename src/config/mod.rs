@@ -5,18 +5,23 @@ pub mod traits;
 pub use schema::{
     apply_runtime_proxy_to_builder, build_runtime_proxy_client,
     build_runtime_proxy_client_with_timeouts, runtime_proxy_config, set_runtime_proxy_config,
-    AgentConfig, AuditConfig, AutonomyConfig, BrowserComputerUseConfig, BrowserConfig,
-    BuiltinHooksConfig, ChannelsConfig, ClassificationRule, ComposioConfig, Config, CostConfig,
-    CronConfig, DelegateAgentConfig, DiscordConfig, DockerRuntimeConfig, EmbeddingRouteConfig,
-    EstopConfig, FeishuConfig, GatewayConfig, HardwareConfig, HardwareTransport, HeartbeatConfig,
-    HooksConfig, HttpRequestConfig, IMessageConfig, IdentityConfig, LarkConfig, MatrixConfig,
-    MemoryConfig, ModelRouteConfig, MultimodalConfig, NextcloudTalkConfig, ObservabilityConfig,
-    OtpConfig, OtpMethod, PeripheralBoardConfig, PeripheralsConfig, ProxyConfig, ProxyScope,
-    QueryClassificationConfig, ReliabilityConfig, ResourceLimitsConfig, RuntimeConfig,
-    SandboxBackend, SandboxConfig, SchedulerConfig, SecretsConfig, SecurityConfig, SkillsConfig,
-    SkillsPromptInjectionMode, SlackConfig, StorageConfig, StorageProviderConfig,
-    StorageProviderSection, StreamMode, TelegramConfig, TranscriptionConfig, TunnelConfig,
-    WebSearchConfig, WebhookConfig,
+    AgentConfig, ArtifactVerificationRule, ArtifactVerificationStrategy, AuditConfig,
+    AutonomyConfig, BrowserComputerUseConfig, BrowserConfig, BuiltinHooksConfig, ChannelsConfig,
+    ClassificationRule, CodeRunConfig, CompletionEvaluator, CompletionLanguagePack,
+    CompletionPolicy, ComposioConfig, Config, ContinuationPromptConfig, CostConfig, CronConfig,
+    DelegateAgentConfig, DiscordConfig, DockerRuntimeConfig, EmbeddingRouteConfig, EstopConfig,
+    FeishuConfig, GatewayConfig, HardwareConfig, HardwareTransport, HeartbeatConfig, HooksConfig,
+    HttpRequestConfig, IMessageConfig, IdentityConfig, LarkConfig, MacroToolConfig, MacroToolStep,
+    MatrixConfig, MemoryConfig, ModelPricing, ModelRouteConfig, MultimodalConfig,
+    NextcloudTalkConfig, ObservabilityConfig, OtpConfig, OtpMethod, PeripheralBoardConfig,
+    PeripheralsConfig, ProviderParams, ProviderRateLimitConfig, ProviderRecordingMode,
+    ProviderSpec, ProxyConfig, ProxyScope, QueryClassificationConfig, ReasoningEffort,
+    ReliabilityConfig, ResourceLimitsConfig, RuntimeConfig, SandboxBackend, SandboxConfig,
+    SchedulerConfig, SecretsConfig, SecurityConfig, SkillsConfig, SkillsPromptInjectionMode,
+    SlackConfig, SqlQueryConfig, StorageConfig, StorageProviderConfig, StorageProviderSection,
+    StreamMode, TaskAcceptanceTests, TaskBudget, TelegramConfig, TelegramReceiveMode,
+    ToolLimitsConfig, ToolPolicyConfig, ToolPolicyRules, TranscriptionConfig, TunnelConfig,
+    WebFetchConfig, WebSearchConfig, WebhookConfig, WebhookTaskConfig,
 };
 
 pub fn name_and_presence<T: traits::ChannelConfig>(channel: &Option<T>) -> (&'static str, bool) {
@@ -45,6 +50,10 @@ mod tests {
             draft_update_interval_ms: 1000,
             interrupt_on_new_message: false,
             mention_only: false,
+            receive_mode: TelegramReceiveMode::Polling,
+            webhook_url: None,
+            port: None,
+            webhook_secret: None,
         };
 
         let discord = DiscordConfig {
@@ -53,6 +62,9 @@ mod tests {
             allowed_users: vec![],
             listen_to_bots: false,
             mention_only: false,
+            channel_ids: vec![],
+            stream_mode: StreamMode::default(),
+            draft_update_interval_ms: 1000,
         };
 
         let lark = LarkConfig {
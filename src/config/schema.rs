@@ -39,6 +39,7 @@ const SUPPORTED_PROXY_SERVICE_KEYS: &[&str] = &[
     "tool.composio",
     "tool.http_request",
     "tool.pushover",
+    "tool.web_fetch",
     "memory.embeddings",
     "tunnel.custom",
     "transcription.groq",
@@ -177,6 +178,26 @@ pub struct Config {
     #[serde(default)]
     pub web_search: WebSearchConfig,
 
+    /// Web page fetch tool configuration (`[web_fetch]`).
+    #[serde(default)]
+    pub web_fetch: WebFetchConfig,
+
+    /// Code execution sandbox tool configuration (`[code_run]`).
+    #[serde(default)]
+    pub code_run: CodeRunConfig,
+
+    /// Database query tool configuration (`[sql_query]`).
+    #[serde(default)]
+    pub sql_query: SqlQueryConfig,
+
+    /// Tools that run a fixed sequence of other tool calls (`[macro_tools.<name>]`).
+    #[serde(default)]
+    pub macro_tools: HashMap<String, MacroToolConfig>,
+
+    /// Gateway endpoints that turn incoming JSON into tasks (`[webhook_tasks.<name>]`).
+    #[serde(default)]
+    pub webhook_tasks: HashMap<String, WebhookTaskConfig>,
+
     /// Proxy configuration for outbound HTTP/HTTPS/SOCKS5 traffic (`[proxy]`).
     #[serde(default)]
     pub proxy: ProxyConfig,
@@ -379,12 +400,105 @@ pub struct AgentConfig {
     /// Maximum conversation history messages retained per session. Default: `50`.
     #[serde(default = "default_agent_max_history_messages")]
     pub max_history_messages: usize,
+    /// Estimated history size (tokens) at which a task run summarizes its
+    /// older assistant turns and tool results. `0` disables. Default: `64000`.
+    #[serde(default = "default_agent_context_max_tokens")]
+    pub context_max_tokens: usize,
     /// Enable parallel tool execution within a single iteration. Default: `false`.
     #[serde(default)]
     pub parallel_tools: bool,
+    /// Maximum tool calls run at once when `parallel_tools` is on. Default: `4`.
+    #[serde(default = "default_agent_max_parallel_tools")]
+    pub max_parallel_tools: usize,
     /// Tool dispatch strategy (e.g. `"auto"`). Default: `"auto"`.
     #[serde(default = "default_agent_tool_dispatcher")]
     pub tool_dispatcher: String,
+    /// Wall-clock limit per tool call; the call is stopped when it runs out.
+    /// `0` disables. Default: `600`.
+    #[serde(default = "default_agent_tool_timeout_secs")]
+    pub tool_timeout_secs: u64,
+    /// Bytes of tool output kept per call; longer output keeps its start and
+    /// end. `0` disables. Default: `1048576`.
+    #[serde(default = "default_agent_tool_max_output_bytes")]
+    pub tool_max_output_bytes: usize,
+    /// Overrides of the two limits above, keyed by tool name
+    /// (`[agent.tool_limits.<tool>]`).
+    #[serde(default)]
+    pub tool_limits: HashMap<String, ToolLimitsConfig>,
+    /// Sampling and reasoning parameters for task runs; a task's own
+    /// parameters override these one by one.
+    #[serde(default)]
+    pub provider_params: ProviderParams,
+}
+
+/// Limits for one tool (`[agent.tool_limits.<tool>]`); unset keys use the
+/// `[agent]` defaults, and `0` disables a limit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolLimitsConfig {
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>,
+}
+
+/// Reasoning depth requested from models with explicit controls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// Model parameters each provider translates to its own API
+/// (`[agent.provider_params]`). Parameters a provider has no equivalent for
+/// are dropped.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProviderParams {
+    #[serde(default)]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Nucleus sampling cutoff (`0.0`-`1.0`).
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    /// Output token cap per response.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Sequences that end the response.
+    #[serde(default)]
+    pub stop: Vec<String>,
+}
+
+impl ProviderParams {
+    pub fn is_empty(&self) -> bool {
+        self.reasoning_effort.is_none()
+            && self.top_p.is_none()
+            && self.max_tokens.is_none()
+            && self.stop.is_empty()
+    }
+
+    /// These parameters, with unset ones taken from `defaults`.
+    pub fn or(&self, defaults: &Self) -> Self {
+        Self {
+            reasoning_effort: self.reasoning_effort.or(defaults.reasoning_effort),
+            top_p: self.top_p.or(defaults.top_p),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            stop: if self.stop.is_empty() {
+                defaults.stop.clone()
+            } else {
+                self.stop.clone()
+            },
+        }
+    }
 }
 
 fn default_agent_max_tool_iterations() -> usize {
@@ -395,18 +509,52 @@ fn default_agent_max_history_messages() -> usize {
     50
 }
 
+fn default_agent_context_max_tokens() -> usize {
+    64_000
+}
+
+fn default_agent_max_parallel_tools() -> usize {
+    4
+}
+
 fn default_agent_tool_dispatcher() -> String {
     "auto".into()
 }
 
+fn default_agent_tool_timeout_secs() -> u64 {
+    600
+}
+
+fn default_agent_tool_max_output_bytes() -> usize {
+    1_048_576
+}
+
 impl Default for AgentConfig {
     fn default() -> Self {
         Self {
             compact_context: false,
             max_tool_iterations: default_agent_max_tool_iterations(),
             max_history_messages: default_agent_max_history_messages(),
+            context_max_tokens: default_agent_context_max_tokens(),
             parallel_tools: false,
+            max_parallel_tools: default_agent_max_parallel_tools(),
             tool_dispatcher: default_agent_tool_dispatcher(),
+            tool_timeout_secs: default_agent_tool_timeout_secs(),
+            tool_max_output_bytes: default_agent_tool_max_output_bytes(),
+            tool_limits: HashMap::new(),
+            provider_params: ProviderParams::default(),
+        }
+    }
+}
+
+impl AgentConfig {
+    /// Tool calls from one model response that may run at once: `1` (in
+    /// order) unless `parallel_tools` is on.
+    pub fn tool_concurrency(&self) -> usize {
+        if self.parallel_tools {
+            self.max_parallel_tools.max(1)
+        } else {
+            1
         }
     }
 }
@@ -558,6 +706,11 @@ pub struct CostConfig {
     /// Per-model pricing (USD per 1M tokens)
     #[serde(default)]
     pub prices: std::collections::HashMap<String, ModelPricing>,
+
+    /// Append each completed task's token usage and estimated cost to its
+    /// final reply (default: false)
+    #[serde(default)]
+    pub report_cost: bool,
 }
 
 /// Per-model pricing entry (USD per 1M tokens).
@@ -593,6 +746,7 @@ impl Default for CostConfig {
             warn_at_percent: default_warn_percent(),
             allow_override: false,
             prices: get_default_pricing(),
+            report_cost: false,
         }
     }
 }
@@ -955,6 +1109,9 @@ pub struct BrowserConfig {
     /// Computer-use sidecar configuration
     #[serde(default)]
     pub computer_use: BrowserComputerUseConfig,
+    /// Browser actions one task run may take (0 = unlimited)
+    #[serde(default = "default_browser_max_actions_per_run")]
+    pub max_actions_per_run: u32,
 }
 
 fn default_browser_backend() -> String {
@@ -965,6 +1122,10 @@ fn default_browser_webdriver_url() -> String {
     "http://127.0.0.1:9515".into()
 }
 
+fn default_browser_max_actions_per_run() -> u32 {
+    50
+}
+
 impl Default for BrowserConfig {
     fn default() -> Self {
         Self {
@@ -976,6 +1137,7 @@ impl Default for BrowserConfig {
             native_webdriver_url: default_browser_webdriver_url(),
             native_chrome_path: None,
             computer_use: BrowserComputerUseConfig::default(),
+            max_actions_per_run: default_browser_max_actions_per_run(),
         }
     }
 }
@@ -1028,12 +1190,16 @@ pub struct WebSearchConfig {
     /// Enable `web_search_tool` for web searches
     #[serde(default)]
     pub enabled: bool,
-    /// Search provider: "duckduckgo" (free), "bing" (free), or "brave" (requires API key)
+    /// Search provider: "duckduckgo" (free), "bing" (free), "brave" (requires API key),
+    /// or "searxng" (requires `searxng_url`)
     #[serde(default = "default_web_search_provider")]
     pub provider: String,
     /// Brave Search API key (required if provider is "brave")
     #[serde(default)]
     pub brave_api_key: Option<String>,
+    /// SearXNG instance base URL with the JSON format enabled (required if provider is "searxng")
+    #[serde(default)]
+    pub searxng_url: Option<String>,
     /// Maximum results per search (1-10)
     #[serde(default = "default_web_search_max_results")]
     pub max_results: usize,
@@ -1060,12 +1226,311 @@ impl Default for WebSearchConfig {
             enabled: false,
             provider: default_web_search_provider(),
             brave_api_key: None,
+            searxng_url: None,
             max_results: default_web_search_max_results(),
             timeout_secs: default_web_search_timeout_secs(),
         }
     }
 }
 
+// ── Web fetch ────────────────────────────────────────────────────
+
+/// Web page fetch tool configuration (`[web_fetch]` section).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WebFetchConfig {
+    /// Enable `web_fetch` for reading web pages as Markdown
+    #[serde(default)]
+    pub enabled: bool,
+    /// Allowed domains (exact or subdomain match, or "*" for any public domain)
+    #[serde(default = "default_web_fetch_allowed_domains")]
+    pub allowed_domains: Vec<String>,
+    /// Maximum characters of page content returned per fetch
+    #[serde(default = "default_web_fetch_max_chars")]
+    pub max_chars: usize,
+    /// Maximum bytes downloaded per page (default: 2MB)
+    #[serde(default = "default_web_fetch_max_response_size")]
+    pub max_response_size: usize,
+    /// Request timeout in seconds
+    #[serde(default = "default_web_fetch_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_web_fetch_allowed_domains() -> Vec<String> {
+    vec!["*".into()]
+}
+
+fn default_web_fetch_max_chars() -> usize {
+    20_000
+}
+
+fn default_web_fetch_max_response_size() -> usize {
+    2_000_000
+}
+
+fn default_web_fetch_timeout_secs() -> u64 {
+    20
+}
+
+impl Default for WebFetchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_domains: default_web_fetch_allowed_domains(),
+            max_chars: default_web_fetch_max_chars(),
+            max_response_size: default_web_fetch_max_response_size(),
+            timeout_secs: default_web_fetch_timeout_secs(),
+        }
+    }
+}
+
+// ── Code run ────────────────────────────────────────────────────
+
+/// Code execution sandbox configuration (`[code_run]` section).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CodeRunConfig {
+    /// Enable `code_run` for executing Python and Node snippets
+    #[serde(default)]
+    pub enabled: bool,
+    /// Where snippets run: "auto" (docker or podman, whichever is installed),
+    /// "docker", "podman", or "native" (unsandboxed; needs full autonomy)
+    #[serde(default = "default_code_run_backend")]
+    pub backend: String,
+    /// Wall-clock limit per run in seconds
+    #[serde(default = "default_code_run_timeout_secs")]
+    pub timeout_secs: u64,
+    /// CPU time limit per run in seconds
+    #[serde(default = "default_code_run_cpu_time_secs")]
+    pub cpu_time_secs: u64,
+    /// Memory limit per run in MB
+    #[serde(default = "default_code_run_max_memory_mb")]
+    pub max_memory_mb: u64,
+    /// CPUs available to a container run
+    #[serde(default = "default_code_run_cpus")]
+    pub cpus: f64,
+    /// Bytes of stdout and of stderr returned per run
+    #[serde(default = "default_code_run_max_output_bytes")]
+    pub max_output_bytes: usize,
+    /// Give container runs network access (native runs always have it)
+    #[serde(default)]
+    pub allow_network: bool,
+    /// Container image for Python snippets
+    #[serde(default = "default_code_run_python_image")]
+    pub python_image: String,
+    /// Container image for Node snippets
+    #[serde(default = "default_code_run_node_image")]
+    pub node_image: String,
+}
+
+fn default_code_run_backend() -> String {
+    "auto".into()
+}
+
+fn default_code_run_timeout_secs() -> u64 {
+    30
+}
+
+fn default_code_run_cpu_time_secs() -> u64 {
+    10
+}
+
+fn default_code_run_max_memory_mb() -> u64 {
+    256
+}
+
+fn default_code_run_cpus() -> f64 {
+    1.0
+}
+
+fn default_code_run_max_output_bytes() -> usize {
+    65_536
+}
+
+fn default_code_run_python_image() -> String {
+    "python:3.12-alpine".into()
+}
+
+fn default_code_run_node_image() -> String {
+    "node:22-alpine".into()
+}
+
+impl Default for CodeRunConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: default_code_run_backend(),
+            timeout_secs: default_code_run_timeout_secs(),
+            cpu_time_secs: default_code_run_cpu_time_secs(),
+            max_memory_mb: default_code_run_max_memory_mb(),
+            cpus: default_code_run_cpus(),
+            max_output_bytes: default_code_run_max_output_bytes(),
+            allow_network: false,
+            python_image: default_code_run_python_image(),
+            node_image: default_code_run_node_image(),
+        }
+    }
+}
+
+// ── SQL query ───────────────────────────────────────────────────
+
+/// Database query tool configuration (`[sql_query]` section).
+///
+/// ```toml
+/// [sql_query]
+/// enabled = true
+/// databases = { app = "data/app.db", analytics = "postgres://reader@localhost/analytics" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SqlQueryConfig {
+    /// Enable `sql_query` for querying the configured databases
+    #[serde(default)]
+    pub enabled: bool,
+    /// Databases by name: a SQLite file (absolute or workspace-relative) or a
+    /// `postgres://` connection URL
+    #[serde(default)]
+    pub databases: HashMap<String, String>,
+    /// Statement kinds that may run, by leading keyword (`select` covers
+    /// queries, including `with ... select`)
+    #[serde(default = "default_sql_query_allowed_statements")]
+    pub allowed_statements: Vec<String>,
+    /// Rows returned per query
+    #[serde(default = "default_sql_query_max_rows")]
+    pub max_rows: usize,
+    /// Query timeout in seconds
+    #[serde(default = "default_sql_query_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_sql_query_allowed_statements() -> Vec<String> {
+    vec!["select".into()]
+}
+
+fn default_sql_query_max_rows() -> usize {
+    200
+}
+
+fn default_sql_query_timeout_secs() -> u64 {
+    10
+}
+
+impl Default for SqlQueryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            databases: HashMap::new(),
+            allowed_statements: default_sql_query_allowed_statements(),
+            max_rows: default_sql_query_max_rows(),
+            timeout_secs: default_sql_query_timeout_secs(),
+        }
+    }
+}
+
+// ── Macro tools ─────────────────────────────────────────────────
+
+/// A tool the model calls once that runs several existing tool calls in order
+/// (`[macro_tools.<name>]` section). `{{param}}` in any string argument of a
+/// step is replaced with the macro's argument of that name.
+///
+/// ```toml
+/// [macro_tools.deploy_docs]
+/// description = "Build the docs and publish them to a host"
+/// parameters = { host = "Host to publish to" }
+/// steps = [
+///   { tool = "shell", args = { command = "mdbook build docs" } },
+///   { tool = "shell", args = { command = "rsync -a docs/book/ {{host}}:/srv/docs" } },
+///   { tool = "http_request", args = { url = "https://{{host}}/docs/", method = "GET" } },
+/// ]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MacroToolConfig {
+    /// Description shown to the model
+    pub description: String,
+    /// String arguments the model must pass, with a description of each
+    #[serde(default)]
+    pub parameters: HashMap<String, String>,
+    /// Tool calls run in order; the macro stops at the first failing step
+    pub steps: Vec<MacroToolStep>,
+}
+
+/// One tool call of a macro tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MacroToolStep {
+    /// Name of the registered tool to call
+    pub tool: String,
+    /// Arguments for the call; string values may contain `{{param}}` placeholders
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+/// `{{name}}` placeholders in the string values of `value`.
+pub fn macro_placeholders(value: &serde_json::Value) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value {
+            serde_json::Value::String(text) => {
+                let mut rest = text.as_str();
+                while let Some(start) = rest.find("{{") {
+                    let Some(len) = rest[start + 2..].find("}}") else {
+                        break;
+                    };
+                    names.push(rest[start + 2..start + 2 + len].trim().to_string());
+                    rest = &rest[start + 2 + len + 2..];
+                }
+            }
+            serde_json::Value::Array(items) => stack.extend(items),
+            serde_json::Value::Object(map) => stack.extend(map.values()),
+            _ => {}
+        }
+    }
+    names
+}
+
+// ── Webhook tasks ───────────────────────────────────────────────
+
+/// A gateway endpoint that starts an agent task for each JSON payload posted
+/// to it (`[webhook_tasks.<name>]` section, served at `POST /hooks/<name>`).
+/// `{{path}}` in the templates is replaced with the payload value at that
+/// dot-separated path (`{{alerts.0.labels.severity}}`), `{{path|fallback}}`
+/// uses the fallback when the value is missing, and `{{.}}` is the whole
+/// payload as JSON.
+///
+/// ```toml
+/// [webhook_tasks.ci_failure]
+/// secret = "shared-secret"
+/// request = "CI failed on {{repository.full_name}} ({{workflow_run.html_url}}). Find the cause and propose a fix."
+/// filters = { action = "completed", "workflow_run.conclusion" = "failure" }
+/// reply_channel = "slack"
+/// reply_target = "C0123456"
+/// allow_tools = ["file_read", "git_operations"]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WebhookTaskConfig {
+    /// Template for the task request
+    pub request: String,
+    /// Payload paths that must render to the given values for a task to
+    /// start; other payloads are acknowledged and skipped
+    #[serde(default)]
+    pub filters: HashMap<String, String>,
+    /// Channel the final reply is sent to (`telegram`, `discord`, `slack`,
+    /// or `mattermost`); unset keeps the result in the task store only
+    #[serde(default)]
+    pub reply_channel: Option<String>,
+    /// Template for the recipient on `reply_channel` (chat, channel, or user
+    /// id). Placeholders here let whoever posts the payload pick who gets
+    /// the answer; prefer a fixed target
+    #[serde(default)]
+    pub reply_target: Option<String>,
+    /// Shared secret callers send as `X-Webhook-Secret`, or use to sign the
+    /// body in `X-Hub-Signature-256` (GitHub style); unset requires the
+    /// gateway bearer token instead
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Only these tools are offered to and run by this endpoint's tasks, on
+    /// top of `autonomy.tool_policy`; empty runs them with no tools
+    #[serde(default)]
+    pub allow_tools: Vec<String>,
+}
+
 // ── Proxy ───────────────────────────────────────────────────────
 
 /// Proxy application scope — determines which outbound traffic uses the proxy.
@@ -1623,7 +2088,7 @@ pub struct MemoryConfig {
     /// For sqlite backend: prune conversation rows older than this many days
     #[serde(default = "default_conversation_retention_days")]
     pub conversation_retention_days: u32,
-    /// Embedding provider: "none" | "openai" | "custom:URL"
+    /// Embedding provider: "none" | "openai" | "openrouter" | "ollama" | "ollama:URL" | "custom:URL"
     #[serde(default = "default_embedding_provider")]
     pub embedding_provider: String,
     /// Embedding model name (e.g. "text-embedding-3-small")
@@ -1660,6 +2125,10 @@ pub struct MemoryConfig {
     /// Max number of cached responses before LRU eviction (default: 5000)
     #[serde(default = "default_response_cache_max")]
     pub response_cache_max_entries: usize,
+    /// Also cache requests sent with a temperature above 0 (default: false;
+    /// sampled replies are meant to vary)
+    #[serde(default)]
+    pub response_cache_any_temperature: bool,
 
     // ── Memory Snapshot (soul backup to Markdown) ─────────────
     /// Enable periodic export of core memories to MEMORY_SNAPSHOT.md
@@ -1742,6 +2211,7 @@ impl Default for MemoryConfig {
             response_cache_enabled: false,
             response_cache_ttl_minutes: default_response_cache_ttl(),
             response_cache_max_entries: default_response_cache_max(),
+            response_cache_any_temperature: false,
             snapshot_enabled: false,
             snapshot_on_hygiene: false,
             auto_hydrate: true,
@@ -1871,45 +2341,513 @@ pub struct AutonomyConfig {
     #[serde(default = "default_true")]
     pub block_high_risk_commands: bool,
 
-    /// Additional environment variables allowed for shell tool subprocesses.
-    ///
-    /// These names are explicitly allowlisted and merged with the built-in safe
-    /// baseline (`PATH`, `HOME`, etc.) after `env_clear()`.
+    /// Additional environment variables allowed for shell tool subprocesses.
+    ///
+    /// These names are explicitly allowlisted and merged with the built-in safe
+    /// baseline (`PATH`, `HOME`, etc.) after `env_clear()`.
+    #[serde(default)]
+    pub shell_env_passthrough: Vec<String>,
+
+    /// Tools that never require approval (e.g. read-only tools).
+    #[serde(default = "default_auto_approve")]
+    pub auto_approve: Vec<String>,
+
+    /// Tools that always require interactive approval, even after "Always".
+    #[serde(default = "default_always_ask")]
+    pub always_ask: Vec<String>,
+
+    /// Extra directory roots the agent may read/write outside the workspace.
+    /// Supports absolute, `~/...`, and workspace-relative entries.
+    /// Resolved paths under any of these roots pass `is_resolved_path_allowed`.
+    #[serde(default)]
+    pub allowed_roots: Vec<String>,
+
+    /// Tools to exclude from non-CLI channels (e.g. Telegram, Discord).
+    ///
+    /// When a tool is listed here, non-CLI channels will not expose it to the
+    /// model in tool specs.
+    #[serde(default)]
+    pub non_cli_excluded_tools: Vec<String>,
+
+    /// Role of each sender on non-CLI channels (`[autonomy.sender_roles]`,
+    /// sender id → role name). Unlisted senders have the role `guest`.
+    #[serde(default)]
+    pub sender_roles: HashMap<String, String>,
+
+    /// Tools hidden from senders of a role (`[autonomy.role_excluded_tools]`),
+    /// e.g. `guest = ["shell"]`.
+    #[serde(default)]
+    pub role_excluded_tools: HashMap<String, Vec<String>>,
+
+    /// Tools hidden from messages the query classifier gives a hint
+    /// (`[autonomy.hint_excluded_tools]`); the key `unclassified` applies when
+    /// no classification rule matches.
+    #[serde(default)]
+    pub hint_excluded_tools: HashMap<String, Vec<String>>,
+
+    /// Enable contract-based completion verification engine.
+    #[serde(default = "default_true")]
+    pub contract_completion_engine: bool,
+
+    /// Enable gray-zone verifier for near-terminal ambiguous states.
+    #[serde(default = "default_true")]
+    pub gray_zone_verifier_enabled: bool,
+
+    /// Timeout in milliseconds for gray-zone verifier calls.
+    #[serde(default = "default_gray_zone_verifier_timeout_ms")]
+    pub gray_zone_verifier_timeout_ms: u64,
+
+    /// Wall-clock budget in seconds for a single task-engine run before the
+    /// watchdog cancels it and marks it failed (`0` disables the watchdog).
+    #[serde(default = "default_task_timeout_secs")]
+    pub task_timeout_secs: u64,
+
+    /// Archive completed, failed, and cancelled task runs this many days after
+    /// they finish (`0` disables automatic archival).
+    #[serde(default = "default_task_archive_after_days")]
+    pub task_archive_after_days: u64,
+
+    /// Move finished task runs this many days old out of the task database
+    /// into compressed archives under `state/archive/` (`0` disables).
+    #[serde(default = "default_task_cold_storage_after_days")]
+    pub task_cold_storage_after_days: u64,
+
+    /// Fail running tasks that have not sent a heartbeat for this many
+    /// minutes and alert their sender (`0` disables the stuck-task detector).
+    #[serde(default = "default_task_stuck_after_mins")]
+    pub task_stuck_after_mins: u64,
+
+    /// Ask before re-running a request that repeats one the same sender made
+    /// this many minutes ago, if that task is still running or completed
+    /// (`0` disables the check).
+    #[serde(default = "default_duplicate_task_window_mins")]
+    pub duplicate_task_window_mins: u64,
+
+    /// Environment variables given to `shell` and `git_operations` during
+    /// task-engine runs (`[autonomy.task_env]`), e.g. a project-specific
+    /// `PATH` or API endpoint. Only child processes of the task see them;
+    /// secret-looking values are redacted in the `task_env` event.
+    #[serde(default)]
+    pub task_env: HashMap<String, String>,
+
+    /// Providers a task switches to, in order, once the current one keeps
+    /// failing with retryable errors (`[[autonomy.task_fallback_providers]]`).
+    #[serde(default)]
+    pub task_fallback_providers: Vec<ProviderSpec>,
+
+    /// Spending limits for a single task run (`[autonomy.task_budget]`).
+    #[serde(default)]
+    pub task_budget: TaskBudget,
+
+    /// Include a progress message for every tool call a task makes, on top of
+    /// round and continuation updates, for senders with verbose progress.
+    #[serde(default = "default_true")]
+    pub task_progress_tool_calls: bool,
+
+    /// Record which workspace files each task round created, modified, or
+    /// deleted, as `files_changed` events in the task log.
+    #[serde(default)]
+    pub task_file_snapshots: bool,
+
+    /// Have the model split each task-engine request into numbered steps and
+    /// run them one at a time, tracking each step's completion.
+    #[serde(default)]
+    pub task_step_decomposition: bool,
+
+    /// Test command code-change tasks must pass before they complete
+    /// (`[autonomy.task_acceptance_tests]`).
+    #[serde(default)]
+    pub task_acceptance_tests: TaskAcceptanceTests,
+
+    /// Back up files before task tools overwrite them, so a run's writes can
+    /// be undone with `zeroclaw task rollback <id>`.
+    #[serde(default)]
+    pub task_write_backups: bool,
+
+    /// Completion strictness for task-engine runs (`[autonomy.completion_policy]`).
+    #[serde(default)]
+    pub completion_policy: CompletionPolicy,
+
+    /// Per-channel completion policies keyed by channel name
+    /// (`[autonomy.channel_completion_policies.imessage]`); channels without an
+    /// entry use `completion_policy`.
+    #[serde(default)]
+    pub channel_completion_policies: HashMap<String, CompletionPolicy>,
+
+    /// Per-call tool permissions by channel and sender
+    /// (`[autonomy.tool_policy]`), checked before each tool call.
+    #[serde(default)]
+    pub tool_policy: ToolPolicyConfig,
+}
+
+/// Tool permission rules checked before every tool call. The top-level rules
+/// apply everywhere; `channels` and `senders` add rules for one channel or
+/// sender id. Rules only narrow what the rest of `[autonomy]` allows.
+///
+/// ```toml
+/// [autonomy.tool_policy]
+/// confirm_tools = ["file_write"]
+/// shell_deny_patterns = ['\brm\s+-rf\b']
+///
+/// [autonomy.tool_policy.channels.telegram]
+/// deny_tools = ["browser"]
+/// file_paths = ["notes"]
+///
+/// [autonomy.tool_policy.senders."+15550100"]
+/// shell_commands = ["git", "ls"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolPolicyConfig {
+    #[serde(flatten)]
+    pub rules: ToolPolicyRules,
+    /// Rules for messages on one channel, keyed by channel name
+    #[serde(default)]
+    pub channels: HashMap<String, ToolPolicyRules>,
+    /// Rules for one sender, keyed by sender id
+    #[serde(default)]
+    pub senders: HashMap<String, ToolPolicyRules>,
+}
+
+/// One layer of [`ToolPolicyConfig`]. Empty lists leave that check off.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolPolicyRules {
+    /// Only these tools may be called
+    #[serde(default)]
+    pub allow_tools: Vec<String>,
+    /// Tools that may never be called
+    #[serde(default)]
+    pub deny_tools: Vec<String>,
+    /// Roots the paths of file tools must fall under (absolute, `~/...`, or
+    /// workspace-relative)
+    #[serde(default)]
+    pub file_paths: Vec<String>,
+    /// Executables `shell` commands may run
+    #[serde(default)]
+    pub shell_commands: Vec<String>,
+    /// Regexes `shell` commands must not match
+    #[serde(default)]
+    pub shell_deny_patterns: Vec<String>,
+    /// Tools whose calls wait for the user's approval
+    #[serde(default)]
+    pub confirm_tools: Vec<String>,
+    /// Regexes for `shell` commands that wait for the user's approval
+    #[serde(default)]
+    pub confirm_shell_patterns: Vec<String>,
+    /// Statement kinds `sql_query` calls may run (e.g. `select`, `insert`)
+    #[serde(default)]
+    pub sql_statements: Vec<String>,
+}
+
+/// A provider and model pair the task engine can fail over to.
+///
+/// ```toml
+/// [[autonomy.task_fallback_providers]]
+/// provider = "openrouter"
+/// model = "anthropic/claude-sonnet-4"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProviderSpec {
+    /// Provider name (must match a known provider name)
+    pub provider: String,
+    /// Model to use with that provider
+    pub model: String,
+    /// Optional API key override for this provider
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// Per-task spending limits checked between task-engine rounds. A run that
+/// has reached any limit stops as blocked and asks its sender whether to
+/// continue. `0` leaves a limit off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TaskBudget {
+    /// Input plus output tokens reported by the provider
+    #[serde(default)]
+    pub max_tokens: u64,
+    /// Estimated cost in USD, priced with `[cost.prices]`
+    #[serde(default)]
+    pub max_cost_usd: f64,
+    /// Executed tool calls
+    #[serde(default)]
+    pub max_tool_calls: usize,
+}
+
+/// Tests a task-engine run must pass before it may complete, for tasks
+/// carrying one of `labels`. An empty `command` turns the check off.
+///
+/// ```toml
+/// [autonomy.task_acceptance_tests]
+/// command = "cargo test --quiet"
+/// labels = ["code"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TaskAcceptanceTests {
+    /// Shell command run in the workspace; exit status 0 means the tests pass
+    #[serde(default)]
+    pub command: String,
+    /// Task labels (classifier hints or caller labels) that require the tests
+    #[serde(default = "default_acceptance_test_labels")]
+    pub labels: Vec<String>,
+    /// Seconds before a test run is killed and counted as failed
+    #[serde(default = "default_acceptance_test_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_acceptance_test_labels() -> Vec<String> {
+    vec!["code".to_string()]
+}
+
+fn default_acceptance_test_timeout_secs() -> u64 {
+    600
+}
+
+impl Default for TaskAcceptanceTests {
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            labels: default_acceptance_test_labels(),
+            timeout_secs: default_acceptance_test_timeout_secs(),
+        }
+    }
+}
+
+/// How the task engine classifies a round's reply as final or in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionEvaluator {
+    /// Evidence contract plus keyword heuristics.
+    #[default]
+    Heuristic,
+    /// Ask a (cheap) model to judge the reply against the tool evidence,
+    /// falling back to heuristics when the judge errors or is unsure.
+    LlmJudge,
+}
+
+/// How strictly the task engine decides a run is finished.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CompletionPolicy {
+    /// Require a read-back after a write before a write task may complete.
+    /// When `false`, a successful write alone satisfies write tasks.
+    #[serde(default = "default_true")]
+    pub require_write_verification: bool,
+
+    /// Consecutive progress-only rounds (no-op rounds count double) before a
+    /// run fails as `stalled_loop`.
+    #[serde(default = "default_progress_stall_threshold")]
+    pub progress_stall_threshold: usize,
+
+    /// Rounds that may return the same answer (compared after normalizing
+    /// case, whitespace, and punctuation) before a run fails as
+    /// `repeated_response`; `0` disables the check.
+    #[serde(default = "default_repeated_response_limit")]
+    pub repeated_response_limit: usize,
+
+    /// Extra phrases marking a reply as a progress update rather than a
+    /// final answer (matched case-insensitively).
+    #[serde(default)]
+    pub progress_phrases: Vec<String>,
+
+    /// Extra phrases marking a reply as final, overriding progress phrases.
+    #[serde(default)]
+    pub completion_phrases: Vec<String>,
+
+    /// Completion classifier (`heuristic` or `llm_judge`).
+    #[serde(default)]
+    pub evaluator: CompletionEvaluator,
+
+    /// Model asked by the `llm_judge` evaluator; `None` uses the task's model.
+    #[serde(default)]
+    pub judge_model: Option<String>,
+
+    /// Minimum judge confidence (0.0-1.0) for its verdict to be used.
+    #[serde(default = "default_judge_min_confidence")]
+    pub judge_min_confidence: f64,
+
+    /// Timeout for a single judge call in milliseconds.
+    #[serde(default = "default_judge_timeout_ms")]
+    pub judge_timeout_ms: u64,
+
+    /// Phrase tables added to the built-in language packs (`en`, `zh`, `ja`,
+    /// `ko`, `es`, `de`). A pack named like a built-in replaces it.
+    #[serde(default)]
+    pub language_packs: Vec<CompletionLanguagePack>,
+
+    /// How written artifacts are confirmed, first matching rule wins.
+    /// Paths matching no rule get the `checksum` strategy.
+    #[serde(default)]
+    pub verification_rules: Vec<ArtifactVerificationRule>,
+
+    /// Language and wording of the prompts that push an unfinished task on.
+    #[serde(default)]
+    pub continuation_prompts: ContinuationPromptConfig,
+}
+
+/// `completion_policy.continuation_prompts`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ContinuationPromptConfig {
+    /// Built-in wording to use (`zh` or `en`); other values fall back to `zh`.
+    #[serde(default = "default_continuation_prompt_language")]
+    pub language: String,
+
+    /// Templates replacing the built-in wording, keyed by
+    /// `write_claim_without_verification`, `missing_required_evidence`,
+    /// `guardrail_notice`, or `in_progress_update`. `{requirements}` and
+    /// `{claims}` are replaced with the unmet requirements and the unverified
+    /// file claims.
+    #[serde(default)]
+    pub templates: HashMap<String, String>,
+}
+
+impl Default for ContinuationPromptConfig {
+    fn default() -> Self {
+        Self {
+            language: default_continuation_prompt_language(),
+            templates: HashMap::new(),
+        }
+    }
+}
+
+fn default_continuation_prompt_language() -> String {
+    "zh".to_string()
+}
+
+/// How the task engine confirms a written artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactVerificationStrategy {
+    /// Re-read the file; it must be non-empty UTF-8 text.
+    ReadBack,
+    /// The file must exist and be non-empty (`test -s`); its SHA-256 is stored.
+    #[default]
+    Checksum,
+    /// `url` (or the path itself when it is a URL) must answer a GET with 2xx.
+    HttpGet,
+    /// `command`, run in the workspace, must exit with status 0.
+    Command,
+}
+
+/// One row of `completion_policy.verification_rules`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ArtifactVerificationRule {
+    /// Glob matched against the written path, e.g. `*.md` or `dist/**`.
+    pub pattern: String,
+
+    pub strategy: ArtifactVerificationStrategy,
+
+    /// URL fetched by `http_get`; `{path}` is replaced with the artifact path.
     #[serde(default)]
-    pub shell_env_passthrough: Vec<String>,
+    pub url: Option<String>,
 
-    /// Tools that never require approval (e.g. read-only tools).
-    #[serde(default = "default_auto_approve")]
-    pub auto_approve: Vec<String>,
+    /// Shell command run by `command`; `{path}` is replaced with the
+    /// shell-quoted artifact path.
+    #[serde(default)]
+    pub command: Option<String>,
 
-    /// Tools that always require interactive approval, even after "Always".
-    #[serde(default = "default_always_ask")]
-    pub always_ask: Vec<String>,
+    /// Timeout for `http_get` and `command` checks in seconds.
+    #[serde(default = "default_verification_timeout_secs")]
+    pub timeout_secs: u64,
+}
 
-    /// Extra directory roots the agent may read/write outside the workspace.
-    /// Supports absolute, `~/...`, and workspace-relative entries.
-    /// Resolved paths under any of these roots pass `is_resolved_path_allowed`.
+fn default_verification_timeout_secs() -> u64 {
+    30
+}
+
+/// Progress/completion phrases for one language, used by the heuristic
+/// completion evaluator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CompletionLanguagePack {
+    /// Pack name, conventionally a language code such as `fr` or `pt-br`.
+    pub name: String,
+
+    /// Phrases marking a reply as a progress update (matched case-insensitively).
     #[serde(default)]
-    pub allowed_roots: Vec<String>,
+    pub progress_phrases: Vec<String>,
 
-    /// Tools to exclude from non-CLI channels (e.g. Telegram, Discord).
-    ///
-    /// When a tool is listed here, non-CLI channels will not expose it to the
-    /// model in tool specs.
+    /// Phrases marking a reply as final, overriding progress phrases.
     #[serde(default)]
-    pub non_cli_excluded_tools: Vec<String>,
+    pub completion_phrases: Vec<String>,
+}
 
-    /// Enable contract-based completion verification engine.
-    #[serde(default = "default_true")]
-    pub contract_completion_engine: bool,
+impl Default for CompletionPolicy {
+    fn default() -> Self {
+        Self {
+            require_write_verification: true,
+            progress_stall_threshold: default_progress_stall_threshold(),
+            repeated_response_limit: default_repeated_response_limit(),
+            progress_phrases: Vec::new(),
+            completion_phrases: Vec::new(),
+            evaluator: CompletionEvaluator::Heuristic,
+            judge_model: None,
+            judge_min_confidence: default_judge_min_confidence(),
+            judge_timeout_ms: default_judge_timeout_ms(),
+            language_packs: Vec::new(),
+            verification_rules: Vec::new(),
+            continuation_prompts: ContinuationPromptConfig::default(),
+        }
+    }
+}
 
-    /// Enable gray-zone verifier for near-terminal ambiguous states.
-    #[serde(default = "default_true")]
-    pub gray_zone_verifier_enabled: bool,
+fn default_progress_stall_threshold() -> usize {
+    6
+}
 
-    /// Timeout in milliseconds for gray-zone verifier calls.
-    #[serde(default = "default_gray_zone_verifier_timeout_ms")]
-    pub gray_zone_verifier_timeout_ms: u64,
+fn default_repeated_response_limit() -> usize {
+    3
+}
+
+fn default_judge_min_confidence() -> f64 {
+    0.7
+}
+
+fn default_judge_timeout_ms() -> u64 {
+    3000
+}
+
+fn validate_completion_policy(scope: &str, policy: &CompletionPolicy) -> Result<()> {
+    if !(0.0..=1.0).contains(&policy.judge_min_confidence) {
+        anyhow::bail!("autonomy.{scope}.judge_min_confidence must be between 0.0 and 1.0");
+    }
+    if policy.judge_timeout_ms == 0 {
+        anyhow::bail!("autonomy.{scope}.judge_timeout_ms must be greater than 0");
+    }
+    for (index, pack) in policy.language_packs.iter().enumerate() {
+        if pack.name.trim().is_empty() {
+            anyhow::bail!("autonomy.{scope}.language_packs[{index}].name must not be empty");
+        }
+        if pack.progress_phrases.is_empty() && pack.completion_phrases.is_empty() {
+            anyhow::bail!(
+                "autonomy.{scope}.language_packs[{index}] ({}) must define at least one phrase",
+                pack.name
+            );
+        }
+    }
+    for (index, rule) in policy.verification_rules.iter().enumerate() {
+        if glob::Pattern::new(rule.pattern.trim()).is_err() || rule.pattern.trim().is_empty() {
+            anyhow::bail!(
+                "autonomy.{scope}.verification_rules[{index}].pattern must be a valid glob"
+            );
+        }
+        if rule.strategy == ArtifactVerificationStrategy::Command
+            && rule.command.as_deref().is_none_or(|c| c.trim().is_empty())
+        {
+            anyhow::bail!(
+                "autonomy.{scope}.verification_rules[{index}] uses `command` but sets no command"
+            );
+        }
+        if rule.timeout_secs == 0 {
+            anyhow::bail!(
+                "autonomy.{scope}.verification_rules[{index}].timeout_secs must be greater than 0"
+            );
+        }
+    }
+    for key in policy.continuation_prompts.templates.keys() {
+        if !crate::agent::prompt::CONTINUATION_PROMPT_TEMPLATES.contains(&key.as_str()) {
+            anyhow::bail!(
+                "autonomy.{scope}.continuation_prompts.templates has unknown template '{key}'"
+            );
+        }
+    }
+    Ok(())
 }
 
 fn default_auto_approve() -> Vec<String> {
@@ -1924,6 +2862,26 @@ fn default_gray_zone_verifier_timeout_ms() -> u64 {
     1500
 }
 
+fn default_task_timeout_secs() -> u64 {
+    900
+}
+
+fn default_task_archive_after_days() -> u64 {
+    30
+}
+
+fn default_task_cold_storage_after_days() -> u64 {
+    90
+}
+
+fn default_task_stuck_after_mins() -> u64 {
+    10
+}
+
+fn default_duplicate_task_window_mins() -> u64 {
+    30
+}
+
 fn is_valid_env_var_name(name: &str) -> bool {
     let mut chars = name.chars();
     match chars.next() {
@@ -1982,9 +2940,28 @@ impl Default for AutonomyConfig {
             always_ask: default_always_ask(),
             allowed_roots: Vec::new(),
             non_cli_excluded_tools: Vec::new(),
+            sender_roles: HashMap::new(),
+            role_excluded_tools: HashMap::new(),
+            hint_excluded_tools: HashMap::new(),
             contract_completion_engine: true,
             gray_zone_verifier_enabled: true,
             gray_zone_verifier_timeout_ms: default_gray_zone_verifier_timeout_ms(),
+            task_timeout_secs: default_task_timeout_secs(),
+            task_archive_after_days: default_task_archive_after_days(),
+            task_cold_storage_after_days: default_task_cold_storage_after_days(),
+            task_stuck_after_mins: default_task_stuck_after_mins(),
+            duplicate_task_window_mins: default_duplicate_task_window_mins(),
+            task_env: HashMap::new(),
+            task_fallback_providers: Vec::new(),
+            task_budget: TaskBudget::default(),
+            task_progress_tool_calls: true,
+            task_file_snapshots: false,
+            task_step_decomposition: false,
+            task_acceptance_tests: TaskAcceptanceTests::default(),
+            task_write_backups: false,
+            completion_policy: CompletionPolicy::default(),
+            channel_completion_policies: HashMap::new(),
+            tool_policy: ToolPolicyConfig::default(),
         }
     }
 }
@@ -2008,6 +2985,44 @@ pub struct RuntimeConfig {
     /// - `Some(false)`: disable reasoning/thinking when supported
     #[serde(default)]
     pub reasoning_enabled: Option<bool>,
+
+    /// Output token cap per response for providers that require one
+    /// (Anthropic). `None` keeps the provider default.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+
+    /// How long Ollama keeps a model loaded after each request (`"30m"`,
+    /// `"-1"` to keep it loaded). `None` keeps Ollama's default.
+    #[serde(default)]
+    pub ollama_keep_alive: Option<String>,
+
+    /// Block threshold applied to every Gemini harm category
+    /// (`BLOCK_NONE`, `BLOCK_ONLY_HIGH`, `BLOCK_MEDIUM_AND_ABOVE`,
+    /// `BLOCK_LOW_AND_ABOVE`, `OFF`). `None` keeps Gemini's defaults.
+    #[serde(default)]
+    pub gemini_safety_threshold: Option<String>,
+
+    /// Record provider traffic to, or replay it from, `provider_recording_path`.
+    #[serde(default)]
+    pub provider_recording: ProviderRecordingMode,
+
+    /// Recording file, relative to the workspace. `None` uses
+    /// `state/provider_recording.jsonl`.
+    #[serde(default)]
+    pub provider_recording_path: Option<String>,
+}
+
+/// Whether provider requests and responses are recorded or replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderRecordingMode {
+    /// Talk to the provider without recording.
+    #[default]
+    Off,
+    /// Append every request/response pair, scrubbed of secrets, to the file.
+    Record,
+    /// Serve responses from the file without calling any provider.
+    Replay,
 }
 
 /// Docker runtime configuration (`[runtime.docker]` section).
@@ -2082,6 +3097,11 @@ impl Default for RuntimeConfig {
             kind: default_runtime_kind(),
             docker: DockerRuntimeConfig::default(),
             reasoning_enabled: None,
+            max_tokens: None,
+            ollama_keep_alive: None,
+            gemini_safety_threshold: None,
+            provider_recording: ProviderRecordingMode::Off,
+            provider_recording_path: None,
         }
     }
 }
@@ -2102,10 +3122,38 @@ pub struct ReliabilityConfig {
     /// Fallback provider chain (e.g. `["anthropic", "openai"]`).
     #[serde(default)]
     pub fallback_providers: Vec<String>,
-    /// Additional API keys for round-robin rotation on rate-limit (429) errors.
-    /// The primary `api_key` is always tried first; these are extras.
+    /// Additional API keys for the primary provider. Together with the
+    /// primary `api_key` they are used round-robin, one per request, moving on
+    /// to the next key on rate-limit (429) errors.
     #[serde(default)]
     pub api_keys: Vec<String>,
+    /// API keys per provider name (primary or fallback entry), rotated the
+    /// same way. Example: `{ openai = ["sk-org-a...", "sk-org-b..."] }`
+    #[serde(default)]
+    pub provider_api_keys: std::collections::HashMap<String, Vec<String>>,
+    /// Seconds a key stays out of rotation after a quota or billing error.
+    #[serde(default = "default_api_key_park_secs")]
+    pub api_key_park_secs: u64,
+    /// Request and token budgets per provider name, shared by every client of
+    /// that provider in the process. Calls over budget wait instead of failing.
+    /// Example: `{ openai = { requests_per_minute = 60, tokens_per_minute = 90000 } }`
+    #[serde(default)]
+    pub provider_rate_limits: std::collections::HashMap<String, ProviderRateLimitConfig>,
+    /// Seconds a single provider call may take (for streams, the wait for each
+    /// next chunk) before it fails as a retryable timeout. `0` disables it.
+    #[serde(default = "default_provider_timeout_secs")]
+    pub provider_timeout_secs: u64,
+    /// Consecutive transient failures that open a provider's circuit; an open
+    /// circuit is skipped until its cooldown passes. `0` disables the breaker.
+    #[serde(default = "default_circuit_breaker_threshold")]
+    pub circuit_breaker_threshold: u32,
+    /// Seconds an open circuit skips its provider before half-opening.
+    #[serde(default = "default_circuit_breaker_cooldown_secs")]
+    pub circuit_breaker_cooldown_secs: u64,
+    /// Seconds between health probes of providers whose circuit is open.
+    /// `0` disables probes; the next request then tests the provider.
+    #[serde(default = "default_health_probe_interval_secs")]
+    pub health_probe_interval_secs: u64,
     /// Per-model fallback chains. When a model fails, try these alternatives in order.
     /// Example: `{ "claude-opus-4-20250514" = ["claude-sonnet-4-20250514", "gpt-4o"] }`
     #[serde(default)]
@@ -2124,6 +3172,17 @@ pub struct ReliabilityConfig {
     pub scheduler_retries: u32,
 }
 
+/// Per-minute budget for one provider (`[reliability.provider_rate_limits.<name>]`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProviderRateLimitConfig {
+    /// Requests started per rolling minute. Unset means unlimited.
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    /// Input plus output tokens per rolling minute. Unset means unlimited.
+    #[serde(default)]
+    pub tokens_per_minute: Option<u64>,
+}
+
 fn default_provider_retries() -> u32 {
     2
 }
@@ -2132,6 +3191,26 @@ fn default_provider_backoff_ms() -> u64 {
     500
 }
 
+fn default_api_key_park_secs() -> u64 {
+    3600
+}
+
+fn default_provider_timeout_secs() -> u64 {
+    300
+}
+
+fn default_circuit_breaker_threshold() -> u32 {
+    5
+}
+
+fn default_circuit_breaker_cooldown_secs() -> u64 {
+    60
+}
+
+fn default_health_probe_interval_secs() -> u64 {
+    30
+}
+
 fn default_channel_backoff_secs() -> u64 {
     2
 }
@@ -2155,6 +3234,13 @@ impl Default for ReliabilityConfig {
             provider_backoff_ms: default_provider_backoff_ms(),
             fallback_providers: Vec::new(),
             api_keys: Vec::new(),
+            provider_api_keys: std::collections::HashMap::new(),
+            api_key_park_secs: default_api_key_park_secs(),
+            provider_rate_limits: std::collections::HashMap::new(),
+            provider_timeout_secs: default_provider_timeout_secs(),
+            circuit_breaker_threshold: default_circuit_breaker_threshold(),
+            circuit_breaker_cooldown_secs: default_circuit_breaker_cooldown_secs(),
+            health_probe_interval_secs: default_health_probe_interval_secs(),
             model_fallbacks: std::collections::HashMap::new(),
             channel_initial_backoff_secs: default_channel_backoff_secs(),
             channel_max_backoff_secs: default_channel_backoff_max_secs(),
@@ -2230,6 +3316,10 @@ pub struct ModelRouteConfig {
     /// Optional API key override for this route's provider
     #[serde(default)]
     pub api_key: Option<String>,
+    /// Temperature for requests routed here by query classification.
+    /// Unset keeps `default_temperature`.
+    #[serde(default)]
+    pub temperature: Option<f64>,
 }
 
 // ── Embedding routing ───────────────────────────────────────────
@@ -2490,6 +3580,11 @@ pub struct ChannelsConfig {
     /// Default: 300s for on-device LLMs (Ollama) which are slower than cloud APIs.
     #[serde(default = "default_channel_message_timeout_secs")]
     pub message_timeout_secs: u64,
+    /// Dispatch weight per channel name while all in-flight slots are busy.
+    /// Queued messages are served weighted round-robin across channels and
+    /// round-robin across senders within a channel. Unlisted channels: `1`.
+    #[serde(default)]
+    pub queue_weights: HashMap<String, u32>,
 }
 
 impl ChannelsConfig {
@@ -2615,6 +3710,7 @@ impl Default for ChannelsConfig {
             nostr: None,
             clawdtalk: None,
             message_timeout_secs: default_channel_message_timeout_secs(),
+            queue_weights: HashMap::new(),
         }
     }
 }
@@ -2655,6 +3751,33 @@ pub struct TelegramConfig {
     /// Direct messages are always processed.
     #[serde(default)]
     pub mention_only: bool,
+    /// Update receive mode: "polling" (default) or "webhook"
+    #[serde(default)]
+    pub receive_mode: TelegramReceiveMode,
+    /// Public HTTPS URL Telegram posts updates to. Required when
+    /// receive_mode = "webhook"; it must forward to `/telegram` on `port`.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// HTTP port for webhook mode only. Must be set when receive_mode = "webhook".
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Secret Telegram sends in `X-Telegram-Bot-Api-Secret-Token` with every
+    /// webhook request; requests without it are rejected. Unset generates a
+    /// random secret at startup (1-256 of `A-Z`, `a-z`, `0-9`, `_`, `-`).
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
+}
+
+/// How ZeroClaw receives updates from the Telegram Bot API.
+///
+/// - `polling` (default) — `getUpdates` long polling; no public URL required.
+/// - `webhook`           — HTTP callback server; requires a public HTTPS endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TelegramReceiveMode {
+    #[default]
+    Polling,
+    Webhook,
 }
 
 impl ChannelConfig for TelegramConfig {
@@ -2684,6 +3807,16 @@ pub struct DiscordConfig {
     /// Other messages in the guild are silently ignored.
     #[serde(default)]
     pub mention_only: bool,
+    /// Guild channel IDs to listen in. Empty = every channel the bot can read.
+    /// Direct messages are always processed.
+    #[serde(default)]
+    pub channel_ids: Vec<String>,
+    /// Streaming mode for progressive response delivery via message edits.
+    #[serde(default)]
+    pub stream_mode: StreamMode,
+    /// Minimum interval (ms) between draft message edits to avoid rate limits.
+    #[serde(default = "default_draft_update_interval_ms")]
+    pub draft_update_interval_ms: u64,
 }
 
 impl ChannelConfig for DiscordConfig {
@@ -3510,6 +4643,11 @@ impl Default for Config {
             http_request: HttpRequestConfig::default(),
             multimodal: MultimodalConfig::default(),
             web_search: WebSearchConfig::default(),
+            web_fetch: WebFetchConfig::default(),
+            code_run: CodeRunConfig::default(),
+            sql_query: SqlQueryConfig::default(),
+            macro_tools: HashMap::new(),
+            webhook_tasks: HashMap::new(),
             proxy: ProxyConfig::default(),
             identity: IdentityConfig::default(),
             cost: CostConfig::default(),
@@ -3987,9 +5125,32 @@ impl Config {
                 );
             }
         }
+        for env_name in self.autonomy.task_env.keys() {
+            if !is_valid_env_var_name(env_name) {
+                anyhow::bail!(
+                    "autonomy.task_env key is invalid ({env_name}); expected [A-Za-z_][A-Za-z0-9_]*"
+                );
+            }
+        }
+        for (i, spec) in self.autonomy.task_fallback_providers.iter().enumerate() {
+            if spec.provider.trim().is_empty() || spec.model.trim().is_empty() {
+                anyhow::bail!(
+                    "autonomy.task_fallback_providers[{i}] needs both provider and model"
+                );
+            }
+        }
+        let max_cost_usd = self.autonomy.task_budget.max_cost_usd;
+        if !max_cost_usd.is_finite() || max_cost_usd < 0.0 {
+            anyhow::bail!("autonomy.task_budget.max_cost_usd must be a finite, non-negative value");
+        }
         if self.autonomy.gray_zone_verifier_timeout_ms == 0 {
             anyhow::bail!("autonomy.gray_zone_verifier_timeout_ms must be greater than 0");
         }
+        validate_completion_policy("completion_policy", &self.autonomy.completion_policy)?;
+        for (channel, policy) in &self.autonomy.channel_completion_policies {
+            validate_completion_policy(&format!("channel_completion_policies.{channel}"), policy)?;
+        }
+        crate::security::ToolPolicy::from_config(&self.autonomy.tool_policy)?;
 
         // Security OTP / estop
         if self.security.otp.token_ttl_secs == 0 {
@@ -4085,6 +5246,106 @@ impl Config {
             }
         }
 
+        // Code run
+        if !matches!(
+            self.code_run.backend.trim(),
+            "auto" | "native" | "docker" | "podman"
+        ) {
+            anyhow::bail!(
+                "code_run.backend must be one of: auto, native, docker, podman (got {})",
+                self.code_run.backend
+            );
+        }
+        if self.code_run.timeout_secs == 0 || self.code_run.cpu_time_secs == 0 {
+            anyhow::bail!(
+                "code_run.timeout_secs and code_run.cpu_time_secs must be greater than 0"
+            );
+        }
+        if self.code_run.max_memory_mb == 0 {
+            anyhow::bail!("code_run.max_memory_mb must be greater than 0");
+        }
+        if !self.code_run.cpus.is_finite() || self.code_run.cpus <= 0.0 {
+            anyhow::bail!("code_run.cpus must be a finite value greater than 0");
+        }
+
+        // SQL query
+        if self.sql_query.max_rows == 0 || self.sql_query.timeout_secs == 0 {
+            anyhow::bail!("sql_query.max_rows and sql_query.timeout_secs must be greater than 0");
+        }
+        for (name, target) in &self.sql_query.databases {
+            if target.trim().is_empty() {
+                anyhow::bail!("sql_query.databases.{name} must not be empty");
+            }
+        }
+
+        // Macro tools
+        for (name, macro_tool) in &self.macro_tools {
+            if name.trim().is_empty() || macro_tool.steps.is_empty() {
+                anyhow::bail!("macro_tools.{name} must have a name and at least one step");
+            }
+            for (index, step) in macro_tool.steps.iter().enumerate() {
+                if step.tool == *name {
+                    anyhow::bail!(
+                        "macro_tools.{name}.steps[{index}] must not call the macro itself"
+                    );
+                }
+                if let Some(unknown) = macro_placeholders(&step.args)
+                    .into_iter()
+                    .find(|param| !macro_tool.parameters.contains_key(param))
+                {
+                    anyhow::bail!(
+                        "macro_tools.{name}.steps[{index}] uses {{{{{unknown}}}}}, which is not in macro_tools.{name}.parameters"
+                    );
+                }
+            }
+        }
+
+        // Webhook tasks
+        for (name, hook) in &self.webhook_tasks {
+            if name.trim().is_empty() || hook.request.trim().is_empty() {
+                anyhow::bail!("webhook_tasks.{name} must have a name and a request template");
+            }
+            match (hook.reply_channel.as_deref(), hook.reply_target.as_deref()) {
+                (None, None) => {}
+                (Some(channel), Some(target)) if !target.trim().is_empty() => {
+                    if !matches!(channel, "telegram" | "discord" | "slack" | "mattermost") {
+                        anyhow::bail!(
+                            "webhook_tasks.{name}.reply_channel must be telegram, discord, slack, or mattermost"
+                        );
+                    }
+                }
+                _ => anyhow::bail!(
+                    "webhook_tasks.{name}.reply_channel and reply_target must be set together"
+                ),
+            }
+        }
+
+        // Channels
+        if let Some(telegram) = &self.channels_config.telegram {
+            if telegram.receive_mode == TelegramReceiveMode::Webhook
+                && (telegram
+                    .webhook_url
+                    .as_deref()
+                    .is_none_or(|url| !url.starts_with("https://"))
+                    || telegram.port.is_none())
+            {
+                anyhow::bail!(
+                    "channels_config.telegram.receive_mode = \"webhook\" requires an https:// webhook_url and a port"
+                );
+            }
+            if let Some(secret) = telegram.webhook_secret.as_deref().map(str::trim) {
+                if secret.len() > 256
+                    || !secret
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    anyhow::bail!(
+                        "channels_config.telegram.webhook_secret must be at most 256 of A-Z, a-z, 0-9, _ and -"
+                    );
+                }
+            }
+        }
+
         // Proxy (delegate to existing validation)
         self.proxy.validate()?;
 
@@ -4256,6 +5517,16 @@ impl Config {
             }
         }
 
+        // SearXNG instance: ZEROCLAW_SEARXNG_URL or SEARXNG_URL
+        if let Ok(url) =
+            std::env::var("ZEROCLAW_SEARXNG_URL").or_else(|_| std::env::var("SEARXNG_URL"))
+        {
+            let url = url.trim();
+            if !url.is_empty() {
+                self.web_search.searxng_url = Some(url.to_string());
+            }
+        }
+
         // Web search max results: ZEROCLAW_WEB_SEARCH_MAX_RESULTS or WEB_SEARCH_MAX_RESULTS
         if let Ok(max_results) = std::env::var("ZEROCLAW_WEB_SEARCH_MAX_RESULTS")
             .or_else(|_| std::env::var("WEB_SEARCH_MAX_RESULTS"))
@@ -4617,6 +5888,125 @@ mod tests {
         assert!(a.contract_completion_engine);
         assert!(a.gray_zone_verifier_enabled);
         assert!(a.gray_zone_verifier_timeout_ms > 0);
+        assert_eq!(a.task_timeout_secs, 900);
+        assert_eq!(a.task_archive_after_days, 30);
+        assert_eq!(a.task_stuck_after_mins, 10);
+        assert_eq!(a.task_cold_storage_after_days, 90);
+        assert_eq!(a.duplicate_task_window_mins, 30);
+        assert_eq!(a.completion_policy, CompletionPolicy::default());
+        assert!(a.completion_policy.require_write_verification);
+        assert_eq!(a.completion_policy.progress_stall_threshold, 6);
+        assert_eq!(a.completion_policy.repeated_response_limit, 3);
+        assert!(a.channel_completion_policies.is_empty());
+    }
+
+    #[test]
+    async fn completion_policy_omitted_fields_use_defaults() {
+        let raw = r#"
+require_write_verification = false
+progress_phrases = ["hang tight"]
+"#;
+        let parsed: CompletionPolicy = toml::from_str(raw).expect("completion policy");
+        assert!(!parsed.require_write_verification);
+        assert_eq!(parsed.progress_stall_threshold, 6);
+        assert_eq!(parsed.progress_phrases, vec!["hang tight".to_string()]);
+        assert!(parsed.completion_phrases.is_empty());
+        assert_eq!(parsed.evaluator, CompletionEvaluator::Heuristic);
+    }
+
+    #[test]
+    async fn completion_policy_llm_judge_settings_are_validated() {
+        let parsed: CompletionPolicy = toml::from_str(
+            r#"
+evaluator = "llm_judge"
+judge_model = "gpt-4o-mini"
+"#,
+        )
+        .expect("completion policy");
+        assert_eq!(parsed.evaluator, CompletionEvaluator::LlmJudge);
+        assert_eq!(parsed.judge_model.as_deref(), Some("gpt-4o-mini"));
+        assert!((parsed.judge_min_confidence - 0.7).abs() < f64::EPSILON);
+
+        let mut cfg = Config::default();
+        cfg.autonomy.channel_completion_policies.insert(
+            "imessage".into(),
+            CompletionPolicy {
+                judge_min_confidence: 1.5,
+                ..parsed
+            },
+        );
+        let err = cfg
+            .validate()
+            .expect_err("confidence above 1 must be rejected");
+        assert!(err
+            .to_string()
+            .contains("channel_completion_policies.imessage.judge_min_confidence"));
+    }
+
+    #[test]
+    async fn completion_policy_language_packs_parse_and_validate() {
+        let parsed: CompletionPolicy = toml::from_str(
+            r#"
+[[language_packs]]
+name = "fr"
+progress_phrases = ["je vérifie"]
+completion_phrases = ["c'est fait"]
+"#,
+        )
+        .expect("completion policy");
+        assert_eq!(parsed.language_packs.len(), 1);
+        assert_eq!(parsed.language_packs[0].name, "fr");
+        assert_eq!(
+            parsed.language_packs[0].completion_phrases,
+            vec!["c'est fait"]
+        );
+
+        let mut cfg = Config::default();
+        cfg.autonomy.completion_policy = parsed;
+        assert!(cfg.validate().is_ok());
+        cfg.autonomy.completion_policy.language_packs[0]
+            .progress_phrases
+            .clear();
+        cfg.autonomy.completion_policy.language_packs[0]
+            .completion_phrases
+            .clear();
+        let err = cfg.validate().expect_err("empty pack must be rejected");
+        assert!(err
+            .to_string()
+            .contains("autonomy.completion_policy.language_packs[0] (fr)"));
+    }
+
+    #[test]
+    async fn completion_policy_verification_rules_parse_and_validate() {
+        let parsed: CompletionPolicy = toml::from_str(
+            r#"
+[[verification_rules]]
+pattern = "*.md"
+strategy = "read_back"
+
+[[verification_rules]]
+pattern = "dist/**"
+strategy = "command"
+command = "test -x {path}"
+"#,
+        )
+        .expect("completion policy");
+        assert_eq!(
+            parsed.verification_rules[0].strategy,
+            ArtifactVerificationStrategy::ReadBack
+        );
+        assert_eq!(parsed.verification_rules[1].timeout_secs, 30);
+
+        let mut cfg = Config::default();
+        cfg.autonomy.completion_policy = parsed;
+        assert!(cfg.validate().is_ok());
+        cfg.autonomy.completion_policy.verification_rules[1].command = None;
+        let err = cfg
+            .validate()
+            .expect_err("command rule without a command must be rejected");
+        assert!(err
+            .to_string()
+            .contains("autonomy.completion_policy.verification_rules[1]"));
     }
 
     #[test]
@@ -4742,9 +6132,28 @@ default_temperature = 0.7
                 always_ask: vec![],
                 allowed_roots: vec![],
                 non_cli_excluded_tools: vec![],
+                sender_roles: HashMap::new(),
+                role_excluded_tools: HashMap::new(),
+                hint_excluded_tools: HashMap::new(),
                 contract_completion_engine: true,
                 gray_zone_verifier_enabled: true,
                 gray_zone_verifier_timeout_ms: 1500,
+                task_timeout_secs: 900,
+                task_archive_after_days: 30,
+                task_cold_storage_after_days: 90,
+                task_stuck_after_mins: 10,
+                duplicate_task_window_mins: 30,
+                task_env: HashMap::new(),
+                task_fallback_providers: Vec::new(),
+                task_budget: TaskBudget::default(),
+                task_progress_tool_calls: true,
+                task_file_snapshots: false,
+                task_step_decomposition: false,
+                task_acceptance_tests: TaskAcceptanceTests::default(),
+                task_write_backups: false,
+                completion_policy: CompletionPolicy::default(),
+                channel_completion_policies: HashMap::new(),
+                tool_policy: ToolPolicyConfig::default(),
             },
             security: SecurityConfig::default(),
             runtime: RuntimeConfig {
@@ -4771,6 +6180,10 @@ default_temperature = 0.7
                     draft_update_interval_ms: default_draft_update_interval_ms(),
                     interrupt_on_new_message: false,
                     mention_only: false,
+                    receive_mode: TelegramReceiveMode::Polling,
+                    webhook_url: None,
+                    port: None,
+                    webhook_secret: None,
                 }),
                 discord: None,
                 slack: None,
@@ -4792,6 +6205,7 @@ default_temperature = 0.7
                 nostr: None,
                 clawdtalk: None,
                 message_timeout_secs: 300,
+                queue_weights: HashMap::new(),
             },
             memory: MemoryConfig::default(),
             storage: StorageConfig::default(),
@@ -4803,6 +6217,11 @@ default_temperature = 0.7
             http_request: HttpRequestConfig::default(),
             multimodal: MultimodalConfig::default(),
             web_search: WebSearchConfig::default(),
+            web_fetch: WebFetchConfig::default(),
+            code_run: CodeRunConfig::default(),
+            sql_query: SqlQueryConfig::default(),
+            macro_tools: HashMap::new(),
+            webhook_tasks: HashMap::new(),
             proxy: ProxyConfig::default(),
             agent: AgentConfig::default(),
             identity: IdentityConfig::default(),
@@ -4903,7 +6322,10 @@ reasoning_enabled = false
         assert!(!cfg.compact_context);
         assert_eq!(cfg.max_tool_iterations, 10);
         assert_eq!(cfg.max_history_messages, 50);
+        assert_eq!(cfg.context_max_tokens, 64_000);
         assert!(!cfg.parallel_tools);
+        assert_eq!(cfg.max_parallel_tools, 4);
+        assert_eq!(cfg.tool_concurrency(), 1);
         assert_eq!(cfg.tool_dispatcher, "auto");
     }
 
@@ -4915,14 +6337,18 @@ default_temperature = 0.7
 compact_context = true
 max_tool_iterations = 20
 max_history_messages = 80
+context_max_tokens = 32000
 parallel_tools = true
+max_parallel_tools = 2
 tool_dispatcher = "xml"
 "#;
         let parsed: Config = toml::from_str(raw).unwrap();
         assert!(parsed.agent.compact_context);
         assert_eq!(parsed.agent.max_tool_iterations, 20);
         assert_eq!(parsed.agent.max_history_messages, 80);
+        assert_eq!(parsed.agent.context_max_tokens, 32_000);
         assert!(parsed.agent.parallel_tools);
+        assert_eq!(parsed.agent.tool_concurrency(), 2);
         assert_eq!(parsed.agent.tool_dispatcher, "xml");
     }
 
@@ -4977,6 +6403,11 @@ tool_dispatcher = "xml"
             http_request: HttpRequestConfig::default(),
             multimodal: MultimodalConfig::default(),
             web_search: WebSearchConfig::default(),
+            web_fetch: WebFetchConfig::default(),
+            code_run: CodeRunConfig::default(),
+            sql_query: SqlQueryConfig::default(),
+            macro_tools: HashMap::new(),
+            webhook_tasks: HashMap::new(),
             proxy: ProxyConfig::default(),
             agent: AgentConfig::default(),
             identity: IdentityConfig::default(),
@@ -5133,6 +6564,10 @@ tool_dispatcher = "xml"
             draft_update_interval_ms: 500,
             interrupt_on_new_message: true,
             mention_only: false,
+            receive_mode: TelegramReceiveMode::Polling,
+            webhook_url: None,
+            port: None,
+            webhook_secret: None,
         };
         let json = serde_json::to_string(&tc).unwrap();
         let parsed: TelegramConfig = serde_json::from_str(&json).unwrap();
@@ -5150,6 +6585,46 @@ tool_dispatcher = "xml"
         assert_eq!(parsed.stream_mode, StreamMode::Off);
         assert_eq!(parsed.draft_update_interval_ms, 1000);
         assert!(!parsed.interrupt_on_new_message);
+        assert_eq!(parsed.receive_mode, TelegramReceiveMode::Polling);
+    }
+
+    #[test]
+    async fn telegram_webhook_mode_requires_https_url_and_port() {
+        let json = r#"{"bot_token":"tok","allowed_users":[],"receive_mode":"webhook","port":8443}"#;
+        let telegram: TelegramConfig = serde_json::from_str(json).unwrap();
+        let mut config = Config::default();
+        config.channels_config.telegram = Some(telegram);
+        assert!(config.validate().is_err());
+
+        let telegram = config.channels_config.telegram.as_mut().unwrap();
+        telegram.webhook_url = Some("https://bot.example.com/telegram".into());
+        assert!(config.validate().is_ok());
+
+        let telegram = config.channels_config.telegram.as_mut().unwrap();
+        telegram.webhook_secret = Some("not a valid token!".into());
+        assert!(config.validate().is_err());
+        let telegram = config.channels_config.telegram.as_mut().unwrap();
+        telegram.webhook_secret = Some("long-random_secret-42".into());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    async fn webhook_task_reply_channel_needs_a_target() {
+        let json = r#"{"request":"Investigate {{alert.title}}","reply_channel":"slack"}"#;
+        let hook: WebhookTaskConfig = serde_json::from_str(json).unwrap();
+        assert!(hook.filters.is_empty());
+        assert!(hook.allow_tools.is_empty());
+        let mut config = Config::default();
+        config.webhook_tasks.insert("alerts".into(), hook);
+        assert!(config.validate().is_err());
+
+        let hook = config.webhook_tasks.get_mut("alerts").unwrap();
+        hook.reply_target = Some("C0123456".into());
+        assert!(config.validate().is_ok());
+
+        let hook = config.webhook_tasks.get_mut("alerts").unwrap();
+        hook.reply_channel = Some("irc".into());
+        assert!(config.validate().is_err());
     }
 
     #[test]
@@ -5160,6 +6635,9 @@ tool_dispatcher = "xml"
             allowed_users: vec![],
             listen_to_bots: false,
             mention_only: false,
+            channel_ids: vec![],
+            stream_mode: StreamMode::default(),
+            draft_update_interval_ms: 1000,
         };
         let json = serde_json::to_string(&dc).unwrap();
         let parsed: DiscordConfig = serde_json::from_str(&json).unwrap();
@@ -5175,6 +6653,9 @@ tool_dispatcher = "xml"
             allowed_users: vec![],
             listen_to_bots: false,
             mention_only: false,
+            channel_ids: vec![],
+            stream_mode: StreamMode::default(),
+            draft_update_interval_ms: 1000,
         };
         let json = serde_json::to_string(&dc).unwrap();
         let parsed: DiscordConfig = serde_json::from_str(&json).unwrap();
@@ -5347,6 +6828,7 @@ allowed_users = ["@ops:matrix.org"]
             nostr: None,
             clawdtalk: None,
             message_timeout_secs: 300,
+            queue_weights: HashMap::new(),
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -5561,6 +7043,7 @@ channel_id = "C123"
             nostr: None,
             clawdtalk: None,
             message_timeout_secs: 300,
+            queue_weights: HashMap::new(),
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -5816,6 +7299,7 @@ default_temperature = 0.7
         assert!(b.computer_use.window_allowlist.is_empty());
         assert!(b.computer_use.max_coordinate_x.is_none());
         assert!(b.computer_use.max_coordinate_y.is_none());
+        assert_eq!(b.max_actions_per_run, 50);
     }
 
     #[test]
@@ -5837,6 +7321,7 @@ default_temperature = 0.7
                 max_coordinate_x: Some(3840),
                 max_coordinate_y: Some(2160),
             },
+            max_actions_per_run: 20,
         };
         let toml_str = toml::to_string(&b).unwrap();
         let parsed: BrowserConfig = toml::from_str(&toml_str).unwrap();
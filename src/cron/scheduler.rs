@@ -295,7 +295,17 @@ async fn deliver_if_configured(config: &Config, job: &CronJob, output: &str) ->
         .to
         .as_deref()
         .ok_or_else(|| anyhow::anyhow!("delivery.to is required for announce mode"))?;
+    announce(config, channel, target, output).await
+}
 
+/// Send `output` to `target` on one of the configured `telegram`, `discord`,
+/// `slack`, or `mattermost` channels.
+pub(crate) async fn announce(
+    config: &Config,
+    channel: &str,
+    target: &str,
+    output: &str,
+) -> Result<()> {
     match channel.to_ascii_lowercase().as_str() {
         "telegram" => {
             let tg = config
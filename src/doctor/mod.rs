@@ -561,26 +561,38 @@ fn provider_validation_error(name: &str) -> Option<String> {
 
 fn embedding_provider_validation_error(name: &str) -> Option<String> {
     let normalized = name.trim();
-    if normalized.eq_ignore_ascii_case("none") || normalized.eq_ignore_ascii_case("openai") {
+    if ["none", "openai", "openrouter", "ollama"]
+        .iter()
+        .any(|known| normalized.eq_ignore_ascii_case(known))
+    {
         return None;
     }
 
-    let Some(url) = normalized.strip_prefix("custom:") else {
-        return Some("supported values: none, openai, custom:<url>".into());
+    let Some((kind, url)) = ["custom", "ollama"].into_iter().find_map(|kind| {
+        normalized
+            .strip_prefix(kind)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .map(|url| (kind, url))
+    }) else {
+        return Some(
+            "supported values: none, openai, openrouter, ollama, ollama:<url>, custom:<url>".into(),
+        );
     };
 
     let url = url.trim();
     if url.is_empty() {
-        return Some("custom provider requires a non-empty URL after 'custom:'".into());
+        return Some(format!(
+            "{kind} provider requires a non-empty URL after '{kind}:'"
+        ));
     }
 
     match reqwest::Url::parse(url) {
         Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => None,
         Ok(parsed) => Some(format!(
-            "custom provider URL must use http/https, got '{}'",
+            "{kind} provider URL must use http/https, got '{}'",
             parsed.scheme()
         )),
-        Err(err) => Some(format!("invalid custom provider URL: {err}")),
+        Err(err) => Some(format!("invalid {kind} provider URL: {err}")),
     }
 }
 
@@ -1097,6 +1109,7 @@ mod tests {
             provider: "groq".into(),
             model: String::new(),
             api_key: None,
+            temperature: None,
         }];
         let mut items = Vec::new();
         check_config_semantics(&config, &mut items);
@@ -21,7 +21,7 @@ fn extract_bearer_token(headers: &HeaderMap) -> Option<&str> {
 }
 
 /// Verify bearer token against PairingGuard. Returns error response if unauthorized.
-fn require_auth(
+pub(super) fn require_auth(
     state: &AppState,
     headers: &HeaderMap,
 ) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
@@ -64,6 +64,12 @@ pub struct CronAddBody {
     pub command: String,
 }
 
+#[derive(Deserialize)]
+pub struct TaskNoteBody {
+    pub body: String,
+    pub author: Option<String>,
+}
+
 // ── Handlers ────────────────────────────────────────────────────
 
 /// GET /api/status — system status overview
@@ -480,6 +486,141 @@ pub async fn handle_api_cost(
     }
 }
 
+/// GET /api/tasks/blocked — blocked task runs with what unblocks each one
+pub async fn handle_api_tasks_blocked(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(e) = require_auth(&state, &headers) {
+        return e.into_response();
+    }
+
+    let Some(engine) = state.task_engine.as_ref() else {
+        return Json(serde_json::json!({"tasks": []})).into_response();
+    };
+    match crate::agent::task_blocked::blocked_inbox(engine.store()) {
+        Ok(tasks) => Json(serde_json::json!({"tasks": tasks})).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to list blocked tasks: {e}")})),
+        )
+            .into_response(),
+    }
+}
+
+/// GET /api/tasks/:id/artifacts — artifacts of a task run with their previews
+pub async fn handle_api_task_artifacts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(e) = require_auth(&state, &headers) {
+        return e.into_response();
+    }
+
+    let Some(engine) = state.task_engine.as_ref() else {
+        return Json(serde_json::json!({"artifacts": []})).into_response();
+    };
+    match engine.store().list_artifacts(&id) {
+        Ok(artifacts) => Json(serde_json::json!({"artifacts": artifacts})).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to list task artifacts: {e}")})),
+        )
+            .into_response(),
+    }
+}
+
+/// GET /api/tasks/:id/notes — operator notes on a task run
+pub async fn handle_api_task_notes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(e) = require_auth(&state, &headers) {
+        return e.into_response();
+    }
+
+    let Some(engine) = state.task_engine.as_ref() else {
+        return Json(serde_json::json!({"notes": []})).into_response();
+    };
+    match engine.store().list_task_notes(&id) {
+        Ok(notes) => Json(serde_json::json!({"notes": notes})).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to list task notes: {e}")})),
+        )
+            .into_response(),
+    }
+}
+
+/// POST /api/tasks/:id/notes — attach an operator note to a task run
+pub async fn handle_api_task_note_add(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(body): Json<TaskNoteBody>,
+) -> impl IntoResponse {
+    if let Err(e) = require_auth(&state, &headers) {
+        return e.into_response();
+    }
+
+    let text = body.body.trim();
+    if text.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Note body must not be empty"})),
+        )
+            .into_response();
+    }
+    let Some(engine) = state.task_engine.as_ref() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({"error": "Task engine is not enabled"})),
+        )
+            .into_response();
+    };
+    let author = body
+        .author
+        .as_deref()
+        .map(str::trim)
+        .filter(|author| !author.is_empty())
+        .unwrap_or("dashboard");
+    match engine.store().add_task_note(&id, author, text) {
+        Ok(note) => Json(serde_json::json!({"note": note})).into_response(),
+        Err(e) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("Failed to add task note: {e}")})),
+        )
+            .into_response(),
+    }
+}
+
+/// GET /api/conversations/:key/branches — exchanges removed with `/undo`
+pub async fn handle_api_conversation_branches(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    if let Err(e) = require_auth(&state, &headers) {
+        return e.into_response();
+    }
+
+    let Some(engine) = state.task_engine.as_ref() else {
+        return Json(serde_json::json!({"branches": []})).into_response();
+    };
+    match engine.store().list_conversation_branches(&key, 50) {
+        Ok(branches) => Json(serde_json::json!({"branches": branches})).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(
+                serde_json::json!({"error": format!("Failed to list conversation branches: {e}")}),
+            ),
+        )
+            .into_response(),
+    }
+}
+
 /// GET /api/cli-tools — discovered CLI tools
 pub async fn handle_api_cli_tools(
     State(state): State<AppState>,
@@ -10,7 +10,10 @@
 pub mod api;
 pub mod sse;
 pub mod static_files;
+pub mod task_api;
+pub mod webhook_tasks;
 pub mod ws;
+pub mod ws_tasks;
 
 use crate::channels::{
     Channel, LinqChannel, NextcloudTalkChannel, SendMessage, WatiChannel, WhatsAppChannel,
@@ -220,6 +223,12 @@ impl IdempotencyStore {
         keys.insert(key.to_owned(), now);
         true
     }
+
+    /// Drop `key`, e.g. when the request it guarded failed, so a retry is
+    /// not answered as a duplicate.
+    fn forget(&self, key: &str) {
+        self.keys.lock().remove(key);
+    }
 }
 
 fn parse_client_ip(value: &str) -> Option<IpAddr> {
@@ -324,6 +333,11 @@ pub struct AppState {
     pub cost_tracker: Option<Arc<CostTracker>>,
     /// SSE broadcast channel for real-time events
     pub event_tx: tokio::sync::broadcast::Sender<serde_json::Value>,
+    /// Live delta streams of tasks started through `/v1/tasks`.
+    pub task_streams: Arc<task_api::TaskStreams>,
+    /// Providers for the task engine's failover chain, from
+    /// `autonomy.task_fallback_providers`.
+    pub task_fallbacks: Arc<Vec<(crate::config::ProviderSpec, Arc<dyn Provider>)>>,
 }
 
 /// Run the HTTP gateway using axum with proper HTTP/1.1 compliance.
@@ -352,23 +366,73 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
     let actual_port = listener.local_addr()?.port();
     let display_addr = format!("{host}:{actual_port}");
 
-    let provider: Arc<dyn Provider> = Arc::from(providers::create_resilient_provider_with_options(
+    // SSE broadcast channel for real-time events
+    let (event_tx, _event_rx) = tokio::sync::broadcast::channel::<serde_json::Value>(256);
+    // Wrap observer with broadcast capability for SSE
+    let broadcast_observer: Arc<dyn crate::observability::Observer> =
+        Arc::new(sse::BroadcastObserver::new(
+            crate::observability::create_observer(&config.observability),
+            event_tx.clone(),
+        ));
+
+    let provider_runtime_options = providers::ProviderRuntimeOptions {
+        auth_profile_override: None,
+        zeroclaw_dir: config.config_path.parent().map(std::path::PathBuf::from),
+        secrets_encrypt: config.secrets.encrypt,
+        reasoning_enabled: config.runtime.reasoning_enabled,
+        max_tokens: config.runtime.max_tokens,
+        ollama_keep_alive: config.runtime.ollama_keep_alive.clone(),
+        gemini_safety_threshold: config.runtime.gemini_safety_threshold.clone(),
+        observer: Some(Arc::clone(&broadcast_observer)),
+    };
+    let provider = providers::create_resilient_provider_with_options(
         config.default_provider.as_deref().unwrap_or("openrouter"),
         config.api_key.as_deref(),
         config.api_url.as_deref(),
         &config.reliability,
-        &providers::ProviderRuntimeOptions {
-            auth_profile_override: None,
-            zeroclaw_dir: config.config_path.parent().map(std::path::PathBuf::from),
-            secrets_encrypt: config.secrets.encrypt,
-            reasoning_enabled: config.runtime.reasoning_enabled,
-        },
+        &provider_runtime_options,
+    )?;
+    let provider: Arc<dyn Provider> = Arc::from(providers::recording::with_recording(
+        providers::caching::with_response_cache(provider, &config.memory, &config.workspace_dir),
+        &config.runtime,
+        &config.workspace_dir,
     )?);
     let model = config
         .default_model
         .clone()
         .unwrap_or_else(|| "anthropic/claude-sonnet-4".into());
     let temperature = config.default_temperature;
+    let provider_name = config.default_provider.as_deref().unwrap_or("openrouter");
+    let task_fallbacks: Vec<(crate::config::ProviderSpec, Arc<dyn Provider>)> = config
+        .autonomy
+        .task_fallback_providers
+        .iter()
+        .filter(|spec| spec.provider != provider_name || spec.model != model)
+        .filter_map(|spec| {
+            let key = spec
+                .api_key
+                .as_deref()
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .or(config.api_key.as_deref());
+            match providers::create_resilient_provider_with_options(
+                &spec.provider,
+                key,
+                None,
+                &config.reliability,
+                &provider_runtime_options,
+            ) {
+                Ok(provider) => Some((spec.clone(), Arc::<dyn Provider>::from(provider))),
+                Err(err) => {
+                    tracing::warn!(
+                        provider = spec.provider.as_str(),
+                        "Ignoring task fallback provider that failed to initialize: {err}"
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
     let mem: Arc<dyn Memory> = Arc::from(memory::create_memory_with_storage(
         &config.memory,
         Some(&config.storage.provider.config),
@@ -409,8 +473,37 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
     let tools_registry: Arc<Vec<ToolSpec>> =
         Arc::new(runtime_tools.iter().map(|t| t.spec()).collect());
 
-    let task_engine = match crate::agent::task_engine::TaskEngine::default_for_workspace(
+    // Gateway tasks run under the same autonomy settings and tool policy as
+    // channel tasks.
+    let task_engine_cfg = crate::agent::task_engine::TaskEngineConfig {
+        gray_zone_verifier_enabled: config.autonomy.gray_zone_verifier_enabled,
+        gray_zone_verifier_timeout_ms: config.autonomy.gray_zone_verifier_timeout_ms,
+        task_timeout_secs: config.autonomy.task_timeout_secs,
+        completion_policy: config.autonomy.completion_policy.clone(),
+        channel_completion_policies: config.autonomy.channel_completion_policies.clone(),
+        task_env: config.autonomy.task_env.clone(),
+        duplicate_task_window_mins: config.autonomy.duplicate_task_window_mins,
+        fallback_providers: config.autonomy.task_fallback_providers.clone(),
+        budget: config.autonomy.task_budget.clone(),
+        model_prices: config.cost.prices.clone(),
+        report_cost: config.cost.report_cost,
+        report_tool_calls: config.autonomy.task_progress_tool_calls,
+        snapshot_workspace_files: config.autonomy.task_file_snapshots,
+        decompose_steps: config.autonomy.task_step_decomposition,
+        acceptance_tests: config.autonomy.task_acceptance_tests.clone(),
+        back_up_writes: config.autonomy.task_write_backups,
+        security: Arc::clone(&security),
+        max_parallel_tools: config.agent.tool_concurrency(),
+        context_max_tokens: config.agent.context_max_tokens,
+        provider_params: config.agent.provider_params.clone(),
+        tool_policy: Arc::new(crate::security::ToolPolicy::from_config(
+            &config.autonomy.tool_policy,
+        )?),
+        ..crate::agent::task_engine::TaskEngineConfig::default()
+    };
+    let task_engine = match crate::agent::task_engine::TaskEngine::new(
         &config.workspace_dir,
+        task_engine_cfg,
     ) {
         Ok(engine) => Some(Arc::new(engine)),
         Err(err) => {
@@ -434,8 +527,6 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         None
     };
 
-    // SSE broadcast channel for real-time events
-    let (event_tx, _event_rx) = tokio::sync::broadcast::channel::<serde_json::Value>(256);
     // Extract webhook secret for authentication
     let webhook_secret_hash: Option<Arc<str>> =
         config.channels_config.webhook.as_ref().and_then(|webhook| {
@@ -616,7 +707,18 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         println!("  POST /nextcloud-talk — Nextcloud Talk bot webhook");
     }
     println!("  GET  /api/*     — REST API (bearer token required)");
+    println!(
+        "  POST /v1/tasks  — task API: create, status, cancel, SSE stream (bearer token required)"
+    );
     println!("  GET  /ws/chat   — WebSocket agent chat");
+    println!("  GET  /ws/tasks  — WebSocket task streaming for custom UIs");
+    if !config.webhook_tasks.is_empty() {
+        let mut names: Vec<&String> = config.webhook_tasks.keys().collect();
+        names.sort();
+        for name in names {
+            println!("  POST /hooks/{name} — webhook task endpoint");
+        }
+    }
     println!("  GET  /health    — health check");
     println!("  GET  /metrics   — Prometheus metrics");
     if let Some(code) = pairing.pairing_code() {
@@ -640,13 +742,6 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         hooks.fire_gateway_start(host, actual_port).await;
     }
 
-    // Wrap observer with broadcast capability for SSE
-    let broadcast_observer: Arc<dyn crate::observability::Observer> =
-        Arc::new(sse::BroadcastObserver::new(
-            crate::observability::create_observer(&config.observability),
-            event_tx.clone(),
-        ));
-
     let state = AppState {
         config: config_state,
         provider,
@@ -676,6 +771,8 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         non_cli_excluded_tools: Arc::new(config.autonomy.non_cli_excluded_tools.clone()),
         cost_tracker,
         event_tx,
+        task_streams: Arc::new(task_api::TaskStreams::default()),
+        task_fallbacks: Arc::new(task_fallbacks),
     };
 
     // Config PUT needs larger body limit (1MB)
@@ -683,6 +780,11 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         .route("/api/config", put(api::handle_api_config_put))
         .layer(RequestBodyLimitLayer::new(1_048_576));
 
+    // Webhook task endpoints take full event payloads (1MB), e.g. from CI or GitHub
+    let webhook_tasks_router = Router::new()
+        .route("/hooks/{name}", post(webhook_tasks::handle_webhook_task))
+        .layer(RequestBodyLimitLayer::new(1_048_576));
+
     // Build router with middleware
     let app = Router::new()
         // ── Existing routes ──
@@ -709,16 +811,37 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         .route("/api/memory", post(api::handle_api_memory_store))
         .route("/api/memory/{key}", delete(api::handle_api_memory_delete))
         .route("/api/cost", get(api::handle_api_cost))
+        .route("/api/tasks/blocked", get(api::handle_api_tasks_blocked))
+        .route(
+            "/api/tasks/{id}/artifacts",
+            get(api::handle_api_task_artifacts),
+        )
+        .route(
+            "/api/tasks/{id}/notes",
+            get(api::handle_api_task_notes).post(api::handle_api_task_note_add),
+        )
+        .route(
+            "/api/conversations/{key}/branches",
+            get(api::handle_api_conversation_branches),
+        )
         .route("/api/cli-tools", get(api::handle_api_cli_tools))
         .route("/api/health", get(api::handle_api_health))
+        // ── Task API for other services ──
+        .route("/v1/tasks", post(task_api::handle_create_task))
+        .route("/v1/tasks/{id}", get(task_api::handle_get_task))
+        .route("/v1/tasks/{id}/cancel", post(task_api::handle_cancel_task))
+        .route("/v1/tasks/{id}/stream", get(task_api::handle_task_stream))
         // ── SSE event stream ──
         .route("/api/events", get(sse::handle_sse_events))
         // ── WebSocket agent chat ──
         .route("/ws/chat", get(ws::handle_ws_chat))
+        // ── WebSocket task streaming for custom UIs ──
+        .route("/ws/tasks", get(ws_tasks::handle_ws_tasks))
         // ── Static assets (web dashboard) ──
         .route("/_app/{*path}", get(static_files::handle_static))
-        // ── Config PUT with larger body limit ──
+        // ── Config PUT and webhook tasks with larger body limits ──
         .merge(config_put_router)
+        .merge(webhook_tasks_router)
         .with_state(state)
         .layer(RequestBodyLimitLayer::new(MAX_BODY_SIZE))
         .layer(TimeoutLayer::with_status_code(
@@ -1633,6 +1756,8 @@ mod tests {
             non_cli_excluded_tools: Arc::new(Vec::new()),
             cost_tracker: None,
             event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
         };
 
         let response = handle_metrics(State(state)).await.into_response();
@@ -1688,6 +1813,8 @@ mod tests {
             non_cli_excluded_tools: Arc::new(Vec::new()),
             cost_tracker: None,
             event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
         };
 
         let response = handle_metrics(State(state)).await.into_response();
@@ -2060,6 +2187,8 @@ mod tests {
             non_cli_excluded_tools: Arc::new(Vec::new()),
             cost_tracker: None,
             event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
         };
 
         let mut headers = HeaderMap::new();
@@ -2130,6 +2259,8 @@ mod tests {
             non_cli_excluded_tools: Arc::new(Vec::new()),
             cost_tracker: None,
             event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
         };
 
         let headers = HeaderMap::new();
@@ -2212,6 +2343,8 @@ mod tests {
             non_cli_excluded_tools: Arc::new(Vec::new()),
             cost_tracker: None,
             event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
         };
 
         let response = handle_webhook(
@@ -2266,6 +2399,8 @@ mod tests {
             non_cli_excluded_tools: Arc::new(Vec::new()),
             cost_tracker: None,
             event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
         };
 
         let mut headers = HeaderMap::new();
@@ -2325,6 +2460,8 @@ mod tests {
             non_cli_excluded_tools: Arc::new(Vec::new()),
             cost_tracker: None,
             event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
         };
 
         let mut headers = HeaderMap::new();
@@ -2389,6 +2526,8 @@ mod tests {
             non_cli_excluded_tools: Arc::new(Vec::new()),
             cost_tracker: None,
             event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
         };
 
         let response = handle_nextcloud_talk_webhook(
@@ -2449,6 +2588,8 @@ mod tests {
             non_cli_excluded_tools: Arc::new(Vec::new()),
             cost_tracker: None,
             event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
         };
 
         let mut headers = HeaderMap::new();
@@ -2485,6 +2626,157 @@ mod tests {
         format!("sha256={}", compute_whatsapp_signature_hex(secret, body))
     }
 
+    /// State with a `signed` webhook task endpoint (secret `hook-secret`) and
+    /// an `open` one without a secret, and no task engine.
+    fn webhook_task_state(pairing: PairingGuard) -> AppState {
+        let hook = |secret: Option<&str>| crate::config::WebhookTaskConfig {
+            request: "Investigate {{alert}}".into(),
+            filters: HashMap::new(),
+            reply_channel: None,
+            reply_target: None,
+            secret: secret.map(str::to_string),
+            allow_tools: Vec::new(),
+        };
+        let mut config = Config::default();
+        config
+            .webhook_tasks
+            .insert("signed".into(), hook(Some("hook-secret")));
+        config.webhook_tasks.insert("open".into(), hook(None));
+        AppState {
+            config: Arc::new(Mutex::new(config)),
+            provider: Arc::new(MockProvider::default()),
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: Arc::new(MockMemory),
+            auto_save: false,
+            webhook_secret_hash: None,
+            pairing: Arc::new(pairing),
+            trust_forwarded_headers: false,
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
+            whatsapp: None,
+            whatsapp_app_secret: None,
+            linq: None,
+            linq_signing_secret: None,
+            nextcloud_talk: None,
+            nextcloud_talk_webhook_secret: None,
+            wati: None,
+            observer: Arc::new(crate::observability::NoopObserver),
+            tools_registry: Arc::new(Vec::new()),
+            runtime_tools: Arc::new(Vec::new()),
+            task_engine: None,
+            hooks: None,
+            max_tool_iterations: 4,
+            multimodal: crate::config::MultimodalConfig::default(),
+            non_cli_excluded_tools: Arc::new(Vec::new()),
+            cost_tracker: None,
+            event_tx: tokio::sync::broadcast::channel(16).0,
+            task_streams: Arc::default(),
+            task_fallbacks: Arc::default(),
+        }
+    }
+
+    async fn post_webhook_task(
+        state: &AppState,
+        name: &str,
+        headers: HeaderMap,
+        body: &'static [u8],
+    ) -> StatusCode {
+        webhook_tasks::handle_webhook_task(
+            State(state.clone()),
+            test_connect_info(),
+            axum::extract::Path(name.to_string()),
+            headers,
+            Bytes::from_static(body),
+        )
+        .await
+        .into_response()
+        .status()
+    }
+
+    #[tokio::test]
+    async fn webhook_task_endpoints_take_their_secret_signature_or_bearer_token() {
+        let body: &'static [u8] = br#"{"alert":"disk full"}"#;
+        let state = webhook_task_state(PairingGuard::new(true, &["zc_valid".into()]));
+        // Authorized calls get as far as the missing task engine.
+        let authorized = StatusCode::SERVICE_UNAVAILABLE;
+        let with = |name: &'static str, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+
+        for headers in [
+            HeaderMap::new(),
+            with("X-Webhook-Secret", "wrong"),
+            with(
+                "X-Hub-Signature-256",
+                &compute_whatsapp_signature_header("wrong", body),
+            ),
+            // The gateway token does not open an endpoint with a secret.
+            with("Authorization", "Bearer zc_valid"),
+        ] {
+            assert_eq!(
+                post_webhook_task(&state, "signed", headers, body).await,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+        for headers in [
+            with("X-Webhook-Secret", "hook-secret"),
+            with(
+                "X-Hub-Signature-256",
+                &compute_whatsapp_signature_header("hook-secret", body),
+            ),
+        ] {
+            assert_eq!(
+                post_webhook_task(&state, "signed", headers, body).await,
+                authorized
+            );
+        }
+
+        for headers in [
+            HeaderMap::new(),
+            with("Authorization", "Bearer zc_invalid"),
+            with("X-Webhook-Secret", "hook-secret"),
+        ] {
+            assert_eq!(
+                post_webhook_task(&state, "open", headers, body).await,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+        assert_eq!(
+            post_webhook_task(
+                &state,
+                "open",
+                with("Authorization", "Bearer zc_valid"),
+                body
+            )
+            .await,
+            authorized
+        );
+        assert_eq!(
+            post_webhook_task(&state, "missing", HeaderMap::new(), body).await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn webhook_task_idempotency_key_is_kept_only_for_started_tasks() {
+        let state = webhook_task_state(PairingGuard::new(false, &[]));
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Webhook-Secret", HeaderValue::from_static("hook-secret"));
+        headers.insert("X-Idempotency-Key", HeaderValue::from_static("delivery-1"));
+
+        // Without a task engine nothing starts, so each retry gets through.
+        for _ in 0..2 {
+            assert_eq!(
+                post_webhook_task(&state, "signed", headers.clone(), br#"{"alert":"x"}"#).await,
+                StatusCode::SERVICE_UNAVAILABLE
+            );
+        }
+        assert!(state.idempotency_store.record_if_new("delivery-1"));
+    }
+
     #[test]
     fn whatsapp_signature_valid() {
         let app_secret = generate_test_secret();
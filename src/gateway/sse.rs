@@ -111,6 +111,19 @@ impl crate::observability::Observer for BroadcastObserver {
                     "timestamp": chrono::Utc::now().to_rfc3339(),
                 })
             }
+            crate::observability::ObserverEvent::TaskLifecycle {
+                task_id,
+                channel,
+                stage,
+                labels,
+            } => serde_json::json!({
+                "type": "task_lifecycle",
+                "task_id": task_id,
+                "channel": channel,
+                "stage": stage,
+                "labels": labels,
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }),
             crate::observability::ObserverEvent::AgentStart { provider, model } => {
                 serde_json::json!({
                     "type": "agent_start",
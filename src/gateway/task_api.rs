@@ -0,0 +1,624 @@
+//! Task API for other services (`/v1/tasks`).
+//!
+//! `POST /v1/tasks` creates a task on the gateway's task engine and runs it
+//! in the background, `GET /v1/tasks/{id}` reports its status, events, and
+//! artifacts, `POST /v1/tasks/{id}/cancel` stops it, and
+//! `GET /v1/tasks/{id}/stream` is a Server-Sent Events stream of the run's
+//! progress notes, response deltas, tool calls, and lifecycle stages that
+//! ends with a `done` or `error` event. All routes take the same bearer token
+//! as `/api/*`. The `/ws/tasks` WebSocket runs its tasks the same way.
+
+use super::api::require_auth;
+use super::AppState;
+use crate::agent::output_format::OutputFormat;
+use crate::agent::task_engine::{
+    ProviderFallback, TaskEngine, TaskProgressReporter, TaskRunRequest,
+};
+use crate::agent::task_types::{TaskEventRecord, TaskRunRecord};
+use crate::config::Config;
+use crate::observability::{Observer, ObserverEvent, ObserverMetric};
+use crate::providers::ChatMessage;
+use anyhow::Result;
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json,
+    },
+};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+/// Channel recorded on tasks created through this API.
+pub const API_CHANNEL: &str = "api";
+
+/// Stream events buffered per task; a subscriber further behind skips ahead.
+const STREAM_CAPACITY: usize = 256;
+
+/// Live event streams of the tasks this gateway is running, by task id.
+#[derive(Default)]
+pub struct TaskStreams {
+    streams: Mutex<HashMap<String, broadcast::Sender<Value>>>,
+}
+
+impl TaskStreams {
+    fn open(&self, task_id: &str, tx: broadcast::Sender<Value>) {
+        self.streams.lock().insert(task_id.to_string(), tx);
+    }
+
+    fn subscribe(&self, task_id: &str) -> Option<broadcast::Receiver<Value>> {
+        self.streams
+            .lock()
+            .get(task_id)
+            .map(broadcast::Sender::subscribe)
+    }
+
+    /// Send the final event of a task and end its stream.
+    fn close(&self, task_id: &str, last: Value) {
+        if let Some(tx) = self.streams.lock().remove(task_id) {
+            let _ = tx.send(last);
+        }
+    }
+}
+
+/// Forwards a run's tool calls and lifecycle stages to its stream, on top
+/// of the gateway's observer.
+struct TaskStreamObserver {
+    inner: Arc<dyn Observer>,
+    tx: broadcast::Sender<Value>,
+}
+
+impl Observer for TaskStreamObserver {
+    fn record_event(&self, event: &ObserverEvent) {
+        self.inner.record_event(event);
+        let event = match event {
+            ObserverEvent::ToolCallStart { tool } => {
+                json!({ "type": "tool_call_start", "tool": tool })
+            }
+            ObserverEvent::ToolCall {
+                tool,
+                duration,
+                success,
+            } => json!({
+                "type": "tool_call",
+                "tool": tool,
+                "duration_ms": duration.as_millis(),
+                "success": success,
+            }),
+            ObserverEvent::TaskLifecycle { stage, labels, .. } => json!({
+                "type": "task_lifecycle",
+                "stage": stage,
+                "labels": labels,
+            }),
+            _ => return,
+        };
+        let _ = self.tx.send(event);
+    }
+
+    fn record_metric(&self, metric: &ObserverMetric) {
+        self.inner.record_metric(metric);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+
+    fn name(&self) -> &str {
+        "task_stream"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// A task to run on the gateway's task engine.
+pub(super) struct TaskSpec {
+    pub channel: &'static str,
+    pub sender: String,
+    pub request: String,
+    pub output_format: Option<OutputFormat>,
+    /// Earlier user and assistant turns of the conversation, oldest first.
+    pub prior_turns: Vec<ChatMessage>,
+    /// Tools the run may use (an empty list allows none); `None` allows
+    /// every tool not excluded for non-CLI channels.
+    pub allowed_tools: Option<Vec<String>>,
+}
+
+/// Id of a started task and a subscription to its stream from the start.
+pub(super) type StartedTask = (String, broadcast::Receiver<Value>);
+
+#[derive(Deserialize)]
+pub struct CreateTaskBody {
+    /// What the agent should do.
+    pub request: String,
+    /// Who the task is for; defaults to `api`.
+    pub sender: Option<String>,
+    pub output_format: Option<OutputFormat>,
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> axum::response::Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+fn engine_unavailable() -> axum::response::Response {
+    error_response(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Task engine is not enabled",
+    )
+}
+
+fn task_json(task: &TaskRunRecord) -> Value {
+    json!({
+        "id": task.id,
+        "channel": task.channel,
+        "sender": task.sender_key,
+        "status": task.status.as_str(),
+        "request": task.original_request,
+        "response": task.last_response,
+        "attempts": task.attempt_count,
+        "created_at": task.created_at,
+        "updated_at": task.updated_at,
+        "completed_at": task.completed_at,
+    })
+}
+
+fn event_json(event: &TaskEventRecord) -> Value {
+    let payload = event
+        .payload_json
+        .as_deref()
+        .and_then(|raw| serde_json::from_str::<Value>(raw).ok());
+    json!({
+        "id": event.id,
+        "type": event.event_type,
+        "payload": payload,
+        "created_at": event.created_at,
+    })
+}
+
+/// A task with its events and artifacts, or `None` if there is no such task.
+fn task_snapshot(engine: &TaskEngine, task_id: &str) -> Result<Option<Value>> {
+    let store = engine.store();
+    let Some(task) = store.get_task_run(task_id)? else {
+        return Ok(None);
+    };
+    let events: Vec<Value> = store.list_events(task_id)?.iter().map(event_json).collect();
+    let artifacts = store.list_artifacts(task_id)?;
+    Ok(Some(json!({
+        "task": task_json(&task),
+        "events": events,
+        "artifacts": artifacts,
+    })))
+}
+
+/// Tools a run may not see or call: those excluded for non-CLI channels, and
+/// with an `allowed` list, every other tool.
+fn excluded_tools<'a>(
+    non_cli_excluded: &[String],
+    tool_names: impl Iterator<Item = &'a str>,
+    allowed: Option<&[String]>,
+) -> Vec<String> {
+    let mut excluded = non_cli_excluded.to_vec();
+    if let Some(allowed) = allowed {
+        for name in tool_names {
+            if !allowed.iter().any(|allowed| allowed == name)
+                && !excluded.iter().any(|excluded| excluded == name)
+            {
+                excluded.push(name.to_string());
+            }
+        }
+    }
+    excluded
+}
+
+/// System prompt for API tasks, built the way the channel runtime builds it:
+/// tools in `excluded` are refused at dispatch, so they are not described.
+fn system_prompt(state: &AppState, config: &Config, excluded: &[String]) -> String {
+    let tool_entries =
+        crate::channels::tool_prompt::tool_prompt_entries(&state.runtime_tools, excluded);
+    let tool_descs = crate::channels::tool_prompt::as_tool_descs(&tool_entries);
+    let skills = crate::skills::load_skills_with_config(&config.workspace_dir, config);
+    let native_tools = state.provider.supports_native_tools();
+    let mut prompt = crate::channels::build_system_prompt_with_mode(
+        &config.workspace_dir,
+        &state.model,
+        &tool_descs,
+        &skills,
+        Some(&config.identity),
+        None,
+        native_tools,
+        config.skills.prompt_injection_mode,
+    );
+    if !native_tools {
+        prompt.push_str(&crate::agent::loop_::build_tool_instructions_filtered(
+            &state.runtime_tools,
+            excluded,
+        ));
+    }
+    prompt
+}
+
+/// Run a task to its end, reporting it through `started` once it exists.
+pub(super) async fn run_task(
+    state: AppState,
+    engine: Arc<TaskEngine>,
+    spec: TaskSpec,
+    started: oneshot::Sender<Result<StartedTask>>,
+) {
+    let TaskSpec {
+        channel,
+        sender,
+        request,
+        output_format,
+        prior_turns,
+        allowed_tools,
+    } = spec;
+    let config = state.config.lock().clone();
+    let provider_name = config
+        .default_provider
+        .clone()
+        .unwrap_or_else(|| "openrouter".to_string());
+    let excluded_tools = excluded_tools(
+        &state.non_cli_excluded_tools,
+        state.runtime_tools.iter().map(|tool| tool.name()),
+        allowed_tools.as_deref(),
+    );
+    let mut history = vec![ChatMessage::system(system_prompt(
+        &state,
+        &config,
+        &excluded_tools,
+    ))];
+    history.extend(prior_turns);
+    history.push(ChatMessage::user(&request));
+
+    let (events_tx, _) = broadcast::channel(STREAM_CAPACITY);
+    let observer = TaskStreamObserver {
+        inner: Arc::clone(&state.observer),
+        tx: events_tx.clone(),
+    };
+    let progress_tx = events_tx.clone();
+    let progress_reporter: TaskProgressReporter = Arc::new(move |content: String| {
+        let _ = progress_tx.send(json!({ "type": "progress", "content": content }));
+    });
+    let (delta_tx, mut delta_rx) = mpsc::channel::<String>(64);
+    let delta_events = events_tx.clone();
+    let delta_pump = tokio::spawn(async move {
+        while let Some(delta) = delta_rx.recv().await {
+            let event = if delta == crate::agent::loop_::DRAFT_CLEAR_SENTINEL {
+                json!({ "type": "reset" })
+            } else {
+                json!({ "type": "delta", "content": delta })
+            };
+            let _ = delta_events.send(event);
+        }
+    });
+
+    let mut req = TaskRunRequest {
+        channel,
+        sender_key: &sender,
+        reply_target: &sender,
+        original_request: &request,
+        provider: state.provider.as_ref(),
+        history: &mut history,
+        tools_registry: &state.runtime_tools,
+        observer: &observer,
+        provider_name: &provider_name,
+        model: &state.model,
+        temperature: state.temperature,
+        multimodal: &state.multimodal,
+        max_tool_iterations: state.max_tool_iterations,
+        // Cancellation goes through the engine, which stops the run it
+        // registered under the task id; there is no caller token to pass.
+        cancellation_token: None,
+        on_delta: Some(delta_tx),
+        hooks: state.hooks.as_deref(),
+        excluded_tools: &excluded_tools,
+        progress_reporter: Some(progress_reporter),
+        labels: Vec::new(),
+        bypass_completion: false,
+        output_format,
+        env: HashMap::new(),
+        fallback_providers: state
+            .task_fallbacks
+            .iter()
+            .map(|(spec, provider)| ProviderFallback {
+                provider: provider.as_ref(),
+                provider_name: spec.provider.as_str(),
+                model: spec.model.as_str(),
+            })
+            .collect(),
+        steps: Vec::new(),
+        provider_params: crate::config::ProviderParams::default(),
+    };
+    let task_id = match engine.start_task(&req) {
+        Ok(task_id) => task_id,
+        Err(e) => {
+            let _ = started.send(Err(e));
+            return;
+        }
+    };
+    let events_rx = events_tx.subscribe();
+    state.task_streams.open(&task_id, events_tx);
+    let _ = started.send(Ok((task_id.clone(), events_rx)));
+
+    let result = engine.run_existing_task(&task_id, &mut req).await;
+    // Flush the remaining deltas before the final event.
+    drop(req);
+    drop(observer);
+    let _ = delta_pump.await;
+
+    let last = match result {
+        Ok(outcome) => json!({
+            "type": "done",
+            "status": outcome.status.as_str(),
+            "response": outcome.final_response,
+        }),
+        Err(e) => {
+            let status = engine
+                .store()
+                .get_task_run(&task_id)
+                .ok()
+                .flatten()
+                .map(|task| task.status.as_str());
+            json!({
+                "type": "error",
+                "status": status,
+                "message": crate::providers::sanitize_api_error(&e.to_string()),
+            })
+        }
+    };
+    state.task_streams.close(&task_id, last);
+}
+
+/// POST /v1/tasks — create a task and start running it
+pub async fn handle_create_task(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<CreateTaskBody>,
+) -> impl IntoResponse {
+    if let Err(e) = require_auth(&state, &headers) {
+        return e.into_response();
+    }
+
+    let request = body.request.trim().to_string();
+    if request.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "Task request must not be empty");
+    }
+    let Some(engine) = state.task_engine.clone() else {
+        return engine_unavailable();
+    };
+    let sender = body
+        .sender
+        .as_deref()
+        .map(str::trim)
+        .filter(|sender| !sender.is_empty())
+        .unwrap_or(API_CHANNEL)
+        .to_string();
+
+    let spec = TaskSpec {
+        channel: API_CHANNEL,
+        sender,
+        request,
+        output_format: body.output_format,
+        prior_turns: Vec::new(),
+        allowed_tools: None,
+    };
+    let (started_tx, started_rx) = oneshot::channel();
+    tokio::spawn(run_task(state, engine, spec, started_tx));
+    match started_rx.await {
+        Ok(Ok((task_id, _))) => (
+            StatusCode::ACCEPTED,
+            Json(json!({
+                "task_id": task_id,
+                "status": "running",
+                "stream": format!("/v1/tasks/{task_id}/stream"),
+            })),
+        )
+            .into_response(),
+        Ok(Err(e)) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to create task: {e}"),
+        ),
+        Err(_) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Task runner stopped before the task started",
+        ),
+    }
+}
+
+/// GET /v1/tasks/:id — task status with its events and artifacts
+pub async fn handle_get_task(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(e) = require_auth(&state, &headers) {
+        return e.into_response();
+    }
+
+    let Some(engine) = state.task_engine.as_ref() else {
+        return engine_unavailable();
+    };
+    match task_snapshot(engine, &id) {
+        Ok(Some(snapshot)) => Json(snapshot).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, format!("Task '{id}' not found")),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load task: {e}"),
+        ),
+    }
+}
+
+/// POST /v1/tasks/:id/cancel — cancel a running, queued, or blocked task
+pub async fn handle_cancel_task(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(e) = require_auth(&state, &headers) {
+        return e.into_response();
+    }
+
+    let Some(engine) = state.task_engine.as_ref() else {
+        return engine_unavailable();
+    };
+    match engine.cancel_task(&id) {
+        Ok(true) => Json(json!({ "task_id": id, "cancelled": true })).into_response(),
+        Ok(false) => error_response(
+            StatusCode::CONFLICT,
+            "Task already finished or is running on another worker",
+        ),
+        Err(e) => error_response(StatusCode::NOT_FOUND, format!("Failed to cancel task: {e}")),
+    }
+}
+
+/// GET /v1/tasks/:id/stream — SSE stream of a running task's progress and deltas
+pub async fn handle_task_stream(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(e) = require_auth(&state, &headers) {
+        return e.into_response();
+    }
+
+    if let Some(rx) = state.task_streams.subscribe(&id) {
+        let stream = BroadcastStream::new(rx).filter_map(
+            |result: Result<Value, tokio_stream::wrappers::errors::BroadcastStreamRecvError>| {
+                result
+                    .ok()
+                    .map(|value| Ok::<_, Infallible>(Event::default().data(value.to_string())))
+            },
+        );
+        return Sse::new(stream)
+            .keep_alive(KeepAlive::default())
+            .into_response();
+    }
+
+    // Not running on this gateway: report where the task ended up instead.
+    let Some(engine) = state.task_engine.as_ref() else {
+        return engine_unavailable();
+    };
+    match engine.store().get_task_run(&id) {
+        Ok(Some(task)) => {
+            let last = json!({
+                "type": "status",
+                "status": task.status.as_str(),
+                "response": task.last_response,
+            });
+            let event = Ok::<_, Infallible>(Event::default().data(last.to_string()));
+            Sse::new(tokio_stream::once(event)).into_response()
+        }
+        Ok(None) => error_response(StatusCode::NOT_FOUND, format!("Task '{id}' not found")),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load task: {e}"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_observer_forwards_only_tool_and_lifecycle_events_of_its_task() {
+        let (tx_a, mut rx_a) = broadcast::channel(8);
+        let (tx_b, mut rx_b) = broadcast::channel(8);
+        let inner: Arc<dyn Observer> = Arc::new(crate::observability::NoopObserver);
+        let task_a = TaskStreamObserver {
+            inner: Arc::clone(&inner),
+            tx: tx_a,
+        };
+        let _task_b = TaskStreamObserver { inner, tx: tx_b };
+
+        task_a.record_event(&ObserverEvent::ToolCallStart {
+            tool: "shell".into(),
+        });
+        task_a.record_event(&ObserverEvent::HeartbeatTick);
+        task_a.record_event(&ObserverEvent::TaskLifecycle {
+            task_id: "task-a".into(),
+            channel: API_CHANNEL.into(),
+            stage: "round".into(),
+            labels: vec!["hint:code".into()],
+        });
+
+        let start = rx_a.try_recv().unwrap();
+        assert_eq!(start["type"], "tool_call_start");
+        assert_eq!(start["tool"], "shell");
+        let stage = rx_a.try_recv().unwrap();
+        assert_eq!(stage["type"], "task_lifecycle");
+        assert_eq!(stage["labels"][0], "hint:code");
+        assert!(rx_a.try_recv().is_err());
+        assert!(rx_b.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn stream_ends_with_the_final_event_once_closed() {
+        let streams = TaskStreams::default();
+        assert!(streams.subscribe("task-1").is_none());
+
+        let (tx, _) = broadcast::channel(STREAM_CAPACITY);
+        streams.open("task-1", tx.clone());
+        let mut rx = streams.subscribe("task-1").unwrap();
+        tx.send(json!({ "type": "delta", "content": "Hel" }))
+            .unwrap();
+        drop(tx);
+        streams.close("task-1", json!({ "type": "done", "status": "completed" }));
+
+        assert_eq!(rx.recv().await.unwrap()["type"], "delta");
+        assert_eq!(rx.recv().await.unwrap()["type"], "done");
+        assert!(rx.recv().await.is_err());
+        assert!(streams.subscribe("task-1").is_none());
+    }
+
+    #[test]
+    fn snapshot_includes_status_and_decoded_event_payloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = TaskEngine::default_for_workspace(dir.path()).unwrap();
+        let task_id = engine
+            .create_task(API_CHANNEL, "svc", "svc", "summarize the logs")
+            .unwrap();
+        engine
+            .store()
+            .append_event(&task_id, "note", Some(&json!({ "text": "hi" })))
+            .unwrap();
+
+        let snapshot = task_snapshot(&engine, &task_id).unwrap().unwrap();
+        assert_eq!(snapshot["task"]["status"], "queued");
+        assert_eq!(snapshot["task"]["request"], "summarize the logs");
+        let note = snapshot["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|event| event["type"] == "note")
+            .unwrap();
+        assert_eq!(note["payload"]["text"], "hi");
+        assert!(task_snapshot(&engine, "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn allowed_tools_exclude_every_other_tool() {
+        let non_cli = vec!["shell".to_string()];
+        let tools = ["shell", "file_read", "file_write", "git_operations"];
+
+        assert_eq!(excluded_tools(&non_cli, tools.into_iter(), None), non_cli);
+        let allowed = vec!["file_read".to_string(), "shell".to_string()];
+        assert_eq!(
+            excluded_tools(&non_cli, tools.into_iter(), Some(allowed.as_slice())),
+            ["shell", "file_write", "git_operations"]
+        );
+        assert_eq!(
+            excluded_tools(&non_cli, tools.into_iter(), Some(&[][..])),
+            ["shell", "file_read", "file_write", "git_operations"]
+        );
+    }
+}
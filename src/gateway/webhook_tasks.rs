@@ -0,0 +1,317 @@
+//! Webhook endpoints that start tasks (`[webhook_tasks.<name>]`, served at
+//! `POST /hooks/{name}`).
+//!
+//! Each endpoint renders its `request` template against the posted JSON
+//! payload and runs the result as a task on the gateway's task engine, so a
+//! CI failure, an alert, or a GitHub event can start an investigation without
+//! anyone typing it in. When the endpoint names a `reply_channel`, the final
+//! answer is sent to the rendered `reply_target` there. Tasks may only use
+//! the endpoint's `allow_tools`; without them they run with no tools.
+//!
+//! Templates replace `{{path}}` with the payload value at a dot-separated
+//! path (numeric segments index arrays), `{{path|fallback}}` with the
+//! fallback when that value is missing or null, and `{{.}}` with the whole
+//! payload as JSON.
+
+use super::task_api::{self, TaskSpec};
+use super::{client_key_from_request, hash_webhook_secret, AppState, RATE_LIMIT_WINDOW_SECS};
+use crate::config::{Config, WebhookTaskConfig};
+use crate::security::pairing::constant_time_eq;
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use tokio::sync::{broadcast, oneshot};
+
+/// Channel recorded on tasks started by webhook endpoints.
+pub const WEBHOOK_TASK_CHANNEL: &str = "webhook";
+
+/// Value at a dot-separated `path` of `payload`; `.` is the payload itself.
+fn lookup<'a>(payload: &'a Value, path: &str) -> Option<&'a Value> {
+    if path == "." {
+        return Some(payload);
+    }
+    path.split('.')
+        .try_fold(payload, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|index| items.get(index)),
+            _ => None,
+        })
+        .filter(|value| !value.is_null())
+}
+
+fn render_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Fill the `{{path}}` and `{{path|fallback}}` placeholders of `template`
+/// from `payload`; a missing value without a fallback renders empty.
+pub fn render_template(template: &str, payload: &Value) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let expr = &rest[start + 2..start + 2 + len];
+        let (path, fallback) = match expr.split_once('|') {
+            Some((path, fallback)) => (path.trim(), fallback.trim()),
+            None => (expr.trim(), ""),
+        };
+        match lookup(payload, path) {
+            Some(value) => out.push_str(&render_value(value)),
+            None => out.push_str(fallback),
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Whether every filter path of `payload` renders to its expected value.
+fn matches_filters(filters: &HashMap<String, String>, payload: &Value) -> bool {
+    filters.iter().all(|(path, expected)| {
+        lookup(payload, path.trim()).is_some_and(|value| render_value(value) == *expected)
+    })
+}
+
+/// Whether the caller may trigger `hook`: with its secret, sent as-is or as a
+/// body signature, when it has one, and with the gateway bearer token when not.
+fn is_authorized(
+    state: &AppState,
+    hook: &WebhookTaskConfig,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> bool {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let Some(secret) = hook
+        .secret
+        .as_deref()
+        .map(str::trim)
+        .filter(|secret| !secret.is_empty())
+    else {
+        return super::api::require_auth(state, headers).is_ok();
+    };
+    if let Some(given) = header("X-Webhook-Secret") {
+        if constant_time_eq(&hash_webhook_secret(given), &hash_webhook_secret(secret)) {
+            return true;
+        }
+    }
+    header("X-Hub-Signature-256")
+        .is_some_and(|signature| super::verify_whatsapp_signature(secret, body, signature))
+}
+
+/// Wait for a task's final event and send it to `target` on `channel`.
+async fn deliver_reply(
+    config: Config,
+    task_id: String,
+    mut events: broadcast::Receiver<Value>,
+    channel: String,
+    target: String,
+) {
+    let text = loop {
+        match events.recv().await {
+            Ok(event) if event["type"] == "done" => {
+                break event["response"].as_str().unwrap_or_default().to_string();
+            }
+            Ok(event) if event["type"] == "error" => {
+                break format!(
+                    "Task {task_id} failed: {}",
+                    event["message"].as_str().unwrap_or("unknown error")
+                );
+            }
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    };
+    if let Err(e) = crate::cron::scheduler::announce(&config, &channel, &target, &text).await {
+        tracing::warn!("Webhook task {task_id}: failed to send reply to {channel}: {e}");
+    }
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> axum::response::Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+/// POST /hooks/:name — start a task from a configured webhook endpoint
+pub async fn handle_webhook_task(
+    State(state): State<AppState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let rate_key =
+        client_key_from_request(Some(peer_addr), &headers, state.trust_forwarded_headers);
+    if !state.rate_limiter.allow_webhook(&rate_key) {
+        tracing::warn!("/hooks/{name} rate limit exceeded");
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({
+                "error": "Too many webhook requests. Please retry later.",
+                "retry_after": RATE_LIMIT_WINDOW_SECS,
+            })),
+        )
+            .into_response();
+    }
+
+    let config = state.config.lock().clone();
+    let Some(hook) = config.webhook_tasks.get(&name) else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            format!("No webhook task endpoint named '{name}'"),
+        );
+    };
+    if !is_authorized(&state, hook, &headers, &body) {
+        tracing::warn!("/hooks/{name}: rejected — invalid secret, signature, or bearer token");
+        return error_response(
+            StatusCode::UNAUTHORIZED,
+            "Unauthorized — send X-Webhook-Secret, X-Hub-Signature-256, or Authorization: Bearer <token>",
+        );
+    }
+    let Ok(payload) = serde_json::from_slice::<Value>(&body) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid JSON body");
+    };
+
+    if !matches_filters(&hook.filters, &payload) {
+        return Json(json!({ "status": "skipped", "reason": "Payload did not match the filters" }))
+            .into_response();
+    }
+    let request = render_template(&hook.request, &payload).trim().to_string();
+    if request.is_empty() {
+        return error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "The request template rendered empty for this payload",
+        );
+    }
+    // A target read from the payload is chosen by whoever posts it.
+    let reply = hook.reply_channel.clone().zip(
+        hook.reply_target
+            .as_deref()
+            .map(|target| render_template(target, &payload).trim().to_string())
+            .filter(|target| !target.is_empty()),
+    );
+    let Some(engine) = state.task_engine.clone() else {
+        return error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Task engine is not enabled",
+        );
+    };
+
+    // The key is held while the task starts and released if it does not, so
+    // only a started task makes a retry a duplicate.
+    let idempotency_key = headers
+        .get("X-Idempotency-Key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    if let Some(key) = idempotency_key {
+        if !state.idempotency_store.record_if_new(key) {
+            return Json(json!({ "status": "duplicate", "idempotent": true })).into_response();
+        }
+    }
+
+    let spec = TaskSpec {
+        channel: WEBHOOK_TASK_CHANNEL,
+        sender: name.clone(),
+        request,
+        output_format: None,
+        prior_turns: Vec::new(),
+        allowed_tools: Some(hook.allow_tools.clone()),
+    };
+    let (started_tx, started_rx) = oneshot::channel();
+    tokio::spawn(task_api::run_task(state.clone(), engine, spec, started_tx));
+    let started = started_rx.await;
+    if !matches!(started, Ok(Ok(_))) {
+        if let Some(key) = idempotency_key {
+            state.idempotency_store.forget(key);
+        }
+    }
+    match started {
+        Ok(Ok((task_id, events))) => {
+            tracing::info!("/hooks/{name}: started task {task_id}");
+            if let Some((channel, target)) = reply {
+                tokio::spawn(deliver_reply(
+                    config.clone(),
+                    task_id.clone(),
+                    events,
+                    channel,
+                    target,
+                ));
+            }
+            (
+                StatusCode::ACCEPTED,
+                Json(json!({ "task_id": task_id, "status": "running" })),
+            )
+                .into_response()
+        }
+        Ok(Err(e)) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to create task: {e}"),
+        ),
+        Err(_) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Task runner stopped before the task started",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_read_nested_paths_with_fallbacks() {
+        let payload = json!({
+            "repository": { "full_name": "acme/api" },
+            "alerts": [{ "labels": { "severity": "critical" }, "count": 3 }],
+            "note": null,
+        });
+
+        assert_eq!(
+            render_template(
+                "{{repository.full_name}}: {{alerts.0.labels.severity}} x{{ alerts.0.count }}",
+                &payload
+            ),
+            "acme/api: critical x3"
+        );
+        assert_eq!(
+            render_template(
+                "[{{note|no note}}][{{missing.path}}][{{alerts.5.count|?}}]",
+                &payload
+            ),
+            "[no note][][?]"
+        );
+        assert!(render_template("{{.}}", &payload).contains("\"full_name\":\"acme/api\""));
+        assert_eq!(render_template("open {{ brace", &payload), "open {{ brace");
+    }
+
+    #[test]
+    fn filters_must_all_match() {
+        let payload = json!({ "action": "completed", "run": { "conclusion": "failure" } });
+        let mut filters = HashMap::from([("action".to_string(), "completed".to_string())]);
+        assert!(matches_filters(&filters, &payload));
+
+        filters.insert("run.conclusion".into(), "success".into());
+        assert!(!matches_filters(&filters, &payload));
+        filters.insert("run.conclusion".into(), "failure".into());
+        assert!(matches_filters(&filters, &payload));
+        filters.insert("sender.login".into(), "bot".into());
+        assert!(!matches_filters(&filters, &payload));
+    }
+}
@@ -3,6 +3,7 @@
 //! Protocol:
 //! ```text
 //! Client -> Server: {"type":"message","content":"Hello"}
+//! Client -> Server: {"type":"message","content":"...","output_format":{"kind":"json_schema","schema":{...}}}
 //! Server -> Client: {"type":"progress","content":"Round 1 running..."}
 //! Server -> Client: {"type":"chunk","content":"Hi! "}
 //! Server -> Client: {"type":"tool_call","name":"shell","args":{...}}
@@ -11,6 +12,7 @@
 //! ```
 
 use super::AppState;
+use crate::agent::output_format::OutputFormat;
 use axum::{
     extract::{
         ws::{Message, WebSocket},
@@ -83,6 +85,21 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
         if content.is_empty() {
             continue;
         }
+        let output_format = match parsed.get("output_format") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(value) => match serde_json::from_value::<OutputFormat>(value.clone()) {
+                Ok(format) => Some(format),
+                Err(e) => {
+                    let err = serde_json::json!({
+                        "type": "error",
+                        "message": format!("Invalid output_format: {e}"),
+                    });
+                    let mut ws_sender = sender.lock().await;
+                    let _ = ws_sender.send(Message::Text(err.to_string().into())).await;
+                    continue;
+                }
+            },
+        };
         println!("  💬 [web_dashboard] from browser: {}", content);
 
         // Process message with the LLM provider
@@ -134,6 +151,7 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
             &content,
             "web_dashboard",
             Some(progress_reporter),
+            output_format,
         )
         .await;
 
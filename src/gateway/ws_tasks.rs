@@ -0,0 +1,360 @@
+//! WebSocket task channel for custom chat frontends.
+//!
+//! Each `message` frame runs as a task on the gateway's task engine, with the
+//! connection's earlier turns as context, and everything the run reports is
+//! streamed back as it happens. One task runs at a time per connection; a
+//! task keeps running if the socket closes, and `GET /v1/tasks/{id}` still
+//! reports it.
+//!
+//! Protocol:
+//! ```text
+//! Client -> Server: {"type":"message","content":"Hello"}
+//! Client -> Server: {"type":"message","content":"...","output_format":{"kind":"json_schema","schema":{...}}}
+//! Client -> Server: {"type":"cancel"}
+//! Server -> Client: {"type":"task_started","task_id":"..."}
+//! Server -> Client: {"type":"progress","task_id":"...","content":"..."}
+//! Server -> Client: {"type":"delta","task_id":"...","content":"Hi! "}
+//! Server -> Client: {"type":"reset","task_id":"..."}
+//! Server -> Client: {"type":"tool_call_start","task_id":"...","tool":"shell"}
+//! Server -> Client: {"type":"tool_call","task_id":"...","tool":"shell","duration_ms":12,"success":true}
+//! Server -> Client: {"type":"task_lifecycle","task_id":"...","stage":"round","labels":[...]}
+//! Server -> Client: {"type":"done","task_id":"...","status":"completed","response":"..."}
+//! Server -> Client: {"type":"error","message":"..."}
+//! ```
+//!
+//! Authenticate with `Authorization: Bearer <token>` or, from a browser,
+//! by offering the `bearer` subprotocol followed by the token
+//! (`new WebSocket(url, ["bearer", token])`). Tokens are not taken from the
+//! query string, which ends up in proxy and access logs. `?sender=<id>`
+//! names the user the tasks are recorded for.
+
+use super::task_api::{self, TaskSpec};
+use super::AppState;
+use crate::agent::output_format::OutputFormat;
+use crate::providers::ChatMessage;
+use crate::security::pairing::PairingGuard;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Query, State, WebSocketUpgrade,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, oneshot};
+
+/// Channel recorded on tasks started over this socket.
+pub const WS_CHANNEL: &str = "ws";
+
+/// Subprotocol a browser offers ahead of its token, since it cannot set
+/// headers on a WebSocket request.
+const BEARER_PROTOCOL: &str = "bearer";
+
+#[derive(Deserialize)]
+pub struct WsTaskQuery {
+    pub sender: Option<String>,
+}
+
+/// The task running for a connection.
+struct RunningTask {
+    task_id: String,
+    request: String,
+    events: broadcast::Receiver<Value>,
+}
+
+type WsSink = SplitSink<WebSocket, Message>;
+
+/// GET /ws/tasks — WebSocket upgrade for streamed task runs
+pub async fn handle_ws_tasks(
+    State(state): State<AppState>,
+    Query(params): Query<WsTaskQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    if !is_authorized(&state.pairing, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "Unauthorized — send Authorization: Bearer <token>, or offer the subprotocols \"bearer\" and <token>",
+        )
+            .into_response();
+    }
+
+    let sender = params
+        .sender
+        .as_deref()
+        .map(str::trim)
+        .filter(|sender| !sender.is_empty())
+        .unwrap_or(WS_CHANNEL)
+        .to_string();
+    ws.protocols([BEARER_PROTOCOL])
+        .on_upgrade(move |socket| handle_socket(socket, state, sender))
+        .into_response()
+}
+
+/// Token from `Authorization: Bearer`, or the subprotocol after `bearer`.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let header_value = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(token) =
+        header_value(header::AUTHORIZATION).and_then(|auth| auth.strip_prefix("Bearer "))
+    {
+        return Some(token.trim());
+    }
+    let mut protocols = header_value(header::SEC_WEBSOCKET_PROTOCOL)?
+        .split(',')
+        .map(str::trim);
+    protocols.find(|protocol| *protocol == BEARER_PROTOCOL)?;
+    protocols.next()
+}
+
+fn is_authorized(pairing: &PairingGuard, headers: &HeaderMap) -> bool {
+    !pairing.require_pairing() || pairing.is_authenticated(bearer_token(headers).unwrap_or(""))
+}
+
+fn error_frame(message: impl Into<String>) -> Value {
+    json!({ "type": "error", "message": message.into() })
+}
+
+async fn send_frame(sink: &mut WsSink, frame: &Value) -> bool {
+    sink.send(Message::Text(frame.to_string().into()))
+        .await
+        .is_ok()
+}
+
+/// Next event of the running task, `None` once its stream has ended. Never
+/// resolves while no task is running.
+async fn next_event(running: &mut Option<RunningTask>) -> Option<Value> {
+    let Some(task) = running.as_mut() else {
+        return std::future::pending().await;
+    };
+    loop {
+        match task.events.recv().await {
+            Ok(event) => return Some(event),
+            Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Frame for an event of `task`, tagged with its id. A final answer also
+/// becomes the connection's next context turns.
+fn task_frame(task: &RunningTask, turns: &mut Vec<ChatMessage>, mut event: Value) -> Value {
+    if event["type"] == "done" {
+        if let Some(response) = event["response"].as_str() {
+            turns.push(ChatMessage::user(&task.request));
+            turns.push(ChatMessage::assistant(response));
+        }
+    }
+    if let Some(fields) = event.as_object_mut() {
+        fields.insert("task_id".into(), json!(task.task_id));
+    }
+    event
+}
+
+/// Act on a client frame; returns the frame to answer with, if any.
+async fn handle_frame(
+    state: &AppState,
+    sender: &str,
+    turns: &[ChatMessage],
+    running: &mut Option<RunningTask>,
+    text: &str,
+) -> Option<Value> {
+    let Ok(frame) = serde_json::from_str::<Value>(text) else {
+        return Some(error_frame("Invalid JSON"));
+    };
+    match frame["type"].as_str().unwrap_or("") {
+        "message" => {
+            if running.is_some() {
+                return Some(error_frame(
+                    "A task is already running; wait for it to finish or send {\"type\":\"cancel\"}",
+                ));
+            }
+            let content = frame["content"].as_str().map(str::trim).unwrap_or("");
+            if content.is_empty() {
+                return Some(error_frame("Message content must not be empty"));
+            }
+            let output_format = match frame.get("output_format") {
+                None | Some(Value::Null) => None,
+                Some(value) => match serde_json::from_value::<OutputFormat>(value.clone()) {
+                    Ok(format) => Some(format),
+                    Err(e) => return Some(error_frame(format!("Invalid output_format: {e}"))),
+                },
+            };
+            let Some(engine) = state.task_engine.clone() else {
+                return Some(error_frame("Task engine is not enabled"));
+            };
+
+            let spec = TaskSpec {
+                channel: WS_CHANNEL,
+                sender: sender.to_string(),
+                request: content.to_string(),
+                output_format,
+                prior_turns: turns.to_vec(),
+                allowed_tools: None,
+            };
+            let (started_tx, started_rx) = oneshot::channel();
+            tokio::spawn(task_api::run_task(state.clone(), engine, spec, started_tx));
+            match started_rx.await {
+                Ok(Ok((task_id, events))) => {
+                    *running = Some(RunningTask {
+                        task_id: task_id.clone(),
+                        request: content.to_string(),
+                        events,
+                    });
+                    Some(json!({ "type": "task_started", "task_id": task_id }))
+                }
+                Ok(Err(e)) => Some(error_frame(format!("Failed to create task: {e}"))),
+                Err(_) => Some(error_frame("Task runner stopped before the task started")),
+            }
+        }
+        "cancel" => {
+            let (Some(task), Some(engine)) = (running.as_ref(), state.task_engine.as_ref()) else {
+                return Some(error_frame("No task is running"));
+            };
+            // The run reports the cancellation on its stream.
+            match engine.cancel_task(&task.task_id) {
+                Ok(_) => None,
+                Err(e) => Some(error_frame(format!("Failed to cancel task: {e}"))),
+            }
+        }
+        other => Some(error_frame(format!("Unknown frame type '{other}'"))),
+    }
+}
+
+async fn handle_socket(socket: WebSocket, state: AppState, sender: String) {
+    let (mut sink, mut source) = socket.split();
+    let mut turns: Vec<ChatMessage> = Vec::new();
+    let mut running: Option<RunningTask> = None;
+
+    loop {
+        tokio::select! {
+            frame = source.next() => {
+                let text = match frame {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                let reply = handle_frame(&state, &sender, &turns, &mut running, &text).await;
+                if let Some(reply) = reply {
+                    if !send_frame(&mut sink, &reply).await {
+                        break;
+                    }
+                }
+            }
+            event = next_event(&mut running) => {
+                let Some(event) = event else {
+                    running = None;
+                    continue;
+                };
+                let Some(task) = running.as_ref() else {
+                    continue;
+                };
+                let frame = task_frame(task, &mut turns, event);
+                if !send_frame(&mut sink, &frame).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn next_event_waits_without_a_task_and_ends_with_its_stream() {
+        let mut running = None;
+        let idle = tokio::time::timeout(Duration::from_millis(20), next_event(&mut running)).await;
+        assert!(idle.is_err());
+
+        let (tx, events) = broadcast::channel(4);
+        let mut running = Some(RunningTask {
+            task_id: "task-1".into(),
+            request: "hello".into(),
+            events,
+        });
+        tx.send(json!({ "type": "delta", "content": "Hi" }))
+            .unwrap();
+        drop(tx);
+        assert_eq!(next_event(&mut running).await.unwrap()["content"], "Hi");
+        assert!(next_event(&mut running).await.is_none());
+    }
+
+    #[test]
+    fn auth_takes_a_bearer_header_or_subprotocol_but_not_the_query() {
+        let pairing = PairingGuard::new(true, &["zc_secret".to_string()]);
+        let headers = |pairs: &[(header::HeaderName, &str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.insert(name.clone(), value.parse().unwrap());
+            }
+            headers
+        };
+
+        assert!(is_authorized(
+            &pairing,
+            &headers(&[(header::AUTHORIZATION, "Bearer zc_secret")])
+        ));
+        assert!(is_authorized(
+            &pairing,
+            &headers(&[(header::SEC_WEBSOCKET_PROTOCOL, "bearer, zc_secret")])
+        ));
+        assert!(!is_authorized(&pairing, &headers(&[])));
+        assert!(!is_authorized(
+            &pairing,
+            &headers(&[(header::AUTHORIZATION, "Bearer zc_wrong")])
+        ));
+        assert!(!is_authorized(
+            &pairing,
+            &headers(&[(header::SEC_WEBSOCKET_PROTOCOL, "zc_secret")])
+        ));
+        assert!(!is_authorized(
+            &pairing,
+            &headers(&[(header::AUTHORIZATION, "Basic zc_secret")])
+        ));
+
+        let open = PairingGuard::new(false, &[]);
+        assert!(is_authorized(&open, &headers(&[])));
+    }
+
+    #[test]
+    fn task_frames_are_tagged_and_only_answers_become_turns() {
+        let (_tx, events) = broadcast::channel(4);
+        let task = RunningTask {
+            task_id: "task-7".into(),
+            request: "deploy".into(),
+            events,
+        };
+        let mut turns = Vec::new();
+
+        let tool = task_frame(
+            &task,
+            &mut turns,
+            json!({ "type": "tool_call", "tool": "shell", "success": true }),
+        );
+        assert_eq!(tool["task_id"], "task-7");
+        assert_eq!(tool["tool"], "shell");
+        let stage = task_frame(
+            &task,
+            &mut turns,
+            json!({ "type": "task_lifecycle", "stage": "round", "labels": [] }),
+        );
+        assert_eq!(stage["task_id"], "task-7");
+        assert!(turns.is_empty());
+
+        let done = task_frame(
+            &task,
+            &mut turns,
+            json!({ "type": "done", "status": "completed", "response": "Deployed" }),
+        );
+        assert_eq!(done["task_id"], "task-7");
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].content, "deploy");
+        assert_eq!(turns[1].content, "Deployed");
+    }
+}
@@ -116,6 +116,16 @@ impl HookRunner {
         join_all(futs).await;
     }
 
+    /// Pinned instructions contributed by all handlers, in priority order.
+    pub async fn collect_pinned_instructions(&self, channel: &str, sender: &str) -> Vec<String> {
+        let futs: Vec<_> = self
+            .handlers
+            .iter()
+            .map(|h| h.pinned_instructions(channel, sender))
+            .collect();
+        join_all(futs).await.into_iter().flatten().collect()
+    }
+
     // ---------------------------------------------------------------
     // Modifying dispatchers (sequential by priority, short-circuit on Cancel)
     // ---------------------------------------------------------------
@@ -39,6 +39,11 @@ pub trait HookHandler: Send + Sync {
     async fn on_message_sent(&self, _channel: &str, _recipient: &str, _content: &str) {}
     async fn on_heartbeat_tick(&self) {}
 
+    /// Instructions pinned for this sender in addition to their `/pin`s.
+    async fn pinned_instructions(&self, _channel: &str, _sender: &str) -> Vec<String> {
+        Vec::new()
+    }
+
     // --- Modifying hooks (sequential by priority, can cancel) ---
     async fn before_model_resolve(
         &self,
@@ -804,6 +804,10 @@ mod tests {
             draft_update_interval_ms: 1000,
             interrupt_on_new_message: false,
             mention_only: false,
+            receive_mode: crate::config::TelegramReceiveMode::Polling,
+            webhook_url: None,
+            port: None,
+            webhook_secret: None,
         });
         let entries = all_integrations();
         let tg = entries.iter().find(|e| e.name == "Telegram").unwrap();
@@ -329,6 +329,42 @@ pub enum MemoryCommands {
     },
 }
 
+/// Task-run inspection and cold-storage subcommands
+#[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TaskCommands {
+    /// Print a task run with its events, artifacts, and notes as JSON
+    Show {
+        /// Task run ID
+        id: String,
+        /// Read the task from cold storage (`state/archive/`)
+        #[arg(long)]
+        archived: bool,
+    },
+    /// Attach an operator note to a task run (shown by `task show`)
+    Note {
+        /// Task run ID
+        id: String,
+        /// Note text
+        text: String,
+        /// Note author (defaults to `$USER`)
+        #[arg(long)]
+        author: Option<String>,
+    },
+    /// Restore the files a task run wrote (needs `autonomy.task_write_backups`)
+    Rollback {
+        /// Task run ID
+        id: String,
+    },
+    /// List blocked task runs with why they are blocked and what unblocks them
+    Blocked,
+    /// Move old finished task runs out of the database into cold storage
+    Archive {
+        /// Age threshold in days (defaults to `autonomy.task_cold_storage_after_days`)
+        #[arg(long)]
+        older_than_days: Option<u64>,
+    },
+}
+
 /// Integration subcommands
 #[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum IntegrationCommands {
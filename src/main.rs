@@ -88,7 +88,7 @@ use config::Config;
 // Re-export so binary modules can use crate::<CommandEnum> while keeping a single source of truth.
 pub use zeroclaw::{
     ChannelCommands, CronCommands, HardwareCommands, IntegrationCommands, MigrateCommands,
-    PeripheralCommands, ServiceCommands, SkillCommands,
+    PeripheralCommands, ServiceCommands, SkillCommands, TaskCommands,
 };
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
@@ -424,6 +424,25 @@ Examples:
         memory_command: MemoryCommands,
     },
 
+    /// Inspect and annotate task runs, and move old ones to cold storage
+    #[command(long_about = "\
+Inspect task-engine runs.
+
+Print a task run with its events, artifacts, and operator notes, \
+including runs already moved out of the database into compressed \
+archives under state/archive/. Notes record triage findings for \
+the rest of the team.
+
+Examples:
+  zeroclaw task show <id>
+  zeroclaw task show <id> --archived
+  zeroclaw task note <id> \"Provider outage, safe to rerun\" --author alice
+  zeroclaw task archive --older-than-days 90")]
+    Task {
+        #[command(subcommand)]
+        task_command: TaskCommands,
+    },
+
     /// Manage configuration
     #[command(long_about = "\
 Manage ZeroClaw configuration.
@@ -734,6 +753,7 @@ async fn main() -> Result<()> {
     let mut config = Config::load_or_init().await?;
     config.apply_env_overrides();
     observability::runtime_trace::init_from_config(&config.observability, &config.workspace_dir);
+    agent::tool_limits::init_from_config(&config.agent);
     if config.security.otp.enabled {
         let config_dir = config
             .config_path
@@ -982,6 +1002,10 @@ async fn main() -> Result<()> {
             memory::cli::handle_command(memory_command, &config).await
         }
 
+        Commands::Task { task_command } => {
+            agent::task_archive::handle_command(task_command, &config)
+        }
+
         Commands::Auth { auth_command } => handle_auth_command(auth_command, &config).await,
 
         Commands::Hardware { hardware_command } => {
@@ -154,6 +154,89 @@ impl EmbeddingProvider for OpenAiEmbedding {
     }
 }
 
+// ── Ollama embedding provider (local models) ─────────────────
+
+/// Default endpoint of a local Ollama server.
+const OLLAMA_DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// Embeddings from a local Ollama server's `/api/embed` (e.g.
+/// `nomic-embed-text`, `all-minilm`, `bge-m3`). No API key, no text leaves
+/// the machine.
+pub struct OllamaEmbedding {
+    base_url: String,
+    model: String,
+    dims: usize,
+}
+
+impl OllamaEmbedding {
+    pub fn new(base_url: &str, model: &str, dims: usize) -> Self {
+        let base_url = base_url.trim().trim_end_matches('/');
+        Self {
+            base_url: base_url
+                .strip_suffix("/api")
+                .unwrap_or(base_url)
+                .to_string(),
+            model: model.to_string(),
+            dims,
+        }
+    }
+
+    fn http_client(&self) -> reqwest::Client {
+        crate::config::build_runtime_proxy_client("memory.embeddings")
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct OllamaEmbedResponse {
+    #[serde(default)]
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbedding {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dims
+    }
+
+    async fn embed(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = serde_json::json!({
+            "model": self.model,
+            "input": texts,
+        });
+
+        let resp = self
+            .http_client()
+            .post(format!("{}/api/embed", self.base_url))
+            .json(&body)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Ollama embedding API error {status}: {text}");
+        }
+
+        let parsed: OllamaEmbedResponse = resp.json().await?;
+        if parsed.embeddings.len() != texts.len() {
+            anyhow::bail!(
+                "Ollama returned {} embeddings for {} inputs",
+                parsed.embeddings.len(),
+                texts.len()
+            );
+        }
+        Ok(parsed.embeddings)
+    }
+}
+
 // ── Factory ──────────────────────────────────────────────────
 
 pub fn create_embedding_provider(
@@ -186,6 +269,11 @@ pub fn create_embedding_provider(
             let key = api_key.unwrap_or("");
             Box::new(OpenAiEmbedding::new(base_url, key, model, dims))
         }
+        "ollama" => Box::new(OllamaEmbedding::new(OLLAMA_DEFAULT_BASE_URL, model, dims)),
+        name if name.starts_with("ollama:") => {
+            let base_url = name.strip_prefix("ollama:").unwrap_or("");
+            Box::new(OllamaEmbedding::new(base_url, model, dims))
+        }
         _ => Box::new(NoopEmbedding),
     }
 }
@@ -264,6 +352,32 @@ mod tests {
         assert!(result.is_empty());
     }
 
+    #[test]
+    fn factory_ollama_defaults_to_local_server() {
+        let p = create_embedding_provider("ollama", None, "nomic-embed-text", 768);
+        assert_eq!(p.name(), "ollama");
+        assert_eq!(p.dimensions(), 768);
+
+        let remote = OllamaEmbedding::new("http://gpu-box:11434/api/", "all-minilm", 384);
+        assert_eq!(remote.base_url, "http://gpu-box:11434");
+        let local = OllamaEmbedding::new(OLLAMA_DEFAULT_BASE_URL, "all-minilm", 384);
+        assert_eq!(local.base_url, "http://localhost:11434");
+    }
+
+    #[test]
+    fn ollama_embed_response_parses_batch() {
+        let parsed: OllamaEmbedResponse =
+            serde_json::from_str(r#"{"model":"all-minilm","embeddings":[[0.1,0.2],[0.3,0.4]]}"#)
+                .unwrap();
+        assert_eq!(parsed.embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+    }
+
+    #[tokio::test]
+    async fn ollama_embed_empty_batch_skips_request() {
+        let p = OllamaEmbedding::new("http://127.0.0.1:9", "all-minilm", 384);
+        assert!(p.embed(&[]).await.unwrap().is_empty());
+    }
+
     #[test]
     fn factory_empty_string_returns_noop() {
         let p = create_embedding_provider("", None, "model", 1536);
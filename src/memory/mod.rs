@@ -185,6 +185,24 @@ pub fn create_memory_with_storage(
     create_memory_with_storage_and_routes(config, &[], storage_provider, workspace_dir, api_key)
 }
 
+/// The configured embedding provider for features outside the memory
+/// backend, such as duplicate-request detection. `None` when
+/// `embedding_provider` is `none` or unknown.
+pub fn create_embedder(
+    config: &MemoryConfig,
+    embedding_routes: &[EmbeddingRouteConfig],
+    api_key: Option<&str>,
+) -> Option<Arc<dyn embeddings::EmbeddingProvider>> {
+    let resolved = resolve_embedding_config(config, embedding_routes, api_key);
+    let embedder = embeddings::create_embedding_provider(
+        &resolved.provider,
+        resolved.api_key.as_deref(),
+        &resolved.model,
+        resolved.dimensions,
+    );
+    (embedder.dimensions() > 0).then(|| Arc::from(embedder))
+}
+
 /// Factory: create memory with optional storage-provider override and embedding routes.
 pub fn create_memory_with_storage_and_routes(
     config: &MemoryConfig,
@@ -481,6 +499,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn create_embedder_is_none_without_an_embedding_provider() {
+        assert!(create_embedder(&MemoryConfig::default(), &[], None).is_none());
+        let cfg = MemoryConfig {
+            embedding_provider: "ollama".into(),
+            embedding_model: "nomic-embed-text".into(),
+            embedding_dimensions: 768,
+            ..MemoryConfig::default()
+        };
+        let embedder = create_embedder(&cfg, &[], None).expect("embedder");
+        assert_eq!(embedder.name(), "ollama");
+        assert_eq!(embedder.dimensions(), 768);
+    }
+
     #[test]
     fn resolve_embedding_config_uses_base_config_when_model_is_not_hint() {
         let cfg = MemoryConfig {
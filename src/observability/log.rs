@@ -44,6 +44,32 @@ impl Observer for LogObserver {
             ObserverEvent::ChannelMessage { channel, direction } => {
                 info!(channel = %channel, direction = %direction, "channel.message");
             }
+            ObserverEvent::TaskLifecycle {
+                task_id,
+                channel,
+                stage,
+                labels,
+            } => {
+                info!(
+                    task_id = %task_id,
+                    channel = %channel,
+                    stage = %stage,
+                    labels = %labels.join(","),
+                    "task.lifecycle"
+                );
+            }
+            ObserverEvent::ProviderCircuit {
+                provider,
+                state,
+                consecutive_failures,
+            } => {
+                info!(
+                    provider = %provider,
+                    state = %state,
+                    consecutive_failures = consecutive_failures,
+                    "provider.circuit"
+                );
+            }
             ObserverEvent::HeartbeatTick => {
                 info!("heartbeat.tick");
             }
@@ -101,6 +127,26 @@ impl Observer for LogObserver {
             ObserverMetric::QueueDepth(d) => {
                 info!(depth = d, "metric.queue_depth");
             }
+            ObserverMetric::TaskQueueDepth(d) => {
+                info!(depth = d, "metric.task_queue_depth");
+            }
+            ObserverMetric::OldestQueuedTaskAge(age) => {
+                info!(age_secs = age.as_secs(), "metric.task_oldest_queued_age");
+            }
+            ObserverMetric::TaskStoreSize(bytes) => {
+                info!(bytes = bytes, "metric.task_store_size");
+            }
+            ObserverMetric::TaskArchiveBacklog(count) => {
+                info!(count = count, "metric.task_archive_backlog");
+            }
+            ObserverMetric::ProviderRateLimitWait { provider, wait } => {
+                let ms = u64::try_from(wait.as_millis()).unwrap_or(u64::MAX);
+                info!(
+                    provider = provider.as_str(),
+                    wait_ms = ms,
+                    "metric.provider_rate_limit_wait"
+                );
+            }
         }
     }
 
@@ -171,6 +217,12 @@ mod tests {
             channel: "telegram".into(),
             direction: "outbound".into(),
         });
+        obs.record_event(&ObserverEvent::TaskLifecycle {
+            task_id: "task-1".into(),
+            channel: "imessage".into(),
+            stage: "started".into(),
+            labels: vec!["task_type:search".into()],
+        });
         obs.record_event(&ObserverEvent::HeartbeatTick);
         obs.record_event(&ObserverEvent::Error {
             component: "provider".into(),
@@ -186,5 +238,13 @@ mod tests {
         obs.record_metric(&ObserverMetric::TokensUsed(u64::MAX));
         obs.record_metric(&ObserverMetric::ActiveSessions(1));
         obs.record_metric(&ObserverMetric::QueueDepth(999));
+        obs.record_metric(&ObserverMetric::TaskQueueDepth(3));
+        obs.record_metric(&ObserverMetric::OldestQueuedTaskAge(Duration::ZERO));
+        obs.record_metric(&ObserverMetric::TaskStoreSize(4096));
+        obs.record_metric(&ObserverMetric::TaskArchiveBacklog(0));
+        obs.record_metric(&ObserverMetric::ProviderRateLimitWait {
+            provider: "openai".into(),
+            wait: Duration::from_millis(250),
+        });
     }
 }
@@ -22,11 +22,17 @@ pub struct OtelObserver {
     tool_duration: Histogram<f64>,
     channel_messages: Counter<u64>,
     heartbeat_ticks: Counter<u64>,
+    task_events: Counter<u64>,
     errors: Counter<u64>,
     request_latency: Histogram<f64>,
     tokens_used: Counter<u64>,
     active_sessions: Gauge<u64>,
     queue_depth: Gauge<u64>,
+    task_queue_depth: Gauge<u64>,
+    task_oldest_queued_age: Gauge<f64>,
+    task_store_size: Gauge<u64>,
+    task_archive_backlog: Gauge<u64>,
+    provider_rate_limit_wait: Histogram<f64>,
 }
 
 impl OtelObserver {
@@ -126,6 +132,11 @@ impl OtelObserver {
             .with_description("Total heartbeat ticks")
             .build();
 
+        let task_events = meter
+            .u64_counter("zeroclaw.task.events")
+            .with_description("Total task lifecycle events by stage and category")
+            .build();
+
         let errors = meter
             .u64_counter("zeroclaw.errors")
             .with_description("Total errors by component")
@@ -152,6 +163,34 @@ impl OtelObserver {
             .with_description("Current message queue depth")
             .build();
 
+        let task_queue_depth = meter
+            .u64_gauge("zeroclaw.task.queue.depth")
+            .with_description("Task runs waiting in the queue")
+            .build();
+
+        let task_oldest_queued_age = meter
+            .f64_gauge("zeroclaw.task.queue.oldest_age")
+            .with_description("Age of the oldest queued task run")
+            .with_unit("s")
+            .build();
+
+        let task_store_size = meter
+            .u64_gauge("zeroclaw.task.store.size")
+            .with_description("Task-store database size")
+            .with_unit("By")
+            .build();
+
+        let task_archive_backlog = meter
+            .u64_gauge("zeroclaw.task.archive.backlog")
+            .with_description("Finished task runs due for cold storage")
+            .build();
+
+        let provider_rate_limit_wait = meter
+            .f64_histogram("zeroclaw.provider.rate_limit.wait")
+            .with_description("Time provider calls waited for their rate limit")
+            .with_unit("s")
+            .build();
+
         Ok(Self {
             tracer_provider,
             meter_provider: meter_provider_clone,
@@ -163,11 +202,17 @@ impl OtelObserver {
             tool_duration,
             channel_messages,
             heartbeat_ticks,
+            task_events,
             errors,
             request_latency,
             tokens_used,
             active_sessions,
             queue_depth,
+            task_queue_depth,
+            task_oldest_queued_age,
+            task_store_size,
+            task_archive_backlog,
+            provider_rate_limit_wait,
         })
     }
 }
@@ -316,6 +361,53 @@ impl Observer for OtelObserver {
                     ],
                 );
             }
+            ObserverEvent::TaskLifecycle {
+                task_id,
+                channel,
+                stage,
+                labels,
+            } => {
+                let mut span = tracer.build(
+                    opentelemetry::trace::SpanBuilder::from_name("task.lifecycle")
+                        .with_kind(SpanKind::Internal)
+                        .with_attributes(vec![
+                            KeyValue::new("task.id", task_id.clone()),
+                            KeyValue::new("task.channel", channel.clone()),
+                            KeyValue::new("task.stage", stage.clone()),
+                            KeyValue::new("task.labels", labels.join(",")),
+                        ]),
+                );
+                span.end();
+
+                let category = labels.first().cloned().unwrap_or_else(|| "none".into());
+                self.task_events.add(
+                    1,
+                    &[
+                        KeyValue::new("stage", stage.clone()),
+                        KeyValue::new("channel", channel.clone()),
+                        KeyValue::new("category", category),
+                    ],
+                );
+            }
+            ObserverEvent::ProviderCircuit {
+                provider,
+                state,
+                consecutive_failures,
+            } => {
+                let mut span = tracer.build(
+                    opentelemetry::trace::SpanBuilder::from_name("provider.circuit")
+                        .with_kind(SpanKind::Internal)
+                        .with_attributes(vec![
+                            KeyValue::new("provider", provider.clone()),
+                            KeyValue::new("circuit.state", state.clone()),
+                            KeyValue::new(
+                                "circuit.consecutive_failures",
+                                i64::from(*consecutive_failures),
+                            ),
+                        ]),
+                );
+                span.end();
+            }
             ObserverEvent::HeartbeatTick => {
                 self.heartbeat_ticks.add(1, &[]);
             }
@@ -352,6 +444,24 @@ impl Observer for OtelObserver {
             ObserverMetric::QueueDepth(d) => {
                 self.queue_depth.record(*d as u64, &[]);
             }
+            ObserverMetric::TaskQueueDepth(d) => {
+                self.task_queue_depth.record(*d, &[]);
+            }
+            ObserverMetric::OldestQueuedTaskAge(age) => {
+                self.task_oldest_queued_age.record(age.as_secs_f64(), &[]);
+            }
+            ObserverMetric::TaskStoreSize(bytes) => {
+                self.task_store_size.record(*bytes, &[]);
+            }
+            ObserverMetric::TaskArchiveBacklog(count) => {
+                self.task_archive_backlog.record(*count, &[]);
+            }
+            ObserverMetric::ProviderRateLimitWait { provider, wait } => {
+                self.provider_rate_limit_wait.record(
+                    wait.as_secs_f64(),
+                    &[KeyValue::new("provider", provider.clone())],
+                );
+            }
         }
     }
 
@@ -450,6 +560,12 @@ mod tests {
             channel: "telegram".into(),
             direction: "inbound".into(),
         });
+        obs.record_event(&ObserverEvent::TaskLifecycle {
+            task_id: "task-1".into(),
+            channel: "imessage".into(),
+            stage: "completed".into(),
+            labels: vec!["task_type:write_artifact".into()],
+        });
         obs.record_event(&ObserverEvent::HeartbeatTick);
         obs.record_event(&ObserverEvent::Error {
             component: "provider".into(),
@@ -465,6 +581,16 @@ mod tests {
         obs.record_metric(&ObserverMetric::TokensUsed(0));
         obs.record_metric(&ObserverMetric::ActiveSessions(3));
         obs.record_metric(&ObserverMetric::QueueDepth(42));
+        obs.record_metric(&ObserverMetric::TaskQueueDepth(7));
+        obs.record_metric(&ObserverMetric::OldestQueuedTaskAge(Duration::from_secs(
+            90,
+        )));
+        obs.record_metric(&ObserverMetric::TaskStoreSize(4096));
+        obs.record_metric(&ObserverMetric::TaskArchiveBacklog(12));
+        obs.record_metric(&ObserverMetric::ProviderRateLimitWait {
+            provider: "openai".into(),
+            wait: Duration::from_secs(1),
+        });
     }
 
     #[test]
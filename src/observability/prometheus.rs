@@ -1,6 +1,7 @@
 use super::traits::{Observer, ObserverEvent, ObserverMetric};
 use prometheus::{
-    Encoder, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounterVec, Registry, TextEncoder,
+    Encoder, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, Registry, TextEncoder,
 };
 
 /// Prometheus-backed observer — exposes metrics for scraping via `/metrics`.
@@ -15,17 +16,24 @@ pub struct PrometheusObserver {
     tool_calls: IntCounterVec,
     channel_messages: IntCounterVec,
     heartbeat_ticks: prometheus::IntCounter,
+    task_events: IntCounterVec,
     errors: IntCounterVec,
 
     // Histograms
     agent_duration: HistogramVec,
     tool_duration: HistogramVec,
     request_latency: Histogram,
+    provider_rate_limit_wait: HistogramVec,
 
     // Gauges
     tokens_used: prometheus::IntGauge,
     active_sessions: GaugeVec,
     queue_depth: GaugeVec,
+    task_queue_depth: IntGauge,
+    task_oldest_queued_age: IntGauge,
+    task_store_size: IntGauge,
+    task_archive_backlog: IntGauge,
+    provider_circuit_state: IntGaugeVec,
 }
 
 impl PrometheusObserver {
@@ -75,6 +83,15 @@ impl PrometheusObserver {
             prometheus::IntCounter::new("zeroclaw_heartbeat_ticks_total", "Total heartbeat ticks")
                 .expect("valid metric");
 
+        let task_events = IntCounterVec::new(
+            prometheus::Opts::new(
+                "zeroclaw_task_events_total",
+                "Total task lifecycle events by stage and category",
+            ),
+            &["stage", "channel", "category"],
+        )
+        .expect("valid metric");
+
         let errors = IntCounterVec::new(
             prometheus::Opts::new("zeroclaw_errors_total", "Total errors by component"),
             &["component"],
@@ -110,6 +127,16 @@ impl PrometheusObserver {
         )
         .expect("valid metric");
 
+        let provider_rate_limit_wait = HistogramVec::new(
+            HistogramOpts::new(
+                "zeroclaw_provider_rate_limit_wait_seconds",
+                "Time provider calls waited for their rate limit in seconds",
+            )
+            .buckets(vec![0.0, 0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0]),
+            &["provider"],
+        )
+        .expect("valid metric");
+
         let tokens_used = prometheus::IntGauge::new(
             "zeroclaw_tokens_used_last",
             "Tokens used in the last request",
@@ -128,6 +155,39 @@ impl PrometheusObserver {
         )
         .expect("valid metric");
 
+        let task_queue_depth = IntGauge::new(
+            "zeroclaw_task_queue_depth",
+            "Task runs waiting in the queue",
+        )
+        .expect("valid metric");
+
+        let task_oldest_queued_age = IntGauge::new(
+            "zeroclaw_task_oldest_queued_age_seconds",
+            "Age of the oldest queued task run in seconds",
+        )
+        .expect("valid metric");
+
+        let task_store_size = IntGauge::new(
+            "zeroclaw_task_store_size_bytes",
+            "Task-store database size in bytes",
+        )
+        .expect("valid metric");
+
+        let task_archive_backlog = IntGauge::new(
+            "zeroclaw_task_archive_backlog",
+            "Finished task runs due for cold storage",
+        )
+        .expect("valid metric");
+
+        let provider_circuit_state = IntGaugeVec::new(
+            prometheus::Opts::new(
+                "zeroclaw_provider_circuit_state",
+                "Provider circuit breaker state (0 closed, 1 half-open, 2 open)",
+            ),
+            &["provider"],
+        )
+        .expect("valid metric");
+
         // Register all metrics
         registry.register(Box::new(agent_starts.clone())).ok();
         registry.register(Box::new(llm_requests.clone())).ok();
@@ -138,13 +198,28 @@ impl PrometheusObserver {
         registry.register(Box::new(tool_calls.clone())).ok();
         registry.register(Box::new(channel_messages.clone())).ok();
         registry.register(Box::new(heartbeat_ticks.clone())).ok();
+        registry.register(Box::new(task_events.clone())).ok();
         registry.register(Box::new(errors.clone())).ok();
         registry.register(Box::new(agent_duration.clone())).ok();
         registry.register(Box::new(tool_duration.clone())).ok();
         registry.register(Box::new(request_latency.clone())).ok();
+        registry
+            .register(Box::new(provider_rate_limit_wait.clone()))
+            .ok();
         registry.register(Box::new(tokens_used.clone())).ok();
         registry.register(Box::new(active_sessions.clone())).ok();
         registry.register(Box::new(queue_depth.clone())).ok();
+        registry.register(Box::new(task_queue_depth.clone())).ok();
+        registry
+            .register(Box::new(task_oldest_queued_age.clone()))
+            .ok();
+        registry.register(Box::new(task_store_size.clone())).ok();
+        registry
+            .register(Box::new(task_archive_backlog.clone()))
+            .ok();
+        registry
+            .register(Box::new(provider_circuit_state.clone()))
+            .ok();
 
         Self {
             registry,
@@ -155,13 +230,20 @@ impl PrometheusObserver {
             tool_calls,
             channel_messages,
             heartbeat_ticks,
+            task_events,
             errors,
             agent_duration,
             tool_duration,
             request_latency,
+            provider_rate_limit_wait,
             tokens_used,
             active_sessions,
             queue_depth,
+            task_queue_depth,
+            task_oldest_queued_age,
+            task_store_size,
+            task_archive_backlog,
+            provider_circuit_state,
         }
     }
 
@@ -242,6 +324,31 @@ impl Observer for PrometheusObserver {
                     .with_label_values(&[channel, direction])
                     .inc();
             }
+            ObserverEvent::TaskLifecycle {
+                channel,
+                stage,
+                labels,
+                ..
+            } => {
+                // Only the leading label is used as a metric dimension to keep
+                // series cardinality bounded; the full set goes to logs/traces.
+                let category = labels.first().map_or("none", String::as_str);
+                self.task_events
+                    .with_label_values(&[stage.as_str(), channel.as_str(), category])
+                    .inc();
+            }
+            ObserverEvent::ProviderCircuit {
+                provider, state, ..
+            } => {
+                let value = match state.as_str() {
+                    "open" => 2,
+                    "half_open" => 1,
+                    _ => 0,
+                };
+                self.provider_circuit_state
+                    .with_label_values(&[provider.as_str()])
+                    .set(value);
+            }
             ObserverEvent::HeartbeatTick => {
                 self.heartbeat_ticks.inc();
             }
@@ -272,6 +379,27 @@ impl Observer for PrometheusObserver {
                     .with_label_values(&[] as &[&str])
                     .set(*d as f64);
             }
+            ObserverMetric::TaskQueueDepth(d) => {
+                self.task_queue_depth
+                    .set(i64::try_from(*d).unwrap_or(i64::MAX));
+            }
+            ObserverMetric::OldestQueuedTaskAge(age) => {
+                self.task_oldest_queued_age
+                    .set(i64::try_from(age.as_secs()).unwrap_or(i64::MAX));
+            }
+            ObserverMetric::TaskStoreSize(bytes) => {
+                self.task_store_size
+                    .set(i64::try_from(*bytes).unwrap_or(i64::MAX));
+            }
+            ObserverMetric::TaskArchiveBacklog(count) => {
+                self.task_archive_backlog
+                    .set(i64::try_from(*count).unwrap_or(i64::MAX));
+            }
+            ObserverMetric::ProviderRateLimitWait { provider, wait } => {
+                self.provider_rate_limit_wait
+                    .with_label_values(&[provider.as_str()])
+                    .observe(wait.as_secs_f64());
+            }
         }
     }
 
@@ -344,6 +472,12 @@ mod tests {
         obs.record_metric(&ObserverMetric::TokensUsed(0));
         obs.record_metric(&ObserverMetric::ActiveSessions(3));
         obs.record_metric(&ObserverMetric::QueueDepth(42));
+        obs.record_metric(&ObserverMetric::TaskQueueDepth(7));
+        obs.record_metric(&ObserverMetric::OldestQueuedTaskAge(Duration::from_secs(
+            90,
+        )));
+        obs.record_metric(&ObserverMetric::TaskStoreSize(4096));
+        obs.record_metric(&ObserverMetric::TaskArchiveBacklog(12));
     }
 
     #[test]
@@ -436,6 +570,36 @@ mod tests {
         assert!(output.contains("zeroclaw_tokens_used_last 200"));
     }
 
+    #[test]
+    fn task_store_gauges_are_exported() {
+        let obs = PrometheusObserver::new();
+        obs.record_metric(&ObserverMetric::TaskQueueDepth(3));
+        obs.record_metric(&ObserverMetric::OldestQueuedTaskAge(Duration::from_secs(
+            125,
+        )));
+        obs.record_metric(&ObserverMetric::TaskStoreSize(1_048_576));
+        obs.record_metric(&ObserverMetric::TaskArchiveBacklog(4));
+
+        let output = obs.encode();
+        assert!(output.contains("zeroclaw_task_queue_depth 3"));
+        assert!(output.contains("zeroclaw_task_oldest_queued_age_seconds 125"));
+        assert!(output.contains("zeroclaw_task_store_size_bytes 1048576"));
+        assert!(output.contains("zeroclaw_task_archive_backlog 4"));
+    }
+
+    #[test]
+    fn provider_rate_limit_waits_are_exported_per_provider() {
+        let obs = PrometheusObserver::new();
+        obs.record_metric(&ObserverMetric::ProviderRateLimitWait {
+            provider: "openai".into(),
+            wait: Duration::from_secs(2),
+        });
+
+        let output = obs.encode();
+        assert!(output
+            .contains(r#"zeroclaw_provider_rate_limit_wait_seconds_count{provider="openai"} 1"#));
+    }
+
     #[test]
     fn llm_response_tracks_request_count_and_tokens() {
         let obs = PrometheusObserver::new();
@@ -493,4 +657,51 @@ mod tests {
         assert!(!output.contains("zeroclaw_tokens_input_total{"));
         assert!(!output.contains("zeroclaw_tokens_output_total{"));
     }
+
+    #[test]
+    fn task_lifecycle_tracks_stage_and_leading_label() {
+        let obs = PrometheusObserver::new();
+        obs.record_event(&ObserverEvent::TaskLifecycle {
+            task_id: "task-1".into(),
+            channel: "imessage".into(),
+            stage: "completed".into(),
+            labels: vec!["task_type:search".into(), "hint:fast".into()],
+        });
+        obs.record_event(&ObserverEvent::TaskLifecycle {
+            task_id: "task-2".into(),
+            channel: "imessage".into(),
+            stage: "completed".into(),
+            labels: Vec::new(),
+        });
+
+        let output = obs.encode();
+        assert!(output.contains(
+            r#"zeroclaw_task_events_total{category="task_type:search",channel="imessage",stage="completed"} 1"#
+        ));
+        assert!(output.contains(
+            r#"zeroclaw_task_events_total{category="none",channel="imessage",stage="completed"} 1"#
+        ));
+    }
+
+    #[test]
+    fn provider_circuit_state_tracks_latest_transition() {
+        let obs = PrometheusObserver::new();
+        obs.record_event(&ObserverEvent::ProviderCircuit {
+            provider: "openai".into(),
+            state: "open".into(),
+            consecutive_failures: 5,
+        });
+        assert!(obs
+            .encode()
+            .contains(r#"zeroclaw_provider_circuit_state{provider="openai"} 2"#));
+
+        obs.record_event(&ObserverEvent::ProviderCircuit {
+            provider: "openai".into(),
+            state: "closed".into(),
+            consecutive_failures: 0,
+        });
+        assert!(obs
+            .encode()
+            .contains(r#"zeroclaw_provider_circuit_state{provider="openai"} 0"#));
+    }
 }
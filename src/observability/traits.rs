@@ -56,6 +56,25 @@ pub enum ObserverEvent {
         /// `"inbound"` or `"outbound"`.
         direction: String,
     },
+    /// A task-engine run moved to a new lifecycle stage.
+    ///
+    /// `labels` carry task tags (classifier hints, compiled contract type) so
+    /// monitoring backends can slice task metrics by category.
+    TaskLifecycle {
+        task_id: String,
+        /// Channel that originated the task (e.g., `"imessage"`).
+        channel: String,
+        /// Lifecycle stage (e.g., `"started"`, `"round"`, `"completed"`).
+        stage: String,
+        labels: Vec<String>,
+    },
+    /// A provider's circuit breaker changed state.
+    ProviderCircuit {
+        provider: String,
+        /// `"open"`, `"half_open"`, or `"closed"`.
+        state: String,
+        consecutive_failures: u32,
+    },
     /// Periodic heartbeat tick from the runtime keep-alive loop.
     HeartbeatTick,
     /// An error occurred in a named component.
@@ -81,6 +100,16 @@ pub enum ObserverMetric {
     ActiveSessions(u64),
     /// Current depth of the inbound message queue.
     QueueDepth(u64),
+    /// Task runs waiting in the `queued` state.
+    TaskQueueDepth(u64),
+    /// Age of the oldest queued task run.
+    OldestQueuedTaskAge(Duration),
+    /// Size of the task-store database on disk, in bytes.
+    TaskStoreSize(u64),
+    /// Finished task runs due for cold storage but still in the task store.
+    TaskArchiveBacklog(u64),
+    /// Time a provider call waited for its per-provider rate limit.
+    ProviderRateLimitWait { provider: String, wait: Duration },
 }
 
 /// Core observability trait for recording agent runtime telemetry.
@@ -1,6 +1,7 @@
 use crate::config::schema::{
     default_nostr_relays, DingTalkConfig, IrcConfig, LarkReceiveMode, LinqConfig,
-    NextcloudTalkConfig, NostrConfig, QQConfig, SignalConfig, StreamMode, WhatsAppConfig,
+    NextcloudTalkConfig, NostrConfig, QQConfig, SignalConfig, StreamMode, TelegramReceiveMode,
+    WhatsAppConfig,
 };
 use crate::config::{
     AutonomyConfig, BrowserConfig, ChannelsConfig, ComposioConfig, Config, DiscordConfig,
@@ -159,6 +160,11 @@ pub async fn run_wizard(force: bool) -> Result<Config> {
         http_request: crate::config::HttpRequestConfig::default(),
         multimodal: crate::config::MultimodalConfig::default(),
         web_search: crate::config::WebSearchConfig::default(),
+        web_fetch: crate::config::WebFetchConfig::default(),
+        code_run: crate::config::CodeRunConfig::default(),
+        sql_query: crate::config::SqlQueryConfig::default(),
+        macro_tools: std::collections::HashMap::new(),
+        webhook_tasks: std::collections::HashMap::new(),
         proxy: crate::config::ProxyConfig::default(),
         identity: crate::config::IdentityConfig::default(),
         cost: crate::config::CostConfig::default(),
@@ -383,6 +389,7 @@ fn memory_config_defaults_for_backend(backend: &str) -> MemoryConfig {
         response_cache_enabled: false,
         response_cache_ttl_minutes: 60,
         response_cache_max_entries: 5_000,
+        response_cache_any_temperature: false,
         snapshot_enabled: false,
         snapshot_on_hygiene: false,
         auto_hydrate: true,
@@ -507,6 +514,11 @@ async fn run_quick_setup_with_home(
         http_request: crate::config::HttpRequestConfig::default(),
         multimodal: crate::config::MultimodalConfig::default(),
         web_search: crate::config::WebSearchConfig::default(),
+        web_fetch: crate::config::WebFetchConfig::default(),
+        code_run: crate::config::CodeRunConfig::default(),
+        sql_query: crate::config::SqlQueryConfig::default(),
+        macro_tools: std::collections::HashMap::new(),
+        webhook_tasks: std::collections::HashMap::new(),
         proxy: crate::config::ProxyConfig::default(),
         identity: crate::config::IdentityConfig::default(),
         cost: crate::config::CostConfig::default(),
@@ -3407,6 +3419,10 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     draft_update_interval_ms: 1000,
                     interrupt_on_new_message: false,
                     mention_only: false,
+                    receive_mode: TelegramReceiveMode::Polling,
+                    webhook_url: None,
+                    port: None,
+                    webhook_secret: None,
                 });
             }
             ChannelMenuChoice::Discord => {
@@ -3506,6 +3522,9 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     allowed_users,
                     listen_to_bots: false,
                     mention_only: false,
+                    channel_ids: vec![],
+                    stream_mode: StreamMode::default(),
+                    draft_update_interval_ms: 1000,
                 });
             }
             ChannelMenuChoice::Slack => {
@@ -1,15 +1,23 @@
+use crate::multimodal;
+use crate::providers::params::{request_body, Dialect};
 use crate::providers::traits::{
-    ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
-    Provider, TokenUsage, ToolCall as ProviderToolCall,
+    self, ChatDelta, ChatMessage, ChatRequest as ProviderChatRequest,
+    ChatResponse as ProviderChatResponse, Provider, ProviderCapabilities, StreamChunk, StreamError,
+    StreamOptions, StreamResult, TokenUsage, ToolCall as ProviderToolCall,
 };
 use crate::tools::ToolSpec;
 use async_trait::async_trait;
+use futures_util::{stream, StreamExt};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+/// Output tokens requested per response unless `runtime.max_tokens` is set.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
 pub struct AnthropicProvider {
     credential: Option<String>,
     base_url: String,
+    max_tokens: u32,
 }
 
 #[derive(Debug, Serialize)]
@@ -51,6 +59,8 @@ struct NativeChatRequest<'a> {
     temperature: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<NativeToolSpec<'a>>>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -83,6 +93,39 @@ enum NativeContentOut {
         #[serde(skip_serializing_if = "Option::is_none")]
         cache_control: Option<CacheControl>,
     },
+    #[serde(rename = "image")]
+    Image { source: ImageSource },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ImageSource {
+    Base64 { media_type: String, data: String },
+    Url { url: String },
+}
+
+impl ImageSource {
+    /// `data:<mime>;base64,<payload>` URIs are sent inline; anything else is
+    /// passed as a URL for the API to fetch.
+    fn from_reference(reference: &str) -> Self {
+        let inline = reference
+            .strip_prefix("data:")
+            .and_then(|rest| rest.split_once(','))
+            .and_then(|(header, data)| {
+                header
+                    .strip_suffix(";base64")
+                    .map(|media_type| (media_type, data))
+            });
+        match inline {
+            Some((media_type, data)) => Self::Base64 {
+                media_type: media_type.to_string(),
+                data: data.to_string(),
+            },
+            None => Self::Url {
+                url: reference.to_string(),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -130,6 +173,8 @@ struct NativeChatResponse {
     content: Vec<NativeContentIn>,
     #[serde(default)]
     usage: Option<AnthropicUsage>,
+    #[serde(default)]
+    stop_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -170,9 +215,19 @@ impl AnthropicProvider {
                 .filter(|k| !k.is_empty())
                 .map(ToString::to_string),
             base_url,
+            max_tokens: DEFAULT_MAX_TOKENS,
         }
     }
 
+    /// Cap each response at `max_tokens` output tokens; `None` or `0` keeps
+    /// the default of 4096.
+    pub fn with_max_tokens(mut self, max_tokens: Option<u32>) -> Self {
+        if let Some(max_tokens) = max_tokens.filter(|n| *n > 0) {
+            self.max_tokens = max_tokens;
+        }
+        self
+    }
+
     fn is_setup_token(token: &str) -> bool {
         token.starts_with("sk-ant-oat01-")
     }
@@ -210,7 +265,7 @@ impl AnthropicProvider {
                     | NativeContentOut::ToolResult { cache_control, .. } => {
                         *cache_control = Some(CacheControl::ephemeral());
                     }
-                    NativeContentOut::ToolUse { .. } => {}
+                    NativeContentOut::ToolUse { .. } | NativeContentOut::Image { .. } => {}
                 }
             }
         }
@@ -291,6 +346,33 @@ impl AnthropicProvider {
         })
     }
 
+    /// User content blocks: `[IMAGE:...]` markers become image blocks, placed
+    /// before the remaining text as the API recommends.
+    fn user_content(content: &str) -> Vec<NativeContentOut> {
+        let (cleaned_text, image_refs) = multimodal::parse_image_markers(content);
+        if image_refs.is_empty() {
+            return vec![NativeContentOut::Text {
+                text: content.to_string(),
+                cache_control: None,
+            }];
+        }
+
+        let mut blocks: Vec<NativeContentOut> = image_refs
+            .iter()
+            .map(|reference| NativeContentOut::Image {
+                source: ImageSource::from_reference(reference),
+            })
+            .collect();
+        let trimmed_text = cleaned_text.trim();
+        if !trimmed_text.is_empty() {
+            blocks.push(NativeContentOut::Text {
+                text: trimmed_text.to_string(),
+                cache_control: None,
+            });
+        }
+        blocks
+    }
+
     fn convert_messages(messages: &[ChatMessage]) -> (Option<SystemPrompt>, Vec<NativeMessage>) {
         let mut system_text = None;
         let mut native_messages = Vec::new();
@@ -334,10 +416,7 @@ impl AnthropicProvider {
                 _ => {
                     native_messages.push(NativeMessage {
                         role: "user".to_string(),
-                        content: vec![NativeContentOut::Text {
-                            text: msg.content.clone(),
-                            cache_control: None,
-                        }],
+                        content: Self::user_content(&msg.content),
                     });
                 }
             }
@@ -372,6 +451,10 @@ impl AnthropicProvider {
         let mut text_parts = Vec::new();
         let mut tool_calls = Vec::new();
 
+        if response.stop_reason.as_deref() == Some("max_tokens") {
+            tracing::warn!("Anthropic response stopped at max_tokens; the reply is truncated");
+        }
+
         let usage = response.usage.map(|u| TokenUsage {
             input_tokens: u.input_tokens,
             output_tokens: u.output_tokens,
@@ -413,12 +496,266 @@ impl AnthropicProvider {
             tool_calls,
             usage,
             reasoning_content: None,
+            finish_reason: response.stop_reason,
         }
     }
 
     fn http_client(&self) -> Client {
         crate::config::build_runtime_proxy_client_with_timeouts("provider.anthropic", 120, 10)
     }
+
+    /// Send a streaming Messages API request and forward its text deltas,
+    /// ending with the whole response: text, tool calls, usage, and stop
+    /// reason.
+    fn stream_messages(
+        &self,
+        system: Option<SystemPrompt>,
+        messages: Vec<NativeMessage>,
+        model: &str,
+        temperature: f64,
+        tools: Option<Vec<NativeToolSpec<'_>>>,
+    ) -> stream::BoxStream<'static, StreamResult<ChatDelta>> {
+        let Some(credential) = self.credential.as_ref() else {
+            return stream::once(async {
+                Err(StreamError::Provider(
+                    "Anthropic credentials not set".to_string(),
+                ))
+            })
+            .boxed();
+        };
+        let request = NativeChatRequest {
+            model: model.to_string(),
+            max_tokens: self.max_tokens,
+            system,
+            messages,
+            temperature,
+            tools,
+            stream: true,
+        };
+        let builder = self.apply_auth(
+            self.http_client()
+                .post(format!("{}/v1/messages", self.base_url))
+                .header("anthropic-version", "2023-06-01")
+                .header("content-type", "application/json")
+                .header("accept", "text/event-stream")
+                .json(&request_body(Dialect::Anthropic, &request)),
+            credential,
+        );
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<StreamResult<ChatDelta>>(100);
+        tokio::spawn(async move {
+            let response = match builder.send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    let _ = tx.send(Err(StreamError::Http(e))).await;
+                    return;
+                }
+            };
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                let _ = tx
+                    .send(Err(StreamError::Provider(format!(
+                        "{status}: {}",
+                        super::sanitize_api_error(&body)
+                    ))))
+                    .await;
+                return;
+            }
+
+            let mut streamed = StreamedResponse::default();
+            if !traits::forward_stream_lines(response.bytes_stream(), &tx, |line| {
+                streamed.push_line(line)
+            })
+            .await
+            {
+                return;
+            }
+            let _ = tx.send(Ok(ChatDelta::Done(streamed.finish()))).await;
+        });
+
+        stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|chunk| (chunk, rx))
+        })
+        .boxed()
+    }
+}
+
+/// One event of a streaming Messages API response (`stream: true`).
+#[derive(Debug, Deserialize)]
+struct StreamEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    /// Content block the event belongs to.
+    #[serde(default)]
+    index: Option<usize>,
+    /// `message_start`: the message so far, with the input token count.
+    #[serde(default)]
+    message: Option<StreamMessage>,
+    /// `content_block_start`: the block's type and, for `tool_use`, its id
+    /// and name.
+    #[serde(default)]
+    content_block: Option<NativeContentIn>,
+    #[serde(default)]
+    delta: Option<StreamDelta>,
+    /// `message_delta`: the output token count so far.
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+    #[serde(default)]
+    error: Option<StreamEventError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamDelta {
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
+    /// `input_json_delta`: the next piece of a tool call's input JSON.
+    #[serde(default)]
+    partial_json: Option<String>,
+    #[serde(default)]
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamEventError {
+    #[serde(default)]
+    message: String,
+}
+
+/// A `tool_use` block of a streaming response; its input arrives as JSON
+/// fragments.
+#[derive(Debug)]
+struct StreamedToolCall {
+    index: Option<usize>,
+    id: String,
+    name: String,
+    input_json: String,
+}
+
+/// The response a streaming Messages API call builds up event by event.
+#[derive(Debug, Default)]
+struct StreamedResponse {
+    text: String,
+    tool_calls: Vec<StreamedToolCall>,
+    usage: Option<TokenUsage>,
+    stop_reason: Option<String>,
+}
+
+impl StreamedResponse {
+    /// Apply one line of the event stream and return the answer text it
+    /// carried. `event:` lines, blank lines, and events without text (block
+    /// starts, pings, `message_stop`) yield `None`.
+    fn push_line(&mut self, line: &str) -> StreamResult<Option<String>> {
+        let Some(data) = line.trim().strip_prefix("data:") else {
+            return Ok(None);
+        };
+        let event: StreamEvent = serde_json::from_str(data.trim()).map_err(StreamError::Json)?;
+        match event.kind.as_str() {
+            "message_start" => {
+                if let Some(usage) = event.message.and_then(|message| message.usage) {
+                    self.record_usage(usage);
+                }
+            }
+            "content_block_start" => {
+                if let Some(block) = event.content_block.filter(|block| block.kind == "tool_use") {
+                    self.tool_calls.push(StreamedToolCall {
+                        index: event.index,
+                        id: block.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                        name: block.name.unwrap_or_default(),
+                        input_json: String::new(),
+                    });
+                }
+            }
+            "content_block_delta" => {
+                let Some(delta) = event.delta else {
+                    return Ok(None);
+                };
+                match delta.kind.as_deref() {
+                    Some("text_delta") => {
+                        let text = delta.text.filter(|text| !text.is_empty());
+                        if let Some(text) = &text {
+                            self.text.push_str(text);
+                        }
+                        return Ok(text);
+                    }
+                    Some("input_json_delta") => {
+                        let call = self
+                            .tool_calls
+                            .iter_mut()
+                            .rev()
+                            .find(|call| call.index == event.index);
+                        if let (Some(call), Some(json)) = (call, delta.partial_json) {
+                            call.input_json.push_str(&json);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "message_delta" => {
+                if let Some(usage) = event.usage {
+                    self.record_usage(usage);
+                }
+                if let Some(reason) = event.delta.and_then(|delta| delta.stop_reason) {
+                    if reason == "max_tokens" {
+                        tracing::warn!(
+                            "Anthropic stream stopped at max_tokens; the reply is truncated"
+                        );
+                    }
+                    self.stop_reason = Some(reason);
+                }
+            }
+            "error" => {
+                return Err(StreamError::Provider(
+                    event
+                        .error
+                        .map(|error| error.message)
+                        .unwrap_or_else(|| "Anthropic stream error".to_string()),
+                ));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// `message_start` reports the input tokens; each `message_delta`
+    /// reports the output tokens so far.
+    fn record_usage(&mut self, usage: AnthropicUsage) {
+        let total = self.usage.get_or_insert_with(TokenUsage::default);
+        total.input_tokens = usage.input_tokens.or(total.input_tokens);
+        total.output_tokens = usage.output_tokens.or(total.output_tokens);
+    }
+
+    fn finish(self) -> ProviderChatResponse {
+        let text = self.text.trim();
+        ProviderChatResponse {
+            text: (!text.is_empty()).then(|| text.to_string()),
+            tool_calls: self
+                .tool_calls
+                .into_iter()
+                .filter(|call| !call.name.is_empty())
+                .map(|call| ProviderToolCall {
+                    id: call.id,
+                    name: call.name,
+                    arguments: if call.input_json.trim().is_empty() {
+                        "{}".to_string()
+                    } else {
+                        call.input_json
+                    },
+                })
+                .collect(),
+            usage: self.usage,
+            reasoning_content: None,
+            finish_reason: self.stop_reason,
+        }
+    }
 }
 
 #[async_trait]
@@ -438,7 +775,7 @@ impl Provider for AnthropicProvider {
 
         let request = ChatRequest {
             model: model.to_string(),
-            max_tokens: 4096,
+            max_tokens: self.max_tokens,
             system: system_prompt.map(ToString::to_string),
             messages: vec![Message {
                 role: "user".to_string(),
@@ -452,7 +789,7 @@ impl Provider for AnthropicProvider {
             .post(format!("{}/v1/messages", self.base_url))
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&request);
+            .json(&request_body(Dialect::Anthropic, &request));
 
         request = self.apply_auth(request, credential);
 
@@ -487,11 +824,12 @@ impl Provider for AnthropicProvider {
 
         let native_request = NativeChatRequest {
             model: model.to_string(),
-            max_tokens: 4096,
+            max_tokens: self.max_tokens,
             system: system_prompt,
             messages,
             temperature,
             tools: Self::convert_tools(request.tools),
+            stream: false,
         };
 
         let req = self
@@ -499,7 +837,7 @@ impl Provider for AnthropicProvider {
             .post(format!("{}/v1/messages", self.base_url))
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&native_request);
+            .json(&request_body(Dialect::Anthropic, &native_request));
 
         let response = self.apply_auth(req, credential).send().await?;
         if !response.status().is_success() {
@@ -510,6 +848,13 @@ impl Provider for AnthropicProvider {
         Ok(Self::parse_native_response(native_response))
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            native_tool_calling: true,
+            vision: true,
+        }
+    }
+
     fn supports_native_tools(&self) -> bool {
         true
     }
@@ -561,6 +906,72 @@ impl Provider for AnthropicProvider {
         self.chat(request, model, temperature).await
     }
 
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        let messages = vec![NativeMessage {
+            role: "user".to_string(),
+            content: vec![NativeContentOut::Text {
+                text: message.to_string(),
+                cache_control: None,
+            }],
+        }];
+        traits::delta_chunks(
+            self.stream_messages(
+                system_prompt.map(|text| SystemPrompt::String(text.to_string())),
+                messages,
+                model,
+                temperature,
+                None,
+            ),
+            options,
+        )
+    }
+
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        let (system, messages) = Self::convert_messages(messages);
+        traits::delta_chunks(
+            self.stream_messages(system, messages, model, temperature, None),
+            options,
+        )
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ProviderChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        let (system, mut messages) = Self::convert_messages(request.messages);
+        if Self::should_cache_conversation(request.messages) {
+            Self::apply_cache_to_last_message(&mut messages);
+        }
+        self.stream_messages(
+            system,
+            messages,
+            model,
+            temperature,
+            Self::convert_tools(request.tools),
+        )
+        .map(|item| item.map_err(anyhow::Error::from))
+        .boxed()
+    }
+
     async fn warmup(&self) -> anyhow::Result<()> {
         if let Some(credential) = self.credential.as_ref() {
             let mut request = self
@@ -1059,6 +1470,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn convert_messages_sends_image_markers_as_image_blocks() {
+        let (_, native) = AnthropicProvider::convert_messages(&[ChatMessage::user(
+            "Compare these\n[IMAGE:data:image/png;base64,abcd]\n[IMAGE:https://example.com/b.jpg]",
+        )]);
+        let blocks = serde_json::to_value(&native[0].content).unwrap();
+        assert_eq!(
+            blocks[0],
+            serde_json::json!({
+                "type": "image",
+                "source": {"type": "base64", "media_type": "image/png", "data": "abcd"}
+            })
+        );
+        assert_eq!(
+            blocks[1],
+            serde_json::json!({
+                "type": "image",
+                "source": {"type": "url", "url": "https://example.com/b.jpg"}
+            })
+        );
+        assert_eq!(
+            blocks[2],
+            serde_json::json!({"type": "text", "text": "Compare these"})
+        );
+        assert!(AnthropicProvider::new(None).supports_vision());
+    }
+
     #[test]
     fn apply_cache_empty_messages() {
         let mut messages = vec![];
@@ -1156,10 +1594,12 @@ mod tests {
             }],
             temperature: 0.7,
             tools: None,
+            stream: false,
         };
 
         let json = serde_json::to_string(&req).unwrap();
         assert!(!json.contains("cache_control"));
+        assert!(!json.contains("stream"));
         assert!(json.contains(r#""system":"System""#));
     }
 
@@ -1353,4 +1793,97 @@ mod tests {
         let result = AnthropicProvider::parse_native_response(resp);
         assert!(result.usage.is_none());
     }
+
+    #[test]
+    fn native_response_reports_stop_reason_as_finish_reason() {
+        let json = r#"{
+            "content": [{"type": "text", "text": "Hello"}],
+            "stop_reason": "end_turn"
+        }"#;
+        let resp: NativeChatResponse = serde_json::from_str(json).unwrap();
+        let result = AnthropicProvider::parse_native_response(resp);
+        assert_eq!(result.finish_reason.as_deref(), Some("end_turn"));
+    }
+
+    #[test]
+    fn with_max_tokens_overrides_default() {
+        let provider = AnthropicProvider::new(Some("key"));
+        assert_eq!(provider.max_tokens, DEFAULT_MAX_TOKENS);
+        assert_eq!(provider.with_max_tokens(Some(16_000)).max_tokens, 16_000);
+        let provider = AnthropicProvider::new(Some("key")).with_max_tokens(Some(0));
+        assert_eq!(provider.max_tokens, DEFAULT_MAX_TOKENS);
+    }
+
+    #[test]
+    fn streamed_response_extracts_text_deltas() {
+        let mut streamed = StreamedResponse::default();
+        assert_eq!(
+            streamed
+                .push_line(
+                    r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hel"}}"#
+                )
+                .unwrap()
+                .as_deref(),
+            Some("Hel")
+        );
+        assert!(streamed
+            .push_line("event: content_block_delta")
+            .unwrap()
+            .is_none());
+        assert!(streamed
+            .push_line(r#"data: {"type":"message_stop"}"#)
+            .unwrap()
+            .is_none());
+        let err = streamed
+            .push_line(
+                r#"data: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Overloaded"));
+    }
+
+    #[test]
+    fn streamed_response_collects_tool_calls_and_usage() {
+        let mut streamed = StreamedResponse::default();
+        for line in [
+            r#"data: {"type":"message_start","message":{"usage":{"input_tokens":120,"output_tokens":1}}}"#,
+            r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Checking."}}"#,
+            r#"data: {"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"shell","input":{}}}"#,
+            r#"data: {"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"command\":"}}"#,
+            r#"data: {"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"\"date\"}"}}"#,
+            r#"data: {"type":"content_block_start","index":2,"content_block":{"type":"tool_use","id":"toolu_2","name":"file_read","input":{}}}"#,
+            r#"data: {"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":42}}"#,
+        ] {
+            streamed.push_line(line).unwrap();
+        }
+
+        let response = streamed.finish();
+        assert_eq!(response.text.as_deref(), Some("Checking."));
+        assert_eq!(response.tool_calls.len(), 2);
+        assert_eq!(response.tool_calls[0].id, "toolu_1");
+        assert_eq!(response.tool_calls[0].name, "shell");
+        assert_eq!(response.tool_calls[0].arguments, r#"{"command":"date"}"#);
+        assert_eq!(response.tool_calls[1].arguments, "{}");
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(120));
+        assert_eq!(usage.output_tokens, Some(42));
+        assert_eq!(response.finish_reason.as_deref(), Some("tool_use"));
+    }
+
+    #[test]
+    fn streaming_request_sets_stream_flag() {
+        let req = NativeChatRequest {
+            model: "claude-3-opus".to_string(),
+            max_tokens: 1024,
+            system: None,
+            messages: Vec::new(),
+            temperature: 0.7,
+            tools: None,
+            stream: true,
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert!(json.contains(r#""stream":true"#));
+        assert!(json.contains(r#""max_tokens":1024"#));
+    }
 }
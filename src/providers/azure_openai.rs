@@ -0,0 +1,292 @@
+//! Azure OpenAI Service provider.
+//!
+//! Azure serves each model from a named deployment, so the configured model
+//! is the deployment name: a request for `gpt-4o-prod` goes to
+//! `{endpoint}/openai/deployments/gpt-4o-prod/chat/completions?api-version=...`
+//! with the key in an `api-key` header. The wire format is OpenAI's, so each
+//! deployment is served by an [`OpenAiCompatibleProvider`] built on first use.
+//! A missing endpoint is reported when the first request is made.
+
+use crate::providers::compatible::{AuthStyle, OpenAiCompatibleProvider};
+use crate::providers::traits::{
+    ChatMessage, ChatRequest, ChatResponse, Provider, ProviderCapabilities, StreamChunk,
+    StreamError, StreamOptions, StreamResult,
+};
+use async_trait::async_trait;
+use futures_util::{stream, StreamExt};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// GA data-plane API version used when none is configured.
+pub const DEFAULT_API_VERSION: &str = "2024-10-21";
+
+const ENDPOINT_ENV: &str = "AZURE_OPENAI_ENDPOINT";
+const API_VERSION_ENV: &str = "AZURE_OPENAI_API_VERSION";
+
+pub struct AzureOpenAiProvider {
+    endpoint: Option<String>,
+    api_version: String,
+    credential: Option<String>,
+    deployments: Mutex<HashMap<String, Arc<OpenAiCompatibleProvider>>>,
+}
+
+impl AzureOpenAiProvider {
+    pub fn new(endpoint: Option<&str>, api_version: &str, credential: Option<&str>) -> Self {
+        Self {
+            endpoint: endpoint
+                .map(|endpoint| endpoint.trim().trim_end_matches('/').to_string())
+                .filter(|endpoint| !endpoint.is_empty()),
+            api_version: api_version.trim().to_string(),
+            credential: credential.map(ToString::to_string),
+            deployments: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Build from `api_url` (or `AZURE_OPENAI_ENDPOINT`), e.g.
+    /// `https://my-resource.openai.azure.com`. An `api-version` query
+    /// parameter on the URL wins over `AZURE_OPENAI_API_VERSION`, which
+    /// wins over [`DEFAULT_API_VERSION`].
+    pub fn from_config(api_url: Option<&str>, credential: Option<&str>) -> Self {
+        let configured = api_url
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+            .or_else(|| env_value(ENDPOINT_ENV));
+        let (endpoint, url_version) = match configured.as_deref().map(split_api_version) {
+            Some((endpoint, version)) => (Some(endpoint), version),
+            None => (None, None),
+        };
+        let api_version = url_version
+            .or_else(|| env_value(API_VERSION_ENV))
+            .unwrap_or_else(|| DEFAULT_API_VERSION.to_string());
+        Self::new(endpoint.as_deref(), &api_version, credential)
+    }
+
+    fn deployment_url(&self, endpoint: &str, deployment: &str) -> String {
+        format!(
+            "{endpoint}/openai/deployments/{}/chat/completions?api-version={}",
+            urlencoding::encode(deployment),
+            urlencoding::encode(&self.api_version)
+        )
+    }
+
+    /// The provider serving `deployment`, built on first use.
+    fn deployment(&self, deployment: &str) -> anyhow::Result<Arc<OpenAiCompatibleProvider>> {
+        let endpoint = self.endpoint.as_deref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Azure OpenAI endpoint not set. Set api_url (e.g. \
+                 https://my-resource.openai.azure.com) or {ENDPOINT_ENV}."
+            )
+        })?;
+        let mut deployments = self.deployments.lock();
+        let provider = deployments
+            .entry(deployment.to_string())
+            .or_insert_with(|| {
+                // Deployment URLs have no Responses API to fall back to.
+                Arc::new(OpenAiCompatibleProvider::new_no_responses_fallback(
+                    "Azure OpenAI",
+                    &self.deployment_url(endpoint, deployment),
+                    self.credential.as_deref(),
+                    AuthStyle::Custom("api-key".into()),
+                ))
+            });
+        Ok(Arc::clone(provider))
+    }
+
+    fn deployment_stream(
+        &self,
+        deployment: &str,
+        open: impl FnOnce(
+            &OpenAiCompatibleProvider,
+        ) -> stream::BoxStream<'static, StreamResult<StreamChunk>>,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        match self.deployment(deployment) {
+            Ok(provider) => open(&provider),
+            Err(e) => {
+                stream::once(async move { Err(StreamError::Provider(e.to_string())) }).boxed()
+            }
+        }
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Split `url` into the resource endpoint and its `api-version` query
+/// parameter, dropping any other query and an `/openai/...` path. A URL that
+/// does not parse is kept as is, for the request to report.
+fn split_api_version(url: &str) -> (String, Option<String>) {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return (url.to_string(), None);
+    };
+    let api_version = parsed
+        .query_pairs()
+        .find(|(key, _)| key == "api-version")
+        .map(|(_, value)| value.into_owned())
+        .filter(|value| !value.is_empty());
+    let path = parsed.path().trim_end_matches('/');
+    let path = path.find("/openai").map_or(path, |index| &path[..index]);
+    let endpoint = format!("{}{path}", parsed.origin().ascii_serialization());
+    (endpoint, api_version)
+}
+
+#[async_trait]
+impl Provider for AzureOpenAiProvider {
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            native_tool_calling: true,
+            vision: false,
+        }
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.deployment(model)?
+            .chat_with_system(system_prompt, message, model, temperature)
+            .await
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.deployment(model)?
+            .chat_with_history(messages, model, temperature)
+            .await
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.deployment(model)?
+            .chat(request, model, temperature)
+            .await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[serde_json::Value],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.deployment(model)?
+            .chat_with_tools(messages, tools, model, temperature)
+            .await
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        self.deployment(model)?
+            .chat_structured(messages, schema, model, temperature)
+            .await
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        true
+    }
+
+    async fn warmup(&self) -> anyhow::Result<()> {
+        // Deployments are only known per request; nothing to warm up yet.
+        Ok(())
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.deployment_stream(model, |provider| {
+            provider.stream_chat_with_system(system_prompt, message, model, temperature, options)
+        })
+    }
+
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.deployment_stream(model, |provider| {
+            provider.stream_chat_with_history(messages, model, temperature, options)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deployments_route_to_their_own_url() {
+        let provider = AzureOpenAiProvider::new(
+            Some("https://my-resource.openai.azure.com/"),
+            "2024-10-21",
+            Some("key"),
+        );
+        let deployment = provider.deployment("gpt-4o-prod").unwrap();
+        assert_eq!(
+            deployment.base_url,
+            "https://my-resource.openai.azure.com/openai/deployments/gpt-4o-prod/chat/completions?api-version=2024-10-21"
+        );
+        assert!(matches!(
+            &deployment.auth_header,
+            AuthStyle::Custom(header) if header == "api-key"
+        ));
+        assert!(Arc::ptr_eq(
+            &deployment,
+            &provider.deployment("gpt-4o-prod").unwrap()
+        ));
+    }
+
+    #[test]
+    fn api_version_is_read_from_the_endpoint_url() {
+        let (endpoint, version) = split_api_version(
+            "https://my-resource.openai.azure.com/openai/deployments/x/chat/completions?api-version=2025-01-01-preview",
+        );
+        assert_eq!(endpoint, "https://my-resource.openai.azure.com");
+        assert_eq!(version.as_deref(), Some("2025-01-01-preview"));
+
+        let (endpoint, version) = split_api_version("https://proxy.example.com/azure");
+        assert_eq!(endpoint, "https://proxy.example.com/azure");
+        assert!(version.is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_endpoint_fails_on_first_request() {
+        let provider = AzureOpenAiProvider::new(None, DEFAULT_API_VERSION, Some("key"));
+        let err = provider
+            .simple_chat("hi", "gpt-4o-prod", 0.0)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(ENDPOINT_ENV));
+    }
+}
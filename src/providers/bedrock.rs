@@ -4,6 +4,7 @@
 //! via environment variables. SigV4 signing is implemented manually
 //! using hmac/sha2 crates — no AWS SDK dependency.
 
+use crate::providers::params::{self, Dialect};
 use crate::providers::traits::{
     ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
     Provider, ProviderCapabilities, TokenUsage, ToolCall as ProviderToolCall, ToolsPayload,
@@ -401,7 +402,6 @@ struct ConverseResponse {
     #[serde(default)]
     output: Option<ConverseOutput>,
     #[serde(default)]
-    #[allow(dead_code)]
     stop_reason: Option<String>,
     #[serde(default)]
     usage: Option<BedrockUsage>,
@@ -511,6 +511,23 @@ impl BedrockProvider {
 
     // ── Cache heuristics (same thresholds as AnthropicProvider) ──
 
+    /// Model id without a cross-region inference profile prefix, e.g.
+    /// `anthropic.claude-3-5-sonnet-20241022-v2:0` for
+    /// `us.anthropic.claude-3-5-sonnet-20241022-v2:0`.
+    fn base_model_id(model_id: &str) -> &str {
+        ["us.", "eu.", "apac.", "us-gov.", "global."]
+            .iter()
+            .find_map(|prefix| model_id.strip_prefix(prefix))
+            .unwrap_or(model_id)
+    }
+
+    /// Only Claude and Nova models accept `cachePoint` blocks; Llama and
+    /// other families reject the whole request when one is present.
+    fn supports_prompt_caching(model_id: &str) -> bool {
+        let base = Self::base_model_id(model_id);
+        base.starts_with("anthropic.claude") || base.starts_with("amazon.nova")
+    }
+
     /// Cache system prompts larger than ~1024 tokens (3KB of text).
     fn should_cache_system(text: &str) -> bool {
         text.len() > 3072
@@ -785,6 +802,7 @@ impl BedrockProvider {
             tool_calls,
             usage,
             reasoning_content: None,
+            finish_reason: response.stop_reason,
         }
     }
 
@@ -796,7 +814,7 @@ impl BedrockProvider {
         model: &str,
         request_body: &ConverseRequest,
     ) -> anyhow::Result<ConverseResponse> {
-        let payload = serde_json::to_vec(request_body)?;
+        let payload = serde_json::to_vec(&params::request_body(Dialect::Bedrock, request_body))?;
 
         // Debug: log image blocks in payload (truncated)
         if let Ok(debug_val) = serde_json::from_slice::<serde_json::Value>(&payload) {
@@ -920,7 +938,7 @@ impl Provider for BedrockProvider {
             let mut blocks = vec![SystemBlock::Text(TextBlock {
                 text: text.to_string(),
             })];
-            if Self::should_cache_system(text) {
+            if Self::supports_prompt_caching(model) && Self::should_cache_system(text) {
                 blocks.push(SystemBlock::CachePoint(CachePointWrapper {
                     cache_point: CachePoint::default_cache(),
                 }));
@@ -960,12 +978,14 @@ impl Provider for BedrockProvider {
 
         let (system_blocks, mut converse_messages) = Self::convert_messages(request.messages);
 
+        let caching = Self::supports_prompt_caching(model);
+
         // Apply cachePoint to system if large.
         let system = system_blocks.map(|mut blocks| {
             let has_large_system = blocks
                 .iter()
                 .any(|b| matches!(b, SystemBlock::Text(tb) if Self::should_cache_system(&tb.text)));
-            if has_large_system {
+            if caching && has_large_system {
                 blocks.push(SystemBlock::CachePoint(CachePointWrapper {
                     cache_point: CachePoint::default_cache(),
                 }));
@@ -974,7 +994,7 @@ impl Provider for BedrockProvider {
         });
 
         // Apply cachePoint to last message if conversation is long.
-        if Self::should_cache_conversation(request.messages) {
+        if caching && Self::should_cache_conversation(request.messages) {
             if let Some(last_msg) = converse_messages.last_mut() {
                 last_msg
                     .content
@@ -1319,6 +1339,25 @@ mod tests {
         assert!(BedrockProvider::should_cache_conversation(&messages));
     }
 
+    #[test]
+    fn prompt_caching_is_limited_to_claude_and_nova() {
+        assert!(BedrockProvider::supports_prompt_caching(
+            "anthropic.claude-3-5-sonnet-20241022-v2:0"
+        ));
+        assert!(BedrockProvider::supports_prompt_caching(
+            "us.anthropic.claude-3-7-sonnet-20250219-v1:0"
+        ));
+        assert!(BedrockProvider::supports_prompt_caching(
+            "eu.amazon.nova-pro-v1:0"
+        ));
+        assert!(!BedrockProvider::supports_prompt_caching(
+            "meta.llama3-1-70b-instruct-v1:0"
+        ));
+        assert!(!BedrockProvider::supports_prompt_caching(
+            "us.meta.llama3-2-90b-instruct-v1:0"
+        ));
+    }
+
     // ── Tool conversion tests ───────────────────────────────────
 
     #[test]
@@ -0,0 +1,534 @@
+use super::traits::{
+    ChatDelta, ChatMessage, ChatRequest, ChatResponse, ProviderCapabilities, StreamChunk,
+    StreamOptions, StreamResult, ToolCall, ToolsPayload,
+};
+use super::Provider;
+use crate::memory::ResponseCache;
+use crate::tools::ToolSpec;
+use async_trait::async_trait;
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+/// What a cache entry keeps of a response. Usage is dropped: a hit costs no
+/// tokens.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedResponse {
+    text: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+impl From<&ChatResponse> for CachedResponse {
+    fn from(response: &ChatResponse) -> Self {
+        Self {
+            text: response.text.clone(),
+            tool_calls: response.tool_calls.clone(),
+            reasoning_content: response.reasoning_content.clone(),
+            finish_reason: response.finish_reason.clone(),
+        }
+    }
+}
+
+impl From<CachedResponse> for ChatResponse {
+    fn from(cached: CachedResponse) -> Self {
+        Self {
+            text: cached.text,
+            tool_calls: cached.tool_calls,
+            usage: None,
+            reasoning_content: cached.reasoning_content,
+            finish_reason: cached.finish_reason,
+        }
+    }
+}
+
+/// Serves repeated identical requests from a [`ResponseCache`] instead of
+/// the wrapped provider.
+///
+/// Entries are keyed by a hash of the model, temperature, the full message
+/// list (system prompt included), the tool specs, and the task's
+/// [`params::current`](super::params::current) parameters, so a recovered or
+/// replayed round with the same inputs is not billed twice. Requests above
+/// temperature 0 sample a fresh answer each time and go to the provider
+/// unless [`with_any_temperature`](Self::with_any_temperature) is set.
+/// `chat_stream` turns share the `chat` entries: a hit is served as one
+/// complete response and a finished stream is stored. Cache failures are
+/// logged and the request goes to the provider. The text-only
+/// `stream_chat_*` calls bypass the cache.
+pub struct CachingProvider {
+    inner: Box<dyn Provider>,
+    cache: ResponseCache,
+    any_temperature: bool,
+}
+
+impl CachingProvider {
+    pub fn new(inner: Box<dyn Provider>, cache: ResponseCache) -> Self {
+        Self {
+            inner,
+            cache,
+            any_temperature: false,
+        }
+    }
+
+    /// Also cache requests sent with a temperature above 0.
+    #[must_use]
+    pub fn with_any_temperature(mut self, any_temperature: bool) -> Self {
+        self.any_temperature = any_temperature;
+        self
+    }
+
+    fn caches(&self, temperature: f64) -> bool {
+        self.any_temperature || temperature <= 0.0
+    }
+
+    fn key(
+        call: &str,
+        messages: &[ChatMessage],
+        tools: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> String {
+        let system = messages
+            .iter()
+            .filter(|message| message.role == "system")
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut payload = serde_json::json!({
+            "call": call,
+            "messages": messages,
+            "tools": tools,
+        });
+        // A response capped or stopped by one task's parameters must not
+        // answer a task with other parameters.
+        if let Some(params) = super::params::current() {
+            payload["params"] = serde_json::to_value(params).unwrap_or_default();
+        }
+        ResponseCache::cache_key(
+            &format!("{model}@{temperature}"),
+            Some(&system),
+            &payload.to_string(),
+        )
+    }
+
+    fn lookup(&self, key: &str) -> Option<ChatResponse> {
+        match self.cache.get(key) {
+            Ok(Some(raw)) => match serde_json::from_str::<CachedResponse>(&raw) {
+                Ok(cached) => {
+                    tracing::debug!(key, "Serving provider response from cache");
+                    Some(cached.into())
+                }
+                Err(err) => {
+                    tracing::warn!("Ignoring unreadable response cache entry: {err}");
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(err) => {
+                tracing::warn!("Response cache lookup failed: {err}");
+                None
+            }
+        }
+    }
+
+    fn store(&self, key: &str, model: &str, response: &ChatResponse) {
+        let Ok(raw) = serde_json::to_string(&CachedResponse::from(response)) else {
+            return;
+        };
+        let tokens = response
+            .usage
+            .as_ref()
+            .and_then(|usage| usage.output_tokens)
+            .unwrap_or(0);
+        let tokens = u32::try_from(tokens).unwrap_or(u32::MAX);
+        if let Err(err) = self.cache.put(key, model, &raw, tokens) {
+            tracing::warn!("Response cache write failed: {err}");
+        }
+    }
+
+    async fn cached_text(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        call: impl std::future::Future<Output = anyhow::Result<String>>,
+    ) -> anyhow::Result<String> {
+        if !self.caches(temperature) {
+            return call.await;
+        }
+        let key = Self::key(
+            "text",
+            messages,
+            &serde_json::Value::Null,
+            model,
+            temperature,
+        );
+        if let Some(hit) = self.lookup(&key) {
+            return Ok(hit.text.unwrap_or_default());
+        }
+        let text = call.await?;
+        let response = ChatResponse {
+            text: Some(text.clone()),
+            tool_calls: Vec::new(),
+            usage: None,
+            reasoning_content: None,
+            finish_reason: None,
+        };
+        self.store(&key, model, &response);
+        Ok(text)
+    }
+}
+
+#[async_trait]
+impl Provider for CachingProvider {
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn convert_tools(&self, tools: &[ToolSpec]) -> ToolsPayload {
+        self.inner.convert_tools(tools)
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let messages: Vec<ChatMessage> = system_prompt
+            .map(ChatMessage::system)
+            .into_iter()
+            .chain(std::iter::once(ChatMessage::user(message)))
+            .collect();
+        self.cached_text(
+            &messages,
+            model,
+            temperature,
+            self.inner
+                .chat_with_system(system_prompt, message, model, temperature),
+        )
+        .await
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.cached_text(
+            messages,
+            model,
+            temperature,
+            self.inner.chat_with_history(messages, model, temperature),
+        )
+        .await
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        if !self.caches(temperature) {
+            return self.inner.chat(request, model, temperature).await;
+        }
+        let tools = serde_json::to_value(request.tools).unwrap_or_default();
+        let key = Self::key("chat", request.messages, &tools, model, temperature);
+        if let Some(hit) = self.lookup(&key) {
+            return Ok(hit);
+        }
+        let response = self.inner.chat(request, model, temperature).await?;
+        self.store(&key, model, &response);
+        Ok(response)
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[serde_json::Value],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        if !self.caches(temperature) {
+            return self
+                .inner
+                .chat_with_tools(messages, tools, model, temperature)
+                .await;
+        }
+        let tools_value = serde_json::Value::Array(tools.to_vec());
+        let key = Self::key("tools", messages, &tools_value, model, temperature);
+        if let Some(hit) = self.lookup(&key) {
+            return Ok(hit);
+        }
+        let response = self
+            .inner
+            .chat_with_tools(messages, tools, model, temperature)
+            .await?;
+        self.store(&key, model, &response);
+        Ok(response)
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        self.inner
+            .chat_structured(messages, schema, model, temperature)
+            .await
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        if !self.caches(temperature) {
+            return self.inner.chat_stream(request, model, temperature);
+        }
+        let tools = serde_json::to_value(request.tools).unwrap_or_default();
+        let key = Self::key("chat", request.messages, &tools, model, temperature);
+        if let Some(hit) = self.lookup(&key) {
+            return stream::once(async move { Ok(ChatDelta::Done(hit)) }).boxed();
+        }
+        let model_name = model.to_string();
+        self.inner
+            .chat_stream(request, model, temperature)
+            .inspect(move |delta| {
+                if let Ok(ChatDelta::Done(response)) = delta {
+                    self.store(&key, &model_name, response);
+                }
+            })
+            .boxed()
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.inner.supports_native_tools()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    async fn warmup(&self) -> anyhow::Result<()> {
+        self.inner.warmup().await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.inner
+            .stream_chat_with_system(system_prompt, message, model, temperature, options)
+    }
+
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.inner
+            .stream_chat_with_history(messages, model, temperature, options)
+    }
+}
+
+/// Wrap `provider` in a [`CachingProvider`] when `[memory]
+/// response_cache_enabled` is set; otherwise return it unchanged.
+pub fn with_response_cache(
+    provider: Box<dyn Provider>,
+    config: &crate::config::MemoryConfig,
+    workspace_dir: &std::path::Path,
+) -> Box<dyn Provider> {
+    match crate::memory::create_response_cache(config, workspace_dir) {
+        Some(cache) => Box::new(
+            CachingProvider::new(provider, cache)
+                .with_any_temperature(config.response_cache_any_temperature),
+        ),
+        None => provider,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    struct CountingProvider {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Provider for CountingProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(format!("{message} #{call}"))
+        }
+    }
+
+    fn caching_provider() -> (TempDir, CachingProvider, Arc<AtomicUsize>) {
+        let tmp = TempDir::new().unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = CachingProvider::new(
+            Box::new(CountingProvider {
+                calls: Arc::clone(&calls),
+            }),
+            ResponseCache::new(tmp.path(), 60, 100).unwrap(),
+        );
+        (tmp, provider, calls)
+    }
+
+    #[tokio::test]
+    async fn identical_requests_are_served_from_cache() {
+        let (_tmp, provider, calls) = caching_provider();
+        let messages = [ChatMessage::system("sys"), ChatMessage::user("hello")];
+        let request = ChatRequest {
+            messages: &messages,
+            tools: None,
+        };
+
+        let first = provider.chat(request, "model", 0.0).await.unwrap();
+        let second = provider.chat(request, "model", 0.0).await.unwrap();
+
+        assert_eq!(first.text.as_deref(), Some("hello #1"));
+        assert_eq!(second.text.as_deref(), Some("hello #1"));
+        assert!(second.usage.is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn model_temperature_and_system_prompt_are_part_of_the_key() {
+        let (_tmp, provider, calls) = caching_provider();
+
+        provider
+            .chat_with_system(Some("a"), "hi", "model", 0.0)
+            .await
+            .unwrap();
+        provider
+            .chat_with_system(Some("b"), "hi", "model", 0.0)
+            .await
+            .unwrap();
+        provider
+            .chat_with_system(Some("a"), "hi", "model", 0.7)
+            .await
+            .unwrap();
+        provider
+            .chat_with_system(Some("a"), "hi", "other", 0.0)
+            .await
+            .unwrap();
+        let repeat = provider
+            .chat_with_system(Some("a"), "hi", "model", 0.0)
+            .await
+            .unwrap();
+
+        assert_eq!(repeat, "hi #1");
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn sampled_requests_skip_the_cache_unless_configured() {
+        let (_tmp, provider, calls) = caching_provider();
+        provider
+            .chat_with_system(None, "hi", "model", 0.7)
+            .await
+            .unwrap();
+        let again = provider
+            .chat_with_system(None, "hi", "model", 0.7)
+            .await
+            .unwrap();
+        assert_eq!(again, "hi #2");
+
+        let provider = provider.with_any_temperature(true);
+        provider
+            .chat_with_system(None, "hi", "model", 0.7)
+            .await
+            .unwrap();
+        let cached = provider
+            .chat_with_system(None, "hi", "model", 0.7)
+            .await
+            .unwrap();
+        assert_eq!(cached, "hi #3");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn streamed_turns_share_the_chat_cache() {
+        let (_tmp, provider, calls) = caching_provider();
+        let messages = [ChatMessage::user("hello")];
+        let request = ChatRequest {
+            messages: &messages,
+            tools: None,
+        };
+
+        let streamed: Vec<_> = provider.chat_stream(request, "model", 0.0).collect().await;
+        assert!(matches!(
+            streamed.last(),
+            Some(Ok(ChatDelta::Done(response))) if response.text.as_deref() == Some("hello #1")
+        ));
+        let replayed: Vec<_> = provider.chat_stream(request, "model", 0.0).collect().await;
+        assert!(matches!(
+            replayed.as_slice(),
+            [Ok(ChatDelta::Done(response))] if response.text.as_deref() == Some("hello #1")
+        ));
+        let chatted = provider.chat(request, "model", 0.0).await.unwrap();
+        assert_eq!(chatted.text.as_deref(), Some("hello #1"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn task_provider_params_are_part_of_the_key() {
+        let (_tmp, provider, calls) = caching_provider();
+        let capped = crate::config::ProviderParams {
+            max_tokens: Some(50),
+            ..crate::config::ProviderParams::default()
+        };
+
+        let short = crate::providers::params::scope(
+            capped.clone(),
+            provider.chat_with_system(None, "hi", "model", 0.0),
+        )
+        .await
+        .unwrap();
+        let full = provider
+            .chat_with_system(None, "hi", "model", 0.0)
+            .await
+            .unwrap();
+        let short_again = crate::providers::params::scope(
+            capped,
+            provider.chat_with_system(None, "hi", "model", 0.0),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(short, "hi #1");
+        assert_eq!(full, "hi #2");
+        assert_eq!(short_again, "hi #1");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
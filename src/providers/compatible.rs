@@ -3,11 +3,13 @@
 //! This module provides a single implementation that works for all of them.
 
 use crate::multimodal;
+use crate::providers::params::{request_body, Dialect};
 use crate::providers::traits::{
     ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
     Provider, StreamChunk, StreamError, StreamOptions, StreamResult, TokenUsage,
     ToolCall as ProviderToolCall,
 };
+use crate::tools::{CleaningStrategy, SchemaCleanr};
 use async_trait::async_trait;
 use futures_util::{stream, StreamExt};
 use reqwest::{
@@ -293,7 +295,10 @@ impl OpenAiCompatibleProvider {
                     "function": {
                         "name": tool.name,
                         "description": tool.description,
-                        "parameters": tool.parameters
+                        "parameters": SchemaCleanr::function_parameters(
+                            tool.parameters.clone(),
+                            CleaningStrategy::OpenAI,
+                        )
                     }
                 })
             })
@@ -357,6 +362,8 @@ struct UsageInfo {
 #[derive(Debug, Deserialize)]
 struct Choice {
     message: ResponseMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 /// Remove `<think>...</think>` blocks from model output.
@@ -871,7 +878,10 @@ impl OpenAiCompatibleProvider {
                         "function": {
                             "name": tool.name,
                             "description": tool.description,
-                            "parameters": tool.parameters,
+                            "parameters": SchemaCleanr::function_parameters(
+                                tool.parameters.clone(),
+                                CleaningStrategy::OpenAI,
+                            ),
                         }
                     })
                 })
@@ -1063,6 +1073,7 @@ impl OpenAiCompatibleProvider {
             tool_calls,
             usage: None,
             reasoning_content,
+            finish_reason: None,
         }
     }
 
@@ -1164,7 +1175,12 @@ impl Provider for OpenAiCompatibleProvider {
         };
 
         let response = match self
-            .apply_auth_header(self.http_client().post(&url).json(&request), credential)
+            .apply_auth_header(
+                self.http_client()
+                    .post(&url)
+                    .json(&request_body(Dialect::Compatible, &request)),
+                credential,
+            )
             .send()
             .await
         {
@@ -1183,12 +1199,13 @@ impl Provider for OpenAiCompatibleProvider {
                         });
                 }
 
-                return Err(chat_error.into());
+                return Err(super::ProviderError::transport(&self.name, &chat_error).into());
             }
         };
 
         if !response.status().is_success() {
             let status = response.status();
+            let headers = response.headers().clone();
             let error = response.text().await?;
             let sanitized = super::sanitize_api_error(&error);
 
@@ -1204,7 +1221,10 @@ impl Provider for OpenAiCompatibleProvider {
                     });
             }
 
-            anyhow::bail!("{} API error ({status}): {sanitized}", self.name);
+            return Err(super::ProviderError::from_status(
+                &self.name, status, &headers, &sanitized,
+            )
+            .into());
         }
 
         let body = response.text().await?;
@@ -1270,7 +1290,12 @@ impl Provider for OpenAiCompatibleProvider {
 
         let url = self.chat_completions_url();
         let response = match self
-            .apply_auth_header(self.http_client().post(&url).json(&request), credential)
+            .apply_auth_header(
+                self.http_client()
+                    .post(&url)
+                    .json(&request_body(Dialect::Compatible, &request)),
+                credential,
+            )
             .send()
             .await
         {
@@ -1289,7 +1314,7 @@ impl Provider for OpenAiCompatibleProvider {
                         });
                 }
 
-                return Err(chat_error.into());
+                return Err(super::ProviderError::transport(&self.name, &chat_error).into());
             }
         };
 
@@ -1384,7 +1409,12 @@ impl Provider for OpenAiCompatibleProvider {
 
         let url = self.chat_completions_url();
         let response = match self
-            .apply_auth_header(self.http_client().post(&url).json(&request), credential)
+            .apply_auth_header(
+                self.http_client()
+                    .post(&url)
+                    .json(&request_body(Dialect::Compatible, &request)),
+                credential,
+            )
             .send()
             .await
         {
@@ -1400,6 +1430,7 @@ impl Provider for OpenAiCompatibleProvider {
                     tool_calls: vec![],
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 });
             }
         };
@@ -1444,6 +1475,7 @@ impl Provider for OpenAiCompatibleProvider {
             tool_calls,
             usage,
             reasoning_content,
+            finish_reason: choice.finish_reason,
         })
     }
 
@@ -1480,7 +1512,9 @@ impl Provider for OpenAiCompatibleProvider {
         let url = self.chat_completions_url();
         let response = match self
             .apply_auth_header(
-                self.http_client().post(&url).json(&native_request),
+                self.http_client()
+                    .post(&url)
+                    .json(&request_body(Dialect::Compatible, &native_request)),
                 credential,
             )
             .send()
@@ -1498,6 +1532,7 @@ impl Provider for OpenAiCompatibleProvider {
                             tool_calls: vec![],
                             usage: None,
                             reasoning_content: None,
+                            finish_reason: None,
                         })
                         .map_err(|responses_err| {
                             anyhow::anyhow!(
@@ -1507,12 +1542,13 @@ impl Provider for OpenAiCompatibleProvider {
                         });
                 }
 
-                return Err(chat_error.into());
+                return Err(super::ProviderError::transport(&self.name, &chat_error).into());
             }
         };
 
         if !response.status().is_success() {
             let status = response.status();
+            let headers = response.headers().clone();
             let error = response.text().await?;
             let sanitized = super::sanitize_api_error(&error);
 
@@ -1527,6 +1563,7 @@ impl Provider for OpenAiCompatibleProvider {
                     tool_calls: vec![],
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 });
             }
 
@@ -1539,6 +1576,7 @@ impl Provider for OpenAiCompatibleProvider {
                         tool_calls: vec![],
                         usage: None,
                         reasoning_content: None,
+                        finish_reason: None,
                     })
                     .map_err(|responses_err| {
                         anyhow::anyhow!(
@@ -1548,7 +1586,10 @@ impl Provider for OpenAiCompatibleProvider {
                     });
             }
 
-            anyhow::bail!("{} API error ({status}): {sanitized}", self.name);
+            return Err(super::ProviderError::from_status(
+                &self.name, status, &headers, &sanitized,
+            )
+            .into());
         }
 
         let native_response: ApiChatResponse = response.json().await?;
@@ -1556,15 +1597,15 @@ impl Provider for OpenAiCompatibleProvider {
             input_tokens: u.prompt_tokens,
             output_tokens: u.completion_tokens,
         });
-        let message = native_response
+        let choice = native_response
             .choices
             .into_iter()
             .next()
-            .map(|choice| choice.message)
             .ok_or_else(|| anyhow::anyhow!("No response from {}", self.name))?;
 
-        let mut result = Self::parse_native_response(message);
+        let mut result = Self::parse_native_response(choice.message);
         result.usage = usage;
+        result.finish_reason = choice.finish_reason;
         Ok(result)
     }
 
@@ -1619,6 +1660,8 @@ impl Provider for OpenAiCompatibleProvider {
             tool_choice: None,
         };
 
+        // Built here: task parameters are not visible inside the spawned task.
+        let body = request_body(Dialect::Compatible, &request);
         let url = self.chat_completions_url();
         let client = self.http_client();
         let auth_header = self.auth_header.clone();
@@ -1628,7 +1671,7 @@ impl Provider for OpenAiCompatibleProvider {
 
         tokio::spawn(async move {
             // Build request with auth
-            let mut req_builder = client.post(&url).json(&request);
+            let mut req_builder = client.post(&url).json(&body);
 
             // Apply auth header
             req_builder = match &auth_header {
@@ -149,6 +149,8 @@ struct UsageInfo {
 #[derive(Debug, Deserialize)]
 struct Choice {
     message: ResponseMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -379,6 +381,7 @@ impl CopilotProvider {
             tool_calls,
             usage,
             reasoning_content: None,
+            finish_reason: choice.finish_reason,
         })
     }
 
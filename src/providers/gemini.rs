@@ -5,11 +5,17 @@
 //! - Google Cloud ADC (`GOOGLE_APPLICATION_CREDENTIALS`)
 
 use crate::auth::AuthService;
-use crate::providers::traits::{ChatMessage, ChatResponse, Provider, TokenUsage};
+use crate::providers::params::{request_body, Dialect};
+use crate::providers::traits::{
+    ChatMessage, ChatResponse, Provider, ProviderError, ProviderErrorKind, TokenUsage, ToolCall,
+    ToolsPayload,
+};
+use crate::tools::{SchemaCleanr, ToolSpec};
 use async_trait::async_trait;
 use directories::UserDirs;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -23,6 +29,8 @@ pub struct GeminiProvider {
     auth_service: Option<AuthService>,
     /// Override profile name for managed auth.
     auth_profile_override: Option<String>,
+    /// Sent as `safetySettings`; empty keeps Gemini's default thresholds.
+    safety_settings: Vec<SafetySetting>,
 }
 
 /// Mutable OAuth token state — supports runtime refresh for long-lived processes.
@@ -88,6 +96,10 @@ struct GenerateContentRequest {
     system_instruction: Option<Content>,
     #[serde(rename = "generationConfig")]
     generation_config: GenerationConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<GeminiTool>>,
+    #[serde(rename = "safetySettings", skip_serializing_if = "Vec::is_empty")]
+    safety_settings: Vec<SafetySetting>,
 }
 
 /// Request envelope for the internal cloudcode-pa API.
@@ -124,6 +136,10 @@ struct InternalGenerateContentRequest {
     system_instruction: Option<Content>,
     #[serde(rename = "generationConfig", skip_serializing_if = "Option::is_none")]
     generation_config: Option<GenerationConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<GeminiTool>>,
+    #[serde(rename = "safetySettings", skip_serializing_if = "Vec::is_empty")]
+    safety_settings: Vec<SafetySetting>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -133,9 +149,49 @@ struct Content {
     parts: Vec<Part>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, Default)]
 struct Part {
-    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(rename = "functionCall", skip_serializing_if = "Option::is_none")]
+    function_call: Option<FunctionCall>,
+    #[serde(rename = "functionResponse", skip_serializing_if = "Option::is_none")]
+    function_response: Option<FunctionResponse>,
+}
+
+impl Part {
+    fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct FunctionCall {
+    name: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct FunctionResponse {
+    name: String,
+    response: serde_json::Value,
+}
+
+/// `tools` entry carrying the function declarations.
+#[derive(Debug, Serialize, Clone)]
+struct GeminiTool {
+    #[serde(rename = "functionDeclarations")]
+    function_declarations: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+struct SafetySetting {
+    category: &'static str,
+    threshold: String,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -153,6 +209,14 @@ struct GenerateContentResponse {
     response: Option<Box<GenerateContentResponse>>,
     #[serde(default, rename = "usageMetadata")]
     usage_metadata: Option<GeminiUsageMetadata>,
+    #[serde(default, rename = "promptFeedback")]
+    prompt_feedback: Option<PromptFeedback>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PromptFeedback {
+    #[serde(default, rename = "blockReason")]
+    block_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -174,6 +238,8 @@ struct InternalGenerateContentResponse {
 struct Candidate {
     #[serde(default)]
     content: Option<CandidateContent>,
+    #[serde(default, rename = "finishReason")]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -188,9 +254,26 @@ struct ResponsePart {
     /// Thinking models (e.g. gemini-3-pro-preview) mark reasoning parts with `thought: true`.
     #[serde(default)]
     thought: bool,
+    #[serde(default, rename = "functionCall")]
+    function_call: Option<FunctionCall>,
 }
 
 impl CandidateContent {
+    /// Split the parts into the answer text and the requested function calls.
+    fn into_reply(mut self) -> (Option<String>, Vec<ToolCall>) {
+        let tool_calls = self
+            .parts
+            .iter_mut()
+            .filter_map(|part| part.function_call.take())
+            .map(|call| ToolCall {
+                id: format!("gemini_{}", uuid::Uuid::new_v4()),
+                name: call.name,
+                arguments: call.args.to_string(),
+            })
+            .collect();
+        (self.effective_text(), tool_calls)
+    }
+
     /// Extract effective text, skipping thinking/signature parts.
     ///
     /// Gemini thinking models (e.g. gemini-3-pro-preview) return parts like:
@@ -241,6 +324,238 @@ impl GenerateContentResponse {
             other => other,
         }
     }
+
+    /// Answer text and function calls of the first candidate, with blocked
+    /// prompts and refused candidates turned into provider errors.
+    fn into_chat_response(self) -> anyhow::Result<ChatResponse> {
+        if let Some(reason) = self.prompt_feedback.and_then(|f| f.block_reason) {
+            return Err(ProviderError {
+                provider: "Gemini".to_string(),
+                kind: ProviderErrorKind::InvalidRequest,
+                message: format!("Gemini blocked the prompt (blockReason={reason})"),
+            }
+            .into());
+        }
+
+        let usage = self.usage_metadata.map(|u| TokenUsage {
+            input_tokens: u.prompt_token_count,
+            output_tokens: u.candidates_token_count,
+        });
+
+        let candidate = self
+            .candidates
+            .and_then(|c| c.into_iter().next())
+            .ok_or_else(|| anyhow::anyhow!("No response from Gemini"))?;
+        if let Some(reason) = candidate.finish_reason.as_deref() {
+            if let Some(err) = finish_reason_error(reason) {
+                return Err(err.into());
+            }
+            if reason == "MAX_TOKENS" {
+                tracing::warn!(
+                    "Gemini response stopped at maxOutputTokens; the reply is truncated"
+                );
+            }
+        }
+
+        let (text, tool_calls) = candidate
+            .content
+            .map(CandidateContent::into_reply)
+            .unwrap_or_default();
+        if text.is_none() && tool_calls.is_empty() {
+            anyhow::bail!("No response from Gemini");
+        }
+
+        Ok(ChatResponse {
+            text,
+            tool_calls,
+            usage,
+            reasoning_content: None,
+            finish_reason: candidate.finish_reason,
+        })
+    }
+}
+
+/// Error for a candidate Gemini stopped without a usable answer.
+///
+/// Content-policy stops (`SAFETY`, `RECITATION`, `BLOCKLIST`, ...) are
+/// `InvalidRequest`: the same prompt is refused again. `MALFORMED_FUNCTION_CALL`
+/// and `OTHER` are sampling failures, so they count as `ServerError` and the
+/// reliable provider retries them. `STOP` and `MAX_TOKENS` keep the answer.
+fn finish_reason_error(reason: &str) -> Option<ProviderError> {
+    let kind = match reason {
+        "STOP" | "MAX_TOKENS" | "FINISH_REASON_UNSPECIFIED" => return None,
+        "MALFORMED_FUNCTION_CALL" | "OTHER" => ProviderErrorKind::ServerError,
+        _ => ProviderErrorKind::InvalidRequest,
+    };
+    Some(ProviderError {
+        provider: "Gemini".to_string(),
+        kind,
+        message: format!("Gemini stopped the response (finishReason={reason})"),
+    })
+}
+
+/// Harm categories `runtime.gemini_safety_threshold` applies to.
+const SAFETY_CATEGORIES: [&str; 4] = [
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+const SAFETY_THRESHOLDS: [&str; 5] = [
+    "BLOCK_NONE",
+    "BLOCK_ONLY_HIGH",
+    "BLOCK_MEDIUM_AND_ABOVE",
+    "BLOCK_LOW_AND_ABOVE",
+    "OFF",
+];
+
+/// One setting per harm category at `threshold`, or `None` for an unknown
+/// threshold.
+fn safety_settings(threshold: &str) -> Option<Vec<SafetySetting>> {
+    let threshold = threshold.trim().to_ascii_uppercase();
+    SAFETY_THRESHOLDS.contains(&threshold.as_str()).then(|| {
+        SAFETY_CATEGORIES
+            .iter()
+            .map(|&category| SafetySetting {
+                category,
+                threshold: threshold.clone(),
+            })
+            .collect()
+    })
+}
+
+/// Gemini `functionDeclarations` for `tools`. Parameters are cleaned of the
+/// JSON Schema keywords Gemini rejects, and omitted for tools without
+/// arguments since Gemini refuses an object schema with no properties.
+fn function_declarations(tools: &[ToolSpec]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            let mut declaration = serde_json::json!({
+                "name": tool.name,
+                "description": tool.description,
+            });
+            let parameters = SchemaCleanr::clean_for_gemini(tool.parameters.clone());
+            if parameters
+                .get("properties")
+                .and_then(serde_json::Value::as_object)
+                .is_some_and(|properties| !properties.is_empty())
+            {
+                declaration["parameters"] = parameters;
+            }
+            declaration
+        })
+        .collect()
+}
+
+/// Split chat history into Gemini's system instruction and contents.
+///
+/// Assistant tool-call messages become `functionCall` parts and tool results
+/// `functionResponse` parts; results of parallel calls share one user turn,
+/// as Gemini expects.
+fn convert_messages(messages: &[ChatMessage]) -> (Option<Content>, Vec<Content>) {
+    let mut system_parts: Vec<&str> = Vec::new();
+    let mut contents: Vec<Content> = Vec::new();
+    let mut call_names: HashMap<String, String> = HashMap::new();
+
+    for msg in messages {
+        match msg.role.as_str() {
+            "system" => system_parts.push(&msg.content),
+            "user" => contents.push(Content {
+                role: Some("user".to_string()),
+                parts: vec![Part::text(msg.content.clone())],
+            }),
+            // Gemini API uses "model" role instead of "assistant"
+            "assistant" => {
+                let parts = parse_assistant_tool_calls(&msg.content, &mut call_names)
+                    .unwrap_or_else(|| vec![Part::text(msg.content.clone())]);
+                contents.push(Content {
+                    role: Some("model".to_string()),
+                    parts,
+                });
+            }
+            "tool" => {
+                let part = parse_tool_result(&msg.content, &call_names)
+                    .unwrap_or_else(|| Part::text(msg.content.clone()));
+                match contents.last_mut() {
+                    Some(last)
+                        if part.function_response.is_some()
+                            && last.parts.iter().all(|p| p.function_response.is_some()) =>
+                    {
+                        last.parts.push(part);
+                    }
+                    _ => contents.push(Content {
+                        role: Some("user".to_string()),
+                        parts: vec![part],
+                    }),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let system_instruction = if system_parts.is_empty() {
+        None
+    } else {
+        Some(Content {
+            role: None,
+            parts: vec![Part::text(system_parts.join("\n\n"))],
+        })
+    };
+    (system_instruction, contents)
+}
+
+fn parse_assistant_tool_calls(
+    content: &str,
+    call_names: &mut HashMap<String, String>,
+) -> Option<Vec<Part>> {
+    let value = serde_json::from_str::<serde_json::Value>(content).ok()?;
+    let tool_calls = value
+        .get("tool_calls")
+        .and_then(|v| serde_json::from_value::<Vec<ToolCall>>(v.clone()).ok())?;
+
+    let mut parts = Vec::new();
+    if let Some(text) = value
+        .get("content")
+        .and_then(serde_json::Value::as_str)
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+    {
+        parts.push(Part::text(text));
+    }
+    for call in tool_calls {
+        let args = serde_json::from_str::<serde_json::Value>(&call.arguments)
+            .unwrap_or_else(|_| serde_json::Value::Object(serde_json::Map::new()));
+        call_names.insert(call.id, call.name.clone());
+        parts.push(Part {
+            function_call: Some(FunctionCall {
+                name: call.name,
+                args,
+            }),
+            ..Part::default()
+        });
+    }
+    Some(parts)
+}
+
+fn parse_tool_result(content: &str, call_names: &HashMap<String, String>) -> Option<Part> {
+    let value = serde_json::from_str::<serde_json::Value>(content).ok()?;
+    let name = value
+        .get("tool_call_id")
+        .and_then(serde_json::Value::as_str)
+        .and_then(|id| call_names.get(id))?;
+    let result = value
+        .get("content")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("");
+    Some(Part {
+        function_response: Some(FunctionResponse {
+            name: name.clone(),
+            response: serde_json::json!({ "content": result }),
+        }),
+        ..Part::default()
+    })
 }
 
 // ══════════════════════════════════════════════════════════════════════════════
@@ -408,6 +723,7 @@ impl GeminiProvider {
             oauth_index: Arc::new(tokio::sync::Mutex::new(0)),
             auth_service: None,
             auth_profile_override: None,
+            safety_settings: Vec::new(),
         }
     }
 
@@ -481,7 +797,23 @@ impl GeminiProvider {
                 None
             },
             auth_profile_override: profile_override,
+            safety_settings: Vec::new(),
+        }
+    }
+
+    /// Apply `threshold` (e.g. `BLOCK_ONLY_HIGH`) to every harm category.
+    /// An unknown threshold is logged and Gemini's defaults are kept.
+    pub fn with_safety_threshold(mut self, threshold: Option<&str>) -> Self {
+        if let Some(threshold) = threshold {
+            match safety_settings(threshold) {
+                Some(settings) => self.safety_settings = settings,
+                None => tracing::warn!(
+                    "Ignoring unknown runtime.gemini_safety_threshold {threshold:?}; expected one of {}",
+                    SAFETY_THRESHOLDS.join(", ")
+                ),
+            }
         }
+        self
     }
 
     fn normalize_non_empty(value: &str) -> Option<String> {
@@ -832,7 +1164,10 @@ impl GeminiProvider {
         project: Option<&str>,
         oauth_token: Option<&str>,
     ) -> reqwest::RequestBuilder {
-        let req = self.http_client().post(url).json(request);
+        let req = self
+            .http_client()
+            .post(url)
+            .json(&request_body(Dialect::Gemini, request));
         match auth {
             GeminiAuth::OAuthToken(_) | GeminiAuth::ManagedOAuth => {
                 let token = oauth_token.unwrap_or_default();
@@ -850,11 +1185,13 @@ impl GeminiProvider {
                         } else {
                             None
                         },
+                        tools: request.tools.clone(),
+                        safety_settings: request.safety_settings.clone(),
                     },
                 };
                 self.http_client()
                     .post(url)
-                    .json(&internal_request)
+                    .json(&request_body(Dialect::Gemini, &internal_request))
                     .bearer_auth(token)
             }
             _ => req,
@@ -874,6 +1211,21 @@ impl GeminiProvider {
             || error_text.contains(r#"Unknown name \"generationConfig\""#)
     }
 
+    /// Typed error for a failed generateContent call.
+    fn api_error(
+        status: reqwest::StatusCode,
+        headers: &reqwest::header::HeaderMap,
+        error_text: &str,
+    ) -> anyhow::Error {
+        ProviderError::from_status(
+            "Gemini",
+            status,
+            headers,
+            &super::sanitize_api_error(error_text),
+        )
+        .into()
+    }
+
     fn should_rotate_oauth_on_error(status: reqwest::StatusCode, error_text: &str) -> bool {
         status == reqwest::StatusCode::TOO_MANY_REQUESTS
             || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
@@ -887,9 +1239,10 @@ impl GeminiProvider {
         &self,
         contents: Vec<Content>,
         system_instruction: Option<Content>,
+        tools: Option<Vec<GeminiTool>>,
         model: &str,
         temperature: f64,
-    ) -> anyhow::Result<(String, Option<TokenUsage>)> {
+    ) -> anyhow::Result<ChatResponse> {
         let auth = self.auth.as_ref().ok_or_else(|| {
             anyhow::anyhow!(
                 "Gemini API key not found. Options:\n\
@@ -939,6 +1292,8 @@ impl GeminiProvider {
                 temperature,
                 max_output_tokens: 8192,
             },
+            tools,
+            safety_settings: self.safety_settings.clone(),
         };
 
         let url = Self::build_generate_content_url(model, auth);
@@ -958,6 +1313,7 @@ impl GeminiProvider {
 
         if !response.status().is_success() {
             let status = response.status();
+            let headers = response.headers().clone();
             let error_text = response.text().await.unwrap_or_default();
 
             if auth.is_oauth() && Self::should_rotate_oauth_on_error(status, &error_text) {
@@ -1011,7 +1367,7 @@ impl GeminiProvider {
                         .send()
                         .await?;
                 } else {
-                    anyhow::bail!("Gemini API error ({status}): {error_text}");
+                    return Err(Self::api_error(status, &headers, &error_text));
                 }
             } else if auth.is_oauth()
                 && Self::should_retry_oauth_without_generation_config(status, &error_text)
@@ -1032,12 +1388,13 @@ impl GeminiProvider {
                     .send()
                     .await?;
             } else {
-                anyhow::bail!("Gemini API error ({status}): {error_text}");
+                return Err(Self::api_error(status, &headers, &error_text));
             }
         }
 
         if !response.status().is_success() {
             let status = response.status();
+            let headers = response.headers().clone();
             let error_text = response.text().await.unwrap_or_default();
             if auth.is_oauth()
                 && Self::should_retry_oauth_without_generation_config(status, &error_text)
@@ -1058,14 +1415,15 @@ impl GeminiProvider {
                     .send()
                     .await?;
             } else {
-                anyhow::bail!("Gemini API error ({status}): {error_text}");
+                return Err(Self::api_error(status, &headers, &error_text));
             }
         }
 
         if !response.status().is_success() {
             let status = response.status();
+            let headers = response.headers().clone();
             let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Gemini API error ({status}): {error_text}");
+            return Err(Self::api_error(status, &headers, &error_text));
         }
 
         let result: GenerateContentResponse = response.json().await?;
@@ -1077,24 +1435,18 @@ impl GeminiProvider {
             anyhow::bail!("Gemini API error: {}", err.message);
         }
 
-        let usage = result.usage_metadata.map(|u| TokenUsage {
-            input_tokens: u.prompt_token_count,
-            output_tokens: u.candidates_token_count,
-        });
-
-        let text = result
-            .candidates
-            .and_then(|c| c.into_iter().next())
-            .and_then(|c| c.content)
-            .and_then(|c| c.effective_text())
-            .ok_or_else(|| anyhow::anyhow!("No response from Gemini"))?;
-
-        Ok((text, usage))
+        result.into_chat_response()
     }
 }
 
 #[async_trait]
 impl Provider for GeminiProvider {
+    fn convert_tools(&self, tools: &[ToolSpec]) -> ToolsPayload {
+        ToolsPayload::Gemini {
+            function_declarations: function_declarations(tools),
+        }
+    }
+
     async fn chat_with_system(
         &self,
         system_prompt: Option<&str>,
@@ -1104,22 +1456,18 @@ impl Provider for GeminiProvider {
     ) -> anyhow::Result<String> {
         let system_instruction = system_prompt.map(|sys| Content {
             role: None,
-            parts: vec![Part {
-                text: sys.to_string(),
-            }],
+            parts: vec![Part::text(sys)],
         });
 
         let contents = vec![Content {
             role: Some("user".to_string()),
-            parts: vec![Part {
-                text: message.to_string(),
-            }],
+            parts: vec![Part::text(message)],
         }];
 
-        let (text, _usage) = self
-            .send_generate_content(contents, system_instruction, model, temperature)
-            .await?;
-        Ok(text)
+        self.send_generate_content(contents, system_instruction, None, model, temperature)
+            .await?
+            .text
+            .ok_or_else(|| anyhow::anyhow!("No response from Gemini"))
     }
 
     async fn chat_with_history(
@@ -1128,50 +1476,11 @@ impl Provider for GeminiProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<String> {
-        let mut system_parts: Vec<&str> = Vec::new();
-        let mut contents: Vec<Content> = Vec::new();
-
-        for msg in messages {
-            match msg.role.as_str() {
-                "system" => {
-                    system_parts.push(&msg.content);
-                }
-                "user" => {
-                    contents.push(Content {
-                        role: Some("user".to_string()),
-                        parts: vec![Part {
-                            text: msg.content.clone(),
-                        }],
-                    });
-                }
-                "assistant" => {
-                    // Gemini API uses "model" role instead of "assistant"
-                    contents.push(Content {
-                        role: Some("model".to_string()),
-                        parts: vec![Part {
-                            text: msg.content.clone(),
-                        }],
-                    });
-                }
-                _ => {}
-            }
-        }
-
-        let system_instruction = if system_parts.is_empty() {
-            None
-        } else {
-            Some(Content {
-                role: None,
-                parts: vec![Part {
-                    text: system_parts.join("\n\n"),
-                }],
-            })
-        };
-
-        let (text, _usage) = self
-            .send_generate_content(contents, system_instruction, model, temperature)
-            .await?;
-        Ok(text)
+        let (system_instruction, contents) = convert_messages(messages);
+        self.send_generate_content(contents, system_instruction, None, model, temperature)
+            .await?
+            .text
+            .ok_or_else(|| anyhow::anyhow!("No response from Gemini"))
     }
 
     async fn chat(
@@ -1180,49 +1489,22 @@ impl Provider for GeminiProvider {
         model: &str,
         temperature: f64,
     ) -> anyhow::Result<ChatResponse> {
-        let mut system_parts: Vec<&str> = Vec::new();
-        let mut contents: Vec<Content> = Vec::new();
-
-        for msg in request.messages {
-            match msg.role.as_str() {
-                "system" => system_parts.push(&msg.content),
-                "user" => contents.push(Content {
-                    role: Some("user".to_string()),
-                    parts: vec![Part {
-                        text: msg.content.clone(),
-                    }],
-                }),
-                "assistant" => contents.push(Content {
-                    role: Some("model".to_string()),
-                    parts: vec![Part {
-                        text: msg.content.clone(),
-                    }],
-                }),
-                _ => {}
-            }
-        }
-
-        let system_instruction = if system_parts.is_empty() {
-            None
-        } else {
-            Some(Content {
-                role: None,
-                parts: vec![Part {
-                    text: system_parts.join("\n\n"),
-                }],
-            })
-        };
+        let (system_instruction, contents) = convert_messages(request.messages);
+        let tools = request
+            .tools
+            .filter(|tools| !tools.is_empty())
+            .map(|tools| {
+                vec![GeminiTool {
+                    function_declarations: function_declarations(tools),
+                }]
+            });
 
-        let (text, usage) = self
-            .send_generate_content(contents, system_instruction, model, temperature)
-            .await?;
+        self.send_generate_content(contents, system_instruction, tools, model, temperature)
+            .await
+    }
 
-        Ok(ChatResponse {
-            text: Some(text),
-            tool_calls: Vec::new(),
-            usage,
-            reasoning_content: None,
-        })
+    fn supports_native_tools(&self) -> bool {
+        true
     }
 
     async fn warmup(&self) -> anyhow::Result<()> {
@@ -1276,6 +1558,7 @@ mod tests {
             oauth_index: Arc::new(tokio::sync::Mutex::new(0)),
             auth_service: None,
             auth_profile_override: None,
+            safety_settings: Vec::new(),
         }
     }
 
@@ -1392,15 +1675,15 @@ mod tests {
         let body = GenerateContentRequest {
             contents: vec![Content {
                 role: Some("user".into()),
-                parts: vec![Part {
-                    text: "hello".into(),
-                }],
+                parts: vec![Part::text("hello")],
             }],
             system_instruction: None,
             generation_config: GenerationConfig {
                 temperature: 0.7,
                 max_output_tokens: 8192,
             },
+            tools: None,
+            safety_settings: Vec::new(),
         };
 
         let request = provider
@@ -1433,15 +1716,15 @@ mod tests {
         let body = GenerateContentRequest {
             contents: vec![Content {
                 role: Some("user".into()),
-                parts: vec![Part {
-                    text: "hello".into(),
-                }],
+                parts: vec![Part::text("hello")],
             }],
             system_instruction: None,
             generation_config: GenerationConfig {
                 temperature: 0.7,
                 max_output_tokens: 8192,
             },
+            tools: None,
+            safety_settings: Vec::new(),
         };
 
         let request = provider
@@ -1477,15 +1760,15 @@ mod tests {
         let body = GenerateContentRequest {
             contents: vec![Content {
                 role: Some("user".into()),
-                parts: vec![Part {
-                    text: "hello".into(),
-                }],
+                parts: vec![Part::text("hello")],
             }],
             system_instruction: None,
             generation_config: GenerationConfig {
                 temperature: 0.7,
                 max_output_tokens: 8192,
             },
+            tools: None,
+            safety_settings: Vec::new(),
         };
 
         let request = provider
@@ -1509,20 +1792,18 @@ mod tests {
         let request = GenerateContentRequest {
             contents: vec![Content {
                 role: Some("user".to_string()),
-                parts: vec![Part {
-                    text: "Hello".to_string(),
-                }],
+                parts: vec![Part::text("Hello")],
             }],
             system_instruction: Some(Content {
                 role: None,
-                parts: vec![Part {
-                    text: "You are helpful".to_string(),
-                }],
+                parts: vec![Part::text("You are helpful")],
             }),
             generation_config: GenerationConfig {
                 temperature: 0.7,
                 max_output_tokens: 8192,
             },
+            tools: None,
+            safety_settings: Vec::new(),
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -1543,15 +1824,15 @@ mod tests {
             request: InternalGenerateContentRequest {
                 contents: vec![Content {
                     role: Some("user".to_string()),
-                    parts: vec![Part {
-                        text: "Hello".to_string(),
-                    }],
+                    parts: vec![Part::text("Hello")],
                 }],
                 system_instruction: None,
                 generation_config: Some(GenerationConfig {
                     temperature: 0.7,
                     max_output_tokens: 8192,
                 }),
+                tools: None,
+                safety_settings: Vec::new(),
             },
         };
 
@@ -1575,12 +1856,12 @@ mod tests {
             request: InternalGenerateContentRequest {
                 contents: vec![Content {
                     role: Some("user".to_string()),
-                    parts: vec![Part {
-                        text: "Hello".to_string(),
-                    }],
+                    parts: vec![Part::text("Hello")],
                 }],
                 system_instruction: None,
                 generation_config: None,
+                tools: None,
+                safety_settings: Vec::new(),
             },
         };
 
@@ -1598,12 +1879,12 @@ mod tests {
             request: InternalGenerateContentRequest {
                 contents: vec![Content {
                     role: Some("user".to_string()),
-                    parts: vec![Part {
-                        text: "Hello".to_string(),
-                    }],
+                    parts: vec![Part::text("Hello")],
                 }],
                 system_instruction: None,
                 generation_config: None,
+                tools: None,
+                safety_settings: Vec::new(),
             },
         };
 
@@ -1942,4 +2223,124 @@ mod tests {
         let resp: GenerateContentResponse = serde_json::from_str(json).unwrap();
         assert!(resp.usage_metadata.is_none());
     }
+
+    // ── Function calling, finish reasons, safety settings ─────────────────
+
+    #[test]
+    fn convert_messages_maps_tool_calls_to_function_parts() {
+        let messages = vec![
+            ChatMessage::system("Be brief"),
+            ChatMessage::user("Weather in Paris and Rome?"),
+            ChatMessage::assistant(
+                r#"{"content":"Checking","tool_calls":[{"id":"c1","name":"weather","arguments":"{\"city\":\"Paris\"}"},{"id":"c2","name":"weather","arguments":"{\"city\":\"Rome\"}"}]}"#,
+            ),
+            ChatMessage::tool(r#"{"tool_call_id":"c1","content":"18C"}"#),
+            ChatMessage::tool(r#"{"tool_call_id":"c2","content":"24C"}"#),
+        ];
+
+        let (system, contents) = convert_messages(&messages);
+        assert_eq!(system.unwrap().parts[0].text.as_deref(), Some("Be brief"));
+        let json = serde_json::to_value(&contents).unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(json[1]["role"], "model");
+        assert_eq!(json[1]["parts"][0]["text"], "Checking");
+        assert_eq!(json[1]["parts"][1]["functionCall"]["name"], "weather");
+        assert_eq!(json[1]["parts"][2]["functionCall"]["args"]["city"], "Rome");
+        assert_eq!(json[2]["role"], "user");
+        assert_eq!(
+            json[2]["parts"][1]["functionResponse"],
+            serde_json::json!({"name": "weather", "response": {"content": "24C"}})
+        );
+    }
+
+    #[test]
+    fn response_function_calls_and_finish_reasons() {
+        let parse = |json: &str| {
+            serde_json::from_str::<GenerateContentResponse>(json)
+                .unwrap()
+                .into_chat_response()
+        };
+
+        let reply = parse(
+            r#"{"candidates": [{"content": {"parts": [{"functionCall": {"name": "shell", "args": {"command": "ls"}}}]}, "finishReason": "STOP"}]}"#,
+        )
+        .unwrap();
+        assert!(reply.text.is_none());
+        assert_eq!(reply.tool_calls[0].name, "shell");
+        assert_eq!(reply.tool_calls[0].arguments, r#"{"command":"ls"}"#);
+
+        let truncated =
+            parse(r#"{"candidates": [{"content": {"parts": [{"text": "Part"}]}, "finishReason": "MAX_TOKENS"}]}"#)
+                .unwrap();
+        assert_eq!(truncated.text.as_deref(), Some("Part"));
+
+        let blocked = parse(r#"{"candidates": [{"finishReason": "SAFETY"}]}"#).unwrap_err();
+        let blocked = ProviderError::find(&blocked).unwrap();
+        assert_eq!(blocked.kind, ProviderErrorKind::InvalidRequest);
+        assert!(blocked.message.contains("finishReason=SAFETY"));
+
+        let malformed =
+            parse(r#"{"candidates": [{"finishReason": "MALFORMED_FUNCTION_CALL"}]}"#).unwrap_err();
+        assert!(ProviderError::find(&malformed).unwrap().is_retryable());
+
+        let prompt =
+            parse(r#"{"promptFeedback": {"blockReason": "PROHIBITED_CONTENT"}}"#).unwrap_err();
+        assert!(!ProviderError::find(&prompt).unwrap().is_retryable());
+    }
+
+    #[test]
+    fn safety_threshold_and_tool_declarations_serialize() {
+        let provider = test_provider(None).with_safety_threshold(Some("block_only_high"));
+        assert_eq!(provider.safety_settings.len(), SAFETY_CATEGORIES.len());
+        assert!(provider
+            .safety_settings
+            .iter()
+            .all(|s| s.threshold == "BLOCK_ONLY_HIGH"));
+        let ignored = test_provider(None).with_safety_threshold(Some("sometimes"));
+        assert!(ignored.safety_settings.is_empty());
+
+        let declarations = function_declarations(&[
+            ToolSpec {
+                name: "shell".to_string(),
+                description: "Run a command".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {"command": {"type": "string"}},
+                    "additionalProperties": false
+                }),
+            },
+            ToolSpec {
+                name: "memory_forget_all".to_string(),
+                description: "Clear memory".to_string(),
+                parameters: serde_json::json!({}),
+            },
+        ]);
+        assert_eq!(
+            declarations[0]["parameters"]["properties"]["command"]["type"],
+            "string"
+        );
+        assert!(declarations[0]["parameters"]
+            .get("additionalProperties")
+            .is_none());
+        assert!(declarations[1].get("parameters").is_none());
+
+        let request = GenerateContentRequest {
+            contents: Vec::new(),
+            system_instruction: None,
+            generation_config: GenerationConfig {
+                temperature: 0.7,
+                max_output_tokens: 8192,
+            },
+            tools: Some(vec![GeminiTool {
+                function_declarations: declarations,
+            }]),
+            safety_settings: provider.safety_settings.clone(),
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["tools"][0]["functionDeclarations"][0]["name"], "shell");
+        assert_eq!(
+            json["safetySettings"][0],
+            serde_json::json!({"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_ONLY_HIGH"})
+        );
+    }
 }
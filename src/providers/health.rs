@@ -0,0 +1,291 @@
+//! Provider health tracking with a circuit breaker per provider.
+//!
+//! After `failure_threshold` consecutive transient failures a provider's
+//! circuit opens: the reliability wrapper skips it, failing over to the next
+//! provider, until the cooldown passes. The circuit then half-opens and
+//! admits a single trial — the next call or health probe — whose result
+//! closes the circuit or opens it again; other calls keep being skipped
+//! meanwhile.
+
+use crate::config::ReliabilityConfig;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through.
+    Closed,
+    /// Calls are skipped until the cooldown passes.
+    Open,
+    /// Cooldown passed; one trial call decides whether the circuit closes or
+    /// re-opens.
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
+            Self::HalfOpen => "half_open",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitSettings {
+    /// Consecutive failures that open a circuit. `0` disables the breaker.
+    pub failure_threshold: u32,
+    /// How long an open circuit skips calls before half-opening.
+    pub cooldown: Duration,
+}
+
+impl CircuitSettings {
+    pub fn from_config(reliability: &ReliabilityConfig) -> Self {
+        Self {
+            failure_threshold: reliability.circuit_breaker_threshold,
+            cooldown: Duration::from_secs(reliability.circuit_breaker_cooldown_secs),
+        }
+    }
+}
+
+/// A circuit changing state, reported to observers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitTransition {
+    pub provider: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Instant,
+    /// When the half-open trial call was admitted.
+    trial_started: Option<Instant>,
+}
+
+impl Default for Breaker {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: Instant::now(),
+            trial_started: None,
+        }
+    }
+}
+
+impl Breaker {
+    /// Time left before this circuit admits a call: the rest of an open
+    /// circuit's cooldown, or, while a half-open trial is in flight, until
+    /// that trial is presumed lost (it was cancelled without a result).
+    fn wait(&self, cooldown: Duration) -> Option<Duration> {
+        let since = match self.state {
+            CircuitState::Closed => return None,
+            CircuitState::Open => self.opened_at,
+            CircuitState::HalfOpen => self.trial_started?,
+        };
+        cooldown
+            .checked_sub(since.elapsed())
+            .filter(|left| !left.is_zero())
+    }
+}
+
+/// Circuit breakers keyed by provider name.
+pub struct ProviderHealth {
+    settings: Mutex<CircuitSettings>,
+    breakers: Mutex<HashMap<String, Breaker>>,
+}
+
+impl ProviderHealth {
+    pub fn new(settings: CircuitSettings) -> Self {
+        Self {
+            settings: Mutex::new(settings),
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The process-wide tracker, so every client of a provider shares one
+    /// circuit. Settings are updated on each call to follow config reloads.
+    pub fn shared(settings: CircuitSettings) -> Arc<Self> {
+        static SHARED: OnceLock<Arc<ProviderHealth>> = OnceLock::new();
+        let health = SHARED.get_or_init(|| Arc::new(Self::new(settings)));
+        *health.settings.lock() = settings;
+        Arc::clone(health)
+    }
+
+    pub fn state(&self, provider: &str) -> CircuitState {
+        self.breakers
+            .lock()
+            .get(provider)
+            .map_or(CircuitState::Closed, |breaker| breaker.state)
+    }
+
+    /// Whether a call to `provider` may go through. An open circuit whose
+    /// cooldown has passed half-opens and admits the call as its trial,
+    /// reported as the returned transition; a half-open circuit admits no
+    /// other call until the trial reports. `Err` carries the time left.
+    pub fn admit(&self, provider: &str) -> Result<Option<CircuitTransition>, Duration> {
+        let settings = *self.settings.lock();
+        if settings.failure_threshold == 0 {
+            return Ok(None);
+        }
+        let mut breakers = self.breakers.lock();
+        let Some(breaker) = breakers.get_mut(provider) else {
+            return Ok(None);
+        };
+        if breaker.state == CircuitState::Closed {
+            return Ok(None);
+        }
+        if let Some(left) = breaker.wait(settings.cooldown) {
+            return Err(left);
+        }
+        breaker.trial_started = Some(Instant::now());
+        if breaker.state == CircuitState::HalfOpen {
+            return Ok(None);
+        }
+        breaker.state = CircuitState::HalfOpen;
+        Ok(Some(transition(provider, breaker)))
+    }
+
+    /// A successful call closes the circuit.
+    pub fn record_success(&self, provider: &str) -> Option<CircuitTransition> {
+        let mut breakers = self.breakers.lock();
+        let breaker = breakers.get_mut(provider)?;
+        let was = breaker.state;
+        breaker.state = CircuitState::Closed;
+        breaker.consecutive_failures = 0;
+        breaker.trial_started = None;
+        (was != CircuitState::Closed).then(|| transition(provider, breaker))
+    }
+
+    /// A failed call opens the circuit once the threshold is reached, or at
+    /// once when half-open. Failures on an open circuit restart its cooldown.
+    pub fn record_failure(&self, provider: &str) -> Option<CircuitTransition> {
+        let settings = *self.settings.lock();
+        if settings.failure_threshold == 0 {
+            return None;
+        }
+        let mut breakers = self.breakers.lock();
+        let breaker = breakers.entry(provider.to_string()).or_default();
+        breaker.consecutive_failures = breaker.consecutive_failures.saturating_add(1);
+        breaker.trial_started = None;
+        let opens = match breaker.state {
+            CircuitState::Closed => breaker.consecutive_failures >= settings.failure_threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => {
+                breaker.opened_at = Instant::now();
+                false
+            }
+        };
+        if !opens {
+            return None;
+        }
+        breaker.state = CircuitState::Open;
+        breaker.opened_at = Instant::now();
+        Some(transition(provider, breaker))
+    }
+
+    /// Whether `provider` is due a health probe: its circuit is not closed
+    /// and would admit a trial call now.
+    pub fn needs_probe(&self, provider: &str) -> bool {
+        let cooldown = self.settings.lock().cooldown;
+        self.breakers.lock().get(provider).is_some_and(|breaker| {
+            breaker.state != CircuitState::Closed && breaker.wait(cooldown).is_none()
+        })
+    }
+}
+
+fn transition(provider: &str, breaker: &Breaker) -> CircuitTransition {
+    CircuitTransition {
+        provider: provider.to_string(),
+        state: breaker.state,
+        consecutive_failures: breaker.consecutive_failures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health(failure_threshold: u32, cooldown: Duration) -> ProviderHealth {
+        ProviderHealth::new(CircuitSettings {
+            failure_threshold,
+            cooldown,
+        })
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let health = health(3, Duration::from_secs(60));
+        assert!(health.record_failure("openai").is_none());
+        assert!(health.record_success("openai").is_none());
+        assert!(health.record_failure("openai").is_none());
+        assert!(health.record_failure("openai").is_none());
+        let opened = health.record_failure("openai").unwrap();
+        assert_eq!(opened.state, CircuitState::Open);
+        assert_eq!(opened.consecutive_failures, 3);
+
+        let remaining = health.admit("openai").unwrap_err();
+        assert!(remaining > Duration::from_secs(59));
+        assert_eq!(health.admit("anthropic"), Ok(None));
+    }
+
+    #[test]
+    fn half_opens_after_cooldown_and_closes_on_success() {
+        let health = health(1, Duration::ZERO);
+        health.record_failure("openai");
+        assert!(health.needs_probe("openai"));
+
+        let half_open = health.admit("openai").unwrap().unwrap();
+        assert_eq!(half_open.state, CircuitState::HalfOpen);
+        let closed = health.record_success("openai").unwrap();
+        assert_eq!(closed.state, CircuitState::Closed);
+        assert_eq!(closed.consecutive_failures, 0);
+        assert!(!health.needs_probe("openai"));
+    }
+
+    #[test]
+    fn half_open_circuit_admits_a_single_trial() {
+        let health = health(1, Duration::from_millis(50));
+        health.record_failure("openai");
+        std::thread::sleep(Duration::from_millis(60));
+
+        assert!(health.admit("openai").unwrap().is_some());
+        assert!(health.admit("openai").is_err());
+        assert!(!health.needs_probe("openai"));
+
+        // A trial that never reports stops blocking after another cooldown.
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(health.needs_probe("openai"));
+        assert_eq!(health.admit("openai"), Ok(None));
+        health.record_success("openai");
+        assert_eq!(health.admit("openai"), Ok(None));
+        assert_eq!(health.admit("openai"), Ok(None));
+    }
+
+    #[test]
+    fn half_open_failure_reopens_at_once() {
+        let health = health(5, Duration::ZERO);
+        for _ in 0..5 {
+            health.record_failure("openai");
+        }
+        health.admit("openai").unwrap();
+        let reopened = health.record_failure("openai").unwrap();
+        assert_eq!(reopened.state, CircuitState::Open);
+    }
+
+    #[test]
+    fn zero_threshold_disables_the_breaker() {
+        let health = health(0, Duration::from_secs(60));
+        for _ in 0..10 {
+            assert!(health.record_failure("openai").is_none());
+        }
+        assert_eq!(health.admit("openai"), Ok(None));
+        assert_eq!(health.state("openai"), CircuitState::Closed);
+    }
+}
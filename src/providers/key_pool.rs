@@ -0,0 +1,441 @@
+use super::reliable::{is_non_retryable_rate_limit, is_rate_limited};
+use super::traits::{
+    build_tool_instructions_text, chat_once_stream, ChatDelta, ChatMessage, ChatRequest,
+    ChatResponse, ProviderCapabilities, StreamChunk, StreamOptions, StreamResult, ToolsPayload,
+};
+use super::{Provider, ProviderError};
+use crate::tools::ToolSpec;
+use async_trait::async_trait;
+use futures_util::future::BoxFuture;
+use futures_util::{stream, StreamExt};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// How long a key sits out after a plain 429 without a `Retry-After` header.
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Usage counters for one pooled key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyStats {
+    /// Last four characters of the key, for logs and status output.
+    pub label: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub rate_limited: u64,
+    /// Out of rotation after a quota error or 429.
+    pub parked: bool,
+}
+
+#[derive(Debug, Default)]
+struct KeyState {
+    requests: u64,
+    errors: u64,
+    rate_limited: u64,
+    parked_until: Option<Instant>,
+}
+
+impl KeyState {
+    fn is_parked(&self, now: Instant) -> bool {
+        self.parked_until.is_some_and(|until| until > now)
+    }
+}
+
+struct PooledKey {
+    label: String,
+    provider: Box<dyn Provider>,
+    state: Mutex<KeyState>,
+}
+
+/// One provider behind several API keys, each with its own client.
+///
+/// Requests go to the keys round-robin. A key answering 429 sits out for the
+/// `Retry-After` wait (or a minute) and the request moves on to the next key;
+/// a key answering with a quota or billing error is parked for `quota_park`.
+/// When every key is parked, the one whose park ends first is tried. Other
+/// errors are returned as-is so [`ReliableProvider`](super::reliable::ReliableProvider)
+/// can retry or fail over.
+pub struct KeyPoolProvider {
+    name: String,
+    keys: Vec<PooledKey>,
+    next: AtomicUsize,
+    quota_park: Duration,
+}
+
+impl KeyPoolProvider {
+    /// `keys` pairs each API key with a provider client built for it.
+    pub fn new(name: &str, keys: Vec<(String, Box<dyn Provider>)>, quota_park: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            keys: keys
+                .into_iter()
+                .map(|(key, provider)| PooledKey {
+                    label: key_label(&key),
+                    provider,
+                    state: Mutex::new(KeyState::default()),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+            quota_park,
+        }
+    }
+
+    pub fn key_stats(&self) -> Vec<KeyStats> {
+        let now = Instant::now();
+        self.keys
+            .iter()
+            .map(|key| {
+                let state = key.state.lock();
+                KeyStats {
+                    label: key.label.clone(),
+                    requests: state.requests,
+                    errors: state.errors,
+                    rate_limited: state.rate_limited,
+                    parked: state.is_parked(now),
+                }
+            })
+            .collect()
+    }
+
+    /// Keys to try for one request: the unparked ones starting at the next
+    /// round-robin position, or the key whose park ends first.
+    fn attempt_order(&self) -> Vec<usize> {
+        if self.keys.is_empty() {
+            return Vec::new();
+        }
+        let now = Instant::now();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let order: Vec<usize> = (0..self.keys.len())
+            .map(|offset| (start + offset) % self.keys.len())
+            .collect();
+        let available: Vec<usize> = order
+            .iter()
+            .copied()
+            .filter(|&index| !self.keys[index].state.lock().is_parked(now))
+            .collect();
+        if !available.is_empty() {
+            return available;
+        }
+        order
+            .into_iter()
+            .min_by_key(|&index| self.keys[index].state.lock().parked_until)
+            .into_iter()
+            .collect()
+    }
+
+    /// Count a failed call and park the key when it was throttled. Returns
+    /// whether the request should move on to the next key.
+    fn record_failure(&self, key: &PooledKey, err: &anyhow::Error) -> bool {
+        let mut state = key.state.lock();
+        state.errors += 1;
+        if !is_rate_limited(err) {
+            return false;
+        }
+        state.rate_limited += 1;
+        let park = if is_non_retryable_rate_limit(err) {
+            self.quota_park
+        } else {
+            ProviderError::find(err)
+                .and_then(ProviderError::retry_after)
+                .unwrap_or(RATE_LIMIT_COOLDOWN)
+        };
+        state.parked_until = Some(Instant::now() + park);
+        tracing::warn!(
+            provider = self.name.as_str(),
+            key = key.label.as_str(),
+            park_secs = park.as_secs(),
+            "API key throttled; parking it and rotating to the next key"
+        );
+        true
+    }
+
+    async fn call<'a, T: Send>(
+        &'a self,
+        call: impl Fn(&'a dyn Provider) -> BoxFuture<'a, anyhow::Result<T>> + Send,
+    ) -> anyhow::Result<T> {
+        let mut last_error = None;
+        for index in self.attempt_order() {
+            let key = &self.keys[index];
+            key.state.lock().requests += 1;
+            match call(key.provider.as_ref()).await {
+                Ok(value) => return Ok(value),
+                Err(err) if self.record_failure(key, &err) => last_error = Some(err),
+                Err(err) => return Err(err),
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| anyhow::anyhow!("No API keys configured for {}", self.name)))
+    }
+
+    fn first_provider(&self) -> Option<&dyn Provider> {
+        self.keys.first().map(|key| key.provider.as_ref())
+    }
+}
+
+/// `...abcd` for a key, never the whole secret.
+fn key_label(key: &str) -> String {
+    let tail: String = key
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("...{tail}")
+}
+
+#[async_trait]
+impl Provider for KeyPoolProvider {
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.first_provider()
+            .map(|provider| provider.capabilities())
+            .unwrap_or_default()
+    }
+
+    fn convert_tools(&self, tools: &[ToolSpec]) -> ToolsPayload {
+        match self.first_provider() {
+            Some(provider) => provider.convert_tools(tools),
+            None => ToolsPayload::PromptGuided {
+                instructions: build_tool_instructions_text(tools),
+            },
+        }
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.call(|provider| provider.chat_with_system(system_prompt, message, model, temperature))
+            .await
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.call(|provider| provider.chat_with_history(messages, model, temperature))
+            .await
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.call(|provider| provider.chat(request, model, temperature))
+            .await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[serde_json::Value],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.call(|provider| provider.chat_with_tools(messages, tools, model, temperature))
+            .await
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        self.call(|provider| provider.chat_structured(messages, schema, model, temperature))
+            .await
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.first_provider()
+            .is_some_and(|provider| provider.supports_native_tools())
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.first_provider()
+            .is_some_and(|provider| provider.supports_vision())
+    }
+
+    async fn warmup(&self) -> anyhow::Result<()> {
+        match self.first_provider() {
+            Some(provider) => provider.warmup().await,
+            None => Ok(()),
+        }
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.first_provider()
+            .is_some_and(|provider| provider.supports_streaming())
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        // Blocking calls keep key failover; streams go to one key like below.
+        if !self.supports_streaming() {
+            return chat_once_stream(self, request, model, temperature);
+        }
+        let index = self.attempt_order().first().copied().unwrap_or_default();
+        match self.keys.get(index) {
+            Some(key) => {
+                key.state.lock().requests += 1;
+                key.provider.chat_stream(request, model, temperature)
+            }
+            None => chat_once_stream(self, request, model, temperature),
+        }
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        // Streams are not retried, so no failover; just spread them over keys.
+        let index = self.attempt_order().first().copied().unwrap_or_default();
+        match self.keys.get(index) {
+            Some(key) => {
+                key.state.lock().requests += 1;
+                key.provider.stream_chat_with_system(
+                    system_prompt,
+                    message,
+                    model,
+                    temperature,
+                    options,
+                )
+            }
+            None => stream::empty().boxed(),
+        }
+    }
+
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        let index = self.attempt_order().first().copied().unwrap_or_default();
+        match self.keys.get(index) {
+            Some(key) => {
+                key.state.lock().requests += 1;
+                key.provider
+                    .stream_chat_with_history(messages, model, temperature, options)
+            }
+            None => stream::empty().boxed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    struct KeyedProvider {
+        key: &'static str,
+        error: Option<&'static str>,
+        calls: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl Provider for KeyedProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            self.calls.lock().push(self.key);
+            match self.error {
+                Some(error) => anyhow::bail!("{error}"),
+                None => Ok(format!("answered by {}", self.key)),
+            }
+        }
+    }
+
+    fn pool(
+        keys: &[(&'static str, Option<&'static str>)],
+    ) -> (KeyPoolProvider, Arc<Mutex<Vec<&'static str>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let providers = keys
+            .iter()
+            .map(|&(key, error)| {
+                let provider: Box<dyn Provider> = Box::new(KeyedProvider {
+                    key,
+                    error,
+                    calls: Arc::clone(&calls),
+                });
+                (key.to_string(), provider)
+            })
+            .collect();
+        (
+            KeyPoolProvider::new("openai", providers, Duration::from_secs(3600)),
+            calls,
+        )
+    }
+
+    #[tokio::test]
+    async fn requests_rotate_across_keys_round_robin() {
+        let (provider, calls) = pool(&[("sk-aaaa", None), ("sk-bbbb", None), ("sk-cccc", None)]);
+        for _ in 0..4 {
+            provider.simple_chat("hi", "gpt-4o", 0.0).await.unwrap();
+        }
+        assert_eq!(
+            *calls.lock(),
+            vec!["sk-aaaa", "sk-bbbb", "sk-cccc", "sk-aaaa"]
+        );
+        assert_eq!(provider.key_stats()[0].label, "...aaaa");
+        assert_eq!(provider.key_stats()[0].requests, 2);
+    }
+
+    #[tokio::test]
+    async fn quota_errors_park_the_key_and_move_to_the_next() {
+        let (provider, calls) = pool(&[
+            (
+                "sk-aaaa",
+                Some("429 Too Many Requests: insufficient_quota for this org"),
+            ),
+            ("sk-bbbb", None),
+        ]);
+        let reply = provider.simple_chat("hi", "gpt-4o", 0.0).await.unwrap();
+        assert_eq!(reply, "answered by sk-bbbb");
+        provider.simple_chat("hi", "gpt-4o", 0.0).await.unwrap();
+        provider.simple_chat("hi", "gpt-4o", 0.0).await.unwrap();
+        assert_eq!(
+            *calls.lock(),
+            vec!["sk-aaaa", "sk-bbbb", "sk-bbbb", "sk-bbbb"]
+        );
+
+        let stats = provider.key_stats();
+        assert!(stats[0].parked);
+        assert_eq!((stats[0].errors, stats[0].rate_limited), (1, 1));
+        assert!(!stats[1].parked);
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_returned_without_rotating() {
+        let (provider, calls) = pool(&[
+            ("sk-aaaa", Some("500 Internal Server Error")),
+            ("sk-bbbb", None),
+        ]);
+        assert!(provider.simple_chat("hi", "gpt-4o", 0.0).await.is_err());
+        assert_eq!(*calls.lock(), vec!["sk-aaaa"]);
+        assert!(!provider.key_stats()[0].parked);
+    }
+}
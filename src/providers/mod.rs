@@ -17,30 +17,46 @@
 //! in [`create_provider_with_url`]. See `AGENTS.md` §7.1 for the full change playbook.
 
 pub mod anthropic;
+pub mod azure_openai;
 pub mod bedrock;
+pub mod caching;
 pub mod compatible;
 pub mod copilot;
 pub mod gemini;
+pub mod health;
+pub mod key_pool;
 pub mod ollama;
 pub mod openai;
 pub mod openai_codex;
 pub mod openrouter;
+pub mod params;
+pub mod rate_limited;
+pub mod recording;
 pub mod reliable;
 pub mod router;
+pub mod simulation;
+pub mod structured;
 pub mod telnyx;
+pub mod timeout;
 pub mod traits;
 
 #[allow(unused_imports)]
 pub use traits::{
-    ChatMessage, ChatRequest, ChatResponse, ConversationMessage, Provider, ProviderCapabilityError,
-    ToolCall, ToolResultMessage,
+    ChatDelta, ChatMessage, ChatRequest, ChatResponse, ConversationMessage, Provider,
+    ProviderCapabilityError, ProviderError, ProviderErrorKind, ToolCall, ToolResultMessage,
 };
 
 use crate::auth::AuthService;
+use crate::observability::Observer;
 use compatible::{AuthStyle, OpenAiCompatibleProvider};
+use health::{CircuitSettings, ProviderHealth};
+use key_pool::KeyPoolProvider;
+use rate_limited::RateLimitedProvider;
 use reliable::ReliableProvider;
 use serde::Deserialize;
 use std::path::PathBuf;
+use std::sync::Arc;
+use timeout::TimeoutProvider;
 
 const MAX_API_ERROR_CHARS: usize = 200;
 const MINIMAX_INTL_BASE_URL: &str = "https://api.minimax.io/v1";
@@ -669,12 +685,35 @@ fn zai_base_url(name: &str) -> Option<&'static str> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ProviderRuntimeOptions {
     pub auth_profile_override: Option<String>,
     pub zeroclaw_dir: Option<PathBuf>,
     pub secrets_encrypt: bool,
     pub reasoning_enabled: Option<bool>,
+    pub max_tokens: Option<u32>,
+    pub ollama_keep_alive: Option<String>,
+    pub gemini_safety_threshold: Option<String>,
+    /// Receives provider-level metrics such as rate-limit waits.
+    pub observer: Option<Arc<dyn Observer>>,
+}
+
+impl std::fmt::Debug for ProviderRuntimeOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderRuntimeOptions")
+            .field("auth_profile_override", &self.auth_profile_override)
+            .field("zeroclaw_dir", &self.zeroclaw_dir)
+            .field("secrets_encrypt", &self.secrets_encrypt)
+            .field("reasoning_enabled", &self.reasoning_enabled)
+            .field("max_tokens", &self.max_tokens)
+            .field("ollama_keep_alive", &self.ollama_keep_alive)
+            .field("gemini_safety_threshold", &self.gemini_safety_threshold)
+            .field(
+                "observer",
+                &self.observer.as_ref().map(|observer| observer.name()),
+            )
+            .finish()
+    }
 }
 
 impl Default for ProviderRuntimeOptions {
@@ -684,6 +723,10 @@ impl Default for ProviderRuntimeOptions {
             zeroclaw_dir: None,
             secrets_encrypt: true,
             reasoning_enabled: None,
+            max_tokens: None,
+            ollama_keep_alive: None,
+            gemini_safety_threshold: None,
+            observer: None,
         }
     }
 }
@@ -765,12 +808,13 @@ pub fn sanitize_api_error(input: &str) -> String {
 /// Build a sanitized provider error from a failed HTTP response.
 pub async fn api_error(provider: &str, response: reqwest::Response) -> anyhow::Error {
     let status = response.status();
+    let headers = response.headers().clone();
     let body = response
         .text()
         .await
         .unwrap_or_else(|_| "<failed to read provider error body>".to_string());
     let sanitized = sanitize_api_error(&body);
-    anyhow::anyhow!("{provider} API error ({status}): {sanitized}")
+    ProviderError::from_status(provider, status, &headers, &sanitized).into()
 }
 
 /// Resolve API key for a provider from config and environment variables.
@@ -810,6 +854,7 @@ fn resolve_provider_credential(name: &str, credential_override: Option<&str>) ->
         "anthropic" => vec!["ANTHROPIC_OAUTH_TOKEN", "ANTHROPIC_API_KEY"],
         "openrouter" => vec!["OPENROUTER_API_KEY"],
         "openai" => vec!["OPENAI_API_KEY"],
+        "azure" | "azure-openai" => vec!["AZURE_OPENAI_API_KEY"],
         "ollama" => vec!["OLLAMA_API_KEY"],
         "venice" => vec!["VENICE_API_KEY"],
         "groq" => vec!["GROQ_API_KEY"],
@@ -955,14 +1000,16 @@ fn create_provider_with_url_and_options(
     match name {
         // ── Primary providers (custom implementations) ───────
         "openrouter" => Ok(Box::new(openrouter::OpenRouterProvider::new(key))),
-        "anthropic" => Ok(Box::new(anthropic::AnthropicProvider::new(key))),
+        "anthropic" => Ok(Box::new(
+            anthropic::AnthropicProvider::new(key).with_max_tokens(options.max_tokens),
+        )),
         "openai" => Ok(Box::new(openai::OpenAiProvider::with_base_url(api_url, key))),
+        simulation::SIMULATION_PROVIDER => Ok(Box::new(simulation::SimulationProvider::new())),
         // Ollama uses api_url for custom base URL (e.g. remote Ollama instance)
-        "ollama" => Ok(Box::new(ollama::OllamaProvider::new_with_reasoning(
-            api_url,
-            key,
-            options.reasoning_enabled,
-        ))),
+        "ollama" => Ok(Box::new(
+            ollama::OllamaProvider::new_with_reasoning(api_url, key, options.reasoning_enabled)
+                .with_keep_alive(options.ollama_keep_alive.clone()),
+        )),
         "gemini" | "google" | "google-gemini" => {
             let state_dir = options
                 .zeroclaw_dir
@@ -974,11 +1021,14 @@ fn create_provider_with_url_and_options(
                     )
                 });
             let auth_service = AuthService::new(&state_dir, options.secrets_encrypt);
-            Ok(Box::new(gemini::GeminiProvider::new_with_auth(
-                key,
-                auth_service,
-                options.auth_profile_override.clone(),
-            )))
+            Ok(Box::new(
+                gemini::GeminiProvider::new_with_auth(
+                    key,
+                    auth_service,
+                    options.auth_profile_override.clone(),
+                )
+                .with_safety_threshold(options.gemini_safety_threshold.as_deref()),
+            ))
         }
         "telnyx" => Ok(Box::new(telnyx::TelnyxProvider::new(key))),
 
@@ -1042,6 +1092,9 @@ fn create_provider_with_url_and_options(
             )
         )),
         "bedrock" | "aws-bedrock" => Ok(Box::new(bedrock::BedrockProvider::new())),
+        "azure" | "azure-openai" => Ok(Box::new(
+            azure_openai::AzureOpenAiProvider::from_config(api_url, key),
+        )),
         name if is_qwen_oauth_alias(name) => {
             let base_url = api_url
                 .map(str::trim)
@@ -1218,10 +1271,10 @@ fn create_provider_with_url_and_options(
                 "Anthropic-custom provider",
                 "anthropic-custom:https://your-api.com",
             )?;
-            Ok(Box::new(anthropic::AnthropicProvider::with_base_url(
-                key,
-                Some(&base_url),
-            )))
+            Ok(Box::new(
+                anthropic::AnthropicProvider::with_base_url(key, Some(&base_url))
+                    .with_max_tokens(options.max_tokens),
+            ))
         }
 
         _ => anyhow::bail!(
@@ -1248,6 +1301,72 @@ fn parse_provider_profile(s: &str) -> (&str, Option<&str>) {
     }
 }
 
+/// Build `name` once per distinct key in `keys` behind a [`KeyPoolProvider`],
+/// or just once (with the only key, or none) when there is nothing to rotate.
+fn with_key_rotation(
+    name: &str,
+    keys: Vec<String>,
+    reliability: &crate::config::ReliabilityConfig,
+    build: impl Fn(Option<&str>) -> anyhow::Result<Box<dyn Provider>>,
+) -> anyhow::Result<Box<dyn Provider>> {
+    let mut distinct: Vec<String> = Vec::new();
+    for key in keys {
+        let key = key.trim().to_string();
+        if !key.is_empty() && !distinct.contains(&key) {
+            distinct.push(key);
+        }
+    }
+    if distinct.len() < 2 {
+        return build(distinct.first().map(String::as_str));
+    }
+    let pooled = distinct
+        .into_iter()
+        .map(|key| {
+            let provider = build(Some(&key))?;
+            Ok((key, provider))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Box::new(KeyPoolProvider::new(
+        name,
+        pooled,
+        std::time::Duration::from_secs(reliability.api_key_park_secs),
+    )))
+}
+
+/// Put `provider` behind its `reliability.provider_rate_limits` budget, if
+/// one is configured for `name`.
+fn with_rate_limit(
+    name: &str,
+    provider: Box<dyn Provider>,
+    reliability: &crate::config::ReliabilityConfig,
+    options: &ProviderRuntimeOptions,
+) -> Box<dyn Provider> {
+    match reliability.provider_rate_limits.get(name) {
+        Some(limits) => Box::new(
+            RateLimitedProvider::new(name, provider, limits)
+                .with_observer(options.observer.clone()),
+        ),
+        None => provider,
+    }
+}
+
+/// Bound calls by `reliability.provider_timeout_secs` and the task's
+/// cancellation token.
+fn with_request_timeout(
+    name: &str,
+    provider: Box<dyn Provider>,
+    reliability: &crate::config::ReliabilityConfig,
+) -> Box<dyn Provider> {
+    match reliability.provider_timeout_secs {
+        0 => provider,
+        secs => Box::new(TimeoutProvider::new(
+            name,
+            provider,
+            std::time::Duration::from_secs(secs),
+        )),
+    }
+}
+
 /// Create provider chain with retry and fallback behavior.
 pub fn create_resilient_provider(
     primary_name: &str,
@@ -1274,12 +1393,38 @@ pub fn create_resilient_provider_with_options(
 ) -> anyhow::Result<Box<dyn Provider>> {
     let mut providers: Vec<(String, Box<dyn Provider>)> = Vec::new();
 
-    let primary_provider = match primary_name {
-        "openai-codex" | "openai_codex" | "codex" => {
-            create_provider_with_options(primary_name, api_key, options)?
-        }
-        _ => create_provider_with_url_and_options(primary_name, api_key, api_url, options)?,
-    };
+    let primary_keys = api_key
+        .map(str::to_string)
+        .into_iter()
+        .chain(reliability.api_keys.iter().cloned())
+        .chain(
+            reliability
+                .provider_api_keys
+                .get(primary_name)
+                .into_iter()
+                .flatten()
+                .cloned(),
+        )
+        .collect();
+    let primary_provider =
+        with_key_rotation(
+            primary_name,
+            primary_keys,
+            reliability,
+            |key| match primary_name {
+                "openai-codex" | "openai_codex" | "codex" => {
+                    create_provider_with_options(primary_name, key, options)
+                }
+                _ => create_provider_with_url_and_options(primary_name, key, api_url, options),
+            },
+        )?;
+    // The deadline and cancellation token wrap the rate limiter, so waiting
+    // for budget is bounded like the request itself.
+    let primary_provider = with_request_timeout(
+        primary_name,
+        with_rate_limit(primary_name, primary_provider, reliability, options),
+        reliability,
+    );
     providers.push((primary_name.to_string(), primary_provider));
 
     for fallback in &reliability.fallback_providers {
@@ -1307,8 +1452,24 @@ pub fn create_resilient_provider_with_options(
             None => options.clone(),
         };
 
-        match create_provider_with_options(provider_name, None, &fallback_options) {
-            Ok(provider) => providers.push((fallback.clone(), provider)),
+        let fallback_keys = reliability
+            .provider_api_keys
+            .get(fallback.as_str())
+            .or_else(|| reliability.provider_api_keys.get(provider_name))
+            .cloned()
+            .unwrap_or_default();
+        let fallback_provider = with_key_rotation(fallback, fallback_keys, reliability, |key| {
+            create_provider_with_options(provider_name, key, &fallback_options)
+        });
+        match fallback_provider {
+            Ok(provider) => providers.push((
+                fallback.clone(),
+                with_request_timeout(
+                    fallback,
+                    with_rate_limit(fallback, provider, reliability, options),
+                    reliability,
+                ),
+            )),
             Err(_error) => {
                 tracing::warn!(
                     fallback_provider = fallback,
@@ -1323,8 +1484,14 @@ pub fn create_resilient_provider_with_options(
         reliability.provider_retries,
         reliability.provider_backoff_ms,
     )
-    .with_api_keys(reliability.api_keys.clone())
-    .with_model_fallbacks(reliability.model_fallbacks.clone());
+    .with_model_fallbacks(reliability.model_fallbacks.clone())
+    .with_health(
+        ProviderHealth::shared(CircuitSettings::from_config(reliability)),
+        options.observer.clone(),
+    )
+    .with_health_probes(std::time::Duration::from_secs(
+        reliability.health_probe_interval_secs,
+    ));
 
     Ok(Box::new(reliable))
 }
@@ -1561,6 +1728,12 @@ pub fn list_providers() -> Vec<ProviderInfo> {
             aliases: &["aws-bedrock"],
             local: false,
         },
+        ProviderInfo {
+            name: "azure",
+            display_name: "Azure OpenAI",
+            aliases: &["azure-openai"],
+            local: false,
+        },
         ProviderInfo {
             name: "qianfan",
             display_name: "Qianfan (Baidu)",
@@ -1678,6 +1851,12 @@ pub fn list_providers() -> Vec<ProviderInfo> {
             aliases: &[],
             local: true,
         },
+        ProviderInfo {
+            name: "simulation",
+            display_name: "Simulation (offline stub)",
+            aliases: &[],
+            local: true,
+        },
         ProviderInfo {
             name: "nvidia",
             display_name: "NVIDIA NIM",
@@ -2103,6 +2282,15 @@ mod tests {
         assert!(create_provider("bedrock", Some("ignored")).is_ok());
     }
 
+    #[test]
+    fn factory_azure_openai() {
+        let endpoint = Some("https://my-resource.openai.azure.com");
+        assert!(create_provider_with_url("azure", Some("key"), endpoint).is_ok());
+        assert!(create_provider_with_url("azure-openai", Some("key"), endpoint).is_ok());
+        // The endpoint is only required once a request is made.
+        assert!(create_provider("azure", Some("key")).is_ok());
+    }
+
     #[test]
     fn factory_qianfan() {
         assert!(create_provider("qianfan", Some("key")).is_ok());
@@ -2177,6 +2365,11 @@ mod tests {
         assert!(create_provider("osaurus", Some("custom-key")).is_ok());
     }
 
+    #[test]
+    fn factory_simulation_needs_no_key() {
+        assert!(create_provider("simulation", None).is_ok());
+    }
+
     #[test]
     fn factory_osaurus_uses_default_key_when_none() {
         // Verify that create_provider_with_url_and_options succeeds even
@@ -2424,6 +2617,13 @@ mod tests {
                 "openai".into(),
             ],
             api_keys: Vec::new(),
+            provider_api_keys: std::collections::HashMap::new(),
+            api_key_park_secs: 3600,
+            provider_rate_limits: std::collections::HashMap::new(),
+            provider_timeout_secs: 300,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 60,
+            health_probe_interval_secs: 30,
             model_fallbacks: std::collections::HashMap::new(),
             channel_initial_backoff_secs: 2,
             channel_max_backoff_secs: 60,
@@ -2463,6 +2663,13 @@ mod tests {
             provider_backoff_ms: 100,
             fallback_providers: vec!["lmstudio".into(), "ollama".into()],
             api_keys: Vec::new(),
+            provider_api_keys: std::collections::HashMap::new(),
+            api_key_park_secs: 3600,
+            provider_rate_limits: std::collections::HashMap::new(),
+            provider_timeout_secs: 300,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 60,
+            health_probe_interval_secs: 30,
             model_fallbacks: std::collections::HashMap::new(),
             channel_initial_backoff_secs: 2,
             channel_max_backoff_secs: 60,
@@ -2485,6 +2692,13 @@ mod tests {
             provider_backoff_ms: 100,
             fallback_providers: vec!["custom:http://host.docker.internal:1234/v1".into()],
             api_keys: Vec::new(),
+            provider_api_keys: std::collections::HashMap::new(),
+            api_key_park_secs: 3600,
+            provider_rate_limits: std::collections::HashMap::new(),
+            provider_timeout_secs: 300,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 60,
+            health_probe_interval_secs: 30,
             model_fallbacks: std::collections::HashMap::new(),
             channel_initial_backoff_secs: 2,
             channel_max_backoff_secs: 60,
@@ -2511,6 +2725,13 @@ mod tests {
                 "lmstudio".into(),
             ],
             api_keys: Vec::new(),
+            provider_api_keys: std::collections::HashMap::new(),
+            api_key_park_secs: 3600,
+            provider_rate_limits: std::collections::HashMap::new(),
+            provider_timeout_secs: 300,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 60,
+            health_probe_interval_secs: 30,
             model_fallbacks: std::collections::HashMap::new(),
             channel_initial_backoff_secs: 2,
             channel_max_backoff_secs: 60,
@@ -2543,6 +2764,13 @@ mod tests {
             provider_backoff_ms: 100,
             fallback_providers: vec!["osaurus".into(), "lmstudio".into()],
             api_keys: Vec::new(),
+            provider_api_keys: std::collections::HashMap::new(),
+            api_key_park_secs: 3600,
+            provider_rate_limits: std::collections::HashMap::new(),
+            provider_timeout_secs: 300,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 60,
+            health_probe_interval_secs: 30,
             model_fallbacks: std::collections::HashMap::new(),
             channel_initial_backoff_secs: 2,
             channel_max_backoff_secs: 60,
@@ -2836,6 +3064,13 @@ mod tests {
             provider_backoff_ms: 100,
             fallback_providers: vec!["openai-codex:second".into()],
             api_keys: Vec::new(),
+            provider_api_keys: std::collections::HashMap::new(),
+            api_key_park_secs: 3600,
+            provider_rate_limits: std::collections::HashMap::new(),
+            provider_timeout_secs: 300,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 60,
+            health_probe_interval_secs: 30,
             model_fallbacks: std::collections::HashMap::new(),
             channel_initial_backoff_secs: 2,
             channel_max_backoff_secs: 60,
@@ -2865,6 +3100,13 @@ mod tests {
                 "nonexistent-provider".into(),
             ],
             api_keys: Vec::new(),
+            provider_api_keys: std::collections::HashMap::new(),
+            api_key_park_secs: 3600,
+            provider_rate_limits: std::collections::HashMap::new(),
+            provider_timeout_secs: 300,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 60,
+            health_probe_interval_secs: 30,
             model_fallbacks: std::collections::HashMap::new(),
             channel_initial_backoff_secs: 2,
             channel_max_backoff_secs: 60,
@@ -1,8 +1,11 @@
 use crate::multimodal;
+use crate::providers::params::{request_body, Dialect};
 use crate::providers::traits::{
-    ChatMessage, ChatResponse, Provider, ProviderCapabilities, TokenUsage, ToolCall,
+    self, ChatDelta, ChatMessage, ChatResponse, Provider, ProviderCapabilities, StreamChunk,
+    StreamError, StreamOptions, StreamResult, TokenUsage, ToolCall,
 };
 use async_trait::async_trait;
+use futures_util::{stream, StreamExt};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -11,6 +14,7 @@ pub struct OllamaProvider {
     base_url: String,
     api_key: Option<String>,
     reasoning_enabled: Option<bool>,
+    keep_alive: Option<String>,
 }
 
 // ─── Request Structures ───────────────────────────────────────────────────────
@@ -25,6 +29,13 @@ struct ChatRequest {
     think: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<serde_json::Value>>,
+    /// How long Ollama keeps the model loaded after the request (e.g. `"30m"`,
+    /// `"-1"` for always).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<String>,
+    /// JSON schema the reply must follow (`chat_structured`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -60,16 +71,18 @@ struct Options {
 
 // ─── Response Structures ──────────────────────────────────────────────────────
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct ApiChatResponse {
     message: ResponseMessage,
     #[serde(default)]
     prompt_eval_count: Option<u64>,
     #[serde(default)]
     eval_count: Option<u64>,
+    #[serde(default)]
+    done_reason: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct ResponseMessage {
     #[serde(default)]
     content: String,
@@ -93,6 +106,54 @@ struct OllamaFunction {
     arguments: serde_json::Value,
 }
 
+/// One line of a streaming `/api/chat` response (newline-delimited JSON).
+#[derive(Debug, Deserialize)]
+struct StreamLine {
+    #[serde(default)]
+    message: Option<ResponseMessage>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+    #[serde(default)]
+    done_reason: Option<String>,
+}
+
+/// Fold one streamed line into `response` and return the text delta it
+/// carried. Tool calls arrive whole in a message line; the final `done`
+/// line brings the token counts and done reason.
+fn push_stream_line(response: &mut ApiChatResponse, line: &str) -> StreamResult<Option<String>> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let parsed: StreamLine = serde_json::from_str(line).map_err(StreamError::Json)?;
+    if let Some(error) = parsed.error {
+        return Err(StreamError::Provider(error));
+    }
+    response.prompt_eval_count = parsed.prompt_eval_count.or(response.prompt_eval_count);
+    response.eval_count = parsed.eval_count.or(response.eval_count);
+    response.done_reason = parsed.done_reason.or(response.done_reason.take());
+    let Some(message) = parsed.message else {
+        return Ok(None);
+    };
+    response.message.tool_calls.extend(message.tool_calls);
+    if let Some(thinking) = message.thinking {
+        response
+            .message
+            .thinking
+            .get_or_insert_with(String::new)
+            .push_str(&thinking);
+    }
+    if message.content.is_empty() {
+        return Ok(None);
+    }
+    response.message.content.push_str(&message.content);
+    Ok(Some(message.content))
+}
+
 // ─── Implementation ───────────────────────────────────────────────────────────
 
 impl OllamaProvider {
@@ -127,9 +188,17 @@ impl OllamaProvider {
             base_url: Self::normalize_base_url(base_url.unwrap_or("http://localhost:11434")),
             api_key,
             reasoning_enabled,
+            keep_alive: None,
         }
     }
 
+    /// Ask Ollama to keep the model loaded for `keep_alive` after each
+    /// request instead of its default of five minutes.
+    pub fn with_keep_alive(mut self, keep_alive: Option<String>) -> Self {
+        self.keep_alive = keep_alive.filter(|value| !value.trim().is_empty());
+        self
+    }
+
     fn is_local_endpoint(&self) -> bool {
         reqwest::Url::parse(&self.base_url)
             .ok()
@@ -182,6 +251,8 @@ impl OllamaProvider {
             options: Options { temperature },
             think: self.reasoning_enabled,
             tools: tools.map(|t| t.to_vec()),
+            keep_alive: self.keep_alive.clone(),
+            format: None,
         }
     }
 
@@ -323,7 +394,17 @@ impl OllamaProvider {
         tools: Option<&[serde_json::Value]>,
     ) -> anyhow::Result<ApiChatResponse> {
         let request = self.build_chat_request(messages, model, temperature, tools);
+        self.send_chat_request(&request, model, temperature, should_auth)
+            .await
+    }
 
+    async fn send_chat_request(
+        &self,
+        request: &ChatRequest,
+        model: &str,
+        temperature: f64,
+        should_auth: bool,
+    ) -> anyhow::Result<ApiChatResponse> {
         let url = format!("{}/api/chat", self.base_url);
 
         tracing::debug!(
@@ -336,7 +417,10 @@ impl OllamaProvider {
             request.tools.as_ref().map_or(0, |t| t.len()),
         );
 
-        let mut request_builder = self.http_client().post(&url).json(&request);
+        let mut request_builder = self
+            .http_client()
+            .post(&url)
+            .json(&request_body(Dialect::Ollama, request));
 
         if should_auth {
             if let Some(key) = self.api_key.as_ref() {
@@ -382,6 +466,74 @@ impl OllamaProvider {
         Ok(chat_response)
     }
 
+    /// Send a streaming `/api/chat` request and forward its content deltas,
+    /// ending with the whole response: text, tool calls, and usage.
+    fn stream_messages(
+        &self,
+        messages: Vec<Message>,
+        model: &str,
+        temperature: f64,
+        tools: Option<&[serde_json::Value]>,
+    ) -> stream::BoxStream<'static, StreamResult<ChatDelta>> {
+        let (normalized_model, should_auth) = match self.resolve_request_details(model) {
+            Ok(details) => details,
+            Err(e) => {
+                let message = e.to_string();
+                return stream::once(async move { Err(StreamError::Provider(message)) }).boxed();
+            }
+        };
+        let mut request = self.build_chat_request(messages, &normalized_model, temperature, tools);
+        request.stream = true;
+        let mut builder = self
+            .http_client()
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&request_body(Dialect::Ollama, &request));
+        if should_auth {
+            if let Some(key) = self.api_key.as_ref() {
+                builder = builder.bearer_auth(key);
+            }
+        }
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<StreamResult<ChatDelta>>(100);
+        tokio::spawn(async move {
+            let response = match builder.send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    let _ = tx.send(Err(StreamError::Http(e))).await;
+                    return;
+                }
+            };
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                let _ = tx
+                    .send(Err(StreamError::Provider(format!(
+                        "Ollama API error ({status}): {}",
+                        super::sanitize_api_error(&body)
+                    ))))
+                    .await;
+                return;
+            }
+
+            let mut streamed = ApiChatResponse::default();
+            if !traits::forward_stream_lines(response.bytes_stream(), &tx, |line| {
+                push_stream_line(&mut streamed, line)
+            })
+            .await
+            {
+                return;
+            }
+            let _ = tx
+                .send(Ok(ChatDelta::Done(Self::chat_response(streamed))))
+                .await;
+        });
+
+        stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|chunk| (chunk, rx))
+        })
+        .boxed()
+    }
+
     /// Convert Ollama tool calls to the JSON format expected by parse_tool_calls in loop_.rs
     ///
     /// Handles quirky model behavior where tool calls are wrapped:
@@ -391,7 +543,7 @@ impl OllamaProvider {
         let formatted_calls: Vec<serde_json::Value> = tool_calls
             .iter()
             .map(|tc| {
-                let (tool_name, tool_args) = self.extract_tool_name_and_args(tc);
+                let (tool_name, tool_args) = Self::extract_tool_name_and_args(tc);
 
                 // Arguments must be a JSON string for parse_tool_calls compatibility
                 let args_str =
@@ -416,7 +568,7 @@ impl OllamaProvider {
     }
 
     /// Extract the actual tool name and arguments from potentially nested structures
-    fn extract_tool_name_and_args(&self, tc: &OllamaToolCall) -> (String, serde_json::Value) {
+    fn extract_tool_name_and_args(tc: &OllamaToolCall) -> (String, serde_json::Value) {
         let name = &tc.function.name;
         let args = &tc.function.arguments;
 
@@ -452,6 +604,98 @@ impl OllamaProvider {
         // Pattern 3: Normal tool call
         (name.clone(), args.clone())
     }
+
+    /// Tool definitions in the OpenAI-compatible JSON `/api/chat` accepts.
+    fn tool_definitions(specs: &[crate::tools::ToolSpec]) -> Vec<serde_json::Value> {
+        specs
+            .iter()
+            .map(|s| {
+                serde_json::json!({
+                    "type": "function",
+                    "function": {
+                        "name": s.name,
+                        "description": s.description,
+                        "parameters": s.parameters
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Convert an `/api/chat` response, blocking or assembled from a stream,
+    /// into a [`ChatResponse`].
+    fn chat_response(response: ApiChatResponse) -> ChatResponse {
+        let usage = if response.prompt_eval_count.is_some() || response.eval_count.is_some() {
+            Some(TokenUsage {
+                input_tokens: response.prompt_eval_count,
+                output_tokens: response.eval_count,
+            })
+        } else {
+            None
+        };
+
+        // Native tool calls returned by the model.
+        if !response.message.tool_calls.is_empty() {
+            let tool_calls: Vec<ToolCall> = response
+                .message
+                .tool_calls
+                .iter()
+                .map(|tc| {
+                    let (name, args) = Self::extract_tool_name_and_args(tc);
+                    ToolCall {
+                        id: tc
+                            .id
+                            .clone()
+                            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                        name,
+                        arguments: serde_json::to_string(&args)
+                            .unwrap_or_else(|_| "{}".to_string()),
+                    }
+                })
+                .collect();
+            let text = if response.message.content.is_empty() {
+                None
+            } else {
+                Some(response.message.content)
+            };
+            return ChatResponse {
+                text,
+                tool_calls,
+                usage,
+                reasoning_content: None,
+                finish_reason: response.done_reason,
+            };
+        }
+
+        // Plain text response.
+        let content = response.message.content;
+        if content.is_empty() {
+            if let Some(thinking) = &response.message.thinking {
+                tracing::warn!(
+                    "Ollama returned empty content with only thinking: '{}'. Model may have stopped prematurely.",
+                    if thinking.len() > 100 { &thinking[..100] } else { thinking }
+                );
+                return ChatResponse {
+                    text: Some(format!(
+                        "I was thinking about this: {}... but I didn't complete my response. Could you try asking again?",
+                        if thinking.len() > 200 { &thinking[..200] } else { thinking }
+                    )),
+                    tool_calls: vec![],
+                    usage,
+                    reasoning_content: None,
+                    finish_reason: None,
+                };
+            }
+            tracing::warn!("Ollama returned empty content with no tool calls");
+        }
+        ChatResponse {
+            text: Some(content),
+            tool_calls: vec![],
+            usage,
+            reasoning_content: None,
+            finish_reason: response.done_reason,
+        }
+    }
 }
 
 #[async_trait]
@@ -604,73 +848,95 @@ impl Provider for OllamaProvider {
             )
             .await?;
 
-        let usage = if response.prompt_eval_count.is_some() || response.eval_count.is_some() {
-            Some(TokenUsage {
-                input_tokens: response.prompt_eval_count,
-                output_tokens: response.eval_count,
-            })
-        } else {
-            None
-        };
+        Ok(Self::chat_response(response))
+    }
 
-        // Native tool calls returned by the model.
-        if !response.message.tool_calls.is_empty() {
-            let tool_calls: Vec<ToolCall> = response
-                .message
-                .tool_calls
-                .iter()
-                .map(|tc| {
-                    let (name, args) = self.extract_tool_name_and_args(tc);
-                    ToolCall {
-                        id: tc
-                            .id
-                            .clone()
-                            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
-                        name,
-                        arguments: serde_json::to_string(&args)
-                            .unwrap_or_else(|_| "{}".to_string()),
-                    }
-                })
-                .collect();
-            let text = if response.message.content.is_empty() {
-                None
-            } else {
-                Some(response.message.content)
-            };
-            return Ok(ChatResponse {
-                text,
-                tool_calls,
-                usage,
-                reasoning_content: None,
-            });
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        let mut messages = Vec::new();
+        if let Some(sys) = system_prompt {
+            messages.push(ChatMessage::system(sys));
         }
+        messages.push(ChatMessage::user(message));
+        self.stream_chat_with_history(&messages, model, temperature, options)
+    }
 
-        // Plain text response.
-        let content = response.message.content;
-        if content.is_empty() {
-            if let Some(thinking) = &response.message.thinking {
-                tracing::warn!(
-                    "Ollama returned empty content with only thinking: '{}'. Model may have stopped prematurely.",
-                    if thinking.len() > 100 { &thinking[..100] } else { thinking }
-                );
-                return Ok(ChatResponse {
-                    text: Some(format!(
-                        "I was thinking about this: {}... but I didn't complete my response. Could you try asking again?",
-                        if thinking.len() > 200 { &thinking[..200] } else { thinking }
-                    )),
-                    tool_calls: vec![],
-                    usage,
-                    reasoning_content: None,
-                });
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        let api_messages = self.convert_messages(messages);
+        traits::delta_chunks(
+            self.stream_messages(api_messages, model, temperature, None),
+            options,
+        )
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: crate::providers::traits::ChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        let tools = request
+            .tools
+            .filter(|specs| !specs.is_empty())
+            .map(Self::tool_definitions);
+        self.stream_messages(
+            self.convert_messages(request.messages),
+            model,
+            temperature,
+            tools.as_deref(),
+        )
+        .map(|item| item.map_err(anyhow::Error::from))
+        .boxed()
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        let (normalized_model, should_auth) = self.resolve_request_details(model)?;
+        let api_messages = self.convert_messages(&super::structured::with_schema_instructions(
+            messages, schema,
+        ));
+        let mut request =
+            self.build_chat_request(api_messages, &normalized_model, temperature, None);
+        request.format = Some(schema.clone());
+
+        let response = self
+            .send_chat_request(&request, &normalized_model, temperature, should_auth)
+            .await?;
+        match super::structured::parse_structured(&response.message.content, schema) {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                tracing::warn!("Ollama format reply rejected, retrying prompt-guided: {err}");
+                super::structured::chat_structured_with_retries(
+                    self,
+                    messages,
+                    schema,
+                    model,
+                    temperature,
+                )
+                .await
             }
-            tracing::warn!("Ollama returned empty content with no tool calls");
         }
-        Ok(ChatResponse {
-            text: Some(content),
-            tool_calls: vec![],
-            usage,
-            reasoning_content: None,
-        })
     }
 
     fn supports_native_tools(&self) -> bool {
@@ -689,19 +955,7 @@ impl Provider for OllamaProvider {
         // Convert ToolSpec to OpenAI-compatible JSON and delegate to chat_with_tools.
         if let Some(specs) = request.tools {
             if !specs.is_empty() {
-                let tools: Vec<serde_json::Value> = specs
-                    .iter()
-                    .map(|s| {
-                        serde_json::json!({
-                            "type": "function",
-                            "function": {
-                                "name": s.name,
-                                "description": s.description,
-                                "parameters": s.parameters
-                            }
-                        })
-                    })
-                    .collect();
+                let tools = Self::tool_definitions(specs);
                 return self
                     .chat_with_tools(request.messages, &tools, model, temperature)
                     .await;
@@ -717,6 +971,7 @@ impl Provider for OllamaProvider {
             tool_calls: vec![],
             usage: None,
             reasoning_content: None,
+            finish_reason: None,
         })
     }
 }
@@ -889,7 +1144,6 @@ mod tests {
 
     #[test]
     fn extract_tool_name_handles_nested_tool_call() {
-        let provider = OllamaProvider::new(None, None);
         let tc = OllamaToolCall {
             id: Some("call_123".into()),
             function: OllamaFunction {
@@ -900,14 +1154,13 @@ mod tests {
                 }),
             },
         };
-        let (name, args) = provider.extract_tool_name_and_args(&tc);
+        let (name, args) = OllamaProvider::extract_tool_name_and_args(&tc);
         assert_eq!(name, "shell");
         assert_eq!(args.get("command").unwrap(), "date");
     }
 
     #[test]
     fn extract_tool_name_handles_prefixed_name() {
-        let provider = OllamaProvider::new(None, None);
         let tc = OllamaToolCall {
             id: Some("call_123".into()),
             function: OllamaFunction {
@@ -915,14 +1168,13 @@ mod tests {
                 arguments: serde_json::json!({"command": "ls"}),
             },
         };
-        let (name, args) = provider.extract_tool_name_and_args(&tc);
+        let (name, args) = OllamaProvider::extract_tool_name_and_args(&tc);
         assert_eq!(name, "shell");
         assert_eq!(args.get("command").unwrap(), "ls");
     }
 
     #[test]
     fn extract_tool_name_handles_normal_call() {
-        let provider = OllamaProvider::new(None, None);
         let tc = OllamaToolCall {
             id: Some("call_123".into()),
             function: OllamaFunction {
@@ -930,7 +1182,7 @@ mod tests {
                 arguments: serde_json::json!({"path": "/tmp/test"}),
             },
         };
-        let (name, args) = provider.extract_tool_name_and_args(&tc);
+        let (name, args) = OllamaProvider::extract_tool_name_and_args(&tc);
         assert_eq!(name, "file_read");
         assert_eq!(args.get("path").unwrap(), "/tmp/test");
     }
@@ -1054,4 +1306,78 @@ mod tests {
         assert!(resp.prompt_eval_count.is_none());
         assert!(resp.eval_count.is_none());
     }
+
+    #[test]
+    fn request_includes_keep_alive_when_configured() {
+        let request =
+            OllamaProvider::new(None, None).build_chat_request(Vec::new(), "llama3", 0.7, None);
+        assert!(serde_json::to_value(request)
+            .unwrap()
+            .get("keep_alive")
+            .is_none());
+
+        let provider = OllamaProvider::new(None, None).with_keep_alive(Some("30m".to_string()));
+        let json =
+            serde_json::to_value(provider.build_chat_request(Vec::new(), "llama3", 0.7, None))
+                .unwrap();
+        assert_eq!(json["keep_alive"], "30m");
+        assert_eq!(json["stream"], false);
+    }
+
+    #[test]
+    fn request_includes_format_schema_only_when_set() {
+        let provider = OllamaProvider::new(None, None);
+        let mut request = provider.build_chat_request(Vec::new(), "llama3", 0.0, None);
+        assert!(serde_json::to_value(&request)
+            .unwrap()
+            .get("format")
+            .is_none());
+
+        let schema = serde_json::json!({"type": "object", "required": ["done"]});
+        request.format = Some(schema.clone());
+        assert_eq!(serde_json::to_value(&request).unwrap()["format"], schema);
+    }
+
+    #[test]
+    fn push_stream_line_yields_content_deltas() {
+        let mut response = ApiChatResponse::default();
+        assert_eq!(
+            push_stream_line(
+                &mut response,
+                r#"{"model":"llama3","message":{"role":"assistant","content":"Hel"},"done":false}"#
+            )
+            .unwrap()
+            .as_deref(),
+            Some("Hel")
+        );
+        assert!(push_stream_line(&mut response, r#"{"model":"llama3","message":{"role":"assistant","content":""},"done":true,"eval_count":12}"#)
+            .unwrap()
+            .is_none());
+        assert!(push_stream_line(&mut response, "\n").unwrap().is_none());
+        assert_eq!(response.message.content, "Hel");
+        assert_eq!(response.eval_count, Some(12));
+        let err =
+            push_stream_line(&mut response, r#"{"error":"model 'nope' not found"}"#).unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn streamed_tool_calls_and_usage_reach_the_final_response() {
+        let mut streamed = ApiChatResponse::default();
+        for line in [
+            r#"{"message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"tool.shell","arguments":{"command":"date"}}}]},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":""},"done":true,"done_reason":"stop","prompt_eval_count":80,"eval_count":9}"#,
+        ] {
+            push_stream_line(&mut streamed, line).unwrap();
+        }
+
+        let response = OllamaProvider::chat_response(streamed);
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].name, "shell");
+        assert_eq!(response.tool_calls[0].arguments, r#"{"command":"date"}"#);
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(80));
+        assert_eq!(usage.output_tokens, Some(9));
+        assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+    }
 }
@@ -1,8 +1,11 @@
+use crate::multimodal;
+use crate::providers::params::{request_body, Dialect};
+use crate::providers::structured;
 use crate::providers::traits::{
     ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
-    Provider, TokenUsage, ToolCall as ProviderToolCall,
+    Provider, ProviderCapabilities, TokenUsage, ToolCall as ProviderToolCall,
 };
-use crate::tools::ToolSpec;
+use crate::tools::{CleaningStrategy, SchemaCleanr, ToolSpec};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -62,13 +65,16 @@ struct NativeChatRequest {
     tools: Option<Vec<NativeToolSpec>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<String>,
+    /// JSON schema the reply must follow (`chat_structured`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
 struct NativeMessage {
     role: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    content: Option<String>,
+    content: Option<NativeContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_call_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -79,6 +85,26 @@ struct NativeMessage {
     reasoning_content: Option<String>,
 }
 
+/// Message content: plain text, or text and image parts for vision input.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum NativeContent {
+    Text(String),
+    Parts(Vec<NativeContentPart>),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum NativeContentPart {
+    Text { text: String },
+    ImageUrl { image_url: NativeImageUrl },
+}
+
+#[derive(Debug, Serialize)]
+struct NativeImageUrl {
+    url: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct NativeToolSpec {
     #[serde(rename = "type")]
@@ -107,6 +133,19 @@ fn parse_native_tool_spec(value: serde_json::Value) -> anyhow::Result<NativeTool
     Ok(spec)
 }
 
+/// `response_format` asking for JSON that follows `schema`. Non-strict, so
+/// schemas without `additionalProperties: false` everywhere are accepted.
+fn json_schema_response_format(schema: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": "response",
+            "schema": schema,
+            "strict": false,
+        },
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct NativeToolCall {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -140,6 +179,8 @@ struct UsageInfo {
 #[derive(Debug, Deserialize)]
 struct NativeChoice {
     message: NativeResponseMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -187,13 +228,44 @@ impl OpenAiProvider {
                     function: NativeToolFunctionSpec {
                         name: tool.name.clone(),
                         description: tool.description.clone(),
-                        parameters: tool.parameters.clone(),
+                        parameters: SchemaCleanr::function_parameters(
+                            tool.parameters.clone(),
+                            CleaningStrategy::OpenAI,
+                        ),
                     },
                 })
                 .collect()
         })
     }
 
+    /// User messages with `[IMAGE:...]` markers become text and `image_url`
+    /// parts; everything else stays plain text.
+    fn to_native_content(role: &str, content: &str) -> NativeContent {
+        if role != "user" {
+            return NativeContent::Text(content.to_string());
+        }
+        let (cleaned_text, image_refs) = multimodal::parse_image_markers(content);
+        if image_refs.is_empty() {
+            return NativeContent::Text(content.to_string());
+        }
+
+        let mut parts = Vec::with_capacity(image_refs.len() + 1);
+        let trimmed_text = cleaned_text.trim();
+        if !trimmed_text.is_empty() {
+            parts.push(NativeContentPart::Text {
+                text: trimmed_text.to_string(),
+            });
+        }
+        parts.extend(
+            image_refs
+                .into_iter()
+                .map(|url| NativeContentPart::ImageUrl {
+                    image_url: NativeImageUrl { url },
+                }),
+        );
+        NativeContent::Parts(parts)
+    }
+
     fn convert_messages(messages: &[ChatMessage]) -> Vec<NativeMessage> {
         messages
             .iter()
@@ -220,7 +292,7 @@ impl OpenAiProvider {
                                 let content = value
                                     .get("content")
                                     .and_then(serde_json::Value::as_str)
-                                    .map(ToString::to_string);
+                                    .map(|text| NativeContent::Text(text.to_string()));
                                 let reasoning_content = value
                                     .get("reasoning_content")
                                     .and_then(serde_json::Value::as_str)
@@ -246,7 +318,7 @@ impl OpenAiProvider {
                         let content = value
                             .get("content")
                             .and_then(serde_json::Value::as_str)
-                            .map(ToString::to_string);
+                            .map(|text| NativeContent::Text(text.to_string()));
                         return NativeMessage {
                             role: "tool".to_string(),
                             content,
@@ -259,7 +331,7 @@ impl OpenAiProvider {
 
                 NativeMessage {
                     role: m.role.clone(),
-                    content: Some(m.content.clone()),
+                    content: Some(Self::to_native_content(&m.role, &m.content)),
                     tool_call_id: None,
                     tool_calls: None,
                     reasoning_content: None,
@@ -287,6 +359,7 @@ impl OpenAiProvider {
             tool_calls,
             usage: None,
             reasoning_content,
+            finish_reason: None,
         }
     }
 
@@ -332,7 +405,7 @@ impl Provider for OpenAiProvider {
             .http_client()
             .post(format!("{}/chat/completions", self.base_url))
             .header("Authorization", format!("Bearer {credential}"))
-            .json(&request)
+            .json(&request_body(Dialect::OpenAi, &request))
             .send()
             .await?;
 
@@ -367,13 +440,14 @@ impl Provider for OpenAiProvider {
             temperature,
             tool_choice: tools.as_ref().map(|_| "auto".to_string()),
             tools,
+            response_format: None,
         };
 
         let response = self
             .http_client()
             .post(format!("{}/chat/completions", self.base_url))
             .header("Authorization", format!("Bearer {credential}"))
-            .json(&native_request)
+            .json(&request_body(Dialect::OpenAi, &native_request))
             .send()
             .await?;
 
@@ -386,17 +460,24 @@ impl Provider for OpenAiProvider {
             input_tokens: u.prompt_tokens,
             output_tokens: u.completion_tokens,
         });
-        let message = native_response
+        let choice = native_response
             .choices
             .into_iter()
             .next()
-            .map(|c| c.message)
             .ok_or_else(|| anyhow::anyhow!("No response from OpenAI"))?;
-        let mut result = Self::parse_native_response(message);
+        let mut result = Self::parse_native_response(choice.message);
         result.usage = usage;
+        result.finish_reason = choice.finish_reason;
         Ok(result)
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            native_tool_calling: true,
+            vision: true,
+        }
+    }
+
     fn supports_native_tools(&self) -> bool {
         true
     }
@@ -430,13 +511,14 @@ impl Provider for OpenAiProvider {
             temperature,
             tool_choice: native_tools.as_ref().map(|_| "auto".to_string()),
             tools: native_tools,
+            response_format: None,
         };
 
         let response = self
             .http_client()
             .post(format!("{}/chat/completions", self.base_url))
             .header("Authorization", format!("Bearer {credential}"))
-            .json(&native_request)
+            .json(&request_body(Dialect::OpenAi, &native_request))
             .send()
             .await?;
 
@@ -449,17 +531,68 @@ impl Provider for OpenAiProvider {
             input_tokens: u.prompt_tokens,
             output_tokens: u.completion_tokens,
         });
-        let message = native_response
+        let choice = native_response
             .choices
             .into_iter()
             .next()
-            .map(|c| c.message)
             .ok_or_else(|| anyhow::anyhow!("No response from OpenAI"))?;
-        let mut result = Self::parse_native_response(message);
+        let mut result = Self::parse_native_response(choice.message);
         result.usage = usage;
+        result.finish_reason = choice.finish_reason;
         Ok(result)
     }
 
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        let credential = self.credential.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("OpenAI API key not set. Set OPENAI_API_KEY or edit config.toml.")
+        })?;
+
+        let native_request = NativeChatRequest {
+            model: model.to_string(),
+            messages: Self::convert_messages(&structured::with_schema_instructions(
+                messages, schema,
+            )),
+            temperature,
+            tools: None,
+            tool_choice: None,
+            response_format: Some(json_schema_response_format(schema)),
+        };
+
+        let response = self
+            .http_client()
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {credential}"))
+            .json(&request_body(Dialect::OpenAi, &native_request))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(super::api_error("OpenAI", response).await);
+        }
+
+        let native_response: NativeChatResponse = response.json().await?;
+        let text = native_response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.effective_content())
+            .unwrap_or_default();
+        match structured::parse_structured(&text, schema) {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                tracing::warn!("OpenAI JSON mode reply rejected, retrying prompt-guided: {err}");
+                structured::chat_structured_with_retries(self, messages, schema, model, temperature)
+                    .await
+            }
+        }
+    }
+
     async fn warmup(&self) -> anyhow::Result<()> {
         if let Some(credential) = self.credential.as_ref() {
             self.http_client()
@@ -693,6 +826,42 @@ mod tests {
             .contains("Invalid OpenAI tool specification"));
     }
 
+    #[test]
+    fn convert_tools_sends_object_parameter_schemas() {
+        let tools = vec![ToolSpec {
+            name: "memory_forget_all".to_string(),
+            description: "Clear memory".to_string(),
+            parameters: serde_json::json!({}),
+        }];
+        let native = OpenAiProvider::convert_tools(Some(&tools)).unwrap();
+        assert_eq!(native[0].kind, "function");
+        assert_eq!(
+            native[0].function.parameters,
+            serde_json::json!({"type": "object", "properties": {}})
+        );
+    }
+
+    #[test]
+    fn structured_request_sends_json_schema_response_format() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "done": { "type": "boolean" } },
+            "required": ["done"]
+        });
+        let request = NativeChatRequest {
+            model: "gpt-4o".to_string(),
+            messages: Vec::new(),
+            temperature: 0.0,
+            tools: None,
+            tool_choice: None,
+            response_format: Some(json_schema_response_format(&schema)),
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["response_format"]["type"], "json_schema");
+        assert_eq!(json["response_format"]["json_schema"]["schema"], schema);
+        assert!(json.get("tools").is_none());
+    }
+
     #[test]
     fn native_tool_spec_deserializes_from_openai_format() {
         let json = serde_json::json!({
@@ -726,6 +895,13 @@ mod tests {
         assert_eq!(usage.completion_tokens, Some(50));
     }
 
+    #[test]
+    fn native_response_parses_finish_reason() {
+        let json = r#"{"choices": [{"message": {"content": "Hello"}, "finish_reason": "length"}]}"#;
+        let resp: NativeChatResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.choices[0].finish_reason.as_deref(), Some("length"));
+    }
+
     #[test]
     fn native_response_parses_without_usage() {
         let json = r#"{"choices": [{"message": {"content": "Hello"}}]}"#;
@@ -802,11 +978,30 @@ mod tests {
         assert!(native[0].reasoning_content.is_none());
     }
 
+    #[test]
+    fn convert_messages_sends_image_markers_as_image_parts() {
+        let native = OpenAiProvider::convert_messages(&[
+            ChatMessage::system("[IMAGE:ignored.png] stays text"),
+            ChatMessage::user("What is this?\n\n[IMAGE:data:image/png;base64,abcd]"),
+        ]);
+        let system = serde_json::to_value(&native[0]).unwrap();
+        assert_eq!(system["content"], "[IMAGE:ignored.png] stays text");
+        let user = serde_json::to_value(&native[1]).unwrap();
+        assert_eq!(user["content"][0]["type"], "text");
+        assert_eq!(user["content"][0]["text"], "What is this?");
+        assert_eq!(user["content"][1]["type"], "image_url");
+        assert_eq!(
+            user["content"][1]["image_url"]["url"],
+            "data:image/png;base64,abcd"
+        );
+        assert!(OpenAiProvider::new(None).supports_vision());
+    }
+
     #[test]
     fn native_message_omits_reasoning_content_when_none() {
         let msg = NativeMessage {
             role: "assistant".to_string(),
-            content: Some("hi".to_string()),
+            content: Some(NativeContent::Text("hi".to_string())),
             tool_call_id: None,
             tool_calls: None,
             reasoning_content: None,
@@ -819,7 +1014,7 @@ mod tests {
     fn native_message_includes_reasoning_content_when_some() {
         let msg = NativeMessage {
             role: "assistant".to_string(),
-            content: Some("hi".to_string()),
+            content: Some(NativeContent::Text("hi".to_string())),
             tool_call_id: None,
             tool_calls: None,
             reasoning_content: Some("thinking...".to_string()),
@@ -1,4 +1,5 @@
 use crate::multimodal;
+use crate::providers::params::{request_body, Dialect};
 use crate::providers::traits::{
     ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
     Provider, ProviderCapabilities, TokenUsage, ToolCall as ProviderToolCall,
@@ -132,6 +133,8 @@ struct UsageInfo {
 #[derive(Debug, Deserialize)]
 struct NativeChoice {
     message: NativeResponseMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -292,6 +295,7 @@ impl OpenRouterProvider {
             tool_calls,
             usage: None,
             reasoning_content,
+            finish_reason: None,
         }
     }
 
@@ -362,7 +366,7 @@ impl Provider for OpenRouterProvider {
                 "https://github.com/theonlyhennygod/zeroclaw",
             )
             .header("X-Title", "ZeroClaw")
-            .json(&request)
+            .json(&request_body(Dialect::OpenRouter, &request))
             .send()
             .await?;
 
@@ -412,7 +416,7 @@ impl Provider for OpenRouterProvider {
                 "https://github.com/theonlyhennygod/zeroclaw",
             )
             .header("X-Title", "ZeroClaw")
-            .json(&request)
+            .json(&request_body(Dialect::OpenRouter, &request))
             .send()
             .await?;
 
@@ -460,7 +464,7 @@ impl Provider for OpenRouterProvider {
                 "https://github.com/theonlyhennygod/zeroclaw",
             )
             .header("X-Title", "ZeroClaw")
-            .json(&native_request)
+            .json(&request_body(Dialect::OpenRouter, &native_request))
             .send()
             .await?;
 
@@ -473,14 +477,14 @@ impl Provider for OpenRouterProvider {
             input_tokens: u.prompt_tokens,
             output_tokens: u.completion_tokens,
         });
-        let message = native_response
+        let choice = native_response
             .choices
             .into_iter()
             .next()
-            .map(|c| c.message)
             .ok_or_else(|| anyhow::anyhow!("No response from OpenRouter"))?;
-        let mut result = Self::parse_native_response(message);
+        let mut result = Self::parse_native_response(choice.message);
         result.usage = usage;
+        result.finish_reason = choice.finish_reason;
         Ok(result)
     }
 
@@ -554,7 +558,7 @@ impl Provider for OpenRouterProvider {
                 "https://github.com/theonlyhennygod/zeroclaw",
             )
             .header("X-Title", "ZeroClaw")
-            .json(&native_request)
+            .json(&request_body(Dialect::OpenRouter, &native_request))
             .send()
             .await?;
 
@@ -567,14 +571,14 @@ impl Provider for OpenRouterProvider {
             input_tokens: u.prompt_tokens,
             output_tokens: u.completion_tokens,
         });
-        let message = native_response
+        let choice = native_response
             .choices
             .into_iter()
             .next()
-            .map(|c| c.message)
             .ok_or_else(|| anyhow::anyhow!("No response from OpenRouter"))?;
-        let mut result = Self::parse_native_response(message);
+        let mut result = Self::parse_native_response(choice.message);
         result.usage = usage;
+        result.finish_reason = choice.finish_reason;
         Ok(result)
     }
 }
@@ -0,0 +1,297 @@
+//! Per-task model parameters, translated for each provider's API.
+//!
+//! The task engine runs each task inside [`scope`] with the task's
+//! [`ProviderParams`] (its own, over `[agent.provider_params]`). Providers
+//! build their request body as usual and pass it through [`request_body`],
+//! which merges the parameters in under the provider's own names. A
+//! parameter a provider has no equivalent for is dropped with a debug log.
+
+use crate::config::{ProviderParams, ReasoningEffort};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::future::Future;
+
+tokio::task_local! {
+    static PARAMS: ProviderParams;
+}
+
+/// Run `future` with provider requests inside it carrying `params`.
+pub async fn scope<F: Future>(params: ProviderParams, future: F) -> F::Output {
+    PARAMS.scope(params, future).await
+}
+
+/// Parameters of the surrounding task, if any are set.
+pub fn current() -> Option<ProviderParams> {
+    PARAMS
+        .try_with(ProviderParams::clone)
+        .ok()
+        .filter(|params| !params.is_empty())
+}
+
+/// Request format a provider's parameters are translated to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// OpenAI chat completions.
+    OpenAi,
+    /// OpenAI-compatible endpoints.
+    Compatible,
+    OpenRouter,
+    Anthropic,
+    /// `generationConfig` of generateContent, plain or wrapped in `request`.
+    Gemini,
+    Ollama,
+    /// Converse `inferenceConfig`.
+    Bedrock,
+}
+
+/// Thinking budget in tokens for providers that take one instead of an
+/// effort level.
+fn thinking_budget(effort: ReasoningEffort) -> u64 {
+    match effort {
+        ReasoningEffort::Low => 1_024,
+        ReasoningEffort::Medium => 4_096,
+        ReasoningEffort::High => 16_384,
+    }
+}
+
+fn dropped(dialect: Dialect, param: &str) {
+    tracing::debug!("{dialect:?} requests have no `{param}` parameter; dropping it");
+}
+
+/// Serialize `body` with the current task's parameters merged in.
+pub fn request_body<T: Serialize>(dialect: Dialect, body: &T) -> Value {
+    let mut value = serde_json::to_value(body).unwrap_or_default();
+    if let Some(params) = current() {
+        apply(dialect, &params, &mut value);
+    }
+    value
+}
+
+/// Merge `params` into the request `body` under `dialect`'s names.
+pub fn apply(dialect: Dialect, params: &ProviderParams, body: &mut Value) {
+    let Some(body) = body.as_object_mut() else {
+        return;
+    };
+    match dialect {
+        Dialect::OpenAi | Dialect::Compatible | Dialect::OpenRouter => {
+            apply_openai_style(dialect, params, body);
+        }
+        Dialect::Anthropic => apply_anthropic(params, body),
+        Dialect::Gemini => {
+            let config = if body.contains_key("request") {
+                body.get_mut("request").and_then(Value::as_object_mut)
+            } else {
+                Some(body)
+            }
+            .and_then(|request| request.get_mut("generationConfig"))
+            .and_then(Value::as_object_mut);
+            if let Some(config) = config {
+                apply_gemini(params, config);
+            }
+        }
+        Dialect::Ollama => apply_ollama(params, body),
+        Dialect::Bedrock => apply_bedrock(params, body),
+    }
+}
+
+fn apply_openai_style(dialect: Dialect, params: &ProviderParams, body: &mut Map<String, Value>) {
+    if let Some(effort) = params.reasoning_effort {
+        if dialect == Dialect::OpenRouter {
+            body.insert("reasoning".into(), json!({ "effort": effort.as_str() }));
+        } else {
+            body.insert("reasoning_effort".into(), json!(effort.as_str()));
+        }
+    }
+    if let Some(top_p) = params.top_p {
+        body.insert("top_p".into(), json!(top_p));
+    }
+    if let Some(max_tokens) = params.max_tokens {
+        // OpenAI's reasoning models reject the older `max_tokens` name.
+        let key = if dialect == Dialect::OpenAi {
+            "max_completion_tokens"
+        } else {
+            "max_tokens"
+        };
+        body.insert(key.into(), json!(max_tokens));
+    }
+    if !params.stop.is_empty() {
+        body.insert("stop".into(), json!(params.stop));
+    }
+}
+
+fn apply_anthropic(params: &ProviderParams, body: &mut Map<String, Value>) {
+    if let Some(max_tokens) = params.max_tokens {
+        body.insert("max_tokens".into(), json!(max_tokens));
+    }
+    if !params.stop.is_empty() {
+        body.insert("stop_sequences".into(), json!(params.stop));
+    }
+    let Some(effort) = params.reasoning_effort else {
+        if let Some(top_p) = params.top_p {
+            body.insert("top_p".into(), json!(top_p));
+        }
+        return;
+    };
+    // Extended thinking counts against `max_tokens`, needs temperature 1,
+    // and does not combine with top_p.
+    let budget = thinking_budget(effort);
+    let answer_tokens = body.get("max_tokens").and_then(Value::as_u64).unwrap_or(0);
+    body.insert("max_tokens".into(), json!(answer_tokens + budget));
+    body.insert(
+        "thinking".into(),
+        json!({ "type": "enabled", "budget_tokens": budget }),
+    );
+    body.insert("temperature".into(), json!(1.0));
+    if params.top_p.is_some() {
+        dropped(Dialect::Anthropic, "top_p");
+    }
+}
+
+fn apply_gemini(params: &ProviderParams, config: &mut Map<String, Value>) {
+    if let Some(effort) = params.reasoning_effort {
+        config.insert(
+            "thinkingConfig".into(),
+            json!({ "thinkingBudget": thinking_budget(effort) }),
+        );
+    }
+    if let Some(top_p) = params.top_p {
+        config.insert("topP".into(), json!(top_p));
+    }
+    if let Some(max_tokens) = params.max_tokens {
+        config.insert("maxOutputTokens".into(), json!(max_tokens));
+    }
+    if !params.stop.is_empty() {
+        config.insert("stopSequences".into(), json!(params.stop));
+    }
+}
+
+fn apply_ollama(params: &ProviderParams, body: &mut Map<String, Value>) {
+    if params.reasoning_effort.is_some() {
+        // Ollama only switches thinking on or off.
+        body.insert("think".into(), json!(true));
+    }
+    let Some(options) = body
+        .entry("options")
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+    else {
+        return;
+    };
+    if let Some(top_p) = params.top_p {
+        options.insert("top_p".into(), json!(top_p));
+    }
+    if let Some(max_tokens) = params.max_tokens {
+        options.insert("num_predict".into(), json!(max_tokens));
+    }
+    if !params.stop.is_empty() {
+        options.insert("stop".into(), json!(params.stop));
+    }
+}
+
+fn apply_bedrock(params: &ProviderParams, body: &mut Map<String, Value>) {
+    if params.reasoning_effort.is_some() {
+        dropped(Dialect::Bedrock, "reasoning_effort");
+    }
+    let Some(config) = body
+        .entry("inferenceConfig")
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+    else {
+        return;
+    };
+    if let Some(top_p) = params.top_p {
+        config.insert("topP".into(), json!(top_p));
+    }
+    if let Some(max_tokens) = params.max_tokens {
+        config.insert("maxTokens".into(), json!(max_tokens));
+    }
+    if !params.stop.is_empty() {
+        config.insert("stopSequences".into(), json!(params.stop));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> ProviderParams {
+        ProviderParams {
+            reasoning_effort: Some(ReasoningEffort::High),
+            top_p: Some(0.9),
+            max_tokens: Some(2_000),
+            stop: vec!["END".into()],
+        }
+    }
+
+    #[test]
+    fn openai_style_dialects_use_their_own_names() {
+        let mut openai = json!({ "model": "o3", "temperature": 0.2 });
+        apply(Dialect::OpenAi, &params(), &mut openai);
+        assert_eq!(openai["reasoning_effort"], "high");
+        assert_eq!(openai["max_completion_tokens"], 2_000);
+        assert_eq!(openai["stop"], json!(["END"]));
+
+        let mut openrouter = json!({});
+        apply(Dialect::OpenRouter, &params(), &mut openrouter);
+        assert_eq!(openrouter["reasoning"], json!({ "effort": "high" }));
+        assert_eq!(openrouter["max_tokens"], 2_000);
+        assert_eq!(openrouter["top_p"], 0.9);
+    }
+
+    #[test]
+    fn anthropic_thinking_reserves_its_budget() {
+        let mut body = json!({ "max_tokens": 4_096, "temperature": 0.2 });
+        apply(Dialect::Anthropic, &params(), &mut body);
+        assert_eq!(
+            body["thinking"],
+            json!({ "type": "enabled", "budget_tokens": 16_384 })
+        );
+        assert_eq!(body["max_tokens"], 2_000 + 16_384);
+        assert_eq!(body["temperature"], 1.0);
+        assert!(body.get("top_p").is_none());
+        assert_eq!(body["stop_sequences"], json!(["END"]));
+    }
+
+    #[test]
+    fn nested_generation_options_are_filled_in() {
+        let mut gemini = json!({ "request": { "generationConfig": { "temperature": 0.2 } } });
+        apply(Dialect::Gemini, &params(), &mut gemini);
+        let config = &gemini["request"]["generationConfig"];
+        assert_eq!(config["maxOutputTokens"], 2_000);
+        assert_eq!(config["thinkingConfig"]["thinkingBudget"], 16_384);
+        assert_eq!(config["temperature"], 0.2);
+
+        let mut ollama = json!({ "options": { "temperature": 0.2 } });
+        apply(Dialect::Ollama, &params(), &mut ollama);
+        assert_eq!(ollama["think"], true);
+        assert_eq!(ollama["options"]["num_predict"], 2_000);
+        assert_eq!(ollama["options"]["temperature"], 0.2);
+    }
+
+    #[tokio::test]
+    async fn request_body_only_changes_inside_a_scope() {
+        let body = json!({ "model": "gpt-4o" });
+        assert_eq!(request_body(Dialect::OpenAi, &body), body);
+
+        let scoped = scope(params(), async { request_body(Dialect::OpenAi, &body) }).await;
+        assert_eq!(scoped["top_p"], 0.9);
+    }
+
+    #[test]
+    fn task_params_override_defaults_one_by_one() {
+        let defaults = ProviderParams {
+            top_p: Some(0.5),
+            stop: vec!["STOP".into()],
+            ..ProviderParams::default()
+        };
+        let task = ProviderParams {
+            max_tokens: Some(100),
+            top_p: Some(0.8),
+            ..ProviderParams::default()
+        };
+        let merged = task.or(&defaults);
+        assert_eq!(merged.top_p, Some(0.8));
+        assert_eq!(merged.max_tokens, Some(100));
+        assert_eq!(merged.stop, vec!["STOP".to_string()]);
+    }
+}
@@ -0,0 +1,416 @@
+use super::traits::{
+    ChatDelta, ChatMessage, ChatRequest, ChatResponse, ProviderCapabilities, StreamChunk,
+    StreamOptions, StreamResult, TokenUsage, ToolsPayload,
+};
+use super::Provider;
+use crate::config::ProviderRateLimitConfig;
+use crate::observability::{Observer, ObserverMetric};
+use crate::tools::ToolSpec;
+use async_trait::async_trait;
+use futures_util::{stream, StreamExt};
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Budgets are counted over a rolling window of this length.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Rough chars-per-token ratio used to charge a request before its usage is known.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Requests and tokens spent inside the current window, oldest first.
+#[derive(Default)]
+struct Window {
+    entries: VecDeque<(Instant, u32, u64)>,
+}
+
+impl Window {
+    fn prune(&mut self, now: Instant) {
+        while self
+            .entries
+            .front()
+            .is_some_and(|&(at, _, _)| now.duration_since(at) >= WINDOW)
+        {
+            self.entries.pop_front();
+        }
+    }
+
+    /// How long to wait before a request charged `tokens` fits, or `None`
+    /// when it fits now. An empty window always admits one request, so an
+    /// oversized prompt is slowed down rather than blocked forever.
+    fn wait_for(
+        &self,
+        limits: &ProviderRateLimitConfig,
+        tokens: u64,
+        now: Instant,
+    ) -> Option<Duration> {
+        let &(oldest, _, _) = self.entries.front()?;
+        let requests: u32 = self.entries.iter().map(|&(_, count, _)| count).sum();
+        let spent: u64 = self.entries.iter().map(|&(_, _, used)| used).sum();
+        let over_requests = limits
+            .requests_per_minute
+            .is_some_and(|limit| requests >= limit);
+        let over_tokens = limits
+            .tokens_per_minute
+            .is_some_and(|limit| spent.saturating_add(tokens) > limit);
+        (over_requests || over_tokens).then(|| (oldest + WINDOW).saturating_duration_since(now))
+    }
+}
+
+/// Rolling per-minute request and token budget for one provider.
+pub struct RateLimiter {
+    limits: Mutex<ProviderRateLimitConfig>,
+    window: Mutex<Window>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: ProviderRateLimitConfig) -> Self {
+        Self {
+            limits: Mutex::new(limits),
+            window: Mutex::new(Window::default()),
+        }
+    }
+
+    /// Wait until a request charged `tokens` fits the budget, then count it.
+    /// Returns how long the call waited.
+    pub async fn acquire(&self, tokens: u64) -> Duration {
+        let started = Instant::now();
+        loop {
+            let wait = {
+                let limits = self.limits.lock().clone();
+                let mut window = self.window.lock();
+                let now = Instant::now();
+                window.prune(now);
+                match window.wait_for(&limits, tokens, now) {
+                    Some(wait) => wait,
+                    None => {
+                        window.entries.push_back((now, 1, tokens));
+                        return started.elapsed();
+                    }
+                }
+            };
+            tokio::time::sleep(wait.max(Duration::from_millis(10))).await;
+        }
+    }
+
+    /// Charge tokens reported after the request was admitted.
+    pub fn record_tokens(&self, tokens: u64) {
+        if tokens > 0 {
+            self.window
+                .lock()
+                .entries
+                .push_back((Instant::now(), 0, tokens));
+        }
+    }
+}
+
+/// Limiters by provider name, so every client of a provider in the process
+/// draws from one budget.
+fn shared_limiter(name: &str, limits: &ProviderRateLimitConfig) -> Arc<RateLimiter> {
+    static LIMITERS: OnceLock<Mutex<HashMap<String, Arc<RateLimiter>>>> = OnceLock::new();
+    let mut limiters = LIMITERS.get_or_init(|| Mutex::new(HashMap::new())).lock();
+    let limiter = limiters
+        .entry(name.to_string())
+        .or_insert_with(|| Arc::new(RateLimiter::new(limits.clone())));
+    *limiter.limits.lock() = limits.clone();
+    Arc::clone(limiter)
+}
+
+fn estimate_tokens(messages: &[ChatMessage]) -> u64 {
+    let chars: usize = messages.iter().map(|message| message.content.len()).sum();
+    u64::try_from(chars / CHARS_PER_TOKEN).unwrap_or(u64::MAX)
+}
+
+/// Holds calls to a provider until they fit its configured requests- and
+/// tokens-per-minute budget, so concurrent tasks queue up instead of
+/// triggering a burst of 429s.
+///
+/// A request is charged its estimated prompt size when admitted; the reported
+/// usage tops that up once the response arrives. Each admission records its
+/// wait as [`ObserverMetric::ProviderRateLimitWait`].
+pub struct RateLimitedProvider {
+    name: String,
+    inner: Box<dyn Provider>,
+    limiter: Arc<RateLimiter>,
+    observer: Option<Arc<dyn Observer>>,
+}
+
+impl RateLimitedProvider {
+    /// Wrap `inner`, sharing the budget of every other wrapper for `name`.
+    pub fn new(name: &str, inner: Box<dyn Provider>, limits: &ProviderRateLimitConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            inner,
+            limiter: shared_limiter(name, limits),
+            observer: None,
+        }
+    }
+
+    pub fn with_observer(mut self, observer: Option<Arc<dyn Observer>>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    async fn admit(&self, estimate: u64) {
+        let wait = self.limiter.acquire(estimate).await;
+        if !wait.is_zero() {
+            tracing::info!(
+                provider = self.name.as_str(),
+                wait_ms = u64::try_from(wait.as_millis()).unwrap_or(u64::MAX),
+                "Provider call delayed by rate limit"
+            );
+        }
+        if let Some(observer) = &self.observer {
+            observer.record_metric(&ObserverMetric::ProviderRateLimitWait {
+                provider: self.name.clone(),
+                wait,
+            });
+        }
+    }
+
+    fn settle(&self, estimate: u64, usage: Option<&TokenUsage>) {
+        let Some(usage) = usage else {
+            return;
+        };
+        let input = usage.input_tokens.unwrap_or(estimate);
+        let output = usage.output_tokens.unwrap_or(0);
+        self.limiter
+            .record_tokens(input.saturating_sub(estimate).saturating_add(output));
+    }
+}
+
+#[async_trait]
+impl Provider for RateLimitedProvider {
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn convert_tools(&self, tools: &[ToolSpec]) -> ToolsPayload {
+        self.inner.convert_tools(tools)
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let chars = system_prompt.map_or(0, str::len) + message.len();
+        self.admit(u64::try_from(chars / CHARS_PER_TOKEN).unwrap_or(u64::MAX))
+            .await;
+        self.inner
+            .chat_with_system(system_prompt, message, model, temperature)
+            .await
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.admit(estimate_tokens(messages)).await;
+        self.inner
+            .chat_with_history(messages, model, temperature)
+            .await
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        let estimate = estimate_tokens(request.messages);
+        self.admit(estimate).await;
+        let response = self.inner.chat(request, model, temperature).await?;
+        self.settle(estimate, response.usage.as_ref());
+        Ok(response)
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[serde_json::Value],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        let estimate = estimate_tokens(messages);
+        self.admit(estimate).await;
+        let response = self
+            .inner
+            .chat_with_tools(messages, tools, model, temperature)
+            .await?;
+        self.settle(estimate, response.usage.as_ref());
+        Ok(response)
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        self.admit(estimate_tokens(messages)).await;
+        self.inner
+            .chat_structured(messages, schema, model, temperature)
+            .await
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.inner.supports_native_tools()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    async fn warmup(&self) -> anyhow::Result<()> {
+        self.inner.warmup().await
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        let estimate = estimate_tokens(request.messages);
+        let model = model.to_string();
+        stream::once(self.admit(estimate))
+            .flat_map(move |()| self.inner.chat_stream(request, &model, temperature))
+            .inspect(move |delta| {
+                if let Ok(ChatDelta::Done(response)) = delta {
+                    self.settle(estimate, response.usage.as_ref());
+                }
+            })
+            .boxed()
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        // These streams are 'static and cannot wait here; they are not throttled.
+        self.inner
+            .stream_chat_with_system(system_prompt, message, model, temperature, options)
+    }
+
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.inner
+            .stream_chat_with_history(messages, model, temperature, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(requests: Option<u32>, tokens: Option<u64>) -> ProviderRateLimitConfig {
+        ProviderRateLimitConfig {
+            requests_per_minute: requests,
+            tokens_per_minute: tokens,
+        }
+    }
+
+    #[test]
+    fn window_admits_until_the_request_limit() {
+        let now = Instant::now();
+        let limits = limits(Some(2), None);
+        let mut window = Window::default();
+        assert_eq!(window.wait_for(&limits, 0, now), None);
+        window.entries.push_back((now, 1, 0));
+        assert_eq!(window.wait_for(&limits, 0, now), None);
+        window.entries.push_back((now, 1, 0));
+        assert_eq!(window.wait_for(&limits, 0, now), Some(WINDOW));
+    }
+
+    #[test]
+    fn window_waits_for_tokens_to_age_out() {
+        let start = Instant::now();
+        let limits = limits(None, Some(1_000));
+        let mut window = Window::default();
+        window.entries.push_back((start, 1, 800));
+        let later = start + Duration::from_secs(20);
+        assert_eq!(window.wait_for(&limits, 100, later), None);
+        assert_eq!(
+            window.wait_for(&limits, 300, later),
+            Some(Duration::from_secs(40))
+        );
+
+        window.prune(start + WINDOW);
+        assert!(window.entries.is_empty());
+        assert_eq!(window.wait_for(&limits, 5_000, later), None);
+    }
+
+    struct UsageProvider;
+
+    #[async_trait]
+    impl Provider for UsageProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            Ok("ok".into())
+        }
+
+        async fn chat(
+            &self,
+            _request: ChatRequest<'_>,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<ChatResponse> {
+            Ok(ChatResponse {
+                text: Some("ok".into()),
+                tool_calls: Vec::new(),
+                usage: Some(TokenUsage {
+                    input_tokens: Some(150),
+                    output_tokens: Some(30),
+                }),
+                reasoning_content: None,
+                finish_reason: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn reported_usage_tops_up_the_admission_estimate() {
+        let provider = RateLimitedProvider::new(
+            "rate-limit-usage-test",
+            Box::new(UsageProvider),
+            &limits(Some(10), Some(10_000)),
+        );
+        let messages = [ChatMessage::user("x".repeat(400))];
+        let request = ChatRequest {
+            messages: &messages,
+            tools: None,
+        };
+        provider.chat(request, "model", 0.0).await.unwrap();
+
+        let window = provider.limiter.window.lock();
+        let requests: u32 = window.entries.iter().map(|&(_, count, _)| count).sum();
+        let tokens: u64 = window.entries.iter().map(|&(_, _, used)| used).sum();
+        assert_eq!(requests, 1);
+        assert_eq!(tokens, 180);
+    }
+}
@@ -0,0 +1,733 @@
+//! Recording and replay of provider traffic.
+//!
+//! [`RecordingProvider`] appends every request and its response (or error)
+//! to a JSONL file in the workspace, with secret-like tokens scrubbed.
+//! [`ReplayProvider`] serves those responses back without calling a model, so
+//! a task run can be reproduced offline or driven deterministically in tests.
+//!
+//! Requests match on call kind, model, temperature, messages, and tools (or
+//! schema). Identical requests get their responses in recorded order.
+//! `chat_stream` turns are recorded as `chat` calls once the stream finishes,
+//! and replay as a single complete response. The text-only `stream_chat_*`
+//! calls are neither recorded nor replayed.
+
+use super::traits::{
+    ChatDelta, ChatMessage, ChatRequest, ChatResponse, ProviderCapabilities, StreamChunk,
+    StreamOptions, StreamResult, TokenUsage, ToolCall, ToolsPayload,
+};
+use super::{scrub_secret_patterns, Provider, ProviderError, ProviderErrorKind};
+use crate::config::{ProviderParams, ProviderRecordingMode, RuntimeConfig};
+use crate::tools::ToolSpec;
+use anyhow::Context;
+use async_trait::async_trait;
+use futures_util::{stream, StreamExt};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Recording file used when `runtime.provider_recording_path` is unset.
+pub const DEFAULT_RECORDING_PATH: &str = "state/provider_recording.jsonl";
+
+/// Provider name on errors raised by replay.
+const REPLAY_PROVIDER: &str = "replay";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedRequest {
+    /// `text`, `chat`, `tools`, or `structured`.
+    call: String,
+    model: String,
+    temperature: f64,
+    messages: Vec<ChatMessage>,
+    /// Tool specs, or the schema of a structured call.
+    #[serde(default)]
+    extra: Value,
+    /// Parameters of the task the call ran in, when it set any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    params: Option<ProviderParams>,
+}
+
+impl RecordedRequest {
+    fn new(
+        call: &str,
+        messages: &[ChatMessage],
+        extra: Value,
+        model: &str,
+        temperature: f64,
+    ) -> Self {
+        Self {
+            call: call.to_string(),
+            model: model.to_string(),
+            temperature,
+            messages: messages.to_vec(),
+            extra,
+            params: super::params::current(),
+        }
+    }
+
+    /// Match key: the scrubbed JSON of the request, as stored on disk.
+    fn key(&self) -> String {
+        scrub_secret_patterns(&serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    text: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+    #[serde(default)]
+    input_tokens: Option<u64>,
+    #[serde(default)]
+    output_tokens: Option<u64>,
+}
+
+impl From<&ChatResponse> for RecordedResponse {
+    fn from(response: &ChatResponse) -> Self {
+        Self {
+            text: response.text.clone(),
+            tool_calls: response.tool_calls.clone(),
+            reasoning_content: response.reasoning_content.clone(),
+            finish_reason: response.finish_reason.clone(),
+            input_tokens: response.usage.as_ref().and_then(|usage| usage.input_tokens),
+            output_tokens: response
+                .usage
+                .as_ref()
+                .and_then(|usage| usage.output_tokens),
+        }
+    }
+}
+
+impl From<RecordedResponse> for ChatResponse {
+    fn from(recorded: RecordedResponse) -> Self {
+        let usage =
+            (recorded.input_tokens.is_some() || recorded.output_tokens.is_some()).then(|| {
+                TokenUsage {
+                    input_tokens: recorded.input_tokens,
+                    output_tokens: recorded.output_tokens,
+                }
+            });
+        Self {
+            text: recorded.text,
+            tool_calls: recorded.tool_calls,
+            usage,
+            reasoning_content: recorded.reasoning_content,
+            finish_reason: recorded.finish_reason,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RecordedOutcome {
+    Response(RecordedResponse),
+    Structured(Value),
+    Error(String),
+}
+
+/// One line of a recording file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedExchange {
+    request: RecordedRequest,
+    #[serde(default)]
+    native_tool_calling: bool,
+    #[serde(default)]
+    vision: bool,
+    outcome: RecordedOutcome,
+}
+
+/// Records every non-streaming call to the wrapped provider.
+///
+/// Recording failures are logged and never fail the call.
+pub struct RecordingProvider {
+    inner: Box<dyn Provider>,
+    path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl RecordingProvider {
+    pub fn new(inner: Box<dyn Provider>, path: PathBuf) -> Self {
+        Self {
+            inner,
+            path,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    fn append(&self, request: RecordedRequest, outcome: RecordedOutcome) {
+        let capabilities = self.inner.capabilities();
+        let exchange = RecordedExchange {
+            request,
+            native_tool_calling: capabilities.native_tool_calling,
+            vision: capabilities.vision,
+            outcome,
+        };
+        let Ok(line) = serde_json::to_string(&exchange) else {
+            return;
+        };
+        let line = scrub_secret_patterns(&line);
+
+        let _guard = self.write_lock.lock();
+        let written = self
+            .path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| {
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+            })
+            .and_then(|mut file| writeln!(file, "{line}"));
+        if let Err(err) = written {
+            tracing::warn!(
+                "Provider recording write to {} failed: {err}",
+                self.path.display()
+            );
+        }
+    }
+
+    fn record_response(&self, request: RecordedRequest, result: &anyhow::Result<ChatResponse>) {
+        let outcome = match result {
+            Ok(response) => RecordedOutcome::Response(response.into()),
+            Err(err) => RecordedOutcome::Error(format!("{err:#}")),
+        };
+        self.append(request, outcome);
+    }
+
+    fn record_text(&self, request: RecordedRequest, result: &anyhow::Result<String>) {
+        let outcome = match result {
+            Ok(text) => RecordedOutcome::Response(RecordedResponse {
+                text: Some(text.clone()),
+                tool_calls: Vec::new(),
+                reasoning_content: None,
+                finish_reason: None,
+                input_tokens: None,
+                output_tokens: None,
+            }),
+            Err(err) => RecordedOutcome::Error(format!("{err:#}")),
+        };
+        self.append(request, outcome);
+    }
+}
+
+fn system_and_user(system_prompt: Option<&str>, message: &str) -> Vec<ChatMessage> {
+    system_prompt
+        .map(ChatMessage::system)
+        .into_iter()
+        .chain(std::iter::once(ChatMessage::user(message)))
+        .collect()
+}
+
+#[async_trait]
+impl Provider for RecordingProvider {
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn convert_tools(&self, tools: &[ToolSpec]) -> ToolsPayload {
+        self.inner.convert_tools(tools)
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let messages = system_and_user(system_prompt, message);
+        let result = self
+            .inner
+            .chat_with_system(system_prompt, message, model, temperature)
+            .await;
+        let request = RecordedRequest::new("text", &messages, Value::Null, model, temperature);
+        self.record_text(request, &result);
+        result
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let result = self
+            .inner
+            .chat_with_history(messages, model, temperature)
+            .await;
+        let request = RecordedRequest::new("text", messages, Value::Null, model, temperature);
+        self.record_text(request, &result);
+        result
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        let tools = serde_json::to_value(request.tools).unwrap_or_default();
+        let recorded = RecordedRequest::new("chat", request.messages, tools, model, temperature);
+        let result = self.inner.chat(request, model, temperature).await;
+        self.record_response(recorded, &result);
+        result
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[Value],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        let result = self
+            .inner
+            .chat_with_tools(messages, tools, model, temperature)
+            .await;
+        let request = RecordedRequest::new(
+            "tools",
+            messages,
+            Value::Array(tools.to_vec()),
+            model,
+            temperature,
+        );
+        self.record_response(request, &result);
+        result
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<Value> {
+        let result = self
+            .inner
+            .chat_structured(messages, schema, model, temperature)
+            .await;
+        let request =
+            RecordedRequest::new("structured", messages, schema.clone(), model, temperature);
+        let outcome = match &result {
+            Ok(value) => RecordedOutcome::Structured(value.clone()),
+            Err(err) => RecordedOutcome::Error(format!("{err:#}")),
+        };
+        self.append(request, outcome);
+        result
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        let tools = serde_json::to_value(request.tools).unwrap_or_default();
+        let mut recorded = Some(RecordedRequest::new(
+            "chat",
+            request.messages,
+            tools,
+            model,
+            temperature,
+        ));
+        self.inner
+            .chat_stream(request, model, temperature)
+            .inspect(move |delta| {
+                let outcome = match delta {
+                    Ok(ChatDelta::Text(_)) => return,
+                    Ok(ChatDelta::Done(response)) => RecordedOutcome::Response(response.into()),
+                    Err(err) => RecordedOutcome::Error(format!("{err:#}")),
+                };
+                if let Some(request) = recorded.take() {
+                    self.append(request, outcome);
+                }
+            })
+            .boxed()
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.inner.supports_native_tools()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    async fn warmup(&self) -> anyhow::Result<()> {
+        self.inner.warmup().await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.inner
+            .stream_chat_with_system(system_prompt, message, model, temperature, options)
+    }
+
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.inner
+            .stream_chat_with_history(messages, model, temperature, options)
+    }
+}
+
+/// Serves responses from a recording instead of calling a model.
+///
+/// A request with no recorded response left fails with a non-retryable
+/// `InvalidRequest` error naming the call, so a diverging replay stops at the
+/// first unrecorded request.
+pub struct ReplayProvider {
+    capabilities: ProviderCapabilities,
+    outcomes: Mutex<HashMap<String, VecDeque<RecordedOutcome>>>,
+}
+
+impl ReplayProvider {
+    /// Load the recording at `path`. Unreadable lines are skipped with a
+    /// warning.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read provider recording {}", path.display()))?;
+        Ok(Self::from_lines(&raw))
+    }
+
+    fn from_lines(raw: &str) -> Self {
+        let mut capabilities = ProviderCapabilities::default();
+        let mut outcomes: HashMap<String, VecDeque<RecordedOutcome>> = HashMap::new();
+        for (index, line) in raw.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<RecordedExchange>(line) {
+                Ok(exchange) => {
+                    capabilities = ProviderCapabilities {
+                        native_tool_calling: exchange.native_tool_calling,
+                        vision: exchange.vision,
+                    };
+                    outcomes
+                        .entry(exchange.request.key())
+                        .or_default()
+                        .push_back(exchange.outcome);
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Skipping unreadable provider recording line {}: {err}",
+                        index + 1
+                    );
+                }
+            }
+        }
+        Self {
+            capabilities,
+            outcomes: Mutex::new(outcomes),
+        }
+    }
+
+    fn next(&self, request: &RecordedRequest) -> anyhow::Result<RecordedOutcome> {
+        self.outcomes
+            .lock()
+            .get_mut(&request.key())
+            .and_then(VecDeque::pop_front)
+            .ok_or_else(|| {
+                let last = request
+                    .messages
+                    .last()
+                    .map(|message| crate::util::truncate_with_ellipsis(&message.content, 80))
+                    .unwrap_or_default();
+                ProviderError {
+                    provider: REPLAY_PROVIDER.to_string(),
+                    kind: ProviderErrorKind::InvalidRequest,
+                    message: format!(
+                        "no recorded response left for {} call to {} (last message: {last:?})",
+                        request.call, request.model
+                    ),
+                }
+                .into()
+            })
+    }
+
+    fn response(&self, request: &RecordedRequest) -> anyhow::Result<ChatResponse> {
+        match self.next(request)? {
+            RecordedOutcome::Response(response) => Ok(response.into()),
+            RecordedOutcome::Structured(value) => Ok(ChatResponse {
+                text: Some(value.to_string()),
+                tool_calls: Vec::new(),
+                usage: None,
+                reasoning_content: None,
+                finish_reason: None,
+            }),
+            RecordedOutcome::Error(message) => Err(anyhow::anyhow!(message)),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for ReplayProvider {
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.capabilities.clone()
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let messages = system_and_user(system_prompt, message);
+        let request = RecordedRequest::new("text", &messages, Value::Null, model, temperature);
+        Ok(self.response(&request)?.text.unwrap_or_default())
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        let request = RecordedRequest::new("text", messages, Value::Null, model, temperature);
+        Ok(self.response(&request)?.text.unwrap_or_default())
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        let tools = serde_json::to_value(request.tools).unwrap_or_default();
+        self.response(&RecordedRequest::new(
+            "chat",
+            request.messages,
+            tools,
+            model,
+            temperature,
+        ))
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[Value],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.response(&RecordedRequest::new(
+            "tools",
+            messages,
+            Value::Array(tools.to_vec()),
+            model,
+            temperature,
+        ))
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<Value> {
+        let request =
+            RecordedRequest::new("structured", messages, schema.clone(), model, temperature);
+        match self.next(&request)? {
+            RecordedOutcome::Structured(value) => Ok(value),
+            RecordedOutcome::Response(response) => {
+                super::structured::parse_structured(&response.text.unwrap_or_default(), schema)
+            }
+            RecordedOutcome::Error(message) => Err(anyhow::anyhow!(message)),
+        }
+    }
+}
+
+/// Apply `[runtime] provider_recording`: wrap `provider` in a
+/// [`RecordingProvider`], replace it with a [`ReplayProvider`], or return it
+/// unchanged.
+pub fn with_recording(
+    provider: Box<dyn Provider>,
+    config: &RuntimeConfig,
+    workspace_dir: &Path,
+) -> anyhow::Result<Box<dyn Provider>> {
+    let path = workspace_dir.join(
+        config
+            .provider_recording_path
+            .as_deref()
+            .unwrap_or(DEFAULT_RECORDING_PATH),
+    );
+    Ok(match config.provider_recording {
+        ProviderRecordingMode::Off => provider,
+        ProviderRecordingMode::Record => {
+            tracing::info!("Recording provider traffic to {}", path.display());
+            Box::new(RecordingProvider::new(provider, path))
+        }
+        ProviderRecordingMode::Replay => {
+            tracing::info!("Replaying provider traffic from {}", path.display());
+            Box::new(ReplayProvider::load(&path)?)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    struct EchoProvider;
+
+    #[async_trait]
+    impl Provider for EchoProvider {
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities {
+                native_tool_calling: true,
+                vision: false,
+            }
+        }
+
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            if message == "fail" {
+                anyhow::bail!("upstream down");
+            }
+            Ok(format!("echo: {message}"))
+        }
+    }
+
+    fn recorder(tmp: &TempDir) -> (RecordingProvider, PathBuf) {
+        let path = tmp.path().join(DEFAULT_RECORDING_PATH);
+        (
+            RecordingProvider::new(Box::new(EchoProvider), path.clone()),
+            path,
+        )
+    }
+
+    #[tokio::test]
+    async fn replay_serves_recorded_responses_in_order() {
+        let tmp = TempDir::new().unwrap();
+        let (recorder, path) = recorder(&tmp);
+        let messages = [ChatMessage::system("sys"), ChatMessage::user("hello")];
+        let request = ChatRequest {
+            messages: &messages,
+            tools: None,
+        };
+        recorder.chat(request, "model", 0.0).await.unwrap();
+        recorder.chat(request, "model", 0.0).await.unwrap();
+        recorder
+            .chat_with_system(None, "fail", "model", 0.0)
+            .await
+            .unwrap_err();
+
+        let replay = ReplayProvider::load(&path).unwrap();
+        assert!(replay.supports_native_tools());
+        let first = replay.chat(request, "model", 0.0).await.unwrap();
+        assert_eq!(first.text.as_deref(), Some("echo: hello"));
+        replay.chat(request, "model", 0.0).await.unwrap();
+        let failed = replay
+            .chat_with_system(None, "fail", "model", 0.0)
+            .await
+            .unwrap_err();
+        assert!(failed.to_string().contains("upstream down"));
+
+        let exhausted = replay.chat(request, "model", 0.0).await.unwrap_err();
+        let typed = ProviderError::find(&exhausted).unwrap();
+        assert_eq!(typed.kind, ProviderErrorKind::InvalidRequest);
+        assert!(typed
+            .message
+            .contains("no recorded response left for chat call"));
+        assert!(replay.chat(request, "other-model", 0.0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn streamed_turns_are_recorded_and_replayed() {
+        let tmp = TempDir::new().unwrap();
+        let (recorder, path) = recorder(&tmp);
+        let messages = [ChatMessage::user("hello")];
+        let request = ChatRequest {
+            messages: &messages,
+            tools: None,
+        };
+        let streamed: Vec<_> = recorder.chat_stream(request, "model", 0.0).collect().await;
+        assert_eq!(streamed.len(), 1);
+
+        let replay = ReplayProvider::load(&path).unwrap();
+        let replayed: Vec<_> = replay.chat_stream(request, "model", 0.0).collect().await;
+        assert!(matches!(
+            replayed.as_slice(),
+            [Ok(ChatDelta::Done(response))] if response.text.as_deref() == Some("echo: hello")
+        ));
+    }
+
+    #[tokio::test]
+    async fn recordings_are_scrubbed_of_secrets() {
+        let tmp = TempDir::new().unwrap();
+        let (recorder, path) = recorder(&tmp);
+        recorder
+            .chat_with_system(None, "my key is sk-abc123def456", "model", 0.0)
+            .await
+            .unwrap();
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("sk-abc123def456"));
+        assert!(raw.contains("[REDACTED]"));
+
+        let replay = ReplayProvider::load(&path).unwrap();
+        let text = replay
+            .chat_with_system(None, "my key is sk-abc123def456", "model", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(text, "echo: my key is [REDACTED]");
+    }
+
+    #[tokio::test]
+    async fn replay_matches_task_provider_params() {
+        let tmp = TempDir::new().unwrap();
+        let (recorder, path) = recorder(&tmp);
+        let capped = ProviderParams {
+            max_tokens: Some(50),
+            ..ProviderParams::default()
+        };
+        crate::providers::params::scope(
+            capped.clone(),
+            recorder.chat_with_system(None, "hello", "model", 0.0),
+        )
+        .await
+        .unwrap();
+
+        let replay = ReplayProvider::load(&path).unwrap();
+        assert!(replay
+            .chat_with_system(None, "hello", "model", 0.0)
+            .await
+            .is_err());
+        let text = crate::providers::params::scope(
+            capped,
+            replay.chat_with_system(None, "hello", "model", 0.0),
+        )
+        .await
+        .unwrap();
+        assert_eq!(text, "echo: hello");
+    }
+}
@@ -1,13 +1,22 @@
+use super::health::{CircuitState, CircuitTransition, ProviderHealth};
 use super::traits::{
-    ChatMessage, ChatRequest, ChatResponse, StreamChunk, StreamOptions, StreamResult,
+    chat_once_stream, ChatDelta, ChatMessage, ChatRequest, ChatResponse, StreamChunk,
+    StreamOptions, StreamResult,
 };
-use super::Provider;
+use super::{Provider, ProviderError, ProviderErrorKind};
+use crate::observability::{Observer, ObserverEvent};
 use async_trait::async_trait;
 use futures_util::{stream, StreamExt};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
 use std::time::Duration;
 
+/// Longest a health probe may take before it counts as a failure.
+const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Message of the minimal request a health probe sends.
+const HEALTH_PROBE_MESSAGE: &str = "ping";
+
 // ── Error Classification ─────────────────────────────────────────────────
 // Errors are split into retryable (transient server/network failures) and
 // non-retryable (permanent client errors). This distinction drives whether
@@ -19,6 +28,9 @@ fn is_non_retryable(err: &anyhow::Error) -> bool {
     if is_context_window_exceeded(err) {
         return true;
     }
+    if let Some(typed) = ProviderError::find(err) {
+        return !typed.is_retryable();
+    }
 
     let msg = err.to_string();
     let msg_lower = msg.to_lowercase();
@@ -47,6 +59,9 @@ fn is_non_retryable(err: &anyhow::Error) -> bool {
     // 4xx errors are generally non-retryable (bad request, auth failure, etc.),
     // except 429 (rate-limit — transient) and 408 (timeout — worth retrying).
     if let Some(reqwest_err) = err.downcast_ref::<reqwest::Error>() {
+        if reqwest_err.is_timeout() {
+            return false;
+        }
         if let Some(status) = reqwest_err.status() {
             let code = status.as_u16();
             return status.is_client_error() && code != 429 && code != 408;
@@ -93,6 +108,11 @@ fn is_non_retryable(err: &anyhow::Error) -> bool {
             || msg_lower.contains("invalid"))
 }
 
+/// The task behind the call was cancelled; nothing else should be tried.
+fn is_cancelled(err: &anyhow::Error) -> bool {
+    ProviderError::find(err).is_some_and(|typed| typed.kind == ProviderErrorKind::Cancelled)
+}
+
 fn is_context_window_exceeded(err: &anyhow::Error) -> bool {
     let lower = err.to_string().to_lowercase();
     let hints = [
@@ -110,7 +130,10 @@ fn is_context_window_exceeded(err: &anyhow::Error) -> bool {
 }
 
 /// Check if an error is a rate-limit (429) error.
-fn is_rate_limited(err: &anyhow::Error) -> bool {
+pub(super) fn is_rate_limited(err: &anyhow::Error) -> bool {
+    if let Some(typed) = ProviderError::find(err) {
+        return matches!(typed.kind, ProviderErrorKind::RateLimited { .. });
+    }
     if let Some(reqwest_err) = err.downcast_ref::<reqwest::Error>() {
         if let Some(status) = reqwest_err.status() {
             return status.as_u16() == 429;
@@ -127,7 +150,7 @@ fn is_rate_limited(err: &anyhow::Error) -> bool {
 /// - plan does not include requested model
 /// - insufficient balance / package not active
 /// - known provider business codes (e.g. Z.AI: 1311, 1113)
-fn is_non_retryable_rate_limit(err: &anyhow::Error) -> bool {
+pub(super) fn is_non_retryable_rate_limit(err: &anyhow::Error) -> bool {
     if !is_rate_limited(err) {
         return false;
     }
@@ -200,6 +223,19 @@ fn parse_retry_after_ms(err: &anyhow::Error) -> Option<u64> {
     None
 }
 
+/// Final error once every provider/model failed. The last typed provider
+/// error stays in the cause chain so callers can still classify it.
+fn all_failed_error(failures: &[String], last_typed_error: Option<ProviderError>) -> anyhow::Error {
+    let summary = format!(
+        "All providers/models failed. Attempts:\n{}",
+        failures.join("\n")
+    );
+    match last_typed_error {
+        Some(typed) => anyhow::Error::new(typed).context(summary),
+        None => anyhow::anyhow!(summary),
+    }
+}
+
 fn failure_reason(rate_limited: bool, non_retryable: bool) -> &'static str {
     if rate_limited && non_retryable {
         "rate_limited_non_retryable"
@@ -233,26 +269,60 @@ fn push_failure(
     ));
 }
 
+fn push_circuit_open(
+    failures: &mut Vec<String>,
+    provider_name: &str,
+    model: &str,
+    error: &ProviderError,
+) {
+    failures.push(format!(
+        "provider={provider_name} model={model}: circuit_open; error={error}"
+    ));
+}
+
+/// Log a circuit state change and report it to `observer`.
+fn report_circuit(observer: Option<&Arc<dyn Observer>>, transition: Option<CircuitTransition>) {
+    let Some(transition) = transition else {
+        return;
+    };
+    tracing::warn!(
+        provider = transition.provider.as_str(),
+        state = transition.state.as_str(),
+        consecutive_failures = transition.consecutive_failures,
+        "Provider circuit changed state"
+    );
+    if let Some(observer) = observer {
+        observer.record_event(&ObserverEvent::ProviderCircuit {
+            provider: transition.provider,
+            state: transition.state.as_str().to_string(),
+            consecutive_failures: transition.consecutive_failures,
+        });
+    }
+}
+
 // ── Resilient Provider Wrapper ────────────────────────────────────────────
 // Three-level failover strategy: model chain → provider chain → retry loop.
 //   Outer loop:  iterate model fallback chain (original model first, then
 //                configured alternatives).
 //   Middle loop: iterate registered providers in priority order.
 //   Inner loop:  retry the same (provider, model) pair with exponential
-//                backoff, rotating API keys on rate-limit errors.
+//                backoff. Providers with several API keys are wrapped in a
+//                `KeyPoolProvider`, which rotates keys on rate-limit errors.
 // Loop invariant: `failures` accumulates every failed attempt so the final
 // error message gives operators a complete diagnostic trail.
 
-/// Provider wrapper with retry, fallback, auth rotation, and model failover.
+/// Provider wrapper with retry, fallback, and model failover.
 pub struct ReliableProvider {
-    providers: Vec<(String, Box<dyn Provider>)>,
+    providers: Vec<(String, Arc<dyn Provider>)>,
     max_retries: u32,
     base_backoff_ms: u64,
-    /// Extra API keys for rotation (index tracks round-robin position).
-    api_keys: Vec<String>,
-    key_index: AtomicUsize,
     /// Per-model fallback chains: model_name → [fallback_model_1, fallback_model_2, ...]
     model_fallbacks: HashMap<String, Vec<String>>,
+    /// Circuit breakers; providers with an open circuit are skipped.
+    health: Option<Arc<ProviderHealth>>,
+    observer: Option<Arc<dyn Observer>>,
+    /// Model each provider was last called with; health probes reuse it.
+    probe_models: Arc<parking_lot::Mutex<HashMap<String, String>>>,
 }
 
 impl ReliableProvider {
@@ -262,446 +332,188 @@ impl ReliableProvider {
         base_backoff_ms: u64,
     ) -> Self {
         Self {
-            providers,
+            providers: providers
+                .into_iter()
+                .map(|(name, provider)| (name, Arc::from(provider)))
+                .collect(),
             max_retries,
             base_backoff_ms: base_backoff_ms.max(50),
-            api_keys: Vec::new(),
-            key_index: AtomicUsize::new(0),
             model_fallbacks: HashMap::new(),
+            health: None,
+            observer: None,
+            probe_models: Arc::default(),
         }
     }
 
-    /// Set additional API keys for round-robin rotation on rate-limit errors.
-    pub fn with_api_keys(mut self, keys: Vec<String>) -> Self {
-        self.api_keys = keys;
-        self
-    }
-
-    /// Set per-model fallback chains.
-    pub fn with_model_fallbacks(mut self, fallbacks: HashMap<String, Vec<String>>) -> Self {
-        self.model_fallbacks = fallbacks;
+    /// Track provider health in `health`, reporting circuit changes to
+    /// `observer`.
+    pub fn with_health(
+        mut self,
+        health: Arc<ProviderHealth>,
+        observer: Option<Arc<dyn Observer>>,
+    ) -> Self {
+        self.health = Some(health);
+        self.observer = observer;
         self
     }
 
-    /// Build the list of models to try: [original, fallback1, fallback2, ...]
-    fn model_chain<'a>(&'a self, model: &'a str) -> Vec<&'a str> {
-        let mut chain = vec![model];
-        if let Some(fallbacks) = self.model_fallbacks.get(model) {
-            chain.extend(fallbacks.iter().map(|s| s.as_str()));
-        }
-        chain
-    }
-
-    /// Advance to the next API key and return it, or None if no extra keys configured.
-    fn rotate_key(&self) -> Option<&str> {
-        if self.api_keys.is_empty() {
-            return None;
-        }
-        let idx = self.key_index.fetch_add(1, Ordering::Relaxed) % self.api_keys.len();
-        Some(&self.api_keys[idx])
-    }
-
-    /// Compute backoff duration, respecting Retry-After if present.
-    fn compute_backoff(&self, base: u64, err: &anyhow::Error) -> u64 {
-        if let Some(retry_after) = parse_retry_after_ms(err) {
-            // Use Retry-After but cap at 30s to avoid indefinite waits
-            retry_after.min(30_000).max(base)
-        } else {
-            base
-        }
-    }
-}
-
-#[async_trait]
-impl Provider for ReliableProvider {
-    async fn warmup(&self) -> anyhow::Result<()> {
-        for (name, provider) in &self.providers {
-            tracing::info!(provider = name, "Warming up provider connection pool");
-            if provider.warmup().await.is_err() {
-                tracing::warn!(provider = name, "Warmup failed (non-fatal)");
-            }
+    /// Every `interval`, probe providers whose open circuit has cooled down
+    /// with a minimal chat request to the model they were last called with,
+    /// as the half-open circuit's trial call: success closes the circuit and
+    /// failure re-opens it. Providers not called yet are left to the next
+    /// request. Probing stops once this provider is dropped.
+    pub fn with_health_probes(self, interval: Duration) -> Self {
+        let Some(health) = self.health.clone() else {
+            return self;
+        };
+        if interval.is_zero() || tokio::runtime::Handle::try_current().is_err() {
+            return self;
         }
-        Ok(())
-    }
-
-    async fn chat_with_system(
-        &self,
-        system_prompt: Option<&str>,
-        message: &str,
-        model: &str,
-        temperature: f64,
-    ) -> anyhow::Result<String> {
-        let models = self.model_chain(model);
-        let mut failures = Vec::new();
-
-        // Outer: model fallback chain. Middle: provider priority. Inner: retries.
-        // Each iteration: attempt one (provider, model) call. On success, return
-        // immediately. On non-retryable error, break to next provider. On
-        // retryable error, sleep with exponential backoff and retry.
-        for current_model in &models {
-            for (provider_name, provider) in &self.providers {
-                let mut backoff_ms = self.base_backoff_ms;
-
-                for attempt in 0..=self.max_retries {
-                    match provider
-                        .chat_with_system(system_prompt, message, current_model, temperature)
-                        .await
-                    {
-                        Ok(resp) => {
-                            if attempt > 0 || *current_model != model {
-                                tracing::info!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    attempt,
-                                    original_model = model,
-                                    "Provider recovered (failover/retry)"
-                                );
-                            }
-                            return Ok(resp);
-                        }
-                        Err(e) => {
-                            let non_retryable_rate_limit = is_non_retryable_rate_limit(&e);
-                            let non_retryable = is_non_retryable(&e) || non_retryable_rate_limit;
-                            let rate_limited = is_rate_limited(&e);
-                            let failure_reason = failure_reason(rate_limited, non_retryable);
-                            let error_detail = compact_error_detail(&e);
-
-                            push_failure(
-                                &mut failures,
-                                provider_name,
-                                current_model,
-                                attempt + 1,
-                                self.max_retries + 1,
-                                failure_reason,
-                                &error_detail,
-                            );
-
-                            // Rate-limit with rotatable keys: cycle to the next API key
-                            // so the retry hits a different quota bucket.
-                            if rate_limited && !non_retryable_rate_limit {
-                                if let Some(new_key) = self.rotate_key() {
-                                    tracing::warn!(
-                                        provider = provider_name,
-                                        error = %error_detail,
-                                        "Rate limited; key rotation selected key ending ...{} \
-                                         but cannot apply (Provider trait has no set_api_key). \
-                                         Retrying with original key.",
-                                        &new_key[new_key.len().saturating_sub(4)..]
-                                    );
-                                }
-                            }
-
-                            if non_retryable {
-                                tracing::warn!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    error = %error_detail,
-                                    "Non-retryable error, moving on"
-                                );
-
-                                if is_context_window_exceeded(&e) {
-                                    anyhow::bail!(
-                                        "Request exceeds model context window; retries and fallbacks were skipped. Attempts:\n{}",
-                                        failures.join("\n")
-                                    );
-                                }
-
-                                break;
-                            }
-
-                            if attempt < self.max_retries {
-                                let wait = self.compute_backoff(backoff_ms, &e);
-                                tracing::warn!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    attempt = attempt + 1,
-                                    backoff_ms = wait,
-                                    reason = failure_reason,
-                                    error = %error_detail,
-                                    "Provider call failed, retrying"
-                                );
-                                tokio::time::sleep(Duration::from_millis(wait)).await;
-                                backoff_ms = (backoff_ms.saturating_mul(2)).min(10_000);
-                            }
-                        }
+        let targets: Vec<(String, Weak<dyn Provider>)> = self
+            .providers
+            .iter()
+            .map(|(name, provider)| (name.clone(), Arc::downgrade(provider)))
+            .collect();
+        let observer = self.observer.clone();
+        let probe_models = Arc::clone(&self.probe_models);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let mut alive = false;
+                for (name, provider) in &targets {
+                    let Some(provider) = provider.upgrade() else {
+                        continue;
+                    };
+                    alive = true;
+                    if !health.needs_probe(name) {
+                        continue;
                     }
+                    let Some(model) = probe_models.lock().get(name).cloned() else {
+                        continue;
+                    };
+                    match health.admit(name) {
+                        Ok(transition) => report_circuit(observer.as_ref(), transition),
+                        // A request is already making the trial call.
+                        Err(_) => continue,
+                    }
+                    let probe = provider.chat_with_system(None, HEALTH_PROBE_MESSAGE, &model, 0.0);
+                    let healthy = matches!(
+                        tokio::time::timeout(HEALTH_PROBE_TIMEOUT, probe).await,
+                        Ok(Ok(_))
+                    );
+                    let transition = if healthy {
+                        health.record_success(name)
+                    } else {
+                        health.record_failure(name)
+                    };
+                    report_circuit(observer.as_ref(), transition);
+                }
+                if !alive {
+                    break;
                 }
-
-                tracing::warn!(
-                    provider = provider_name,
-                    model = *current_model,
-                    "Exhausted retries, trying next provider/model"
-                );
             }
+        });
+        self
+    }
 
-            if *current_model != model {
-                tracing::warn!(
-                    original_model = model,
-                    fallback_model = *current_model,
-                    "Model fallback exhausted all providers, trying next fallback model"
-                );
+    /// A typed `CircuitOpen` error when `provider_name` must be skipped.
+    fn circuit_admit(&self, provider_name: &str) -> Result<(), ProviderError> {
+        let Some(health) = &self.health else {
+            return Ok(());
+        };
+        match health.admit(provider_name) {
+            Ok(transition) => {
+                report_circuit(self.observer.as_ref(), transition);
+                Ok(())
             }
+            Err(remaining) => Err(ProviderError {
+                provider: provider_name.to_string(),
+                kind: ProviderErrorKind::CircuitOpen {
+                    retry_after: remaining,
+                },
+                message: format!(
+                    "{provider_name} is failing repeatedly; circuit open for another {}s",
+                    remaining.as_secs().max(1)
+                ),
+            }),
         }
-
-        anyhow::bail!(
-            "All providers/models failed. Attempts:\n{}",
-            failures.join("\n")
-        )
     }
 
-    async fn chat_with_history(
-        &self,
-        messages: &[ChatMessage],
-        model: &str,
-        temperature: f64,
-    ) -> anyhow::Result<String> {
-        let models = self.model_chain(model);
-        let mut failures = Vec::new();
-
-        for current_model in &models {
-            for (provider_name, provider) in &self.providers {
-                let mut backoff_ms = self.base_backoff_ms;
-
-                for attempt in 0..=self.max_retries {
-                    match provider
-                        .chat_with_history(messages, current_model, temperature)
-                        .await
-                    {
-                        Ok(resp) => {
-                            if attempt > 0 || *current_model != model {
-                                tracing::info!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    attempt,
-                                    original_model = model,
-                                    "Provider recovered (failover/retry)"
-                                );
-                            }
-                            return Ok(resp);
-                        }
-                        Err(e) => {
-                            let non_retryable_rate_limit = is_non_retryable_rate_limit(&e);
-                            let non_retryable = is_non_retryable(&e) || non_retryable_rate_limit;
-                            let rate_limited = is_rate_limited(&e);
-                            let failure_reason = failure_reason(rate_limited, non_retryable);
-                            let error_detail = compact_error_detail(&e);
-
-                            push_failure(
-                                &mut failures,
-                                provider_name,
-                                current_model,
-                                attempt + 1,
-                                self.max_retries + 1,
-                                failure_reason,
-                                &error_detail,
-                            );
-
-                            if rate_limited && !non_retryable_rate_limit {
-                                if let Some(new_key) = self.rotate_key() {
-                                    tracing::warn!(
-                                        provider = provider_name,
-                                        error = %error_detail,
-                                        "Rate limited; key rotation selected key ending ...{} \
-                                         but cannot apply (Provider trait has no set_api_key). \
-                                         Retrying with original key.",
-                                        &new_key[new_key.len().saturating_sub(4)..]
-                                    );
-                                }
-                            }
-
-                            if non_retryable {
-                                tracing::warn!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    error = %error_detail,
-                                    "Non-retryable error, moving on"
-                                );
-
-                                if is_context_window_exceeded(&e) {
-                                    anyhow::bail!(
-                                        "Request exceeds model context window; retries and fallbacks were skipped. Attempts:\n{}",
-                                        failures.join("\n")
-                                    );
-                                }
-
-                                break;
-                            }
-
-                            if attempt < self.max_retries {
-                                let wait = self.compute_backoff(backoff_ms, &e);
-                                tracing::warn!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    attempt = attempt + 1,
-                                    backoff_ms = wait,
-                                    reason = failure_reason,
-                                    error = %error_detail,
-                                    "Provider call failed, retrying"
-                                );
-                                tokio::time::sleep(Duration::from_millis(wait)).await;
-                                backoff_ms = (backoff_ms.saturating_mul(2)).min(10_000);
-                            }
-                        }
-                    }
-                }
-
-                tracing::warn!(
-                    provider = provider_name,
-                    model = *current_model,
-                    "Exhausted retries, trying next provider/model"
-                );
-            }
+    fn circuit_success(&self, provider_name: &str) {
+        if let Some(health) = &self.health {
+            report_circuit(self.observer.as_ref(), health.record_success(provider_name));
         }
-
-        anyhow::bail!(
-            "All providers/models failed. Attempts:\n{}",
-            failures.join("\n")
-        )
     }
 
-    fn supports_native_tools(&self) -> bool {
-        self.providers
-            .first()
-            .map(|(_, p)| p.supports_native_tools())
-            .unwrap_or(false)
+    /// Count a transient failure. Returns whether the circuit is now open.
+    fn circuit_failure(&self, provider_name: &str) -> bool {
+        let Some(health) = &self.health else {
+            return false;
+        };
+        report_circuit(self.observer.as_ref(), health.record_failure(provider_name));
+        health.state(provider_name) == CircuitState::Open
     }
 
-    fn supports_vision(&self) -> bool {
-        self.providers
-            .iter()
-            .any(|(_, provider)| provider.supports_vision())
+    /// Set per-model fallback chains.
+    pub fn with_model_fallbacks(mut self, fallbacks: HashMap<String, Vec<String>>) -> Self {
+        self.model_fallbacks = fallbacks;
+        self
     }
 
-    async fn chat_with_tools(
-        &self,
-        messages: &[ChatMessage],
-        tools: &[serde_json::Value],
-        model: &str,
-        temperature: f64,
-    ) -> anyhow::Result<ChatResponse> {
-        let models = self.model_chain(model);
-        let mut failures = Vec::new();
-
-        for current_model in &models {
-            for (provider_name, provider) in &self.providers {
-                let mut backoff_ms = self.base_backoff_ms;
-
-                for attempt in 0..=self.max_retries {
-                    match provider
-                        .chat_with_tools(messages, tools, current_model, temperature)
-                        .await
-                    {
-                        Ok(resp) => {
-                            if attempt > 0 || *current_model != model {
-                                tracing::info!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    attempt,
-                                    original_model = model,
-                                    "Provider recovered (failover/retry)"
-                                );
-                            }
-                            return Ok(resp);
-                        }
-                        Err(e) => {
-                            let non_retryable_rate_limit = is_non_retryable_rate_limit(&e);
-                            let non_retryable = is_non_retryable(&e) || non_retryable_rate_limit;
-                            let rate_limited = is_rate_limited(&e);
-                            let failure_reason = failure_reason(rate_limited, non_retryable);
-                            let error_detail = compact_error_detail(&e);
-
-                            push_failure(
-                                &mut failures,
-                                provider_name,
-                                current_model,
-                                attempt + 1,
-                                self.max_retries + 1,
-                                failure_reason,
-                                &error_detail,
-                            );
-
-                            if rate_limited && !non_retryable_rate_limit {
-                                if let Some(new_key) = self.rotate_key() {
-                                    tracing::warn!(
-                                        provider = provider_name,
-                                        error = %error_detail,
-                                        "Rate limited; key rotation selected key ending ...{} \
-                                         but cannot apply (Provider trait has no set_api_key). \
-                                         Retrying with original key.",
-                                        &new_key[new_key.len().saturating_sub(4)..]
-                                    );
-                                }
-                            }
-
-                            if non_retryable {
-                                tracing::warn!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    error = %error_detail,
-                                    "Non-retryable error, moving on"
-                                );
-
-                                if is_context_window_exceeded(&e) {
-                                    anyhow::bail!(
-                                        "Request exceeds model context window; retries and fallbacks were skipped. Attempts:\n{}",
-                                        failures.join("\n")
-                                    );
-                                }
-
-                                break;
-                            }
-
-                            if attempt < self.max_retries {
-                                let wait = self.compute_backoff(backoff_ms, &e);
-                                tracing::warn!(
-                                    provider = provider_name,
-                                    model = *current_model,
-                                    attempt = attempt + 1,
-                                    backoff_ms = wait,
-                                    reason = failure_reason,
-                                    error = %error_detail,
-                                    "Provider call failed, retrying"
-                                );
-                                tokio::time::sleep(Duration::from_millis(wait)).await;
-                                backoff_ms = (backoff_ms.saturating_mul(2)).min(10_000);
-                            }
-                        }
-                    }
-                }
+    /// Build the list of models to try: [original, fallback1, fallback2, ...]
+    fn model_chain<'a>(&'a self, model: &'a str) -> Vec<&'a str> {
+        let mut chain = vec![model];
+        if let Some(fallbacks) = self.model_fallbacks.get(model) {
+            chain.extend(fallbacks.iter().map(|s| s.as_str()));
+        }
+        chain
+    }
 
-                tracing::warn!(
-                    provider = provider_name,
-                    model = *current_model,
-                    "Exhausted retries, trying next provider/model"
-                );
-            }
+    /// Compute backoff duration, respecting Retry-After if present.
+    fn compute_backoff(&self, base: u64, err: &anyhow::Error) -> u64 {
+        let typed_retry_after = ProviderError::find(err)
+            .and_then(ProviderError::retry_after)
+            .map(|wait| u64::try_from(wait.as_millis()).unwrap_or(u64::MAX));
+        if let Some(retry_after) = typed_retry_after.or_else(|| parse_retry_after_ms(err)) {
+            // Use Retry-After but cap at 30s to avoid indefinite waits
+            retry_after.min(30_000).max(base)
+        } else {
+            base
         }
-
-        anyhow::bail!(
-            "All providers/models failed. Attempts:\n{}",
-            failures.join("\n")
-        )
     }
 
-    async fn chat(
-        &self,
-        request: ChatRequest<'_>,
-        model: &str,
-        temperature: f64,
-    ) -> anyhow::Result<ChatResponse> {
+    /// Run `call` for each (model, provider) pair in failover order until one
+    /// succeeds, retrying transient failures of a pair with backoff. `call`
+    /// receives the provider and the model to use for that attempt.
+    async fn call_with_failover<T, F, Fut>(&self, model: &str, mut call: F) -> anyhow::Result<T>
+    where
+        F: FnMut(Arc<dyn Provider>, String) -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<T>>,
+    {
         let models = self.model_chain(model);
         let mut failures = Vec::new();
+        let mut last_typed_error = None;
 
+        // Outer: model fallback chain. Middle: provider priority. Inner: retries.
+        // Each iteration: attempt one (provider, model) call. On success, return
+        // immediately. On non-retryable error, break to next provider. On
+        // retryable error, sleep with exponential backoff and retry.
         for current_model in &models {
             for (provider_name, provider) in &self.providers {
+                if let Err(open) = self.circuit_admit(provider_name) {
+                    push_circuit_open(&mut failures, provider_name, current_model, &open);
+                    last_typed_error = Some(open);
+                    continue;
+                }
+                self.probe_models
+                    .lock()
+                    .insert(provider_name.clone(), (*current_model).to_string());
                 let mut backoff_ms = self.base_backoff_ms;
 
                 for attempt in 0..=self.max_retries {
-                    let req = ChatRequest {
-                        messages: request.messages,
-                        tools: request.tools,
-                    };
-                    match provider.chat(req, current_model, temperature).await {
+                    match call(Arc::clone(provider), (*current_model).to_string()).await {
                         Ok(resp) => {
                             if attempt > 0 || *current_model != model {
                                 tracing::info!(
@@ -712,9 +524,13 @@ impl Provider for ReliableProvider {
                                     "Provider recovered (failover/retry)"
                                 );
                             }
+                            self.circuit_success(provider_name);
                             return Ok(resp);
                         }
                         Err(e) => {
+                            if let Some(typed) = ProviderError::find(&e) {
+                                last_typed_error = Some(typed.clone());
+                            }
                             let non_retryable_rate_limit = is_non_retryable_rate_limit(&e);
                             let non_retryable = is_non_retryable(&e) || non_retryable_rate_limit;
                             let rate_limited = is_rate_limited(&e);
@@ -731,19 +547,6 @@ impl Provider for ReliableProvider {
                                 &error_detail,
                             );
 
-                            if rate_limited && !non_retryable_rate_limit {
-                                if let Some(new_key) = self.rotate_key() {
-                                    tracing::warn!(
-                                        provider = provider_name,
-                                        error = %error_detail,
-                                        "Rate limited; key rotation selected key ending ...{} \
-                                         but cannot apply (Provider trait has no set_api_key). \
-                                         Retrying with original key.",
-                                        &new_key[new_key.len().saturating_sub(4)..]
-                                    );
-                                }
-                            }
-
                             if non_retryable {
                                 tracing::warn!(
                                     provider = provider_name,
@@ -752,6 +555,10 @@ impl Provider for ReliableProvider {
                                     "Non-retryable error, moving on"
                                 );
 
+                                if is_cancelled(&e) {
+                                    return Err(e);
+                                }
+
                                 if is_context_window_exceeded(&e) {
                                     anyhow::bail!(
                                         "Request exceeds model context window; retries and fallbacks were skipped. Attempts:\n{}",
@@ -762,6 +569,15 @@ impl Provider for ReliableProvider {
                                 break;
                             }
 
+                            if self.circuit_failure(provider_name) {
+                                tracing::warn!(
+                                    provider = provider_name,
+                                    model = *current_model,
+                                    "Circuit opened, moving on"
+                                );
+                                break;
+                            }
+
                             if attempt < self.max_retries {
                                 let wait = self.compute_backoff(backoff_ms, &e);
                                 tracing::warn!(
@@ -796,16 +612,156 @@ impl Provider for ReliableProvider {
             }
         }
 
-        anyhow::bail!(
-            "All providers/models failed. Attempts:\n{}",
-            failures.join("\n")
-        )
+        Err(all_failed_error(&failures, last_typed_error))
+    }
+}
+
+#[async_trait]
+impl Provider for ReliableProvider {
+    async fn warmup(&self) -> anyhow::Result<()> {
+        for (name, provider) in &self.providers {
+            tracing::info!(provider = name, "Warming up provider connection pool");
+            if provider.warmup().await.is_err() {
+                tracing::warn!(provider = name, "Warmup failed (non-fatal)");
+            }
+        }
+        Ok(())
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.call_with_failover(model, move |provider, current_model| async move {
+            provider
+                .chat_with_system(system_prompt, message, &current_model, temperature)
+                .await
+        })
+        .await
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.call_with_failover(model, move |provider, current_model| async move {
+            provider
+                .chat_with_history(messages, &current_model, temperature)
+                .await
+        })
+        .await
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        self.call_with_failover(model, move |provider, current_model| async move {
+            provider
+                .chat_structured(messages, schema, &current_model, temperature)
+                .await
+        })
+        .await
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.providers
+            .first()
+            .map(|(_, p)| p.supports_native_tools())
+            .unwrap_or(false)
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.providers
+            .iter()
+            .any(|(_, provider)| provider.supports_vision())
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[serde_json::Value],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.call_with_failover(model, move |provider, current_model| async move {
+            provider
+                .chat_with_tools(messages, tools, &current_model, temperature)
+                .await
+        })
+        .await
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.call_with_failover(model, move |provider, current_model| async move {
+            provider.chat(request, &current_model, temperature).await
+        })
+        .await
     }
 
     fn supports_streaming(&self) -> bool {
         self.providers.iter().any(|(_, p)| p.supports_streaming())
     }
 
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        // Only the primary provider streams; everything else takes the
+        // retrying `chat` path.
+        let Some((provider_name, provider)) = self
+            .providers
+            .first()
+            .filter(|(_, provider)| provider.supports_streaming())
+        else {
+            return chat_once_stream(self, request, model, temperature);
+        };
+        let model = model.to_string();
+        let deltas = provider.chat_stream(request, &model, temperature);
+
+        // A stream that fails before producing any text is redone through
+        // `chat`, with its retries and fallbacks. Once text reached the
+        // caller, the error is final.
+        stream::unfold(Some((deltas, false)), move |state| {
+            let model = model.clone();
+            async move {
+                let (mut deltas, streamed) = state?;
+                match deltas.next().await? {
+                    Ok(delta) => {
+                        let streamed = streamed || matches!(delta, ChatDelta::Text(_));
+                        Some((Ok(delta), Some((deltas, streamed))))
+                    }
+                    Err(err) if !streamed => {
+                        tracing::warn!(
+                            provider = provider_name,
+                            model = model,
+                            "Stream failed before any output, retrying without streaming: {err}"
+                        );
+                        let response = self.chat(request, &model, temperature).await;
+                        Some((response.map(ChatDelta::Done), None))
+                    }
+                    Err(err) => Some((Err(err), None)),
+                }
+            }
+        })
+        .boxed()
+    }
+
     fn stream_chat_with_system(
         &self,
         system_prompt: Option<&str>,
@@ -817,7 +773,10 @@ impl Provider for ReliableProvider {
         // Try each provider/model combination for streaming
         // For streaming, we use the first provider that supports it and has streaming enabled
         for (provider_name, provider) in &self.providers {
-            if !provider.supports_streaming() || !options.enabled {
+            if !provider.supports_streaming()
+                || !options.enabled
+                || self.circuit_admit(provider_name).is_err()
+            {
                 continue;
             }
 
@@ -879,6 +838,7 @@ impl Provider for ReliableProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
 
     struct MockProvider {
@@ -1026,6 +986,187 @@ mod tests {
         assert_eq!(fallback_calls.load(Ordering::SeqCst), 1);
     }
 
+    fn circuit_health(failure_threshold: u32) -> Arc<ProviderHealth> {
+        Arc::new(ProviderHealth::new(
+            crate::providers::health::CircuitSettings {
+                failure_threshold,
+                cooldown: Duration::from_secs(60),
+            },
+        ))
+    }
+
+    #[tokio::test]
+    async fn open_circuit_skips_provider_on_later_calls() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let fallback_calls = Arc::new(AtomicUsize::new(0));
+        let health = circuit_health(2);
+
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&primary_calls),
+                        fail_until_attempt: usize::MAX,
+                        response: "never",
+                        error: "primary down",
+                    }),
+                ),
+                (
+                    "fallback".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&fallback_calls),
+                        fail_until_attempt: 0,
+                        response: "from fallback",
+                        error: "fallback down",
+                    }),
+                ),
+            ],
+            3,
+            1,
+        )
+        .with_health(Arc::clone(&health), None);
+
+        let first = provider.simple_chat("hello", "test", 0.0).await.unwrap();
+        assert_eq!(first, "from fallback");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(health.state("primary"), CircuitState::Open);
+
+        let second = provider.simple_chat("hello", "test", 0.0).await.unwrap();
+        assert_eq!(second, "from fallback");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn health_probe_sends_a_chat_request_and_closes_the_circuit() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let health = Arc::new(ProviderHealth::new(
+            crate::providers::health::CircuitSettings {
+                failure_threshold: 1,
+                cooldown: Duration::from_millis(20),
+            },
+        ));
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&primary_calls),
+                        fail_until_attempt: 1,
+                        response: "from primary",
+                        error: "primary down",
+                    }),
+                ),
+                (
+                    "fallback".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::new(AtomicUsize::new(0)),
+                        fail_until_attempt: 0,
+                        response: "from fallback",
+                        error: "fallback down",
+                    }),
+                ),
+            ],
+            0,
+            1,
+        )
+        .with_health(Arc::clone(&health), None)
+        .with_health_probes(Duration::from_millis(10));
+
+        let first = provider.simple_chat("hello", "test", 0.0).await.unwrap();
+        assert_eq!(first, "from fallback");
+        assert_eq!(health.state("primary"), CircuitState::Open);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(health.state("primary"), CircuitState::Closed);
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn all_open_circuits_fail_fast_with_typed_error() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![(
+                "only".into(),
+                Box::new(MockProvider {
+                    calls: Arc::clone(&calls),
+                    fail_until_attempt: usize::MAX,
+                    response: "never",
+                    error: "service unavailable",
+                }),
+            )],
+            0,
+            1,
+        )
+        .with_health(circuit_health(1), None);
+
+        assert!(provider.simple_chat("hello", "test", 0.0).await.is_err());
+        let err = provider
+            .simple_chat("hello", "test", 0.0)
+            .await
+            .unwrap_err();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let typed = ProviderError::find(&err).unwrap();
+        assert!(matches!(typed.kind, ProviderErrorKind::CircuitOpen { .. }));
+        assert!(!typed.is_retryable());
+        assert!(err.to_string().contains("circuit_open"));
+    }
+
+    #[tokio::test]
+    async fn cancelled_calls_skip_remaining_providers() {
+        struct Hanging;
+
+        #[async_trait]
+        impl Provider for Hanging {
+            async fn chat_with_system(
+                &self,
+                _system_prompt: Option<&str>,
+                _message: &str,
+                _model: &str,
+                _temperature: f64,
+            ) -> anyhow::Result<String> {
+                std::future::pending().await
+            }
+        }
+
+        let fallback_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(crate::providers::timeout::TimeoutProvider::new(
+                        "primary",
+                        Box::new(Hanging),
+                        Duration::from_secs(3600),
+                    )),
+                ),
+                (
+                    "fallback".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&fallback_calls),
+                        fail_until_attempt: 0,
+                        response: "from fallback",
+                        error: "fallback down",
+                    }),
+                ),
+            ],
+            2,
+            1,
+        );
+        let token = tokio_util::sync::CancellationToken::new();
+        token.cancel();
+
+        let err = crate::providers::timeout::scope(token, provider.simple_chat("hi", "test", 0.0))
+            .await
+            .unwrap_err();
+
+        let typed = ProviderError::find(&err).unwrap();
+        assert_eq!(typed.kind, ProviderErrorKind::Cancelled);
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 0);
+    }
+
     #[tokio::test]
     async fn returns_aggregated_error_when_all_providers_fail() {
         let provider = ReliableProvider::new(
@@ -1368,36 +1509,6 @@ mod tests {
         assert_eq!(calls.load(Ordering::SeqCst), 1);
     }
 
-    // ── New tests: auth rotation ──
-
-    #[tokio::test]
-    async fn auth_rotation_cycles_keys() {
-        let provider = ReliableProvider::new(
-            vec![(
-                "p".into(),
-                Box::new(MockProvider {
-                    calls: Arc::new(AtomicUsize::new(0)),
-                    fail_until_attempt: 0,
-                    response: "ok",
-                    error: "",
-                }),
-            )],
-            0,
-            1,
-        )
-        .with_api_keys(vec!["key-a".into(), "key-b".into(), "key-c".into()]);
-
-        // Rotate 5 times, verify round-robin
-        let keys: Vec<&str> = (0..5).map(|_| provider.rotate_key().unwrap()).collect();
-        assert_eq!(keys, vec!["key-a", "key-b", "key-c", "key-a", "key-b"]);
-    }
-
-    #[tokio::test]
-    async fn auth_rotation_returns_none_when_empty() {
-        let provider = ReliableProvider::new(vec![], 0, 1);
-        assert!(provider.rotate_key().is_none());
-    }
-
     // ── New tests: Retry-After parsing ──
 
     #[test]
@@ -1704,6 +1815,7 @@ mod tests {
                 tool_calls: self.tool_calls.clone(),
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             })
         }
     }
@@ -1897,6 +2009,7 @@ mod tests {
                 tool_calls: vec![],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             })
         }
     }
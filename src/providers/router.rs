@@ -1,6 +1,7 @@
-use super::traits::{ChatMessage, ChatRequest, ChatResponse};
+use super::traits::{ChatDelta, ChatMessage, ChatRequest, ChatResponse};
 use super::Provider;
 use async_trait::async_trait;
+use futures_util::stream;
 use std::collections::HashMap;
 
 /// A single route: maps a task hint to a provider + model combo.
@@ -137,6 +138,31 @@ impl Provider for RouterProvider {
         provider.chat(request, &resolved_model, temperature).await
     }
 
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        let (provider_idx, resolved_model) = self.resolve(model);
+        let (_, provider) = &self.providers[provider_idx];
+        provider
+            .chat_structured(messages, schema, &resolved_model, temperature)
+            .await
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        let (provider_idx, resolved_model) = self.resolve(model);
+        let (_, provider) = &self.providers[provider_idx];
+        provider.chat_stream(request, &resolved_model, temperature)
+    }
+
     async fn chat_with_tools(
         &self,
         messages: &[ChatMessage],
@@ -0,0 +1,283 @@
+//! Offline rule-based provider for local development.
+//!
+//! `default_provider = "simulation"` (or `--provider simulation`) swaps the
+//! model for a deterministic stub, so the agent loop, task engine, channels,
+//! and task store can be exercised without network access or API keys. Network
+//! tools are replaced with canned responses at the same time (see
+//! [`crate::tools::simulated`]).
+//!
+//! The stub follows three scripts, picked from the latest user request:
+//!
+//! - a request to save or write a named file calls `file_write`, reads the
+//!   file back with `file_read`, then reports where it was saved;
+//! - a search request calls `web_search_tool` and summarizes the result;
+//! - anything else is echoed back.
+
+use crate::agent::prompt::TASK_ENGINE_PROMPT_PREFIX;
+use crate::providers::traits::{
+    ChatMessage, ChatRequest, ChatResponse, Provider, ProviderCapabilities, ToolCall,
+};
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+/// Provider name that enables simulation mode.
+pub const SIMULATION_PROVIDER: &str = "simulation";
+
+/// Characters of tool output repeated in a final answer.
+const MAX_ECHOED_OUTPUT_CHARS: usize = 200;
+
+const WRITE_MARKERS: &[&str] = &["save", "write", "create", "保存", "写入", "写到", "生成"];
+const SEARCH_MARKERS: &[&str] = &[
+    "search",
+    "look up",
+    "find",
+    "news",
+    "搜索",
+    "搜一下",
+    "新闻",
+];
+
+static FILE_PATH_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9_~./-]*[A-Za-z0-9_-]\.[A-Za-z][A-Za-z0-9]{0,7}").unwrap()
+});
+
+pub struct SimulationProvider;
+
+impl SimulationProvider {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for SimulationProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Next step of the script for the current request.
+enum Step {
+    Call {
+        name: &'static str,
+        arguments: Value,
+    },
+    Reply(String),
+}
+
+#[async_trait]
+impl Provider for SimulationProvider {
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            native_tool_calling: true,
+            vision: false,
+        }
+    }
+
+    async fn chat_with_system(
+        &self,
+        _system_prompt: Option<&str>,
+        message: &str,
+        _model: &str,
+        _temperature: f64,
+    ) -> anyhow::Result<String> {
+        Ok(echo(message))
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        _model: &str,
+        _temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        let available: Vec<&str> = request
+            .tools
+            .unwrap_or_default()
+            .iter()
+            .map(|tool| tool.name.as_str())
+            .collect();
+        let (text, tool_calls) = match next_step(request.messages, &available) {
+            Step::Call { name, arguments } => (
+                None,
+                vec![ToolCall {
+                    id: format!("sim-{}", request.messages.len()),
+                    name: name.to_string(),
+                    arguments: arguments.to_string(),
+                }],
+            ),
+            Step::Reply(text) => (Some(text), Vec::new()),
+        };
+        Ok(ChatResponse {
+            text,
+            tool_calls,
+            usage: None,
+            reasoning_content: None,
+            finish_reason: None,
+        })
+    }
+}
+
+fn next_step(messages: &[ChatMessage], available: &[&str]) -> Step {
+    let Some(request_index) = messages.iter().rposition(|message| {
+        message.role == "user" && !message.content.starts_with(TASK_ENGINE_PROMPT_PREFIX)
+    }) else {
+        return Step::Reply(echo(""));
+    };
+    let request = messages[request_index].content.as_str();
+    let lower = request.to_lowercase();
+    let after = &messages[request_index + 1..];
+    let called = called_tools(after);
+    let not_yet_called =
+        |name: &str| available.contains(&name) && !called.iter().any(|done| done == name);
+    let last_output = last_tool_output(after);
+
+    let path = FILE_PATH_RE.find(request).map(|found| found.as_str());
+    if let Some(path) = path.filter(|_| contains_any(&lower, WRITE_MARKERS)) {
+        if not_yet_called("file_write") {
+            return Step::Call {
+                name: "file_write",
+                arguments: serde_json::json!({
+                    "path": path,
+                    "content": format!("Simulated content for: {request}\n"),
+                }),
+            };
+        }
+        if not_yet_called("file_read") {
+            return Step::Call {
+                name: "file_read",
+                arguments: serde_json::json!({ "path": path }),
+            };
+        }
+        if !called.is_empty() {
+            return Step::Reply(format!("Done. Saved to `{path}`."));
+        }
+    }
+
+    if contains_any(&lower, SEARCH_MARKERS) && available.contains(&"web_search_tool") {
+        if not_yet_called("web_search_tool") {
+            return Step::Call {
+                name: "web_search_tool",
+                arguments: serde_json::json!({ "query": request }),
+            };
+        }
+        return Step::Reply(format!(
+            "Here is what I found:\n{}",
+            crate::util::truncate_with_ellipsis(
+                last_output.as_deref().unwrap_or_default(),
+                MAX_ECHOED_OUTPUT_CHARS
+            )
+        ));
+    }
+
+    Step::Reply(echo(request))
+}
+
+fn echo(message: &str) -> String {
+    format!("[simulation] You said: {}", message.trim())
+}
+
+fn contains_any(lower: &str, markers: &[&str]) -> bool {
+    markers.iter().any(|marker| lower.contains(marker))
+}
+
+/// Tool names called in native-tool assistant messages.
+fn called_tools(messages: &[ChatMessage]) -> Vec<String> {
+    messages
+        .iter()
+        .filter(|message| message.role == "assistant")
+        .filter_map(|message| serde_json::from_str::<Value>(&message.content).ok())
+        .flat_map(|value| {
+            value
+                .get("tool_calls")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+        })
+        .filter_map(|call| call.get("name").and_then(Value::as_str).map(String::from))
+        .collect()
+}
+
+fn last_tool_output(messages: &[ChatMessage]) -> Option<String> {
+    let message = messages.iter().rfind(|message| message.role == "tool")?;
+    let content = serde_json::from_str::<Value>(&message.content)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("content")
+                .and_then(Value::as_str)
+                .map(String::from)
+        });
+    Some(content.unwrap_or_else(|| message.content.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolSpec;
+
+    fn spec(name: &str) -> ToolSpec {
+        ToolSpec {
+            name: name.to_string(),
+            description: String::new(),
+            parameters: serde_json::json!({}),
+        }
+    }
+
+    async fn step(provider: &SimulationProvider, messages: &[ChatMessage]) -> ChatResponse {
+        let tools = [
+            spec("file_write"),
+            spec("file_read"),
+            spec("web_search_tool"),
+        ];
+        provider
+            .chat(
+                ChatRequest {
+                    messages,
+                    tools: Some(&tools),
+                },
+                "sim",
+                0.0,
+            )
+            .await
+            .expect("simulated chat")
+    }
+
+    #[tokio::test]
+    async fn write_request_writes_reads_back_and_reports() {
+        let provider = SimulationProvider::new();
+        let mut messages = vec![
+            ChatMessage::system("system"),
+            ChatMessage::user("save a summary to notes/q3.md"),
+        ];
+        for expected in ["file_write", "file_read"] {
+            let response = step(&provider, &messages).await;
+            assert_eq!(response.tool_calls[0].name, expected);
+            messages.push(ChatMessage::assistant(
+                serde_json::json!({"content": null, "tool_calls": [{
+                    "id": response.tool_calls[0].id,
+                    "name": expected,
+                    "arguments": response.tool_calls[0].arguments,
+                }]})
+                .to_string(),
+            ));
+            messages.push(ChatMessage::tool(
+                serde_json::json!({"tool_call_id": response.tool_calls[0].id, "content": "ok"})
+                    .to_string(),
+            ));
+        }
+        let response = step(&provider, &messages).await;
+        assert!(!response.has_tool_calls());
+        assert_eq!(response.text_or_empty(), "Done. Saved to `notes/q3.md`.");
+    }
+
+    #[tokio::test]
+    async fn plain_messages_are_echoed() {
+        let provider = SimulationProvider::new();
+        let response = step(&provider, &[ChatMessage::user("hello there")]).await;
+        assert_eq!(
+            response.text_or_empty(),
+            "[simulation] You said: hello there"
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Structured (JSON schema) output for providers without a native JSON mode.
+//!
+//! [`chat_structured_with_retries`] puts the schema in the system prompt,
+//! parses the reply, and validates it against the schema. An invalid reply is
+//! sent back with the validation error for another attempt, so callers get
+//! either parseable JSON of the requested shape or an error.
+
+use super::traits::{ChatMessage, ChatRequest, Provider, ProviderError, ProviderErrorKind};
+use crate::agent::output_format::schema_violations;
+use serde_json::Value;
+
+/// Corrective attempts after the first invalid reply.
+pub const STRUCTURED_OUTPUT_RETRIES: usize = 2;
+
+/// System prompt addition that asks for JSON matching `schema`.
+pub fn schema_instructions(schema: &Value) -> String {
+    format!(
+        "Respond with a single JSON value that matches this JSON schema. Output the JSON only, with no prose and no code fences.\n\nSchema:\n{schema}"
+    )
+}
+
+/// Parse a model reply as JSON and validate it against `schema`.
+///
+/// Tolerates code fences and text around a single JSON object or array.
+pub fn parse_structured(raw: &str, schema: &Value) -> anyhow::Result<Value> {
+    let value = extract_json(raw).ok_or_else(|| anyhow::anyhow!("reply is not valid JSON"))?;
+    let violations = schema_violations(schema, &value);
+    if !violations.is_empty() {
+        anyhow::bail!("reply does not match schema: {}", violations.join("; "));
+    }
+    Ok(value)
+}
+
+fn extract_json(raw: &str) -> Option<Value> {
+    let trimmed = raw.trim();
+    let unfenced = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.trim_end().strip_suffix("```"))
+        .unwrap_or(trimmed)
+        .trim();
+    if let Ok(value) = serde_json::from_str(unfenced) {
+        return Some(value);
+    }
+    [('{', '}'), ('[', ']')].iter().find_map(|&(open, close)| {
+        let start = unfenced.find(open)?;
+        let end = unfenced.rfind(close)?;
+        (end > start)
+            .then(|| serde_json::from_str(&unfenced[start..=end]).ok())
+            .flatten()
+    })
+}
+
+/// Prompt-guided structured output with validating retries.
+///
+/// This is the default [`Provider::chat_structured`]; native implementations
+/// fall back to it when the provider's JSON mode returns an invalid reply.
+pub async fn chat_structured_with_retries<P: Provider + ?Sized>(
+    provider: &P,
+    messages: &[ChatMessage],
+    schema: &Value,
+    model: &str,
+    temperature: f64,
+) -> anyhow::Result<Value> {
+    let mut conversation = with_schema_instructions(messages, schema);
+    let request_text = messages
+        .iter()
+        .rfind(|message| message.role == "user")
+        .map(|message| message.content.clone())
+        .unwrap_or_default();
+
+    let mut last_error = String::new();
+    for attempt in 0..=STRUCTURED_OUTPUT_RETRIES {
+        let response = provider
+            .chat(
+                ChatRequest {
+                    messages: &conversation,
+                    tools: None,
+                },
+                model,
+                temperature,
+            )
+            .await?;
+        let raw = response.text.unwrap_or_default();
+        match parse_structured(&raw, schema) {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                tracing::debug!(attempt, "Structured output rejected: {err}");
+                last_error = err.to_string();
+                conversation.push(ChatMessage::assistant(raw));
+                // Restate the request: providers without history support only
+                // see the last user message.
+                conversation.push(ChatMessage::user(format!(
+                    "{request_text}\n\nYour previous reply was rejected ({last_error}). Return only JSON that matches the schema."
+                )));
+            }
+        }
+    }
+
+    Err(ProviderError {
+        provider: "structured_output".to_string(),
+        kind: ProviderErrorKind::InvalidOutput,
+        message: format!(
+            "structured output still invalid after {} attempts: {last_error}",
+            STRUCTURED_OUTPUT_RETRIES + 1
+        ),
+    }
+    .into())
+}
+
+/// `messages` with the schema instructions appended to the system prompt
+/// (or prepended as one when there is none).
+pub fn with_schema_instructions(messages: &[ChatMessage], schema: &Value) -> Vec<ChatMessage> {
+    let instructions = schema_instructions(schema);
+    let mut conversation = messages.to_vec();
+    match conversation
+        .iter_mut()
+        .find(|message| message.role == "system")
+    {
+        Some(system) => {
+            if !system.content.is_empty() {
+                system.content.push_str("\n\n");
+            }
+            system.content.push_str(&instructions);
+        }
+        None => conversation.insert(0, ChatMessage::system(instructions)),
+    }
+    conversation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    fn verdict_schema() -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "done": { "type": "boolean" },
+                "reason": { "type": "string" }
+            },
+            "required": ["done"],
+            "additionalProperties": false
+        })
+    }
+
+    struct ScriptedProvider {
+        replies: Mutex<Vec<&'static str>>,
+        prompts: Mutex<Vec<(Option<String>, String)>>,
+    }
+
+    #[async_trait]
+    impl Provider for ScriptedProvider {
+        async fn chat_with_system(
+            &self,
+            system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            self.prompts
+                .lock()
+                .unwrap()
+                .push((system_prompt.map(str::to_string), message.to_string()));
+            Ok(self.replies.lock().unwrap().remove(0).to_string())
+        }
+    }
+
+    fn scripted(replies: Vec<&'static str>) -> ScriptedProvider {
+        ScriptedProvider {
+            replies: Mutex::new(replies),
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn parses_fenced_and_embedded_json() {
+        let schema = verdict_schema();
+        let fenced = parse_structured("```json\n{\"done\": true}\n```", &schema).unwrap();
+        assert_eq!(fenced["done"], true);
+        let embedded = parse_structured("Verdict: {\"done\": false} ok", &schema).unwrap();
+        assert_eq!(embedded["done"], false);
+        assert!(parse_structured("probably done", &schema).is_err());
+        let mismatch = parse_structured("{\"done\": true, \"extra\": 1}", &schema).unwrap_err();
+        assert!(mismatch
+            .to_string()
+            .contains("$: unexpected property `extra`"));
+    }
+
+    #[tokio::test]
+    async fn invalid_replies_are_retried_with_the_error() {
+        let provider = scripted(vec!["sure, done!", "{\"done\": true}"]);
+        let messages = [
+            ChatMessage::system("judge"),
+            ChatMessage::user("is it done?"),
+        ];
+
+        let value =
+            chat_structured_with_retries(&provider, &messages, &verdict_schema(), "model", 0.0)
+                .await
+                .unwrap();
+
+        assert_eq!(value, serde_json::json!({"done": true}));
+        let prompts = provider.prompts.lock().unwrap();
+        assert_eq!(prompts.len(), 2);
+        let system = prompts[0].0.as_deref().unwrap();
+        assert!(system.starts_with("judge\n\nRespond with a single JSON value"));
+        assert!(prompts[1].1.starts_with("is it done?"));
+        assert!(prompts[1].1.contains("reply is not valid JSON"));
+    }
+
+    #[tokio::test]
+    async fn gives_up_with_a_non_retryable_error() {
+        let provider = scripted(vec!["no", "still no", "{\"done\": 1}"]);
+        let err = chat_structured_with_retries(
+            &provider,
+            &[ChatMessage::user("is it done?")],
+            &verdict_schema(),
+            "model",
+            0.0,
+        )
+        .await
+        .unwrap_err();
+
+        let typed = ProviderError::find(&err).unwrap();
+        assert_eq!(typed.kind, ProviderErrorKind::InvalidOutput);
+        assert!(!typed.is_retryable());
+        assert!(typed.message.contains("$.done: expected type \"boolean\""));
+    }
+}
@@ -0,0 +1,347 @@
+//! Per-request deadline and cancellation for provider calls.
+//!
+//! [`TimeoutProvider`] bounds each call by `reliability.provider_timeout_secs`
+//! and aborts it as soon as the cancellation token of the surrounding task run
+//! fires (see [`scope`]). Aborting drops the call's future, which drops the
+//! in-flight HTTP request. Timeouts are `Transport` errors, so the reliability
+//! chain retries them; cancellations are `Cancelled` errors and never are.
+
+use super::traits::{
+    ChatDelta, ChatMessage, ChatRequest, ChatResponse, ProviderCapabilities, StreamChunk,
+    StreamError, StreamOptions, StreamResult, ToolsPayload,
+};
+use super::{Provider, ProviderError, ProviderErrorKind};
+use crate::tools::ToolSpec;
+use async_trait::async_trait;
+use futures_util::{stream, StreamExt};
+use std::future::Future;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+tokio::task_local! {
+    static CANCELLATION: CancellationToken;
+}
+
+/// Run `future` with provider calls inside it aborted when `token` is cancelled.
+pub async fn scope<F: Future>(token: CancellationToken, future: F) -> F::Output {
+    CANCELLATION.scope(token, future).await
+}
+
+fn current_token() -> Option<CancellationToken> {
+    CANCELLATION.try_with(CancellationToken::clone).ok()
+}
+
+async fn cancelled(token: Option<&CancellationToken>) {
+    match token {
+        Some(token) => token.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Why a guarded call or stream stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stop {
+    TimedOut,
+    Cancelled,
+}
+
+/// End `inner` with `on_stop` when no item arrives within `idle_timeout` or
+/// `token` is cancelled.
+fn guard_stream<'a, T: Send + 'a>(
+    inner: stream::BoxStream<'a, T>,
+    idle_timeout: Duration,
+    token: Option<CancellationToken>,
+    on_stop: impl Fn(Stop) -> T + Send + 'a,
+) -> stream::BoxStream<'a, T> {
+    stream::unfold(Some(inner), move |state| {
+        let token = token.clone();
+        async move {
+            let mut inner = state?;
+            let next = tokio::select! {
+                biased;
+                () = cancelled(token.as_ref()) => Err(Stop::Cancelled),
+                next = tokio::time::timeout(idle_timeout, inner.next()) => {
+                    next.map_err(|_| Stop::TimedOut)
+                }
+            };
+            match next {
+                Ok(Some(item)) => Some((Ok(item), Some(inner))),
+                Ok(None) => None,
+                Err(stop) => Some((Err(stop), None)),
+            }
+        }
+    })
+    .map(move |item| item.unwrap_or_else(&on_stop))
+    .boxed()
+}
+
+/// Bounds every call to `inner` by a deadline and the task's cancellation.
+///
+/// Streams are bounded per chunk: the deadline applies to the wait for each
+/// next chunk, so a long but steady answer is not cut off.
+pub struct TimeoutProvider {
+    name: String,
+    inner: Box<dyn Provider>,
+    timeout: Duration,
+}
+
+impl TimeoutProvider {
+    pub fn new(name: &str, inner: Box<dyn Provider>, timeout: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            inner,
+            timeout,
+        }
+    }
+
+    fn stop_message(&self, stop: Stop) -> String {
+        match stop {
+            Stop::TimedOut => format!("{} request timed out after {:?}", self.name, self.timeout),
+            Stop::Cancelled => format!("{} request cancelled", self.name),
+        }
+    }
+
+    fn stop_error(&self, stop: Stop) -> anyhow::Error {
+        ProviderError {
+            provider: self.name.clone(),
+            kind: match stop {
+                Stop::TimedOut => ProviderErrorKind::Transport,
+                Stop::Cancelled => ProviderErrorKind::Cancelled,
+            },
+            message: self.stop_message(stop),
+        }
+        .into()
+    }
+
+    async fn guard<T>(&self, call: impl Future<Output = anyhow::Result<T>>) -> anyhow::Result<T> {
+        let token = current_token();
+        tokio::select! {
+            biased;
+            () = cancelled(token.as_ref()) => Err(self.stop_error(Stop::Cancelled)),
+            result = tokio::time::timeout(self.timeout, call) => {
+                result.unwrap_or_else(|_| Err(self.stop_error(Stop::TimedOut)))
+            }
+        }
+    }
+
+    fn guard_chunks(
+        &self,
+        inner: stream::BoxStream<'static, StreamResult<StreamChunk>>,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        let timed_out = self.stop_message(Stop::TimedOut);
+        let cancelled = self.stop_message(Stop::Cancelled);
+        guard_stream(inner, self.timeout, current_token(), move |stop| {
+            Err(StreamError::Provider(match stop {
+                Stop::TimedOut => timed_out.clone(),
+                Stop::Cancelled => cancelled.clone(),
+            }))
+        })
+    }
+}
+
+#[async_trait]
+impl Provider for TimeoutProvider {
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn convert_tools(&self, tools: &[ToolSpec]) -> ToolsPayload {
+        self.inner.convert_tools(tools)
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.guard(
+            self.inner
+                .chat_with_system(system_prompt, message, model, temperature),
+        )
+        .await
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<String> {
+        self.guard(self.inner.chat_with_history(messages, model, temperature))
+            .await
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.guard(self.inner.chat(request, model, temperature))
+            .await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[serde_json::Value],
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<ChatResponse> {
+        self.guard(
+            self.inner
+                .chat_with_tools(messages, tools, model, temperature),
+        )
+        .await
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        self.guard(
+            self.inner
+                .chat_structured(messages, schema, model, temperature),
+        )
+        .await
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.inner.supports_native_tools()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    async fn warmup(&self) -> anyhow::Result<()> {
+        self.guard(self.inner.warmup()).await
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        guard_stream(
+            self.inner.chat_stream(request, model, temperature),
+            self.timeout,
+            current_token(),
+            move |stop| Err(self.stop_error(stop)),
+        )
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.guard_chunks(self.inner.stream_chat_with_system(
+            system_prompt,
+            message,
+            model,
+            temperature,
+            options,
+        ))
+    }
+
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.guard_chunks(self.inner.stream_chat_with_history(
+            messages,
+            model,
+            temperature,
+            options,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Never answers.
+    struct HangingProvider;
+
+    #[async_trait]
+    impl Provider for HangingProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            std::future::pending().await
+        }
+    }
+
+    fn hanging(timeout: Duration) -> TimeoutProvider {
+        TimeoutProvider::new("slow", Box::new(HangingProvider), timeout)
+    }
+
+    #[tokio::test]
+    async fn hung_calls_time_out_as_retryable_transport_errors() {
+        let err = hanging(Duration::from_millis(50))
+            .simple_chat("hi", "model", 0.0)
+            .await
+            .unwrap_err();
+
+        let typed = ProviderError::find(&err).unwrap();
+        assert_eq!(typed.kind, ProviderErrorKind::Transport);
+        assert!(typed.is_retryable());
+        assert_eq!(typed.message, "slow request timed out after 50ms");
+    }
+
+    #[tokio::test]
+    async fn cancelling_the_scope_aborts_the_call() {
+        let token = CancellationToken::new();
+        let provider = hanging(Duration::from_secs(3600));
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let err = scope(token, provider.simple_chat("hi", "model", 0.0))
+            .await
+            .unwrap_err();
+
+        let typed = ProviderError::find(&err).unwrap();
+        assert_eq!(typed.kind, ProviderErrorKind::Cancelled);
+        assert!(!typed.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn stalled_streams_end_with_an_error() {
+        let inner = stream::iter(vec![1])
+            .chain(stream::pending())
+            .map(Ok::<i32, String>)
+            .boxed();
+        let items: Vec<_> = guard_stream(inner, Duration::from_millis(50), None, |stop| {
+            Err(format!("{stop:?}"))
+        })
+        .collect()
+        .await;
+
+        assert_eq!(items, vec![Ok(1), Err("TimedOut".to_string())]);
+    }
+}
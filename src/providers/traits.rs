@@ -1,8 +1,9 @@
 use crate::tools::ToolSpec;
 use async_trait::async_trait;
-use futures_util::{stream, StreamExt};
+use futures_util::{stream, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::fmt::Write;
+use std::time::Duration;
 
 /// A single message in a conversation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +40,27 @@ impl ChatMessage {
             content: content.into(),
         }
     }
+
+    /// A user message carrying images as `[IMAGE:<ref>]` markers. A reference
+    /// is a local path, an http(s) URL, or a `data:` URI; vision-capable
+    /// providers send them as image parts.
+    pub fn user_with_images<S: AsRef<str>>(text: impl Into<String>, images: &[S]) -> Self {
+        let mut content = text.into();
+        for image in images {
+            if !content.is_empty() {
+                content.push('\n');
+            }
+            content.push_str("[IMAGE:");
+            content.push_str(image.as_ref());
+            content.push(']');
+        }
+        Self::user(content)
+    }
+
+    /// Image references attached to this message.
+    pub fn image_refs(&self) -> Vec<String> {
+        crate::multimodal::parse_image_markers(&self.content).1
+    }
 }
 
 /// A tool call requested by the LLM.
@@ -70,6 +92,9 @@ pub struct ChatResponse {
     /// sent back in subsequent API requests — some providers reject tool-call
     /// history that omits this field.
     pub reasoning_content: Option<String>,
+    /// Why the model stopped, as the provider reported it (`stop`,
+    /// `end_turn`, `MAX_TOKENS`, ...). `None` when the provider gives none.
+    pub finish_reason: Option<String>,
 }
 
 impl ChatResponse {
@@ -187,6 +212,98 @@ impl StreamOptions {
     }
 }
 
+/// One increment of a [`Provider::chat_stream`] response.
+#[derive(Debug, Clone)]
+pub enum ChatDelta {
+    /// Answer text generated since the previous delta.
+    Text(String),
+    /// The complete response; the last item of a successful stream.
+    Done(ChatResponse),
+}
+
+/// A `chat_stream` that makes one blocking [`Provider::chat`] call and yields
+/// its response as a single [`ChatDelta::Done`].
+pub fn chat_once_stream<'a, P: Provider + ?Sized>(
+    provider: &'a P,
+    request: ChatRequest<'a>,
+    model: &str,
+    temperature: f64,
+) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+    let model = model.to_string();
+    stream::once(async move {
+        provider
+            .chat(request, &model, temperature)
+            .await
+            .map(ChatDelta::Done)
+    })
+    .boxed()
+}
+
+/// Turn a provider's [`ChatDelta`] stream into `stream_chat_*` text chunks,
+/// ending with a final chunk once the response is done.
+pub fn delta_chunks(
+    deltas: stream::BoxStream<'static, StreamResult<ChatDelta>>,
+    options: StreamOptions,
+) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+    deltas
+        .map(move |item| {
+            item.map(|delta| match delta {
+                ChatDelta::Text(text) if options.count_tokens => {
+                    StreamChunk::delta(text).with_token_estimate()
+                }
+                ChatDelta::Text(text) => StreamChunk::delta(text),
+                ChatDelta::Done(_) => StreamChunk::final_chunk(),
+            })
+        })
+        .boxed()
+}
+
+/// Feed a streamed response body to `push_line` one line at a time and send
+/// each text delta it returns. Lines are split on raw bytes, so multi-byte
+/// characters cut across network chunks are decoded whole, and a last line
+/// without a trailing newline is still pushed. Returns `false`, after sending
+/// any error, when the body or a line failed or the receiver went away.
+pub async fn forward_stream_lines<B: AsRef<[u8]>>(
+    body: impl Stream<Item = reqwest::Result<B>>,
+    tx: &tokio::sync::mpsc::Sender<StreamResult<ChatDelta>>,
+    mut push_line: impl FnMut(&str) -> StreamResult<Option<String>>,
+) -> bool {
+    let mut body = std::pin::pin!(body);
+    let mut pending: Vec<u8> = Vec::new();
+    let mut at_end = false;
+    while !at_end {
+        match body.next().await {
+            Some(Ok(bytes)) => pending.extend_from_slice(bytes.as_ref()),
+            Some(Err(e)) => {
+                let _ = tx.send(Err(StreamError::Http(e))).await;
+                return false;
+            }
+            None => at_end = true,
+        }
+        loop {
+            let end = match pending.iter().position(|b| *b == b'\n') {
+                Some(pos) => pos + 1,
+                None if at_end && !pending.is_empty() => pending.len(),
+                None => break,
+            };
+            let line: Vec<u8> = pending.drain(..end).collect();
+            match push_line(&String::from_utf8_lossy(&line)) {
+                Ok(Some(text)) => {
+                    if tx.send(Ok(ChatDelta::Text(text))).await.is_err() {
+                        return false;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
 /// Result type for streaming operations.
 pub type StreamResult<T> = std::result::Result<T, StreamError>;
 
@@ -218,6 +335,117 @@ pub struct ProviderCapabilityError {
     pub message: String,
 }
 
+/// What went wrong with a provider call, as far as retrying is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderErrorKind {
+    /// No HTTP response arrived (connect, TLS, DNS, or timeout failure).
+    Transport,
+    /// HTTP 429, with the wait the server asked for in `Retry-After`.
+    RateLimited { retry_after: Option<Duration> },
+    /// HTTP 5xx or 408.
+    ServerError,
+    /// HTTP 401 or 403.
+    Auth,
+    /// Any other 4xx; the same request will fail again.
+    InvalidRequest,
+    /// The reply did not match the requested output schema.
+    InvalidOutput,
+    /// The provider's circuit breaker is open after repeated failures; calls
+    /// are skipped until the cooldown passes.
+    CircuitOpen { retry_after: Duration },
+    /// The task that made the call was cancelled mid-request.
+    Cancelled,
+}
+
+/// Typed provider failure. Providers return it (inside `anyhow::Error`) for
+/// failed HTTP calls so retry logic can tell throttling and outages from bad
+/// requests without matching on message text.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{message}")]
+pub struct ProviderError {
+    pub provider: String,
+    pub kind: ProviderErrorKind,
+    pub message: String,
+}
+
+impl ProviderError {
+    /// Classify a non-success response. `detail` should already be sanitized.
+    pub fn from_status(
+        provider: &str,
+        status: reqwest::StatusCode,
+        headers: &reqwest::header::HeaderMap,
+        detail: &str,
+    ) -> Self {
+        let kind = match status.as_u16() {
+            429 => ProviderErrorKind::RateLimited {
+                retry_after: headers
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| parse_retry_after(value, chrono::Utc::now())),
+            },
+            401 | 403 => ProviderErrorKind::Auth,
+            408 => ProviderErrorKind::ServerError,
+            code if (500..600).contains(&code) => ProviderErrorKind::ServerError,
+            _ => ProviderErrorKind::InvalidRequest,
+        };
+        Self {
+            provider: provider.to_string(),
+            kind,
+            message: format!("{provider} API error ({status}): {detail}"),
+        }
+    }
+
+    /// A request that never got a response.
+    pub fn transport(provider: &str, err: &reqwest::Error) -> Self {
+        Self {
+            provider: provider.to_string(),
+            kind: ProviderErrorKind::Transport,
+            message: format!(
+                "{provider} transport error: {}",
+                super::sanitize_api_error(&err.to_string())
+            ),
+        }
+    }
+
+    /// Whether the same request may succeed later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind,
+            ProviderErrorKind::Transport
+                | ProviderErrorKind::RateLimited { .. }
+                | ProviderErrorKind::ServerError
+        )
+    }
+
+    /// Wait requested by the server before the next attempt.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.kind {
+            ProviderErrorKind::RateLimited { retry_after } => retry_after,
+            ProviderErrorKind::CircuitOpen { retry_after } => Some(retry_after),
+            _ => None,
+        }
+    }
+
+    /// The first `ProviderError` in `err`'s cause chain.
+    pub fn find(err: &anyhow::Error) -> Option<&Self> {
+        err.chain().find_map(|cause| cause.downcast_ref::<Self>())
+    }
+}
+
+/// Parse a `Retry-After` header: delay seconds or an HTTP date.
+pub fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        at.signed_duration_since(now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
 /// Provider capabilities declaration.
 ///
 /// Describes what features a provider supports, enabling intelligent
@@ -363,6 +591,7 @@ pub trait Provider: Send + Sync {
                     tool_calls: Vec::new(),
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 });
             }
         }
@@ -375,9 +604,26 @@ pub trait Provider: Send + Sync {
             tool_calls: Vec::new(),
             usage: None,
             reasoning_content: None,
+            finish_reason: None,
         })
     }
 
+    /// Chat whose reply must be a JSON value matching `schema`.
+    ///
+    /// The default puts the schema in the system prompt and validates the
+    /// reply, sending invalid replies back for a corrected one (see
+    /// [`super::structured`]). Providers with a native JSON mode override it.
+    async fn chat_structured(
+        &self,
+        messages: &[ChatMessage],
+        schema: &serde_json::Value,
+        model: &str,
+        temperature: f64,
+    ) -> anyhow::Result<serde_json::Value> {
+        super::structured::chat_structured_with_retries(self, messages, schema, model, temperature)
+            .await
+    }
+
     /// Whether provider supports native tool calls over API.
     fn supports_native_tools(&self) -> bool {
         self.capabilities().native_tool_calling
@@ -410,9 +656,25 @@ pub trait Provider: Send + Sync {
             tool_calls: Vec::new(),
             usage: None,
             reasoning_content: None,
+            finish_reason: None,
         })
     }
 
+    /// Structured chat that yields answer text as it is generated.
+    ///
+    /// The stream ends with a [`ChatDelta::Done`] holding the full response,
+    /// tool calls included. The default makes one blocking [`Provider::chat`]
+    /// call, so every provider can be driven this way; providers with
+    /// incremental output override it.
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatRequest<'a>,
+        model: &str,
+        temperature: f64,
+    ) -> stream::BoxStream<'a, anyhow::Result<ChatDelta>> {
+        chat_once_stream(self, request, model, temperature)
+    }
+
     /// Whether provider supports streaming responses.
     /// Default implementation returns false.
     fn supports_streaming(&self) -> bool {
@@ -528,6 +790,24 @@ mod tests {
         assert_eq!(tool.role, "tool");
     }
 
+    #[test]
+    fn user_with_images_round_trips_image_refs() {
+        let msg = ChatMessage::user_with_images(
+            "What is in these?",
+            &["/tmp/a.png", "data:image/png;base64,abcd"],
+        );
+        assert_eq!(msg.role, "user");
+        assert_eq!(
+            msg.content,
+            "What is in these?\n[IMAGE:/tmp/a.png]\n[IMAGE:data:image/png;base64,abcd]"
+        );
+        assert_eq!(
+            msg.image_refs(),
+            vec!["/tmp/a.png", "data:image/png;base64,abcd"]
+        );
+        assert!(ChatMessage::user("plain").image_refs().is_empty());
+    }
+
     #[test]
     fn chat_response_helpers() {
         let empty = ChatResponse {
@@ -535,6 +815,7 @@ mod tests {
             tool_calls: vec![],
             usage: None,
             reasoning_content: None,
+            finish_reason: None,
         };
         assert!(!empty.has_tool_calls());
         assert_eq!(empty.text_or_empty(), "");
@@ -548,6 +829,7 @@ mod tests {
             }],
             usage: None,
             reasoning_content: None,
+            finish_reason: None,
         };
         assert!(with_tools.has_tool_calls());
         assert_eq!(with_tools.text_or_empty(), "Let me check");
@@ -570,6 +852,7 @@ mod tests {
                 output_tokens: Some(50),
             }),
             reasoning_content: None,
+            finish_reason: None,
         };
         assert_eq!(resp.usage.as_ref().unwrap().input_tokens, Some(100));
         assert_eq!(resp.usage.as_ref().unwrap().output_tokens, Some(50));
@@ -948,4 +1231,78 @@ mod tests {
 
         assert!(message.contains("non-prompt-guided"));
     }
+
+    #[tokio::test]
+    async fn chat_stream_deltas_become_text_chunks_then_a_final_chunk() {
+        let response = ChatResponse {
+            text: Some("Hello".into()),
+            tool_calls: Vec::new(),
+            usage: None,
+            reasoning_content: None,
+            finish_reason: None,
+        };
+        let deltas = stream::iter(vec![
+            Ok(ChatDelta::Text("Hel".into())),
+            Ok(ChatDelta::Text("lo".into())),
+            Ok(ChatDelta::Done(response)),
+        ])
+        .boxed();
+        let chunks: Vec<_> = delta_chunks(deltas, StreamOptions::new(true).with_token_count())
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 3);
+        assert!(matches!(&chunks[0], Ok(chunk) if chunk.delta == "Hel" && chunk.token_count == 1));
+        assert!(matches!(&chunks[1], Ok(chunk) if chunk.delta == "lo" && !chunk.is_final));
+        assert!(matches!(&chunks[2], Ok(chunk) if chunk.is_final && chunk.delta.is_empty()));
+
+        // Providers without incremental output answer with a single `Done`.
+        let request = ChatRequest {
+            messages: &[ChatMessage::user("Hello")],
+            tools: None,
+        };
+        let provider = MockProvider {
+            supports_native: false,
+        };
+        let deltas: Vec<_> = provider.chat_stream(request, "model", 0.7).collect().await;
+        assert_eq!(deltas.len(), 1);
+        assert!(matches!(&deltas[0], Ok(ChatDelta::Done(_))));
+    }
+
+    #[tokio::test]
+    async fn stream_lines_are_split_across_chunks_and_flushed_at_the_end() {
+        let body = stream::iter(
+            ["a\n", "b", "c\nd\u{e9}"]
+                .map(|chunk| Ok::<_, reqwest::Error>(chunk.as_bytes().to_vec())),
+        );
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut lines = Vec::new();
+        let finished = forward_stream_lines(body, &tx, |line| {
+            lines.push(line.to_string());
+            Ok(Some(line.trim().to_string()))
+        })
+        .await;
+        assert!(finished);
+        assert_eq!(lines, ["a\n", "bc\n", "d\u{e9}"]);
+        drop(tx);
+        let mut texts = Vec::new();
+        while let Some(Ok(ChatDelta::Text(text))) = rx.recv().await {
+            texts.push(text);
+        }
+        assert_eq!(texts, ["a", "bc", "d\u{e9}"]);
+
+        // A multi-byte character cut between chunks is decoded whole.
+        let bytes = "\u{e9}\n".as_bytes();
+        let body = stream::iter([
+            Ok::<_, reqwest::Error>(bytes[..1].to_vec()),
+            Ok(bytes[1..].to_vec()),
+        ]);
+        let (tx, _rx) = tokio::sync::mpsc::channel(8);
+        let mut lines = Vec::new();
+        forward_stream_lines(body, &tx, |line| {
+            lines.push(line.to_string());
+            Ok(None)
+        })
+        .await;
+        assert_eq!(lines, ["\u{e9}\n"]);
+    }
 }
@@ -37,6 +37,7 @@ pub mod pairing;
 pub mod policy;
 pub mod prompt_guard;
 pub mod secrets;
+pub mod tool_policy;
 pub mod traits;
 
 #[allow(unused_imports)]
@@ -53,6 +54,7 @@ pub use pairing::PairingGuard;
 pub use policy::{AutonomyLevel, SecurityPolicy};
 #[allow(unused_imports)]
 pub use secrets::SecretStore;
+pub use tool_policy::ToolPolicy;
 #[allow(unused_imports)]
 pub use traits::{NoopSandbox, Sandbox};
 // Prompt injection defense exports
@@ -152,7 +152,7 @@ fn home_dir() -> Option<PathBuf> {
     std::env::var_os("HOME").map(PathBuf::from)
 }
 
-fn expand_user_path(path: &str) -> PathBuf {
+pub(crate) fn expand_user_path(path: &str) -> PathBuf {
     if path == "~" {
         if let Some(home) = home_dir() {
             return home;
@@ -302,6 +302,18 @@ fn split_unquoted_segments(command: &str) -> Vec<String> {
     segments
 }
 
+/// Executable name of each sub-command of `command`, without its directory
+/// or leading environment assignments.
+pub(crate) fn command_executables(command: &str) -> Vec<String> {
+    split_unquoted_segments(command)
+        .iter()
+        .filter_map(|segment| skip_env_assignments(segment).split_whitespace().next())
+        .filter_map(|word| word.rsplit('/').next())
+        .filter(|base| !base.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
 /// Detect a single unquoted `&` operator (background/chain). `&&` is allowed.
 ///
 /// We treat any standalone `&` as unsafe in policy validation because it can
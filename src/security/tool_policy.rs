@@ -0,0 +1,693 @@
+//! Per-call tool permissions (`[autonomy.tool_policy]`).
+//!
+//! [`ToolPolicy`] sits between the tool loop and the tool registry. Before a
+//! call is dispatched, the loop asks [`check`] for a verdict under the rules
+//! of the surrounding [`scope`]: the configured defaults, then the rules for
+//! the message's channel and sender. Deny lists and patterns add up across
+//! these layers, and every layer with an allow list must admit the call, so
+//! a layer can only narrow what the others allow.
+//!
+//! Calls that need confirmation end the tool loop with [`ConfirmationRequired`]
+//! so the task engine can block the task until the user approves; the resumed
+//! run passes the approved calls back in through [`PolicyScope::approved`].
+//!
+//! `git_operations` calls are matched by the shell patterns as the git
+//! command they stand for (e.g. `git push --force origin main`), and the
+//! destructive ones (see [`destructive_git_call`]) always need confirmation.
+
+use super::policy::{command_executables, expand_user_path};
+use crate::config::{ToolPolicyConfig, ToolPolicyRules};
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Tools whose files are checked against `file_paths` (see [`file_path_arg`]).
+const FILE_TOOLS: &[&str] = &[
+    "file_read",
+    "file_write",
+    "file_edit",
+    "pdf_read",
+    "image_info",
+    "content_search",
+    "code_search",
+    "list_dir",
+    "send_file",
+    "glob_search",
+    "code_run",
+    "sql_query",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolVerdict {
+    Allow,
+    /// The call must not run; the reason is shown to the model.
+    Deny(String),
+    /// The call may only run once the user approves it.
+    Confirm(String),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Rules {
+    allow_tools: Vec<String>,
+    deny_tools: Vec<String>,
+    file_paths: Vec<String>,
+    shell_commands: Vec<String>,
+    shell_deny_patterns: Vec<Regex>,
+    confirm_tools: Vec<String>,
+    confirm_shell_patterns: Vec<Regex>,
+    sql_statements: Vec<String>,
+}
+
+impl Rules {
+    fn compile(scope: &str, rules: &ToolPolicyRules) -> Result<Self> {
+        let patterns = |field: &str, sources: &[String]| {
+            sources
+                .iter()
+                .map(|source| {
+                    Regex::new(source).with_context(|| {
+                        format!(
+                            "autonomy.tool_policy{scope}.{field} has an invalid regex: {source}"
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            allow_tools: rules.allow_tools.clone(),
+            deny_tools: rules.deny_tools.clone(),
+            file_paths: rules.file_paths.clone(),
+            shell_commands: rules.shell_commands.clone(),
+            shell_deny_patterns: patterns("shell_deny_patterns", &rules.shell_deny_patterns)?,
+            confirm_tools: rules.confirm_tools.clone(),
+            confirm_shell_patterns: patterns(
+                "confirm_shell_patterns",
+                &rules.confirm_shell_patterns,
+            )?,
+            sql_statements: rules
+                .sql_statements
+                .iter()
+                .map(|kind| kind.trim().to_ascii_lowercase())
+                .collect(),
+        })
+    }
+
+    fn denial(&self, tool: &str, args: &serde_json::Value, workspace_dir: &Path) -> Option<String> {
+        if self.deny_tools.iter().any(|denied| denied == tool) {
+            return Some(format!("`{tool}` is denied by the tool policy"));
+        }
+        if !self.allow_tools.is_empty() && !self.allow_tools.iter().any(|allowed| allowed == tool) {
+            return Some(format!(
+                "`{tool}` is not among the tool policy's allowed tools"
+            ));
+        }
+        if FILE_TOOLS.contains(&tool) && !self.file_paths.is_empty() {
+            let path = file_path_arg(tool, args);
+            let resolved = resolve(&path, workspace_dir);
+            if !self
+                .file_paths
+                .iter()
+                .any(|root| resolved.starts_with(resolve(root, workspace_dir)))
+            {
+                return Some(format!(
+                    "path `{path}` is outside the tool policy's file paths"
+                ));
+            }
+        }
+        if let Some(command) = command_line(tool, args) {
+            if let Some(pattern) = self
+                .shell_deny_patterns
+                .iter()
+                .find(|pattern| pattern.is_match(&command))
+            {
+                return Some(format!(
+                    "the command matches the denied pattern `{pattern}`"
+                ));
+            }
+        }
+        if tool == "sql_query" && !self.sql_statements.is_empty() {
+            let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("");
+            let kind = sql_statement_kind(query);
+            if !self.sql_statements.contains(&kind) {
+                return Some(format!(
+                    "`{kind}` statements are not among the tool policy's SQL statements"
+                ));
+            }
+        }
+        if matches!(tool, "shell" | "process_start") && !self.shell_commands.is_empty() {
+            if let Some(executable) = command_executables(shell_command(args))
+                .into_iter()
+                .find(|executable| !self.shell_commands.contains(executable))
+            {
+                return Some(format!(
+                    "`{executable}` is not among the tool policy's shell commands"
+                ));
+            }
+        }
+        None
+    }
+
+    fn confirmation(&self, tool: &str, args: &serde_json::Value) -> Option<String> {
+        if self.confirm_tools.iter().any(|confirm| confirm == tool) {
+            return Some(format!("`{tool}` calls need the user's approval"));
+        }
+        if let Some(command) = command_line(tool, args) {
+            if let Some(pattern) = self
+                .confirm_shell_patterns
+                .iter()
+                .find(|pattern| pattern.is_match(&command))
+            {
+                return Some(format!(
+                    "commands matching `{pattern}` need the user's approval"
+                ));
+            }
+        }
+        None
+    }
+}
+
+fn shell_command(args: &serde_json::Value) -> &str {
+    args.get("command").and_then(|v| v.as_str()).unwrap_or("")
+}
+
+/// Path a file tool call reaches: its `path` argument, the literal directory
+/// a `glob_search` pattern starts from, or the whole workspace for tools
+/// that can touch any file in it (`code_run`, `sql_query`).
+fn file_path_arg(tool: &str, args: &serde_json::Value) -> String {
+    let text = |key: &str| args.get(key).and_then(|v| v.as_str()).unwrap_or(".");
+    match tool {
+        "glob_search" => {
+            let literal: Vec<&str> = text("pattern")
+                .split('/')
+                .take_while(|part| !part.contains(['*', '?', '[', '{']))
+                .collect();
+            let dir = literal.join("/");
+            if dir.is_empty() {
+                ".".to_string()
+            } else {
+                dir
+            }
+        }
+        "code_run" | "sql_query" => ".".to_string(),
+        _ => text("path").to_string(),
+    }
+}
+
+/// Command line the shell patterns are matched against: a `shell` or
+/// `process_start` call's command, or the git command a `git_operations`
+/// call stands for.
+fn command_line(tool: &str, args: &serde_json::Value) -> Option<String> {
+    match tool {
+        "shell" | "process_start" => Some(shell_command(args).to_string()),
+        "git_operations" => Some(git_command_line(args)),
+        _ => None,
+    }
+}
+
+fn git_command_line(args: &serde_json::Value) -> String {
+    let text = |key: &str, default: &'static str| {
+        args.get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or(default)
+            .to_string()
+    };
+    let flag = |key: &str, set: &'static str| {
+        if args.get(key).and_then(|v| v.as_bool()).unwrap_or(false) {
+            set
+        } else {
+            ""
+        }
+    };
+    let operation = text("operation", "");
+    let line = match operation.as_str() {
+        "push" => format!(
+            "push {} {} {}",
+            flag("force", "--force"),
+            text("remote", "origin"),
+            text("branch", "")
+        ),
+        "reset" => format!(
+            "reset --{} {}",
+            text("mode", "mixed"),
+            text("target", "HEAD")
+        ),
+        "checkout" => format!("checkout {} {}", flag("create", "-b"), text("branch", "")),
+        "branch" => format!("branch {}", text("branch", "")),
+        "add" => format!("add {}", text("paths", "")),
+        "stash" => format!("stash {}", text("action", "push")),
+        _ => operation,
+    };
+    format!(
+        "git {}",
+        line.split_whitespace().collect::<Vec<_>>().join(" ")
+    )
+}
+
+/// Kind of a `sql_query` statement by its leading keyword: `select` for
+/// queries (`select`, `values`, `table`, and `with` without a data-modifying
+/// part), otherwise the keyword itself (`insert`, `update`, `pragma`, ...).
+pub fn sql_statement_kind(query: &str) -> String {
+    let mut rest = query.trim_start();
+    // Skip leading comments and parentheses.
+    loop {
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, after)| after);
+        } else if let Some(inner) = rest.strip_prefix('(') {
+            rest = inner;
+        } else {
+            break;
+        }
+        rest = rest.trim_start();
+    }
+    let mut words = rest
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase);
+    match words.next().as_deref() {
+        None => String::new(),
+        Some("select" | "values" | "table") => "select".into(),
+        Some("with") => words
+            .find(|word| matches!(word.as_str(), "insert" | "update" | "delete" | "merge"))
+            .unwrap_or_else(|| "select".into()),
+        Some(keyword) => keyword.to_string(),
+    }
+}
+
+/// What makes a `git_operations` call destructive (`push` with `force`, or
+/// `reset` with mode `hard`), if it is.
+pub fn destructive_git_call(args: &serde_json::Value) -> Option<&'static str> {
+    let text = |key: &str| args.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    match text("operation") {
+        "push" if args.get("force").and_then(|v| v.as_bool()) == Some(true) => Some("force pushes"),
+        "reset" if text("mode") == "hard" => Some("hard resets"),
+        _ => None,
+    }
+}
+
+/// `path` made absolute against `workspace_dir`, with `.` and `..` resolved
+/// lexically so paths that do not exist yet can be checked.
+fn resolve(path: &str, workspace_dir: &Path) -> PathBuf {
+    let expanded = expand_user_path(path.trim());
+    let joined = if expanded.is_absolute() {
+        expanded
+    } else {
+        workspace_dir.join(expanded)
+    };
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Compiled `[autonomy.tool_policy]`.
+#[derive(Debug, Clone, Default)]
+pub struct ToolPolicy {
+    rules: Rules,
+    channels: HashMap<String, Rules>,
+    /// Keyed by lowercased sender id.
+    senders: HashMap<String, Rules>,
+}
+
+impl ToolPolicy {
+    pub fn from_config(config: &ToolPolicyConfig) -> Result<Self> {
+        let layers = |kind: &str, rules: &HashMap<String, ToolPolicyRules>, lowercase: bool| {
+            rules
+                .iter()
+                .map(|(key, rules)| {
+                    let compiled = Rules::compile(&format!(".{kind}.\"{key}\""), rules)?;
+                    let key = if lowercase {
+                        key.to_lowercase()
+                    } else {
+                        key.clone()
+                    };
+                    Ok((key, compiled))
+                })
+                .collect::<Result<HashMap<_, _>>>()
+        };
+        Ok(Self {
+            rules: Rules::compile("", &config.rules)?,
+            channels: layers("channels", &config.channels, false)?,
+            senders: layers("senders", &config.senders, true)?,
+        })
+    }
+
+    /// Verdict for `tool` called with `args` by `sender` on `channel`.
+    pub fn check(
+        &self,
+        channel: &str,
+        sender: &str,
+        workspace_dir: &Path,
+        tool: &str,
+        args: &serde_json::Value,
+    ) -> ToolVerdict {
+        let layers: Vec<&Rules> = std::iter::once(&self.rules)
+            .chain(self.channels.get(channel))
+            .chain(self.senders.get(&sender.to_lowercase()))
+            .collect();
+        if let Some(reason) = layers
+            .iter()
+            .find_map(|rules| rules.denial(tool, args, workspace_dir))
+        {
+            return ToolVerdict::Deny(reason);
+        }
+        if tool == "git_operations" {
+            if let Some(what) = destructive_git_call(args) {
+                return ToolVerdict::Confirm(format!("git {what} need the user's approval"));
+            }
+        }
+        layers
+            .iter()
+            .find_map(|rules| rules.confirmation(tool, args))
+            .map_or(ToolVerdict::Allow, ToolVerdict::Confirm)
+    }
+}
+
+/// Who is calling tools in a run, and which calls they already approved.
+#[derive(Debug, Clone)]
+pub struct PolicyScope {
+    pub policy: Arc<ToolPolicy>,
+    pub channel: String,
+    pub sender: String,
+    pub workspace_dir: PathBuf,
+    /// Argument hashes of calls the user approved.
+    pub approved: HashSet<String>,
+    /// Whether the run can stop to ask for approval. Without it, calls that
+    /// need confirmation are denied.
+    pub confirmable: bool,
+}
+
+tokio::task_local! {
+    static SCOPE: PolicyScope;
+}
+
+/// Run `future` with its tool calls checked against `scope`.
+pub async fn scope<F: Future>(scope: PolicyScope, future: F) -> F::Output {
+    SCOPE.scope(scope, future).await
+}
+
+/// Whether tool calls are being checked against a policy scope.
+pub fn in_scope() -> bool {
+    SCOPE.try_with(|_| ()).is_ok()
+}
+
+/// Verdict for a call in the surrounding scope. Calls outside any scope are
+/// allowed, and so are calls needing confirmation the user already approved.
+pub fn check(tool: &str, args: &serde_json::Value, arguments_hash: &str) -> ToolVerdict {
+    SCOPE
+        .try_with(|scope| {
+            match scope.policy.check(
+                &scope.channel,
+                &scope.sender,
+                &scope.workspace_dir,
+                tool,
+                args,
+            ) {
+                ToolVerdict::Confirm(_) if scope.approved.contains(arguments_hash) => {
+                    ToolVerdict::Allow
+                }
+                ToolVerdict::Confirm(reason) if !scope.confirmable => ToolVerdict::Deny(format!(
+                    "{reason}, and approvals can only be asked for in task runs"
+                )),
+                verdict => verdict,
+            }
+        })
+        .unwrap_or(ToolVerdict::Allow)
+}
+
+/// Error ending a tool loop at a call that needs the user's approval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmationRequired {
+    pub tool: String,
+    pub arguments_hash: String,
+    /// Short, credential-scrubbed summary of the call's arguments.
+    pub summary: String,
+    pub reason: String,
+}
+
+impl ConfirmationRequired {
+    pub fn find(err: &anyhow::Error) -> Option<&Self> {
+        err.chain().find_map(|source| source.downcast_ref::<Self>())
+    }
+}
+
+impl std::fmt::Display for ConfirmationRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` call waits for approval ({}): {}",
+            self.tool, self.reason, self.summary
+        )
+    }
+}
+
+impl std::error::Error for ConfirmationRequired {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy() -> ToolPolicy {
+        ToolPolicy::from_config(&ToolPolicyConfig {
+            rules: ToolPolicyRules {
+                deny_tools: vec!["browser".into()],
+                shell_deny_patterns: vec![r"\brm\s+-rf\b".into()],
+                confirm_shell_patterns: vec![r"^git\s+push\b".into()],
+                ..ToolPolicyRules::default()
+            },
+            channels: HashMap::from([(
+                "telegram".to_string(),
+                ToolPolicyRules {
+                    file_paths: vec!["notes".into()],
+                    confirm_tools: vec!["file_write".into()],
+                    ..ToolPolicyRules::default()
+                },
+            )]),
+            senders: HashMap::from([(
+                "Guest@Example.com".to_string(),
+                ToolPolicyRules {
+                    allow_tools: vec!["shell".into(), "file_read".into()],
+                    shell_commands: vec!["ls".into(), "git".into()],
+                    ..ToolPolicyRules::default()
+                },
+            )]),
+        })
+        .unwrap()
+    }
+
+    fn verdict(channel: &str, sender: &str, tool: &str, args: serde_json::Value) -> ToolVerdict {
+        policy().check(channel, sender, Path::new("/ws"), tool, &args)
+    }
+
+    #[test]
+    fn layers_deny_by_tool_and_command() {
+        assert!(matches!(
+            verdict("cli", "me", "browser", json!({})),
+            ToolVerdict::Deny(_)
+        ));
+        assert!(matches!(
+            verdict(
+                "cli",
+                "me",
+                "shell",
+                json!({ "command": "cd build && rm -rf out" })
+            ),
+            ToolVerdict::Deny(_)
+        ));
+        assert_eq!(
+            verdict("cli", "me", "shell", json!({ "command": "cargo build" })),
+            ToolVerdict::Allow
+        );
+
+        // The sender's allow lists narrow the defaults further.
+        assert!(matches!(
+            verdict(
+                "cli",
+                "guest@example.com",
+                "file_write",
+                json!({ "path": "a.txt" })
+            ),
+            ToolVerdict::Deny(_)
+        ));
+        assert_eq!(
+            verdict(
+                "cli",
+                "guest@example.com",
+                "shell",
+                json!({ "command": "ls | git status" })
+            ),
+            ToolVerdict::Allow
+        );
+        assert_eq!(
+            verdict(
+                "cli",
+                "guest@example.com",
+                "shell",
+                json!({ "command": "ls && curl x" })
+            ),
+            ToolVerdict::Deny("`curl` is not among the tool policy's shell commands".into())
+        );
+    }
+
+    #[test]
+    fn file_paths_are_resolved_against_the_workspace() {
+        let read = |path: &str| verdict("telegram", "me", "file_read", json!({ "path": path }));
+        assert_eq!(read("notes/today.md"), ToolVerdict::Allow);
+        assert_eq!(read("/ws/notes/./todo.md"), ToolVerdict::Allow);
+        assert!(matches!(read("notes/../secrets.txt"), ToolVerdict::Deny(_)));
+        assert!(matches!(read("/etc/passwd"), ToolVerdict::Deny(_)));
+        let glob = |pattern: &str| {
+            verdict(
+                "telegram",
+                "me",
+                "glob_search",
+                json!({ "pattern": pattern }),
+            )
+        };
+        assert_eq!(glob("notes/**/*.md"), ToolVerdict::Allow);
+        assert!(matches!(glob("**/*.md"), ToolVerdict::Deny(_)));
+        assert!(matches!(
+            verdict(
+                "telegram",
+                "me",
+                "code_run",
+                json!({ "language": "python" })
+            ),
+            ToolVerdict::Deny(_)
+        ));
+        assert!(matches!(
+            verdict(
+                "telegram",
+                "me",
+                "sql_query",
+                json!({ "query": "select 1" })
+            ),
+            ToolVerdict::Deny(_)
+        ));
+        // Path rules only apply on the channel that set them.
+        assert_eq!(
+            verdict("cli", "me", "file_read", json!({ "path": "/etc/passwd" })),
+            ToolVerdict::Allow
+        );
+    }
+
+    #[tokio::test]
+    async fn approved_calls_skip_confirmation() {
+        let args = json!({ "command": "git push origin main" });
+        let make_scope = |approved: HashSet<String>| PolicyScope {
+            policy: Arc::new(policy()),
+            channel: "cli".into(),
+            sender: "me".into(),
+            workspace_dir: PathBuf::from("/ws"),
+            approved,
+            confirmable: true,
+        };
+        assert_eq!(check("shell", &args, "abc"), ToolVerdict::Allow);
+
+        let pending = scope(make_scope(HashSet::new()), async {
+            check("shell", &args, "abc")
+        })
+        .await;
+        assert!(matches!(pending, ToolVerdict::Confirm(_)));
+
+        let approved = scope(make_scope(HashSet::from(["abc".to_string()])), async {
+            check("shell", &args, "abc")
+        })
+        .await;
+        assert_eq!(approved, ToolVerdict::Allow);
+    }
+
+    #[test]
+    fn git_calls_are_matched_as_git_commands() {
+        let git = |args: serde_json::Value| verdict("cli", "me", "git_operations", args);
+        assert_eq!(git(json!({ "operation": "status" })), ToolVerdict::Allow);
+        // `confirm_shell_patterns` has `^git\s+push\b`.
+        assert!(matches!(
+            git(json!({ "operation": "push", "branch": "main" })),
+            ToolVerdict::Confirm(_)
+        ));
+        assert_eq!(
+            git(json!({ "operation": "reset", "mode": "hard", "target": "HEAD~1" })),
+            ToolVerdict::Confirm("git hard resets need the user's approval".into())
+        );
+        assert_eq!(
+            git_command_line(&json!({ "operation": "push", "force": true, "branch": "dev" })),
+            "git push --force origin dev"
+        );
+        assert_eq!(
+            git_command_line(&json!({ "operation": "checkout", "create": true, "branch": "fix" })),
+            "git checkout -b fix"
+        );
+    }
+
+    #[test]
+    fn sql_statements_are_classified_and_limited_per_layer() {
+        assert_eq!(sql_statement_kind("  -- count\nSELECT 1"), "select");
+        assert_eq!(
+            sql_statement_kind("with t as (select 1) select * from t"),
+            "select"
+        );
+        assert_eq!(
+            sql_statement_kind("WITH old AS (SELECT id FROM a) DELETE FROM b"),
+            "delete"
+        );
+        assert_eq!(sql_statement_kind("/* x */ (select 1)"), "select");
+        assert_eq!(sql_statement_kind("PRAGMA table_info(t)"), "pragma");
+
+        let policy = ToolPolicy::from_config(&ToolPolicyConfig {
+            senders: HashMap::from([(
+                "analyst".to_string(),
+                ToolPolicyRules {
+                    sql_statements: vec!["SELECT".into()],
+                    ..ToolPolicyRules::default()
+                },
+            )]),
+            ..ToolPolicyConfig::default()
+        })
+        .unwrap();
+        let query = |sender: &str, sql: &str| {
+            policy.check(
+                "cli",
+                sender,
+                Path::new("/ws"),
+                "sql_query",
+                &json!({ "query": sql }),
+            )
+        };
+        assert_eq!(query("analyst", "select * from t"), ToolVerdict::Allow);
+        assert!(matches!(
+            query("analyst", "update t set a = 1"),
+            ToolVerdict::Deny(_)
+        ));
+        assert_eq!(query("admin", "update t set a = 1"), ToolVerdict::Allow);
+    }
+
+    #[test]
+    fn invalid_patterns_name_their_setting() {
+        let err = ToolPolicy::from_config(&ToolPolicyConfig {
+            channels: HashMap::from([(
+                "slack".to_string(),
+                ToolPolicyRules {
+                    shell_deny_patterns: vec!["(".into()],
+                    ..ToolPolicyRules::default()
+                },
+            )]),
+            ..ToolPolicyConfig::default()
+        })
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("autonomy.tool_policy.channels.\"slack\".shell_deny_patterns"));
+    }
+}
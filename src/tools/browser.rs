@@ -4,20 +4,56 @@
 //! Optionally, a Rust-native backend can be enabled at build time via
 //! `--features browser-native` and selected through config.
 //! Computer-use (OS-level) actions are supported via an optional sidecar endpoint.
+//!
+//! Task runs execute inside [`budget_scope`], which caps how many browser
+//! actions one run may take (`browser.max_actions_per_run`) so a model stuck
+//! clicking through a page cannot drive the browser indefinitely.
 
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolKind, ToolResult};
 use crate::security::SecurityPolicy;
 use anyhow::Context;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::future::Future;
 use std::net::ToSocketAddrs;
 use std::process::Stdio;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::process::Command;
 use tracing::debug;
 
+tokio::task_local! {
+    static RUN_ACTIONS: Arc<AtomicU32>;
+}
+
+/// Run `future` with its browser actions counted against one per-run budget.
+pub async fn budget_scope<F: Future>(future: F) -> F::Output {
+    RUN_ACTIONS.scope(Arc::default(), future).await
+}
+
+/// Count one action against the surrounding run's budget. Outside a run, or
+/// with `max_actions` 0, nothing is counted.
+fn take_run_action(max_actions: u32) -> Result<(), String> {
+    if max_actions == 0 {
+        return Ok(());
+    }
+    RUN_ACTIONS
+        .try_with(|used| {
+            let previous = used.fetch_add(1, Ordering::SeqCst);
+            if previous >= max_actions {
+                used.fetch_sub(1, Ordering::SeqCst);
+                Err(format!(
+                    "Browser action budget exhausted: this run already used {max_actions} actions (browser.max_actions_per_run). Finish with what you have or ask the user to continue."
+                ))
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap_or(Ok(()))
+}
+
 /// Computer-use sidecar settings.
 #[derive(Clone)]
 pub struct ComputerUseConfig {
@@ -67,6 +103,8 @@ pub struct BrowserTool {
     native_webdriver_url: String,
     native_chrome_path: Option<String>,
     computer_use: ComputerUseConfig,
+    /// Actions one run may take; 0 means unlimited.
+    max_actions_per_run: u32,
     #[cfg(feature = "browser-native")]
     native_state: tokio::sync::Mutex<native_backend::NativeBrowserState>,
 }
@@ -234,11 +272,18 @@ impl BrowserTool {
             native_webdriver_url,
             native_chrome_path,
             computer_use,
+            max_actions_per_run: 0,
             #[cfg(feature = "browser-native")]
             native_state: tokio::sync::Mutex::new(native_backend::NativeBrowserState::default()),
         }
     }
 
+    /// Cap the actions one run may take; 0 leaves runs unlimited.
+    pub fn with_max_actions_per_run(mut self, max_actions: u32) -> Self {
+        self.max_actions_per_run = max_actions;
+        self
+    }
+
     /// Check if agent-browser CLI is available
     pub async fn is_agent_browser_available() -> bool {
         Command::new("agent-browser")
@@ -878,7 +923,9 @@ impl Tool for BrowserTool {
             "Web/browser automation with pluggable backends (agent-browser, rust-native, computer_use). ",
             "Supports DOM actions plus optional OS-level actions (mouse_move, mouse_click, mouse_drag, ",
             "key_type, key_press, screen_capture) through a computer-use sidecar. Use 'snapshot' to map ",
-            "interactive elements to refs (@e1, @e2). Enforces browser.allowed_domains for open actions."
+            "interactive elements to refs (@e1, @e2). Use 'extract_text' to read a page's visible text ",
+            "(or one element's with 'selector'). Enforces browser.allowed_domains for open actions ",
+            "and browser.max_actions_per_run per run."
         )
     }
 
@@ -889,7 +936,7 @@ impl Tool for BrowserTool {
                 "action": {
                     "type": "string",
                     "enum": ["open", "snapshot", "click", "fill", "type", "get_text",
-                             "get_title", "get_url", "screenshot", "wait", "press",
+                             "extract_text", "get_title", "get_url", "screenshot", "wait", "press",
                              "hover", "scroll", "is_visible", "close", "find",
                              "mouse_move", "mouse_click", "mouse_drag", "key_type",
                              "key_press", "screen_capture"],
@@ -996,6 +1043,10 @@ impl Tool for BrowserTool {
         })
     }
 
+    fn kind(&self) -> ToolKind {
+        ToolKind::Network
+    }
+
     async fn execute(&self, args: Value) -> anyhow::Result<ToolResult> {
         // Security checks
         if !self.security.can_act() {
@@ -1039,6 +1090,17 @@ impl Tool for BrowserTool {
             });
         }
 
+        // Closing the browser is always allowed so a run can clean up.
+        if action_str != "close" {
+            if let Err(error) = take_run_action(self.max_actions_per_run) {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(error),
+                });
+            }
+        }
+
         if backend == ResolvedBackend::ComputerUse {
             return self.execute_computer_use_action(action_str, &args).await;
         }
@@ -1812,6 +1874,13 @@ fn parse_browser_action(action_str: &str, args: &Value) -> anyhow::Result<Browse
                 selector: selector.into(),
             })
         }
+        "extract_text" => Ok(BrowserAction::GetText {
+            selector: args
+                .get("selector")
+                .and_then(|v| v.as_str())
+                .unwrap_or("body")
+                .into(),
+        }),
         "get_title" => Ok(BrowserAction::GetTitle),
         "get_url" => Ok(BrowserAction::GetUrl),
         "screenshot" => Ok(BrowserAction::Screenshot {
@@ -1906,6 +1975,7 @@ fn is_supported_browser_action(action: &str) -> bool {
             | "fill"
             | "type"
             | "get_text"
+            | "extract_text"
             | "get_title"
             | "get_url"
             | "screenshot"
@@ -2389,6 +2459,28 @@ mod tests {
         assert!(tool.validate_url("https://example.com").is_err());
     }
 
+    #[tokio::test]
+    async fn run_budget_stops_actions_past_the_limit() {
+        let security = Arc::new(SecurityPolicy::default());
+        let tool = BrowserTool::new(security, vec!["example.com".into()], None)
+            .with_max_actions_per_run(1);
+
+        budget_scope(async {
+            assert!(take_run_action(tool.max_actions_per_run).is_ok());
+            let error = take_run_action(tool.max_actions_per_run).unwrap_err();
+            assert!(error.contains("budget exhausted"));
+        })
+        .await;
+        // A new run starts with a fresh budget; outside a run nothing is counted.
+        budget_scope(async { assert!(take_run_action(1).is_ok()) }).await;
+        assert!(take_run_action(1).is_ok());
+
+        match parse_browser_action("extract_text", &json!({})).unwrap() {
+            BrowserAction::GetText { selector } => assert_eq!(selector, "body"),
+            other => panic!("unexpected action: {other:?}"),
+        }
+    }
+
     #[test]
     fn computer_use_only_action_detection_is_correct() {
         assert!(is_computer_use_only_action("mouse_move"));
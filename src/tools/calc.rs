@@ -0,0 +1,722 @@
+//! Exact-enough arithmetic, unit conversion, and date math for the model,
+//! so it neither computes in its head nor shells out to `bc`.
+//!
+//! Three forms are accepted:
+//! - arithmetic: `(1200 * 1.07^5) / 12`, `sqrt(2) * max(3, 4)`
+//! - conversion: `<expression> <unit> to <unit>`, e.g. `72 F to C`,
+//!   `3.5 GiB in MB`, `(5 + 7) ft to m`
+//! - dates: `2026-10-16 + 45 days`, `today - 2 weeks + 1 month`,
+//!   `2026-12-25 - 2026-10-16`
+
+use super::traits::{Tool, ToolResult};
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use chrono::{Datelike, Months, NaiveDate};
+use serde_json::json;
+
+/// Evaluate arithmetic, unit conversions, and date math
+pub struct CalcTool;
+
+impl CalcTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for CalcTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for CalcTool {
+    fn name(&self) -> &str {
+        "calc"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluate a math expression exactly instead of computing it yourself. Supports + - * / % ^, parentheses, \
+         functions (sqrt, cbrt, abs, exp, ln, log, log2, sin, cos, tan, asin, acos, atan, round, floor, ceil, min, max, pow) \
+         and constants (pi, e, tau); unit conversion as '<expr> <unit> to <unit>' (length, mass, time, data, volume, speed, temperature); \
+         and date math such as '2026-10-16 + 45 days', 'today - 2 weeks', or '2026-12-25 - 2026-10-16'."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Expression to evaluate, e.g. '1500 * 1.05^3', '12 km to mi', '2026-10-16 + 90 days'"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let expression = args
+            .get("expression")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'expression' parameter"))?;
+
+        match evaluate(expression) {
+            Ok(result) => Ok(ToolResult {
+                success: true,
+                output: format!("{} = {result}", expression.trim()),
+                error: None,
+            }),
+            Err(e) => Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!("Cannot evaluate '{}': {e}", expression.trim())),
+            }),
+        }
+    }
+}
+
+/// Evaluate `expression` in whichever of the three forms it is written.
+pub fn evaluate(expression: &str) -> anyhow::Result<String> {
+    let expression = expression.trim();
+    if expression.is_empty() {
+        bail!("empty expression");
+    }
+    if let Some(result) = evaluate_date(expression)? {
+        return Ok(result);
+    }
+    if let Some(result) = evaluate_conversion(expression)? {
+        return Ok(result);
+    }
+    Ok(format_number(evaluate_arithmetic(expression)?))
+}
+
+fn format_number(value: f64) -> String {
+    if value == 0.0 {
+        return "0".into();
+    }
+    let magnitude = value.abs();
+    if !(1e-6..1e15).contains(&magnitude) {
+        return format!("{value:e}");
+    }
+    let fixed = format!("{value:.10}");
+    fixed
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+// ── Arithmetic ─────────────────────────────────────────────────
+
+/// Deepest nesting of parentheses, signs, and exponents an expression may
+/// use, so the recursive parser cannot run out of stack.
+const MAX_NESTING: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            // Exponent: 1e3, 2.5E-4
+            if i < chars.len()
+                && matches!(chars[i], 'e' | 'E')
+                && (chars.get(i + 1).is_some_and(char::is_ascii_digit)
+                    || (matches!(chars.get(i + 1), Some('+' | '-'))
+                        && chars.get(i + 2).is_some_and(char::is_ascii_digit)))
+            {
+                i += 2;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            let number = text
+                .parse::<f64>()
+                .with_context(|| format!("invalid number '{text}'"))?;
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            match c {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    tokens.push(Token::Op('^'));
+                    i += 1;
+                }
+                '+' | '-' | '*' | '/' | '%' | '^' => tokens.push(Token::Op(c)),
+                '×' => tokens.push(Token::Op('*')),
+                '÷' => tokens.push(Token::Op('/')),
+                '(' => tokens.push(Token::LParen),
+                ')' => tokens.push(Token::RParen),
+                ',' => tokens.push(Token::Comma),
+                other => bail!("unexpected character '{other}'"),
+            }
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: &Token, what: &str) -> anyhow::Result<()> {
+        match self.advance() {
+            Some(ref token) if token == expected => Ok(()),
+            _ => bail!("expected {what}"),
+        }
+    }
+
+    /// expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> anyhow::Result<f64> {
+        let mut value = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> anyhow::Result<f64> {
+        let mut value = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/' | '%'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            value = match op {
+                '*' => value * rhs,
+                _ if rhs == 0.0 => bail!("division by zero"),
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    /// unary := ('-' | '+') unary | power
+    ///
+    /// Every nested parenthesis, sign, and exponent recurses through here,
+    /// so this is where the nesting depth is counted.
+    fn unary(&mut self) -> anyhow::Result<f64> {
+        if self.depth >= MAX_NESTING {
+            bail!("expression nests deeper than {MAX_NESTING} levels");
+        }
+        self.depth += 1;
+        let value = self.signed();
+        self.depth -= 1;
+        value
+    }
+
+    fn signed(&mut self) -> anyhow::Result<f64> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.pos += 1;
+                Ok(-self.unary()?)
+            }
+            Some(Token::Op('+')) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// power := primary ('^' unary)?, right-associative so `-2^2` is -4
+    fn power(&mut self) -> anyhow::Result<f64> {
+        let base = self.primary()?;
+        if self.peek() == Some(&Token::Op('^')) {
+            self.pos += 1;
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> anyhow::Result<f64> {
+        match self.advance() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::LParen) => {
+                let value = self.expr()?;
+                self.expect(&Token::RParen, "')'")?;
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::LParen) {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if self.peek() != Some(&Token::RParen) {
+                        args.push(self.expr()?);
+                        while self.peek() == Some(&Token::Comma) {
+                            self.pos += 1;
+                            args.push(self.expr()?);
+                        }
+                    }
+                    self.expect(&Token::RParen, "')' after function arguments")?;
+                    call_function(&name, &args)
+                } else {
+                    constant(&name)
+                }
+            }
+            Some(token) => bail!("unexpected {token:?}"),
+            None => bail!("unexpected end of expression"),
+        }
+    }
+}
+
+fn constant(name: &str) -> anyhow::Result<f64> {
+    match name.to_ascii_lowercase().as_str() {
+        "pi" => Ok(std::f64::consts::PI),
+        "e" => Ok(std::f64::consts::E),
+        "tau" => Ok(std::f64::consts::TAU),
+        _ => bail!("unknown name '{name}'"),
+    }
+}
+
+fn call_function(name: &str, args: &[f64]) -> anyhow::Result<f64> {
+    let lower = name.to_ascii_lowercase();
+    let one = |f: fn(f64) -> f64| -> anyhow::Result<f64> {
+        match args {
+            [x] => Ok(f(*x)),
+            _ => bail!("{name}() takes 1 argument, got {}", args.len()),
+        }
+    };
+    match lower.as_str() {
+        "sqrt" => one(f64::sqrt),
+        "cbrt" => one(f64::cbrt),
+        "abs" => one(f64::abs),
+        "exp" => one(f64::exp),
+        "ln" => one(f64::ln),
+        "log2" => one(f64::log2),
+        "log" | "log10" => match args {
+            [x] => Ok(x.log10()),
+            [x, base] if lower == "log" => Ok(x.log(*base)),
+            _ => bail!("{name}() takes 1 argument (or 2 for log(x, base))"),
+        },
+        "sin" => one(f64::sin),
+        "cos" => one(f64::cos),
+        "tan" => one(f64::tan),
+        "asin" => one(f64::asin),
+        "acos" => one(f64::acos),
+        "atan" => one(f64::atan),
+        "floor" => one(f64::floor),
+        "ceil" => one(f64::ceil),
+        "round" => match args {
+            [x] => Ok(x.round()),
+            [x, digits] => {
+                // Past 15 decimal places an f64 has no digits left to round.
+                let Some(digits) = (0..=15).find(|&d| f64::from(d) == *digits) else {
+                    bail!("round() digits must be a whole number from 0 to 15");
+                };
+                let scale = 10f64.powi(digits);
+                Ok((x * scale).round() / scale)
+            }
+            _ => bail!("round() takes 1 or 2 arguments"),
+        },
+        "pow" => match args {
+            [x, y] => Ok(x.powf(*y)),
+            _ => bail!("pow() takes 2 arguments"),
+        },
+        "min" | "max" if !args.is_empty() => {
+            let fold = if lower == "min" { f64::min } else { f64::max };
+            Ok(args.iter().copied().fold(args[0], fold))
+        }
+        "min" | "max" => bail!("{name}() needs at least 1 argument"),
+        _ => bail!("unknown function '{name}'"),
+    }
+}
+
+fn evaluate_arithmetic(expression: &str) -> anyhow::Result<f64> {
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expr()?;
+    if let Some(token) = parser.peek() {
+        bail!("unexpected {token:?} after the expression");
+    }
+    if !value.is_finite() {
+        bail!("result is not a finite number");
+    }
+    Ok(value)
+}
+
+// ── Unit conversion ────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Time,
+    Data,
+    Volume,
+    Speed,
+    Temperature,
+}
+
+/// Unit names (matched case-insensitively; the first is shown in results)
+/// with their dimension and size in the dimension's base unit (m, kg, s,
+/// byte, L, m/s). Temperatures are converted through [`to_kelvin`] instead.
+#[rustfmt::skip]
+const UNITS: &[(&[&str], Dimension, f64)] = &[
+    (&["mm", "millimeter", "millimeters"], Dimension::Length, 0.001),
+    (&["cm", "centimeter", "centimeters"], Dimension::Length, 0.01),
+    (&["m", "meter", "meters", "metre", "metres"], Dimension::Length, 1.0),
+    (&["km", "kilometer", "kilometers"], Dimension::Length, 1000.0),
+    (&["in", "inch", "inches"], Dimension::Length, 0.0254),
+    (&["ft", "foot", "feet"], Dimension::Length, 0.3048),
+    (&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+    (&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+    (&["nmi", "nautical mile", "nautical miles"], Dimension::Length, 1852.0),
+    (&["mg", "milligram", "milligrams"], Dimension::Mass, 1e-6),
+    (&["g", "gram", "grams"], Dimension::Mass, 0.001),
+    (&["kg", "kilogram", "kilograms"], Dimension::Mass, 1.0),
+    (&["t", "tonne", "tonnes"], Dimension::Mass, 1000.0),
+    (&["oz", "ounce", "ounces"], Dimension::Mass, 0.028349523125),
+    (&["lb", "lbs", "pound", "pounds"], Dimension::Mass, 0.45359237),
+    (&["ms", "millisecond", "milliseconds"], Dimension::Time, 0.001),
+    (&["s", "sec", "secs", "second", "seconds"], Dimension::Time, 1.0),
+    (&["min", "mins", "minute", "minutes"], Dimension::Time, 60.0),
+    (&["h", "hr", "hrs", "hour", "hours"], Dimension::Time, 3600.0),
+    (&["d", "day", "days"], Dimension::Time, 86_400.0),
+    (&["wk", "week", "weeks"], Dimension::Time, 604_800.0),
+    (&["yr", "year", "years"], Dimension::Time, 31_557_600.0),
+    (&["bit", "bits"], Dimension::Data, 0.125),
+    (&["B", "byte", "bytes"], Dimension::Data, 1.0),
+    (&["kB", "kilobyte", "kilobytes"], Dimension::Data, 1e3),
+    (&["MB", "megabyte", "megabytes"], Dimension::Data, 1e6),
+    (&["GB", "gigabyte", "gigabytes"], Dimension::Data, 1e9),
+    (&["TB", "terabyte", "terabytes"], Dimension::Data, 1e12),
+    (&["KiB", "kibibyte", "kibibytes"], Dimension::Data, 1024.0),
+    (&["MiB", "mebibyte", "mebibytes"], Dimension::Data, 1_048_576.0),
+    (&["GiB", "gibibyte", "gibibytes"], Dimension::Data, 1_073_741_824.0),
+    (&["TiB", "tebibyte", "tebibytes"], Dimension::Data, 1_099_511_627_776.0),
+    (&["mL", "milliliter", "milliliters"], Dimension::Volume, 0.001),
+    (&["L", "liter", "liters", "litre", "litres"], Dimension::Volume, 1.0),
+    (&["m3", "cubic meter", "cubic meters"], Dimension::Volume, 1000.0),
+    (&["floz", "fl oz", "fluid ounce", "fluid ounces"], Dimension::Volume, 0.0295735295625),
+    (&["cup", "cups"], Dimension::Volume, 0.2365882365),
+    (&["gal", "gallon", "gallons"], Dimension::Volume, 3.785411784),
+    (&["m/s", "mps"], Dimension::Speed, 1.0),
+    (&["km/h", "kmh", "kph"], Dimension::Speed, 1000.0 / 3600.0),
+    (&["mph", "mi/h"], Dimension::Speed, 0.44704),
+    (&["kn", "knot", "knots"], Dimension::Speed, 1852.0 / 3600.0),
+    (&["°C", "c", "celsius"], Dimension::Temperature, 0.0),
+    (&["°F", "f", "fahrenheit"], Dimension::Temperature, 0.0),
+    (&["K", "kelvin"], Dimension::Temperature, 0.0),
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Unit {
+    name: &'static str,
+    dimension: Dimension,
+    factor: f64,
+}
+
+fn lookup_unit(name: &str) -> Option<Unit> {
+    let name = name.trim();
+    UNITS.iter().find_map(|(names, dimension, factor)| {
+        names
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(name))
+            .then_some(Unit {
+                name: names[0],
+                dimension: *dimension,
+                factor: *factor,
+            })
+    })
+}
+
+fn to_kelvin(value: f64, unit: &str) -> f64 {
+    match unit {
+        "°C" => value + 273.15,
+        "°F" => (value - 32.0) * 5.0 / 9.0 + 273.15,
+        _ => value,
+    }
+}
+
+fn from_kelvin(kelvin: f64, unit: &str) -> f64 {
+    match unit {
+        "°C" => kelvin - 273.15,
+        "°F" => (kelvin - 273.15) * 9.0 / 5.0 + 32.0,
+        _ => kelvin,
+    }
+}
+
+/// `<expression> <unit> to|in <unit>`; `None` when the input is not a
+/// conversion.
+fn evaluate_conversion(expression: &str) -> anyhow::Result<Option<String>> {
+    let lower = expression.to_ascii_lowercase();
+    let Some((split, keyword_len)) = [" to ", " in "]
+        .iter()
+        .filter_map(|keyword| lower.rfind(keyword).map(|at| (at, keyword.len())))
+        .max_by_key(|(at, _)| *at)
+    else {
+        return Ok(None);
+    };
+    let Some(target) = lookup_unit(&expression[split + keyword_len..]) else {
+        return Ok(None);
+    };
+    let source_text = expression[..split].trim();
+
+    // The longest trailing unit name, so `ft` wins over `t` and `km` over `m`.
+    let Some((amount_text, source)) = source_text
+        .char_indices()
+        .skip(1)
+        .find_map(|(at, _)| lookup_unit(&source_text[at..]).map(|unit| (&source_text[..at], unit)))
+    else {
+        bail!("no unit to convert from in '{source_text}'");
+    };
+    if source.dimension != target.dimension {
+        bail!(
+            "cannot convert {} ({:?}) to {} ({:?})",
+            source.name,
+            source.dimension,
+            target.name,
+            target.dimension
+        );
+    }
+
+    let amount = evaluate_arithmetic(amount_text)?;
+    let converted = if source.dimension == Dimension::Temperature {
+        from_kelvin(to_kelvin(amount, source.name), target.name)
+    } else {
+        amount * source.factor / target.factor
+    };
+    Ok(Some(format!(
+        "{} {}",
+        format_number(converted),
+        target.name
+    )))
+}
+
+// ── Date math ──────────────────────────────────────────────────
+
+enum DateOperand {
+    Date(NaiveDate),
+    Days(i64),
+    Months(i64),
+}
+
+fn parse_date_operand(text: &str, today: NaiveDate) -> anyhow::Result<DateOperand> {
+    let text = text.trim().to_lowercase();
+    match text.as_str() {
+        "today" | "now" => return Ok(DateOperand::Date(today)),
+        "tomorrow" => return Ok(DateOperand::Date(today + chrono::Duration::days(1))),
+        "yesterday" => return Ok(DateOperand::Date(today - chrono::Duration::days(1))),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+        return Ok(DateOperand::Date(date));
+    }
+
+    let mut parts = text.split_whitespace();
+    let (Some(count), Some(unit), None) = (parts.next(), parts.next(), parts.next()) else {
+        bail!("expected a date (YYYY-MM-DD or today) or a duration like '3 weeks', got '{text}'");
+    };
+    let count: i64 = count
+        .parse()
+        .with_context(|| format!("invalid count '{count}' in '{text}'"))?;
+    let too_long = || format!("duration '{text}' is out of range");
+    match unit.trim_end_matches('s') {
+        "day" | "d" => Ok(DateOperand::Days(count)),
+        "week" | "wk" | "w" => Ok(DateOperand::Days(
+            count.checked_mul(7).with_context(too_long)?,
+        )),
+        "month" | "mo" => Ok(DateOperand::Months(count)),
+        "year" | "yr" | "y" => Ok(DateOperand::Months(
+            count.checked_mul(12).with_context(too_long)?,
+        )),
+        _ => bail!("unknown date unit '{unit}' (use days, weeks, months, or years)"),
+    }
+}
+
+fn looks_like_date(text: &str) -> bool {
+    let text = text.trim().to_lowercase();
+    matches!(text.as_str(), "today" | "now" | "tomorrow" | "yesterday")
+        || (text.len() >= 10
+            && text.as_bytes()[4] == b'-'
+            && text.as_bytes()[7] == b'-'
+            && text[..4].bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Split `text` into operands at `+` and at `-` that is not inside a
+/// `YYYY-MM-DD` literal.
+fn split_date_terms(text: &str) -> Vec<(char, &str)> {
+    let bytes = text.as_bytes();
+    let mut terms = Vec::new();
+    let mut sign = '+';
+    let mut start = 0;
+    for (at, &byte) in bytes.iter().enumerate() {
+        let inside_date = byte == b'-'
+            && at > 0
+            && bytes[at - 1].is_ascii_digit()
+            && bytes.get(at + 1).is_some_and(u8::is_ascii_digit);
+        if (byte == b'+' || byte == b'-') && !inside_date {
+            terms.push((sign, &text[start..at]));
+            sign = byte as char;
+            start = at + 1;
+        }
+    }
+    terms.push((sign, &text[start..]));
+    terms
+}
+
+fn format_date(date: NaiveDate) -> String {
+    format!("{} ({})", date.format("%Y-%m-%d"), date.weekday())
+}
+
+/// Date arithmetic starting from a date; `None` when the input does not
+/// start with one.
+fn evaluate_date(expression: &str) -> anyhow::Result<Option<String>> {
+    evaluate_date_from(expression, chrono::Local::now().date_naive())
+}
+
+fn evaluate_date_from(expression: &str, today: NaiveDate) -> anyhow::Result<Option<String>> {
+    let terms = split_date_terms(expression);
+    let Some((_, first)) = terms.first() else {
+        return Ok(None);
+    };
+    if !looks_like_date(first) {
+        return Ok(None);
+    }
+
+    let DateOperand::Date(mut date) = parse_date_operand(first, today)? else {
+        return Ok(None);
+    };
+    for (sign, text) in &terms[1..] {
+        let negative = *sign == '-';
+        match parse_date_operand(text, today)? {
+            DateOperand::Days(days) => {
+                let days = if negative {
+                    days.checked_neg()
+                } else {
+                    Some(days)
+                };
+                date = days
+                    .and_then(chrono::TimeDelta::try_days)
+                    .and_then(|delta| date.checked_add_signed(delta))
+                    .context("date out of range")?;
+            }
+            DateOperand::Months(months) => {
+                let months =
+                    Months::new(u32::try_from(months.unsigned_abs()).context("too many months")?);
+                let shifted = if negative {
+                    date.checked_sub_months(months)
+                } else {
+                    date.checked_add_months(months)
+                };
+                date = shifted.context("date out of range")?;
+            }
+            DateOperand::Date(other) if negative && terms.len() == 2 => {
+                let days = (date - other).num_days();
+                let weeks = days.abs() / 7;
+                let rest = days.abs() % 7;
+                return Ok(Some(format!("{days} days ({weeks} weeks {rest} days)")));
+            }
+            DateOperand::Date(_) => {
+                bail!("dates can only be subtracted from each other as 'DATE - DATE'");
+            }
+        }
+    }
+    Ok(Some(format_date(date)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_arithmetic_with_precedence_and_functions() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), "7");
+        assert_eq!(evaluate("-2^2").unwrap(), "-4");
+        assert_eq!(evaluate("2^3^2").unwrap(), "512");
+        assert_eq!(evaluate("0.1 + 0.2").unwrap(), "0.3");
+        assert_eq!(evaluate("(1200 * 1.05^2) / 12").unwrap(), "110.25");
+        assert_eq!(evaluate("sqrt(16) + max(1, 7, 3) % 4").unwrap(), "7");
+        assert_eq!(evaluate("round(pi, 4)").unwrap(), "3.1416");
+        assert!(evaluate("round(pi, -1)").is_err());
+        assert!(evaluate("round(pi, 2.5)").is_err());
+        assert!(evaluate("round(pi, 99)").is_err());
+        assert_eq!(evaluate("1_000_000 * 3").unwrap(), "3000000");
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("2 +").is_err());
+        assert!(evaluate("foo(1)").is_err());
+
+        let nested = format!("{}1{}", "(".repeat(200), ")".repeat(200));
+        assert_eq!(evaluate(&nested).unwrap(), "1");
+        let too_deep = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert!(evaluate(&too_deep).is_err());
+        assert!(evaluate(&format!("{}1", "-".repeat(100_000))).is_err());
+    }
+
+    #[test]
+    fn converts_units_within_a_dimension() {
+        assert_eq!(evaluate("5 km to m").unwrap(), "5000 m");
+        assert_eq!(evaluate("12 in in cm").unwrap(), "30.48 cm");
+        assert_eq!(evaluate("(5 + 5) ft to m").unwrap(), "3.048 m");
+        assert_eq!(evaluate("212 F to C").unwrap(), "100 °C");
+        assert_eq!(evaluate("1 GiB to MB").unwrap(), "1073.741824 MB");
+        assert!(evaluate("5 kg to m").is_err());
+    }
+
+    #[test]
+    fn does_date_math() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let eval = |text: &str| evaluate_date_from(text, today).unwrap().unwrap();
+
+        assert_eq!(eval("2026-10-16 + 45 days"), "2026-11-30 (Mon)");
+        assert_eq!(eval("today - 2 weeks"), "2026-10-02 (Fri)");
+        assert_eq!(eval("2026-01-31 + 1 month"), "2026-02-28 (Sat)");
+        assert_eq!(eval("2026-12-25 - 2026-10-16"), "70 days (10 weeks 0 days)");
+        assert!(evaluate_date_from("3 + 4", today).unwrap().is_none());
+
+        for huge in [
+            "2026-01-01 + 200000000000000 days",
+            "2026-01-01 - 9223372036854775807 weeks",
+            "2026-01-01 + 9223372036854775807 years",
+        ] {
+            assert!(evaluate_date_from(huge, today).is_err(), "{huge}");
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! `code_run`: execute a Python or Node snippet under CPU, memory, and time
+//! limits (`[code_run]`).
+//!
+//! The snippet is piped to the interpreter's stdin, so it never touches the
+//! workspace. With a container backend (`docker` or `podman`) it runs in a
+//! throwaway container with a read-only root, a small `/tmp`, no network
+//! unless `allow_network` is set, and cgroup memory and CPU limits. The native
+//! backend runs the host interpreter in a scratch directory that is removed
+//! afterwards, with `ulimit` CPU and memory limits and an empty environment
+//! apart from `PATH`; it limits resources, not file or network access, so it
+//! runs only when `backend = "native"` is set and autonomy is `full`.
+
+use super::shell::truncate_output;
+use super::traits::{Tool, ToolResult};
+use crate::config::CodeRunConfig;
+use crate::security::{AutonomyLevel, SecurityPolicy};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Python,
+    Node,
+}
+
+impl Language {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "python" | "python3" | "py" => Some(Self::Python),
+            "node" | "nodejs" | "javascript" | "js" => Some(Self::Node),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Python => "python",
+            Self::Node => "node",
+        }
+    }
+
+    fn interpreter(self) -> &'static str {
+        match self {
+            Self::Python => "python3",
+            Self::Node => "node",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    Native,
+    /// Container CLI: `docker` or `podman`.
+    Container(&'static str),
+}
+
+impl Backend {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::Container(program) => program,
+        }
+    }
+}
+
+/// Backend for the configured `code_run.backend`. `auto` picks an installed
+/// container CLI and never falls back to the unsandboxed native backend,
+/// which must be named explicitly and needs full autonomy.
+fn resolve_backend(configured: &str, autonomy: AutonomyLevel) -> anyhow::Result<Backend> {
+    match configured.trim() {
+        "native" if autonomy == AutonomyLevel::Full => Ok(Backend::Native),
+        "native" => anyhow::bail!(
+            "code_run.backend = \"native\" runs snippets without a sandbox and needs autonomy.level = \"full\""
+        ),
+        "docker" => Ok(Backend::Container("docker")),
+        "podman" => Ok(Backend::Container("podman")),
+        "auto" => ["docker", "podman"]
+            .into_iter()
+            .find(|program| which::which(program).is_ok())
+            .map(Backend::Container)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "code_run.backend = \"auto\" found neither docker nor podman; install one, or set backend = \"native\" to run snippets unsandboxed"
+                )
+            }),
+        other => anyhow::bail!(
+            "Unknown code_run.backend '{other}'. Use one of: auto, native, docker, podman"
+        ),
+    }
+}
+
+/// Code execution sandbox tool
+pub struct CodeRunTool {
+    security: Arc<SecurityPolicy>,
+    config: CodeRunConfig,
+}
+
+impl CodeRunTool {
+    pub fn new(security: Arc<SecurityPolicy>, config: CodeRunConfig) -> Self {
+        Self { security, config }
+    }
+
+    fn native_command(&self, language: Language, dir: &Path) -> Command {
+        // V8 reserves far more address space than it uses, so node gets a
+        // heap limit instead of `ulimit -v`.
+        let limits = match language {
+            Language::Python => format!(
+                "ulimit -t {}; ulimit -v {} 2>/dev/null; exec python3 -",
+                self.config.cpu_time_secs,
+                self.config.max_memory_mb.saturating_mul(1024)
+            ),
+            Language::Node => format!(
+                "ulimit -t {}; exec node --max-old-space-size={} -",
+                self.config.cpu_time_secs, self.config.max_memory_mb
+            ),
+        };
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(limits).current_dir(dir).env_clear();
+        if let Ok(path) = std::env::var("PATH") {
+            cmd.env("PATH", path);
+        }
+        cmd.env("HOME", dir).env("TMPDIR", dir);
+        cmd
+    }
+
+    fn container_command(&self, program: &str, language: Language, name: &str) -> Command {
+        let image = match language {
+            Language::Python => &self.config.python_image,
+            Language::Node => &self.config.node_image,
+        };
+        let mut cmd = Command::new(program);
+        cmd.args(["run", "--rm", "-i", "--name", name]);
+        if !self.config.allow_network {
+            cmd.args(["--network", "none"]);
+        }
+        cmd.args([
+            "--read-only",
+            "--tmpfs",
+            "/tmp:rw,size=64m",
+            "--pids-limit",
+            "64",
+            "--workdir",
+            "/tmp",
+            "--env",
+            "HOME=/tmp",
+        ])
+        .arg(format!("--memory={}m", self.config.max_memory_mb))
+        .arg(format!("--cpus={}", self.config.cpus))
+        .arg(format!("--ulimit=cpu={}", self.config.cpu_time_secs))
+        .arg(image)
+        .args([language.interpreter(), "-"]);
+        cmd
+    }
+}
+
+/// Run `cmd` with `code` on stdin; `None` when the time limit ran out and
+/// the process was killed.
+async fn run_snippet(
+    mut cmd: Command,
+    code: &str,
+    timeout: Duration,
+) -> std::io::Result<Option<std::process::Output>> {
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = cmd.spawn()?;
+    let stdin = child.stdin.take();
+    let run = async move {
+        if let Some(mut stdin) = stdin {
+            // A snippet that exits early closes the pipe; its output still
+            // tells what happened.
+            let _ = stdin.write_all(code.as_bytes()).await;
+        }
+        child.wait_with_output().await
+    };
+    match tokio::time::timeout(timeout, run).await {
+        Ok(output) => output.map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+#[async_trait]
+impl Tool for CodeRunTool {
+    fn name(&self) -> &str {
+        "code_run"
+    }
+
+    fn description(&self) -> &str {
+        "Run a Python or Node.js snippet in a sandbox with CPU, memory, and time limits. Returns the exit code, stdout, and stderr. Files the snippet writes are discarded after the run"
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "language": {
+                    "type": "string",
+                    "enum": ["python", "node"],
+                    "description": "Interpreter to run the code with"
+                },
+                "code": {
+                    "type": "string",
+                    "description": "Complete program source; print results to stdout"
+                }
+            },
+            "required": ["language", "code"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let code = args
+            .get("code")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'code' parameter"))?;
+        let language_arg = args
+            .get("language")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'language' parameter"))?;
+        let Some(language) = Language::parse(language_arg) else {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!(
+                    "Unsupported language '{language_arg}'. Use 'python' or 'node'"
+                )),
+            });
+        };
+
+        if !self.security.can_act() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Action blocked: autonomy is read-only".into()),
+            });
+        }
+
+        if !self.security.record_action() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Action blocked: rate limit exceeded".into()),
+            });
+        }
+
+        let backend = match resolve_backend(&self.config.backend, self.security.autonomy) {
+            Ok(backend) => backend,
+            Err(e) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(e.to_string()),
+                });
+            }
+        };
+
+        let name = format!("zeroclaw-code-{}", uuid::Uuid::new_v4().simple());
+        let (cmd, scratch_dir) = match backend {
+            Backend::Native => {
+                let dir = std::env::temp_dir().join(&name);
+                if let Err(e) = tokio::fs::create_dir_all(&dir).await {
+                    return Ok(ToolResult {
+                        success: false,
+                        output: String::new(),
+                        error: Some(format!("Failed to create scratch directory: {e}")),
+                    });
+                }
+                (self.native_command(language, &dir), Some(dir))
+            }
+            Backend::Container(program) => (self.container_command(program, language, &name), None),
+        };
+
+        let timeout_secs = self.config.timeout_secs.max(1);
+        let started = Instant::now();
+        let result = run_snippet(cmd, code, Duration::from_secs(timeout_secs)).await;
+        let elapsed = started.elapsed().as_secs_f64();
+        if let Some(dir) = scratch_dir {
+            let _ = tokio::fs::remove_dir_all(dir).await;
+        }
+
+        let output = match result {
+            Ok(Some(output)) => output,
+            Ok(None) => {
+                // Killing the CLI leaves the container running.
+                if let Backend::Container(program) = backend {
+                    let _ = Command::new(program)
+                        .args(["kill", &name])
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .status()
+                        .await;
+                }
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(format!(
+                        "Code timed out after {timeout_secs}s and was killed"
+                    )),
+                });
+            }
+            Err(e) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(format!(
+                        "Failed to start {} ({} backend): {e}",
+                        language.interpreter(),
+                        backend.name()
+                    )),
+                });
+            }
+        };
+
+        let mut stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let mut stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        truncate_output(&mut stdout, self.config.max_output_bytes.max(1), "stdout");
+        truncate_output(&mut stderr, self.config.max_output_bytes.max(1), "stderr");
+
+        let status = match output.status.code() {
+            Some(code) => format!("Exit code: {code}"),
+            None => "Killed by a signal (CPU or memory limit reached?)".to_string(),
+        };
+        let mut report = format!(
+            "{status} ({}, {} backend, {elapsed:.1}s)",
+            language.name(),
+            backend.name()
+        );
+        for (stream, text) in [("stdout", &stdout), ("stderr", &stderr)] {
+            if !text.trim().is_empty() {
+                report.push_str(&format!("\n{stream}:\n{}", text.trim_end()));
+            }
+        }
+
+        let success = output.status.success();
+        Ok(ToolResult {
+            success,
+            error: (!success).then(|| report.clone()),
+            output: report,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(timeout_secs: u64) -> CodeRunTool {
+        CodeRunTool::new(
+            Arc::new(SecurityPolicy {
+                autonomy: AutonomyLevel::Full,
+                ..SecurityPolicy::default()
+            }),
+            CodeRunConfig {
+                enabled: true,
+                backend: "native".into(),
+                timeout_secs,
+                ..CodeRunConfig::default()
+            },
+        )
+    }
+
+    #[test]
+    fn languages_and_backends_resolve() {
+        assert_eq!(Language::parse(" Py "), Some(Language::Python));
+        assert_eq!(Language::parse("javascript"), Some(Language::Node));
+        assert_eq!(Language::parse("ruby"), None);
+        assert_eq!(
+            resolve_backend("native", AutonomyLevel::Full).unwrap(),
+            Backend::Native
+        );
+        assert_eq!(
+            resolve_backend("podman", AutonomyLevel::Supervised).unwrap(),
+            Backend::Container("podman")
+        );
+        assert!(resolve_backend("lxc", AutonomyLevel::Full)
+            .unwrap_err()
+            .to_string()
+            .contains("lxc"));
+    }
+
+    #[test]
+    fn native_backend_needs_explicit_config_and_full_autonomy() {
+        let err = resolve_backend("native", AutonomyLevel::Supervised).unwrap_err();
+        assert!(err.to_string().contains("autonomy.level"));
+
+        let has_container = ["docker", "podman"]
+            .into_iter()
+            .any(|program| which::which(program).is_ok());
+        if !has_container {
+            let err = resolve_backend("auto", AutonomyLevel::Full).unwrap_err();
+            assert!(err.to_string().contains("neither docker nor podman"));
+        }
+    }
+
+    #[tokio::test]
+    async fn native_python_reports_exit_code_and_output() {
+        if which::which("python3").is_err() {
+            return;
+        }
+        let result = tool(30)
+            .execute(json!({ "language": "python", "code": "print(6 * 7)" }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert!(result
+            .output
+            .starts_with("Exit code: 0 (python, native backend"));
+        assert!(result.output.contains("stdout:\n42"));
+
+        let result = tool(30)
+            .execute(json!({
+                "language": "python",
+                "code": "import sys\nprint('bad input', file=sys.stderr)\nsys.exit(3)"
+            }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert!(error.starts_with("Exit code: 3"));
+        assert!(error.contains("stderr:\nbad input"));
+    }
+
+    #[tokio::test]
+    async fn native_run_is_killed_at_the_time_limit() {
+        if which::which("python3").is_err() {
+            return;
+        }
+        let result = tool(1)
+            .execute(json!({ "language": "python", "code": "import time\ntime.sleep(30)" }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("timed out after 1s"));
+    }
+}
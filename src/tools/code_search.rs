@@ -0,0 +1,433 @@
+use super::list_dir::resolve_workspace_path;
+use super::traits::{Tool, ToolKind, ToolResult};
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+
+const TIMEOUT_SECS: u64 = 30;
+const DEFAULT_CONTEXT: usize = 2;
+const MAX_CONTEXT: usize = 5;
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 50;
+/// Characters kept of each shown line; minified files have very long ones.
+const MAX_LINE_CHARS: usize = 300;
+
+/// Search code in the workspace with ripgrep, returning matches grouped by
+/// file with surrounding lines, one page at a time.
+///
+/// Uses `rg --json`, so hidden and `.gitignore`'d files are skipped and each
+/// match carries its line number. Every page reports the total match count,
+/// so the model can tell whether to read on or refine the query.
+pub struct CodeSearchTool {
+    security: Arc<SecurityPolicy>,
+    has_rg: bool,
+}
+
+impl CodeSearchTool {
+    pub fn new(security: Arc<SecurityPolicy>) -> Self {
+        let has_rg = which::which("rg").is_ok();
+        Self { security, has_rg }
+    }
+}
+
+/// Lines ripgrep reported for one file.
+#[derive(Debug, Default)]
+struct FileHits {
+    path: String,
+    /// Line number to text and whether it is a match (not context).
+    lines: BTreeMap<u64, (String, bool)>,
+    matches: Vec<u64>,
+}
+
+fn clip_line(text: &str) -> String {
+    let text = text.trim_end_matches(['\n', '\r']);
+    match text.char_indices().nth(MAX_LINE_CHARS) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+/// Parse `rg --json` output into per-file hits with workspace-relative paths.
+fn parse_rg_json(raw: &str, workspace: &Path) -> Vec<FileHits> {
+    let mut files: Vec<FileHits> = Vec::new();
+    for line in raw.lines() {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let data = &event["data"];
+        match event["type"].as_str() {
+            Some("begin") => {
+                // Non-UTF-8 paths come as bytes; there is no way to show them.
+                let Some(path) = data["path"]["text"].as_str() else {
+                    continue;
+                };
+                let path = Path::new(path);
+                files.push(FileHits {
+                    path: path
+                        .strip_prefix(workspace)
+                        .unwrap_or(path)
+                        .display()
+                        .to_string(),
+                    ..FileHits::default()
+                });
+            }
+            Some(kind @ ("match" | "context")) => {
+                let (Some(number), Some(text), Some(file)) = (
+                    data["line_number"].as_u64(),
+                    data["lines"]["text"].as_str(),
+                    files.last_mut(),
+                ) else {
+                    continue;
+                };
+                let is_match = kind == "match";
+                file.lines.insert(number, (clip_line(text), is_match));
+                if is_match {
+                    file.matches.push(number);
+                }
+            }
+            _ => {}
+        }
+    }
+    files.retain(|file| !file.matches.is_empty());
+    files
+}
+
+/// Render page `page` (1-based) of `page_size` matches, each with up to
+/// `context` lines around it.
+fn format_page(
+    files: &[FileHits],
+    page: usize,
+    page_size: usize,
+    context: u64,
+) -> Result<String, String> {
+    let total: usize = files.iter().map(|file| file.matches.len()).sum();
+    if total == 0 {
+        return Ok("No matches found.".into());
+    }
+    let pages = total.div_ceil(page_size);
+    if page > pages {
+        return Err(format!(
+            "Page {page} is past the last page ({pages}) of {total} matches."
+        ));
+    }
+    let start = (page - 1) * page_size;
+    let end = (start + page_size).min(total);
+
+    let mut output = format!(
+        "Matches {}-{end} of {total} in {} files (page {page} of {pages}",
+        start + 1,
+        files.len()
+    );
+    if page < pages {
+        let _ = write!(output, "; next: page={}", page + 1);
+    }
+    output.push(')');
+
+    let mut seen = 0;
+    for file in files {
+        let first = seen;
+        seen += file.matches.len();
+        if seen <= start || first >= end {
+            continue;
+        }
+        let shown =
+            &file.matches[start.saturating_sub(first)..(end - first).min(file.matches.len())];
+        // Merge each match's context window with the previous one.
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        for &number in shown {
+            let range = (number.saturating_sub(context), number + context);
+            match ranges.last_mut() {
+                Some(last) if range.0 <= last.1 + 1 => last.1 = last.1.max(range.1),
+                _ => ranges.push(range),
+            }
+        }
+        let _ = write!(output, "\n\n{}", file.path);
+        for (index, (from, to)) in ranges.into_iter().enumerate() {
+            if index > 0 {
+                output.push_str("\n--");
+            }
+            for (number, (text, is_match)) in file.lines.range(from..=to) {
+                let separator = if *is_match { ':' } else { '-' };
+                let _ = write!(output, "\n{number}{separator}{text}");
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[async_trait]
+impl Tool for CodeSearchTool {
+    fn name(&self) -> &str {
+        "code_search"
+    }
+
+    fn description(&self) -> &str {
+        "Search code in the workspace with ripgrep. Returns matches grouped by file, \
+         with line numbers and surrounding lines, one page at a time; skips hidden and \
+         .gitignore'd files. Use this instead of `grep`/`rg` in the shell. \
+         Example: query='fn execute', glob='*.rs'."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Regular expression to search for (a plain string when literal is true)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Directory or file to search, relative to workspace root. Defaults to '.'",
+                    "default": "."
+                },
+                "glob": {
+                    "type": "string",
+                    "description": "Only search files matching this glob, e.g. '*.rs' or 'src/**/*.ts'"
+                },
+                "literal": {
+                    "type": "boolean",
+                    "description": "Match the query as a plain string",
+                    "default": false
+                },
+                "case_sensitive": {
+                    "type": "boolean",
+                    "description": "Force case-sensitive (true) or case-insensitive (false) matching. By default an all-lowercase query ignores case"
+                },
+                "context": {
+                    "type": "integer",
+                    "description": "Lines shown before and after each match (default 2, max 5)",
+                    "default": 2
+                },
+                "page": {
+                    "type": "integer",
+                    "description": "Page of results to return, starting at 1",
+                    "default": 1
+                },
+                "page_size": {
+                    "type": "integer",
+                    "description": "Matches per page (default 20, max 50)",
+                    "default": 20
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    fn kind(&self) -> ToolKind {
+        ToolKind::ReadLike
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let query = args
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'query' parameter"))?;
+        if query.is_empty() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Empty query is not allowed.".into()),
+            });
+        }
+        let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+        let glob = args.get("glob").and_then(|v| v.as_str());
+        let literal = args
+            .get("literal")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let case_sensitive = args.get("case_sensitive").and_then(|v| v.as_bool());
+        let number = |key: &str, default: usize, min: usize, max: usize| {
+            args.get(key)
+                .and_then(|v| v.as_u64())
+                .map_or(default, |n| usize::try_from(n).unwrap_or(max))
+                .clamp(min, max)
+        };
+        let context = number("context", DEFAULT_CONTEXT, 0, MAX_CONTEXT);
+        let page = number("page", 1, 1, usize::MAX);
+        let page_size = number("page_size", DEFAULT_PAGE_SIZE, 1, MAX_PAGE_SIZE);
+
+        if !self.has_rg {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(
+                    "code_search needs ripgrep (rg), which is not installed. Use content_search instead."
+                        .into(),
+                ),
+            });
+        }
+
+        if self.security.is_rate_limited() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Rate limit exceeded: too many actions in the last hour".into()),
+            });
+        }
+
+        let resolved = match resolve_workspace_path(&self.security, path) {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(error),
+                });
+            }
+        };
+
+        if !self.security.record_action() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Rate limit exceeded: action budget exhausted".into()),
+            });
+        }
+
+        let mut cmd = tokio::process::Command::new("rg");
+        cmd.arg("--json").arg("--context").arg(context.to_string());
+        match case_sensitive {
+            Some(true) => cmd.arg("--case-sensitive"),
+            Some(false) => cmd.arg("--ignore-case"),
+            None => cmd.arg("--smart-case"),
+        };
+        if literal {
+            cmd.arg("--fixed-strings");
+        }
+        if let Some(glob) = glob {
+            cmd.arg("--glob").arg(glob);
+        }
+        cmd.arg("--").arg(query).arg(&resolved);
+
+        // Security: clear environment, keep only safe variables
+        cmd.env_clear();
+        for key in &["PATH", "HOME", "LANG", "LC_ALL", "LC_CTYPE"] {
+            if let Ok(val) = std::env::var(key) {
+                cmd.env(key, val);
+            }
+        }
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let output =
+            match tokio::time::timeout(std::time::Duration::from_secs(TIMEOUT_SECS), cmd.output())
+                .await
+            {
+                Ok(Ok(output)) => output,
+                Ok(Err(e)) => {
+                    return Ok(ToolResult {
+                        success: false,
+                        output: String::new(),
+                        error: Some(format!("Failed to execute search command: {e}")),
+                    });
+                }
+                Err(_) => {
+                    return Ok(ToolResult {
+                        success: false,
+                        output: String::new(),
+                        error: Some(format!("Search timed out after {TIMEOUT_SECS} seconds.")),
+                    });
+                }
+            };
+
+        let workspace = std::fs::canonicalize(&self.security.workspace_dir)
+            .unwrap_or_else(|_| self.security.workspace_dir.clone());
+        let files = parse_rg_json(&String::from_utf8_lossy(&output.stdout), &workspace);
+
+        // Exit code 2 also covers unreadable files next to real matches;
+        // only fail when nothing was found.
+        if output.status.code() == Some(2) && files.is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!("Search error: {}", stderr.trim())),
+            });
+        }
+
+        match format_page(&files, page, page_size, context as u64) {
+            Ok(output) => Ok(ToolResult {
+                success: true,
+                output,
+                error: None,
+            }),
+            Err(error) => Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(error),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: &str, path: &str, number: u64, text: &str) -> String {
+        json!({
+            "type": kind,
+            "data": {
+                "path": { "text": path },
+                "lines": { "text": format!("{text}\n") },
+                "line_number": number
+            }
+        })
+        .to_string()
+    }
+
+    fn sample() -> Vec<FileHits> {
+        let begin = |path: &str| json!({ "type": "begin", "data": { "path": { "text": path } } });
+        let raw = [
+            begin("/ws/src/a.rs").to_string(),
+            event("context", "/ws/src/a.rs", 1, "use x;"),
+            event("match", "/ws/src/a.rs", 2, "fn run() {"),
+            event("match", "/ws/src/a.rs", 3, "    run_inner();"),
+            event("context", "/ws/src/a.rs", 4, "}"),
+            event("context", "/ws/src/a.rs", 9, "// later"),
+            event("match", "/ws/src/a.rs", 10, "fn run_inner() {}"),
+            json!({ "type": "end", "data": {} }).to_string(),
+            begin("/ws/src/b.rs").to_string(),
+            event("match", "/ws/src/b.rs", 7, "run();"),
+            json!({ "type": "summary", "data": {} }).to_string(),
+        ]
+        .join("\n");
+        parse_rg_json(&raw, Path::new("/ws"))
+    }
+
+    #[test]
+    fn matches_are_grouped_by_file_with_merged_context() {
+        let files = sample();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "src/a.rs");
+        assert_eq!(files[0].matches, vec![2, 3, 10]);
+        assert_eq!(
+            format_page(&files, 1, 20, 1).unwrap(),
+            "Matches 1-4 of 4 in 2 files (page 1 of 1)\n\nsrc/a.rs\n1-use x;\n2:fn run() {\n3:    run_inner();\n4-}\n--\n9-// later\n10:fn run_inner() {}\n\nsrc/b.rs\n7:run();"
+        );
+    }
+
+    #[test]
+    fn pages_split_matches_across_files() {
+        let files = sample();
+        assert_eq!(
+            format_page(&files, 1, 2, 0).unwrap(),
+            "Matches 1-2 of 4 in 2 files (page 1 of 2; next: page=2)\n\nsrc/a.rs\n2:fn run() {\n3:    run_inner();"
+        );
+        assert_eq!(
+            format_page(&files, 2, 2, 0).unwrap(),
+            "Matches 3-4 of 4 in 2 files (page 2 of 2)\n\nsrc/a.rs\n10:fn run_inner() {}\n\nsrc/b.rs\n7:run();"
+        );
+        assert!(format_page(&files, 3, 2, 0)
+            .unwrap_err()
+            .contains("past the last page"));
+        assert_eq!(format_page(&[], 1, 20, 2).unwrap(), "No matches found.");
+    }
+}
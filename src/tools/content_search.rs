@@ -1,4 +1,4 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolKind, ToolResult};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
@@ -97,6 +97,10 @@ impl Tool for ContentSearchTool {
         })
     }
 
+    fn kind(&self) -> ToolKind {
+        ToolKind::ReadLike
+    }
+
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         // --- Parse parameters ---
         let pattern = args
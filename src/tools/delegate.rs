@@ -1,4 +1,4 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolKind, ToolResult};
 use crate::agent::loop_::run_tool_call_loop;
 use crate::config::DelegateAgentConfig;
 use crate::observability::traits::{Observer, ObserverEvent, ObserverMetric};
@@ -411,6 +411,8 @@ impl DelegateTool {
                 None,
                 None,
                 &[],
+                None,
+                1,
             ),
         )
         .await;
@@ -473,6 +475,10 @@ impl Tool for ToolArcRef {
         self.inner.parameters_schema()
     }
 
+    fn kind(&self) -> ToolKind {
+        self.inner.kind()
+    }
+
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         self.inner.execute(args).await
     }
@@ -602,6 +608,7 @@ mod tests {
                     tool_calls: Vec::new(),
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 })
             } else {
                 Ok(ChatResponse {
@@ -613,6 +620,7 @@ mod tests {
                     }],
                     usage: None,
                     reasoning_content: None,
+                    finish_reason: None,
                 })
             }
         }
@@ -647,6 +655,7 @@ mod tests {
                 }],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             })
         }
     }
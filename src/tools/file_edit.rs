@@ -1,15 +1,20 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolKind, ToolResult};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
+use std::fmt::Write;
 use std::sync::Arc;
 
-/// Edit a file by replacing an exact string match with new content.
+/// Edit a file in place with search/replace hunks or a unified diff.
 ///
-/// Uses `old_string` → `new_string` precise replacement within the workspace.
-/// The `old_string` must appear exactly once in the file (zero matches = not
-/// found, multiple matches = ambiguous). `new_string` may be empty to delete
-/// the matched text. Security checks mirror [`super::file_write::FileWriteTool`].
+/// The classic form replaces `old_string` with `new_string`; `edits` applies
+/// several such replacements in order, and `diff` applies the hunks of a
+/// unified diff. Text that does not match exactly is retried line by line
+/// ignoring trailing whitespace, then indentation, and every search must
+/// resolve to one place (diff hunks take the one nearest their line number).
+/// Either every hunk applies or the file is left untouched; `dry_run` only
+/// reports what would change. The result lists each applied hunk. Security
+/// checks mirror [`super::file_write::FileWriteTool`].
 pub struct FileEditTool {
     security: Arc<SecurityPolicy>,
 }
@@ -20,6 +25,424 @@ impl FileEditTool {
     }
 }
 
+/// The change a call asks for.
+enum EditSpec {
+    Replace { old: String, new: String },
+    Edits(Vec<(String, String)>),
+    Diff(Vec<Hunk>),
+}
+
+impl EditSpec {
+    fn headline(&self) -> String {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        match self {
+            Self::Replace { .. } => "replaced 1 occurrence".to_string(),
+            Self::Edits(edits) => format!("applied {} edit{}", edits.len(), plural(edits.len())),
+            Self::Diff(hunks) => format!("applied {} hunk{}", hunks.len(), plural(hunks.len())),
+        }
+    }
+}
+
+/// How loosely a run of lines matched the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MatchLevel {
+    Exact,
+    TrailingWhitespace,
+    Indentation,
+}
+
+const MATCH_LEVELS: [MatchLevel; 3] = [
+    MatchLevel::Exact,
+    MatchLevel::TrailingWhitespace,
+    MatchLevel::Indentation,
+];
+
+impl MatchLevel {
+    fn normalize(self, line: &str) -> &str {
+        match self {
+            Self::Exact => line,
+            Self::TrailingWhitespace => line.trim_end(),
+            Self::Indentation => line.trim(),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::TrailingWhitespace => "ignoring trailing whitespace",
+            Self::Indentation => "ignoring indentation",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineOp {
+    Context,
+    Remove,
+    Add,
+}
+
+/// A run of lines to replace, as in a unified diff hunk.
+#[derive(Debug, Clone, Default)]
+struct Hunk {
+    /// 0-based line where the diff places the hunk.
+    expected: Option<usize>,
+    lines: Vec<(LineOp, String)>,
+}
+
+impl Hunk {
+    fn replacement(old: &str, new: &str) -> Self {
+        let lines = old
+            .lines()
+            .map(|line| (LineOp::Remove, line.to_string()))
+            .chain(new.lines().map(|line| (LineOp::Add, line.to_string())))
+            .collect();
+        Self {
+            expected: None,
+            lines,
+        }
+    }
+
+    /// Lines the hunk expects in the file: context and removals.
+    fn old_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines
+            .iter()
+            .filter(|(op, _)| *op != LineOp::Add)
+            .map(|(_, line)| line.as_str())
+    }
+
+    fn count(&self, op: LineOp) -> usize {
+        self.lines
+            .iter()
+            .filter(|(line_op, _)| *line_op == op)
+            .count()
+    }
+}
+
+/// Where a hunk was applied, for the result summary.
+struct AppliedHunk {
+    /// 1-based line of the first replaced line.
+    line: usize,
+    level: MatchLevel,
+    added: usize,
+    removed: usize,
+}
+
+enum LocateError {
+    NotFound,
+    Ambiguous(usize),
+}
+
+/// Hunks of a unified diff. File headers are skipped; hunk line counts are
+/// not enforced, since the lines themselves are matched against the file.
+fn parse_unified_diff(diff: &str) -> Result<Vec<Hunk>, String> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut current: Option<Hunk> = None;
+    let mut lines = diff.lines().peekable();
+    while let Some(line) = lines.next() {
+        if let Some(header) = line.strip_prefix("@@") {
+            hunks.extend(current.take());
+            let expected =
+                parse_hunk_start(header).ok_or_else(|| format!("Invalid hunk header: {line}"))?;
+            current = Some(Hunk {
+                expected: Some(expected),
+                lines: Vec::new(),
+            });
+            continue;
+        }
+        // A `---` line followed by `+++` starts the next file, not a removal.
+        let file_header = line.starts_with("diff ")
+            || (line.starts_with("--- ")
+                && lines.peek().is_some_and(|next| next.starts_with("+++ ")));
+        if file_header {
+            hunks.extend(current.take());
+            continue;
+        }
+        let Some(hunk) = current.as_mut() else {
+            continue;
+        };
+        if let Some(text) = line.strip_prefix('+') {
+            hunk.lines.push((LineOp::Add, text.to_string()));
+        } else if let Some(text) = line.strip_prefix('-') {
+            hunk.lines.push((LineOp::Remove, text.to_string()));
+        } else if let Some(text) = line.strip_prefix(' ') {
+            hunk.lines.push((LineOp::Context, text.to_string()));
+        } else if line.is_empty() {
+            // Editors often strip the space off blank context lines.
+            hunk.lines.push((LineOp::Context, String::new()));
+        } else if !line.starts_with('\\') {
+            return Err(format!("Unexpected line in diff hunk: {line}"));
+        }
+    }
+    hunks.extend(current);
+    hunks.retain(|hunk| !hunk.lines.is_empty());
+    if hunks.is_empty() {
+        return Err("diff contains no hunks".into());
+    }
+    Ok(hunks)
+}
+
+/// 0-based line a hunk header (the text after `@@`) places the hunk at.
+fn parse_hunk_start(header: &str) -> Option<usize> {
+    let old_range = header
+        .trim_start()
+        .strip_prefix('-')?
+        .split_whitespace()
+        .next()?;
+    let (start, count) = old_range.split_once(',').unwrap_or((old_range, "1"));
+    let start: usize = start.parse().ok()?;
+    let count: usize = count.parse().ok()?;
+    // An empty old range inserts after line `start`.
+    Some(if count == 0 {
+        start
+    } else {
+        start.saturating_sub(1)
+    })
+}
+
+fn parse_edits(edits: &serde_json::Value) -> Result<Vec<(String, String)>, String> {
+    let edits = edits
+        .as_array()
+        .filter(|edits| !edits.is_empty())
+        .ok_or("edits must be a non-empty array")?;
+    edits
+        .iter()
+        .enumerate()
+        .map(|(index, edit)| {
+            let field = |key: &str| edit.get(key).and_then(|v| v.as_str());
+            match (field("old_string"), field("new_string")) {
+                (Some(old), Some(new)) if !old.is_empty() => Ok((old.to_string(), new.to_string())),
+                _ => Err(format!(
+                    "edit {} needs a non-empty old_string and a new_string",
+                    index + 1
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Start of `hunk` in `lines[from..]`. The strictest level with a match
+/// wins; among several matches the one nearest the hunk's expected line is
+/// taken, and a hunk without one must match once.
+fn locate(lines: &[String], from: usize, hunk: &Hunk) -> Result<(usize, MatchLevel), LocateError> {
+    let old: Vec<&str> = hunk.old_lines().collect();
+    if old.is_empty() {
+        let at = hunk
+            .expected
+            .unwrap_or(lines.len())
+            .clamp(from, lines.len());
+        return Ok((at, MatchLevel::Exact));
+    }
+    if lines.len() < from + old.len() {
+        return Err(LocateError::NotFound);
+    }
+    for level in MATCH_LEVELS {
+        let starts: Vec<usize> = (from..=lines.len() - old.len())
+            .filter(|&start| {
+                lines[start..start + old.len()]
+                    .iter()
+                    .zip(&old)
+                    .all(|(line, want)| level.normalize(line) == level.normalize(want))
+            })
+            .collect();
+        match (starts.as_slice(), hunk.expected) {
+            ([], _) => {}
+            ([start], _) => return Ok((*start, level)),
+            (_, Some(expected)) => {
+                let nearest = starts
+                    .iter()
+                    .copied()
+                    .min_by_key(|start| start.abs_diff(expected))
+                    .unwrap_or_default();
+                return Ok((nearest, level));
+            }
+            (_, None) => return Err(LocateError::Ambiguous(starts.len())),
+        }
+    }
+    Err(LocateError::NotFound)
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Replace `hunk`'s lines at `at`, keeping the file's own context lines.
+/// Added lines are re-indented when the match ignored indentation. Returns
+/// the index just past the replacement.
+fn apply_hunk(lines: &mut Vec<String>, at: usize, level: MatchLevel, hunk: &Hunk) -> usize {
+    let reindent = if level == MatchLevel::Indentation {
+        hunk.old_lines()
+            .enumerate()
+            .find(|(_, line)| !line.trim().is_empty())
+            .map(|(offset, line)| {
+                (
+                    indentation(line).to_string(),
+                    indentation(&lines[at + offset]).to_string(),
+                )
+            })
+    } else {
+        None
+    };
+    let mut replacement = Vec::new();
+    let mut cursor = at;
+    for (op, text) in &hunk.lines {
+        match op {
+            LineOp::Context => {
+                replacement.push(lines[cursor].clone());
+                cursor += 1;
+            }
+            LineOp::Remove => cursor += 1,
+            LineOp::Add => replacement.push(match &reindent {
+                Some((hunk_indent, file_indent)) => match text.strip_prefix(hunk_indent.as_str()) {
+                    Some(rest) => format!("{file_indent}{rest}"),
+                    None => text.clone(),
+                },
+                None => text.clone(),
+            }),
+        }
+    }
+    let end = at + replacement.len();
+    lines.splice(at..cursor, replacement);
+    end
+}
+
+/// `content` split into lines, with its line ending and whether it ends
+/// with one.
+fn split_lines(content: &str) -> (Vec<String>, &'static str, bool) {
+    let eol = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let lines = content.lines().map(ToString::to_string).collect();
+    (lines, eol, content.ends_with('\n'))
+}
+
+fn join_lines(lines: &[String], eol: &str, trailing_eol: bool) -> String {
+    let mut content = lines.join(eol);
+    if trailing_eol && !lines.is_empty() {
+        content.push_str(eol);
+    }
+    content
+}
+
+/// Replace the one occurrence of `old` in `content`: exactly if it occurs
+/// verbatim, otherwise line by line with looser matching.
+fn replace_once(content: &str, old: &str, new: &str) -> Result<(String, AppliedHunk), String> {
+    let exact = content.matches(old).count();
+    if exact > 1 {
+        return Err(format!(
+            "old_string matches {exact} times; must match exactly once"
+        ));
+    }
+    if let Some(index) = content.find(old) {
+        let hunk = AppliedHunk {
+            line: content[..index].matches('\n').count() + 1,
+            level: MatchLevel::Exact,
+            added: new.lines().count(),
+            removed: old.lines().count(),
+        };
+        return Ok((content.replacen(old, new, 1), hunk));
+    }
+    let hunk = Hunk::replacement(old, new);
+    let (mut lines, eol, trailing_eol) = split_lines(content);
+    match locate(&lines, 0, &hunk) {
+        Ok((at, level)) => {
+            apply_hunk(&mut lines, at, level, &hunk);
+            let applied = AppliedHunk {
+                line: at + 1,
+                level,
+                added: hunk.count(LineOp::Add),
+                removed: hunk.count(LineOp::Remove),
+            };
+            Ok((join_lines(&lines, eol, trailing_eol), applied))
+        }
+        Err(LocateError::NotFound) => Err("old_string not found in file".into()),
+        Err(LocateError::Ambiguous(count)) => Err(format!(
+            "old_string matches {count} places when whitespace is ignored; must match exactly once"
+        )),
+    }
+}
+
+/// `content` with every hunk of `spec` applied, or the first that fails.
+fn apply_spec(content: &str, spec: &EditSpec) -> Result<(String, Vec<AppliedHunk>), String> {
+    match spec {
+        EditSpec::Replace { old, new } => {
+            replace_once(content, old, new).map(|(content, hunk)| (content, vec![hunk]))
+        }
+        EditSpec::Edits(edits) => {
+            let mut content = content.to_string();
+            let mut applied = Vec::with_capacity(edits.len());
+            for (index, (old, new)) in edits.iter().enumerate() {
+                let (edited, hunk) = replace_once(&content, old, new)
+                    .map_err(|error| format!("edit {}: {error}", index + 1))?;
+                content = edited;
+                applied.push(hunk);
+            }
+            Ok((content, applied))
+        }
+        EditSpec::Diff(hunks) => {
+            let (mut lines, eol, trailing_eol) = split_lines(content);
+            let mut applied = Vec::with_capacity(hunks.len());
+            let mut from = 0;
+            for (index, hunk) in hunks.iter().enumerate() {
+                let (at, level) = locate(&lines, from, hunk).map_err(|error| {
+                    let first = hunk.old_lines().next().unwrap_or_default();
+                    match error {
+                        LocateError::NotFound => format!(
+                            "hunk {} does not match the file (first expected line: {first:?})",
+                            index + 1
+                        ),
+                        LocateError::Ambiguous(count) => {
+                            format!("hunk {} matches {count} places", index + 1)
+                        }
+                    }
+                })?;
+                from = apply_hunk(&mut lines, at, level, hunk);
+                applied.push(AppliedHunk {
+                    line: at + 1,
+                    level,
+                    added: hunk.count(LineOp::Add),
+                    removed: hunk.count(LineOp::Remove),
+                });
+            }
+            Ok((join_lines(&lines, eol, trailing_eol), applied))
+        }
+    }
+}
+
+fn summarize(
+    path: &str,
+    spec: &EditSpec,
+    content: &str,
+    hunks: &[AppliedHunk],
+    dry_run: bool,
+) -> String {
+    let added: usize = hunks.iter().map(|hunk| hunk.added).sum();
+    let removed: usize = hunks.iter().map(|hunk| hunk.removed).sum();
+    let totals = format!("{} bytes, +{added} -{removed} lines", content.len());
+    let mut summary = if dry_run {
+        format!(
+            "Dry run for {path}: {} in memory ({totals}); file not changed",
+            spec.headline()
+        )
+    } else {
+        format!("Edited {path}: {} ({totals})", spec.headline())
+    };
+    for (index, hunk) in hunks.iter().enumerate() {
+        let _ = write!(
+            summary,
+            "\n  hunk {}: line {}, {}, +{} -{}",
+            index + 1,
+            hunk.line,
+            hunk.level.label(),
+            hunk.added,
+            hunk.removed
+        );
+    }
+    summary
+}
+
 #[async_trait]
 impl Tool for FileEditTool {
     fn name(&self) -> &str {
@@ -27,7 +450,7 @@ impl Tool for FileEditTool {
     }
 
     fn description(&self) -> &str {
-        "Edit a file by replacing an exact string match with new content"
+        "Edit a file in place with search/replace hunks or a unified diff; whitespace differences are tolerated and dry_run validates without writing"
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -40,17 +463,42 @@ impl Tool for FileEditTool {
                 },
                 "old_string": {
                     "type": "string",
-                    "description": "The exact text to find and replace (must appear exactly once in the file)"
+                    "description": "The text to find and replace (must appear exactly once in the file)"
                 },
                 "new_string": {
                     "type": "string",
                     "description": "The replacement text (empty string to delete the matched text)"
+                },
+                "edits": {
+                    "type": "array",
+                    "description": "Several replacements applied in order, instead of old_string/new_string",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "old_string": { "type": "string" },
+                            "new_string": { "type": "string" }
+                        },
+                        "required": ["old_string", "new_string"]
+                    }
+                },
+                "diff": {
+                    "type": "string",
+                    "description": "A unified diff for this file (@@ hunks with ' ', '-', '+' lines), instead of old_string/new_string"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Check that the edit applies and report it without writing the file",
+                    "default": false
                 }
             },
-            "required": ["path", "old_string", "new_string"]
+            "required": ["path"]
         })
     }
 
+    fn kind(&self) -> ToolKind {
+        ToolKind::WriteLike
+    }
+
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         // ── 1. Extract parameters ──────────────────────────────────
         let path = args
@@ -58,23 +506,51 @@ impl Tool for FileEditTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing 'path' parameter"))?;
 
-        let old_string = args
-            .get("old_string")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Missing 'old_string' parameter"))?;
-
-        let new_string = args
-            .get("new_string")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Missing 'new_string' parameter"))?;
-
-        if old_string.is_empty() {
-            return Ok(ToolResult {
-                success: false,
-                output: String::new(),
-                error: Some("old_string must not be empty".into()),
-            });
-        }
+        let dry_run = args
+            .get("dry_run")
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false);
+
+        let forms = ["diff", "edits", "old_string"]
+            .iter()
+            .filter(|key| args.get(**key).is_some())
+            .count();
+        let spec = if forms > 1 {
+            Err("Pass only one of diff, edits, or old_string/new_string".to_string())
+        } else if let Some(diff) = args.get("diff").and_then(|v| v.as_str()) {
+            parse_unified_diff(diff).map(EditSpec::Diff)
+        } else if let Some(edits) = args.get("edits") {
+            parse_edits(edits).map(EditSpec::Edits)
+        } else {
+            let old_string = args
+                .get("old_string")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing 'old_string' parameter"))?;
+
+            let new_string = args
+                .get("new_string")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing 'new_string' parameter"))?;
+
+            if old_string.is_empty() {
+                Err("old_string must not be empty".to_string())
+            } else {
+                Ok(EditSpec::Replace {
+                    old: old_string.to_string(),
+                    new: new_string.to_string(),
+                })
+            }
+        };
+        let spec = match spec {
+            Ok(spec) => spec,
+            Err(error) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(error),
+                });
+            }
+        };
 
         // ── 2. Autonomy check ──────────────────────────────────────
         if !self.security.can_act() {
@@ -161,8 +637,8 @@ impl Tool for FileEditTool {
             }
         }
 
-        // ── 8. Record action ───────────────────────────────────────
-        if !self.security.record_action() {
+        // ── 8. Record action (dry runs change nothing) ────────────
+        if !dry_run && !self.security.record_action() {
             return Ok(ToolResult {
                 success: false,
                 output: String::new(),
@@ -170,7 +646,7 @@ impl Tool for FileEditTool {
             });
         }
 
-        // ── 9. Read → match → replace → write ─────────────────────
+        // ── 9. Read → apply hunks → write ──────────────────────────
         let content = match tokio::fs::read_to_string(&resolved_target).await {
             Ok(c) => c,
             Err(e) => {
@@ -182,35 +658,29 @@ impl Tool for FileEditTool {
             }
         };
 
-        let match_count = content.matches(old_string).count();
-
-        if match_count == 0 {
-            return Ok(ToolResult {
-                success: false,
-                output: String::new(),
-                error: Some("old_string not found in file".into()),
-            });
-        }
-
-        if match_count > 1 {
+        let (new_content, hunks) = match apply_spec(&content, &spec) {
+            Ok(edited) => edited,
+            Err(error) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(error),
+                });
+            }
+        };
+        let summary = summarize(path, &spec, &new_content, &hunks, dry_run);
+        if dry_run {
             return Ok(ToolResult {
-                success: false,
-                output: String::new(),
-                error: Some(format!(
-                    "old_string matches {match_count} times; must match exactly once"
-                )),
+                success: true,
+                output: summary,
+                error: None,
             });
         }
 
-        let new_content = content.replacen(old_string, new_string, 1);
-
         match tokio::fs::write(&resolved_target, &new_content).await {
             Ok(()) => Ok(ToolResult {
                 success: true,
-                output: format!(
-                    "Edited {path}: replaced 1 occurrence ({} bytes)",
-                    new_content.len()
-                ),
+                output: summary,
                 error: None,
             }),
             Err(e) => Ok(ToolResult {
@@ -261,10 +731,10 @@ mod tests {
         assert!(schema["properties"]["path"].is_object());
         assert!(schema["properties"]["old_string"].is_object());
         assert!(schema["properties"]["new_string"].is_object());
-        let required = schema["required"].as_array().unwrap();
-        assert!(required.contains(&json!("path")));
-        assert!(required.contains(&json!("old_string")));
-        assert!(required.contains(&json!("new_string")));
+        assert!(schema["properties"]["diff"].is_object());
+        assert!(schema["properties"]["dry_run"].is_object());
+        // old_string/new_string are one of three ways to describe the edit.
+        assert_eq!(schema["required"], json!(["path"]));
     }
 
     #[tokio::test]
@@ -297,6 +767,120 @@ mod tests {
         let _ = tokio::fs::remove_dir_all(&dir).await;
     }
 
+    #[tokio::test]
+    async fn file_edit_matches_ignoring_indentation_and_reindents() {
+        let dir = std::env::temp_dir().join("zeroclaw_test_file_edit_fuzzy");
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(
+            dir.join("main.py"),
+            "def main():\n    if ready:  \n        run()\n",
+        )
+        .await
+        .unwrap();
+
+        let tool = FileEditTool::new(test_security(dir.clone()));
+        let result = tool
+            .execute(json!({
+                "path": "main.py",
+                "old_string": "if ready:\n    run()",
+                "new_string": "if ready:\n    run()\n    report()"
+            }))
+            .await
+            .unwrap();
+
+        assert!(result.success, "edit should succeed: {:?}", result.error);
+        assert!(result
+            .output
+            .contains("line 2, ignoring indentation, +3 -2"));
+        let content = tokio::fs::read_to_string(dir.join("main.py"))
+            .await
+            .unwrap();
+        assert_eq!(
+            content,
+            "def main():\n    if ready:\n        run()\n        report()\n"
+        );
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn file_edit_applies_unified_diff_with_shifted_lines() {
+        let dir = std::env::temp_dir().join("zeroclaw_test_file_edit_diff");
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(dir.join("notes.md"), "# Notes\n\nintro\n- a\n- b\n- c\n")
+            .await
+            .unwrap();
+
+        // The hunks' line numbers are off by one, as if written against an
+        // older version of the file.
+        let diff = "--- a/notes.md\n+++ b/notes.md\n@@ -1,2 +1,2 @@\n-# Notes\n+# Team notes\n \n@@ -3,2 +3,3 @@\n - a\n+- a2\n - b\n";
+        let tool = FileEditTool::new(test_security(dir.clone()));
+        let result = tool
+            .execute(json!({ "path": "notes.md", "diff": diff }))
+            .await
+            .unwrap();
+
+        assert!(result.success, "diff should apply: {:?}", result.error);
+        assert!(result.output.contains("applied 2 hunks"));
+        assert!(result.output.contains("hunk 2: line 4, exact, +1 -0"));
+        let content = tokio::fs::read_to_string(dir.join("notes.md"))
+            .await
+            .unwrap();
+        assert_eq!(content, "# Team notes\n\nintro\n- a\n- a2\n- b\n- c\n");
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn file_edit_dry_run_and_failed_hunks_leave_file_untouched() {
+        let dir = std::env::temp_dir().join("zeroclaw_test_file_edit_dry_run");
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(dir.join("test.txt"), "alpha\nbeta\n")
+            .await
+            .unwrap();
+
+        let tool = FileEditTool::new(test_security(dir.clone()));
+        let dry_run = tool
+            .execute(json!({
+                "path": "test.txt",
+                "edits": [{ "old_string": "alpha", "new_string": "ALPHA" }],
+                "dry_run": true
+            }))
+            .await
+            .unwrap();
+        assert!(dry_run.success, "{:?}", dry_run.error);
+        assert!(dry_run
+            .output
+            .starts_with("Dry run for test.txt: applied 1 edit"));
+
+        // The second edit fails, so the first is not written either.
+        let failed = tool
+            .execute(json!({
+                "path": "test.txt",
+                "edits": [
+                    { "old_string": "alpha", "new_string": "ALPHA" },
+                    { "old_string": "gamma", "new_string": "GAMMA" }
+                ]
+            }))
+            .await
+            .unwrap();
+        assert!(!failed.success);
+        assert_eq!(
+            failed.error.as_deref(),
+            Some("edit 2: old_string not found in file")
+        );
+
+        let content = tokio::fs::read_to_string(dir.join("test.txt"))
+            .await
+            .unwrap();
+        assert_eq!(content, "alpha\nbeta\n");
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
     #[tokio::test]
     async fn file_edit_not_found() {
         let dir = std::env::temp_dir().join("zeroclaw_test_file_edit_notfound");
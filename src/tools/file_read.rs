@@ -1,4 +1,4 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolKind, ToolResult};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
@@ -48,6 +48,10 @@ impl Tool for FileReadTool {
         })
     }
 
+    fn kind(&self) -> ToolKind {
+        ToolKind::ReadLike
+    }
+
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         let path = args
             .get("path")
@@ -745,6 +749,7 @@ mod tests {
                         tool_calls: vec![],
                         usage: None,
                         reasoning_content: None,
+                        finish_reason: None,
                     });
                 }
                 Ok(guard.remove(0))
@@ -805,6 +810,7 @@ mod tests {
                 }],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             },
             // Turn 1 continued: provider sees tool result and answers
             ChatResponse {
@@ -812,6 +818,7 @@ mod tests {
                 tool_calls: vec![],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             },
         ]);
 
@@ -898,12 +905,14 @@ mod tests {
                 }],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             },
             ChatResponse {
                 text: Some("The file appears to be binary data.".into()),
                 tool_calls: vec![],
                 usage: None,
                 reasoning_content: None,
+                finish_reason: None,
             },
         ]);
 
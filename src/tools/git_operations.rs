@@ -1,4 +1,5 @@
 use super::traits::{Tool, ToolResult};
+use crate::security::tool_policy;
 use crate::security::{AutonomyLevel, SecurityPolicy};
 use async_trait::async_trait;
 use serde_json::json;
@@ -6,6 +7,8 @@ use std::sync::Arc;
 
 /// Git operations tool for structured repository management.
 /// Provides safe, parsed git operations with JSON output.
+/// Force pushes and hard resets go through the tool policy, which asks the
+/// user to approve each one.
 pub struct GitOperationsTool {
     security: Arc<SecurityPolicy>,
     workspace_dir: std::path::PathBuf,
@@ -49,11 +52,29 @@ impl GitOperationsTool {
         Ok(result)
     }
 
+    /// A single branch, remote, or commit argument that cannot be read as
+    /// an option.
+    fn single_arg(&self, value: &str, what: &str) -> anyhow::Result<String> {
+        match self.sanitize_git_args(value)?.as_slice() {
+            [arg] if !arg.starts_with('-') => Ok(arg.clone()),
+            _ => anyhow::bail!("Invalid {what} specification"),
+        }
+    }
+
+    fn branch_name(&self, value: &str) -> anyhow::Result<String> {
+        let name = self.single_arg(value, "branch")?;
+        // Block dangerous branch names
+        if name.contains('@') || name.contains('^') || name.contains('~') {
+            anyhow::bail!("Branch name contains invalid characters");
+        }
+        Ok(name)
+    }
+
     /// Check if an operation requires write access
     fn requires_write_access(&self, operation: &str) -> bool {
         matches!(
             operation,
-            "commit" | "add" | "checkout" | "stash" | "reset" | "revert"
+            "commit" | "add" | "checkout" | "stash" | "reset" | "revert" | "push"
         )
     }
 
@@ -66,11 +87,10 @@ impl GitOperationsTool {
     }
 
     async fn run_git_command(&self, args: &[&str]) -> anyhow::Result<String> {
-        let output = tokio::process::Command::new("git")
-            .args(args)
-            .current_dir(&self.workspace_dir)
-            .output()
-            .await?;
+        let mut command = tokio::process::Command::new("git");
+        command.args(args).current_dir(&self.workspace_dir);
+        super::task_env::apply(&mut command);
+        let output = command.output().await?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -247,7 +267,23 @@ impl GitOperationsTool {
         })
     }
 
-    async fn git_branch(&self, _args: serde_json::Value) -> anyhow::Result<ToolResult> {
+    async fn git_branch(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        if let Some(name) = args.get("branch").and_then(|v| v.as_str()) {
+            let name = self.branch_name(name)?;
+            return match self.run_git_command(&["branch", &name]).await {
+                Ok(_) => Ok(ToolResult {
+                    success: true,
+                    output: format!("Created branch: {name}"),
+                    error: None,
+                }),
+                Err(e) => Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(format!("Branch creation failed: {e}")),
+                }),
+            };
+        }
+
         let output = self
             .run_git_command(&["branch", "--format=%(refname:short)|%(HEAD)"])
             .await?;
@@ -355,32 +391,107 @@ impl GitOperationsTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing 'branch' parameter"))?;
 
-        // Sanitize branch name
-        let sanitized = self.sanitize_git_args(branch)?;
+        let create = args
+            .get("create")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
-        if sanitized.is_empty() || sanitized.len() > 1 {
-            anyhow::bail!("Invalid branch specification");
-        }
+        let branch_name = self.branch_name(branch)?;
 
-        let branch_name = &sanitized[0];
+        let output = if create {
+            self.run_git_command(&["checkout", "-b", &branch_name])
+                .await
+        } else {
+            self.run_git_command(&["checkout", &branch_name]).await
+        };
 
-        // Block dangerous branch names
-        if branch_name.contains('@') || branch_name.contains('^') || branch_name.contains('~') {
-            anyhow::bail!("Branch name contains invalid characters");
+        match output {
+            Ok(_) => Ok(ToolResult {
+                success: true,
+                output: if create {
+                    format!("Created and switched to branch: {branch_name}")
+                } else {
+                    format!("Switched to branch: {branch_name}")
+                },
+                error: None,
+            }),
+            Err(e) => Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!("Checkout failed: {e}")),
+            }),
         }
+    }
 
-        let output = self.run_git_command(&["checkout", branch_name]).await;
+    async fn git_push(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let remote = self.single_arg(
+            args.get("remote")
+                .and_then(|v| v.as_str())
+                .unwrap_or("origin"),
+            "remote",
+        )?;
+        let branch = args
+            .get("branch")
+            .and_then(|v| v.as_str())
+            .map(|branch| self.branch_name(branch))
+            .transpose()?;
+        let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        // A forced push still refuses to drop commits it has not seen.
+        let mut git_args = vec!["push"];
+        if force {
+            git_args.push("--force-with-lease");
+        }
+        git_args.push(&remote);
+        if let Some(branch) = &branch {
+            git_args.push(branch);
+        }
 
-        match output {
+        match self.run_git_command(&git_args).await {
             Ok(_) => Ok(ToolResult {
                 success: true,
-                output: format!("Switched to branch: {branch_name}"),
+                output: format!(
+                    "Pushed {} to {remote}{}",
+                    branch.as_deref().unwrap_or("current branch"),
+                    if force { " (forced)" } else { "" }
+                ),
                 error: None,
             }),
             Err(e) => Ok(ToolResult {
                 success: false,
                 output: String::new(),
-                error: Some(format!("Checkout failed: {e}")),
+                error: Some(format!("Push failed: {e}")),
+            }),
+        }
+    }
+
+    async fn git_reset(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let mode = args.get("mode").and_then(|v| v.as_str()).unwrap_or("mixed");
+        if !matches!(mode, "soft" | "mixed" | "hard") {
+            anyhow::bail!("Unknown reset mode: {mode}. Use: soft, mixed, hard");
+        }
+        let target = self.single_arg(
+            args.get("target")
+                .and_then(|v| v.as_str())
+                .unwrap_or("HEAD"),
+            "reset target",
+        )?;
+
+        match self
+            .run_git_command(&["reset", &format!("--{mode}"), &target])
+            .await
+        {
+            Ok(out) => Ok(ToolResult {
+                success: true,
+                output: format!("Reset ({mode}) to {target}\n{out}")
+                    .trim_end()
+                    .to_string(),
+                error: None,
+            }),
+            Err(e) => Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!("Reset failed: {e}")),
             }),
         }
     }
@@ -430,7 +541,7 @@ impl Tool for GitOperationsTool {
     }
 
     fn description(&self) -> &str {
-        "Perform structured Git operations (status, diff, log, branch, commit, add, checkout, stash). Provides parsed JSON output and integrates with security policy for autonomy controls."
+        "Perform structured Git operations (status, diff, log, branch, commit, add, checkout, stash, push, reset). Provides parsed JSON output and integrates with security policy for autonomy controls. Force pushes and hard resets need the user's approval."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -439,7 +550,7 @@ impl Tool for GitOperationsTool {
             "properties": {
                 "operation": {
                     "type": "string",
-                    "enum": ["status", "diff", "log", "branch", "commit", "add", "checkout", "stash"],
+                    "enum": ["status", "diff", "log", "branch", "commit", "add", "checkout", "stash", "push", "reset"],
                     "description": "Git operation to perform"
                 },
                 "message": {
@@ -452,7 +563,28 @@ impl Tool for GitOperationsTool {
                 },
                 "branch": {
                     "type": "string",
-                    "description": "Branch name (for 'checkout' operation)"
+                    "description": "Branch name (for 'checkout' and 'push'; for 'branch', creates it instead of listing)"
+                },
+                "create": {
+                    "type": "boolean",
+                    "description": "Create the branch before switching, like `checkout -b` (for 'checkout' operation)"
+                },
+                "remote": {
+                    "type": "string",
+                    "description": "Remote to push to (for 'push' operation, default: 'origin')"
+                },
+                "force": {
+                    "type": "boolean",
+                    "description": "Force the push with --force-with-lease; needs the user's approval (for 'push' operation)"
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["soft", "mixed", "hard"],
+                    "description": "Reset mode (for 'reset' operation, default: 'mixed'); 'hard' needs the user's approval"
+                },
+                "target": {
+                    "type": "string",
+                    "description": "Commit to reset to (for 'reset' operation, default: 'HEAD')"
                 },
                 "files": {
                     "type": "string",
@@ -515,7 +647,8 @@ impl Tool for GitOperationsTool {
         }
 
         // Check autonomy level for write operations
-        if self.requires_write_access(operation) {
+        let creates_branch = operation == "branch" && args.get("branch").is_some();
+        if self.requires_write_access(operation) || creates_branch {
             if !self.security.can_act() {
                 return Ok(ToolResult {
                     success: false,
@@ -538,6 +671,20 @@ impl Tool for GitOperationsTool {
             }
         }
 
+        // Destructive operations are approved through the tool policy; a
+        // run without one only allows them at full autonomy.
+        if let Some(what) = tool_policy::destructive_git_call(&args) {
+            if !tool_policy::in_scope() && self.security.autonomy != AutonomyLevel::Full {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(format!(
+                        "Action blocked: git {what} need the user's approval, which only channel and task runs can ask for"
+                    )),
+                });
+            }
+        }
+
         // Record action for rate limiting
         if !self.security.record_action() {
             return Ok(ToolResult {
@@ -557,6 +704,8 @@ impl Tool for GitOperationsTool {
             "add" => self.git_add(args).await,
             "checkout" => self.git_checkout(args).await,
             "stash" => self.git_stash(args).await,
+            "push" => self.git_push(args).await,
+            "reset" => self.git_reset(args).await,
             _ => Ok(ToolResult {
                 success: false,
                 output: String::new(),
@@ -660,6 +809,8 @@ mod tests {
         assert!(tool.requires_write_access("commit"));
         assert!(tool.requires_write_access("add"));
         assert!(tool.requires_write_access("checkout"));
+        assert!(tool.requires_write_access("push"));
+        assert!(tool.requires_write_access("reset"));
 
         assert!(!tool.requires_write_access("status"));
         assert!(!tool.requires_write_access("diff"));
@@ -794,7 +945,7 @@ mod tests {
 
         let tool = test_tool(tmp.path());
 
-        let result = tool.execute(json!({"operation": "push"})).await.unwrap();
+        let result = tool.execute(json!({"operation": "rebase"})).await.unwrap();
         assert!(!result.success);
         assert!(result
             .error
@@ -803,6 +954,46 @@ mod tests {
             .contains("Unknown operation"));
     }
 
+    #[tokio::test]
+    async fn creates_branches_and_gates_destructive_operations() {
+        let tmp = TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .env("GIT_AUTHOR_NAME", "test")
+                .env("GIT_AUTHOR_EMAIL", "test@example.com")
+                .env("GIT_COMMITTER_NAME", "test")
+                .env("GIT_COMMITTER_EMAIL", "test@example.com")
+                .output()
+                .unwrap()
+        };
+        git(&["init"]);
+        git(&["commit", "--allow-empty", "-m", "init"]);
+        let tool = test_tool(tmp.path());
+
+        let result = tool
+            .execute(json!({"operation": "checkout", "branch": "feature/x", "create": true}))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        let result = tool.execute(json!({"operation": "branch"})).await.unwrap();
+        assert!(result.output.contains("\"current\": \"feature/x\""));
+
+        assert!(tool
+            .execute(json!({"operation": "push", "remote": "--upload-pack=evil"}))
+            .await
+            .is_err());
+
+        // No tool policy scope and supervised autonomy: nobody can approve.
+        let result = tool
+            .execute(json!({"operation": "reset", "mode": "hard", "target": "HEAD"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("hard resets"));
+    }
+
     #[test]
     fn truncates_multibyte_commit_message_without_panicking() {
         let long = "🦀".repeat(2500);
@@ -1,4 +1,4 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{record_result_metadata, Tool, ToolKind, ToolResult};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
@@ -7,6 +7,8 @@ use std::time::Duration;
 
 /// HTTP request tool for API interactions.
 /// Supports GET, POST, PUT, DELETE methods with configurable security.
+/// JSON responses are pretty-printed; bodies are read only up to
+/// `max_response_size`.
 pub struct HttpRequestTool {
     security: Arc<SecurityPolicy>,
     allowed_domains: Vec<String>,
@@ -94,6 +96,7 @@ impl HttpRequestTool {
             .map(|(key, value)| {
                 let lower = key.to_lowercase();
                 let is_sensitive = lower.contains("authorization")
+                    || lower.contains("cookie")
                     || lower.contains("api-key")
                     || lower.contains("apikey")
                     || lower.contains("token")
@@ -112,7 +115,7 @@ impl HttpRequestTool {
         url: &str,
         method: reqwest::Method,
         headers: Vec<(String, String)>,
-        body: Option<&str>,
+        body: Option<String>,
     ) -> anyhow::Result<reqwest::Response> {
         let timeout_secs = if self.timeout_secs == 0 {
             tracing::warn!("http_request: timeout_secs is 0, using safe default of 30s");
@@ -133,13 +136,45 @@ impl HttpRequestTool {
             request = request.header(&key, &value);
         }
 
-        if let Some(body_str) = body {
-            request = request.body(body_str.to_string());
+        if let Some(body) = body {
+            request = request.body(body);
         }
 
         Ok(request.send().await?)
     }
 
+    /// Read the body up to `max_response_size` bytes; the flag is set when
+    /// the rest was cut off.
+    async fn read_body(&self, mut response: reqwest::Response) -> anyhow::Result<(Vec<u8>, bool)> {
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            let room = self.max_response_size.saturating_sub(body.len());
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                return Ok((body, true));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok((body, false))
+    }
+
+    /// Body text for the model: JSON pretty-printed, the rest as is.
+    fn format_body(&self, body: &[u8], cut_off: bool) -> String {
+        let text = String::from_utf8_lossy(body);
+        if cut_off {
+            return format!("{text}\n\n... [Response truncated due to size limit] ...");
+        }
+        let trimmed = text.trim_start();
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(trimmed) {
+                if let Ok(pretty) = serde_json::to_string_pretty(&value) {
+                    return self.truncate_response(&pretty);
+                }
+            }
+        }
+        self.truncate_response(&text)
+    }
+
     fn truncate_response(&self, text: &str) -> String {
         if text.len() > self.max_response_size {
             let mut truncated = text
@@ -186,12 +221,20 @@ impl Tool for HttpRequestTool {
                 "body": {
                     "type": "string",
                     "description": "Optional request body (for POST, PUT, PATCH requests)"
+                },
+                "json": {
+                    "type": "object",
+                    "description": "Optional JSON request body, used instead of `body`; sent with Content-Type: application/json unless a Content-Type header is given"
                 }
             },
             "required": ["url"]
         })
     }
 
+    fn kind(&self) -> ToolKind {
+        ToolKind::Network
+    }
+
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         let url = args
             .get("url")
@@ -200,7 +243,11 @@ impl Tool for HttpRequestTool {
 
         let method_str = args.get("method").and_then(|v| v.as_str()).unwrap_or("GET");
         let headers_val = args.get("headers").cloned().unwrap_or(json!({}));
-        let body = args.get("body").and_then(|v| v.as_str());
+        let json_body = args.get("json").filter(|v| !v.is_null());
+        let body = match json_body {
+            Some(value) => Some(value.to_string()),
+            None => args.get("body").and_then(|v| v.as_str()).map(String::from),
+        };
 
         if !self.security.can_act() {
             return Ok(ToolResult {
@@ -240,7 +287,14 @@ impl Tool for HttpRequestTool {
             }
         };
 
-        let request_headers = self.parse_headers(&headers_val);
+        let mut request_headers = self.parse_headers(&headers_val);
+        if json_body.is_some()
+            && !request_headers
+                .iter()
+                .any(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        {
+            request_headers.push(("Content-Type".into(), "application/json".into()));
+        }
 
         match self
             .execute_request(&url, method, request_headers, body)
@@ -251,29 +305,36 @@ impl Tool for HttpRequestTool {
                 let status_code = status.as_u16();
 
                 // Get response headers (redact sensitive ones)
-                let response_headers = response.headers().iter();
-                let headers_text = response_headers
-                    .map(|(k, _)| {
-                        let is_sensitive = k.as_str().to_lowercase().contains("set-cookie");
-                        if is_sensitive {
-                            format!("{}: ***REDACTED***", k.as_str())
-                        } else {
-                            format!("{}: {:?}", k.as_str(), k.as_str())
-                        }
+                let response_headers = response
+                    .headers()
+                    .iter()
+                    .map(|(k, v)| {
+                        (
+                            k.as_str().to_string(),
+                            String::from_utf8_lossy(v.as_bytes()).into_owned(),
+                        )
                     })
+                    .collect::<Vec<_>>();
+                let headers_text = Self::redact_headers_for_display(&response_headers)
+                    .into_iter()
+                    .map(|(k, v)| format!("{k}: {v}"))
                     .collect::<Vec<_>>()
                     .join(", ");
 
                 // Get response body with size limit
-                let response_text = match response.text().await {
-                    Ok(text) => self.truncate_response(&text),
+                let response_text = match self.read_body(response).await {
+                    Ok((body, cut_off)) => self.format_body(&body, cut_off),
                     Err(e) => format!("[Failed to read response body: {e}]"),
                 };
 
+                if status.is_success() {
+                    record_result_metadata("sources", json!({ "url": url }));
+                }
                 let output = format!(
-                    "Status: {} {}\nResponse Headers: {}\n\nResponse Body:\n{}",
+                    "Status: {} {}\nURL: {}\nResponse Headers: {}\n\nResponse Body:\n{}",
                     status_code,
                     status.canonical_reason().unwrap_or("Unknown"),
+                    url,
                     headers_text,
                     response_text
                 );
@@ -297,9 +358,9 @@ impl Tool for HttpRequestTool {
     }
 }
 
-// Helper functions similar to browser_open.rs
+// Helper functions similar to browser_open.rs; `web_fetch` shares them.
 
-fn normalize_allowed_domains(domains: Vec<String>) -> Vec<String> {
+pub(super) fn normalize_allowed_domains(domains: Vec<String>) -> Vec<String> {
     let mut normalized = domains
         .into_iter()
         .filter_map(|d| normalize_domain(&d))
@@ -338,7 +399,7 @@ fn normalize_domain(raw: &str) -> Option<String> {
     Some(d)
 }
 
-fn extract_host(url: &str) -> anyhow::Result<String> {
+pub(super) fn extract_host(url: &str) -> anyhow::Result<String> {
     let rest = url
         .strip_prefix("http://")
         .or_else(|| url.strip_prefix("https://"))
@@ -376,7 +437,7 @@ fn extract_host(url: &str) -> anyhow::Result<String> {
     Ok(host)
 }
 
-fn host_matches_allowlist(host: &str, allowed_domains: &[String]) -> bool {
+pub(super) fn host_matches_allowlist(host: &str, allowed_domains: &[String]) -> bool {
     if allowed_domains.iter().any(|domain| domain == "*") {
         return true;
     }
@@ -389,7 +450,7 @@ fn host_matches_allowlist(host: &str, allowed_domains: &[String]) -> bool {
     })
 }
 
-fn is_private_or_local_host(host: &str) -> bool {
+pub(super) fn is_private_or_local_host(host: &str) -> bool {
     // Strip brackets from IPv6 addresses like [::1]
     let bare = host
         .strip_prefix('[')
@@ -727,6 +788,18 @@ mod tests {
         assert!(truncated.contains("[Response truncated"));
     }
 
+    #[test]
+    fn format_body_pretty_prints_json() {
+        let tool = test_tool(vec!["example.com"]);
+        let body = tool.format_body(br#"{"id":7,"tags":["a"]}"#, false);
+        assert_eq!(body, "{\n  \"id\": 7,\n  \"tags\": [\n    \"a\"\n  ]\n}");
+        assert_eq!(tool.format_body(b"plain text", false), "plain text");
+
+        let cut_off = tool.format_body(br#"{"id":7,"ta"#, true);
+        assert!(cut_off.starts_with(r#"{"id":7,"ta"#));
+        assert!(cut_off.contains("[Response truncated"));
+    }
+
     #[test]
     fn parse_headers_preserves_original_values() {
         let tool = test_tool(vec!["example.com"]);
@@ -755,9 +828,13 @@ mod tests {
             ("Content-Type".into(), "application/json".into()),
             ("X-API-Key".into(), "my-key".into()),
             ("X-Secret-Token".into(), "tok-123".into()),
+            ("Set-Cookie".into(), "session=abc".into()),
         ];
         let redacted = HttpRequestTool::redact_headers_for_display(&headers);
-        assert_eq!(redacted.len(), 4);
+        assert_eq!(redacted.len(), 5);
+        assert!(redacted
+            .iter()
+            .any(|(k, v)| k == "Set-Cookie" && v == "***REDACTED***"));
         assert!(redacted
             .iter()
             .any(|(k, v)| k == "Authorization" && v == "***REDACTED***"));
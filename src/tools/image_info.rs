@@ -1,4 +1,4 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{Tool, ToolKind, ToolResult};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
@@ -145,6 +145,10 @@ impl Tool for ImageInfoTool {
         })
     }
 
+    fn kind(&self) -> ToolKind {
+        ToolKind::ReadLike
+    }
+
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         let path_str = args
             .get("path")
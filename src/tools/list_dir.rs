@@ -0,0 +1,482 @@
+use super::traits::{Tool, ToolKind, ToolResult};
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use serde_json::json;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const DEFAULT_MAX_DEPTH: usize = 3;
+const MAX_DEPTH: usize = 10;
+const DEFAULT_MAX_ENTRIES: usize = 300;
+const MAX_ENTRIES: usize = 2000;
+
+/// List a workspace directory as an indented tree.
+///
+/// Entries matched by `.gitignore` files (the workspace's, the listed
+/// directory's ancestors', and those found while descending) are skipped, as
+/// are hidden entries unless asked for. `.git` is never listed. Symlinks are
+/// shown with their target but not followed.
+pub struct ListDirTool {
+    security: Arc<SecurityPolicy>,
+}
+
+impl ListDirTool {
+    pub fn new(security: Arc<SecurityPolicy>) -> Self {
+        Self { security }
+    }
+}
+
+/// Resolve a workspace-relative `path` for a read-only tool, rejecting
+/// absolute paths, traversal, and anything the security policy forbids.
+pub(super) fn resolve_workspace_path(
+    security: &SecurityPolicy,
+    path: &str,
+) -> Result<PathBuf, String> {
+    if Path::new(path).is_absolute() {
+        return Err("Absolute paths are not allowed. Use a relative path.".into());
+    }
+    if path.contains("../") || path.contains("..\\") || path == ".." {
+        return Err("Path traversal ('..') is not allowed.".into());
+    }
+    if !security.is_path_allowed(path) {
+        return Err(format!("Path '{path}' is not allowed by security policy."));
+    }
+    let resolved = std::fs::canonicalize(security.workspace_dir.join(path))
+        .map_err(|e| format!("Cannot resolve path '{path}': {e}"))?;
+    if !security.is_resolved_path_allowed(&resolved) {
+        return Err(format!(
+            "Resolved path for '{path}' is outside the allowed workspace."
+        ));
+    }
+    Ok(resolved)
+}
+
+/// One `.gitignore` line.
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    /// Workspace-relative directory of the `.gitignore` it came from.
+    base: PathBuf,
+    pattern: glob::Pattern,
+    negated: bool,
+    dir_only: bool,
+    /// Patterns with a `/` match the path below `base`; others match any
+    /// entry's name.
+    anchored: bool,
+}
+
+impl IgnoreRule {
+    fn matches(&self, rel: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let Ok(sub) = rel.strip_prefix(&self.base) else {
+            return false;
+        };
+        if self.anchored {
+            let options = glob::MatchOptions {
+                case_sensitive: true,
+                require_literal_separator: true,
+                require_literal_leading_dot: false,
+            };
+            self.pattern
+                .matches_with(&sub.to_string_lossy().replace('\\', "/"), options)
+        } else {
+            sub.file_name()
+                .is_some_and(|name| self.pattern.matches(&name.to_string_lossy()))
+        }
+    }
+}
+
+fn parse_gitignore(text: &str, base: &Path) -> Vec<IgnoreRule> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (negated, line) = line
+                .strip_prefix('!')
+                .map_or((false, line), |rest| (true, rest));
+            let (dir_only, line) = line
+                .strip_suffix('/')
+                .map_or((false, line), |rest| (true, rest));
+            let anchored = line.contains('/');
+            let line = line.trim_start_matches('/');
+            if line.is_empty() {
+                return None;
+            }
+            Some(IgnoreRule {
+                base: base.to_path_buf(),
+                pattern: glob::Pattern::new(line).ok()?,
+                negated,
+                dir_only,
+                anchored,
+            })
+        })
+        .collect()
+}
+
+fn load_gitignore(dir: &Path, rel: &Path) -> Vec<IgnoreRule> {
+    std::fs::read_to_string(dir.join(".gitignore"))
+        .map(|text| parse_gitignore(&text, rel))
+        .unwrap_or_default()
+}
+
+/// The last matching rule decides, so a later `!pattern` re-includes.
+fn is_ignored(rules: &[IgnoreRule], rel: &Path, is_dir: bool) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(rel, is_dir))
+        .is_some_and(|rule| !rule.negated)
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+struct Walk {
+    max_depth: usize,
+    max_entries: usize,
+    show_hidden: bool,
+    include_ignored: bool,
+    lines: Vec<String>,
+    dirs: usize,
+    files: usize,
+    ignored: usize,
+    /// Entries inside directories at the depth limit.
+    below_depth: usize,
+    truncated: bool,
+}
+
+impl Walk {
+    /// List `dir` (workspace-relative `rel`) at indentation `depth`.
+    fn walk(&mut self, dir: &Path, rel: &Path, depth: usize, inherited: &[IgnoreRule]) {
+        let mut rules = inherited.to_vec();
+        if !self.include_ignored {
+            rules.extend(load_gitignore(dir, rel));
+        }
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut entries: Vec<(String, PathBuf, std::fs::Metadata)> = read_dir
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let path = entry.path();
+                let meta = std::fs::symlink_metadata(&path).ok()?;
+                Some((entry.file_name().to_string_lossy().into_owned(), path, meta))
+            })
+            .collect();
+        // Directories first, then by name.
+        entries.sort_by(|a, b| b.2.is_dir().cmp(&a.2.is_dir()).then_with(|| a.0.cmp(&b.0)));
+
+        let indent = "  ".repeat(depth);
+        for (name, path, meta) in entries {
+            if name == ".git" || (!self.show_hidden && name.starts_with('.')) {
+                continue;
+            }
+            let entry_rel = rel.join(&name);
+            let is_dir = meta.is_dir();
+            if !self.include_ignored && is_ignored(&rules, &entry_rel, is_dir) {
+                self.ignored += 1;
+                continue;
+            }
+            if self.dirs + self.files >= self.max_entries {
+                self.truncated = true;
+                return;
+            }
+            if meta.file_type().is_symlink() {
+                self.files += 1;
+                let target = std::fs::read_link(&path)
+                    .map(|target| target.display().to_string())
+                    .unwrap_or_else(|_| "?".into());
+                self.lines.push(format!("{indent}{name} -> {target}"));
+            } else if is_dir {
+                self.dirs += 1;
+                if depth + 1 < self.max_depth {
+                    self.lines.push(format!("{indent}{name}/"));
+                    self.walk(&path, &entry_rel, depth + 1, &rules);
+                    if self.truncated {
+                        return;
+                    }
+                } else {
+                    let count = std::fs::read_dir(&path).map_or(0, Iterator::count);
+                    self.below_depth += count;
+                    if count == 0 {
+                        self.lines.push(format!("{indent}{name}/"));
+                    } else {
+                        self.lines
+                            .push(format!("{indent}{name}/ ({count} entries not shown)"));
+                    }
+                }
+            } else {
+                self.files += 1;
+                self.lines
+                    .push(format!("{indent}{name} ({})", format_size(meta.len())));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ListDirTool {
+    fn name(&self) -> &str {
+        "list_dir"
+    }
+
+    fn description(&self) -> &str {
+        "List a workspace directory as a tree with file sizes. \
+         Skips .gitignore'd and hidden entries by default; directories at max_depth \
+         show how many entries they hold. Use this instead of `ls`/`find` in the shell."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to list, relative to workspace root. Defaults to '.'",
+                    "default": "."
+                },
+                "max_depth": {
+                    "type": "integer",
+                    "description": "Levels to descend; 1 lists only the directory's own entries (default 3, max 10)",
+                    "default": 3
+                },
+                "max_entries": {
+                    "type": "integer",
+                    "description": "Entries to list before stopping (default 300, max 2000)",
+                    "default": 300
+                },
+                "show_hidden": {
+                    "type": "boolean",
+                    "description": "Include entries whose names start with '.'",
+                    "default": false
+                },
+                "include_ignored": {
+                    "type": "boolean",
+                    "description": "Include entries matched by .gitignore",
+                    "default": false
+                }
+            }
+        })
+    }
+
+    fn kind(&self) -> ToolKind {
+        ToolKind::ReadLike
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+        let limit = |key: &str, default: usize, max: usize| {
+            args.get(key)
+                .and_then(|v| v.as_u64())
+                .map_or(default, |n| usize::try_from(n).unwrap_or(max))
+                .clamp(1, max)
+        };
+        let max_depth = limit("max_depth", DEFAULT_MAX_DEPTH, MAX_DEPTH);
+        let max_entries = limit("max_entries", DEFAULT_MAX_ENTRIES, MAX_ENTRIES);
+        let flag = |key: &str| args.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+        if self.security.is_rate_limited() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Rate limit exceeded: too many actions in the last hour".into()),
+            });
+        }
+
+        let resolved = match resolve_workspace_path(&self.security, path) {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(error),
+                });
+            }
+        };
+        if !resolved.is_dir() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!("'{path}' is not a directory.")),
+            });
+        }
+
+        if !self.security.record_action() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Rate limit exceeded: action budget exhausted".into()),
+            });
+        }
+
+        let workspace = std::fs::canonicalize(&self.security.workspace_dir)
+            .unwrap_or_else(|_| self.security.workspace_dir.clone());
+        let rel = resolved
+            .strip_prefix(&workspace)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        let mut walk = Walk {
+            max_depth,
+            max_entries,
+            show_hidden: flag("show_hidden"),
+            include_ignored: flag("include_ignored"),
+            lines: Vec::new(),
+            dirs: 0,
+            files: 0,
+            ignored: 0,
+            below_depth: 0,
+            truncated: false,
+        };
+        // Rules from the workspace root down to the listed directory; the
+        // walk adds the listed directory's own.
+        let mut rules = Vec::new();
+        if !walk.include_ignored {
+            for ancestor in rel
+                .ancestors()
+                .skip(1)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+            {
+                rules.extend(load_gitignore(&workspace.join(ancestor), ancestor));
+            }
+        }
+        walk.walk(&resolved, &rel, 0, &rules);
+
+        let root = if rel.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            rel.display().to_string()
+        };
+        let mut output = format!("{root}/\n");
+        for line in &walk.lines {
+            let _ = writeln!(output, "  {line}");
+        }
+        let _ = write!(
+            output,
+            "\n{} directories, {} files listed",
+            walk.dirs, walk.files
+        );
+        if walk.below_depth > 0 {
+            let _ = write!(
+                output,
+                "; {} entries below max_depth {max_depth}",
+                walk.below_depth
+            );
+        }
+        if walk.ignored > 0 {
+            let _ = write!(output, "; {} ignored by .gitignore", walk.ignored);
+        }
+        if walk.truncated {
+            let _ = write!(
+                output,
+                "\n[Listing stopped at max_entries {max_entries}; list a subdirectory or lower max_depth]"
+            );
+        }
+
+        Ok(ToolResult {
+            success: true,
+            output,
+            error: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{AutonomyLevel, SecurityPolicy};
+    use tempfile::TempDir;
+
+    fn tool(workspace: &Path) -> ListDirTool {
+        ListDirTool::new(Arc::new(SecurityPolicy {
+            autonomy: AutonomyLevel::Supervised,
+            workspace_dir: workspace.to_path_buf(),
+            ..SecurityPolicy::default()
+        }))
+    }
+
+    fn create_tree(dir: &TempDir) {
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/tools")).unwrap();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n*.log\n!keep.log\n").unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("src/tools/mod.rs"), vec![b'x'; 2048]).unwrap();
+        std::fs::write(root.join("src/tools/shell.rs"), "").unwrap();
+        std::fs::write(root.join("build.log"), "").unwrap();
+        std::fs::write(root.join("keep.log"), "kept").unwrap();
+        std::fs::write(root.join("target/debug/app"), "").unwrap();
+    }
+
+    #[tokio::test]
+    async fn lists_tree_without_ignored_and_hidden_entries() {
+        let dir = TempDir::new().unwrap();
+        create_tree(&dir);
+        let result = tool(dir.path()).execute(json!({})).await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(
+            result.output,
+            "./\n  src/\n    tools/\n      mod.rs (2.0 KB)\n      shell.rs (0 B)\n    main.rs (13 B)\n  keep.log (4 B)\n\n2 directories, 4 files listed; 2 ignored by .gitignore"
+        );
+
+        let result = tool(dir.path())
+            .execute(json!({ "path": "src", "max_depth": 1, "include_ignored": true }))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.output,
+            "src/\n  tools/ (2 entries not shown)\n  main.rs (13 B)\n\n1 directories, 1 files listed; 2 entries below max_depth 1"
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_at_max_entries_and_rejects_escapes() {
+        let dir = TempDir::new().unwrap();
+        create_tree(&dir);
+        let result = tool(dir.path())
+            .execute(json!({ "max_entries": 2 }))
+            .await
+            .unwrap();
+        assert!(result.output.contains("  src/\n    tools/\n\n"));
+        assert!(result.output.contains("[Listing stopped at max_entries 2"));
+
+        let result = tool(dir.path())
+            .execute(json!({ "path": "../" }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("traversal"));
+    }
+
+    #[test]
+    fn gitignore_rules_follow_git_precedence() {
+        let rules = parse_gitignore(
+            "# comment\n/docs/*.md\nbuild\n!build\n*.tmp\n",
+            Path::new("pkg"),
+        );
+        assert!(is_ignored(&rules, Path::new("pkg/docs/a.md"), false));
+        assert!(!is_ignored(&rules, Path::new("pkg/x/docs/a.md"), false));
+        assert!(!is_ignored(&rules, Path::new("pkg/build"), true));
+        assert!(is_ignored(&rules, Path::new("pkg/a/b.tmp"), false));
+        assert!(!is_ignored(&rules, Path::new("other/b.tmp"), false));
+    }
+}
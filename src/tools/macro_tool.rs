@@ -0,0 +1,405 @@
+//! Tools defined in config as a fixed sequence of other tool calls
+//! (`[macro_tools.<name>]`).
+//!
+//! The model sees one tool taking the macro's string parameters. A call
+//! substitutes them into each step's `{{param}}` placeholders and runs the
+//! steps in order through the registered tools, stopping at the first
+//! failure, so a multi-call routine costs one round instead of several.
+//! Arguments substituted into a step's `command` are shell-quoted.
+//!
+//! Before the macro runs, the tool loop passes every step through its
+//! before-tool-call hooks and approval gate (see [`Tool::step_calls`]) and
+//! hands the resulting calls over with [`with_gated_steps`]. Every step then
+//! gets the argument and tool-policy checks a direct call would; a step that
+//! needs a tool-policy confirmation fails instead, since a macro cannot
+//! pause halfway. Excluding any tool a macro calls excludes the macro too
+//! (see [`Tool::inner_tools`]).
+
+use super::shell_session::shell_quote;
+use super::traits::{Tool, ToolKind, ToolResult};
+use crate::config::{MacroToolConfig, MacroToolStep};
+use crate::security::tool_policy::{self, ToolVerdict};
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+tokio::task_local! {
+    /// Step calls that already went through the loop's hooks and approval
+    /// gate, keyed by the arguments hash of the macro call.
+    static GATED_STEPS: HashMap<String, Vec<(String, Value)>>;
+}
+
+/// Run `future` with macro calls using the gated step calls in `steps`
+/// (macro call arguments hash → step calls) instead of substituting their
+/// own.
+pub async fn with_gated_steps<F: Future>(
+    steps: HashMap<String, Vec<(String, Value)>>,
+    future: F,
+) -> F::Output {
+    GATED_STEPS.scope(steps, future).await
+}
+
+pub struct MacroTool {
+    name: String,
+    description: String,
+    /// Parameter names and descriptions, sorted by name.
+    parameters: Vec<(String, String)>,
+    steps: Vec<MacroToolStep>,
+    tools: Arc<Vec<Arc<dyn Tool>>>,
+}
+
+impl MacroTool {
+    /// `tools` are the registered tools the steps may call.
+    pub fn new(name: &str, config: &MacroToolConfig, tools: Arc<Vec<Arc<dyn Tool>>>) -> Self {
+        let mut parameters: Vec<(String, String)> = config
+            .parameters
+            .iter()
+            .map(|(name, description)| (name.clone(), description.clone()))
+            .collect();
+        parameters.sort();
+        Self {
+            name: name.to_string(),
+            description: config.description.clone(),
+            parameters,
+            steps: config.steps.clone(),
+            tools,
+        }
+    }
+
+    fn find_tool(&self, name: &str) -> Option<&Arc<dyn Tool>> {
+        self.tools.iter().find(|tool| tool.name() == name)
+    }
+
+    /// Run one step; `Err` carries why it failed.
+    async fn run_step(&self, name: &str, step_args: Value) -> Result<String, String> {
+        let tool = self
+            .find_tool(name)
+            .ok_or_else(|| format!("tool '{name}' is not available"))?;
+
+        let violations = tool.argument_violations(&step_args);
+        if !violations.is_empty() {
+            return Err(format!(
+                "invalid arguments in the macro definition: {}",
+                violations.join("; ")
+            ));
+        }
+
+        let arguments_hash = crate::agent::loop_::tool_arguments_hash(name, &step_args);
+        match tool_policy::check(name, &step_args, &arguments_hash) {
+            ToolVerdict::Allow => {}
+            ToolVerdict::Deny(reason) => return Err(format!("blocked by tool policy: {reason}")),
+            ToolVerdict::Confirm(reason) => {
+                return Err(format!(
+                    "needs the user's approval ({reason}); call `{name}` directly instead"
+                ));
+            }
+        }
+
+        if tool.kind() == ToolKind::WriteLike {
+            crate::agent::workspace_transaction::before_write(&step_args);
+        }
+        match tool.execute(step_args).await {
+            Ok(result) if result.success => Ok(result.output),
+            Ok(result) => Err(result.error.unwrap_or(result.output)),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+/// Replace `{{param}}` in every string of `value` with the argument of that
+/// name, shell-quoted under a `command` key; placeholders without an
+/// argument are left as they are.
+fn substitute(value: &Value, args: &Map<String, Value>, quote: bool) -> Value {
+    match value {
+        Value::String(text) => {
+            let mut out = text.clone();
+            for (name, arg) in args {
+                let mut replacement = arg.as_str().map_or_else(|| arg.to_string(), str::to_string);
+                if quote {
+                    replacement = shell_quote(&replacement);
+                }
+                out = out
+                    .replace(&format!("{{{{{name}}}}}"), &replacement)
+                    .replace(&format!("{{{{ {name} }}}}"), &replacement);
+            }
+            Value::String(out)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute(item, args, quote))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| {
+                    let quote = quote || key == "command";
+                    (key.clone(), substitute(item, args, quote))
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[async_trait]
+impl Tool for MacroTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        let properties: Map<String, Value> = self
+            .parameters
+            .iter()
+            .map(|(name, description)| {
+                (
+                    name.clone(),
+                    json!({ "type": "string", "description": description }),
+                )
+            })
+            .collect();
+        let required: Vec<&str> = self
+            .parameters
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false
+        })
+    }
+
+    fn kind(&self) -> ToolKind {
+        let writes = self.steps.iter().any(|step| {
+            self.find_tool(&step.tool)
+                .is_some_and(|tool| tool.kind() == ToolKind::WriteLike)
+        });
+        if writes {
+            ToolKind::WriteLike
+        } else {
+            ToolKind::Other
+        }
+    }
+
+    fn inner_tools(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.steps.iter().map(|step| step.tool.clone()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn step_calls(&self, args: &Value) -> Vec<(String, Value)> {
+        let args = args.as_object().cloned().unwrap_or_default();
+        self.steps
+            .iter()
+            .map(|step| {
+                let step_args = match substitute(&step.args, &args, false) {
+                    Value::Null => Value::Object(Map::new()),
+                    other => other,
+                };
+                (step.tool.clone(), step_args)
+            })
+            .collect()
+    }
+
+    async fn execute(&self, args: Value) -> anyhow::Result<ToolResult> {
+        let arguments_hash = crate::agent::loop_::tool_arguments_hash(&self.name, &args);
+        let calls = GATED_STEPS
+            .try_with(|gated| gated.get(&arguments_hash).cloned())
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| self.step_calls(&args));
+        let total = calls.len();
+        let mut completed = Vec::with_capacity(total);
+
+        for (index, (name, step_args)) in calls.into_iter().enumerate() {
+            match self.run_step(&name, step_args).await {
+                Ok(output) => {
+                    completed.push(format!("[step {}/{total}: {name}]\n{output}", index + 1));
+                }
+                Err(reason) => {
+                    let mut error = format!("Step {}/{total} ({name}) failed: {reason}", index + 1);
+                    if !completed.is_empty() {
+                        error.push_str("\n\nCompleted steps:\n\n");
+                        error.push_str(&completed.join("\n\n"));
+                    }
+                    return Ok(ToolResult {
+                        success: false,
+                        output: String::new(),
+                        error: Some(error),
+                    });
+                }
+            }
+        }
+
+        Ok(ToolResult {
+            success: true,
+            output: completed.join("\n\n"),
+            error: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ToolPolicyConfig, ToolPolicyRules};
+    use crate::security::tool_policy::{PolicyScope, ToolPolicy};
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    struct EchoTool {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<Value>>>,
+    }
+
+    #[async_trait]
+    impl Tool for EchoTool {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "Echo the message"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": { "message": { "type": "string" } },
+                "required": ["message"]
+            })
+        }
+
+        async fn execute(&self, args: Value) -> anyhow::Result<ToolResult> {
+            self.calls.lock().unwrap().push(args.clone());
+            Ok(ToolResult {
+                success: true,
+                output: args["message"].as_str().unwrap_or_default().to_string(),
+                error: None,
+            })
+        }
+    }
+
+    fn macro_tool(calls: &Arc<Mutex<Vec<Value>>>) -> MacroTool {
+        let config = MacroToolConfig {
+            description: "Greet twice".into(),
+            parameters: HashMap::from([("who".to_string(), "Name to greet".to_string())]),
+            steps: vec![
+                MacroToolStep {
+                    tool: "echo".into(),
+                    args: json!({ "message": "hello {{who}}" }),
+                },
+                MacroToolStep {
+                    tool: "shout".into(),
+                    args: json!({ "message": "BYE {{ who }}" }),
+                },
+            ],
+        };
+        let tools: Vec<Arc<dyn Tool>> = vec![
+            Arc::new(EchoTool {
+                name: "echo",
+                calls: Arc::clone(calls),
+            }),
+            Arc::new(EchoTool {
+                name: "shout",
+                calls: Arc::clone(calls),
+            }),
+        ];
+        MacroTool::new("greet", &config, Arc::new(tools))
+    }
+
+    #[tokio::test]
+    async fn runs_steps_in_order_with_substituted_arguments() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let tool = macro_tool(&calls);
+
+        assert_eq!(tool.inner_tools(), vec!["echo", "shout"]);
+        assert_eq!(tool.parameters_schema()["required"], json!(["who"]));
+
+        let result = tool.execute(json!({ "who": "Ada" })).await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(
+            result.output,
+            "[step 1/2: echo]\nhello Ada\n\n[step 2/2: shout]\nBYE Ada"
+        );
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn shell_quotes_arguments_substituted_into_a_command() {
+        let args = json!({ "host": "x; rm -rf ~", "who": "it's me" });
+        let args = args.as_object().unwrap();
+        let step = json!({
+            "command": "rsync -a docs/ {{host}}:/srv/docs",
+            "message": "hi {{who}}"
+        });
+
+        let substituted = substitute(&step, args, false);
+        assert_eq!(
+            substituted["command"],
+            "rsync -a docs/ 'x; rm -rf ~':/srv/docs"
+        );
+        assert_eq!(substituted["message"], "hi it's me");
+
+        let quoted = substitute(&json!({ "command": "echo {{who}}" }), args, false);
+        assert_eq!(quoted["command"], r"echo 'it'\''s me'");
+    }
+
+    #[tokio::test]
+    async fn runs_the_step_calls_the_tool_loop_gated() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let tool = macro_tool(&calls);
+        let args = json!({ "who": "Ada" });
+        let hash = crate::agent::loop_::tool_arguments_hash("greet", &args);
+        let gated = HashMap::from([(
+            hash,
+            vec![("echo".to_string(), json!({ "message": "rewritten" }))],
+        )]);
+
+        let result = with_gated_steps(gated, tool.execute(args)).await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.output, "[step 1/1: echo]\nrewritten");
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stops_at_a_step_the_tool_policy_denies() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let tool = macro_tool(&calls);
+        let policy = ToolPolicy::from_config(&ToolPolicyConfig {
+            rules: ToolPolicyRules {
+                deny_tools: vec!["shout".into()],
+                ..ToolPolicyRules::default()
+            },
+            ..ToolPolicyConfig::default()
+        })
+        .unwrap();
+        let scope = PolicyScope {
+            policy: Arc::new(policy),
+            channel: "cli".into(),
+            sender: "tester".into(),
+            workspace_dir: std::env::temp_dir(),
+            approved: HashSet::new(),
+            confirmable: false,
+        };
+
+        let result = tool_policy::scope(scope, tool.execute(json!({ "who": "Ada" })))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert!(error.starts_with("Step 2/2 (shout) failed: blocked by tool policy"));
+        assert!(error.contains("[step 1/2: echo]\nhello Ada"));
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+}
@@ -17,7 +17,10 @@
 
 pub mod browser;
 pub mod browser_open;
+pub mod calc;
 pub mod cli_discovery;
+pub mod code_run;
+pub mod code_search;
 pub mod composio;
 pub mod content_search;
 pub mod cron_add;
@@ -37,22 +40,34 @@ pub mod hardware_memory_map;
 pub mod hardware_memory_read;
 pub mod http_request;
 pub mod image_info;
+pub mod list_dir;
+pub mod macro_tool;
 pub mod memory_forget;
 pub mod memory_recall;
 pub mod memory_store;
 pub mod model_routing_config;
 pub mod pdf_read;
+pub mod process;
 pub mod proxy_config;
 pub mod pushover;
 pub mod schedule;
 pub mod schema;
 pub mod screenshot;
+pub mod send_file;
 pub mod shell;
+pub mod shell_session;
+pub mod simulated;
+pub mod sql_query;
+pub mod task_env;
 pub mod traits;
+pub mod web_fetch;
 pub mod web_search_tool;
 
 pub use browser::{BrowserTool, ComputerUseConfig};
 pub use browser_open::BrowserOpenTool;
+pub use calc::CalcTool;
+pub use code_run::CodeRunTool;
+pub use code_search::CodeSearchTool;
 pub use composio::ComposioTool;
 pub use content_search::ContentSearchTool;
 pub use cron_add::CronAddTool;
@@ -72,21 +87,28 @@ pub use hardware_memory_map::HardwareMemoryMapTool;
 pub use hardware_memory_read::HardwareMemoryReadTool;
 pub use http_request::HttpRequestTool;
 pub use image_info::ImageInfoTool;
+pub use list_dir::ListDirTool;
+pub use macro_tool::MacroTool;
 pub use memory_forget::MemoryForgetTool;
 pub use memory_recall::MemoryRecallTool;
 pub use memory_store::MemoryStoreTool;
 pub use model_routing_config::ModelRoutingConfigTool;
 pub use pdf_read::PdfReadTool;
+pub use process::{ProcessLogsTool, ProcessStartTool, ProcessStatusTool, ProcessStopTool};
 pub use proxy_config::ProxyConfigTool;
 pub use pushover::PushoverTool;
 pub use schedule::ScheduleTool;
 #[allow(unused_imports)]
 pub use schema::{CleaningStrategy, SchemaCleanr};
 pub use screenshot::ScreenshotTool;
+pub use send_file::SendFileTool;
 pub use shell::ShellTool;
+pub use simulated::simulate_network_tools;
+pub use sql_query::SqlQueryTool;
 pub use traits::Tool;
 #[allow(unused_imports)]
-pub use traits::{ToolResult, ToolSpec};
+pub use traits::{ToolKind, ToolResult, ToolResultEnvelope, ToolSpec, ToolStatus};
+pub use web_fetch::WebFetchTool;
 pub use web_search_tool::WebSearchTool;
 
 use crate::config::{Config, DelegateAgentConfig};
@@ -122,6 +144,18 @@ impl Tool for ArcDelegatingTool {
         self.inner.parameters_schema()
     }
 
+    fn kind(&self) -> ToolKind {
+        self.inner.kind()
+    }
+
+    fn inner_tools(&self) -> Vec<String> {
+        self.inner.inner_tools()
+    }
+
+    fn step_calls(&self, args: &serde_json::Value) -> Vec<(String, serde_json::Value)> {
+        self.inner.step_calls(args)
+    }
+
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         self.inner.execute(args).await
     }
@@ -147,7 +181,9 @@ pub fn default_tools_with_runtime(
         Box::new(FileWriteTool::new(security.clone())),
         Box::new(FileEditTool::new(security.clone())),
         Box::new(GlobSearchTool::new(security.clone())),
-        Box::new(ContentSearchTool::new(security)),
+        Box::new(ContentSearchTool::new(security.clone())),
+        Box::new(ListDirTool::new(security.clone())),
+        Box::new(CodeSearchTool::new(security)),
     ]
 }
 
@@ -198,13 +234,24 @@ pub fn all_tools_with_runtime(
     fallback_api_key: Option<&str>,
     root_config: &crate::config::Config,
 ) -> Vec<Box<dyn Tool>> {
+    let process_dir = process::process_dir(root_config);
     let mut tool_arcs: Vec<Arc<dyn Tool>> = vec![
-        Arc::new(ShellTool::new(security.clone(), runtime)),
+        Arc::new(ShellTool::new(security.clone(), runtime.clone())),
+        Arc::new(ProcessStartTool::new(
+            security.clone(),
+            runtime,
+            &process_dir,
+        )),
+        Arc::new(ProcessStatusTool::new(&process_dir)),
+        Arc::new(ProcessLogsTool::new(&process_dir)),
+        Arc::new(ProcessStopTool::new(security.clone(), &process_dir)),
         Arc::new(FileReadTool::new(security.clone())),
         Arc::new(FileWriteTool::new(security.clone())),
         Arc::new(FileEditTool::new(security.clone())),
         Arc::new(GlobSearchTool::new(security.clone())),
         Arc::new(ContentSearchTool::new(security.clone())),
+        Arc::new(ListDirTool::new(security.clone())),
+        Arc::new(CodeSearchTool::new(security.clone())),
         Arc::new(CronAddTool::new(config.clone(), security.clone())),
         Arc::new(CronListTool::new(config.clone())),
         Arc::new(CronRemoveTool::new(config.clone(), security.clone())),
@@ -228,6 +275,8 @@ pub fn all_tools_with_runtime(
             security.clone(),
             workspace_dir.to_path_buf(),
         )),
+        Arc::new(SendFileTool::new(security.clone())),
+        Arc::new(CalcTool::new()),
     ];
 
     if browser_config.enabled {
@@ -237,24 +286,27 @@ pub fn all_tools_with_runtime(
             browser_config.allowed_domains.clone(),
         )));
         // Add full browser automation tool (pluggable backend)
-        tool_arcs.push(Arc::new(BrowserTool::new_with_backend(
-            security.clone(),
-            browser_config.allowed_domains.clone(),
-            browser_config.session_name.clone(),
-            browser_config.backend.clone(),
-            browser_config.native_headless,
-            browser_config.native_webdriver_url.clone(),
-            browser_config.native_chrome_path.clone(),
-            ComputerUseConfig {
-                endpoint: browser_config.computer_use.endpoint.clone(),
-                api_key: browser_config.computer_use.api_key.clone(),
-                timeout_ms: browser_config.computer_use.timeout_ms,
-                allow_remote_endpoint: browser_config.computer_use.allow_remote_endpoint,
-                window_allowlist: browser_config.computer_use.window_allowlist.clone(),
-                max_coordinate_x: browser_config.computer_use.max_coordinate_x,
-                max_coordinate_y: browser_config.computer_use.max_coordinate_y,
-            },
-        )));
+        tool_arcs.push(Arc::new(
+            BrowserTool::new_with_backend(
+                security.clone(),
+                browser_config.allowed_domains.clone(),
+                browser_config.session_name.clone(),
+                browser_config.backend.clone(),
+                browser_config.native_headless,
+                browser_config.native_webdriver_url.clone(),
+                browser_config.native_chrome_path.clone(),
+                ComputerUseConfig {
+                    endpoint: browser_config.computer_use.endpoint.clone(),
+                    api_key: browser_config.computer_use.api_key.clone(),
+                    timeout_ms: browser_config.computer_use.timeout_ms,
+                    allow_remote_endpoint: browser_config.computer_use.allow_remote_endpoint,
+                    window_allowlist: browser_config.computer_use.window_allowlist.clone(),
+                    max_coordinate_x: browser_config.computer_use.max_coordinate_x,
+                    max_coordinate_y: browser_config.computer_use.max_coordinate_y,
+                },
+            )
+            .with_max_actions_per_run(browser_config.max_actions_per_run),
+        ));
     }
 
     if http_config.enabled {
@@ -268,11 +320,38 @@ pub fn all_tools_with_runtime(
 
     // Web search tool (enabled by default for GLM and other models)
     if root_config.web_search.enabled {
-        tool_arcs.push(Arc::new(WebSearchTool::new(
-            root_config.web_search.provider.clone(),
-            root_config.web_search.brave_api_key.clone(),
-            root_config.web_search.max_results,
-            root_config.web_search.timeout_secs,
+        tool_arcs.push(Arc::new(
+            WebSearchTool::new(
+                root_config.web_search.provider.clone(),
+                root_config.web_search.brave_api_key.clone(),
+                root_config.web_search.max_results,
+                root_config.web_search.timeout_secs,
+            )
+            .with_searxng_url(root_config.web_search.searxng_url.clone()),
+        ));
+    }
+
+    if root_config.web_fetch.enabled {
+        tool_arcs.push(Arc::new(WebFetchTool::new(
+            security.clone(),
+            root_config.web_fetch.allowed_domains.clone(),
+            root_config.web_fetch.max_chars,
+            root_config.web_fetch.max_response_size,
+            root_config.web_fetch.timeout_secs,
+        )));
+    }
+
+    if root_config.code_run.enabled {
+        tool_arcs.push(Arc::new(CodeRunTool::new(
+            security.clone(),
+            root_config.code_run.clone(),
+        )));
+    }
+
+    if root_config.sql_query.enabled {
+        tool_arcs.push(Arc::new(SqlQueryTool::new(
+            security.clone(),
+            root_config.sql_query.clone(),
         )));
     }
 
@@ -316,6 +395,10 @@ pub fn all_tools_with_runtime(
                     .map(std::path::PathBuf::from),
                 secrets_encrypt: root_config.secrets.encrypt,
                 reasoning_enabled: root_config.runtime.reasoning_enabled,
+                max_tokens: root_config.runtime.max_tokens,
+                ollama_keep_alive: root_config.runtime.ollama_keep_alive.clone(),
+                gemini_safety_threshold: root_config.runtime.gemini_safety_threshold.clone(),
+                observer: None,
             },
         )
         .with_parent_tools(parent_tools)
@@ -323,7 +406,32 @@ pub fn all_tools_with_runtime(
         tool_arcs.push(Arc::new(delegate_tool));
     }
 
-    boxed_registry_from_arcs(tool_arcs)
+    // Macro tools call the tools registered above by name.
+    if !root_config.macro_tools.is_empty() {
+        let base_tools = Arc::new(tool_arcs.clone());
+        let mut names: Vec<&String> = root_config.macro_tools.keys().collect();
+        names.sort();
+        for name in names {
+            if base_tools.iter().any(|tool| tool.name() == name.as_str()) {
+                tracing::warn!("Skipping macro tool '{name}': a built-in tool has that name");
+                continue;
+            }
+            tool_arcs.push(Arc::new(MacroTool::new(
+                name,
+                &root_config.macro_tools[name],
+                Arc::clone(&base_tools),
+            )));
+        }
+    }
+
+    let registry = boxed_registry_from_arcs(tool_arcs);
+    if root_config.default_provider.as_deref()
+        == Some(crate::providers::simulation::SIMULATION_PROVIDER)
+    {
+        simulate_network_tools(registry)
+    } else {
+        registry
+    }
 }
 
 #[cfg(test)]
@@ -344,7 +452,7 @@ mod tests {
     fn default_tools_has_expected_count() {
         let security = Arc::new(SecurityPolicy::default());
         let tools = default_tools(security);
-        assert_eq!(tools.len(), 6);
+        assert_eq!(tools.len(), 8);
     }
 
     #[test]
@@ -385,6 +493,9 @@ mod tests {
         assert!(names.contains(&"schedule"));
         assert!(names.contains(&"model_routing_config"));
         assert!(names.contains(&"pushover"));
+        assert!(names.contains(&"send_file"));
+        assert!(names.contains(&"calc"));
+        assert!(names.contains(&"process_start"));
         assert!(names.contains(&"proxy_config"));
     }
 
@@ -426,6 +537,9 @@ mod tests {
         assert!(names.contains(&"content_search"));
         assert!(names.contains(&"model_routing_config"));
         assert!(names.contains(&"pushover"));
+        assert!(names.contains(&"send_file"));
+        assert!(names.contains(&"calc"));
+        assert!(names.contains(&"process_start"));
         assert!(names.contains(&"proxy_config"));
     }
 
@@ -440,6 +554,8 @@ mod tests {
         assert!(names.contains(&"file_edit"));
         assert!(names.contains(&"glob_search"));
         assert!(names.contains(&"content_search"));
+        assert!(names.contains(&"list_dir"));
+        assert!(names.contains(&"code_search"));
     }
 
     #[test]
@@ -467,6 +467,7 @@ impl ModelRoutingConfigTool {
             provider: provider.clone(),
             model: model.clone(),
             api_key: None,
+            temperature: None,
         });
 
         next_route.hint = hint.clone();
@@ -0,0 +1,713 @@
+//! Background processes that outlive the tool call that started them:
+//! `process_start`, `process_status`, `process_logs`, and `process_stop`.
+//!
+//! `process_start` runs a command through the same checks as `shell` and
+//! returns at once with an id. Each process leaves its record (command, PID,
+//! start time) in `state/processes/<id>.json` under the config directory,
+//! outside the workspace file tools can write to, its combined stdout and
+//! stderr in `<id>.log`, and its exit code in `<id>.exit` when it ends, so a
+//! later round, a later task, or the daemon after a restart can check on a
+//! dev server or a long build. Processes run in their own process group;
+//! `process_stop` sends SIGTERM to the group and SIGKILL if it outlives a
+//! grace period. Only processes this daemon started and has not yet reaped
+//! can be stopped: their PIDs come from memory, never from a record.
+
+use super::shell::{collect_allowed_shell_env_vars, truncate_output};
+use super::shell_session::shell_quote;
+use super::traits::{Tool, ToolResult};
+use crate::runtime::RuntimeAdapter;
+use crate::security::SecurityPolicy;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// Directory under the config directory holding the process records and logs.
+pub const PROCESS_DIR: &str = "state/processes";
+/// Log lines `process_logs` returns by default.
+const DEFAULT_LOG_LINES: usize = 100;
+/// Most log lines one `process_logs` call returns.
+const MAX_LOG_LINES: usize = 2000;
+/// Bytes of log one `process_logs` call returns at most.
+const MAX_LOG_BYTES: usize = 65_536;
+/// How long `process_stop` waits after SIGTERM before sending SIGKILL.
+const STOP_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProcessRecord {
+    id: String,
+    command: String,
+    pid: u32,
+    started_at: String,
+    #[serde(default)]
+    stopped_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProcessState {
+    Running,
+    Exited(i32),
+    Stopped,
+    /// Not running, and it left no exit code (killed by a signal, or the
+    /// machine restarted).
+    Gone,
+}
+
+impl std::fmt::Display for ProcessState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Running => write!(f, "running"),
+            Self::Exited(code) => write!(f, "exited with code {code}"),
+            Self::Stopped => write!(f, "stopped"),
+            Self::Gone => write!(f, "ended without an exit code"),
+        }
+    }
+}
+
+/// Where the process tools keep records and logs: [`PROCESS_DIR`] under the
+/// config directory, or under the workspace when there is none.
+pub fn process_dir(config: &crate::config::Config) -> PathBuf {
+    config
+        .config_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(&config.workspace_dir)
+        .join(PROCESS_DIR)
+}
+
+/// PIDs of the processes this daemon started and has not reaped yet, by id.
+/// A reaped PID can be reused by an unrelated process, so it leaves the map
+/// as soon as its process ends.
+fn spawned() -> &'static Mutex<HashMap<String, u32>> {
+    static SPAWNED: OnceLock<Mutex<HashMap<String, u32>>> = OnceLock::new();
+    SPAWNED.get_or_init(Mutex::default)
+}
+
+/// Records and logs under [`PROCESS_DIR`].
+#[derive(Debug, Clone)]
+struct ProcessStore {
+    dir: PathBuf,
+}
+
+impl ProcessStore {
+    fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn record_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    fn log_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.log"))
+    }
+
+    fn exit_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.exit"))
+    }
+
+    fn save(&self, record: &ProcessRecord) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        std::fs::write(
+            self.record_path(&record.id),
+            serde_json::to_vec_pretty(record)?,
+        )?;
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<ProcessRecord> {
+        if !is_valid_process_id(id) {
+            anyhow::bail!("Invalid process id: {id}");
+        }
+        let raw = std::fs::read(self.record_path(id))
+            .with_context(|| format!("No background process with id {id}"))?;
+        serde_json::from_slice(&raw).with_context(|| format!("Corrupt process record for {id}"))
+    }
+
+    /// All records, oldest first.
+    fn list(&self) -> Vec<ProcessRecord> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut records: Vec<ProcessRecord> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+            })
+            .collect();
+        records.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        records
+    }
+
+    fn state(&self, record: &ProcessRecord) -> ProcessState {
+        if spawned().lock().contains_key(&record.id) || is_alive(record.pid) {
+            return ProcessState::Running;
+        }
+        if record.stopped_at.is_some() {
+            return ProcessState::Stopped;
+        }
+        std::fs::read_to_string(self.exit_path(&record.id))
+            .ok()
+            .and_then(|code| code.trim().parse().ok())
+            .map_or(ProcessState::Gone, ProcessState::Exited)
+    }
+
+    fn describe(&self, record: &ProcessRecord) -> String {
+        format!(
+            "{} (pid {}): {} — started {}\n  command: {}",
+            record.id,
+            record.pid,
+            self.state(record),
+            record.started_at,
+            record.command
+        )
+    }
+}
+
+fn is_valid_process_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// `pid` as a target for `kill`, unless it is 0 or 1, which `kill` reads as
+/// the caller's own process group and every reachable process.
+#[cfg(unix)]
+fn signal_target(pid: u32) -> Option<libc::pid_t> {
+    libc::pid_t::try_from(pid).ok().filter(|pid| *pid > 1)
+}
+
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    let Some(pid) = signal_target(pid) else {
+        return false;
+    };
+    // Signal 0 only checks that the process exists.
+    unsafe { libc::kill(pid, 0) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_alive(_pid: u32) -> bool {
+    false
+}
+
+/// Send `signal` to the process group led by `pid`.
+#[cfg(unix)]
+fn signal_group(pid: u32, signal: libc::c_int) {
+    if let Some(pid) = signal_target(pid) {
+        unsafe {
+            libc::kill(-pid, signal);
+        }
+    }
+}
+
+fn failure(error: impl Into<String>) -> ToolResult {
+    ToolResult {
+        success: false,
+        output: String::new(),
+        error: Some(error.into()),
+    }
+}
+
+fn process_id_arg(args: &serde_json::Value) -> Result<&str> {
+    args.get("id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing 'id' parameter"))
+}
+
+/// Start a command in the background
+pub struct ProcessStartTool {
+    security: Arc<SecurityPolicy>,
+    runtime: Arc<dyn RuntimeAdapter>,
+    store: ProcessStore,
+}
+
+impl ProcessStartTool {
+    pub fn new(
+        security: Arc<SecurityPolicy>,
+        runtime: Arc<dyn RuntimeAdapter>,
+        process_dir: &Path,
+    ) -> Self {
+        Self {
+            security,
+            runtime,
+            store: ProcessStore::new(process_dir),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ProcessStartTool {
+    fn name(&self) -> &str {
+        "process_start"
+    }
+
+    fn description(&self) -> &str {
+        "Start a long-running shell command (dev server, long build, watcher) in the background and return its id at once. Check it later with process_status and process_logs, even from a later task; end it with process_stop"
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "The shell command to run in the background"
+                },
+                "approved": {
+                    "type": "boolean",
+                    "description": "Set true to explicitly approve medium/high-risk commands in supervised mode",
+                    "default": false
+                }
+            },
+            "required": ["command"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let command = args
+            .get("command")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'command' parameter"))?;
+        let approved = args
+            .get("approved")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        if !cfg!(unix) || self.runtime.name() != "native" {
+            return Ok(failure(
+                "Background processes need the native runtime on a Unix host",
+            ));
+        }
+
+        if self.security.is_rate_limited() {
+            return Ok(failure(
+                "Rate limit exceeded: too many actions in the last hour",
+            ));
+        }
+
+        if let Err(reason) = self.security.validate_command_execution(command, approved) {
+            return Ok(failure(reason));
+        }
+
+        if let Some(path) = self.security.forbidden_path_argument(command) {
+            return Ok(failure(format!("Path blocked by security policy: {path}")));
+        }
+
+        if !self.security.record_action() {
+            return Ok(failure("Rate limit exceeded: action budget exhausted"));
+        }
+
+        let store = &self.store;
+        let id = format!("proc-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        std::fs::create_dir_all(&store.dir)
+            .with_context(|| format!("Failed to create {}", store.dir.display()))?;
+
+        // The exit trap records the exit code for status checks that come
+        // after this daemon stopped waiting on the process.
+        let session = super::shell_session::current();
+        let mut script = format!(
+            "__zc_exit_file={}\ntrap 'printf \"%s\\n\" \"$?\" > \"$__zc_exit_file\"' EXIT\n",
+            shell_quote(&store.exit_path(&id).to_string_lossy())
+        );
+        if let Some(cwd) = session.as_ref().and_then(|session| session.cwd()) {
+            script.push_str(&format!(
+                "cd {} || exit 1\n",
+                shell_quote(&cwd.to_string_lossy())
+            ));
+        }
+        script.push_str(command);
+        script.push('\n');
+
+        let mut cmd = match self
+            .runtime
+            .build_shell_command(&script, &self.security.workspace_dir)
+        {
+            Ok(cmd) => cmd,
+            Err(e) => return Ok(failure(format!("Failed to build runtime command: {e}"))),
+        };
+        // Same environment as `shell`: the sanitized baseline, then the
+        // task's variables and the session's exports.
+        cmd.env_clear();
+        for var in collect_allowed_shell_env_vars(&self.security) {
+            if let Ok(val) = std::env::var(&var) {
+                cmd.env(&var, val);
+            }
+        }
+        super::task_env::apply(&mut cmd);
+        if let Some(session) = &session {
+            session.apply(&mut cmd);
+        }
+
+        let log = std::fs::File::create(store.log_path(&id))
+            .with_context(|| format!("Failed to create log for {id}"))?;
+        cmd.stdin(std::process::Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => return Ok(failure(format!("Failed to start command: {e}"))),
+        };
+        let Some(pid) = child.id() else {
+            return Ok(failure("Command exited before its PID could be read"));
+        };
+        spawned().lock().insert(id.clone(), pid);
+        // Reap the child when it ends so it does not linger as a zombie
+        // that still looks alive, and forget its PID before it can be reused.
+        let reaped_id = id.clone();
+        tokio::spawn(async move {
+            let _ = child.wait().await;
+            spawned().lock().remove(&reaped_id);
+        });
+
+        let record = ProcessRecord {
+            id: id.clone(),
+            command: command.to_string(),
+            pid,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            stopped_at: None,
+        };
+        store.save(&record)?;
+
+        Ok(ToolResult {
+            success: true,
+            output: format!(
+                "Started {id} (pid {pid}) in the background; use process_status or process_logs with id \"{id}\" to check on it"
+            ),
+            error: None,
+        })
+    }
+}
+
+/// Report the state of background processes
+pub struct ProcessStatusTool {
+    store: ProcessStore,
+}
+
+impl ProcessStatusTool {
+    pub fn new(process_dir: &Path) -> Self {
+        Self {
+            store: ProcessStore::new(process_dir),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ProcessStatusTool {
+    fn name(&self) -> &str {
+        "process_status"
+    }
+
+    fn description(&self) -> &str {
+        "Show whether a background process started with process_start is still running or how it ended; without an id, list all known background processes"
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "description": "Process id returned by process_start; omit to list all"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let store = &self.store;
+        let output = match args.get("id").and_then(|v| v.as_str()) {
+            Some(id) => match store.load(id) {
+                Ok(record) => store.describe(&record),
+                Err(e) => return Ok(failure(e.to_string())),
+            },
+            None => {
+                let records = store.list();
+                if records.is_empty() {
+                    "No background processes".to_string()
+                } else {
+                    records
+                        .iter()
+                        .map(|record| store.describe(record))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+        };
+        Ok(ToolResult {
+            success: true,
+            output,
+            error: None,
+        })
+    }
+}
+
+/// Read the end of a background process's output
+pub struct ProcessLogsTool {
+    store: ProcessStore,
+}
+
+impl ProcessLogsTool {
+    pub fn new(process_dir: &Path) -> Self {
+        Self {
+            store: ProcessStore::new(process_dir),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ProcessLogsTool {
+    fn name(&self) -> &str {
+        "process_logs"
+    }
+
+    fn description(&self) -> &str {
+        "Read the last lines of a background process's combined stdout and stderr"
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "description": "Process id returned by process_start"
+                },
+                "lines": {
+                    "type": "integer",
+                    "description": "Number of lines from the end of the log (default 100, max 2000)",
+                    "minimum": 1
+                }
+            },
+            "required": ["id"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let id = process_id_arg(&args)?;
+        let lines = args
+            .get("lines")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_LOG_LINES, |n| {
+                usize::try_from(n).unwrap_or(MAX_LOG_LINES)
+            })
+            .clamp(1, MAX_LOG_LINES);
+
+        let store = &self.store;
+        let record = match store.load(id) {
+            Ok(record) => record,
+            Err(e) => return Ok(failure(e.to_string())),
+        };
+        let raw = match tokio::fs::read(store.log_path(id)).await {
+            Ok(raw) => raw,
+            Err(e) => return Ok(failure(format!("Failed to read log for {id}: {e}"))),
+        };
+        let text = String::from_utf8_lossy(&raw);
+        let all: Vec<&str> = text.lines().collect();
+        let mut tail = all[all.len().saturating_sub(lines)..].join("\n");
+        truncate_output(&mut tail, MAX_LOG_BYTES, "log");
+
+        Ok(ToolResult {
+            success: true,
+            output: format!(
+                "{id} is {}; last {} of {} log lines:\n{tail}",
+                store.state(&record),
+                lines.min(all.len()),
+                all.len()
+            ),
+            error: None,
+        })
+    }
+}
+
+/// Stop a background process
+pub struct ProcessStopTool {
+    security: Arc<SecurityPolicy>,
+    store: ProcessStore,
+}
+
+impl ProcessStopTool {
+    pub fn new(security: Arc<SecurityPolicy>, process_dir: &Path) -> Self {
+        Self {
+            security,
+            store: ProcessStore::new(process_dir),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ProcessStopTool {
+    fn name(&self) -> &str {
+        "process_stop"
+    }
+
+    fn description(&self) -> &str {
+        "Stop a background process started with process_start (SIGTERM, then SIGKILL if it does not exit within 5 seconds)"
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "description": "Process id returned by process_start"
+                }
+            },
+            "required": ["id"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let id = process_id_arg(&args)?;
+        let store = &self.store;
+        let mut record = match store.load(id) {
+            Ok(record) => record,
+            Err(e) => return Ok(failure(e.to_string())),
+        };
+
+        // Signal only the PID this daemon spawned, never the recorded one.
+        let Some(pid) = spawned().lock().get(id).copied() else {
+            let state = store.state(&record);
+            if state == ProcessState::Running {
+                return Ok(failure(format!(
+                    "{id} was not started by this daemon run, so it cannot be stopped here; stop pid {} by hand if it is still yours",
+                    record.pid
+                )));
+            }
+            return Ok(ToolResult {
+                success: true,
+                output: format!("{id} is not running ({state})"),
+                error: None,
+            });
+        };
+
+        if !self.security.record_action() {
+            return Ok(failure("Rate limit exceeded: action budget exhausted"));
+        }
+
+        #[cfg(unix)]
+        {
+            let running = || spawned().lock().contains_key(id);
+            signal_group(pid, libc::SIGTERM);
+            let deadline = tokio::time::Instant::now() + STOP_GRACE;
+            while running() && tokio::time::Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            if running() {
+                signal_group(pid, libc::SIGKILL);
+            }
+        }
+
+        record.stopped_at = Some(chrono::Utc::now().to_rfc3339());
+        store.save(&record)?;
+        Ok(ToolResult {
+            success: true,
+            output: format!("Stopped {id} (pid {pid})"),
+            error: None,
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::runtime::NativeRuntime;
+    use crate::security::AutonomyLevel;
+
+    #[tokio::test]
+    async fn starts_checks_and_stops_a_background_process() {
+        let workspace = tempfile::tempdir().unwrap();
+        let security = Arc::new(SecurityPolicy {
+            autonomy: AutonomyLevel::Full,
+            workspace_dir: workspace.path().to_path_buf(),
+            allowed_commands: vec!["echo".into(), "sleep".into()],
+            ..SecurityPolicy::default()
+        });
+        let process_dir = workspace.path().join(PROCESS_DIR);
+        let start = ProcessStartTool::new(
+            security.clone(),
+            Arc::new(NativeRuntime::new()),
+            &process_dir,
+        );
+        let status = ProcessStatusTool::new(&process_dir);
+        let logs = ProcessLogsTool::new(&process_dir);
+        let stop = ProcessStopTool::new(security.clone(), &process_dir);
+
+        let started = start
+            .execute(json!({"command": "echo warming up && sleep 30"}))
+            .await
+            .unwrap();
+        assert!(started.success, "{:?}", started.error);
+        let store = ProcessStore::new(&process_dir);
+        let id = store.list()[0].id.clone();
+
+        let report = status.execute(json!({"id": id})).await.unwrap();
+        assert!(report.output.contains("running"), "{}", report.output);
+
+        let mut output = String::new();
+        for _ in 0..50 {
+            output = logs.execute(json!({"id": id})).await.unwrap().output;
+            if output.contains("warming up") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(output.contains("warming up"), "{output}");
+
+        let stopped = stop.execute(json!({"id": id})).await.unwrap();
+        assert!(stopped.success, "{:?}", stopped.error);
+        let report = status.execute(json!({"id": id})).await.unwrap();
+        assert!(report.output.contains("stopped"), "{}", report.output);
+
+        let traversal = logs.execute(json!({"id": "../secrets"})).await.unwrap();
+        assert!(!traversal.success);
+    }
+
+    #[tokio::test]
+    async fn stop_never_signals_a_pid_read_from_a_record() {
+        let workspace = tempfile::tempdir().unwrap();
+        let security = Arc::new(SecurityPolicy {
+            autonomy: AutonomyLevel::Full,
+            workspace_dir: workspace.path().to_path_buf(),
+            ..SecurityPolicy::default()
+        });
+        let store = ProcessStore::new(workspace.path());
+        for (id, pid) in [("proc-forged1", 1), ("proc-forged0", 0)] {
+            store
+                .save(&ProcessRecord {
+                    id: id.into(),
+                    command: "sleep 30".into(),
+                    pid,
+                    started_at: chrono::Utc::now().to_rfc3339(),
+                    stopped_at: None,
+                })
+                .unwrap();
+        }
+        assert_eq!(signal_target(0), None);
+        assert_eq!(signal_target(1), None);
+        assert!(!is_alive(1));
+
+        let stop = ProcessStopTool::new(security, workspace.path());
+        for id in ["proc-forged1", "proc-forged0"] {
+            let result = stop.execute(json!({"id": id})).await.unwrap();
+            assert!(result.output.contains("not running"), "{}", result.output);
+            assert!(store.load(id).unwrap().stopped_at.is_none());
+        }
+    }
+}
@@ -147,6 +147,23 @@ impl SchemaCleanr {
         Self::clean_with_defs(schema, &defs, strategy, &mut HashSet::new())
     }
 
+    /// Clean a tool's parameter schema with `strategy` and make the top level
+    /// an object schema with `properties`, which function-calling APIs
+    /// require even for tools that take no arguments.
+    pub fn function_parameters(schema: Value, strategy: CleaningStrategy) -> Value {
+        let mut cleaned = Self::clean(schema, strategy);
+        if !cleaned.is_object() {
+            cleaned = json!({});
+        }
+        if let Some(obj) = cleaned.as_object_mut() {
+            let object_type = obj.entry("type").or_insert_with(|| json!("object")) == "object";
+            if object_type {
+                obj.entry("properties").or_insert_with(|| json!({}));
+            }
+        }
+        cleaned
+    }
+
     /// Validate that a schema is suitable for LLM tool calling.
     ///
     /// Returns an error if the schema is invalid or missing required fields.
@@ -703,6 +720,22 @@ mod tests {
         assert!(SchemaCleanr::validate(&invalid).is_err());
     }
 
+    #[test]
+    fn test_function_parameters_returns_object_schema() {
+        let empty = SchemaCleanr::function_parameters(json!({}), CleaningStrategy::OpenAI);
+        assert_eq!(empty, json!({"type": "object", "properties": {}}));
+
+        let with_ref = SchemaCleanr::function_parameters(
+            json!({
+                "type": "object",
+                "properties": { "path": { "$ref": "#/$defs/Path" } },
+                "$defs": { "Path": { "type": "string" } }
+            }),
+            CleaningStrategy::OpenAI,
+        );
+        assert_eq!(with_ref["properties"]["path"]["type"], "string");
+    }
+
     #[test]
     fn test_strategy_differences() {
         let schema = json!({
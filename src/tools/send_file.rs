@@ -0,0 +1,257 @@
+//! Send a workspace file back to the conversation a task came from.
+//!
+//! The channel runtime runs each reply inside [`scope`] with the channel and
+//! reply target of the inbound message. Inside it, `send_file` uploads the
+//! file there (an iMessage attachment, a Slack upload, a Telegram document);
+//! outside a channel conversation the tool reports that there is nowhere to
+//! send to. The task engine records each successful transfer as an
+//! `artifact_sent` event.
+
+use super::traits::{Tool, ToolKind, ToolResult};
+use crate::channels::traits::Channel;
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use serde_json::json;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Largest file the tool will upload; most channels reject bigger ones.
+const MAX_FILE_SIZE_BYTES: u64 = 50 * 1024 * 1024;
+
+tokio::task_local! {
+    static ROUTE: Option<ReplyRoute>;
+}
+
+/// Where files sent during the current reply go.
+#[derive(Clone)]
+pub struct ReplyRoute {
+    pub channel: Arc<dyn Channel>,
+    pub recipient: String,
+    pub thread_ts: Option<String>,
+}
+
+/// Run `future` with `send_file` delivering to `route`; `None` leaves the
+/// tool without a destination.
+pub async fn scope<F: Future>(route: Option<ReplyRoute>, future: F) -> F::Output {
+    ROUTE.scope(route, future).await
+}
+
+/// Route of the surrounding channel conversation, if any.
+pub fn current() -> Option<ReplyRoute> {
+    ROUTE.try_with(Clone::clone).ok().flatten()
+}
+
+/// Upload a workspace file to the originating channel
+pub struct SendFileTool {
+    security: Arc<SecurityPolicy>,
+}
+
+impl SendFileTool {
+    pub fn new(security: Arc<SecurityPolicy>) -> Self {
+        Self { security }
+    }
+}
+
+fn failure(error: impl Into<String>) -> ToolResult {
+    ToolResult {
+        success: false,
+        output: String::new(),
+        error: Some(error.into()),
+    }
+}
+
+#[async_trait]
+impl Tool for SendFileTool {
+    fn name(&self) -> &str {
+        "send_file"
+    }
+
+    fn description(&self) -> &str {
+        "Send a workspace file to the user in the conversation this request came from (iMessage attachment, Slack upload, Telegram document). Use it to deliver reports, exports, or other files the task produced."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to the file. Relative paths resolve from workspace; outside paths require policy allowlist."
+                },
+                "caption": {
+                    "type": "string",
+                    "description": "Optional short message sent with the file"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn kind(&self) -> ToolKind {
+        ToolKind::Network
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let path = args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'path' parameter"))?;
+        let caption = args
+            .get("caption")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|caption| !caption.is_empty());
+
+        let Some(route) = current() else {
+            return Ok(failure(
+                "No channel conversation to send the file to; send_file only works when replying on a channel",
+            ));
+        };
+
+        if self.security.is_rate_limited() {
+            return Ok(failure(
+                "Rate limit exceeded: too many actions in the last hour",
+            ));
+        }
+
+        if !self.security.is_path_allowed(path) {
+            return Ok(failure(format!(
+                "Path not allowed by security policy: {path}"
+            )));
+        }
+
+        if !self.security.record_action() {
+            return Ok(failure("Rate limit exceeded: action budget exhausted"));
+        }
+
+        let full_path = self.security.workspace_dir.join(path);
+        let resolved_path = match tokio::fs::canonicalize(&full_path).await {
+            Ok(p) => p,
+            Err(e) => return Ok(failure(format!("Failed to resolve file path: {e}"))),
+        };
+
+        if !self.security.is_resolved_path_allowed(&resolved_path) {
+            return Ok(failure(
+                self.security
+                    .resolved_path_violation_message(&resolved_path),
+            ));
+        }
+
+        let size = match tokio::fs::metadata(&resolved_path).await {
+            Ok(meta) if !meta.is_file() => {
+                return Ok(failure(format!("Not a regular file: {path}")));
+            }
+            Ok(meta) if meta.len() > MAX_FILE_SIZE_BYTES => {
+                return Ok(failure(format!(
+                    "File too large to send: {} bytes (limit: {MAX_FILE_SIZE_BYTES} bytes)",
+                    meta.len()
+                )));
+            }
+            Ok(meta) => meta.len(),
+            Err(e) => return Ok(failure(format!("Failed to read file metadata: {e}"))),
+        };
+
+        match route
+            .channel
+            .send_file(
+                &route.recipient,
+                route.thread_ts.as_deref(),
+                &resolved_path,
+                caption,
+            )
+            .await
+        {
+            Ok(()) => Ok(ToolResult {
+                success: true,
+                output: format!(
+                    "Sent {path} ({size} bytes) to the {} conversation",
+                    route.channel.name()
+                ),
+                error: None,
+            }),
+            Err(e) => Ok(failure(format!("Failed to send {path}: {e}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::traits::{ChannelMessage, SendMessage};
+    use crate::security::AutonomyLevel;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<(String, PathBuf, Option<String>)>>,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn send(&self, _message: &SendMessage) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn send_file(
+            &self,
+            recipient: &str,
+            _thread_ts: Option<&str>,
+            path: &Path,
+            caption: Option<&str>,
+        ) -> anyhow::Result<()> {
+            self.sent.lock().unwrap().push((
+                recipient.to_string(),
+                path.to_path_buf(),
+                caption.map(str::to_string),
+            ));
+            Ok(())
+        }
+    }
+
+    fn test_security(workspace: PathBuf) -> Arc<SecurityPolicy> {
+        Arc::new(SecurityPolicy {
+            autonomy: AutonomyLevel::Supervised,
+            workspace_dir: workspace,
+            ..SecurityPolicy::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn sends_workspace_file_to_the_reply_route_only_inside_a_scope() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("report.md"), "# Report").unwrap();
+        let tool = SendFileTool::new(test_security(dir.path().to_path_buf()));
+        let args = json!({"path": "report.md", "caption": "Here you go"});
+
+        let outside = tool.execute(args.clone()).await.unwrap();
+        assert!(!outside.success);
+
+        let channel = Arc::new(RecordingChannel::default());
+        let route = Some(ReplyRoute {
+            channel: channel.clone(),
+            recipient: "alice".into(),
+            thread_ts: None,
+        });
+        let result = scope(route, tool.execute(args)).await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.output.contains("8 bytes"));
+
+        let sent = channel.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, "alice");
+        assert!(sent[0].1.ends_with("report.md"));
+        assert_eq!(sent[0].2.as_deref(), Some("Here you go"));
+    }
+}
@@ -3,13 +3,15 @@ use crate::runtime::RuntimeAdapter;
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
-/// Maximum shell command execution time before kill.
+/// Default shell command execution time before kill.
 const SHELL_TIMEOUT_SECS: u64 = 60;
-/// Maximum output size in bytes (1MB).
+/// Longest `timeout_secs` a call may ask for.
+const MAX_SHELL_TIMEOUT_SECS: u64 = 600;
+/// Maximum output size in bytes (1MB), per stream.
 const MAX_OUTPUT_BYTES: usize = 1_048_576;
 /// Environment variables safe to pass to shell commands.
 /// Only functional variables are included — never API keys or secrets.
@@ -29,7 +31,7 @@ impl ShellTool {
     }
 }
 
-fn is_valid_env_var_name(name: &str) -> bool {
+pub(super) fn is_valid_env_var_name(name: &str) -> bool {
     let mut chars = name.chars();
     match chars.next() {
         Some(first) if first.is_ascii_alphabetic() || first == '_' => {}
@@ -38,7 +40,7 @@ fn is_valid_env_var_name(name: &str) -> bool {
     chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
 }
 
-fn collect_allowed_shell_env_vars(security: &SecurityPolicy) -> Vec<String> {
+pub(super) fn collect_allowed_shell_env_vars(security: &SecurityPolicy) -> Vec<String> {
     let mut out = Vec::new();
     let mut seen = HashSet::new();
     for key in SAFE_ENV_VARS
@@ -57,6 +59,42 @@ fn collect_allowed_shell_env_vars(security: &SecurityPolicy) -> Vec<String> {
     out
 }
 
+/// Cut `text` to at most `limit` bytes, keeping its start and end: the end
+/// of a build log usually holds the error.
+pub(crate) fn truncate_output(text: &mut String, limit: usize, stream: &str) {
+    if text.len() <= limit {
+        return;
+    }
+    let mut head_end = limit / 2;
+    while !text.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = text.len() - (limit - limit / 2);
+    while !text.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    let omitted = tail_start - head_end;
+    *text = format!(
+        "{}\n... [{omitted} bytes of {stream} omitted] ...\n{}",
+        &text[..head_end],
+        &text[tail_start..]
+    );
+}
+
+/// Error text for a command that ran and failed: exit code, then its output.
+fn failure_report(status: std::process::ExitStatus, stdout: &str, stderr: &str) -> String {
+    let mut report = match status.code() {
+        Some(code) => format!("Command exited with code {code}"),
+        None => "Command was terminated by a signal".to_string(),
+    };
+    for (stream, text) in [("stdout", stdout), ("stderr", stderr)] {
+        if !text.trim().is_empty() {
+            report.push_str(&format!("\n{stream}:\n{}", text.trim_end()));
+        }
+    }
+    report
+}
+
 #[async_trait]
 impl Tool for ShellTool {
     fn name(&self) -> &str {
@@ -64,7 +102,7 @@ impl Tool for ShellTool {
     }
 
     fn description(&self) -> &str {
-        "Execute a shell command in the workspace directory"
+        "Execute a shell command in the workspace directory. Within a task, the working directory and exported variables carry over to the next call"
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -79,6 +117,16 @@ impl Tool for ShellTool {
                     "type": "boolean",
                     "description": "Set true to explicitly approve medium/high-risk commands in supervised mode",
                     "default": false
+                },
+                "timeout_secs": {
+                    "type": "integer",
+                    "description": "Seconds before the command is killed (default 60, max 600)",
+                    "minimum": 1
+                },
+                "max_output_bytes": {
+                    "type": "integer",
+                    "description": "Bytes of stdout and of stderr to return; longer output keeps its start and end (default and max 1048576)",
+                    "minimum": 1
                 }
             },
             "required": ["command"]
@@ -94,6 +142,18 @@ impl Tool for ShellTool {
             .get("approved")
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
+        let timeout_secs = args
+            .get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(SHELL_TIMEOUT_SECS)
+            .clamp(1, MAX_SHELL_TIMEOUT_SECS);
+        let max_output_bytes = args
+            .get("max_output_bytes")
+            .and_then(|v| v.as_u64())
+            .map_or(MAX_OUTPUT_BYTES, |n| {
+                usize::try_from(n).unwrap_or(usize::MAX)
+            })
+            .clamp(1, MAX_OUTPUT_BYTES);
 
         if self.security.is_rate_limited() {
             return Ok(ToolResult {
@@ -130,12 +190,22 @@ impl Tool for ShellTool {
             });
         }
 
+        // Inside a task the command resumes the session's directory and
+        // reports where it ended behind a marker only this call knows. Docker
+        // paths and variables are the container's, so it stays one-shot.
+        let session = super::shell_session::current().filter(|_| self.runtime.name() == "native");
+        let marker = format!("__ZEROCLAW_SHELL_STATE_{}__", uuid::Uuid::new_v4().simple());
+        let script = match &session {
+            Some(session) => super::shell_session::wrap(command, session.cwd().as_deref(), &marker),
+            None => command.to_string(),
+        };
+
         // Execute with timeout to prevent hanging commands.
         // Clear the environment to prevent leaking API keys and other secrets
         // (CWE-200), then re-add only safe, functional variables.
         let mut cmd = match self
             .runtime
-            .build_shell_command(command, &self.security.workspace_dir)
+            .build_shell_command(&script, &self.security.workspace_dir)
         {
             Ok(cmd) => cmd,
             Err(e) => {
@@ -153,27 +223,55 @@ impl Tool for ShellTool {
                 cmd.env(&var, val);
             }
         }
+        // Per-task variables (`autonomy.task_env`) are added last so a task
+        // can point tools at its own PATH or endpoints.
+        super::task_env::apply(&mut cmd);
+        if let Some(session) = &session {
+            session.apply(&mut cmd);
+        }
+        let sent: BTreeMap<String, String> = cmd
+            .as_std()
+            .get_envs()
+            .filter_map(|(key, value)| {
+                Some((key.to_str()?.to_string(), value?.to_str()?.to_string()))
+            })
+            .collect();
+        cmd.kill_on_drop(true);
 
-        let result =
-            tokio::time::timeout(Duration::from_secs(SHELL_TIMEOUT_SECS), cmd.output()).await;
+        let result = tokio::time::timeout(Duration::from_secs(timeout_secs), cmd.output()).await;
 
         match result {
             Ok(Ok(output)) => {
                 let mut stdout = String::from_utf8_lossy(&output.stdout).to_string();
                 let mut stderr = String::from_utf8_lossy(&output.stderr).to_string();
 
-                // Truncate output to prevent OOM
-                if stdout.len() > MAX_OUTPUT_BYTES {
-                    stdout.truncate(stdout.floor_char_boundary(MAX_OUTPUT_BYTES));
-                    stdout.push_str("\n... [output truncated at 1MB]");
+                if let Some(session) = &session {
+                    let (visible, report) = super::shell_session::split_report(&stdout, &marker);
+                    if let Some(report) = report {
+                        // Only a directory inside the workspace is kept;
+                        // anything else sends the next call back to the root.
+                        let cwd = std::fs::canonicalize(&report.cwd).unwrap_or(report.cwd);
+                        let cwd = self.security.is_resolved_path_allowed(&cwd).then_some(cwd);
+                        session.update(cwd, &sent, &report.env);
+                    }
+                    stdout = visible;
                 }
-                if stderr.len() > MAX_OUTPUT_BYTES {
-                    stderr.truncate(stderr.floor_char_boundary(MAX_OUTPUT_BYTES));
-                    stderr.push_str("\n... [stderr truncated at 1MB]");
+
+                // Truncate output to prevent OOM
+                truncate_output(&mut stdout, max_output_bytes, "stdout");
+                truncate_output(&mut stderr, max_output_bytes, "stderr");
+
+                if !output.status.success() {
+                    let error = failure_report(output.status, &stdout, &stderr);
+                    return Ok(ToolResult {
+                        success: false,
+                        output: stdout,
+                        error: Some(error),
+                    });
                 }
 
                 Ok(ToolResult {
-                    success: output.status.success(),
+                    success: true,
                     output: stdout,
                     error: if stderr.is_empty() {
                         None
@@ -191,7 +289,7 @@ impl Tool for ShellTool {
                 success: false,
                 output: String::new(),
                 error: Some(format!(
-                    "Command timed out after {SHELL_TIMEOUT_SECS}s and was killed"
+                    "Command timed out after {timeout_secs}s and was killed"
                 )),
             }),
         }
@@ -302,6 +400,64 @@ mod tests {
             .await
             .expect("command with nonexistent path should return a result");
         assert!(!result.success);
+        assert!(result
+            .error
+            .as_deref()
+            .unwrap_or("")
+            .starts_with("Command exited with code "));
+    }
+
+    #[tokio::test]
+    async fn task_session_keeps_cwd_and_exports_between_calls() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workspace.path().join("app")).unwrap();
+        let security = Arc::new(SecurityPolicy {
+            autonomy: AutonomyLevel::Full,
+            workspace_dir: workspace.path().to_path_buf(),
+            allowed_commands: vec!["cd".into(), "export".into(), "pwd".into(), "env".into()],
+            ..SecurityPolicy::default()
+        });
+        let tool = ShellTool::new(security, test_runtime());
+
+        let (pwd, env) = crate::tools::shell_session::scope(Arc::default(), async {
+            for command in ["cd app", "export BUILD_MODE=release"] {
+                let result = tool.execute(json!({ "command": command })).await.unwrap();
+                assert!(result.success, "{command}: {:?}", result.error);
+                assert!(!result.output.contains("__ZEROCLAW_SHELL_STATE_"));
+            }
+            let pwd = tool.execute(json!({"command": "pwd"})).await.unwrap();
+            let env = tool.execute(json!({"command": "env"})).await.unwrap();
+            (pwd.output, env.output)
+        })
+        .await;
+        assert!(pwd.trim().ends_with("/app"), "{pwd}");
+        assert!(env.contains("BUILD_MODE=release"));
+
+        // Outside a task every call starts over in the workspace root.
+        tool.execute(json!({"command": "cd app"})).await.unwrap();
+        let pwd = tool.execute(json!({"command": "pwd"})).await.unwrap();
+        assert!(!pwd.output.trim().ends_with("/app"));
+    }
+
+    #[tokio::test]
+    async fn shell_honors_per_call_timeout() {
+        let security = Arc::new(SecurityPolicy {
+            autonomy: AutonomyLevel::Full,
+            workspace_dir: std::env::temp_dir(),
+            allowed_commands: vec!["sleep".into()],
+            ..SecurityPolicy::default()
+        });
+        let tool = ShellTool::new(security, test_runtime());
+        let result = tool
+            .execute(json!({"command": "sleep 5", "timeout_secs": 1}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result
+            .error
+            .as_deref()
+            .unwrap_or("")
+            .contains("timed out after 1s"));
     }
 
     #[tokio::test]
@@ -562,6 +718,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn truncated_output_keeps_start_and_end() {
+        let mut text = format!("start{}end", "x".repeat(100));
+        truncate_output(&mut text, 20, "stdout");
+        assert!(text.starts_with("start"));
+        assert!(text.ends_with("end"));
+        assert!(text.contains("[88 bytes of stdout omitted]"));
+
+        let mut short = "ok".to_string();
+        truncate_output(&mut short, 20, "stdout");
+        assert_eq!(short, "ok");
+    }
+
     // ── §5.3 Non-UTF8 binary output tests ────────────────────
 
     #[test]
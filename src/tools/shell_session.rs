@@ -0,0 +1,265 @@
+//! Shell state carried between `shell` calls of one task run.
+//!
+//! The task engine runs each task inside [`scope`] with an empty
+//! [`ShellSession`]. Inside it, every `shell` command starts in the directory
+//! the previous one ended in and sees the variables earlier commands exported
+//! or unset, so a multi-step build can `cd` and `export` once. The command is
+//! wrapped in a script whose exit trap prints a marker followed by `pwd` and
+//! `env`; [`split_report`] strips that from the output. Outside a task every
+//! command is one-shot in the workspace root.
+//!
+//! Variables that change which program runs or what a shell executes on
+//! start (`PATH`, loader and interpreter-startup variables) are never
+//! carried over, so an `export PATH=$PWD/bin:$PATH` cannot make a later
+//! allow-listed command run a workspace binary.
+
+use super::shell::is_valid_env_var_name;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Variables the shell maintains itself; never carried between calls.
+const SHELL_MANAGED_VARS: &[&str] = &["PWD", "OLDPWD", "SHLVL", "_"];
+
+/// Variables that pick the binary a command runs or code a shell runs on
+/// start; a command's changes to them are dropped.
+const PROTECTED_VARS: &[&str] = &[
+    "PATH",
+    "BASH_ENV",
+    "ENV",
+    "IFS",
+    "PROMPT_COMMAND",
+    "SHELLOPTS",
+    "BASHOPTS",
+    "CDPATH",
+    "GLOBIGNORE",
+    "PS4",
+];
+
+/// Prefixes of dynamic-loader variables (`LD_PRELOAD`, `DYLD_INSERT_LIBRARIES`, …).
+const PROTECTED_VAR_PREFIXES: &[&str] = &["LD_", "DYLD_"];
+
+/// Whether a command's change to `key` may be carried into later commands.
+fn may_persist(key: &str) -> bool {
+    is_valid_env_var_name(key)
+        && !SHELL_MANAGED_VARS.contains(&key)
+        && !PROTECTED_VARS.contains(&key)
+        && !PROTECTED_VAR_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+        // Exported bash functions run in every later bash.
+        && !key.starts_with("BASH_FUNC_")
+}
+
+tokio::task_local! {
+    static SESSION: Arc<ShellSession>;
+}
+
+#[derive(Debug, Default)]
+pub struct ShellSession {
+    state: Mutex<SessionState>,
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    cwd: Option<PathBuf>,
+    /// `None` marks a variable a command unset.
+    env: BTreeMap<String, Option<String>>,
+}
+
+/// Directory and exported variables a wrapped command ended with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateReport {
+    pub cwd: PathBuf,
+    pub env: BTreeMap<String, String>,
+}
+
+/// Run `future` with its `shell` calls sharing `session`.
+pub async fn scope<F: Future>(session: Arc<ShellSession>, future: F) -> F::Output {
+    SESSION.scope(session, future).await
+}
+
+/// Session of the surrounding task, if any.
+pub fn current() -> Option<Arc<ShellSession>> {
+    SESSION.try_with(Arc::clone).ok()
+}
+
+impl ShellSession {
+    /// Directory the next command starts in; `None` means the workspace root.
+    pub fn cwd(&self) -> Option<PathBuf> {
+        self.state().cwd.clone()
+    }
+
+    /// Add the session's variables to `command`, after the baseline.
+    pub fn apply(&self, command: &mut tokio::process::Command) {
+        for (key, value) in &self.state().env {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+    }
+
+    /// Record where a command ended. `cwd` is `None` when the reported
+    /// directory may not be kept. Variables are compared with `sent`, the
+    /// environment the command started with, so only its own changes stick;
+    /// an empty report (`env` itself failed) leaves them as they were.
+    /// Changes to `PATH`, loader, and shell-startup variables are dropped.
+    pub fn update(
+        &self,
+        cwd: Option<PathBuf>,
+        sent: &BTreeMap<String, String>,
+        reported: &BTreeMap<String, String>,
+    ) {
+        let mut state = self.state();
+        state.cwd = cwd;
+        if reported.is_empty() {
+            return;
+        }
+        for (key, value) in reported {
+            if may_persist(key) && sent.get(key) != Some(value) {
+                state.env.insert(key.clone(), Some(value.clone()));
+            }
+        }
+        for key in sent.keys() {
+            if may_persist(key) && !reported.contains_key(key) {
+                state.env.insert(key.clone(), None);
+            }
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SessionState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+pub(super) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// `command` as a script that starts in `cwd` and reports its final state
+/// after `marker` on stdout, keeping the command's exit status.
+pub fn wrap(command: &str, cwd: Option<&Path>, marker: &str) -> String {
+    let mut script = format!(
+        "trap '__zc_status=$?; printf \"\\n%s\\n\" {marker}; pwd; env; exit $__zc_status' EXIT\n"
+    );
+    if let Some(cwd) = cwd {
+        script.push_str(&format!(
+            "cd {} || exit 1\n",
+            shell_quote(&cwd.to_string_lossy())
+        ));
+    }
+    script.push_str(command);
+    script.push('\n');
+    script
+}
+
+/// Split wrapped output into what the command printed and its state report.
+/// Output without the marker (e.g. a command that replaced the exit trap) is
+/// returned unchanged.
+pub fn split_report(stdout: &str, marker: &str) -> (String, Option<StateReport>) {
+    let Some(index) = stdout.rfind(&format!("\n{marker}\n")) else {
+        return (stdout.to_string(), None);
+    };
+    let visible = stdout[..index].to_string();
+    let mut lines = stdout[index + marker.len() + 2..].lines();
+    let Some(cwd) = lines.next().filter(|line| !line.is_empty()) else {
+        return (visible, None);
+    };
+    let mut env = BTreeMap::new();
+    let mut last: Option<String> = None;
+    for line in lines {
+        match line.split_once('=') {
+            Some((key, value)) if is_valid_env_var_name(key) => {
+                env.insert(key.to_string(), value.to_string());
+                last = Some(key.to_string());
+            }
+            // Continuation of a multi-line value.
+            _ => {
+                if let Some(value) = last.as_ref().and_then(|key| env.get_mut(key)) {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+            }
+        }
+    }
+    (
+        visible,
+        Some(StateReport {
+            cwd: PathBuf::from(cwd),
+            env,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_is_split_from_output_and_diffed_against_sent_env() {
+        let marker = "__ZEROCLAW_SHELL_STATE_test__";
+        let stdout = format!(
+            "built ok\n\n{marker}\n/work/app\nPATH=/usr/bin\nBUILD_MODE=release\nNOTES=line one\nline two\nSHLVL=1\n"
+        );
+        let (visible, report) = split_report(&stdout, marker);
+        assert_eq!(visible, "built ok\n");
+        let report = report.expect("state report");
+        assert_eq!(report.cwd, PathBuf::from("/work/app"));
+        assert_eq!(report.env["NOTES"], "line one\nline two");
+
+        let session = ShellSession::default();
+        let sent = BTreeMap::from([
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("LANG".to_string(), "C".to_string()),
+        ]);
+        session.update(Some(report.cwd.clone()), &sent, &report.env);
+        let state = session.state();
+        assert_eq!(state.cwd.as_deref(), Some(Path::new("/work/app")));
+        assert_eq!(state.env["BUILD_MODE"].as_deref(), Some("release"));
+        assert_eq!(state.env["LANG"], None);
+        assert!(!state.env.contains_key("PATH"));
+        assert!(!state.env.contains_key("SHLVL"));
+    }
+
+    #[test]
+    fn path_loader_and_startup_variables_are_not_carried_over() {
+        let session = ShellSession::default();
+        let sent = BTreeMap::from([
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("IFS".to_string(), " ".to_string()),
+        ]);
+        let reported = BTreeMap::from([
+            ("PATH".to_string(), "/work/bin:/usr/bin".to_string()),
+            ("LD_PRELOAD".to_string(), "/work/evil.so".to_string()),
+            ("LD_LIBRARY_PATH".to_string(), "/work/lib".to_string()),
+            (
+                "DYLD_INSERT_LIBRARIES".to_string(),
+                "/work/evil.dylib".to_string(),
+            ),
+            ("BASH_ENV".to_string(), "/work/rc".to_string()),
+            ("ENV".to_string(), "/work/rc".to_string()),
+            ("PROMPT_COMMAND".to_string(), "curl evil".to_string()),
+            ("BASH_FUNC_git%%".to_string(), "() { :; }".to_string()),
+            ("BUILD_MODE".to_string(), "release".to_string()),
+        ]);
+        session.update(None, &sent, &reported);
+
+        let state = session.state();
+        assert_eq!(
+            state.env.keys().map(String::as_str).collect::<Vec<_>>(),
+            vec!["BUILD_MODE"]
+        );
+    }
+
+    #[test]
+    fn output_without_marker_is_left_alone() {
+        let (visible, report) = split_report("plain output\n", "__marker__");
+        assert_eq!(visible, "plain output\n");
+        assert!(report.is_none());
+        assert!(wrap("make", Some(Path::new("/w/it's")), "__m__").contains("cd '/w/it'\\''s'"));
+    }
+}
@@ -0,0 +1,107 @@
+//! Canned stand-ins for network tools in simulation mode.
+//!
+//! With the `simulation` provider, tools that reach the network
+//! ([`ToolKind::Network`]) keep their names and schemas but return fixed,
+//! offline results, so runs that search or fetch still produce evidence
+//! without a connection.
+
+use super::traits::{Tool, ToolKind, ToolResult};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+pub struct SimulatedTool {
+    inner: Arc<dyn Tool>,
+}
+
+impl SimulatedTool {
+    pub fn new(inner: Arc<dyn Tool>) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl Tool for SimulatedTool {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        self.inner.parameters_schema()
+    }
+
+    fn kind(&self) -> ToolKind {
+        self.inner.kind()
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let arg = |key: &str| args.get(key).and_then(serde_json::Value::as_str);
+        let output = match self.name() {
+            "web_search_tool" => {
+                let query = arg("query").unwrap_or_default();
+                format!(
+                    "Search results for: {query} (via simulation)\n\
+                     1. Simulated result for {query}\n   https://example.com/simulated/1\n\
+                     2. Another simulated result\n   https://example.com/simulated/2"
+                )
+            }
+            "http_request" | "browser_open" | "browser" => format!(
+                "[simulation] 200 OK from {}\n<html><body>Simulated page</body></html>",
+                arg("url").unwrap_or("https://example.com/")
+            ),
+            name => format!("[simulation] {name} is mocked offline; arguments: {args}"),
+        };
+        Ok(ToolResult {
+            success: true,
+            output,
+            error: None,
+        })
+    }
+}
+
+/// Replace network tools in `tools` with [`SimulatedTool`]s.
+pub fn simulate_network_tools(tools: Vec<Box<dyn Tool>>) -> Vec<Box<dyn Tool>> {
+    tools
+        .into_iter()
+        .map(|tool| -> Box<dyn Tool> {
+            if tool.kind() == ToolKind::Network {
+                Box::new(SimulatedTool::new(Arc::from(tool)))
+            } else {
+                tool
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::SecurityPolicy;
+    use crate::tools::{FileReadTool, WebSearchTool};
+
+    #[tokio::test]
+    async fn network_tools_are_replaced_and_local_tools_kept() {
+        let security = Arc::new(SecurityPolicy::default());
+        let tools: Vec<Box<dyn Tool>> = vec![
+            Box::new(FileReadTool::new(security)),
+            Box::new(WebSearchTool::new("duckduckgo".into(), None, 5, 15)),
+        ];
+        let tools = simulate_network_tools(tools);
+
+        let search = tools
+            .iter()
+            .find(|tool| tool.name() == "web_search_tool")
+            .expect("search tool kept");
+        assert_eq!(search.kind(), ToolKind::Network);
+        let result = search
+            .execute(serde_json::json!({"query": "rust news"}))
+            .await
+            .expect("simulated search");
+        assert!(result.success);
+        assert!(result.output.contains("https://example.com/simulated/1"));
+        assert_eq!(tools[0].name(), "file_read");
+    }
+}
@@ -0,0 +1,542 @@
+//! `sql_query`: run one SQL statement against a configured database
+//! (`[sql_query]`) and return the result as a Markdown table.
+//!
+//! Databases are SQLite files, or PostgreSQL connection URLs in builds with
+//! the `memory-postgres` feature. Each call runs a single statement whose
+//! kind ([`sql_statement_kind`]) must be in `allowed_statements`; the tool
+//! policy's `sql_statements` can narrow that further per channel or sender.
+//! While only `select` is allowed, SQLite files are opened read-only and
+//! PostgreSQL sessions are set read-only, so a query that writes anyway fails.
+
+use super::traits::{Tool, ToolResult};
+use crate::config::SqlQueryConfig;
+use crate::security::tool_policy::sql_statement_kind;
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use serde_json::json;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Characters kept of each cell.
+const MAX_CELL_CHARS: usize = 200;
+
+/// Result of one statement.
+#[derive(Debug, PartialEq, Eq)]
+enum QueryOutput {
+    Rows {
+        columns: Vec<String>,
+        rows: Vec<Vec<String>>,
+        /// More rows than `max_rows` came back.
+        truncated: bool,
+    },
+    Affected(u64),
+}
+
+/// Where a configured database lives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Sqlite(PathBuf),
+    Postgres(String),
+}
+
+/// Database query tool
+pub struct SqlQueryTool {
+    security: Arc<SecurityPolicy>,
+    config: SqlQueryConfig,
+}
+
+impl SqlQueryTool {
+    pub fn new(security: Arc<SecurityPolicy>, config: SqlQueryConfig) -> Self {
+        Self { security, config }
+    }
+
+    fn target(&self, name: Option<&str>) -> Result<(String, Target), String> {
+        let mut names: Vec<&String> = self.config.databases.keys().collect();
+        names.sort();
+        let name = match (name, names.as_slice()) {
+            (Some(name), _) => name.to_string(),
+            (None, [only]) => (*only).clone(),
+            (None, _) => {
+                return Err(format!(
+                    "Pass 'database'; configured databases: {}",
+                    join_names(&names)
+                ))
+            }
+        };
+        let Some(location) = self.config.databases.get(&name) else {
+            return Err(format!(
+                "Unknown database '{name}'; configured databases: {}",
+                join_names(&names)
+            ));
+        };
+        let location = location.trim();
+        let target = if location.starts_with("postgres://") || location.starts_with("postgresql://")
+        {
+            Target::Postgres(location.to_string())
+        } else {
+            let path = crate::security::policy::expand_user_path(location);
+            Target::Sqlite(if path.is_absolute() {
+                path
+            } else {
+                self.security.workspace_dir.join(path)
+            })
+        };
+        Ok((name, target))
+    }
+
+    /// Whether nothing but queries may run, so the connection can be
+    /// read-only.
+    fn read_only(&self) -> bool {
+        self.config
+            .allowed_statements
+            .iter()
+            .all(|kind| kind.trim().eq_ignore_ascii_case("select"))
+    }
+}
+
+fn join_names(names: &[&String]) -> String {
+    if names.is_empty() {
+        "none".into()
+    } else {
+        names
+            .iter()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Whether `sql` holds more than one statement. Semicolons in quotes and
+/// comments do not count, and a trailing one is fine.
+fn has_multiple_statements(sql: &str) -> bool {
+    let mut chars = sql.chars().peekable();
+    let mut ended = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                ended = false;
+                for inner in chars.by_ref() {
+                    if inner == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for inner in chars.by_ref() {
+                    if previous == '*' && inner == '/' {
+                        break;
+                    }
+                    previous = inner;
+                }
+            }
+            ';' => ended = true,
+            c if c.is_whitespace() => {}
+            _ => {
+                if ended {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+fn sqlite_cell(value: rusqlite::types::ValueRef<'_>) -> String {
+    use rusqlite::types::ValueRef;
+    match value {
+        ValueRef::Null => "NULL".into(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+        ValueRef::Blob(blob) => format!("<{} bytes>", blob.len()),
+    }
+}
+
+fn run_sqlite(
+    conn: &rusqlite::Connection,
+    sql: &str,
+    read_only: bool,
+    max_rows: usize,
+) -> anyhow::Result<QueryOutput> {
+    let mut stmt = conn.prepare(sql)?;
+    if read_only && !stmt.readonly() {
+        anyhow::bail!("the statement would write to the database");
+    }
+    let columns: Vec<String> = stmt
+        .column_names()
+        .into_iter()
+        .map(ToString::to_string)
+        .collect();
+    if columns.is_empty() {
+        return Ok(QueryOutput::Affected(stmt.execute([])? as u64));
+    }
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    let mut result = stmt.query([])?;
+    while let Some(row) = result.next()? {
+        if rows.len() == max_rows {
+            truncated = true;
+            break;
+        }
+        rows.push(
+            (0..columns.len())
+                .map(|index| row.get_ref(index).map(sqlite_cell))
+                .collect::<rusqlite::Result<Vec<_>>>()?,
+        );
+    }
+    Ok(QueryOutput::Rows {
+        columns,
+        rows,
+        truncated,
+    })
+}
+
+#[cfg(feature = "memory-postgres")]
+fn run_postgres(
+    url: &str,
+    sql: &str,
+    read_only: bool,
+    max_rows: usize,
+    timeout: Duration,
+) -> anyhow::Result<QueryOutput> {
+    use anyhow::Context;
+    use postgres::SimpleQueryMessage;
+
+    let mut config: postgres::Config = url.parse().context("invalid PostgreSQL connection URL")?;
+    config.connect_timeout(timeout);
+    let mut client = config
+        .connect(postgres::NoTls)
+        .context("failed to connect to PostgreSQL")?;
+    client.batch_execute(&format!("SET statement_timeout = {}", timeout.as_millis()))?;
+    if read_only {
+        client.batch_execute("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY")?;
+    }
+
+    let mut columns = Vec::new();
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    let mut affected = None;
+    for message in client.simple_query(sql)? {
+        match message {
+            SimpleQueryMessage::Row(row) => {
+                if columns.is_empty() {
+                    columns = row
+                        .columns()
+                        .iter()
+                        .map(|column| column.name().to_string())
+                        .collect();
+                }
+                if rows.len() == max_rows {
+                    truncated = true;
+                    continue;
+                }
+                rows.push(
+                    (0..row.len())
+                        .map(|index| row.get(index).unwrap_or("NULL").to_string())
+                        .collect(),
+                );
+            }
+            SimpleQueryMessage::CommandComplete(count) => affected = Some(count),
+            _ => {}
+        }
+    }
+    if columns.is_empty() && sql_statement_kind(sql) != "select" {
+        return Ok(QueryOutput::Affected(affected.unwrap_or(0)));
+    }
+    Ok(QueryOutput::Rows {
+        columns,
+        rows,
+        truncated,
+    })
+}
+
+#[cfg(not(feature = "memory-postgres"))]
+fn run_postgres(
+    _url: &str,
+    _sql: &str,
+    _read_only: bool,
+    _max_rows: usize,
+    _timeout: Duration,
+) -> anyhow::Result<QueryOutput> {
+    anyhow::bail!(
+        "PostgreSQL databases need a build with `memory-postgres`; rebuild with `--features memory-postgres`"
+    )
+}
+
+fn markdown_cell(value: &str) -> String {
+    let mut cell: String = value.chars().take(MAX_CELL_CHARS).collect();
+    if value.chars().count() > MAX_CELL_CHARS {
+        cell.push('…');
+    }
+    cell.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+fn format_output(output: &QueryOutput, max_rows: usize) -> String {
+    match output {
+        QueryOutput::Affected(1) => "1 row affected".into(),
+        QueryOutput::Affected(count) => format!("{count} rows affected"),
+        QueryOutput::Rows { columns, rows, .. } if columns.is_empty() => {
+            format!("{} rows", rows.len())
+        }
+        QueryOutput::Rows {
+            columns,
+            rows,
+            truncated,
+        } => {
+            let header: Vec<String> = columns.iter().map(|c| markdown_cell(c)).collect();
+            let mut table = format!(
+                "| {} |\n|{}|",
+                header.join(" | "),
+                vec!["---"; columns.len()].join("|")
+            );
+            for row in rows {
+                let cells: Vec<String> = row.iter().map(|c| markdown_cell(c)).collect();
+                let _ = write!(table, "\n| {} |", cells.join(" | "));
+            }
+            if *truncated {
+                let _ = write!(
+                    table,
+                    "\n\nShowing the first {max_rows} rows; add LIMIT/OFFSET or narrow the query for the rest."
+                );
+            } else {
+                let _ = write!(
+                    table,
+                    "\n\n{} row{}",
+                    rows.len(),
+                    if rows.len() == 1 { "" } else { "s" }
+                );
+            }
+            table
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SqlQueryTool {
+    fn name(&self) -> &str {
+        "sql_query"
+    }
+
+    fn description(&self) -> &str {
+        "Run one SQL statement against a configured database (SQLite or PostgreSQL) and get the result as a Markdown table. Only SELECT queries are allowed unless the configuration allows more"
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        let mut names: Vec<&String> = self.config.databases.keys().collect();
+        names.sort();
+        json!({
+            "type": "object",
+            "properties": {
+                "database": {
+                    "type": "string",
+                    "enum": names,
+                    "description": "Configured database to query; optional when only one is configured"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "A single SQL statement"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let query = args
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'query' parameter"))?
+            .trim();
+        let database = args.get("database").and_then(|v| v.as_str());
+
+        let fail = |error: String| {
+            Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(error),
+            })
+        };
+
+        if query.is_empty() {
+            return fail("Empty query is not allowed.".into());
+        }
+        if has_multiple_statements(query) {
+            return fail("Run one statement per call.".into());
+        }
+        let kind = sql_statement_kind(query);
+        if !self
+            .config
+            .allowed_statements
+            .iter()
+            .any(|allowed| allowed.trim().eq_ignore_ascii_case(&kind))
+        {
+            return fail(format!(
+                "`{kind}` statements are not allowed; [sql_query].allowed_statements is [{}]",
+                self.config.allowed_statements.join(", ")
+            ));
+        }
+
+        let (name, target) = match self.target(database) {
+            Ok(found) => found,
+            Err(error) => return fail(error),
+        };
+
+        if kind != "select" && !self.security.can_act() {
+            return fail("Action blocked: autonomy is read-only".into());
+        }
+        if self.security.is_rate_limited() || !self.security.record_action() {
+            return fail("Action blocked: rate limit exceeded".into());
+        }
+
+        let read_only = self.read_only();
+        let max_rows = self.config.max_rows.max(1);
+        let timeout = Duration::from_secs(self.config.timeout_secs.max(1));
+        let sql = query.to_string();
+
+        let result = match target {
+            Target::Sqlite(path) => {
+                if !path.is_file() {
+                    return fail(format!("Database '{name}' not found at {}", path.display()));
+                }
+                let flags = if read_only {
+                    rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
+                } else {
+                    rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE
+                };
+                let conn = match rusqlite::Connection::open_with_flags(&path, flags) {
+                    Ok(conn) => conn,
+                    Err(e) => return fail(format!("Failed to open database '{name}': {e}")),
+                };
+                let _ = conn.busy_timeout(timeout);
+                // Interrupting is the only way to stop a running statement.
+                let interrupt = conn.get_interrupt_handle();
+                let task = tokio::task::spawn_blocking(move || {
+                    run_sqlite(&conn, &sql, read_only, max_rows)
+                });
+                match tokio::time::timeout(timeout, task).await {
+                    Ok(joined) => joined?,
+                    Err(_) => {
+                        interrupt.interrupt();
+                        return fail(format!("Query timed out after {}s", timeout.as_secs()));
+                    }
+                }
+            }
+            Target::Postgres(url) => {
+                let task = tokio::task::spawn_blocking(move || {
+                    run_postgres(&url, &sql, read_only, max_rows, timeout)
+                });
+                // The server cancels the statement at the same timeout; the
+                // extra second covers connecting.
+                match tokio::time::timeout(timeout + Duration::from_secs(1), task).await {
+                    Ok(joined) => joined?,
+                    Err(_) => return fail(format!("Query timed out after {}s", timeout.as_secs())),
+                }
+            }
+        };
+
+        match result {
+            Ok(output) => Ok(ToolResult {
+                success: true,
+                output: format_output(&output, max_rows),
+                error: None,
+            }),
+            Err(e) => fail(format!("Query failed on '{name}': {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn tool(dir: &TempDir, allowed_statements: &[&str], max_rows: usize) -> SqlQueryTool {
+        let conn = rusqlite::Connection::open(dir.path().join("app.db")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS users (id INTEGER, name TEXT);
+             DELETE FROM users;
+             INSERT INTO users VALUES (1, 'ada'), (2, 'bob|by'), (3, NULL);",
+        )
+        .unwrap();
+        SqlQueryTool::new(
+            Arc::new(SecurityPolicy {
+                workspace_dir: dir.path().to_path_buf(),
+                ..SecurityPolicy::default()
+            }),
+            SqlQueryConfig {
+                enabled: true,
+                databases: HashMap::from([("app".to_string(), "app.db".to_string())]),
+                allowed_statements: allowed_statements.iter().map(|s| s.to_string()).collect(),
+                max_rows,
+                ..SqlQueryConfig::default()
+            },
+        )
+    }
+
+    #[test]
+    fn multiple_statements_are_detected_outside_quotes_and_comments() {
+        assert!(!has_multiple_statements("select ';' from t;  "));
+        assert!(!has_multiple_statements("select 1 -- done; really\n"));
+        assert!(!has_multiple_statements("select /* a; b */ 1"));
+        assert!(has_multiple_statements("select 1; drop table t"));
+    }
+
+    #[tokio::test]
+    async fn select_returns_markdown_table() {
+        let dir = TempDir::new().unwrap();
+        let result = tool(&dir, &["select"], 200)
+            .execute(json!({ "query": "SELECT id, name FROM users ORDER BY id" }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(
+            result.output,
+            "| id | name |\n|---|---|\n| 1 | ada |\n| 2 | bob\\|by |\n| 3 | NULL |\n\n3 rows"
+        );
+
+        let result = tool(&dir, &["select"], 2)
+            .execute(json!({ "database": "app", "query": "select id from users" }))
+            .await
+            .unwrap();
+        assert!(result.output.contains("Showing the first 2 rows"));
+    }
+
+    #[tokio::test]
+    async fn writes_need_an_allowed_statement_kind() {
+        let dir = TempDir::new().unwrap();
+        let result = tool(&dir, &["select"], 200)
+            .execute(json!({ "query": "DELETE FROM users" }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result
+            .error
+            .unwrap()
+            .contains("`delete` statements are not allowed"));
+
+        let result = tool(&dir, &["select", "insert"], 200)
+            .execute(json!({ "query": "INSERT INTO users VALUES (4, 'cy')" }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.output, "1 row affected");
+
+        let result = tool(&dir, &["select"], 200)
+            .execute(json!({ "database": "crm", "query": "select 1" }))
+            .await
+            .unwrap();
+        assert!(result.error.unwrap().contains("configured databases: app"));
+    }
+}
@@ -0,0 +1,113 @@
+//! Environment variables scoped to one task run.
+//!
+//! The task engine runs each round inside [`scope`] with `autonomy.task_env`
+//! merged with the caller's variables. Process-spawning tools (`shell`,
+//! `git_operations`) add them to their child processes after the sanitized
+//! baseline. The daemon's own environment is never modified, so concurrent
+//! tasks cannot see each other's values.
+
+use super::shell::is_valid_env_var_name;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Arc;
+
+/// Key fragments whose values are never written to task events.
+const SENSITIVE_KEY_MARKERS: &[&str] = &[
+    "KEY",
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "CREDENTIAL",
+    "AUTH",
+];
+
+pub type TaskEnv = Arc<BTreeMap<String, String>>;
+
+tokio::task_local! {
+    static TASK_ENV: TaskEnv;
+}
+
+/// Configured variables overlaid with the caller's; invalid names are dropped.
+pub fn merge(configured: &HashMap<String, String>, caller: &HashMap<String, String>) -> TaskEnv {
+    Arc::new(
+        configured
+            .iter()
+            .chain(caller)
+            .filter(|(key, _)| is_valid_env_var_name(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+    )
+}
+
+/// Run `future` with `env` visible to the tools it executes.
+pub async fn scope<F: Future>(env: TaskEnv, future: F) -> F::Output {
+    TASK_ENV.scope(env, future).await
+}
+
+/// Add the current task's variables to `command`; a no-op outside a task.
+pub fn apply(command: &mut tokio::process::Command) {
+    let _ = TASK_ENV.try_with(|env| {
+        command.envs(env.iter());
+    });
+}
+
+/// `env` as a JSON object safe to store: values of secret-looking keys are
+/// replaced and known credential patterns are scrubbed from the rest.
+pub fn redacted(env: &BTreeMap<String, String>) -> serde_json::Value {
+    env.iter()
+        .map(|(key, value)| {
+            let upper = key.to_ascii_uppercase();
+            let shown = if SENSITIVE_KEY_MARKERS
+                .iter()
+                .any(|marker| upper.contains(marker))
+            {
+                "[REDACTED]".to_string()
+            } else {
+                crate::providers::scrub_secret_patterns(value)
+            };
+            (key.clone(), serde_json::Value::String(shown))
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scoped_variables_reach_commands_and_are_redacted_in_logs() {
+        let configured = HashMap::from([
+            ("PATH".to_string(), "/opt/project/bin:/usr/bin".to_string()),
+            (
+                "API_BASE".to_string(),
+                "https://staging.example".to_string(),
+            ),
+        ]);
+        let caller = HashMap::from([
+            ("API_BASE".to_string(), "https://dev.example".to_string()),
+            ("DEPLOY_TOKEN".to_string(), "s3cr3t".to_string()),
+            ("bad-name".to_string(), "x".to_string()),
+        ]);
+        let env = merge(&configured, &caller);
+        assert_eq!(env.len(), 3);
+        assert_eq!(env["API_BASE"], "https://dev.example");
+
+        let logged = redacted(&env);
+        assert_eq!(logged["DEPLOY_TOKEN"], "[REDACTED]");
+        assert_eq!(logged["PATH"], "/opt/project/bin:/usr/bin");
+
+        let output = scope(env, async {
+            let mut command = tokio::process::Command::new("/bin/sh");
+            command.args(["-c", "echo \"$API_BASE\""]).env_clear();
+            apply(&mut command);
+            command.output().await.expect("run /bin/sh")
+        })
+        .await;
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "https://dev.example"
+        );
+    }
+}
@@ -1,5 +1,40 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::future::Future;
+
+tokio::task_local! {
+    static RESULT_METADATA: RefCell<serde_json::Map<String, serde_json::Value>>;
+}
+
+/// Run one tool call and return the structured metadata it recorded with
+/// [`record_result_metadata`] next to its result.
+pub async fn collect_result_metadata<F: Future>(
+    future: F,
+) -> (F::Output, serde_json::Map<String, serde_json::Value>) {
+    RESULT_METADATA
+        .scope(RefCell::default(), async {
+            let output = future.await;
+            (output, RESULT_METADATA.with(RefCell::take))
+        })
+        .await
+}
+
+/// Append `value` to the metadata array under `key` for the running tool
+/// call, e.g. the pages a fetch or search surfaced. Outside
+/// [`collect_result_metadata`] nothing is recorded.
+pub fn record_result_metadata(key: &str, value: serde_json::Value) {
+    let _ = RESULT_METADATA.try_with(|metadata| {
+        let mut metadata = metadata.borrow_mut();
+        match metadata
+            .entry(key)
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()))
+        {
+            serde_json::Value::Array(values) => values.push(value),
+            other => *other = serde_json::Value::Array(vec![value]),
+        }
+    });
+}
 
 /// Result of a tool execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,6 +44,91 @@ pub struct ToolResult {
     pub error: Option<String>,
 }
 
+/// How a tool call ended, as recorded in conversation history. Consumers
+/// read this instead of guessing from words like "failed" in the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolStatus {
+    #[default]
+    Ok,
+    /// The tool ran (or was meant to run) and failed.
+    Error,
+    /// The call never ran: a hook, the user, or a policy refused it.
+    Denied,
+}
+
+impl ToolStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Error => "error",
+            Self::Denied => "denied",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ok" => Some(Self::Ok),
+            "error" => Some(Self::Error),
+            "denied" => Some(Self::Denied),
+            _ => None,
+        }
+    }
+
+    pub fn is_ok(self) -> bool {
+        self == Self::Ok
+    }
+}
+
+/// A tool result as stored in history: status, the text the model sees,
+/// and metadata such as the tool name and duration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolResultEnvelope {
+    pub status: ToolStatus,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ToolResultEnvelope {
+    pub fn new(status: ToolStatus, content: impl Into<String>) -> Self {
+        Self {
+            status,
+            content: content.into(),
+            metadata: serde_json::Map::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_metadata(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.metadata.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Body of a native `tool` history message. Providers read
+    /// `tool_call_id` and `content` and ignore the other fields.
+    pub fn to_tool_message(&self, tool_call_id: Option<&str>) -> String {
+        let mut message = serde_json::json!({
+            "tool_call_id": tool_call_id,
+            "content": self.content,
+            "status": self.status,
+        });
+        if !self.metadata.is_empty() {
+            message["metadata"] = serde_json::Value::Object(self.metadata.clone());
+        }
+        message.to_string()
+    }
+
+    /// `<tool_result>` block for prompt-mode history.
+    pub fn to_prompt_block(&self, tool_name: &str) -> String {
+        format!(
+            "<tool_result name=\"{tool_name}\" status=\"{}\">\n{}\n</tool_result>",
+            self.status.as_str(),
+            self.content
+        )
+    }
+}
+
 /// Description of a tool for the LLM
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolSpec {
@@ -17,6 +137,19 @@ pub struct ToolSpec {
     pub parameters: serde_json::Value,
 }
 
+/// What a tool does to the world, as far as completion evidence is concerned.
+/// A successful `WriteLike` call counts as write evidence, `ReadLike` as
+/// read-back, and `Network` as search/fetch evidence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolKind {
+    WriteLike,
+    ReadLike,
+    Network,
+    #[default]
+    Other,
+}
+
 /// Core tool trait — implement for any capability
 #[async_trait]
 pub trait Tool: Send + Sync {
@@ -29,9 +162,42 @@ pub trait Tool: Send + Sync {
     /// JSON schema for parameters
     fn parameters_schema(&self) -> serde_json::Value;
 
+    /// Evidence kind of a successful call. Tools that write files (patch,
+    /// append, MCP write tools) should return `ToolKind::WriteLike`.
+    fn kind(&self) -> ToolKind {
+        ToolKind::Other
+    }
+
+    /// Other tools this tool calls itself (macro tools). A run that excludes
+    /// any of them excludes this tool as well.
+    fn inner_tools(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Calls this tool makes for `args`, in order (macro tools). The tool
+    /// loop runs each through its hooks and approval gate first.
+    fn step_calls(&self, _args: &serde_json::Value) -> Vec<(String, serde_json::Value)> {
+        Vec::new()
+    }
+
     /// Execute the tool with given arguments
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult>;
 
+    /// Ways `args` departs from [`Tool::parameters_schema`]; empty when the
+    /// call is well-formed. The tool loop checks this before `execute` and
+    /// hands the problems back to the model instead of running the tool.
+    /// `null` arguments and `null` properties count as absent.
+    fn argument_violations(&self, args: &serde_json::Value) -> Vec<String> {
+        let mut args = match args {
+            serde_json::Value::Null => serde_json::Value::Object(serde_json::Map::new()),
+            other => other.clone(),
+        };
+        if let Some(object) = args.as_object_mut() {
+            object.retain(|_, value| !value.is_null());
+        }
+        crate::agent::output_format::schema_violations(&self.parameters_schema(), &args)
+    }
+
     /// Get the full spec for LLM registration
     fn spec(&self) -> ToolSpec {
         ToolSpec {
@@ -80,6 +246,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn argument_violations_check_the_parameter_schema() {
+        let tool = DummyTool;
+        assert!(tool
+            .argument_violations(&serde_json::json!({"value": "x"}))
+            .is_empty());
+        assert!(tool
+            .argument_violations(&serde_json::Value::Null)
+            .is_empty());
+        assert!(tool
+            .argument_violations(&serde_json::json!({"value": null}))
+            .is_empty());
+        assert_eq!(
+            tool.argument_violations(&serde_json::json!({"value": 3})),
+            vec!["$.value: expected type \"string\"".to_string()]
+        );
+        assert!(!tool
+            .argument_violations(&serde_json::json!("value"))
+            .is_empty());
+    }
+
     #[test]
     fn spec_uses_tool_metadata_and_schema() {
         let tool = DummyTool;
@@ -89,6 +276,7 @@ mod tests {
         assert_eq!(spec.description, "A deterministic test tool");
         assert_eq!(spec.parameters["type"], "object");
         assert_eq!(spec.parameters["properties"]["value"]["type"], "string");
+        assert_eq!(tool.kind(), ToolKind::Other);
     }
 
     #[tokio::test]
@@ -104,6 +292,25 @@ mod tests {
         assert!(result.error.is_none());
     }
 
+    #[tokio::test]
+    async fn result_metadata_is_collected_per_call() {
+        record_result_metadata(
+            "sources",
+            serde_json::json!({"url": "https://ignored.example"}),
+        );
+        let ((), metadata) = collect_result_metadata(async {
+            record_result_metadata("sources", serde_json::json!({"url": "https://a.example"}));
+            record_result_metadata("sources", serde_json::json!({"url": "https://b.example"}));
+        })
+        .await;
+        assert_eq!(
+            serde_json::Value::Object(metadata),
+            serde_json::json!({
+                "sources": [{"url": "https://a.example"}, {"url": "https://b.example"}]
+            })
+        );
+    }
+
     #[test]
     fn tool_result_serialization_roundtrip() {
         let result = ToolResult {
@@ -118,4 +325,24 @@ mod tests {
         assert!(!parsed.success);
         assert_eq!(parsed.error.as_deref(), Some("boom"));
     }
+
+    #[test]
+    fn envelope_renders_status_for_both_history_formats() {
+        let envelope = ToolResultEnvelope::new(ToolStatus::Denied, "Denied by user.")
+            .with_metadata("tool", "shell");
+
+        let message: serde_json::Value =
+            serde_json::from_str(&envelope.to_tool_message(Some("call_1"))).unwrap();
+        assert_eq!(message["tool_call_id"], "call_1");
+        assert_eq!(message["content"], "Denied by user.");
+        assert_eq!(message["status"], "denied");
+        assert_eq!(message["metadata"]["tool"], "shell");
+
+        assert_eq!(
+            ToolResultEnvelope::new(ToolStatus::Ok, "error: none").to_prompt_block("file_read"),
+            "<tool_result name=\"file_read\" status=\"ok\">\nerror: none\n</tool_result>"
+        );
+        assert_eq!(ToolStatus::parse(" Error "), Some(ToolStatus::Error));
+        assert_eq!(ToolStatus::parse("maybe"), None);
+    }
 }
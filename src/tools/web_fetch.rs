@@ -0,0 +1,432 @@
+//! `web_fetch`: download a page and return its readable content as Markdown.
+//!
+//! Pages go through the same host checks as `http_request` (allowlist, no
+//! local or private hosts), including every redirect hop. HTML is reduced to
+//! its main content (`<article>` or `<main>` when present, otherwise `<body>`
+//! without scripts, navigation, and forms) and converted to Markdown; other
+//! text types are returned as is. The result is cut to `max_chars` so one
+//! page cannot fill the context window.
+
+use super::http_request::{
+    extract_host, host_matches_allowlist, is_private_or_local_host, normalize_allowed_domains,
+};
+use super::traits::{record_result_metadata, Tool, ToolKind, ToolResult};
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use regex::{Captures, Regex};
+use serde_json::json;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+const MAX_REDIRECTS: usize = 5;
+
+/// Elements whose content is never part of the readable text.
+const NOISE_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "nav", "footer", "aside", "form", "iframe",
+    "button",
+];
+
+fn regex(pattern: &str) -> Regex {
+    Regex::new(pattern).expect("static web_fetch regex")
+}
+
+static TITLE: LazyLock<Regex> = LazyLock::new(|| regex(r"(?is)<title\b[^>]*>(.*?)</title\s*>"));
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| regex(r"(?s)<!--.*?-->"));
+static NOISE: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    NOISE_TAGS
+        .iter()
+        .map(|tag| regex(&format!(r"(?is)<{tag}\b[^>]*>.*?</{tag}\s*>")))
+        .collect()
+});
+static CONTAINERS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    ["article", "main", "body"]
+        .iter()
+        .map(|tag| regex(&format!(r"(?is)<{tag}\b[^>]*>(.*)</{tag}\s*>")))
+        .collect()
+});
+static PRE: LazyLock<Regex> = LazyLock::new(|| regex(r"(?is)<pre\b[^>]*>(.*?)</pre\s*>"));
+static LINK: LazyLock<Regex> =
+    LazyLock::new(|| regex(r#"(?is)<a\b[^>]*?\bhref\s*=\s*["']([^"']*)["'][^>]*>(.*?)</a\s*>"#));
+static STRONG: LazyLock<Regex> =
+    LazyLock::new(|| regex(r"(?is)<(?:strong|b)\b[^>]*>(.*?)</(?:strong|b)\s*>"));
+static EMPHASIS: LazyLock<Regex> =
+    LazyLock::new(|| regex(r"(?is)<(?:em|i)\b[^>]*>(.*?)</(?:em|i)\s*>"));
+static CODE: LazyLock<Regex> = LazyLock::new(|| regex(r"(?is)<code\b[^>]*>(.*?)</code\s*>"));
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| regex(r"(?is)<h([1-6])\b[^>]*>(.*?)</h[1-6]\s*>"));
+static LIST_ITEM: LazyLock<Regex> = LazyLock::new(|| regex(r"(?i)<li\b[^>]*>"));
+static LINE_BREAK: LazyLock<Regex> = LazyLock::new(|| {
+    regex(
+        r"(?i)<br\s*/?>|</?(?:p|div|section|article|main|header|ul|ol|table|tr|blockquote|dl|dt|dd|figure|figcaption)\b[^>]*>",
+    )
+});
+static CELL_END: LazyLock<Regex> = LazyLock::new(|| regex(r"(?i)</t[dh]\s*>"));
+static TAG: LazyLock<Regex> = LazyLock::new(|| regex(r"(?s)<[^>]+>"));
+static NUMERIC_ENTITY: LazyLock<Regex> = LazyLock::new(|| regex(r"&#([xX]?)([0-9a-fA-F]+);"));
+
+pub struct WebFetchTool {
+    security: Arc<SecurityPolicy>,
+    allowed_domains: Vec<String>,
+    max_chars: usize,
+    max_response_size: usize,
+    timeout_secs: u64,
+}
+
+impl WebFetchTool {
+    pub fn new(
+        security: Arc<SecurityPolicy>,
+        allowed_domains: Vec<String>,
+        max_chars: usize,
+        max_response_size: usize,
+        timeout_secs: u64,
+    ) -> Self {
+        Self {
+            security,
+            allowed_domains: normalize_allowed_domains(allowed_domains),
+            max_chars: max_chars.max(1),
+            max_response_size: max_response_size.max(1),
+            timeout_secs: timeout_secs.max(1),
+        }
+    }
+
+    fn validate_url(&self, raw_url: &str) -> anyhow::Result<reqwest::Url> {
+        let url = raw_url.trim();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            anyhow::bail!("Only http:// and https:// URLs are allowed");
+        }
+        check_host(url, &self.allowed_domains)?;
+        Ok(reqwest::Url::parse(url)?)
+    }
+
+    fn client(&self) -> anyhow::Result<reqwest::Client> {
+        let allowed_domains = self.allowed_domains.clone();
+        // Every hop is checked, so a redirect cannot reach a blocked host.
+        let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match check_host(attempt.url().as_str(), &allowed_domains) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e.to_string()),
+            }
+        });
+        let builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.timeout_secs))
+            .connect_timeout(Duration::from_secs(10))
+            .redirect(redirects)
+            .user_agent("Mozilla/5.0 (compatible; ZeroClaw web_fetch)");
+        let builder = crate::config::apply_runtime_proxy_to_builder(builder, "tool.web_fetch");
+        Ok(builder.build()?)
+    }
+
+    /// Read the body up to `max_response_size` bytes.
+    async fn read_body(&self, mut response: reqwest::Response) -> anyhow::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            let room = self.max_response_size.saturating_sub(body.len());
+            if chunk.len() >= room {
+                body.extend_from_slice(&chunk[..room]);
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+}
+
+fn check_host(url: &str, allowed_domains: &[String]) -> anyhow::Result<()> {
+    if allowed_domains.is_empty() {
+        anyhow::bail!(
+            "web_fetch is enabled but no allowed_domains are configured. Add [web_fetch].allowed_domains in config.toml"
+        );
+    }
+    let host = extract_host(url)?;
+    if is_private_or_local_host(&host) {
+        anyhow::bail!("Blocked local/private host: {host}");
+    }
+    if !host_matches_allowlist(&host, allowed_domains) {
+        anyhow::bail!("Host '{host}' is not in web_fetch.allowed_domains");
+    }
+    Ok(())
+}
+
+/// Cut `text` to `max_chars`, saying how much was left out.
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars).collect();
+    format!("{kept}\n\n... [page truncated at {max_chars} of {total} characters]")
+}
+
+fn decode_entities(text: &str) -> String {
+    let text = NUMERIC_ENTITY.replace_all(text, |caps: &Captures| {
+        let radix = if caps[1].is_empty() { 10 } else { 16 };
+        u32::from_str_radix(&caps[2], radix)
+            .ok()
+            .and_then(char::from_u32)
+            .map_or_else(|| caps[0].to_string(), String::from)
+    });
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Inline text of an element: tags dropped, whitespace collapsed.
+fn inline_text(html: &str) -> String {
+    decode_entities(&TAG.replace_all(html, " "))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Title and Markdown body of an HTML page; links are made absolute
+/// against `base`.
+fn html_to_markdown(html: &str, base: &reqwest::Url) -> (Option<String>, String) {
+    let title = TITLE
+        .captures(html)
+        .map(|caps| inline_text(&caps[1]))
+        .filter(|title| !title.is_empty());
+
+    let mut html = COMMENT.replace_all(html, "").into_owned();
+    for noise in NOISE.iter() {
+        html = noise.replace_all(&html, "").into_owned();
+    }
+    let html = CONTAINERS
+        .iter()
+        .find_map(|container| container.captures(&html).map(|caps| caps[1].to_string()))
+        .unwrap_or(html);
+
+    // Preformatted blocks keep their layout; they are set aside until the
+    // rest of the page has been reflowed.
+    let mut blocks = Vec::new();
+    let html = PRE.replace_all(&html, |caps: &Captures| {
+        let code = decode_entities(&TAG.replace_all(&caps[1], ""));
+        blocks.push(format!("```\n{}\n```", code.trim_matches('\n')));
+        format!("\n\u{0}{}\u{0}\n", blocks.len() - 1)
+    });
+    let html = LINK.replace_all(&html, |caps: &Captures| {
+        let text = inline_text(&caps[2]);
+        let href = caps[1].trim();
+        if text.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+            return text;
+        }
+        match base.join(href) {
+            Ok(url) => format!("[{text}]({url})"),
+            Err(_) => text,
+        }
+    });
+    let html = STRONG.replace_all(&html, "**$1**");
+    let html = EMPHASIS.replace_all(&html, "_${1}_");
+    let html = CODE.replace_all(&html, "`$1`");
+    let html = HEADING.replace_all(&html, |caps: &Captures| {
+        let level: usize = caps[1].parse().unwrap_or(1);
+        format!("\n\n{} {}\n\n", "#".repeat(level), inline_text(&caps[2]))
+    });
+    let html = LIST_ITEM.replace_all(&html, "\n- ");
+    let html = LINE_BREAK.replace_all(&html, "\n");
+    let html = CELL_END.replace_all(&html, " ");
+    let text = decode_entities(&TAG.replace_all(&html, ""));
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() && lines.last().is_none_or(String::is_empty) {
+            continue;
+        }
+        let line = match line
+            .strip_prefix('\u{0}')
+            .and_then(|rest| rest.strip_suffix('\u{0}'))
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| blocks.get(index))
+        {
+            Some(block) => block.clone(),
+            None => line,
+        };
+        lines.push(line);
+    }
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    (title, lines.join("\n"))
+}
+
+#[async_trait]
+impl Tool for WebFetchTool {
+    fn name(&self) -> &str {
+        "web_fetch"
+    }
+
+    fn description(&self) -> &str {
+        "Fetch a web page and return its main content as Markdown (title, headings, links, lists, code). \
+        Use after web_search_tool to read a result. Long pages are truncated."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "HTTP or HTTPS URL of the page"
+                },
+                "max_chars": {
+                    "type": "integer",
+                    "description": "Characters of content to return (capped by config)",
+                    "minimum": 1
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    fn kind(&self) -> ToolKind {
+        ToolKind::Network
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let url = args
+            .get("url")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'url' parameter"))?;
+        let max_chars = args
+            .get("max_chars")
+            .and_then(|v| v.as_u64())
+            .map_or(self.max_chars, |n| usize::try_from(n).unwrap_or(usize::MAX))
+            .clamp(1, self.max_chars);
+
+        if !self.security.record_action() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Action blocked: rate limit exceeded".into()),
+            });
+        }
+
+        let url = match self.validate_url(url) {
+            Ok(url) => url,
+            Err(e) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(e.to_string()),
+                })
+            }
+        };
+
+        let response = match self.client()?.get(url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(format!("Fetch failed: {e}")),
+                })
+            }
+        };
+        let status = response.status();
+        let final_url = response.url().clone();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let body = match self.read_body(response).await {
+            Ok(body) => String::from_utf8_lossy(&body).into_owned(),
+            Err(e) => format!("[Failed to read response body: {e}]"),
+        };
+
+        let is_html = content_type.contains("html")
+            || (content_type.is_empty() && body.trim_start().starts_with('<'));
+        let (title, content) = if is_html {
+            html_to_markdown(&body, &final_url)
+        } else {
+            (None, body.trim().to_string())
+        };
+
+        if status.is_success() {
+            record_result_metadata(
+                "sources",
+                json!({ "url": final_url.as_str(), "title": title.as_deref() }),
+            );
+        }
+        let mut output = format!(
+            "Status: {} {}\nURL: {final_url}\n",
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("Unknown")
+        );
+        if let Some(title) = title {
+            output.push_str(&format!("Title: {title}\n"));
+        }
+        output.push('\n');
+        output.push_str(&truncate_chars(&content, max_chars));
+
+        Ok(ToolResult {
+            success: status.is_success(),
+            output,
+            error: (!status.is_success()).then(|| format!("HTTP {}", status.as_u16())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_tool(allowed_domains: Vec<&str>) -> WebFetchTool {
+        WebFetchTool::new(
+            Arc::new(SecurityPolicy::default()),
+            allowed_domains.into_iter().map(String::from).collect(),
+            20_000,
+            1_000_000,
+            20,
+        )
+    }
+
+    #[test]
+    fn page_is_reduced_to_main_content_markdown() {
+        let html = r##"<html><head><title>Release &amp; notes</title><style>p{}</style></head>
+            <body><nav><a href="/">Home</a></nav>
+            <article><h1>Version <em>2.0</em></h1>
+            <p>Read the <a href="/docs/upgrade">upgrade guide</a> or <a href="#top">jump</a>.</p>
+            <ul><li>Faster <b>builds</b></li><li>New <code>--json</code> flag</li></ul>
+            <pre><code>cargo install   zeroclaw
+  --locked</code></pre>
+            <script>alert(1)</script></article>
+            <footer>Copyright</footer></body></html>"##;
+        let base = reqwest::Url::parse("https://example.com/blog/post").unwrap();
+        let (title, markdown) = html_to_markdown(html, &base);
+        assert_eq!(title.as_deref(), Some("Release & notes"));
+        assert_eq!(
+            markdown,
+            "# Version _2.0_\n\nRead the [upgrade guide](https://example.com/docs/upgrade) or jump.\n\n- Faster **builds**\n- New `--json` flag\n\n```\ncargo install   zeroclaw\n  --locked\n```"
+        );
+    }
+
+    #[test]
+    fn long_pages_are_truncated_with_a_note() {
+        let text = truncate_chars(&"é".repeat(30), 10);
+        assert!(text.starts_with(&"é".repeat(10)));
+        assert!(text.ends_with("[page truncated at 10 of 30 characters]"));
+        assert_eq!(truncate_chars("short", 10), "short");
+    }
+
+    #[test]
+    fn urls_must_pass_the_allowlist() {
+        let tool = test_tool(vec!["*"]);
+        assert!(tool.validate_url("https://docs.rs/regex").is_ok());
+        let err = tool.validate_url("http://127.0.0.1:8080").unwrap_err();
+        assert!(err.to_string().contains("local/private"));
+
+        let tool = test_tool(vec!["example.com"]);
+        let err = tool.validate_url("https://other.org").unwrap_err();
+        assert!(err.to_string().contains("allowed_domains"));
+        assert!(tool.validate_url("ftp://example.com").is_err());
+    }
+}
@@ -1,14 +1,20 @@
-use super::traits::{Tool, ToolResult};
+use super::traits::{record_result_metadata, Tool, ToolKind, ToolResult};
+use crate::util::truncate_with_ellipsis;
 use async_trait::async_trait;
 use regex::Regex;
 use serde_json::json;
 use std::time::Duration;
 
+/// Longest snippet kept per result, so a page of hits stays small in context.
+const MAX_SNIPPET_CHARS: usize = 300;
+
 /// Web search tool for searching the internet.
-/// Supports multiple providers: DuckDuckGo (free), Bing (free), Brave (requires API key).
+/// Supports multiple providers: DuckDuckGo (free), Bing (free), Brave (requires API key),
+/// and a self-hosted SearXNG instance.
 pub struct WebSearchTool {
     provider: String,
     brave_api_key: Option<String>,
+    searxng_url: Option<String>,
     max_results: usize,
     timeout_secs: u64,
 }
@@ -23,11 +29,91 @@ impl WebSearchTool {
         Self {
             provider: provider.trim().to_lowercase(),
             brave_api_key,
+            searxng_url: None,
             max_results: max_results.clamp(1, 10),
             timeout_secs: timeout_secs.max(1),
         }
     }
 
+    /// Base URL of the SearXNG instance used by the `searxng` provider.
+    pub fn with_searxng_url(mut self, url: Option<String>) -> Self {
+        self.searxng_url = url
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+        self
+    }
+
+    async fn search_searxng(&self, query: &str) -> anyhow::Result<String> {
+        let base_url = self.searxng_url.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("SearXNG URL not configured (web_search.searxng_url)")
+        })?;
+
+        let search_url = format!(
+            "{}/search?q={}&format=json",
+            base_url,
+            urlencoding::encode(query)
+        );
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.timeout_secs))
+            .build()?;
+
+        let response = client
+            .get(&search_url)
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("SearXNG search failed with status: {}", response.status());
+        }
+
+        let json: serde_json::Value = response.json().await?;
+        self.parse_searxng_results(&json, query)
+    }
+
+    fn parse_searxng_results(
+        &self,
+        json: &serde_json::Value,
+        query: &str,
+    ) -> anyhow::Result<String> {
+        let results = json
+            .get("results")
+            .and_then(|r| r.as_array())
+            .ok_or_else(|| anyhow::anyhow!("Invalid SearXNG response"))?;
+
+        let mut lines = vec![format!("Search results for: {} (via SearXNG)", query)];
+        let mut found = 0;
+
+        for result in results {
+            if found >= self.max_results {
+                break;
+            }
+            let Some(url) = result.get("url").and_then(|u| u.as_str()) else {
+                continue;
+            };
+            let title = result
+                .get("title")
+                .and_then(|t| t.as_str())
+                .unwrap_or("No title");
+            let content = result.get("content").and_then(|c| c.as_str()).unwrap_or("");
+
+            found += 1;
+            lines.push(format!("{}. {}", found, title));
+            lines.push(format!("   {}", url));
+            record_hit(title, url);
+            if !content.trim().is_empty() {
+                lines.push(format!("   {}", trim_snippet(content)));
+            }
+        }
+
+        if found == 0 {
+            return Ok(format!("No results found for: {}", query));
+        }
+
+        Ok(lines.join("\n"))
+    }
+
     async fn search_duckduckgo(&self, query: &str) -> anyhow::Result<String> {
         let encoded_query = urlencoding::encode(query);
         let search_url = format!("https://html.duckduckgo.com/html/?q={}", encoded_query);
@@ -84,13 +170,14 @@ impl WebSearchTool {
 
             lines.push(format!("{}. {}", i + 1, title.trim()));
             lines.push(format!("   {}", url_str.trim()));
+            record_hit(&title, &url_str);
 
             // Add snippet if available
             if i < snippet_matches.len() {
                 let snippet = strip_tags(&snippet_matches[i][1]);
                 let snippet = snippet.trim();
                 if !snippet.is_empty() {
-                    lines.push(format!("   {}", snippet));
+                    lines.push(format!("   {}", trim_snippet(snippet)));
                 }
             }
         }
@@ -177,8 +264,9 @@ impl WebSearchTool {
 
             lines.push(format!("{}. {}", i + 1, title));
             lines.push(format!("   {}", url));
+            record_hit(title, url);
             if !description.is_empty() {
-                lines.push(format!("   {}", description));
+                lines.push(format!("   {}", trim_snippet(description)));
             }
         }
 
@@ -217,12 +305,13 @@ impl WebSearchTool {
             found += 1;
             lines.push(format!("{}. {}", found, title));
             lines.push(format!("   {}", url.trim()));
+            record_hit(title, &url);
 
             if let Some(snippet_caps) = snippet_regex.captures(block) {
                 let snippet = strip_tags(&snippet_caps[1]);
                 let snippet = snippet.trim();
                 if !snippet.is_empty() {
-                    lines.push(format!("   {}", snippet));
+                    lines.push(format!("   {}", trim_snippet(snippet)));
                 }
             }
         }
@@ -247,11 +336,29 @@ fn decode_ddg_redirect_url(raw_url: &str) -> String {
     raw_url.to_string()
 }
 
+/// Record a hit as `search_results` metadata so the engine can cite it
+/// without re-reading the formatted text.
+fn record_hit(title: &str, url: &str) {
+    let url = url.trim();
+    if url.starts_with("https://") || url.starts_with("http://") {
+        record_result_metadata(
+            "search_results",
+            json!({ "url": url, "title": title.trim() }),
+        );
+    }
+}
+
 fn strip_tags(content: &str) -> String {
     let re = Regex::new(r"<[^>]+>").unwrap();
     re.replace_all(content, "").to_string()
 }
 
+/// One-line snippet capped at [`MAX_SNIPPET_CHARS`].
+fn trim_snippet(snippet: &str) -> String {
+    let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate_with_ellipsis(&snippet, MAX_SNIPPET_CHARS)
+}
+
 #[async_trait]
 impl Tool for WebSearchTool {
     fn name(&self) -> &str {
@@ -275,6 +382,10 @@ impl Tool for WebSearchTool {
         })
     }
 
+    fn kind(&self) -> ToolKind {
+        ToolKind::Network
+    }
+
     async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
         let query = args
             .get("query")
@@ -291,8 +402,9 @@ impl Tool for WebSearchTool {
             "duckduckgo" | "ddg" => self.search_duckduckgo(query).await?,
             "bing" => self.search_bing(query).await?,
             "brave" => self.search_brave(query).await?,
+            "searxng" => self.search_searxng(query).await?,
             _ => anyhow::bail!(
-                "Unknown search provider: '{}'. Set tools.web_search.provider to 'duckduckgo', 'bing', or 'brave' in config.toml",
+                "Unknown search provider: '{}'. Set tools.web_search.provider to 'duckduckgo', 'bing', 'brave', or 'searxng' in config.toml",
                 self.provider
             ),
         };
@@ -446,6 +558,51 @@ mod tests {
         assert!(result
             .unwrap_err()
             .to_string()
-            .contains("'duckduckgo', 'bing', or 'brave'"));
+            .contains("'duckduckgo', 'bing', 'brave', or 'searxng'"));
+    }
+
+    #[test]
+    fn test_parse_searxng_results_trims_snippets() {
+        let tool = WebSearchTool::new("searxng".to_string(), None, 1, 15)
+            .with_searxng_url(Some("https://searx.example.org/".into()));
+        assert_eq!(
+            tool.searxng_url.as_deref(),
+            Some("https://searx.example.org")
+        );
+        let json = json!({"results": [
+            {"title": "Example", "url": "https://example.com", "content": "word ".repeat(200)},
+            {"title": "Second", "url": "https://example.org", "content": "skipped"}
+        ]});
+        let result = tool.parse_searxng_results(&json, "test").unwrap();
+        assert!(result.contains("(via SearXNG)"));
+        assert!(result.contains("1. Example\n   https://example.com"));
+        assert!(!result.contains("Second"));
+        let snippet = result.lines().last().unwrap().trim();
+        assert!(snippet.chars().count() <= MAX_SNIPPET_CHARS + 3);
+    }
+
+    #[tokio::test]
+    async fn test_parsed_hits_are_recorded_as_search_results() {
+        let tool = WebSearchTool::new("brave".to_string(), None, 5, 15);
+        let json = json!({"web": {"results": [
+            {"title": "Example", "url": "https://example.com", "description": "d"},
+            {"title": "No link", "url": ""}
+        ]}});
+        let (result, metadata) = crate::tools::traits::collect_result_metadata(async {
+            tool.parse_brave_results(&json, "test")
+        })
+        .await;
+        assert!(result.unwrap().contains("1. Example"));
+        assert_eq!(
+            metadata["search_results"],
+            json!([{"url": "https://example.com", "title": "Example"}])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_searxng_without_url() {
+        let tool = WebSearchTool::new("searxng".to_string(), None, 5, 15);
+        let result = tool.execute(json!({"query": "test"})).await;
+        assert!(result.unwrap_err().to_string().contains("searxng_url"));
     }
 }
@@ -8,6 +8,7 @@ import type {
   CostSummary,
   CliTool,
   HealthSnapshot,
+  TaskArtifact,
 } from '../types/api';
 import { clearToken, getToken, setToken } from './auth';
 
@@ -235,3 +236,13 @@ export function getCliTools(): Promise<CliTool[]> {
     unwrapField(data, 'cli_tools'),
   );
 }
+
+// ---------------------------------------------------------------------------
+// Task artifacts
+// ---------------------------------------------------------------------------
+
+export function getTaskArtifacts(taskId: string): Promise<TaskArtifact[]> {
+  return apiFetch<TaskArtifact[] | { artifacts: TaskArtifact[] }>(
+    `/api/tasks/${encodeURIComponent(taskId)}/artifacts`,
+  ).then((data) => unwrapField(data, 'artifacts'));
+}
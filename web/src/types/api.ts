@@ -88,6 +88,25 @@ export interface CliTool {
   category: string;
 }
 
+export interface ArtifactPreview {
+  mime_type: string;
+  size_bytes: number;
+  summary: string;
+  excerpt: string | null;
+  thumbnail: string | null;
+}
+
+export interface TaskArtifact {
+  id: number;
+  task_id: string;
+  path: string;
+  verified: boolean;
+  checksum: string | null;
+  verified_at: string | null;
+  mime_type: string | null;
+  preview: ArtifactPreview | null;
+}
+
 export interface SSEEvent {
   type: string;
   timestamp?: string;